@@ -0,0 +1,247 @@
+//! Dates and date ranges.
+
+use rescribe_core::PropValue;
+use std::collections::HashMap;
+
+/// A year with an optional month (1-12) and day (1-31).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateParts {
+    pub year: i32,
+    pub month: Option<u8>,
+    pub day: Option<u8>,
+}
+
+impl DateParts {
+    pub fn new(year: i32, month: Option<u8>, day: Option<u8>) -> Self {
+        Self { year, month, day }
+    }
+
+    /// Parse `YYYY`, `YYYY-MM` or `YYYY-MM-DD`.
+    fn parse(text: &str) -> Option<Self> {
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let mut fields = text.split('-');
+        let year = fields.next().filter(|y| y.len() >= 4)?;
+        let year: i32 = year.parse().ok()?;
+        let month = fields.next().map(str::parse::<u8>).transpose().ok()?;
+        let day = fields.next().map(str::parse::<u8>).transpose().ok()?;
+        if fields.next().is_some()
+            || month.is_some_and(|m| !(1..=12).contains(&m))
+            || day.is_some_and(|d| !(1..=31).contains(&d))
+        {
+            return None;
+        }
+        Some(Self::new(if negative { -year } else { year }, month, day))
+    }
+
+    fn to_edtf(self) -> String {
+        let mut text = if self.year < 0 {
+            format!("-{:04}", -self.year)
+        } else {
+            format!("{:04}", self.year)
+        };
+        if let Some(month) = self.month {
+            text.push_str(&format!("-{month:02}"));
+            if let Some(day) = self.day {
+                text.push_str(&format!("-{day:02}"));
+            }
+        }
+        text
+    }
+
+    fn to_prop(self) -> PropValue {
+        let mut parts = vec![PropValue::Int(self.year.into())];
+        if let Some(month) = self.month {
+            parts.push(PropValue::Int(month.into()));
+            if let Some(day) = self.day {
+                parts.push(PropValue::Int(day.into()));
+            }
+        }
+        PropValue::List(parts)
+    }
+
+    fn from_prop(value: &PropValue) -> Option<Self> {
+        let PropValue::List(parts) = value else {
+            return None;
+        };
+        let number = |index: usize| -> Option<i64> {
+            match parts.get(index)? {
+                PropValue::Int(n) => Some(*n),
+                PropValue::String(s) => s.trim().parse().ok(),
+                _ => None,
+            }
+        };
+        let in_range = |n: i64, max: i64| (1..=max).contains(&n).then_some(n as u8);
+        let month = number(1).and_then(|m| in_range(m, 12));
+        Some(Self {
+            year: i32::try_from(number(0)?).ok()?,
+            month,
+            day: month.and(number(2).and_then(|d| in_range(d, 31))),
+        })
+    }
+}
+
+/// A date variable such as `bib:issued`.
+///
+/// A date is a single day, month or year, or a range of them. Dates that
+/// cannot be expressed that way, such as "Spring 1999", are literals. As a
+/// property value a date is a map with the CSL JSON keys: `date-parts` (a
+/// list of one or two `[year, month, day]` lists), `literal` and `circa`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Date {
+    pub start: Option<DateParts>,
+    /// End of a range.
+    pub end: Option<DateParts>,
+    pub literal: Option<String>,
+    /// Whether the date is approximate.
+    pub circa: bool,
+}
+
+impl Date {
+    /// A single date.
+    pub fn new(parts: DateParts) -> Self {
+        Self {
+            start: Some(parts),
+            ..Self::default()
+        }
+    }
+
+    /// A date given as free text.
+    pub fn literal(text: impl Into<String>) -> Self {
+        Self {
+            literal: Some(text.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Parse an ISO 8601 / EDTF date such as `2020`, `2020-05-15`, a range
+    /// `2019/2020` or an approximate `1850~`, as used by BibLaTeX.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (text, circa) = match text.strip_suffix(['~', '?', '%']) {
+            Some(rest) => (rest, true),
+            None => (text, false),
+        };
+        let (start, end) = match text.split_once('/') {
+            Some((start, end)) => (start, Some(end)),
+            None => (text, None),
+        };
+        let end = match end {
+            Some(end) => Some(DateParts::parse(end)?),
+            None => None,
+        };
+        Some(Self {
+            start: Some(DateParts::parse(start)?),
+            end,
+            literal: None,
+            circa,
+        })
+    }
+
+    /// The year the date starts in.
+    pub fn year(&self) -> Option<i32> {
+        self.start.map(|start| start.year)
+    }
+
+    /// The date in EDTF notation, or `None` for a literal date.
+    pub fn to_edtf(&self) -> Option<String> {
+        let mut text = self.start?.to_edtf();
+        if let Some(end) = self.end {
+            text.push('/');
+            text.push_str(&end.to_edtf());
+        }
+        if self.circa {
+            text.push('~');
+        }
+        Some(text)
+    }
+
+    /// Read a date from a property value. Strings are parsed, or taken as
+    /// literals if they are not in EDTF notation.
+    pub fn from_prop(value: &PropValue) -> Option<Self> {
+        match value {
+            PropValue::String(s) => Some(Self::parse(s).unwrap_or_else(|| Self::literal(s))),
+            PropValue::Map(map) => {
+                let mut date = match map.get("date-parts") {
+                    Some(PropValue::List(ranges)) => Self {
+                        start: ranges.first().and_then(DateParts::from_prop),
+                        end: ranges.get(1).and_then(DateParts::from_prop),
+                        ..Self::default()
+                    },
+                    _ => Self::default(),
+                };
+                if let Some(PropValue::String(literal)) = map.get("literal") {
+                    date.literal = Some(literal.clone());
+                }
+                if date.start.is_none()
+                    && date.literal.is_none()
+                    && let Some(PropValue::String(raw)) = map.get("raw")
+                {
+                    date = Self::from_prop(&PropValue::String(raw.clone()))?;
+                }
+                date.circa |= match map.get("circa") {
+                    Some(PropValue::Bool(circa)) => *circa,
+                    Some(PropValue::Int(circa)) => *circa != 0,
+                    Some(PropValue::String(circa)) => !circa.is_empty(),
+                    _ => false,
+                };
+                (date.start.is_some() || date.literal.is_some()).then_some(date)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<Date> for PropValue {
+    fn from(date: Date) -> Self {
+        let mut map = HashMap::new();
+        if let Some(start) = date.start {
+            let mut ranges = vec![start.to_prop()];
+            ranges.extend(date.end.map(DateParts::to_prop));
+            map.insert("date-parts".to_string(), PropValue::List(ranges));
+        }
+        if let Some(literal) = date.literal {
+            map.insert("literal".to_string(), PropValue::String(literal));
+        }
+        if date.circa {
+            map.insert("circa".to_string(), PropValue::Bool(true));
+        }
+        PropValue::Map(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let date = Date::parse("2020-05-15").unwrap();
+        assert_eq!(date.start, Some(DateParts::new(2020, Some(5), Some(15))));
+        assert_eq!(date.to_edtf().as_deref(), Some("2020-05-15"));
+
+        let range = Date::parse("2019-03/2020~").unwrap();
+        assert_eq!(range.end, Some(DateParts::new(2020, None, None)));
+        assert!(range.circa);
+        assert_eq!(range.to_edtf().as_deref(), Some("2019-03/2020~"));
+
+        assert_eq!(Date::parse("Spring 1999"), None);
+        assert_eq!(Date::parse("2020-13"), None);
+    }
+
+    #[test]
+    fn test_prop_round_trip() {
+        for text in ["1984", "2020-05-15", "2019/2020", "1850~"] {
+            let date = Date::parse(text).unwrap();
+            assert_eq!(Date::from_prop(&date.clone().into()), Some(date));
+        }
+        let literal = Date::literal("Spring 1999");
+        assert_eq!(Date::from_prop(&literal.clone().into()), Some(literal));
+        assert_eq!(
+            Date::from_prop(&PropValue::String("2001-02".into())).and_then(|d| d.to_edtf()),
+            Some("2001-02".into())
+        );
+    }
+}
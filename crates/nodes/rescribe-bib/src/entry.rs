@@ -0,0 +1,137 @@
+//! Typed view over `bib:entry` nodes.
+
+use crate::{Date, Name, PREFIX, node, prop};
+use rescribe_core::{Node, PropValue};
+
+/// A `bib:entry` node.
+#[derive(Debug, Clone, Copy)]
+pub struct Entry<'a>(&'a Node);
+
+impl<'a> Entry<'a> {
+    /// View `node` as an entry, if it is one.
+    pub fn from_node(node: &'a Node) -> Option<Self> {
+        (node.kind.as_str() == node::ENTRY).then_some(Self(node))
+    }
+
+    /// The underlying node.
+    pub fn node(&self) -> &'a Node {
+        self.0
+    }
+
+    /// Citation key (default empty).
+    pub fn id(&self) -> &'a str {
+        self.0.props.get_str(prop::ID).unwrap_or_default()
+    }
+
+    /// CSL item type (default `document`).
+    pub fn entry_type(&self) -> &'a str {
+        self.text(prop::TYPE).unwrap_or("document")
+    }
+
+    /// A non-empty string variable, by property key.
+    pub fn text(&self, key: &str) -> Option<&'a str> {
+        self.0.props.get_str(key).filter(|s| !s.is_empty())
+    }
+
+    /// The names of a name variable, by property key.
+    pub fn names(&self, key: &str) -> Vec<Name> {
+        self.0
+            .props
+            .get(key)
+            .map(Name::from_list)
+            .unwrap_or_default()
+    }
+
+    /// A date variable, by property key.
+    pub fn date(&self, key: &str) -> Option<Date> {
+        self.0.props.get(key).and_then(Date::from_prop)
+    }
+
+    /// A format-specific field from [`bib:custom`](prop::CUSTOM).
+    pub fn custom(&self, key: &str) -> Option<&'a str> {
+        match self.0.props.get(prop::CUSTOM) {
+            Some(PropValue::Map(map)) => match map.get(key) {
+                Some(PropValue::String(s)) => Some(s.as_str()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// All format-specific fields, sorted by key.
+    pub fn custom_fields(&self) -> Vec<(&'a str, &'a str)> {
+        let mut fields: Vec<_> = match self.0.props.get(prop::CUSTOM) {
+            Some(PropValue::Map(map)) => map
+                .iter()
+                .filter_map(|(key, value)| match value {
+                    PropValue::String(s) => Some((key.as_str(), s.as_str())),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        fields.sort();
+        fields
+    }
+
+    /// All CSL variables set on the entry, by CSL name and sorted, except
+    /// the ID and type.
+    pub fn variables(&self) -> Vec<(&'a str, &'a PropValue)> {
+        let mut variables: Vec<_> = self
+            .0
+            .props
+            .iter()
+            .filter(|(key, _)| !matches!(key.as_str(), prop::ID | prop::TYPE | prop::CUSTOM))
+            .filter_map(|(key, value)| Some((key.strip_prefix(PREFIX)?, value)))
+            .collect();
+        variables.sort_by_key(|(name, _)| *name);
+        variables
+    }
+}
+
+/// All entries in `root` and its descendants, in document order.
+pub fn entries(root: &Node) -> Vec<Entry<'_>> {
+    fn collect<'a>(node: &'a Node, out: &mut Vec<Entry<'a>>) {
+        match Entry::from_node(node) {
+            Some(entry) => out.push(entry),
+            None => node.children.iter().for_each(|child| collect(child, out)),
+        }
+    }
+    let mut out = Vec::new();
+    collect(root, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_entry_view() {
+        let custom = HashMap::from([
+            ("bibtex:eprint".to_string(), PropValue::from("2101.00001")),
+            ("ris:M3".to_string(), PropValue::from("Print")),
+        ]);
+        let root = Node::new("document").child(
+            Node::new("div").child(
+                Node::new(node::ENTRY)
+                    .prop(prop::ID, "a")
+                    .prop(prop::TITLE, "Title")
+                    .prop(prop::PAGE, "")
+                    .prop(prop::CUSTOM, PropValue::Map(custom)),
+            ),
+        );
+
+        let found = entries(&root);
+        assert_eq!(found.len(), 1);
+        let entry = found[0];
+        assert_eq!(entry.id(), "a");
+        assert_eq!(entry.entry_type(), "document");
+        assert_eq!(entry.text(prop::PAGE), None);
+        assert_eq!(entry.custom("ris:M3"), Some("Print"));
+        assert_eq!(entry.custom_fields()[0].0, "bibtex:eprint");
+        let names: Vec<_> = entry.variables().into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, ["page", "title"]);
+    }
+}
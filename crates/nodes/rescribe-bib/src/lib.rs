@@ -0,0 +1,254 @@
+//! Bibliography node kinds for rescribe.
+//!
+//! A bibliography is a sequence of `bib:entry` nodes, one per cited work.
+//! Entries use the data model of the Citation Style Language: each CSL
+//! variable is stored as a `bib:` property of the same name, so `title`
+//! becomes `bib:title` and `DOI` becomes `bib:DOI`. Name variables hold a
+//! list of [`Name`] maps, date variables a [`Date`] map and all others a
+//! string. Fields of a source format that have no CSL variable are kept in
+//! the [`bib:custom`](prop::CUSTOM) map under a key prefixed with the
+//! format, such as `bibtex:eprint` or `ris:M3`.
+//!
+//! ```
+//! use rescribe_bib::{Date, Entry, Name, node, prop};
+//! use rescribe_core::Node;
+//!
+//! let node = Node::new(node::ENTRY)
+//!     .prop(prop::ID, "knuth1984")
+//!     .prop(prop::TYPE, "book")
+//!     .prop(prop::TITLE, "The TeXbook")
+//!     .prop(prop::AUTHOR, Name::list([Name::parse("Knuth, Donald E.")]))
+//!     .prop(prop::ISSUED, Date::parse("1984").unwrap());
+//!
+//! let entry = Entry::from_node(&node).unwrap();
+//! assert_eq!(entry.names(prop::AUTHOR)[0].family.as_deref(), Some("Knuth"));
+//! assert_eq!(entry.date(prop::ISSUED).unwrap().year(), Some(1984));
+//! ```
+
+pub use rescribe_core::*;
+
+mod date;
+mod entry;
+mod name;
+
+pub use date::{Date, DateParts};
+pub use entry::{Entry, entries};
+pub use name::Name;
+
+/// Bibliography node kind constants.
+pub mod node {
+    /// A bibliography entry, with its data in `bib:` properties.
+    pub const ENTRY: &str = "bib:entry";
+}
+
+/// Bibliography property key constants.
+///
+/// Only the most common CSL variables have a constant; any other variable
+/// is stored under `bib:` followed by its CSL name.
+pub mod prop {
+    /// Citation key (required).
+    pub const ID: &str = "bib:id";
+    /// CSL item type such as `article-journal` or `book` (required).
+    pub const TYPE: &str = "bib:type";
+    /// Format-specific fields without a CSL variable (map of strings).
+    pub const CUSTOM: &str = "bib:custom";
+
+    // Names
+    /// Authors.
+    pub const AUTHOR: &str = "bib:author";
+    /// Editors.
+    pub const EDITOR: &str = "bib:editor";
+    /// Translators.
+    pub const TRANSLATOR: &str = "bib:translator";
+    /// Authors of the containing work, such as a book's for a chapter.
+    pub const CONTAINER_AUTHOR: &str = "bib:container-author";
+    /// Editors of the series.
+    pub const COLLECTION_EDITOR: &str = "bib:collection-editor";
+
+    // Dates
+    /// Publication date.
+    pub const ISSUED: &str = "bib:issued";
+    /// Date an online resource was accessed.
+    pub const ACCESSED: &str = "bib:accessed";
+    /// Date of the event, such as a conference.
+    pub const EVENT_DATE: &str = "bib:event-date";
+    /// Date of the original publication.
+    pub const ORIGINAL_DATE: &str = "bib:original-date";
+
+    // Titles
+    /// Title.
+    pub const TITLE: &str = "bib:title";
+    /// Short form of the title.
+    pub const TITLE_SHORT: &str = "bib:title-short";
+    /// Title of the containing work: journal, book or proceedings.
+    pub const CONTAINER_TITLE: &str = "bib:container-title";
+    /// Abbreviated title of the containing work.
+    pub const CONTAINER_TITLE_SHORT: &str = "bib:container-title-short";
+    /// Title of the series.
+    pub const COLLECTION_TITLE: &str = "bib:collection-title";
+    /// Number within the series.
+    pub const COLLECTION_NUMBER: &str = "bib:collection-number";
+
+    // Locators and publication details
+    /// Volume.
+    pub const VOLUME: &str = "bib:volume";
+    /// Issue of a periodical.
+    pub const ISSUE: &str = "bib:issue";
+    /// Page range, such as `45-67`.
+    pub const PAGE: &str = "bib:page";
+    /// Edition.
+    pub const EDITION: &str = "bib:edition";
+    /// Number of a report, patent or similar.
+    pub const NUMBER: &str = "bib:number";
+    /// Total number of pages.
+    pub const NUMBER_OF_PAGES: &str = "bib:number-of-pages";
+    /// Total number of volumes.
+    pub const NUMBER_OF_VOLUMES: &str = "bib:number-of-volumes";
+    /// Chapter number.
+    pub const CHAPTER_NUMBER: &str = "bib:chapter-number";
+    /// Publisher, or the institution of a report or thesis.
+    pub const PUBLISHER: &str = "bib:publisher";
+    /// Place of publication.
+    pub const PUBLISHER_PLACE: &str = "bib:publisher-place";
+    /// Subtype, such as `PhD thesis`.
+    pub const GENRE: &str = "bib:genre";
+    /// Medium or format.
+    pub const MEDIUM: &str = "bib:medium";
+    /// Version of software or a dataset.
+    pub const VERSION: &str = "bib:version";
+    /// Publication status, such as `in press`.
+    pub const STATUS: &str = "bib:status";
+
+    // Identifiers
+    /// Digital Object Identifier.
+    pub const DOI: &str = "bib:DOI";
+    /// URL.
+    pub const URL: &str = "bib:URL";
+    /// ISBN.
+    pub const ISBN: &str = "bib:ISBN";
+    /// ISSN.
+    pub const ISSN: &str = "bib:ISSN";
+    /// Library call number.
+    pub const CALL_NUMBER: &str = "bib:call-number";
+
+    // Notes
+    /// Abstract.
+    pub const ABSTRACT: &str = "bib:abstract";
+    /// Note.
+    pub const NOTE: &str = "bib:note";
+    /// Annotation.
+    pub const ANNOTE: &str = "bib:annote";
+    /// Keywords, separated by commas.
+    pub const KEYWORD: &str = "bib:keyword";
+    /// Language tag.
+    pub const LANGUAGE: &str = "bib:language";
+}
+
+/// Prefix of the properties holding CSL variables.
+pub const PREFIX: &str = "bib:";
+
+/// CSL variables holding names.
+pub const NAME_VARIABLES: &[&str] = &[
+    "author",
+    "chair",
+    "collection-editor",
+    "compiler",
+    "composer",
+    "container-author",
+    "contributor",
+    "curator",
+    "director",
+    "editor",
+    "editorial-director",
+    "executive-producer",
+    "guest",
+    "host",
+    "illustrator",
+    "interviewer",
+    "narrator",
+    "organizer",
+    "original-author",
+    "performer",
+    "producer",
+    "recipient",
+    "reviewed-author",
+    "script-writer",
+    "series-creator",
+    "translator",
+];
+
+/// CSL variables holding dates.
+pub const DATE_VARIABLES: &[&str] = &[
+    "accessed",
+    "available-date",
+    "event-date",
+    "issued",
+    "original-date",
+    "submitted",
+];
+
+/// Whether the CSL variable `name` (without the `bib:` prefix) holds names.
+pub fn is_name_variable(name: &str) -> bool {
+    NAME_VARIABLES.contains(&name)
+}
+
+/// Whether the CSL variable `name` (without the `bib:` prefix) holds a date.
+pub fn is_date_variable(name: &str) -> bool {
+    DATE_VARIABLES.contains(&name)
+}
+
+/// Schema for the bibliography node kinds.
+///
+/// Entries are leaf blocks. Name and date variables are checked for their
+/// list and map types; other variables are not declared, since CSL allows
+/// numbers for some of them.
+pub fn schema() -> Schema {
+    let mut entry = KindSchema::new()
+        .category("block")
+        .leaf()
+        .required(prop::ID, PropType::String)
+        .required(prop::TYPE, PropType::String)
+        .optional(prop::CUSTOM, PropType::Map);
+    for name in NAME_VARIABLES {
+        entry = entry.optional(format!("{PREFIX}{name}"), PropType::List);
+    }
+    for name in DATE_VARIABLES {
+        entry = entry.optional(format!("{PREFIX}{name}"), PropType::Map);
+    }
+    Schema::new().kind(node::ENTRY, entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema() {
+        let doc = Document::new().with_content(
+            Node::new("document")
+                .child(
+                    Node::new(node::ENTRY)
+                        .prop(prop::ID, "a")
+                        .prop(prop::TYPE, "book")
+                        .prop(prop::AUTHOR, Name::list([Name::literal("WHO")])),
+                )
+                .child(
+                    Node::new(node::ENTRY)
+                        .prop(prop::ID, "b")
+                        .prop(prop::ISSUED, "2020"),
+                ),
+        );
+        let messages: Vec<_> = schema()
+            .validate(&doc)
+            .into_iter()
+            .map(|w| format!("{} {}", w.path.unwrap(), w.message))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "/1 bib:entry is missing required property 'bib:type'",
+                "/1 property 'bib:issued' of bib:entry should be map, found string",
+            ]
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Personal and organisational names.
+
+use rescribe_core::PropValue;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A name in a name variable such as `bib:author`.
+///
+/// The parts follow CSL: particles such as the "van" of "Vincent van Gogh"
+/// are kept apart from the family name, and names that are not split into
+/// parts, such as organisations, are literals. As a property value a name
+/// is a map with the CSL JSON keys (`family`, `given`,
+/// `non-dropping-particle`, `dropping-particle`, `suffix` and `literal`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Name {
+    pub family: Option<String>,
+    pub given: Option<String>,
+    /// Particle kept with the family name when sorting ("van" in Dutch).
+    pub non_dropping_particle: Option<String>,
+    /// Particle dropped when sorting by family name ("de" in French).
+    pub dropping_particle: Option<String>,
+    pub suffix: Option<String>,
+    pub literal: Option<String>,
+}
+
+impl Name {
+    /// A name that is not split into parts, such as an organisation.
+    pub fn literal(name: impl Into<String>) -> Self {
+        Self {
+            literal: Some(name.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Parse a name written the BibTeX way: `First von Last`,
+    /// `von Last, First` or `von Last, Jr, First`.
+    ///
+    /// Lowercase words before the family name are particles.
+    pub fn parse(text: &str) -> Self {
+        let parts: Vec<&str> = text.split(',').map(str::trim).collect();
+        match parts.as_slice() {
+            [whole] => {
+                let words: Vec<&str> = whole.split_whitespace().collect();
+                let Some((last, rest)) = words.split_last() else {
+                    return Self::default();
+                };
+                // "von" runs from the first lowercase word to the last one
+                // before the final word.
+                let von_start = rest.iter().position(|w| is_lowercase(w));
+                match von_start {
+                    Some(start) => {
+                        let end = rest.iter().rposition(|w| is_lowercase(w)).unwrap_or(start);
+                        let family = [&rest[end + 1..], &[*last]].concat().join(" ");
+                        let mut name = Self::inverted(&family, &rest[..start].join(" "), "");
+                        name.non_dropping_particle = non_empty(rest[start..=end].join(" "));
+                        name
+                    }
+                    None => Self::inverted(last, &rest.join(" "), ""),
+                }
+            }
+            [last, given] => Self::inverted(last, given, ""),
+            [last, suffix, given, ..] => Self::inverted(last, given, suffix),
+            [] => Self::default(),
+        }
+    }
+
+    /// Build a name from its family part, which may start with particles
+    /// such as "van der", its given names and a suffix.
+    pub fn inverted(last: &str, given: &str, suffix: &str) -> Self {
+        let words: Vec<&str> = last.split_whitespace().collect();
+        let particles = words
+            .iter()
+            .take(words.len().saturating_sub(1))
+            .take_while(|w| is_lowercase(w))
+            .count();
+        Self {
+            family: non_empty(words[particles..].join(" ")),
+            given: non_empty(given.trim().to_string()),
+            non_dropping_particle: non_empty(words[..particles].join(" ")),
+            dropping_particle: None,
+            suffix: non_empty(suffix.trim().to_string()),
+            literal: None,
+        }
+    }
+
+    /// The family name with its particles, such as "van Gogh", or the
+    /// literal name.
+    pub fn family_part(&self) -> Option<String> {
+        if let Some(literal) = &self.literal {
+            return Some(literal.clone());
+        }
+        let parts: Vec<&str> = [
+            &self.dropping_particle,
+            &self.non_dropping_particle,
+            &self.family,
+        ]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect();
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+
+    /// Read a name from a property value. A string is taken as a literal.
+    pub fn from_prop(value: &PropValue) -> Option<Self> {
+        match value {
+            PropValue::String(s) => Some(Self::literal(s.clone())),
+            PropValue::Map(map) => {
+                let part = |key: &str| match map.get(key) {
+                    Some(PropValue::String(s)) if !s.is_empty() => Some(s.clone()),
+                    _ => None,
+                };
+                Some(Self {
+                    family: part("family"),
+                    given: part("given"),
+                    non_dropping_particle: part("non-dropping-particle"),
+                    dropping_particle: part("dropping-particle"),
+                    suffix: part("suffix"),
+                    literal: part("literal"),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Read the names of a name variable.
+    pub fn from_list(value: &PropValue) -> Vec<Self> {
+        match value {
+            PropValue::List(items) => items.iter().filter_map(Self::from_prop).collect(),
+            other => Self::from_prop(other).into_iter().collect(),
+        }
+    }
+
+    /// The property value of a name variable holding `names`.
+    pub fn list(names: impl IntoIterator<Item = Name>) -> PropValue {
+        PropValue::List(names.into_iter().map(PropValue::from).collect())
+    }
+}
+
+impl From<Name> for PropValue {
+    fn from(name: Name) -> Self {
+        let mut map = HashMap::new();
+        let parts = [
+            ("family", name.family),
+            ("given", name.given),
+            ("non-dropping-particle", name.non_dropping_particle),
+            ("dropping-particle", name.dropping_particle),
+            ("suffix", name.suffix),
+            ("literal", name.literal),
+        ];
+        for (key, part) in parts {
+            if let Some(part) = part {
+                map.insert(key.to_string(), PropValue::String(part));
+            }
+        }
+        PropValue::Map(map)
+    }
+}
+
+/// The name in reading order, such as "Vincent van Gogh" or
+/// "Martin Luther King, Jr.".
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let words: Vec<String> = [self.given.clone(), self.family_part()]
+            .into_iter()
+            .flatten()
+            .collect();
+        f.write_str(&words.join(" "))?;
+        match &self.suffix {
+            Some(suffix) => write!(f, ", {suffix}"),
+            None => Ok(()),
+        }
+    }
+}
+
+fn is_lowercase(word: &str) -> bool {
+    word.chars()
+        .find(|c| c.is_alphabetic())
+        .is_some_and(char::is_lowercase)
+}
+
+fn non_empty(s: String) -> Option<String> {
+    (!s.is_empty()).then_some(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(name: &Name) -> [Option<&str>; 4] {
+        [
+            name.given.as_deref(),
+            name.non_dropping_particle.as_deref(),
+            name.family.as_deref(),
+            name.suffix.as_deref(),
+        ]
+    }
+
+    #[test]
+    fn test_parse() {
+        let cases = [
+            (
+                "Donald E. Knuth",
+                [Some("Donald E."), None, Some("Knuth"), None],
+            ),
+            (
+                "Knuth, Donald E.",
+                [Some("Donald E."), None, Some("Knuth"), None],
+            ),
+            (
+                "Ludwig van Beethoven",
+                [Some("Ludwig"), Some("van"), Some("Beethoven"), None],
+            ),
+            (
+                "van der Waals, Johannes",
+                [Some("Johannes"), Some("van der"), Some("Waals"), None],
+            ),
+            (
+                "King, Jr, Martin Luther",
+                [Some("Martin Luther"), None, Some("King"), Some("Jr")],
+            ),
+            ("Aristotle", [None, None, Some("Aristotle"), None]),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parts(&Name::parse(text)), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_prop_round_trip() {
+        let name = Name::parse("Ludwig van Beethoven");
+        assert_eq!(Name::from_prop(&name.clone().into()), Some(name.clone()));
+        assert_eq!(name.to_string(), "Ludwig van Beethoven");
+        assert_eq!(name.family_part().as_deref(), Some("van Beethoven"));
+
+        let names = Name::from_list(&Name::list([Name::literal("WHO"), name]));
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[0].to_string(), "WHO");
+    }
+}
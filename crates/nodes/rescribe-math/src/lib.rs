@@ -1,10 +1,17 @@
 //! Math node kinds for rescribe.
 //!
 //! This crate provides node kinds for mathematical notation,
-//! supporting both presentation and semantic math.
+//! supporting both presentation and semantic math, and reading and writing
+//! those nodes as TeX (see [`tex`]) and as MathML (see [`mathml`]).
 
 pub use rescribe_core::*;
 
+pub mod mathml;
+pub mod tex;
+
+pub use mathml::MathmlError;
+pub use tex::TexError;
+
 /// Math node kind constants.
 pub mod node {
     /// Inline math expression.
@@ -39,6 +46,8 @@ pub mod node {
     pub const MATRIX_CELL: &str = "math:matrix_cell";
     /// Parenthesized/bracketed expression.
     pub const FENCED: &str = "math:fenced";
+    /// A horizontal group of expressions.
+    pub const MATH_ROW: &str = "math:row";
     /// A table/aligned environment.
     pub const MATH_TABLE: &str = "math:table";
 
@@ -77,7 +86,8 @@ pub mod prop {
     pub const OPEN_DELIM: &str = "math:open";
     /// Closing delimiter for fenced expressions.
     pub const CLOSE_DELIM: &str = "math:close";
-    /// Accent character (hat, tilde, etc.).
+    /// Accent character (hat, tilde, etc.), or the brace character of a
+    /// `math:brace`.
     pub const ACCENT_CHAR: &str = "math:accent_char";
     /// Whether accent stretches over content.
     pub const ACCENT_STRETCHY: &str = "math:accent_stretchy";
@@ -91,6 +101,120 @@ pub mod prop {
     pub const COLUMN_ALIGN: &str = "math:column_align";
     /// Row alignment.
     pub const ROW_ALIGN: &str = "math:row_align";
+    /// Style variant of a token (`normal`, `bold`, `double-struck`, etc.,
+    /// as MathML `mathvariant`).
+    pub const MATH_VARIANT: &str = "math:variant";
+    /// Thickness of a fraction bar (`0` for binomial coefficients).
+    pub const LINE_THICKNESS: &str = "math:linethickness";
+    /// Width of a math space, as a CSS length such as `0.1667em`.
+    pub const SPACE_WIDTH: &str = "math:width";
+}
+
+/// Schema for the math node kinds.
+///
+/// Math expressions belong to the `math` category. `math_inline` is inline
+/// content and `math_display` may appear as a block or inline, matching the
+/// `block` and `inline` categories of the standard schema; merge the two to
+/// validate documents containing math. Leaf tokens carry their text in the
+/// `content` property.
+pub fn schema() -> Schema {
+    const MATH: &str = "math";
+    let math = || KindSchema::new().category(MATH).children(&[MATH]);
+    let token = || {
+        KindSchema::new()
+            .category(MATH)
+            .leaf()
+            .required("content", PropType::String)
+            .optional(prop::MATH_VARIANT, PropType::String)
+    };
+    let container = |kind: KindSchema| {
+        kind.children(&[MATH])
+            .optional(prop::MATH_FORMAT, PropType::String)
+            .optional(prop::MATH_SOURCE, PropType::String)
+    };
+
+    Schema::new()
+        .kind(
+            node::MATH_INLINE,
+            container(KindSchema::new().category("inline")),
+        )
+        .kind(
+            node::MATH_DISPLAY,
+            container(KindSchema::new().category("block").category("inline")),
+        )
+        .kind(
+            node::FRACTION,
+            math()
+                .arity(2, Some(2))
+                .optional(prop::LINE_THICKNESS, PropType::String),
+        )
+        .kind(node::ROOT, math().arity(1, Some(2)))
+        .kind(node::MATH_SUB, math().arity(2, Some(2)))
+        .kind(node::MATH_SUP, math().arity(2, Some(2)))
+        .kind(node::MATH_SUBSUP, math().arity(3, Some(3)))
+        .kind(node::MATH_UNDER, math().arity(2, Some(2)))
+        .kind(node::MATH_OVER, math().arity(2, Some(2)))
+        .kind(node::MATH_UNDEROVER, math().arity(3, Some(3)))
+        .kind(
+            node::MATRIX,
+            KindSchema::new()
+                .category(MATH)
+                .children(&[node::MATRIX_ROW])
+                .optional(prop::COLUMN_ALIGN, PropType::String)
+                .optional(prop::ROW_ALIGN, PropType::String),
+        )
+        .kind(
+            node::MATH_TABLE,
+            KindSchema::new()
+                .category(MATH)
+                .children(&[node::MATRIX_ROW])
+                .optional(prop::COLUMN_ALIGN, PropType::String)
+                .optional(prop::ROW_ALIGN, PropType::String),
+        )
+        .kind(
+            node::MATRIX_ROW,
+            KindSchema::new().children(&[node::MATRIX_CELL]),
+        )
+        .kind(node::MATRIX_CELL, KindSchema::new().children(&[MATH]))
+        .kind(
+            node::FENCED,
+            math()
+                .optional(prop::OPEN_DELIM, PropType::String)
+                .optional(prop::CLOSE_DELIM, PropType::String),
+        )
+        .kind(node::MATH_ROW, math())
+        .kind(
+            node::OPERATOR,
+            token()
+                .optional(prop::OPERATOR_FORM, PropType::String)
+                .optional(prop::LARGE_OP, PropType::Bool)
+                .optional(prop::MOVE_LIMITS, PropType::Bool),
+        )
+        .kind(node::IDENTIFIER, token())
+        .kind(node::NUMBER, token())
+        .kind(node::MATH_TEXT, token())
+        .kind(
+            node::MATH_SPACE,
+            KindSchema::new()
+                .category(MATH)
+                .leaf()
+                .optional(prop::SPACE_WIDTH, PropType::String),
+        )
+        .kind(
+            node::ACCENT,
+            math()
+                .arity(1, Some(1))
+                .optional(prop::ACCENT_CHAR, PropType::String)
+                .optional(prop::ACCENT_STRETCHY, PropType::Bool),
+        )
+        .kind(
+            node::MATH_BRACE,
+            math()
+                .arity(1, Some(2))
+                .optional(prop::ACCENT_CHAR, PropType::String),
+        )
+        .kind(node::MATH_STRIKE, math())
+        .kind(node::ENCLOSED, math())
 }
 
 #[cfg(test)]
@@ -106,4 +230,26 @@ mod tests {
         assert_eq!(math.kind.as_str(), "math_inline");
         assert_eq!(math.props.get_str(prop::MATH_FORMAT), Some("latex"));
     }
+
+    #[test]
+    fn test_schema() {
+        let number = |n: &str| Node::new(node::NUMBER).prop("content", n);
+        let doc = Document::new().with_content(
+            Node::new("document").child(
+                Node::new(node::MATH_DISPLAY)
+                    .child(
+                        Node::new(node::FRACTION)
+                            .child(number("1"))
+                            .child(number("2")),
+                    )
+                    .child(Node::new(node::MATH_SUP).child(number("3"))),
+            ),
+        );
+        let messages: Vec<_> = schema()
+            .validate(&doc)
+            .into_iter()
+            .map(|w| format!("{} {}", w.path.unwrap(), w.message))
+            .collect();
+        assert_eq!(messages, ["/0/1 math:sup expects 2 children, found 1"]);
+    }
 }
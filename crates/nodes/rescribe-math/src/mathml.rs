@@ -0,0 +1,833 @@
+//! Reading and writing MathML as math nodes.
+//!
+//! [`emit`] writes a `math_inline` or `math_display` node as a MathML
+//! Presentation `<math>` element, from its math children or, when it has
+//! none, from its TeX source. The TeX source goes along as an
+//! `application/x-tex` annotation.
+//!
+//! ```
+//! use rescribe_math::{Node, mathml, node, prop};
+//!
+//! let math = Node::new(node::MATH_INLINE).prop(prop::MATH_SOURCE, "x^2");
+//! let xml = mathml::emit(&math).unwrap();
+//! assert!(xml.contains("<msup><mi>x</mi><mn>2</mn></msup>"));
+//!
+//! let math = mathml::parse(&xml).unwrap();
+//! assert_eq!(math.children[0].kind.as_str(), node::MATH_SUP);
+//! assert_eq!(math.props.get_str(prop::MATH_SOURCE), Some("x^2"));
+//! ```
+//!
+//! [`parse`] reads a `<math>` element back into math nodes, whatever its
+//! namespace prefix. Its `math:source` is the TeX annotation if there is
+//! one and otherwise TeX written with [`tex::emit`]. Style wrappers such as
+//! `<mstyle>` and invisible operators are dropped, and elements this crate
+//! has no node kind for become rows of their content.
+
+use crate::{node, prop, tex};
+use quick_xml::Reader;
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use rescribe_core::Node;
+
+/// The MathML namespace.
+pub const NAMESPACE: &str = "http://www.w3.org/1998/Math/MathML";
+
+/// Property holding the text of token nodes.
+const CONTENT: &str = "content";
+
+/// How deeply elements may nest, so that hostile input fails instead of
+/// overflowing the stack.
+const MAX_DEPTH: usize = 256;
+
+/// Encodings of annotations holding TeX source.
+const TEX_ENCODINGS: &[&str] = &["application/x-tex", "TeX", "LaTeX", "text/x-latex"];
+
+/// Error from reading or writing MathML.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid MathML: {message}")]
+pub struct MathmlError {
+    pub message: String,
+}
+
+impl MathmlError {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+/// Write a math node as a MathML `<math>` element.
+///
+/// Math without children is parsed from its TeX source first; it is an
+/// error if the source is not valid TeX or is in another notation.
+pub fn emit(math: &Node) -> Result<String, MathmlError> {
+    emit_prefixed(math, "")
+}
+
+/// Write a math node as a MathML `<math>` element whose elements have the
+/// namespace prefix `prefix`, such as `mml` in JATS and DocBook.
+pub fn emit_prefixed(math: &Node, prefix: &str) -> Result<String, MathmlError> {
+    let source = math
+        .props
+        .get_str(prop::MATH_FORMAT)
+        .is_none_or(|format| format == "latex")
+        .then(|| math.props.get_str(prop::MATH_SOURCE))
+        .flatten();
+    let parsed;
+    let children = if !math.children.is_empty() {
+        &math.children
+    } else if let Some(source) = source {
+        parsed = tex::parse(source).map_err(|e| MathmlError::new(e.to_string()))?;
+        &parsed
+    } else if let Some(format) = math.props.get_str(prop::MATH_FORMAT) {
+        return Err(MathmlError::new(format!("cannot write {format} math")));
+    } else {
+        &math.children
+    };
+
+    let mut emitter = Emitter {
+        out: String::new(),
+        prefix: if prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{prefix}:")
+        },
+    };
+    let xmlns = if prefix.is_empty() {
+        "xmlns".to_string()
+    } else {
+        format!("xmlns:{prefix}")
+    };
+    let mut attrs = vec![(xmlns.as_str(), NAMESPACE)];
+    if math.kind.as_str() == node::MATH_DISPLAY {
+        attrs.push(("display", "block"));
+    }
+    emitter.open("math", &attrs);
+    match source {
+        Some(source) => {
+            emitter.open("semantics", &[]);
+            emitter.row(children);
+            emitter.open("annotation", &[("encoding", "application/x-tex")]);
+            emitter.out.push_str(&escape(source));
+            emitter.close("annotation");
+            emitter.close("semantics");
+        }
+        None => emitter.list(children),
+    }
+    emitter.close("math");
+    Ok(emitter.out)
+}
+
+struct Emitter {
+    out: String,
+    /// The namespace prefix with its colon, or nothing.
+    prefix: String,
+}
+
+impl Emitter {
+    fn open(&mut self, name: &str, attrs: &[(&str, &str)]) {
+        self.out.push('<');
+        self.out.push_str(&self.prefix);
+        self.out.push_str(name);
+        for (key, value) in attrs {
+            self.out.push(' ');
+            self.out.push_str(key);
+            self.out.push_str("=\"");
+            self.out.push_str(&escape(*value));
+            self.out.push('"');
+        }
+        self.out.push('>');
+    }
+
+    fn close(&mut self, name: &str) {
+        self.out.push_str("</");
+        self.out.push_str(&self.prefix);
+        self.out.push_str(name);
+        self.out.push('>');
+    }
+
+    fn element(&mut self, name: &str, attrs: &[(&str, &str)], children: &[Node]) {
+        self.open(name, attrs);
+        self.list(children);
+        self.close(name);
+    }
+
+    fn list(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    /// Nodes as one element, in an `<mrow>` unless there is just one.
+    fn row(&mut self, nodes: &[Node]) {
+        match nodes {
+            [node] => self.node(node),
+            nodes => self.element("mrow", &[], nodes),
+        }
+    }
+
+    fn token(&mut self, name: &str, node: &Node, attrs: &[(&str, &str)]) {
+        let mut attrs = attrs.to_vec();
+        if let Some(variant) = node.props.get_str(prop::MATH_VARIANT) {
+            attrs.push(("mathvariant", variant));
+        }
+        self.open(name, &attrs);
+        self.out
+            .push_str(&escape(node.props.get_str(CONTENT).unwrap_or_default()));
+        self.close(name);
+    }
+
+    fn fence(&mut self, delimiter: &str, form: &str) {
+        if !delimiter.is_empty() {
+            self.open("mo", &[("fence", "true"), ("form", form)]);
+            self.out.push_str(&escape(delimiter));
+            self.close("mo");
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        let children = node.children.as_slice();
+        match node.kind.as_str() {
+            node::IDENTIFIER => self.token("mi", node, &[]),
+            node::NUMBER => self.token("mn", node, &[]),
+            node::MATH_TEXT => self.token("mtext", node, &[]),
+            node::OPERATOR => {
+                let mut attrs = Vec::new();
+                if let Some(form) = node.props.get_str(prop::OPERATOR_FORM) {
+                    attrs.push(("form", form));
+                }
+                if node.props.get_bool(prop::LARGE_OP) == Some(true) {
+                    attrs.push(("largeop", "true"));
+                }
+                match node.props.get_bool(prop::MOVE_LIMITS) {
+                    Some(true) => attrs.push(("movablelimits", "true")),
+                    Some(false) => attrs.push(("movablelimits", "false")),
+                    None => {}
+                }
+                self.token("mo", node, &attrs);
+            }
+            node::MATH_SPACE => {
+                let width = node.props.get_str(prop::SPACE_WIDTH).unwrap_or("0em");
+                self.open("mspace", &[("width", width)]);
+                self.close("mspace");
+            }
+            node::MATH_ROW => self.element("mrow", &[], children),
+            node::FRACTION => match node.props.get_str(prop::LINE_THICKNESS) {
+                Some(thickness) => {
+                    self.element("mfrac", &[("linethickness", thickness)], children);
+                }
+                None => self.element("mfrac", &[], children),
+            },
+            node::ROOT if children.len() > 1 => self.element("mroot", &[], children),
+            node::ROOT => self.element("msqrt", &[], children),
+            node::MATH_SUB => self.element("msub", &[], children),
+            node::MATH_SUP => self.element("msup", &[], children),
+            node::MATH_SUBSUP => self.element("msubsup", &[], children),
+            node::MATH_UNDER => self.element("munder", &[], children),
+            node::MATH_OVER => self.element("mover", &[], children),
+            node::MATH_UNDEROVER => self.element("munderover", &[], children),
+            node::MATRIX | node::MATH_TABLE => {
+                let mut attrs = Vec::new();
+                if let Some(align) = node.props.get_str(prop::COLUMN_ALIGN) {
+                    attrs.push(("columnalign", align));
+                }
+                if let Some(align) = node.props.get_str(prop::ROW_ALIGN) {
+                    attrs.push(("rowalign", align));
+                }
+                // Aligned equations are set in display style, matrices not
+                if node.kind.as_str() == node::MATH_TABLE {
+                    attrs.push(("displaystyle", "true"));
+                }
+                self.open("mtable", &attrs);
+                for row in children {
+                    self.open("mtr", &[]);
+                    for cell in &row.children {
+                        self.element("mtd", &[], &cell.children);
+                    }
+                    self.close("mtr");
+                }
+                self.close("mtable");
+            }
+            node::FENCED => {
+                self.open("mrow", &[]);
+                let open = node.props.get_str(prop::OPEN_DELIM).unwrap_or_default();
+                let close = node.props.get_str(prop::CLOSE_DELIM).unwrap_or_default();
+                self.fence(open, "prefix");
+                self.list(children);
+                self.fence(close, "postfix");
+                self.close("mrow");
+            }
+            node::ACCENT => {
+                let stretchy = node.props.get_bool(prop::ACCENT_STRETCHY) == Some(true);
+                self.open("mover", &[("accent", "true")]);
+                self.row(children);
+                self.open(
+                    "mo",
+                    &[("stretchy", if stretchy { "true" } else { "false" })],
+                );
+                self.out.push_str(&escape(
+                    node.props.get_str(prop::ACCENT_CHAR).unwrap_or_default(),
+                ));
+                self.close("mo");
+                self.close("mover");
+            }
+            node::MATH_BRACE => {
+                let brace = node.props.get_str(prop::ACCENT_CHAR).unwrap_or(OVERBRACE);
+                let (name, accent) = if brace == UNDERBRACE {
+                    ("munder", "accentunder")
+                } else {
+                    ("mover", "accent")
+                };
+                let annotation = children.get(1);
+                if annotation.is_some() {
+                    self.open(name, &[]);
+                }
+                self.open(name, &[(accent, "true")]);
+                self.list(&children[..children.len().min(1)]);
+                self.open("mo", &[]);
+                self.out.push_str(brace);
+                self.close("mo");
+                self.close(name);
+                if let Some(annotation) = annotation {
+                    self.node(annotation);
+                    self.close(name);
+                }
+            }
+            node::MATH_STRIKE => {
+                self.element("menclose", &[("notation", "updiagonalstrike")], children);
+            }
+            node::ENCLOSED => self.element("menclose", &[("notation", "box")], children),
+            _ => self.element("mrow", &[], children),
+        }
+    }
+}
+
+const OVERBRACE: &str = "\u{23de}";
+const UNDERBRACE: &str = "\u{23df}";
+
+/// Parse a MathML `<math>` element into a `math_inline` or `math_display`
+/// node.
+pub fn parse(xml: &str) -> Result<Node, MathmlError> {
+    let root = El::parse(xml)?;
+    if root.name != "math" {
+        return Err(MathmlError::new(format!(
+            "expected <math>, found <{}>",
+            root.name
+        )));
+    }
+    let display = root.attr("display") == Some("block") || root.attr("mode") == Some("display");
+
+    // The TeX annotation of a semantics element is the source
+    let (children, source) = match root.children.as_slice() {
+        [semantics] if semantics.name == "semantics" => {
+            let source = semantics
+                .children
+                .iter()
+                .find(|c| {
+                    c.name == "annotation"
+                        && c.attr("encoding")
+                            .is_some_and(|e| TEX_ENCODINGS.contains(&e))
+                })
+                .map(|c| c.text.trim().to_string());
+            let content = &semantics.children[..semantics.children.len().min(1)];
+            (list(content)?, source)
+        }
+        children => (list(children)?, None),
+    };
+    let source = match source {
+        Some(source) => source,
+        None => tex::emit(&children),
+    };
+    let kind = if display {
+        node::MATH_DISPLAY
+    } else {
+        node::MATH_INLINE
+    };
+    Ok(Node::new(kind)
+        .prop(prop::MATH_SOURCE, source)
+        .children(children))
+}
+
+/// An element, with its namespace prefix dropped.
+#[derive(Debug, Default)]
+struct El {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<El>,
+    text: String,
+}
+
+impl El {
+    fn attr(&self, key: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn open(e: &BytesStart<'_>) -> Result<El, MathmlError> {
+        let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+        let mut attrs = Vec::new();
+        for attr in e.attributes() {
+            let attr = attr.map_err(|e| MathmlError::new(e.to_string()))?;
+            let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
+            let value = attr
+                .unescape_value()
+                .map_err(|e| MathmlError::new(e.to_string()))?;
+            attrs.push((key, value.to_string()));
+        }
+        Ok(El {
+            name,
+            attrs,
+            ..El::default()
+        })
+    }
+
+    /// Parse XML into its first element.
+    fn parse(xml: &str) -> Result<El, MathmlError> {
+        let mut reader = Reader::from_str(xml);
+        let mut stack: Vec<El> = Vec::new();
+        loop {
+            let event = reader
+                .read_event()
+                .map_err(|e| MathmlError::new(format!("XML error: {e}")))?;
+            let done = match event {
+                Event::Start(e) => {
+                    if stack.len() == MAX_DEPTH {
+                        return Err(MathmlError::new("elements nested too deeply"));
+                    }
+                    stack.push(El::open(&e)?);
+                    None
+                }
+                Event::Empty(e) => Some(El::open(&e)?),
+                Event::End(_) => stack.pop(),
+                Event::Text(e) => {
+                    if let Some(el) = stack.last_mut() {
+                        let text = e.decode().map_err(|e| MathmlError::new(e.to_string()))?;
+                        el.text.push_str(&text);
+                    }
+                    None
+                }
+                Event::CData(e) => {
+                    if let Some(el) = stack.last_mut() {
+                        let text = e.decode().map_err(|e| MathmlError::new(e.to_string()))?;
+                        el.text.push_str(&text);
+                    }
+                    None
+                }
+                Event::GeneralRef(e) => {
+                    if let Some(el) = stack.last_mut() {
+                        let name = String::from_utf8_lossy(e.as_ref()).to_string();
+                        match e.resolve_char_ref().ok().flatten() {
+                            Some(ch) => el.text.push(ch),
+                            None => match quick_xml::escape::resolve_xml_entity(&name)
+                                .or_else(|| entity(&name))
+                            {
+                                Some(entity) => el.text.push_str(entity),
+                                None => {
+                                    return Err(MathmlError::new(format!(
+                                        "unknown entity &{name};"
+                                    )));
+                                }
+                            },
+                        }
+                    }
+                    None
+                }
+                Event::Eof => return Err(MathmlError::new("no <math> element")),
+                _ => None,
+            };
+            if let Some(el) = done {
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(el),
+                    None => return Ok(el),
+                }
+            }
+        }
+    }
+}
+
+/// The common MathML entities beyond XML's own.
+fn entity(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "ApplyFunction" | "af" => "\u{2061}",
+        "InvisibleTimes" | "it" => "\u{2062}",
+        "InvisibleComma" | "ic" => "\u{2063}",
+        "nbsp" | "NonBreakingSpace" => "\u{a0}",
+        "ThinSpace" => "\u{2009}",
+        "minus" => "\u{2212}",
+        "times" => "\u{d7}",
+        "sdot" => "\u{22c5}",
+        "le" => "\u{2264}",
+        "ge" => "\u{2265}",
+        "ne" => "\u{2260}",
+        "infin" => "\u{221e}",
+        "sum" => "\u{2211}",
+        "int" => "\u{222b}",
+        "pi" => "\u{3c0}",
+        _ => return None,
+    })
+}
+
+/// The nodes for elements in sequence, with a lone row opened up.
+fn list(elements: &[El]) -> Result<Vec<Node>, MathmlError> {
+    let mut nodes = Vec::new();
+    for el in elements {
+        nodes.extend(convert(el)?);
+    }
+    if let [only] = nodes.as_slice()
+        && only.kind.as_str() == node::MATH_ROW
+    {
+        return Ok(nodes.remove(0).children);
+    }
+    Ok(nodes)
+}
+
+/// The node for the content of an element: a single node, or a row.
+fn row(elements: &[El]) -> Result<Node, MathmlError> {
+    let mut nodes = list(elements)?;
+    Ok(if nodes.len() == 1 {
+        nodes.remove(0)
+    } else {
+        Node::new(node::MATH_ROW).children(nodes)
+    })
+}
+
+/// The arguments of a layout element such as `<mfrac>`, one node each.
+fn arguments(el: &El, arity: usize) -> Result<Vec<Node>, MathmlError> {
+    if el.children.len() != arity {
+        return Err(MathmlError::new(format!(
+            "<{}> takes {arity} arguments, found {}",
+            el.name,
+            el.children.len()
+        )));
+    }
+    el.children
+        .iter()
+        .map(|child| row(std::slice::from_ref(child)))
+        .collect()
+}
+
+/// The text of a token element, with whitespace collapsed.
+fn token_text(el: &El) -> String {
+    el.text
+        .split_ascii_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn token(kind: &str, el: &El) -> Node {
+    let node = Node::new(kind).prop(CONTENT, token_text(el));
+    match el.attr("mathvariant") {
+        Some(variant) => node.prop(prop::MATH_VARIANT, variant),
+        None => node,
+    }
+}
+
+/// Whether `el` is the opening or closing fence of a row.
+fn is_fence(el: &El, form: &str) -> bool {
+    let delimiters = if form == "prefix" {
+        "([{\u{27e8}\u{230a}\u{2308}|\u{2016}"
+    } else {
+        ")]}\u{27e9}\u{230b}\u{2309}|\u{2016}"
+    };
+    let text = token_text(el);
+    el.name == "mo"
+        && (el.attr("fence") == Some("true")
+            || el.attr("form") == Some(form) && text.chars().all(|c| delimiters.contains(c)))
+}
+
+fn convert(el: &El) -> Result<Option<Node>, MathmlError> {
+    let node = match el.name.as_str() {
+        "mi" => token(node::IDENTIFIER, el),
+        "mn" => token(node::NUMBER, el),
+        "mtext" | "ms" => token(node::MATH_TEXT, el),
+        "mo" => {
+            let mut op = token(node::OPERATOR, el);
+            let text = token_text(el);
+            // Invisible function application, times, separator and plus
+            if text.is_empty() || text.chars().all(|c| ('\u{2061}'..='\u{2064}').contains(&c)) {
+                return Ok(None);
+            }
+            if let Some(form @ ("prefix" | "infix" | "postfix")) = el.attr("form") {
+                op = op.prop(prop::OPERATOR_FORM, form);
+            }
+            if el.attr("largeop") == Some("true") {
+                op = op.prop(prop::LARGE_OP, true);
+            }
+            match el.attr("movablelimits") {
+                Some("true") => op = op.prop(prop::MOVE_LIMITS, true),
+                Some("false") => op = op.prop(prop::MOVE_LIMITS, false),
+                _ => {}
+            }
+            op
+        }
+        "mspace" => {
+            let space = Node::new(node::MATH_SPACE);
+            match el.attr("width") {
+                Some(width) => space.prop(prop::SPACE_WIDTH, width),
+                None => space,
+            }
+        }
+        "mrow" => {
+            let children = el.children.as_slice();
+            let open = children.first().filter(|c| is_fence(c, "prefix"));
+            let close = children
+                .last()
+                .filter(|_| children.len() > 1 || open.is_none())
+                .filter(|c| is_fence(c, "postfix"));
+            if open.is_none() && close.is_none() {
+                return Ok(Some(row(children)?));
+            }
+            let start = usize::from(open.is_some());
+            let end = children.len() - usize::from(close.is_some());
+            Node::new(node::FENCED)
+                .prop(prop::OPEN_DELIM, open.map(token_text).unwrap_or_default())
+                .prop(prop::CLOSE_DELIM, close.map(token_text).unwrap_or_default())
+                .children(list(&children[start..end])?)
+        }
+        "mfenced" => {
+            let separators: Vec<char> = el
+                .attr("separators")
+                .unwrap_or(",")
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect();
+            let mut children = Vec::new();
+            for (i, child) in el.children.iter().enumerate() {
+                if i > 0
+                    && let Some(separator) = separators.get(i - 1).or(separators.last())
+                {
+                    children.push(Node::new(node::OPERATOR).prop(CONTENT, separator.to_string()));
+                }
+                children.push(row(std::slice::from_ref(child))?);
+            }
+            Node::new(node::FENCED)
+                .prop(prop::OPEN_DELIM, el.attr("open").unwrap_or("("))
+                .prop(prop::CLOSE_DELIM, el.attr("close").unwrap_or(")"))
+                .children(children)
+        }
+        "mfrac" => {
+            let fraction = Node::new(node::FRACTION).children(arguments(el, 2)?);
+            match el.attr("linethickness") {
+                Some("0" | "0pt" | "0em" | "0px") => fraction.prop(prop::LINE_THICKNESS, "0"),
+                Some(thickness) => fraction.prop(prop::LINE_THICKNESS, thickness),
+                None => fraction,
+            }
+        }
+        "msqrt" => Node::new(node::ROOT).child(row(&el.children)?),
+        "mroot" => Node::new(node::ROOT).children(arguments(el, 2)?),
+        "msub" => Node::new(node::MATH_SUB).children(arguments(el, 2)?),
+        "msup" => Node::new(node::MATH_SUP).children(arguments(el, 2)?),
+        "msubsup" => Node::new(node::MATH_SUBSUP).children(arguments(el, 3)?),
+        "munder" | "mover" => {
+            let over = el.name == "mover";
+            let mut args = arguments(el, 2)?;
+            let accent = if over { "accent" } else { "accentunder" };
+            let mark = (el.attr(accent) == Some("true") && el.children[1].name == "mo")
+                .then(|| token_text(&el.children[1]));
+            let script = args.remove(1);
+            let base = args.remove(0);
+            match mark.as_deref() {
+                Some(brace @ (OVERBRACE | UNDERBRACE)) => Node::new(node::MATH_BRACE)
+                    .prop(prop::ACCENT_CHAR, brace)
+                    .child(base),
+                Some(mark) if over => {
+                    let accent = Node::new(node::ACCENT)
+                        .prop(prop::ACCENT_CHAR, mark)
+                        .child(base);
+                    match el.children[1].attr("stretchy") {
+                        Some("true") => accent.prop(prop::ACCENT_STRETCHY, true),
+                        _ => accent,
+                    }
+                }
+                // A brace takes the script on its side as its annotation
+                _ if base.kind.as_str() == node::MATH_BRACE
+                    && base.children.len() == 1
+                    && base.props.get_str(prop::ACCENT_CHAR)
+                        == Some(if over { OVERBRACE } else { UNDERBRACE }) =>
+                {
+                    base.child(script)
+                }
+                _ => {
+                    let kind = if over {
+                        node::MATH_OVER
+                    } else {
+                        node::MATH_UNDER
+                    };
+                    Node::new(kind).child(base).child(script)
+                }
+            }
+        }
+        "munderover" => Node::new(node::MATH_UNDEROVER).children(arguments(el, 3)?),
+        "mtable" => {
+            let kind = if el.attr("displaystyle") == Some("true") {
+                node::MATH_TABLE
+            } else {
+                node::MATRIX
+            };
+            let mut table = Node::new(kind);
+            if let Some(align) = el.attr("columnalign") {
+                table = table.prop(prop::COLUMN_ALIGN, align);
+            }
+            if let Some(align) = el.attr("rowalign") {
+                table = table.prop(prop::ROW_ALIGN, align);
+            }
+            for tr in &el.children {
+                // The first cell of a labeled row is its label
+                let cells = match tr.name.as_str() {
+                    "mtr" => &tr.children[..],
+                    "mlabeledtr" => &tr.children[tr.children.len().min(1)..],
+                    _ => continue,
+                };
+                let mut row = Node::new(node::MATRIX_ROW);
+                for td in cells {
+                    row = row.child(Node::new(node::MATRIX_CELL).children(list(&td.children)?));
+                }
+                table = table.child(row);
+            }
+            table
+        }
+        "menclose" => {
+            let notation = el.attr("notation").unwrap_or("longdiv");
+            let kind = if notation.contains("strike") {
+                node::MATH_STRIKE
+            } else {
+                node::ENCLOSED
+            };
+            Node::new(kind).children(list(&el.children)?)
+        }
+        "semantics" | "maction" => {
+            return row(&el.children[..el.children.len().min(1)]).map(Some);
+        }
+        "annotation" | "annotation-xml" | "none" | "mprescripts" => return Ok(None),
+        _ => row(&el.children)?,
+    };
+    Ok(Some(node))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn math(kind: &str, source: &str) -> Node {
+        Node::new(kind).prop(prop::MATH_SOURCE, source)
+    }
+
+    #[test]
+    fn test_emit() {
+        let xml = emit(&math(node::MATH_INLINE, r"\frac{a}{2}")).unwrap();
+        assert_eq!(
+            xml,
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><semantics>\
+             <mfrac><mi>a</mi><mn>2</mn></mfrac>\
+             <annotation encoding=\"application/x-tex\">\\frac{a}{2}</annotation>\
+             </semantics></math>"
+        );
+
+        let xml = emit_prefixed(&math(node::MATH_DISPLAY, r"\left( x \right. < 1"), "mml").unwrap();
+        assert!(xml.starts_with(
+            "<mml:math xmlns:mml=\"http://www.w3.org/1998/Math/MathML\" display=\"block\">"
+        ));
+        assert!(xml.contains(
+            "<mml:mrow><mml:mrow><mml:mo fence=\"true\" form=\"prefix\">(</mml:mo>\
+             <mml:mi>x</mml:mi></mml:mrow><mml:mo>&lt;</mml:mo><mml:mn>1</mml:mn></mml:mrow>"
+        ));
+
+        let error = emit(&math(node::MATH_INLINE, r"\frac{a")).unwrap_err();
+        assert_eq!(error.message, "invalid TeX math at offset 7: expected '}'");
+        let typst = math(node::MATH_INLINE, "x^2").prop(prop::MATH_FORMAT, "typst");
+        assert_eq!(emit(&typst).unwrap_err().message, "cannot write typst math");
+    }
+
+    #[test]
+    fn test_parse() {
+        let math = parse(
+            r#"<mml:math xmlns:mml="http://www.w3.org/1998/Math/MathML" display="block">
+                <mml:mrow>
+                  <mml:msubsup><mml:mo>&#x222B;</mml:mo><mml:mn>0</mml:mn><mml:mn>1</mml:mn></mml:msubsup>
+                  <mml:mi>f</mml:mi><mml:mo>&ApplyFunction;</mml:mo>
+                  <mml:mfenced><mml:mi>x</mml:mi></mml:mfenced>
+                  <mml:mspace width="0.1667em"/><mml:mi mathvariant="normal">d</mml:mi><mml:mi>x</mml:mi>
+                </mml:mrow>
+              </mml:math>"#,
+        )
+        .unwrap();
+        assert_eq!(math.kind.as_str(), node::MATH_DISPLAY);
+        let kinds: Vec<_> = math.children.iter().map(|n| n.kind.as_str()).collect();
+        assert_eq!(
+            kinds,
+            [
+                node::MATH_SUBSUP,
+                node::IDENTIFIER,
+                node::FENCED,
+                node::MATH_SPACE,
+                node::IDENTIFIER,
+                node::IDENTIFIER
+            ]
+        );
+        assert_eq!(
+            math.props.get_str(prop::MATH_SOURCE),
+            Some(r"\int_0^1 f\left( x \right) \, \mathrm{d}x")
+        );
+
+        let math = parse(
+            r#"<math><semantics><mrow><mover accent="true"><mi>x</mi><mo>^</mo></mover></mrow>
+               <annotation encoding="application/x-tex">\hat x</annotation></semantics></math>"#,
+        )
+        .unwrap();
+        assert_eq!(math.kind.as_str(), node::MATH_INLINE);
+        assert_eq!(math.children[0].kind.as_str(), node::ACCENT);
+        assert_eq!(math.props.get_str(prop::MATH_SOURCE), Some(r"\hat x"));
+
+        let error = parse("<math><mfrac><mn>1</mn></mfrac></math>").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid MathML: <mfrac> takes 2 arguments, found 1"
+        );
+        assert!(parse("<mrow/>").is_err());
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let nested = |n| {
+            format!(
+                "<math>{}<mi>x</mi>{}</math>",
+                "<mrow>".repeat(n),
+                "</mrow>".repeat(n)
+            )
+        };
+        let error = parse(&nested(5000)).unwrap_err();
+        assert_eq!(error.message, "elements nested too deeply");
+        let math = parse(&nested(100)).unwrap();
+        assert_eq!(math.children[0].kind.as_str(), node::IDENTIFIER);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let sources = [
+            r"x_i^2 + \alpha \leq \Gamma",
+            r"\frac{a+1}{2} \sqrt[3]{x} \binom{n}{k}",
+            r"\sum_{i=1}^n i \lim_{x \to 0} \int\limits_0^1 \operatorname*{arg\,max}_x",
+            r"\left\langle x \middle| y \right\rangle \left\{ x \right.",
+            r"\begin{pmatrix} 1 & 0 \\ 0 & 1 \end{pmatrix} \begin{aligned} a &= b \\ &= c \end{aligned}",
+            r"\begin{cases} 1 & x > 0 \\ 0 & \text{otherwise} \end{cases}",
+            r"\hat{x} \widetilde{AB} \overbrace{a+b}^{n} \underbrace{c}_{k} \underline{y}",
+            r"\mathbb{R} \mathrm{d}x \cancel{y} \boxed{z} a\,b\quad c",
+        ];
+        for source in sources {
+            let nodes = tex::parse(source).unwrap();
+            let xml = emit(&math(node::MATH_INLINE, source)).unwrap();
+            let read = parse(&xml).unwrap();
+            assert_eq!(read.props.get_str(prop::MATH_SOURCE), Some(source));
+            // Without the annotation, the source is written from the nodes
+            let read = parse(&xml.replace("application/x-tex", "text/plain")).unwrap();
+            assert_eq!(
+                tex::emit(&read.children),
+                tex::emit(&nodes),
+                "{source}\n{xml}"
+            );
+        }
+    }
+}
@@ -0,0 +1,2013 @@
+//! Reading and writing TeX math as math nodes.
+//!
+//! [`parse`] reads the math-mode subset of LaTeX that documents commonly
+//! use: letters, numbers and operators, `^` and `_` scripts and primes,
+//! `\frac` and `\binom`, `\sqrt`, `\left…\right` and `\big` delimiters,
+//! accents, fonts such as `\mathbf` and `\mathbb`, `\text`,
+//! `\operatorname`, large operators and their limits, spacing commands,
+//! the Greek alphabet and common symbols, and the matrix, `cases`, `array`
+//! and `aligned` environments.
+//!
+//! ```
+//! use rescribe_math::{node, tex};
+//!
+//! let nodes = tex::parse(r"\frac{1}{2} + x^2").unwrap();
+//! let kinds: Vec<_> = nodes.iter().map(|n| n.kind.as_str()).collect();
+//! assert_eq!(kinds, [node::FRACTION, node::OPERATOR, node::MATH_SUP]);
+//! ```
+//!
+//! Groups of more than one node become a [`math:row`](node::MATH_ROW).
+//! Letters are separate identifiers, as TeX sets them, except inside font
+//! commands such as `\mathrm{d}` where a run of letters is one identifier
+//! with a [`math:variant`](prop::MATH_VARIANT). Presentation-only commands
+//! such as `\displaystyle`, `\color` and `\label` are dropped; any other
+//! unknown command is an error.
+//!
+//! [`emit`] writes math nodes back as TeX, for math that comes from other
+//! notations such as MathML.
+
+use crate::{node, prop};
+use rescribe_core::Node;
+
+/// Property holding the text of token nodes.
+const CONTENT: &str = "content";
+
+/// Error from parsing TeX math.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid TeX math at offset {position}: {message}")]
+pub struct TexError {
+    /// Byte offset into the source.
+    pub position: usize,
+    pub message: String,
+}
+
+/// Parse TeX math source into math nodes.
+pub fn parse(source: &str) -> Result<Vec<Node>, TexError> {
+    let mut parser = Parser {
+        src: source,
+        pos: 0,
+        variant: None,
+        depth: 0,
+    };
+    let nodes = parser.parse_list(false)?;
+    parser.skip_space();
+    match parser.peek() {
+        None => Ok(nodes),
+        Some('}') => Err(parser.error("unmatched '}'")),
+        Some('&') => Err(parser.error("'&' outside an environment")),
+        _ => Err(parser.error(format!(
+            "unexpected '{}'",
+            parser.peek_command().unwrap_or_default()
+        ))),
+    }
+}
+
+/// Write math nodes as TeX source that [`parse`] reads back as the same
+/// nodes.
+///
+/// ```
+/// use rescribe_math::tex;
+///
+/// let nodes = tex::parse(r"\frac{\alpha}{2}+\sqrt{x_1}").unwrap();
+/// assert_eq!(tex::emit(&nodes), r"\frac{\alpha}{2} + \sqrt{x_1}");
+/// ```
+pub fn emit(nodes: &[Node]) -> String {
+    emit_list(nodes)
+}
+
+/// What a control word stands for.
+#[derive(Clone, Copy)]
+enum Symbol {
+    /// An identifier such as `\alpha`.
+    Ident(&'static str),
+    /// An identifier set upright, such as `\Gamma`.
+    Upright(&'static str),
+    /// An operator, relation or delimiter.
+    Op(&'static str),
+    /// A large operator, and whether its limits go above and below.
+    Large(&'static str, bool),
+    /// A function name such as `\sin`.
+    Function,
+    /// A function name whose limits go below, such as `\lim`.
+    Limits,
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    /// The font of tokens being read, as a MathML `mathvariant`.
+    variant: Option<&'static str>,
+    /// How many groups and commands are open around the current position.
+    depth: usize,
+}
+
+/// How deeply groups and commands may nest, so that hostile input fails
+/// instead of overflowing the stack.
+const MAX_DEPTH: usize = 256;
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: impl Into<String>) -> TexError {
+        TexError {
+            position: self.pos,
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    /// Run `parse` one level deeper, failing past [`MAX_DEPTH`].
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, TexError>,
+    ) -> Result<T, TexError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("math nested too deeply"));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), TexError> {
+        self.skip_space();
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{c}'")))
+        }
+    }
+
+    /// Skip whitespace and `%` comments.
+    fn skip_space(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.bump();
+            } else if c == '%' {
+                while let Some(c) = self.bump()
+                    && c != '\n'
+                {}
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// The control sequence at the current position, with its backslash,
+    /// without consuming it.
+    fn peek_command(&self) -> Option<&'a str> {
+        let rest: &'a str = &self.src[self.pos..];
+        let name = rest.strip_prefix('\\')?;
+        let len = match name.find(|c: char| !c.is_ascii_alphabetic()) {
+            Some(0) => name.chars().next().map_or(0, char::len_utf8),
+            Some(len) => len,
+            None => name.len(),
+        };
+        Some(&rest[..len + 1])
+    }
+
+    /// Consume a control sequence, returning its name without the
+    /// backslash.
+    fn command(&mut self) -> Option<&'a str> {
+        let start = self.pos;
+        let len = self.peek_command()?.len();
+        self.pos += len;
+        Some(&self.src[start + 1..start + len])
+    }
+
+    /// Consume the command `name` (with backslash) if it comes next.
+    fn eat_command(&mut self, name: &str) -> bool {
+        self.skip_space();
+        if self.peek_command() == Some(name) {
+            self.pos += name.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether the input ends here or a list must stop here: at a closing
+    /// brace, an alignment character, a row break, `\end` or `\right`, or
+    /// at `]` when reading an optional argument.
+    fn at_end_of_list(&self, bracket: bool) -> bool {
+        match self.peek() {
+            None | Some('}' | '&') => true,
+            Some(']') => bracket,
+            Some('\\') => matches!(self.peek_command(), Some("\\\\" | "\\end" | "\\right")),
+            _ => false,
+        }
+    }
+
+    /// Read atoms up to the end of the current list, handling `\over` and
+    /// its relatives.
+    fn parse_list(&mut self, bracket: bool) -> Result<Vec<Node>, TexError> {
+        let mut nodes = Vec::new();
+        let mut numerator: Option<(Vec<Node>, &str)> = None;
+        loop {
+            self.skip_space();
+            if self.at_end_of_list(bracket) {
+                break;
+            }
+            if let Some(name @ ("\\over" | "\\atop" | "\\choose")) = self.peek_command() {
+                if numerator.is_some() {
+                    return Err(self.error(format!("ambiguous {name}")));
+                }
+                self.pos += name.len();
+                numerator = Some((std::mem::take(&mut nodes), name));
+                continue;
+            }
+            let nucleus = match self.peek() {
+                // A script with nothing before it attaches to an empty base
+                Some('^' | '_') => row(Vec::new()),
+                _ => match self.parse_atom()? {
+                    Some(nucleus) => nucleus,
+                    None => continue,
+                },
+            };
+            nodes.push(self.parse_scripts(nucleus)?);
+        }
+        Ok(match numerator {
+            Some((num, name)) => {
+                let fraction = Node::new(node::FRACTION).child(row(num)).child(row(nodes));
+                match name {
+                    "\\over" => vec![fraction],
+                    "\\atop" => vec![fraction.prop(prop::LINE_THICKNESS, "0")],
+                    _ => vec![fenced(
+                        "(",
+                        ")",
+                        vec![fraction.prop(prop::LINE_THICKNESS, "0")],
+                    )],
+                }
+            }
+            None => nodes,
+        })
+    }
+
+    /// Read a brace group (after its `{`), restoring the font at its end.
+    fn parse_group(&mut self) -> Result<Node, TexError> {
+        self.nested(|p| {
+            let variant = p.variant;
+            let nodes = p.parse_list(false)?;
+            p.variant = variant;
+            p.expect('}')?;
+            Ok(row(nodes))
+        })
+    }
+
+    /// Read a command or macro argument: a group or a single token.
+    fn parse_argument(&mut self) -> Result<Node, TexError> {
+        self.skip_space();
+        match self.peek() {
+            Some('{') => {
+                self.bump();
+                self.parse_group()
+            }
+            Some('\\') => match self.parse_atom()? {
+                Some(node) => Ok(node),
+                None => Err(self.error("missing argument")),
+            },
+            Some(c) if !matches!(c, '}' | '&' | '^' | '_' | ']') => {
+                self.bump();
+                if c.is_ascii_digit() {
+                    Ok(self.token(node::NUMBER, c.to_string()))
+                } else {
+                    self.char_atom(c, false)
+                }
+            }
+            _ => Err(self.error("missing argument")),
+        }
+    }
+
+    /// Read an optional `[…]` argument.
+    fn parse_optional(&mut self) -> Result<Option<Node>, TexError> {
+        self.skip_space();
+        if !self.eat('[') {
+            return Ok(None);
+        }
+        let nodes = self.parse_list(true)?;
+        self.expect(']')?;
+        Ok(Some(row(nodes)))
+    }
+
+    /// Read a brace group as raw text, for `\text` and command names.
+    fn raw_group(&mut self) -> Result<String, TexError> {
+        self.expect('{')?;
+        let mut text = String::new();
+        let mut depth = 0;
+        loop {
+            let Some(c) = self.bump() else {
+                return Err(self.error("missing '}'"));
+            };
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 => return Ok(text),
+                '}' => depth -= 1,
+                '~' => text.push('\u{a0}'),
+                '\\' => match self.bump() {
+                    Some(c @ ('{' | '}' | '$' | '%' | '&' | '#' | '_' | '\\')) => text.push(c),
+                    Some(' ') => text.push(' '),
+                    Some(',' | ':' | ';') => text.push('\u{2009}'),
+                    Some('!') => {}
+                    Some(c) => {
+                        text.push('\\');
+                        text.push(c);
+                    }
+                    None => return Err(self.error("missing '}'")),
+                },
+                c => text.push(c),
+            }
+        }
+    }
+
+    fn token(&self, kind: &str, content: impl Into<String>) -> Node {
+        let node = Node::new(kind).prop(CONTENT, content.into());
+        match self.variant {
+            Some(variant) => node.prop(prop::MATH_VARIANT, variant),
+            None => node,
+        }
+    }
+
+    /// The atom for a character other than a digit, taking the letters
+    /// following a letter in a font if `merge` is set.
+    fn char_atom(&mut self, c: char, merge: bool) -> Result<Node, TexError> {
+        if c.is_alphabetic() {
+            let mut name = c.to_string();
+            // In a font, a run of letters is one identifier
+            if merge && self.variant.is_some() {
+                while let Some(c) = self.peek()
+                    && c.is_alphabetic()
+                {
+                    self.bump();
+                    name.push(c);
+                }
+            }
+            return Ok(self.token(node::IDENTIFIER, name));
+        }
+        let op = match c {
+            '-' => "\u{2212}",
+            '*' => "\u{2217}",
+            '\'' => "\u{2032}",
+            '~' => return Ok(space("0.3333em")),
+            '$' | '#' | '^' | '_' | '{' | '}' | '&' | ']' => {
+                self.pos -= c.len_utf8();
+                return Err(self.error(format!("unexpected '{c}'")));
+            }
+            _ => return Ok(self.token(node::OPERATOR, c.to_string())),
+        };
+        Ok(self.token(node::OPERATOR, op))
+    }
+
+    /// Read one atom, or `None` for a command that produces nothing.
+    fn parse_atom(&mut self) -> Result<Option<Node>, TexError> {
+        let Some(c) = self.peek() else {
+            return Err(self.error("unexpected end of math"));
+        };
+        match c {
+            '{' => {
+                self.bump();
+                self.parse_group().map(Some)
+            }
+            '\\' => self.nested(Self::parse_command),
+            c if c.is_ascii_digit() || c == '.' => {
+                let start = self.pos;
+                while let Some(c) = self.peek() {
+                    let digit_follows =
+                        self.src[self.pos + 1..].starts_with(|c: char| c.is_ascii_digit());
+                    if c.is_ascii_digit() || (c == '.' && digit_follows) {
+                        self.bump();
+                    } else {
+                        break;
+                    }
+                }
+                if self.pos == start {
+                    self.bump();
+                    return Ok(Some(self.token(node::OPERATOR, ".")));
+                }
+                let number = &self.src[start..self.pos];
+                Ok(Some(self.token(node::NUMBER, number)))
+            }
+            c => {
+                self.bump();
+                self.char_atom(c, true).map(Some)
+            }
+        }
+    }
+
+    /// Attach any scripts and primes following `base`.
+    fn parse_scripts(&mut self, mut base: Node) -> Result<Node, TexError> {
+        let mut limits = base.props.get_bool(prop::MOVE_LIMITS) == Some(true);
+        let mut sub = None;
+        let mut sup: Option<Node> = None;
+        let mut primes = 0;
+        loop {
+            if self.eat_command("\\limits") {
+                limits = true;
+                if base.props.get_bool(prop::MOVE_LIMITS).is_some() {
+                    base.props.set(prop::MOVE_LIMITS, false);
+                }
+                continue;
+            }
+            if self.eat_command("\\nolimits") {
+                limits = false;
+                continue;
+            }
+            self.skip_space();
+            match self.peek() {
+                Some('_') => {
+                    if sub.is_some() {
+                        return Err(self.error("double subscript"));
+                    }
+                    self.bump();
+                    sub = Some(self.parse_argument()?);
+                }
+                Some('^') => {
+                    if sup.is_some() && primes == 0 {
+                        return Err(self.error("double superscript"));
+                    }
+                    self.bump();
+                    let script = self.parse_argument()?;
+                    // Primes come before the superscript
+                    sup = Some(match sup.take() {
+                        Some(primes) => row(vec![primes, script]),
+                        None => script,
+                    });
+                    primes = 0;
+                }
+                Some('\'') if sup.is_none() => {
+                    while self.eat('\'') {
+                        primes += 1;
+                    }
+                    let mark = match primes {
+                        1 => "\u{2032}".to_string(),
+                        2 => "\u{2033}".to_string(),
+                        3 => "\u{2034}".to_string(),
+                        n => "\u{2032}".repeat(n),
+                    };
+                    sup = Some(self.token(node::OPERATOR, mark));
+                }
+                _ => break,
+            }
+        }
+
+        // A brace takes its annotation from the script on its side
+        if base.kind.as_str() == node::MATH_BRACE {
+            let over = base.props.get_str(prop::ACCENT_CHAR) == Some(OVERBRACE);
+            let annotation = if over { sup.take() } else { sub.take() };
+            if let Some(annotation) = annotation {
+                base.children.push(annotation);
+            }
+            limits = true;
+        }
+
+        let (kind, scripts) = match (sub, sup) {
+            (None, None) => return Ok(base),
+            (Some(sub), None) if limits => (node::MATH_UNDER, vec![sub]),
+            (None, Some(sup)) if limits => (node::MATH_OVER, vec![sup]),
+            (Some(sub), Some(sup)) if limits => (node::MATH_UNDEROVER, vec![sub, sup]),
+            (Some(sub), None) => (node::MATH_SUB, vec![sub]),
+            (None, Some(sup)) => (node::MATH_SUP, vec![sup]),
+            (Some(sub), Some(sup)) => (node::MATH_SUBSUP, vec![sub, sup]),
+        };
+        Ok(Node::new(kind).child(base).children(scripts))
+    }
+
+    /// Read a delimiter after `\left`, `\right`, `\middle` or `\big`.
+    fn parse_delimiter(&mut self) -> Result<&'static str, TexError> {
+        self.skip_space();
+        let delimiter = match self.peek() {
+            Some('.') => "",
+            Some('(') => "(",
+            Some(')') => ")",
+            Some('[') => "[",
+            Some(']') => "]",
+            Some('|') => "|",
+            Some('/') => "/",
+            Some('<') => "\u{27e8}",
+            Some('>') => "\u{27e9}",
+            Some('\\') => {
+                let name = self.peek_command().unwrap_or_default();
+                match symbol(&name[1..]) {
+                    Some(Symbol::Op(op)) => {
+                        self.pos += name.len();
+                        return Ok(op);
+                    }
+                    _ => return Err(self.error(format!("{name} is not a delimiter"))),
+                }
+            }
+            _ => return Err(self.error("missing delimiter")),
+        };
+        self.bump();
+        Ok(delimiter)
+    }
+
+    /// Read the control sequence at the current position and what follows
+    /// it.
+    fn parse_command(&mut self) -> Result<Option<Node>, TexError> {
+        let start = self.pos;
+        let name = self.command().unwrap_or_default();
+
+        if let Some(symbol) = symbol(name) {
+            return Ok(Some(match symbol {
+                Symbol::Ident(ident) => self.token(node::IDENTIFIER, ident),
+                Symbol::Upright(ident) => {
+                    let ident = self.token(node::IDENTIFIER, ident);
+                    if self.variant.is_none() {
+                        ident.prop(prop::MATH_VARIANT, "normal")
+                    } else {
+                        ident
+                    }
+                }
+                Symbol::Op(op) => self.token(node::OPERATOR, op),
+                Symbol::Large(op, limits) => {
+                    let op = self.token(node::OPERATOR, op).prop(prop::LARGE_OP, true);
+                    if limits {
+                        op.prop(prop::MOVE_LIMITS, true)
+                    } else {
+                        op
+                    }
+                }
+                Symbol::Function => Node::new(node::IDENTIFIER).prop(CONTENT, name),
+                Symbol::Limits => {
+                    let name = match name {
+                        "liminf" => "lim inf",
+                        "limsup" => "lim sup",
+                        name => name,
+                    };
+                    Node::new(node::OPERATOR)
+                        .prop(CONTENT, name)
+                        .prop(prop::MOVE_LIMITS, true)
+                }
+            }));
+        }
+        if let Some(width) = space_width(name) {
+            return Ok(Some(space(width)));
+        }
+        if let Some(variant) = font(name) {
+            let outer = self.variant.replace(variant);
+            let argument = self.parse_argument();
+            self.variant = outer;
+            return argument.map(Some);
+        }
+        if let Some(variant) = font_switch(name) {
+            // Applies to the rest of the enclosing group
+            self.variant = Some(variant);
+            return Ok(None);
+        }
+        if let Some((accent, stretchy)) = accent(name) {
+            let base = self.parse_argument()?;
+            let node = Node::new(node::ACCENT)
+                .prop(prop::ACCENT_CHAR, accent)
+                .child(base);
+            return Ok(Some(if stretchy {
+                node.prop(prop::ACCENT_STRETCHY, true)
+            } else {
+                node
+            }));
+        }
+
+        let node = match name {
+            "frac" | "dfrac" | "tfrac" | "cfrac" => {
+                let num = self.parse_argument()?;
+                let den = self.parse_argument()?;
+                Node::new(node::FRACTION).child(num).child(den)
+            }
+            "binom" | "dbinom" | "tbinom" => {
+                let n = self.parse_argument()?;
+                let k = self.parse_argument()?;
+                let fraction = Node::new(node::FRACTION)
+                    .prop(prop::LINE_THICKNESS, "0")
+                    .child(n)
+                    .child(k);
+                fenced("(", ")", vec![fraction])
+            }
+            "sqrt" => {
+                let index = self.parse_optional()?;
+                let radicand = self.parse_argument()?;
+                Node::new(node::ROOT).child(radicand).children(index)
+            }
+            "left" => {
+                let open = self.parse_delimiter()?;
+                let body = self.parse_list(false)?;
+                if !self.eat_command("\\right") {
+                    return Err(self.error("missing \\right"));
+                }
+                let close = self.parse_delimiter()?;
+                fenced(open, close, body)
+            }
+            "right" => return Err(self.error("\\right without \\left")),
+            "middle" | "big" | "Big" | "bigg" | "Bigg" | "bigl" | "Bigl" | "biggl" | "Biggl"
+            | "bigr" | "Bigr" | "biggr" | "Biggr" | "bigm" | "Bigm" | "biggm" | "Biggm" => {
+                let delimiter = self.parse_delimiter()?;
+                self.token(node::OPERATOR, delimiter)
+            }
+            "overbrace" | "underbrace" => {
+                let brace = if name == "overbrace" {
+                    OVERBRACE
+                } else {
+                    UNDERBRACE
+                };
+                let base = self.parse_argument()?;
+                Node::new(node::MATH_BRACE)
+                    .prop(prop::ACCENT_CHAR, brace)
+                    .child(base)
+            }
+            "underline" => {
+                let base = self.parse_argument()?;
+                Node::new(node::MATH_UNDER)
+                    .child(base)
+                    .child(Node::new(node::OPERATOR).prop(CONTENT, "_"))
+            }
+            "overset" | "stackrel" | "underset" => {
+                let script = self.parse_argument()?;
+                let base = self.parse_argument()?;
+                let kind = if name == "underset" {
+                    node::MATH_UNDER
+                } else {
+                    node::MATH_OVER
+                };
+                Node::new(kind).child(base).child(script)
+            }
+            "xrightarrow" | "xleftarrow" => {
+                let below = self.parse_optional()?;
+                let above = self.parse_argument()?;
+                let arrow = if name == "xrightarrow" {
+                    "\u{2192}"
+                } else {
+                    "\u{2190}"
+                };
+                let arrow = Node::new(node::OPERATOR).prop(CONTENT, arrow);
+                match below {
+                    Some(below) => Node::new(node::MATH_UNDEROVER)
+                        .child(arrow)
+                        .child(below)
+                        .child(above),
+                    None => Node::new(node::MATH_OVER).child(arrow).child(above),
+                }
+            }
+            "boxed" | "fbox" => {
+                let body = self.parse_argument()?;
+                Node::new(node::ENCLOSED).child(body)
+            }
+            "cancel" | "bcancel" | "xcancel" => {
+                let body = self.parse_argument()?;
+                Node::new(node::MATH_STRIKE).child(body)
+            }
+            "text" | "textrm" | "textnormal" | "mbox" | "hbox" | "textup" => {
+                let text = self.raw_group()?;
+                Node::new(node::MATH_TEXT).prop(CONTENT, text)
+            }
+            "textbf" | "textit" | "texttt" | "textsf" => {
+                let variant = match name {
+                    "textbf" => "bold",
+                    "textit" => "italic",
+                    "texttt" => "monospace",
+                    _ => "sans-serif",
+                };
+                let text = self.raw_group()?;
+                Node::new(node::MATH_TEXT)
+                    .prop(CONTENT, text)
+                    .prop(prop::MATH_VARIANT, variant)
+            }
+            "operatorname" | "mathop" => {
+                let limits = name == "operatorname" && self.eat('*');
+                let text = self.raw_group()?;
+                if limits || name == "mathop" {
+                    Node::new(node::OPERATOR)
+                        .prop(CONTENT, text)
+                        .prop(prop::MOVE_LIMITS, true)
+                } else {
+                    Node::new(node::IDENTIFIER).prop(CONTENT, text)
+                }
+            }
+            "not" => {
+                let Some(negated) = self.parse_atom()? else {
+                    return Err(self.error("nothing to negate"));
+                };
+                match negated.props.get_str(CONTENT) {
+                    Some("=") => self.token(node::OPERATOR, "\u{2260}"),
+                    Some("\u{2208}") => self.token(node::OPERATOR, "\u{2209}"),
+                    Some(op) if negated.kind.as_str() == node::OPERATOR => {
+                        self.token(node::OPERATOR, format!("{op}\u{338}"))
+                    }
+                    _ => return Err(self.error("\\not applies to a relation")),
+                }
+            }
+            "bmod" | "mod" => Node::new(node::OPERATOR).prop(CONTENT, "mod"),
+            "hspace" => {
+                self.eat('*');
+                let width = self.raw_group()?;
+                space(&width)
+            }
+            "pmod" => {
+                let modulus = self.parse_argument()?;
+                let body = vec![
+                    Node::new(node::OPERATOR).prop(CONTENT, "mod"),
+                    space("0.3333em"),
+                    modulus,
+                ];
+                row(vec![space("1em"), fenced("(", ")", body)])
+            }
+            "begin" => self.parse_environment()?,
+            "end" => return Err(self.error("\\end without \\begin")),
+            "displaystyle" | "textstyle" | "scriptstyle" | "scriptscriptstyle" | "nonumber"
+            | "notag" | "hline" | "strut" | "mathstrut" | "allowbreak" | "nobreak" | "limits"
+            | "nolimits" => return Ok(None),
+            "label" | "tag" | "color" => {
+                if name == "tag" {
+                    self.eat('*');
+                }
+                self.raw_group()?;
+                return Ok(None);
+            }
+            "textcolor" => {
+                self.raw_group()?;
+                self.parse_argument()?
+            }
+            "mathord" | "mathbin" | "mathrel" | "mathopen" | "mathclose" | "mathpunct"
+            | "mathinner" => self.parse_argument()?,
+            _ => {
+                self.pos = start;
+                return Err(self.error(format!("unknown command \\{name}")));
+            }
+        };
+        Ok(Some(node))
+    }
+
+    /// Read an environment after `\begin`.
+    fn parse_environment(&mut self) -> Result<Node, TexError> {
+        let env = self.raw_group()?;
+        let (open, close, default_align) = match env.as_str() {
+            "matrix" | "smallmatrix" | "array" | "subarray" => ("", "", None),
+            "pmatrix" => ("(", ")", None),
+            "bmatrix" => ("[", "]", None),
+            "Bmatrix" => ("{", "}", None),
+            "vmatrix" => ("|", "|", None),
+            "Vmatrix" => ("\u{2016}", "\u{2016}", None),
+            "cases" | "dcases" => ("{", "", Some("left")),
+            "rcases" => ("", "}", Some("left")),
+            "aligned" | "align" | "align*" | "alignat" | "alignat*" | "alignedat" | "split"
+            | "eqnarray" | "eqnarray*" => ("", "", Some("right left")),
+            "gathered" | "gather" | "gather*" | "multline" | "multline*" => {
+                ("", "", Some("center"))
+            }
+            "equation" | "equation*" | "displaymath" => {
+                let body = self.parse_list(false)?;
+                self.end_environment(&env)?;
+                return Ok(row(body));
+            }
+            _ => return Err(self.error(format!("unknown environment {env}"))),
+        };
+
+        let mut align = default_align.map(str::to_string);
+        match env.as_str() {
+            "array" | "subarray" => {
+                let spec = self.raw_group()?;
+                let columns: Vec<_> = spec
+                    .chars()
+                    .filter_map(|c| match c {
+                        'l' => Some("left"),
+                        'c' => Some("center"),
+                        'r' => Some("right"),
+                        _ => None,
+                    })
+                    .collect();
+                align = (!columns.is_empty()).then(|| columns.join(" "));
+            }
+            "alignat" | "alignat*" | "alignedat" => {
+                self.raw_group()?;
+            }
+            _ => {}
+        }
+
+        let rows = self.parse_rows(&env)?;
+        let kind = if default_align.is_some() && open.is_empty() && close.is_empty() {
+            node::MATH_TABLE
+        } else {
+            node::MATRIX
+        };
+        let mut table = Node::new(kind);
+        if let Some(align) = align {
+            // Alignments repeat across the widest row
+            let pattern: Vec<_> = align.split(' ').collect();
+            let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+            let columns: Vec<_> = (0..width.max(pattern.len()))
+                .map(|i| pattern[i % pattern.len()])
+                .collect();
+            table = table.prop(prop::COLUMN_ALIGN, columns.join(" "));
+        }
+        for cells in rows {
+            let row = Node::new(node::MATRIX_ROW).children(
+                cells
+                    .into_iter()
+                    .map(|cell| Node::new(node::MATRIX_CELL).children(cell)),
+            );
+            table = table.child(row);
+        }
+        Ok(if open.is_empty() && close.is_empty() {
+            table
+        } else {
+            fenced(open, close, vec![table])
+        })
+    }
+
+    /// Read the rows of an environment up to and including its `\end`.
+    fn parse_rows(&mut self, env: &str) -> Result<Vec<Vec<Vec<Node>>>, TexError> {
+        let mut rows = Vec::new();
+        let mut cells = Vec::new();
+        loop {
+            let variant = self.variant;
+            cells.push(self.parse_list(false)?);
+            self.variant = variant;
+            self.skip_space();
+            if self.eat('&') {
+                continue;
+            }
+            if self.eat_command("\\\\") {
+                // Skip a row spacing such as `\\[2pt]`
+                self.skip_space();
+                if self.peek() == Some('[') {
+                    while let Some(c) = self.bump()
+                        && c != ']'
+                    {}
+                }
+                rows.push(std::mem::take(&mut cells));
+                continue;
+            }
+            self.end_environment(env)?;
+            // A row break ends the last row rather than starting another
+            if !(cells.len() == 1 && cells[0].is_empty() && !rows.is_empty()) {
+                rows.push(cells);
+            }
+            return Ok(rows);
+        }
+    }
+
+    /// Read `\end{env}`.
+    fn end_environment(&mut self, env: &str) -> Result<(), TexError> {
+        if !self.eat_command("\\end") {
+            return Err(self.error(format!("missing \\end{{{env}}}")));
+        }
+        let end = self.raw_group()?;
+        if end != env {
+            return Err(self.error(format!("\\begin{{{env}}} ended by \\end{{{end}}}")));
+        }
+        Ok(())
+    }
+}
+
+const OVERBRACE: &str = "\u{23de}";
+const UNDERBRACE: &str = "\u{23df}";
+
+/// A single node, or a row of several.
+fn row(mut nodes: Vec<Node>) -> Node {
+    if nodes.len() == 1 {
+        nodes.remove(0)
+    } else {
+        Node::new(node::MATH_ROW).children(nodes)
+    }
+}
+
+fn fenced(open: &str, close: &str, children: Vec<Node>) -> Node {
+    Node::new(node::FENCED)
+        .prop(prop::OPEN_DELIM, open)
+        .prop(prop::CLOSE_DELIM, close)
+        .children(children)
+}
+
+fn space(width: &str) -> Node {
+    Node::new(node::MATH_SPACE).prop(prop::SPACE_WIDTH, width)
+}
+
+/// The width of a spacing command.
+fn space_width(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "," | "thinspace" => "0.1667em",
+        ":" | ">" | "medspace" => "0.2222em",
+        ";" | "thickspace" => "0.2778em",
+        "!" | "negthinspace" => "-0.1667em",
+        " " => "0.3333em",
+        "enspace" => "0.5em",
+        "quad" => "1em",
+        "qquad" => "2em",
+        _ => return None,
+    })
+}
+
+/// Font commands taking an argument and their variants. Where several
+/// commands give the same variant, the first is the one [`emit`] writes.
+const FONTS: &[(&str, &str)] = &[
+    ("mathrm", "normal"),
+    ("mathup", "normal"),
+    ("operatorfont", "normal"),
+    ("mathbf", "bold"),
+    ("mathbfup", "bold"),
+    ("mathit", "italic"),
+    ("boldsymbol", "bold-italic"),
+    ("bm", "bold-italic"),
+    ("mathbfit", "bold-italic"),
+    ("mathsf", "sans-serif"),
+    ("mathtt", "monospace"),
+    ("mathcal", "script"),
+    ("mathscr", "script"),
+    ("mathfrak", "fraktur"),
+    ("mathbb", "double-struck"),
+];
+
+/// The variant of a font command taking an argument.
+fn font(name: &str) -> Option<&'static str> {
+    FONTS
+        .iter()
+        .find(|(command, _)| *command == name)
+        .map(|&(_, variant)| variant)
+}
+
+/// The variant of an old-style font switch such as `\bf`.
+fn font_switch(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "rm" => "normal",
+        "bf" => "bold",
+        "it" => "italic",
+        "sf" => "sans-serif",
+        "tt" => "monospace",
+        "cal" => "script",
+        _ => return None,
+    })
+}
+
+/// Accent commands, their characters and whether they stretch.
+const ACCENTS: &[(&str, &str, bool)] = &[
+    ("hat", "^", false),
+    ("widehat", "^", true),
+    ("check", "\u{2c7}", false),
+    ("widecheck", "\u{2c7}", true),
+    ("tilde", "~", false),
+    ("widetilde", "~", true),
+    ("acute", "\u{b4}", false),
+    ("grave", "`", false),
+    ("dot", "\u{2d9}", false),
+    ("ddot", "\u{a8}", false),
+    ("dddot", "\u{20db}", false),
+    ("breve", "\u{2d8}", false),
+    ("bar", "\u{af}", false),
+    ("overline", "\u{203e}", true),
+    ("vec", "\u{2192}", false),
+    ("overrightarrow", "\u{2192}", true),
+    ("overleftarrow", "\u{2190}", true),
+    ("overleftrightarrow", "\u{2194}", true),
+    ("mathring", "\u{2da}", false),
+];
+
+/// The character of an accent command, and whether it stretches.
+fn accent(name: &str) -> Option<(&'static str, bool)> {
+    ACCENTS
+        .iter()
+        .find(|(command, _, _)| *command == name)
+        .map(|&(_, accent, stretchy)| (accent, stretchy))
+}
+
+/// Control words and what they stand for. Where several words stand for the
+/// same symbol, the first is the one [`emit`] writes.
+const SYMBOLS: &[(&str, Symbol)] = &[
+    // Greek
+    ("alpha", Symbol::Ident("α")),
+    ("beta", Symbol::Ident("β")),
+    ("gamma", Symbol::Ident("γ")),
+    ("delta", Symbol::Ident("δ")),
+    ("epsilon", Symbol::Ident("ϵ")),
+    ("varepsilon", Symbol::Ident("ε")),
+    ("zeta", Symbol::Ident("ζ")),
+    ("eta", Symbol::Ident("η")),
+    ("theta", Symbol::Ident("θ")),
+    ("vartheta", Symbol::Ident("ϑ")),
+    ("iota", Symbol::Ident("ι")),
+    ("kappa", Symbol::Ident("κ")),
+    ("varkappa", Symbol::Ident("ϰ")),
+    ("lambda", Symbol::Ident("λ")),
+    ("mu", Symbol::Ident("μ")),
+    ("nu", Symbol::Ident("ν")),
+    ("xi", Symbol::Ident("ξ")),
+    ("omicron", Symbol::Ident("ο")),
+    ("pi", Symbol::Ident("π")),
+    ("varpi", Symbol::Ident("ϖ")),
+    ("rho", Symbol::Ident("ρ")),
+    ("varrho", Symbol::Ident("ϱ")),
+    ("sigma", Symbol::Ident("σ")),
+    ("varsigma", Symbol::Ident("ς")),
+    ("tau", Symbol::Ident("τ")),
+    ("upsilon", Symbol::Ident("υ")),
+    ("phi", Symbol::Ident("ϕ")),
+    ("varphi", Symbol::Ident("φ")),
+    ("chi", Symbol::Ident("χ")),
+    ("psi", Symbol::Ident("ψ")),
+    ("omega", Symbol::Ident("ω")),
+    ("Gamma", Symbol::Upright("Γ")),
+    ("Delta", Symbol::Upright("Δ")),
+    ("Theta", Symbol::Upright("Θ")),
+    ("Lambda", Symbol::Upright("Λ")),
+    ("Xi", Symbol::Upright("Ξ")),
+    ("Pi", Symbol::Upright("Π")),
+    ("Sigma", Symbol::Upright("Σ")),
+    ("Upsilon", Symbol::Upright("Υ")),
+    ("Phi", Symbol::Upright("Φ")),
+    ("Psi", Symbol::Upright("Ψ")),
+    ("Omega", Symbol::Upright("Ω")),
+    ("digamma", Symbol::Ident("ϝ")),
+    // Letter-like symbols
+    ("infty", Symbol::Ident("∞")),
+    ("partial", Symbol::Ident("∂")),
+    ("nabla", Symbol::Ident("∇")),
+    ("emptyset", Symbol::Ident("∅")),
+    ("varnothing", Symbol::Ident("∅")),
+    ("hbar", Symbol::Ident("ℏ")),
+    ("hslash", Symbol::Ident("ℏ")),
+    ("ell", Symbol::Ident("ℓ")),
+    ("imath", Symbol::Ident("ı")),
+    ("jmath", Symbol::Ident("ȷ")),
+    ("Re", Symbol::Ident("ℜ")),
+    ("Im", Symbol::Ident("ℑ")),
+    ("aleph", Symbol::Ident("ℵ")),
+    ("beth", Symbol::Ident("ℶ")),
+    ("wp", Symbol::Ident("℘")),
+    ("angle", Symbol::Ident("∠")),
+    ("measuredangle", Symbol::Ident("∡")),
+    ("triangle", Symbol::Ident("△")),
+    ("square", Symbol::Ident("□")),
+    ("Box", Symbol::Ident("□")),
+    ("blacksquare", Symbol::Ident("■")),
+    ("Diamond", Symbol::Ident("◇")),
+    ("clubsuit", Symbol::Ident("♣")),
+    ("diamondsuit", Symbol::Ident("♢")),
+    ("heartsuit", Symbol::Ident("♡")),
+    ("spadesuit", Symbol::Ident("♠")),
+    ("flat", Symbol::Ident("♭")),
+    ("natural", Symbol::Ident("♮")),
+    ("sharp", Symbol::Ident("♯")),
+    ("top", Symbol::Ident("⊤")),
+    ("bot", Symbol::Ident("⊥")),
+    // Binary operators
+    ("pm", Symbol::Op("±")),
+    ("mp", Symbol::Op("∓")),
+    ("times", Symbol::Op("×")),
+    ("div", Symbol::Op("÷")),
+    ("cdot", Symbol::Op("⋅")),
+    ("ast", Symbol::Op("∗")),
+    ("star", Symbol::Op("⋆")),
+    ("circ", Symbol::Op("∘")),
+    ("bullet", Symbol::Op("∙")),
+    ("oplus", Symbol::Op("⊕")),
+    ("ominus", Symbol::Op("⊖")),
+    ("otimes", Symbol::Op("⊗")),
+    ("odot", Symbol::Op("⊙")),
+    ("oslash", Symbol::Op("⊘")),
+    ("cup", Symbol::Op("∪")),
+    ("cap", Symbol::Op("∩")),
+    ("sqcup", Symbol::Op("⊔")),
+    ("sqcap", Symbol::Op("⊓")),
+    ("uplus", Symbol::Op("⊎")),
+    ("setminus", Symbol::Op("∖")),
+    ("smallsetminus", Symbol::Op("∖")),
+    ("wedge", Symbol::Op("∧")),
+    ("land", Symbol::Op("∧")),
+    ("vee", Symbol::Op("∨")),
+    ("lor", Symbol::Op("∨")),
+    ("neg", Symbol::Op("¬")),
+    ("lnot", Symbol::Op("¬")),
+    ("dagger", Symbol::Op("†")),
+    ("ddagger", Symbol::Op("‡")),
+    ("amalg", Symbol::Op("⨿")),
+    ("wr", Symbol::Op("≀")),
+    ("diamond", Symbol::Op("⋄")),
+    ("triangleleft", Symbol::Op("◁")),
+    ("triangleright", Symbol::Op("▷")),
+    ("bigtriangleup", Symbol::Op("△")),
+    ("bigtriangledown", Symbol::Op("▽")),
+    // Relations
+    ("leq", Symbol::Op("≤")),
+    ("le", Symbol::Op("≤")),
+    ("geq", Symbol::Op("≥")),
+    ("ge", Symbol::Op("≥")),
+    ("leqslant", Symbol::Op("⩽")),
+    ("geqslant", Symbol::Op("⩾")),
+    ("neq", Symbol::Op("≠")),
+    ("ne", Symbol::Op("≠")),
+    ("approx", Symbol::Op("≈")),
+    ("equiv", Symbol::Op("≡")),
+    ("sim", Symbol::Op("∼")),
+    ("simeq", Symbol::Op("≃")),
+    ("cong", Symbol::Op("≅")),
+    ("propto", Symbol::Op("∝")),
+    ("in", Symbol::Op("∈")),
+    ("notin", Symbol::Op("∉")),
+    ("ni", Symbol::Op("∋")),
+    ("owns", Symbol::Op("∋")),
+    ("subset", Symbol::Op("⊂")),
+    ("supset", Symbol::Op("⊃")),
+    ("subseteq", Symbol::Op("⊆")),
+    ("supseteq", Symbol::Op("⊇")),
+    ("subsetneq", Symbol::Op("⊊")),
+    ("supsetneq", Symbol::Op("⊋")),
+    ("sqsubseteq", Symbol::Op("⊑")),
+    ("sqsupseteq", Symbol::Op("⊒")),
+    ("ll", Symbol::Op("≪")),
+    ("gg", Symbol::Op("≫")),
+    ("lesssim", Symbol::Op("≲")),
+    ("gtrsim", Symbol::Op("≳")),
+    ("prec", Symbol::Op("≺")),
+    ("succ", Symbol::Op("≻")),
+    ("preceq", Symbol::Op("⪯")),
+    ("succeq", Symbol::Op("⪰")),
+    ("perp", Symbol::Op("⊥")),
+    ("parallel", Symbol::Op("∥")),
+    ("mid", Symbol::Op("∣")),
+    ("nmid", Symbol::Op("∤")),
+    ("models", Symbol::Op("⊨")),
+    ("vdash", Symbol::Op("⊢")),
+    ("dashv", Symbol::Op("⊣")),
+    ("doteq", Symbol::Op("≐")),
+    ("asymp", Symbol::Op("≍")),
+    ("coloneqq", Symbol::Op("≔")),
+    // Arrows
+    ("to", Symbol::Op("→")),
+    ("rightarrow", Symbol::Op("→")),
+    ("gets", Symbol::Op("←")),
+    ("leftarrow", Symbol::Op("←")),
+    ("leftrightarrow", Symbol::Op("↔")),
+    ("Rightarrow", Symbol::Op("⇒")),
+    ("Leftarrow", Symbol::Op("⇐")),
+    ("Leftrightarrow", Symbol::Op("⇔")),
+    ("longrightarrow", Symbol::Op("⟶")),
+    ("longleftarrow", Symbol::Op("⟵")),
+    ("longleftrightarrow", Symbol::Op("⟷")),
+    ("Longrightarrow", Symbol::Op("⟹")),
+    ("implies", Symbol::Op("⟹")),
+    ("Longleftarrow", Symbol::Op("⟸")),
+    ("impliedby", Symbol::Op("⟸")),
+    ("Longleftrightarrow", Symbol::Op("⟺")),
+    ("iff", Symbol::Op("⟺")),
+    ("mapsto", Symbol::Op("↦")),
+    ("longmapsto", Symbol::Op("⟼")),
+    ("uparrow", Symbol::Op("↑")),
+    ("downarrow", Symbol::Op("↓")),
+    ("updownarrow", Symbol::Op("↕")),
+    ("Uparrow", Symbol::Op("⇑")),
+    ("Downarrow", Symbol::Op("⇓")),
+    ("hookrightarrow", Symbol::Op("↪")),
+    ("hookleftarrow", Symbol::Op("↩")),
+    ("rightharpoonup", Symbol::Op("⇀")),
+    ("leftharpoonup", Symbol::Op("↼")),
+    ("rightleftharpoons", Symbol::Op("⇌")),
+    ("nearrow", Symbol::Op("↗")),
+    ("searrow", Symbol::Op("↘")),
+    ("nwarrow", Symbol::Op("↖")),
+    ("swarrow", Symbol::Op("↙")),
+    // Logic, dots and punctuation
+    ("forall", Symbol::Op("∀")),
+    ("exists", Symbol::Op("∃")),
+    ("nexists", Symbol::Op("∄")),
+    ("therefore", Symbol::Op("∴")),
+    ("because", Symbol::Op("∵")),
+    ("ldots", Symbol::Op("…")),
+    ("dots", Symbol::Op("…")),
+    ("dotsc", Symbol::Op("…")),
+    ("dotso", Symbol::Op("…")),
+    ("cdots", Symbol::Op("⋯")),
+    ("dotsb", Symbol::Op("⋯")),
+    ("dotsm", Symbol::Op("⋯")),
+    ("dotsi", Symbol::Op("⋯")),
+    ("vdots", Symbol::Op("⋮")),
+    ("ddots", Symbol::Op("⋱")),
+    ("colon", Symbol::Op(":")),
+    ("prime", Symbol::Op("′")),
+    ("%", Symbol::Op("%")),
+    ("$", Symbol::Op("$")),
+    ("#", Symbol::Op("#")),
+    ("&", Symbol::Op("&")),
+    ("_", Symbol::Op("_")),
+    // Delimiters
+    ("{", Symbol::Op("{")),
+    ("lbrace", Symbol::Op("{")),
+    ("}", Symbol::Op("}")),
+    ("rbrace", Symbol::Op("}")),
+    ("lbrack", Symbol::Op("[")),
+    ("rbrack", Symbol::Op("]")),
+    ("langle", Symbol::Op("⟨")),
+    ("rangle", Symbol::Op("⟩")),
+    ("lfloor", Symbol::Op("⌊")),
+    ("rfloor", Symbol::Op("⌋")),
+    ("lceil", Symbol::Op("⌈")),
+    ("rceil", Symbol::Op("⌉")),
+    ("vert", Symbol::Op("|")),
+    ("lvert", Symbol::Op("|")),
+    ("rvert", Symbol::Op("|")),
+    ("|", Symbol::Op("‖")),
+    ("Vert", Symbol::Op("‖")),
+    ("lVert", Symbol::Op("‖")),
+    ("rVert", Symbol::Op("‖")),
+    ("backslash", Symbol::Op("\\")),
+    // Large operators
+    ("sum", Symbol::Large("∑", true)),
+    ("prod", Symbol::Large("∏", true)),
+    ("coprod", Symbol::Large("∐", true)),
+    ("bigcup", Symbol::Large("⋃", true)),
+    ("bigcap", Symbol::Large("⋂", true)),
+    ("bigsqcup", Symbol::Large("⨆", true)),
+    ("bigvee", Symbol::Large("⋁", true)),
+    ("bigwedge", Symbol::Large("⋀", true)),
+    ("bigoplus", Symbol::Large("⨁", true)),
+    ("bigotimes", Symbol::Large("⨂", true)),
+    ("bigodot", Symbol::Large("⨀", true)),
+    ("biguplus", Symbol::Large("⨄", true)),
+    ("int", Symbol::Large("∫", false)),
+    ("iint", Symbol::Large("∬", false)),
+    ("iiint", Symbol::Large("∭", false)),
+    ("oint", Symbol::Large("∮", false)),
+    ("oiint", Symbol::Large("∯", false)),
+    // Function names
+    ("arccos", Symbol::Function),
+    ("arcsin", Symbol::Function),
+    ("arctan", Symbol::Function),
+    ("arg", Symbol::Function),
+    ("cos", Symbol::Function),
+    ("cosh", Symbol::Function),
+    ("cot", Symbol::Function),
+    ("coth", Symbol::Function),
+    ("csc", Symbol::Function),
+    ("deg", Symbol::Function),
+    ("dim", Symbol::Function),
+    ("exp", Symbol::Function),
+    ("hom", Symbol::Function),
+    ("ker", Symbol::Function),
+    ("lg", Symbol::Function),
+    ("ln", Symbol::Function),
+    ("log", Symbol::Function),
+    ("sec", Symbol::Function),
+    ("sin", Symbol::Function),
+    ("sinh", Symbol::Function),
+    ("tan", Symbol::Function),
+    ("tanh", Symbol::Function),
+    ("det", Symbol::Limits),
+    ("gcd", Symbol::Limits),
+    ("inf", Symbol::Limits),
+    ("lim", Symbol::Limits),
+    ("liminf", Symbol::Limits),
+    ("limsup", Symbol::Limits),
+    ("max", Symbol::Limits),
+    ("min", Symbol::Limits),
+    ("Pr", Symbol::Limits),
+    ("sup", Symbol::Limits),
+];
+
+/// The symbol a control word stands for.
+fn symbol(name: &str) -> Option<Symbol> {
+    SYMBOLS
+        .iter()
+        .find(|(word, _)| *word == name)
+        .map(|&(_, symbol)| symbol)
+}
+
+/// A control word for a token, preferring identifiers for identifiers and
+/// operators for operators.
+fn command_for(content: &str, identifier: bool) -> Option<&'static str> {
+    let find = |identifier: bool| {
+        SYMBOLS.iter().find_map(|&(word, symbol)| {
+            let found = match symbol {
+                Symbol::Ident(c) | Symbol::Upright(c) => identifier && c == content,
+                Symbol::Op(c) | Symbol::Large(c, _) => !identifier && c == content,
+                Symbol::Function | Symbol::Limits => false,
+            };
+            found.then_some(word)
+        })
+    };
+    find(identifier).or_else(|| find(!identifier))
+}
+
+/// Nodes in sequence, spaced around operators and wherever a control word
+/// would otherwise run into a letter.
+fn emit_list(nodes: &[Node]) -> String {
+    let mut out = String::new();
+    let mut space_after = false;
+    let mut after_token = true;
+    for node in nodes {
+        let tex = emit_node(node);
+        let kind = node.kind.as_str();
+        let spaced = kind == node::OPERATOR
+            && !matches!(
+                node.props.get_str(CONTENT),
+                Some("(" | ")" | "[" | "]" | "|" | "," | "." | "!" | "\u{2032}")
+            );
+        // Spaces set off the operands of operators and larger constructs
+        let word_follows = tex.starts_with(|c: char| c.is_alphanumeric() || c == '\\');
+        if !out.is_empty()
+            && (space_after
+                || spaced
+                || (!after_token && word_follows)
+                || runs_together(&out, &tex))
+        {
+            out.push(' ');
+        }
+        out.push_str(&tex);
+        space_after = spaced;
+        after_token = matches!(kind, node::IDENTIFIER | node::NUMBER | node::OPERATOR);
+    }
+    out
+}
+
+/// Whether `next` would be read as part of `prev` without a space between.
+fn runs_together(prev: &str, next: &str) -> bool {
+    let word = prev.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let after_word = word.len() < prev.len() && word.ends_with('\\');
+    let after_digit = prev.ends_with(|c: char| c.is_ascii_digit());
+    (after_word && next.starts_with(|c: char| c.is_ascii_alphabetic()))
+        || (after_digit && next.starts_with(|c: char| c.is_ascii_digit()))
+}
+
+/// A node as the argument of a command or script.
+fn emit_argument(node: &Node) -> String {
+    let tex = emit_node(node);
+    if node.kind.as_str() == node::MATH_ROW || tex.chars().count() == 1 {
+        tex
+    } else {
+        format!("{{{tex}}}")
+    }
+}
+
+/// An argument in braces even when it is a single character.
+fn braced(node: Option<&Node>) -> String {
+    let tex = node.map(emit_argument).unwrap_or_default();
+    if tex.starts_with('{') {
+        tex
+    } else {
+        format!("{{{tex}}}")
+    }
+}
+
+fn emit_node(node: &Node) -> String {
+    let child = |i: usize| node.children.get(i);
+    match node.kind.as_str() {
+        node::IDENTIFIER | node::OPERATOR | node::NUMBER => emit_token(node),
+        node::MATH_TEXT => {
+            let command = match node.props.get_str(prop::MATH_VARIANT) {
+                Some("bold") => "textbf",
+                Some("italic") => "textit",
+                Some("monospace") => "texttt",
+                Some("sans-serif") => "textsf",
+                _ => "text",
+            };
+            let text = node.props.get_str(CONTENT).unwrap_or_default();
+            format!(r"\{command}{{{}}}", escape_text(text))
+        }
+        node::MATH_SPACE => {
+            let width = node.props.get_str(prop::SPACE_WIDTH).unwrap_or("0em");
+            let command = [
+                r"\,",
+                r"\:",
+                r"\;",
+                r"\!",
+                r"\ ",
+                r"\enspace",
+                r"\quad",
+                r"\qquad",
+            ]
+            .into_iter()
+            .find(|command| space_width(&command[1..]) == Some(width));
+            match command {
+                Some(command) => command.to_string(),
+                None => format!(r"\hspace{{{width}}}"),
+            }
+        }
+        node::MATH_ROW => format!("{{{}}}", emit_list(&node.children)),
+        node::FRACTION if node.props.get_str(prop::LINE_THICKNESS) == Some("0") => {
+            let part = |i| child(i).map(emit_node).unwrap_or_default();
+            format!(r"{{{} \atop {}}}", part(0), part(1))
+        }
+        node::FRACTION => format!(r"\frac{}{}", braced(child(0)), braced(child(1))),
+        node::ROOT => match child(1) {
+            Some(index) => format!(r"\sqrt[{}]{}", emit_node(index), braced(child(0))),
+            None => format!(r"\sqrt{}", braced(child(0))),
+        },
+        node::MATH_SUB => emit_scripts(child(0), child(1), None),
+        node::MATH_SUP => emit_scripts(child(0), None, child(1)),
+        node::MATH_SUBSUP => emit_scripts(child(0), child(1), child(2)),
+        node::MATH_UNDER => emit_limits(child(0), child(1), None),
+        node::MATH_OVER => emit_limits(child(0), None, child(1)),
+        node::MATH_UNDEROVER => emit_limits(child(0), child(1), child(2)),
+        node::MATRIX | node::MATH_TABLE => emit_table(node, None),
+        node::FENCED => emit_fenced(node),
+        node::ACCENT => {
+            let accent = node.props.get_str(prop::ACCENT_CHAR).unwrap_or_default();
+            let stretchy = node.props.get_bool(prop::ACCENT_STRETCHY) == Some(true);
+            let command = ACCENTS
+                .iter()
+                .find(|&&(_, c, s)| c == accent && s == stretchy)
+                .or_else(|| ACCENTS.iter().find(|&&(_, c, _)| c == accent));
+            match command {
+                Some((name, _, _)) => format!(r"\{name}{}", braced(child(0))),
+                None => format!(r"\overset{{{}}}{}", escape(accent, false), braced(child(0))),
+            }
+        }
+        node::MATH_BRACE => {
+            let under = node.props.get_str(prop::ACCENT_CHAR) == Some(UNDERBRACE);
+            let (command, script) = if under {
+                ("underbrace", '_')
+            } else {
+                ("overbrace", '^')
+            };
+            let mut tex = format!(r"\{command}{}", braced(child(0)));
+            if let Some(annotation) = child(1) {
+                tex.push(script);
+                tex.push_str(&emit_argument(annotation));
+            }
+            tex
+        }
+        node::MATH_STRIKE => format!(r"\cancel{{{}}}", emit_list(&node.children)),
+        node::ENCLOSED => format!(r"\boxed{{{}}}", emit_list(&node.children)),
+        _ => emit_list(&node.children),
+    }
+}
+
+/// A base with a subscript and superscript.
+fn emit_scripts(base: Option<&Node>, sub: Option<&Node>, sup: Option<&Node>) -> String {
+    let tex = match base {
+        // Scripts on scripts need a group
+        Some(base) if is_scripted(base) => format!("{{{}}}", emit_node(base)),
+        // Operators taking limits take them as scripts only when told
+        Some(base) if base.props.get_bool(prop::MOVE_LIMITS) == Some(true) => {
+            format!(r"{}\nolimits", emit_node(base))
+        }
+        Some(base) => emit_node(base),
+        None => "{}".to_string(),
+    };
+    push_scripts(tex, sub, sup)
+}
+
+fn push_scripts(mut tex: String, sub: Option<&Node>, sup: Option<&Node>) -> String {
+    if let Some(sub) = sub {
+        tex.push('_');
+        tex.push_str(&emit_argument(sub));
+    }
+    if let Some(sup) = sup {
+        // Primes are written as they are typed
+        match sup.props.get_str(CONTENT) {
+            Some("\u{2032}") => tex.push('\''),
+            Some("\u{2033}") => tex.push_str("''"),
+            Some("\u{2034}") => tex.push_str("'''"),
+            _ => {
+                tex.push('^');
+                tex.push_str(&emit_argument(sup));
+            }
+        }
+    }
+    tex
+}
+
+fn is_scripted(node: &Node) -> bool {
+    matches!(
+        node.kind.as_str(),
+        node::MATH_SUB
+            | node::MATH_SUP
+            | node::MATH_SUBSUP
+            | node::MATH_UNDER
+            | node::MATH_OVER
+            | node::MATH_UNDEROVER
+    )
+}
+
+/// A base with an underscript and overscript: the limits of an operator,
+/// or expressions stacked above and below.
+fn emit_limits(base: Option<&Node>, under: Option<&Node>, over: Option<&Node>) -> String {
+    if let Some(op) = base.filter(|base| base.kind.as_str() == node::OPERATOR) {
+        if op.props.get_bool(prop::MOVE_LIMITS) == Some(true) {
+            return push_scripts(emit_node(op), under, over);
+        }
+        if op.props.get_bool(prop::LARGE_OP) == Some(true) {
+            return push_scripts(format!(r"{}\limits", emit_node(op)), under, over);
+        }
+    }
+    let arrow = match base.and_then(|n| n.props.get_str(CONTENT)) {
+        Some("\u{2192}") => Some("xrightarrow"),
+        Some("\u{2190}") => Some("xleftarrow"),
+        _ => None,
+    };
+    if let (Some(arrow), Some(over)) = (arrow, over) {
+        let under = under
+            .map(|under| format!("[{}]", emit_node(under)))
+            .unwrap_or_default();
+        return format!(r"\{arrow}{under}{}", braced(Some(over)));
+    }
+    if over.is_none() && under.and_then(|n| n.props.get_str(CONTENT)) == Some("_") {
+        return format!(r"\underline{}", braced(base));
+    }
+    let mut tex = braced(base);
+    if let Some(over) = over {
+        tex = format!(r"\overset{}{tex}", braced(Some(over)));
+    }
+    if let Some(under) = under {
+        tex = format!(r"\underset{}{{{tex}}}", braced(Some(under)));
+    }
+    tex
+}
+
+/// A fenced expression, as a matrix or `cases` environment, `\binom` or
+/// `\left…\right`.
+fn emit_fenced(node: &Node) -> String {
+    let open = node.props.get_str(prop::OPEN_DELIM).unwrap_or_default();
+    let close = node.props.get_str(prop::CLOSE_DELIM).unwrap_or_default();
+    if let [only] = node.children.as_slice() {
+        let environment = match (open, close) {
+            ("(", ")") => "pmatrix",
+            ("[", "]") => "bmatrix",
+            ("{", "}") => "Bmatrix",
+            ("|", "|") => "vmatrix",
+            ("\u{2016}", "\u{2016}") => "Vmatrix",
+            ("{", "") => "cases",
+            ("", "}") => "rcases",
+            _ => "",
+        };
+        let cases = environment.ends_with("cases");
+        let left_aligned = only
+            .props
+            .get_str(prop::COLUMN_ALIGN)
+            .is_some_and(|align| align.split(' ').all(|a| a == "left"));
+        match only.kind.as_str() {
+            node::MATRIX if !environment.is_empty() && cases == left_aligned => {
+                return emit_table(only, Some(environment));
+            }
+            node::FRACTION
+                if (open, close) == ("(", ")")
+                    && only.props.get_str(prop::LINE_THICKNESS) == Some("0") =>
+            {
+                let part = |i| braced(only.children.get(i));
+                return format!(r"\binom{}{}", part(0), part(1));
+            }
+            _ => {}
+        }
+    }
+    format!(
+        r"\left{} {} \right{}",
+        delimiter(open),
+        emit_list(&node.children),
+        delimiter(close)
+    )
+}
+
+/// A delimiter after `\left` or `\right`.
+fn delimiter(delimiter: &str) -> String {
+    match delimiter {
+        "" => ".".to_string(),
+        _ => escape(delimiter, false),
+    }
+}
+
+/// A matrix or table, in `environment` or one that its alignment calls for.
+fn emit_table(node: &Node, environment: Option<&str>) -> String {
+    let align = node.props.get_str(prop::COLUMN_ALIGN);
+    let columns: Vec<_> = align.map(|a| a.split(' ').collect()).unwrap_or_default();
+    let aligned = !columns.is_empty()
+        && columns
+            .iter()
+            .zip(["right", "left"].into_iter().cycle())
+            .all(|(a, b)| *a == b);
+    let mut spec = String::new();
+    let environment = match environment {
+        Some(environment) => environment,
+        None if node.kind.as_str() == node::MATH_TABLE && aligned => "aligned",
+        None if node.kind.as_str() == node::MATH_TABLE
+            && !columns.is_empty()
+            && columns.iter().all(|a| *a == "center") =>
+        {
+            "gathered"
+        }
+        None if columns.is_empty() => "matrix",
+        None => {
+            spec = columns
+                .iter()
+                .map(|a| match *a {
+                    "left" => 'l',
+                    "right" => 'r',
+                    _ => 'c',
+                })
+                .collect();
+            spec = format!("{{{spec}}}");
+            "array"
+        }
+    };
+    let rows: Vec<_> = node
+        .children
+        .iter()
+        .map(|row| {
+            let cells: Vec<_> = row
+                .children
+                .iter()
+                .map(|c| emit_list(&c.children))
+                .collect();
+            cells.join(" & ")
+        })
+        .collect();
+    format!(
+        r"\begin{{{environment}}}{spec} {} \end{{{environment}}}",
+        rows.join(r" \\ ")
+    )
+}
+
+/// An identifier, operator or number, in its font.
+fn emit_token(node: &Node) -> String {
+    let content = node.props.get_str(CONTENT).unwrap_or_default();
+    let variant = node.props.get_str(prop::MATH_VARIANT);
+    let identifier = node.kind.as_str() == node::IDENTIFIER;
+    let operator = node.kind.as_str() == node::OPERATOR;
+    let limits = node.props.get_bool(prop::MOVE_LIMITS) == Some(true);
+
+    // Words: function names and operator names
+    let is_word = content.chars().count() > 1
+        && content
+            .chars()
+            .all(|c| c.is_alphabetic() || c == ' ' || c == '\u{2009}');
+    if is_word && variant.is_none() {
+        let word = content.replace(' ', "");
+        match symbol(&word) {
+            Some(Symbol::Function) if identifier => return format!(r"\{word}"),
+            Some(Symbol::Limits) if operator => return format!(r"\{word}"),
+            _ => {}
+        }
+        if operator && content == "mod" {
+            return r"\bmod".to_string();
+        }
+        let star = if operator && limits { "*" } else { "" };
+        return format!(
+            r"\operatorname{star}{{{}}}",
+            content.replace('\u{2009}', r"\,")
+        );
+    }
+
+    let tex = match content.strip_suffix('\u{338}') {
+        Some(relation) if operator => format!(r"\not{}", escape(relation, false)),
+        _ if node.kind.as_str() == node::NUMBER => content.to_string(),
+        _ => escape(content, identifier),
+    };
+    let upright = tex
+        .strip_prefix('\\')
+        .is_some_and(|word| matches!(symbol(word), Some(Symbol::Upright(_))));
+    match variant {
+        // Capital Greek is upright anyway
+        Some("normal") if upright => tex,
+        Some(variant) => match FONTS.iter().find(|&&(_, v)| v == variant) {
+            Some((command, _)) => format!(r"\{command}{{{tex}}}"),
+            None => tex,
+        },
+        None => tex,
+    }
+}
+
+/// The TeX for the text of a token, with control words for the characters
+/// that have them.
+fn escape(content: &str, identifier: bool) -> String {
+    let mut out = String::new();
+    let mut after_word = false;
+    for c in content.chars() {
+        if after_word && c.is_ascii_alphabetic() {
+            out.push(' ');
+        }
+        after_word = false;
+        match c {
+            '\u{2212}' => out.push('-'),
+            '\u{2217}' => out.push('*'),
+            '{' | '}' | '$' | '%' | '#' | '&' | '_' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '^' => out.push_str(r"\text{^}"),
+            '~' => out.push_str(r"\sim"),
+            c if c.is_ascii() && c != '\\' => out.push(c),
+            c => match command_for(c.encode_utf8(&mut [0; 4]), identifier) {
+                Some(word) => {
+                    out.push('\\');
+                    out.push_str(word);
+                    after_word = true;
+                }
+                None => out.push(c),
+            },
+        }
+    }
+    out
+}
+
+/// The text of `\text`, escaped.
+fn escape_text(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        match c {
+            '{' | '}' | '$' | '%' | '#' | '&' | '_' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\u{a0}' => out.push('~'),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A compact rendering of nodes: tokens as their content, other nodes
+    /// as their kind without the `math:` prefix and their children.
+    fn show(nodes: &[Node]) -> String {
+        nodes.iter().map(show_node).collect::<Vec<_>>().join(" ")
+    }
+
+    fn show_node(node: &Node) -> String {
+        if let Some(content) = node.props.get_str(CONTENT) {
+            return content.to_string();
+        }
+        let kind = node.kind.as_str().trim_start_matches("math:");
+        if node.kind.as_str() == node::MATH_SPACE {
+            return format!("space({})", node.props.get_str(prop::SPACE_WIDTH).unwrap());
+        }
+        format!("{kind}({})", show(&node.children))
+    }
+
+    fn parsed(source: &str) -> String {
+        show(&parse(source).unwrap())
+    }
+
+    #[test]
+    fn test_tokens() {
+        let nodes = parse(r"2x - 3.5y \leq \alpha\Gamma").unwrap();
+        assert_eq!(show(&nodes), "2 x − 3.5 y ≤ α Γ");
+        let kinds: Vec<_> = nodes.iter().map(|n| n.kind.as_str()).collect();
+        assert_eq!(
+            kinds,
+            [
+                node::NUMBER,
+                node::IDENTIFIER,
+                node::OPERATOR,
+                node::NUMBER,
+                node::IDENTIFIER,
+                node::OPERATOR,
+                node::IDENTIFIER,
+                node::IDENTIFIER
+            ]
+        );
+        assert_eq!(nodes[7].props.get_str(prop::MATH_VARIANT), Some("normal"));
+        assert_eq!(parsed(r"a\,b\quad c"), "a space(0.1667em) b space(1em) c");
+        assert_eq!(parsed("% comment\nx"), "x");
+    }
+
+    #[test]
+    fn test_fractions_roots_and_scripts() {
+        assert_eq!(parsed(r"\frac{a+1}{2}"), "fraction(row(a + 1) 2)");
+        assert_eq!(parsed(r"\frac12"), "fraction(1 2)");
+        assert_eq!(parsed(r"\sqrt[3]{x}"), "root(x 3)");
+        assert_eq!(parsed(r"\sqrt x"), "root(x)");
+        assert_eq!(parsed("x_i^2"), "subsup(x i 2)");
+        assert_eq!(parsed("x^{10}"), "sup(x 10)");
+        assert_eq!(parsed("e^{-x}"), "sup(e row(− x))");
+        assert_eq!(parsed("f'(x)"), "sup(f ′) ( x )");
+        assert_eq!(parsed("f''^2"), "sup(f row(″ 2))");
+        assert_eq!(parsed("{}^{14}C"), "sup(row() 14) C");
+        assert_eq!(parsed(r"{a \over b}"), "fraction(a b)");
+        let binom = parse(r"\binom{n}{k}").unwrap();
+        assert_eq!(show(&binom), "fenced(fraction(n k))");
+        assert_eq!(
+            binom[0].children[0].props.get_str(prop::LINE_THICKNESS),
+            Some("0")
+        );
+    }
+
+    #[test]
+    fn test_large_operators_and_functions() {
+        let nodes = parse(r"\sum_{i=1}^n i").unwrap();
+        assert_eq!(show(&nodes), "underover(∑ row(i = 1) n) i");
+        let sum = &nodes[0].children[0];
+        assert_eq!(sum.props.get_bool(prop::LARGE_OP), Some(true));
+        assert_eq!(sum.props.get_bool(prop::MOVE_LIMITS), Some(true));
+
+        assert_eq!(parsed(r"\int_0^1"), "subsup(∫ 0 1)");
+        assert_eq!(parsed(r"\int\limits_0^1"), "underover(∫ 0 1)");
+        assert_eq!(parsed(r"\sum\nolimits_i"), "sub(∑ i)");
+        assert_eq!(parsed(r"\lim_{x\to 0}"), "under(lim row(x → 0))");
+        assert_eq!(parsed(r"\sin^2\theta"), "sup(sin 2) θ");
+        assert_eq!(parsed(r"\operatorname{Tr} A"), "Tr A");
+        assert_eq!(
+            parsed(r"\operatorname*{arg\,max}_x"),
+            "under(arg\u{2009}max x)"
+        );
+    }
+
+    #[test]
+    fn test_fences_and_environments() {
+        let nodes = parse(r"\left( \frac{a}{b} \right.").unwrap();
+        assert_eq!(show(&nodes), "fenced(fraction(a b))");
+        assert_eq!(nodes[0].props.get_str(prop::OPEN_DELIM), Some("("));
+        assert_eq!(nodes[0].props.get_str(prop::CLOSE_DELIM), Some(""));
+        assert_eq!(
+            parsed(r"\left\langle x \middle| y \right\rangle"),
+            "fenced(x | y)"
+        );
+        assert_eq!(parsed(r"\bigl( x \bigr)"), "( x )");
+
+        let nodes = parse(r"\begin{pmatrix} 1 & 0 \\ 0 & 1 \end{pmatrix}").unwrap();
+        assert_eq!(
+            show(&nodes),
+            "fenced(matrix(matrix_row(matrix_cell(1) matrix_cell(0)) \
+             matrix_row(matrix_cell(0) matrix_cell(1))))"
+        );
+
+        let nodes = parse(r"\begin{aligned} a &= b \\ &= c \\ \end{aligned}").unwrap();
+        assert_eq!(
+            show(&nodes),
+            "table(matrix_row(matrix_cell(a) matrix_cell(= b)) \
+             matrix_row(matrix_cell() matrix_cell(= c)))"
+        );
+        assert_eq!(
+            nodes[0].props.get_str(prop::COLUMN_ALIGN),
+            Some("right left")
+        );
+
+        let nodes = parse(r"\begin{cases} 1 & x > 0 \\ 0 & \text{otherwise} \end{cases}").unwrap();
+        assert_eq!(nodes[0].props.get_str(prop::OPEN_DELIM), Some("{"));
+        let matrix = &nodes[0].children[0];
+        assert_eq!(matrix.props.get_str(prop::COLUMN_ALIGN), Some("left left"));
+        assert_eq!(
+            show(&matrix.children[1].children),
+            "matrix_cell(0) matrix_cell(otherwise)"
+        );
+
+        let nodes = parse(r"\begin{array}{l|r} a & b \end{array}").unwrap();
+        assert_eq!(
+            nodes[0].props.get_str(prop::COLUMN_ALIGN),
+            Some("left right")
+        );
+    }
+
+    #[test]
+    fn test_accents_fonts_and_text() {
+        let nodes = parse(r"\hat{x} \widetilde{AB}").unwrap();
+        assert_eq!(show(&nodes), "accent(x) accent(row(A B))");
+        assert_eq!(nodes[0].props.get_str(prop::ACCENT_CHAR), Some("^"));
+        assert_eq!(nodes[0].props.get_bool(prop::ACCENT_STRETCHY), None);
+        assert_eq!(nodes[1].props.get_bool(prop::ACCENT_STRETCHY), Some(true));
+
+        let nodes = parse(r"\mathbb{R}^n \mathrm{d}x").unwrap();
+        assert_eq!(show(&nodes), "sup(R n) d x");
+        let real = &nodes[0].children[0];
+        assert_eq!(
+            real.props.get_str(prop::MATH_VARIANT),
+            Some("double-struck")
+        );
+        assert_eq!(nodes[1].props.get_str(prop::MATH_VARIANT), Some("normal"));
+        assert_eq!(nodes[2].props.get_str(prop::MATH_VARIANT), None);
+        assert_eq!(parsed(r"\mathrm{max}"), "max");
+        assert_eq!(parsed(r"{\bf v} w"), "v w");
+
+        assert_eq!(parsed(r"x \text{if } y"), "x if  y");
+        assert_eq!(parsed(r"\overbrace{a+b}^{n}"), "brace(row(a + b) n)");
+        assert_eq!(
+            parsed(r"\underbrace{a}_{k} \overset{!}{=}"),
+            "brace(a k) over(= !)"
+        );
+        assert_eq!(parsed(r"a \not= b \not\in C"), "a ≠ b ∉ C");
+        assert_eq!(parsed(r"\displaystyle\color{red} x \label{eq}"), "x");
+    }
+
+    #[test]
+    fn test_output_matches_schema() {
+        let source = r"\sum_{k=0}^{n} \binom{n}{k} x^k = \left( 1 + x \right)^n \quad
+            \begin{pmatrix} \hat a & \mathbf{b} \\ \text{c} & \cancel{d} \end{pmatrix}
+            \overbrace{x}^{y} \sqrt[3]{z} \boxed{\lim_{t \to 0} f'(t)}";
+        let math = Node::new(node::MATH_DISPLAY).children(parse(source).unwrap());
+        let doc = rescribe_core::Document::new().with_content(Node::new("document").child(math));
+        let messages: Vec<_> = crate::schema()
+            .validate(&doc)
+            .into_iter()
+            .map(|w| w.message)
+            .collect();
+        assert!(messages.is_empty(), "{messages:?}");
+    }
+
+    /// Whether two node lists are the same, ignoring spans.
+    fn same(a: &[Node], b: &[Node]) -> bool {
+        a.len() == b.len()
+            && a.iter().zip(b).all(|(a, b)| {
+                a.kind == b.kind && a.props == b.props && same(&a.children, &b.children)
+            })
+    }
+
+    #[test]
+    fn test_emit() {
+        let emitted = |source| emit(&parse(source).unwrap());
+        assert_eq!(emitted(r"x_i^2+\alpha"), r"x_i^2 + \alpha");
+        assert_eq!(emitted(r"\sin\theta"), r"\sin\theta");
+        assert_eq!(emitted(r"\sin x"), r"\sin x");
+        assert_eq!(emitted(r"\sum_{k=0}^n k"), r"\sum_{k = 0}^n k");
+        assert_eq!(emitted(r"\mathbb{R}^n"), r"\mathbb{R}^n");
+        assert_eq!(emitted(r"\binom{n}{k}"), r"\binom{n}{k}");
+        assert_eq!(emitted(r"\left(x\right."), r"\left( x \right.");
+        assert_eq!(emitted(r"f'(x)"), r"f'(x)");
+        assert_eq!(
+            emitted(r"\begin{pmatrix}1&0\\0&1\end{pmatrix}"),
+            r"\begin{pmatrix} 1 & 0 \\ 0 & 1 \end{pmatrix}"
+        );
+    }
+
+    #[test]
+    fn test_emit_round_trips() {
+        let sources = [
+            r"2x - 3.5y \leq \alpha\Gamma \ne \not\subset",
+            r"\frac{a+1}{2} \sqrt[3]{x} \sqrt{x^2} {a \atop b} \binom{n}{k}",
+            r"x_i^2 e^{-x} f''(x) g'^2 {}^{14}C {x^2}^3",
+            r"\sum_{i=1}^n \int_0^1 \int\limits_0^1 \sum\nolimits_i \lim_{x\to 0} \liminf_n",
+            r"\sin^2\theta \log x \operatorname{Tr} A \operatorname*{arg\,max}_x a \bmod b",
+            r"\left\langle x \middle| y \right\rangle \left\{ x \right. \left\| v \right\|",
+            r"\begin{pmatrix} 1 & 0 \\ 0 & 1 \end{pmatrix} \begin{vmatrix} a \end{vmatrix}",
+            r"\begin{aligned} a &= b \\ &= c \end{aligned} \begin{gathered} a \\ b \end{gathered}",
+            r"\begin{cases} 1 & x > 0 \\ 0 & \text{otherwise} \end{cases}",
+            r"\begin{array}{lr} a & b \end{array} \begin{matrix} a \end{matrix}",
+            r"\hat{x} \widetilde{AB} \vec v \overline{z} \overrightarrow{AB}",
+            r"\mathbb{R} \mathrm{d}x \mathbf{v} \mathcal{F} \boldsymbol\alpha \mathrm{max}",
+            r"\text{if } \textbf{bold} \text{a \{b\}}",
+            r"\overbrace{a+b}^{n} \underbrace{c}_{k} \overset{!}{=} \underset{x}{\to}",
+            r"\underline{x} \cancel{y} \boxed{z} \xrightarrow[a]{b}",
+            r"a\,b\:c\;d\!e\ f\quad g\qquad h \hspace{3pt} i",
+            r"\{ x \} \% \& \# \backslash \infty \partial \nabla",
+        ];
+        for source in sources {
+            let nodes = parse(source).unwrap();
+            let tex = emit(&nodes);
+            let reparsed = parse(&tex).unwrap_or_else(|e| panic!("{source} → {tex}: {e}"));
+            assert!(
+                same(&nodes, &reparsed),
+                "{source} → {tex}\n{}\n{}",
+                show(&nodes),
+                show(&reparsed)
+            );
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |source| parse(source).unwrap_err();
+        assert_eq!(error(r"x + \foo").message, r"unknown command \foo");
+        assert_eq!(error(r"x + \foo").position, 4);
+        assert_eq!(error("x^").message, "missing argument");
+        assert_eq!(error("x^1^2").message, "double superscript");
+        assert_eq!(error("{x").message, "expected '}'");
+        assert_eq!(error("x}").message, "unmatched '}'");
+        assert_eq!(error(r"\left( x").message, r"missing \right");
+        assert_eq!(error("a & b").message, "'&' outside an environment");
+        assert_eq!(
+            error(r"\begin{matrix} a \end{cases}").message,
+            r"\begin{matrix} ended by \end{cases}"
+        );
+        assert_eq!(error(r"\begin{foo}").message, "unknown environment foo");
+        assert_eq!(error(r"a \\ b").message, r"unexpected '\\'");
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        // Unoptimized builds use several times the stack per level that
+        // release builds do, more than a test thread has.
+        let nested = |open: &str, close: &str, n| open.repeat(n) + "x" + &close.repeat(n);
+        let test = move || {
+            for source in [
+                nested("{", "}", 5000),
+                nested(r"\frac{", "}{2}", 5000),
+                nested(r"\sqrt ", "", 5000),
+                nested(r"\left(", r"\right)", 5000),
+                nested("x^{", "}", 5000),
+            ] {
+                assert_eq!(
+                    parse(&source).unwrap_err().message,
+                    "math nested too deeply"
+                );
+            }
+            assert!(parse(&nested("{", "}", 100)).is_ok());
+            assert!(parse(&nested(r"\frac{", "}{2}", 100)).is_ok());
+        };
+        std::thread::Builder::new()
+            .stack_size(64 << 20)
+            .spawn(test)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+}
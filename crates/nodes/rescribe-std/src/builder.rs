@@ -23,7 +23,7 @@
 //! );
 //! ```
 
-use crate::{Document, Node, node, prop};
+use crate::{Citation, Document, Node, node, prop};
 
 /// Build a document with type-safe structure.
 pub fn doc<F>(f: F) -> Document
@@ -403,6 +403,20 @@ impl InlineBuilder {
             .push(Node::new(node::FOOTNOTE_REF).prop(prop::LABEL, label.into()));
         self
     }
+
+    /// Add a citation; `f` builds the citation as written.
+    pub fn cite<F>(mut self, citations: impl IntoIterator<Item = Citation>, f: F) -> Self
+    where
+        F: FnOnce(InlineBuilder) -> InlineBuilder,
+    {
+        let inner = f(InlineBuilder::new());
+        self.children.push(
+            Node::new(node::CITE)
+                .prop(prop::CITATIONS, Citation::list(citations))
+                .children(inner.children),
+        );
+        self
+    }
 }
 
 /// Builder for lists.
@@ -0,0 +1,393 @@
+//! Citations held by `cite` nodes.
+//!
+//! A `cite` node lists its citations in the `citations` property and keeps
+//! the citation as written in the source as its children, which writers
+//! that do not know citations can emit instead:
+//!
+//! ```
+//! use rescribe_std::{Citation, Node, NodeExt, node, prop};
+//!
+//! let cite = Node::new(node::CITE)
+//!     .prop(prop::CITATIONS, Citation::list([Citation::new("smith2020").with_suffix(", p. 4")]))
+//!     .child(Node::new(node::TEXT).prop(prop::CONTENT, "[@smith2020, p. 4]"));
+//! let citation = &cite.as_cite().unwrap().citations()[0];
+//! assert_eq!(citation.label.as_deref(), Some("page"));
+//! assert_eq!(citation.locator.as_deref(), Some("4"));
+//! ```
+
+use crate::PropValue;
+use std::collections::HashMap;
+
+/// How a citation is rendered in the text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CitationMode {
+    /// A parenthetical or footnote citation: "(Smith 2020)".
+    #[default]
+    Normal,
+    /// The author is part of the sentence: "Smith (2020)".
+    AuthorInText,
+    /// The author is already named in the text: "(2020)".
+    SuppressAuthor,
+}
+
+impl CitationMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::AuthorInText => "author-in-text",
+            Self::SuppressAuthor => "suppress-author",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "normal" => Some(Self::Normal),
+            "author-in-text" => Some(Self::AuthorInText),
+            "suppress-author" => Some(Self::SuppressAuthor),
+            _ => None,
+        }
+    }
+}
+
+/// A citation of one bibliography entry.
+///
+/// As a property value a citation is a map with the keys `id`, `prefix`,
+/// `suffix`, `locator`, `label` and `mode`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Citation {
+    /// Key of the cited entry.
+    pub id: String,
+    /// Text before the citation, such as "see".
+    pub prefix: Option<String>,
+    /// Text after the citation and its locator, as written.
+    pub suffix: Option<String>,
+    /// The cited part of the entry, such as "33-35".
+    pub locator: Option<String>,
+    /// CSL locator type, such as `page` or `chapter`.
+    pub label: Option<String>,
+    pub mode: CitationMode,
+}
+
+/// Locator terms and their CSL locator types, longest first where one term
+/// starts another.
+const LOCATOR_TERMS: &[(&str, &str)] = &[
+    ("pages", "page"),
+    ("page", "page"),
+    ("pp.", "page"),
+    ("p.", "page"),
+    ("chapters", "chapter"),
+    ("chapter", "chapter"),
+    ("chaps.", "chapter"),
+    ("chap.", "chapter"),
+    ("ch.", "chapter"),
+    ("sections", "section"),
+    ("section", "section"),
+    ("secs.", "section"),
+    ("sec.", "section"),
+    ("§§", "section"),
+    ("§", "section"),
+    ("volumes", "volume"),
+    ("volume", "volume"),
+    ("vols.", "volume"),
+    ("vol.", "volume"),
+    ("figures", "figure"),
+    ("figure", "figure"),
+    ("figs.", "figure"),
+    ("fig.", "figure"),
+    ("numbers", "issue"),
+    ("number", "issue"),
+    ("nos.", "issue"),
+    ("no.", "issue"),
+    ("paragraphs", "paragraph"),
+    ("paragraph", "paragraph"),
+    ("paras.", "paragraph"),
+    ("para.", "paragraph"),
+    ("¶¶", "paragraph"),
+    ("¶", "paragraph"),
+    ("lines", "line"),
+    ("line", "line"),
+    ("ll.", "line"),
+    ("l.", "line"),
+    ("notes", "note"),
+    ("note", "note"),
+    ("nn.", "note"),
+    ("n.", "note"),
+    ("columns", "column"),
+    ("column", "column"),
+    ("cols.", "column"),
+    ("col.", "column"),
+    ("books", "book"),
+    ("book", "book"),
+    ("bks.", "book"),
+    ("bk.", "book"),
+    ("parts", "part"),
+    ("part", "part"),
+    ("pts.", "part"),
+    ("pt.", "part"),
+    ("verses", "verse"),
+    ("verse", "verse"),
+    ("vv.", "verse"),
+    ("v.", "verse"),
+    ("opp.", "opus"),
+    ("op.", "opus"),
+    ("s.vv.", "sub-verbo"),
+    ("s.v.", "sub-verbo"),
+    ("fols.", "folio"),
+    ("fol.", "folio"),
+];
+
+/// Abbreviations written for locator types: singular and plural.
+const LOCATOR_ABBREVIATIONS: &[(&str, &str, &str)] = &[
+    ("page", "p.", "pp."),
+    ("chapter", "chap.", "chaps."),
+    ("section", "sec.", "secs."),
+    ("volume", "vol.", "vols."),
+    ("figure", "fig.", "figs."),
+    ("issue", "no.", "nos."),
+    ("paragraph", "para.", "paras."),
+    ("line", "l.", "ll."),
+    ("note", "n.", "nn."),
+    ("column", "col.", "cols."),
+    ("book", "bk.", "bks."),
+    ("part", "pt.", "pts."),
+    ("verse", "v.", "vv."),
+    ("opus", "op.", "opp."),
+    ("sub-verbo", "s.v.", "s.vv."),
+    ("folio", "fol.", "fols."),
+];
+
+impl Citation {
+    /// A normal citation of the entry `id`.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Set the citation mode.
+    pub fn with_mode(mut self, mode: CitationMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Set the prefix; empty text clears it.
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = (!prefix.trim().is_empty()).then(|| prefix.trim().to_string());
+        self
+    }
+
+    /// Set the text after the citation, splitting off a leading locator.
+    ///
+    /// A locator is a locator term such as "p." or "chap." followed by
+    /// the cited part, or a bare number, which is taken as a page:
+    /// `, pp. 33-35, 38 and passim` has the page locator `33-35, 38` and
+    /// the suffix ` and passim`. The locator ends at a comma that is not
+    /// followed by a number; braces delimit one explicitly, as in
+    /// `p. {iv, vi}`.
+    pub fn with_suffix(mut self, text: &str) -> Self {
+        let rest = text.trim_start_matches([',', ' ']);
+        let term = LOCATOR_TERMS
+            .iter()
+            .find(|(term, _)| {
+                rest.strip_prefix(term)
+                    .is_some_and(|after| after.starts_with([' ', '{']) || after.starts_with(digit))
+            })
+            .map(|(term, label)| (&rest[term.len()..], *label));
+        let (after, label) = match term {
+            Some((after, label)) => (after.trim_start(), label),
+            None if rest.starts_with(digit) => (rest, "page"),
+            None => {
+                self.suffix = (!text.is_empty()).then(|| text.to_string());
+                return self;
+            }
+        };
+
+        let (locator, suffix) = match after.strip_prefix('{') {
+            Some(braced) => braced.split_once('}').unwrap_or((braced, "")),
+            None => split_locator(after),
+        };
+        if locator.trim().is_empty() {
+            self.suffix = Some(text.to_string());
+            return self;
+        }
+        self.locator = Some(locator.trim().to_string());
+        self.label = Some(label.to_string());
+        self.suffix = (!suffix.is_empty()).then(|| suffix.to_string());
+        self
+    }
+
+    /// The locator with its abbreviated term, such as "pp. 33-35".
+    pub fn locator_text(&self) -> Option<String> {
+        let locator = self.locator.as_deref()?;
+        let label = self.label.as_deref().unwrap_or("page");
+        let plural = locator.contains(['-', '–', ',', '&']);
+        let term = LOCATOR_ABBREVIATIONS
+            .iter()
+            .find(|(l, _, _)| *l == label)
+            .map_or(
+                label,
+                |(_, singular, plural_term)| {
+                    if plural { plural_term } else { singular }
+                },
+            );
+        // Braces keep a locator with commas from being split on reading.
+        if locator.contains(", ") {
+            Some(format!("{term} {{{locator}}}"))
+        } else {
+            Some(format!("{term} {locator}"))
+        }
+    }
+
+    /// The locator and suffix as one text, such as ", p. 4, emphasis
+    /// added"; [`Citation::with_suffix`] reads it back.
+    pub fn full_suffix(&self) -> Option<String> {
+        match (self.locator_text(), &self.suffix) {
+            (Some(locator), Some(suffix)) => Some(format!(", {locator}{suffix}")),
+            (Some(locator), None) => Some(format!(", {locator}")),
+            (None, suffix) => suffix.clone(),
+        }
+    }
+
+    /// Read a citation from a property value. A string is taken as an id.
+    pub fn from_prop(value: &PropValue) -> Option<Self> {
+        match value {
+            PropValue::String(id) => Some(Self::new(id.clone())),
+            PropValue::Map(map) => {
+                let part = |key: &str| match map.get(key) {
+                    Some(PropValue::String(s)) if !s.is_empty() => Some(s.clone()),
+                    _ => None,
+                };
+                Some(Self {
+                    id: part("id")?,
+                    prefix: part("prefix"),
+                    suffix: part("suffix"),
+                    locator: part("locator"),
+                    label: part("label"),
+                    mode: part("mode")
+                        .and_then(|m| CitationMode::parse(&m))
+                        .unwrap_or_default(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Read the citations of a `citations` property.
+    pub fn from_list(value: &PropValue) -> Vec<Self> {
+        match value {
+            PropValue::List(items) => items.iter().filter_map(Self::from_prop).collect(),
+            other => Self::from_prop(other).into_iter().collect(),
+        }
+    }
+
+    /// The `citations` property value holding `citations`.
+    pub fn list(citations: impl IntoIterator<Item = Citation>) -> PropValue {
+        PropValue::List(citations.into_iter().map(PropValue::from).collect())
+    }
+}
+
+impl From<Citation> for PropValue {
+    fn from(citation: Citation) -> Self {
+        let mut map = HashMap::new();
+        map.insert("id".to_string(), PropValue::String(citation.id));
+        let parts = [
+            ("prefix", citation.prefix),
+            ("suffix", citation.suffix),
+            ("locator", citation.locator),
+            ("label", citation.label),
+        ];
+        for (key, value) in parts {
+            if let Some(value) = value {
+                map.insert(key.to_string(), PropValue::String(value));
+            }
+        }
+        if citation.mode != CitationMode::Normal {
+            map.insert("mode".to_string(), citation.mode.as_str().into());
+        }
+        PropValue::Map(map)
+    }
+}
+
+fn digit(c: char) -> bool {
+    c.is_ascii_digit()
+}
+
+/// Split text after a locator term at the first comma not followed by a
+/// number.
+fn split_locator(text: &str) -> (&str, &str) {
+    let mut search = 0;
+    while let Some(i) = text[search..].find(',').map(|i| i + search) {
+        let after = text[i + 1..].trim_start();
+        if !after.starts_with(digit) {
+            return (&text[..i], &text[i..]);
+        }
+        search = i + 1;
+    }
+    (text, "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(text: &str) -> (Option<String>, Option<String>, Option<String>) {
+        let citation = Citation::new("key").with_suffix(text);
+        (citation.label, citation.locator, citation.suffix)
+    }
+
+    fn some(s: &str) -> Option<String> {
+        Some(s.to_string())
+    }
+
+    #[test]
+    fn test_locators() {
+        assert_eq!(parts(", p. 4"), (some("page"), some("4"), None));
+        assert_eq!(
+            parts(", pp. 33-35, 38 and passim"),
+            (some("page"), some("33-35, 38 and passim"), None)
+        );
+        assert_eq!(
+            parts(", chap. 2, emphasis added"),
+            (some("chapter"), some("2"), some(", emphasis added"))
+        );
+        assert_eq!(parts("12"), (some("page"), some("12"), None));
+        assert_eq!(
+            parts(", p. {iv, vi}, passim"),
+            (some("page"), some("iv, vi"), some(", passim"))
+        );
+        assert_eq!(parts(" and passim"), (None, None, some(" and passim")));
+        assert_eq!(
+            parts(", pointing out"),
+            (None, None, some(", pointing out"))
+        );
+    }
+
+    #[test]
+    fn test_full_suffix() {
+        for text in [
+            ", p. 4",
+            ", pp. 3-5, emphasis added",
+            ", secs. {1, a}",
+            " passim",
+        ] {
+            let citation = Citation::new("key").with_suffix(text);
+            assert_eq!(citation.full_suffix().as_deref(), Some(text));
+        }
+        let citation = Citation::new("key").with_suffix("12");
+        assert_eq!(citation.full_suffix().as_deref(), Some(", p. 12"));
+    }
+
+    #[test]
+    fn test_prop_round_trip() {
+        let citation = Citation::new("smith2020")
+            .with_prefix("see ")
+            .with_suffix(", p. 4")
+            .with_mode(CitationMode::SuppressAuthor);
+        let value = Citation::list([citation.clone(), Citation::new("doe")]);
+        assert_eq!(
+            Citation::from_list(&value),
+            vec![citation, Citation::new("doe")]
+        );
+    }
+}
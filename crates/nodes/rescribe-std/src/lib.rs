@@ -8,6 +8,18 @@ pub use rescribe_core::*;
 /// Type-safe document builder API.
 pub mod builder;
 
+mod cite;
+pub use cite::{Citation, CitationMode};
+
+mod schema;
+pub use schema::{category, schema};
+
+mod view;
+pub use view::{
+    Alignment, Cite, CodeBlock, Heading, Image, Link, List, NodeExt, Section, Table, TableCell,
+    TableRow, TableSection,
+};
+
 /// Standard node kind constants.
 pub mod node {
     // Block-level nodes
@@ -93,7 +105,7 @@ pub mod node {
     pub const SMALL_CAPS: &str = "small_caps";
     /// Quoted text (use `quote_type` property: single/double).
     pub const QUOTED: &str = "quoted";
-    /// A citation.
+    /// A citation (use `citations` property; children hold the citation as written).
     pub const CITE: &str = "cite";
 }
 
@@ -132,14 +144,28 @@ pub mod prop {
     pub const FORMAT: &str = "format";
     /// Quote type (single, double).
     pub const QUOTE_TYPE: &str = "quote_type";
+    /// Opening quotation mark of a quoted node, when not the writer's default.
+    pub const QUOTE_OPEN: &str = "quote_open";
+    /// Closing quotation mark of a quoted node, when not the writer's default.
+    pub const QUOTE_CLOSE: &str = "quote_close";
     /// Footnote/reference label.
     pub const LABEL: &str = "label";
     /// Column alignment (left, center, right).
     pub const ALIGN: &str = "align";
+    /// Comma-separated alignment of each table column (left, center, right, none).
+    pub const COLUMN_ALIGNMENTS: &str = "column_alignments";
     /// Column span for table cells.
     pub const COLSPAN: &str = "colspan";
     /// Row span for table cells.
     pub const ROWSPAN: &str = "rowspan";
+    /// Hierarchical section number of a heading (e.g. "1.2.3").
+    pub const NUMBER: &str = "number";
+    /// Deepest heading level listed in a table of contents.
+    pub const DEPTH: &str = "depth";
+    /// Citations of a cite node (list of maps, see [`crate::Citation`]).
+    pub const CITATIONS: &str = "citations";
+    /// Number of the footnote a cite node appears in.
+    pub const NOTE_NUM: &str = "note_num";
 
     // Style properties (presentational)
     /// Font family.
@@ -192,6 +218,19 @@ pub mod prop {
     pub const MD_BREAK_CHAR: &str = "md:break_char";
 }
 
+/// Standard class names, used in the `classes` property.
+pub mod class {
+    /// A `div` wrapping a heading and the content it introduces.
+    pub const SECTION: &str = "section";
+    /// A heading excluded from section numbering.
+    pub const UNNUMBERED: &str = "unnumbered";
+    /// A heading left out of the table of contents.
+    pub const UNLISTED: &str = "unlisted";
+    /// A `div` holding a table of contents; when empty, a marker for where
+    /// one should be generated.
+    pub const TOC: &str = "toc";
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,241 @@
+//! Schema for the standard node kinds.
+
+use crate::{KindSchema, PropType, Schema, node, prop};
+
+/// Node categories used by the standard schema.
+pub mod category {
+    /// Block-level content (paragraphs, lists, tables, ...).
+    pub const BLOCK: &str = "block";
+    /// Inline content (text, emphasis, links, ...).
+    pub const INLINE: &str = "inline";
+}
+
+use category::{BLOCK, INLINE};
+
+/// Schema for every standard node kind.
+///
+/// Kinds from other vocabularies (such as `rescribe-math`) are accepted
+/// anywhere unless their schema is merged in.
+pub fn schema() -> Schema {
+    let block = || KindSchema::new().category(BLOCK);
+    let inline = || KindSchema::new().category(INLINE);
+    let flow = [BLOCK, INLINE];
+
+    Schema::new()
+        .root(node::DOCUMENT)
+        .kind(node::DOCUMENT, KindSchema::new().children(&[BLOCK]))
+        // Blocks
+        .kind(node::PARAGRAPH, block().children(&[INLINE]))
+        .kind(
+            node::HEADING,
+            block()
+                .children(&[INLINE])
+                .required(prop::LEVEL, PropType::Int),
+        )
+        .kind(
+            node::CODE_BLOCK,
+            block()
+                .leaf()
+                .required(prop::CONTENT, PropType::String)
+                .optional(prop::LANGUAGE, PropType::String),
+        )
+        .kind(node::BLOCKQUOTE, block().children(&flow))
+        .kind(
+            node::LIST,
+            block()
+                .children(&[node::LIST_ITEM])
+                .optional(prop::ORDERED, PropType::Bool)
+                .optional(prop::START, PropType::Int)
+                .optional(prop::TIGHT, PropType::Bool)
+                .optional(prop::LIST_STYLE, PropType::String),
+        )
+        .kind(
+            node::LIST_ITEM,
+            KindSchema::new()
+                .children(&flow)
+                .optional(prop::CHECKED, PropType::Bool),
+        )
+        .kind(
+            node::TABLE,
+            block()
+                .children(&[
+                    node::CAPTION,
+                    node::TABLE_HEAD,
+                    node::TABLE_BODY,
+                    node::TABLE_FOOT,
+                    node::TABLE_ROW,
+                ])
+                .optional(prop::COLUMN_ALIGNMENTS, PropType::String),
+        )
+        .kind(
+            node::TABLE_HEAD,
+            KindSchema::new().children(&[node::TABLE_ROW]),
+        )
+        .kind(
+            node::TABLE_BODY,
+            KindSchema::new().children(&[node::TABLE_ROW]),
+        )
+        .kind(
+            node::TABLE_FOOT,
+            KindSchema::new().children(&[node::TABLE_ROW]),
+        )
+        .kind(
+            node::TABLE_ROW,
+            KindSchema::new().children(&[node::TABLE_CELL, node::TABLE_HEADER]),
+        )
+        .kind(node::TABLE_CELL, table_cell())
+        .kind(node::TABLE_HEADER, table_cell())
+        .kind(
+            node::FIGURE,
+            block().children(&[BLOCK, INLINE, node::CAPTION]),
+        )
+        .kind(node::CAPTION, KindSchema::new().children(&flow))
+        .kind(node::HORIZONTAL_RULE, block().leaf())
+        .kind(node::DIV, block().children(&flow))
+        .kind(
+            node::RAW_BLOCK,
+            block()
+                .leaf()
+                .required(prop::FORMAT, PropType::String)
+                .required(prop::CONTENT, PropType::String),
+        )
+        .kind(
+            node::DEFINITION_LIST,
+            block().children(&[node::DEFINITION_TERM, node::DEFINITION_DESC]),
+        )
+        .kind(node::DEFINITION_TERM, KindSchema::new().children(&[INLINE]))
+        .kind(node::DEFINITION_DESC, KindSchema::new().children(&flow))
+        .kind(
+            node::FOOTNOTE_DEF,
+            block()
+                .children(&flow)
+                .optional(prop::LABEL, PropType::String),
+        )
+        // Inlines
+        .kind(
+            node::TEXT,
+            inline().leaf().required(prop::CONTENT, PropType::String),
+        )
+        .kind(node::EMPHASIS, inline().children(&[INLINE]))
+        .kind(node::STRONG, inline().children(&[INLINE]))
+        .kind(node::STRIKEOUT, inline().children(&[INLINE]))
+        .kind(node::UNDERLINE, inline().children(&[INLINE]))
+        .kind(node::SUBSCRIPT, inline().children(&[INLINE]))
+        .kind(node::SUPERSCRIPT, inline().children(&[INLINE]))
+        .kind(node::SMALL_CAPS, inline().children(&[INLINE]))
+        .kind(node::SPAN, inline().children(&[INLINE]))
+        .kind(
+            node::CODE,
+            inline().leaf().required(prop::CONTENT, PropType::String),
+        )
+        .kind(
+            node::LINK,
+            inline()
+                .children(&[INLINE])
+                .required(prop::URL, PropType::String)
+                .optional(prop::TITLE, PropType::String),
+        )
+        .kind(
+            node::IMAGE,
+            inline()
+                .leaf()
+                .optional(prop::URL, PropType::String)
+                .optional(prop::ALT, PropType::String)
+                .optional(prop::TITLE, PropType::String)
+                .optional(prop::RESOURCE_ID, PropType::String),
+        )
+        .kind(node::LINE_BREAK, inline().leaf())
+        .kind(node::SOFT_BREAK, inline().leaf())
+        .kind(
+            node::RAW_INLINE,
+            inline()
+                .leaf()
+                .required(prop::FORMAT, PropType::String)
+                .required(prop::CONTENT, PropType::String),
+        )
+        .kind(
+            node::FOOTNOTE_REF,
+            inline().leaf().required(prop::LABEL, PropType::String),
+        )
+        .kind(
+            node::QUOTED,
+            inline()
+                .children(&[INLINE])
+                .optional(prop::QUOTE_TYPE, PropType::String)
+                .optional(prop::QUOTE_OPEN, PropType::String)
+                .optional(prop::QUOTE_CLOSE, PropType::String),
+        )
+        .kind(
+            node::CITE,
+            inline()
+                .children(&[INLINE])
+                .optional(prop::CITATIONS, PropType::List)
+                .optional(prop::NOTE_NUM, PropType::Int),
+        )
+}
+
+fn table_cell() -> KindSchema {
+    KindSchema::new()
+        .children(&[BLOCK, INLINE])
+        .optional(prop::COLSPAN, PropType::Int)
+        .optional(prop::ROWSPAN, PropType::Int)
+        .optional(prop::ALIGN, PropType::String)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::doc;
+    use crate::{Citation, Document, Node};
+
+    fn messages(doc: &Document) -> Vec<String> {
+        schema()
+            .validate(doc)
+            .iter()
+            .map(|w| format!("{} {}", w.path.as_ref().unwrap(), w.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_builder_output_is_valid() {
+        let document = doc(|d| {
+            d.heading(1, |i| i.text("Title"))
+                .para(|i| {
+                    i.text("Some ")
+                        .strong(|i| i.text("bold"))
+                        .link("https://example.com", |i| i.text("link"))
+                        .footnote_ref("1")
+                        .cite([Citation::new("smith2020")], |i| i.text("[@smith2020]"))
+                })
+                .bullet_list(|l| l.item(|i| i.text("one")).item(|i| i.text("two")))
+                .table(|t| {
+                    t.header(|r| r.cell(|i| i.text("A")))
+                        .row(|r| r.cell(|i| i.text("1")))
+                })
+                .code_block_lang("fn main() {}", "rust")
+                .blockquote(|q| q.para(|i| i.text("quote")))
+        });
+        assert_eq!(messages(&document), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_malformed_trees() {
+        let document = Document::new().with_content(
+            Node::new(node::DOCUMENT)
+                .child(Node::new(node::TABLE_ROW))
+                .child(Node::new(node::HEADING).child(Node::new(node::TEXT)))
+                .child(Node::new(node::LIST).child(Node::new(node::PARAGRAPH)))
+                .child(Node::new(node::PARAGRAPH).child(Node::new(node::PARAGRAPH))),
+        );
+        assert_eq!(
+            messages(&document),
+            [
+                "/0 table_row is not allowed in document",
+                "/1 heading is missing required property 'level'",
+                "/1/0 text is missing required property 'content'",
+                "/2/0 paragraph is not allowed in list",
+                "/3/0 paragraph is not allowed in paragraph",
+            ]
+        );
+    }
+}
@@ -0,0 +1,594 @@
+//! Typed views over standard nodes.
+//!
+//! Views wrap a [`Node`] of a known kind and read its properties with the
+//! standard defaults, so readers of the tree agree on what a missing or
+//! out-of-range property means:
+//!
+//! ```
+//! use rescribe_std::builder::doc;
+//! use rescribe_std::NodeExt;
+//!
+//! let document = doc(|d| d.heading(2, |i| i.text("Intro")));
+//! let heading = document.content.children[0].as_heading().unwrap();
+//! assert_eq!(heading.level(), 2);
+//! ```
+
+use crate::{Citation, Node, class, node, prop};
+
+/// Access standard nodes through typed views.
+///
+/// Each method returns `None` if the node is of a different kind.
+pub trait NodeExt {
+    fn as_heading(&self) -> Option<Heading<'_>>;
+    fn as_link(&self) -> Option<Link<'_>>;
+    fn as_image(&self) -> Option<Image<'_>>;
+    fn as_code_block(&self) -> Option<CodeBlock<'_>>;
+    fn as_list(&self) -> Option<List<'_>>;
+    fn as_table(&self) -> Option<Table<'_>>;
+    fn as_section(&self) -> Option<Section<'_>>;
+    fn as_cite(&self) -> Option<Cite<'_>>;
+
+    /// Whether the space-separated `classes` property contains `class`.
+    fn has_class(&self, class: &str) -> bool;
+}
+
+impl NodeExt for Node {
+    fn as_heading(&self) -> Option<Heading<'_>> {
+        (self.kind.as_str() == node::HEADING).then_some(Heading(self))
+    }
+
+    fn as_link(&self) -> Option<Link<'_>> {
+        (self.kind.as_str() == node::LINK).then_some(Link(self))
+    }
+
+    fn as_image(&self) -> Option<Image<'_>> {
+        (self.kind.as_str() == node::IMAGE).then_some(Image(self))
+    }
+
+    fn as_code_block(&self) -> Option<CodeBlock<'_>> {
+        (self.kind.as_str() == node::CODE_BLOCK).then_some(CodeBlock(self))
+    }
+
+    fn as_list(&self) -> Option<List<'_>> {
+        (self.kind.as_str() == node::LIST).then_some(List(self))
+    }
+
+    fn as_table(&self) -> Option<Table<'_>> {
+        (self.kind.as_str() == node::TABLE).then_some(Table(self))
+    }
+
+    fn as_section(&self) -> Option<Section<'_>> {
+        (self.kind.as_str() == node::DIV && self.has_class(class::SECTION)).then_some(Section(self))
+    }
+
+    fn as_cite(&self) -> Option<Cite<'_>> {
+        (self.kind.as_str() == node::CITE).then_some(Cite(self))
+    }
+
+    fn has_class(&self, class: &str) -> bool {
+        self.props
+            .get_str(prop::CLASSES)
+            .is_some_and(|classes| classes.split_whitespace().any(|c| c == class))
+    }
+}
+
+/// Non-empty string property.
+fn non_empty<'a>(node: &'a Node, key: &str) -> Option<&'a str> {
+    node.props.get_str(key).filter(|s| !s.is_empty())
+}
+
+/// A `heading` node.
+#[derive(Debug, Clone, Copy)]
+pub struct Heading<'a>(&'a Node);
+
+impl<'a> Heading<'a> {
+    /// The underlying node.
+    pub fn node(&self) -> &'a Node {
+        self.0
+    }
+
+    /// Heading level, clamped to 1-6 (default 1).
+    pub fn level(&self) -> u8 {
+        self.0.props.get_int(prop::LEVEL).unwrap_or(1).clamp(1, 6) as u8
+    }
+
+    /// Anchor identifier, if any.
+    pub fn id(&self) -> Option<&'a str> {
+        non_empty(self.0, prop::ID)
+    }
+
+    /// Hierarchical section number such as `1.2`, if any.
+    pub fn number(&self) -> Option<&'a str> {
+        non_empty(self.0, prop::NUMBER)
+    }
+
+    /// Inline content.
+    pub fn content(&self) -> &'a [Node] {
+        &self.0.children
+    }
+}
+
+/// A section: a `div` with the `section` class.
+///
+/// Sections hold a heading followed by the content up to the next heading
+/// of the same or a higher level, including nested sections.
+#[derive(Debug, Clone, Copy)]
+pub struct Section<'a>(&'a Node);
+
+impl<'a> Section<'a> {
+    /// The underlying node.
+    pub fn node(&self) -> &'a Node {
+        self.0
+    }
+
+    /// The section heading, if the section starts with one.
+    pub fn heading(&self) -> Option<Heading<'a>> {
+        self.0.children.first().and_then(|c| c.as_heading())
+    }
+
+    /// Section level: the `level` property, else the heading level (default 1).
+    pub fn level(&self) -> u8 {
+        match self.0.props.get_int(prop::LEVEL) {
+            Some(level) => level.clamp(1, 6) as u8,
+            None => self.heading().map_or(1, |h| h.level()),
+        }
+    }
+
+    /// Identifier of the section, else of its heading.
+    pub fn id(&self) -> Option<&'a str> {
+        non_empty(self.0, prop::ID).or_else(|| self.heading()?.id())
+    }
+
+    /// Content after the heading.
+    pub fn body(&self) -> &'a [Node] {
+        let skip = usize::from(self.heading().is_some());
+        &self.0.children[skip..]
+    }
+}
+
+/// A `link` node.
+#[derive(Debug, Clone, Copy)]
+pub struct Link<'a>(&'a Node);
+
+impl<'a> Link<'a> {
+    /// The underlying node.
+    pub fn node(&self) -> &'a Node {
+        self.0
+    }
+
+    /// Link target (empty if missing).
+    pub fn url(&self) -> &'a str {
+        self.0.props.get_str(prop::URL).unwrap_or("")
+    }
+
+    /// Link title, if any.
+    pub fn title(&self) -> Option<&'a str> {
+        non_empty(self.0, prop::TITLE)
+    }
+
+    /// Inline content.
+    pub fn content(&self) -> &'a [Node] {
+        &self.0.children
+    }
+}
+
+/// A `cite` node.
+#[derive(Debug, Clone, Copy)]
+pub struct Cite<'a>(&'a Node);
+
+impl<'a> Cite<'a> {
+    /// The underlying node.
+    pub fn node(&self) -> &'a Node {
+        self.0
+    }
+
+    /// Citations, in order (empty if missing).
+    pub fn citations(&self) -> Vec<Citation> {
+        self.0
+            .props
+            .get(prop::CITATIONS)
+            .map(Citation::from_list)
+            .unwrap_or_default()
+    }
+
+    /// Number of the footnote the citation appears in, if known.
+    pub fn note_num(&self) -> Option<i64> {
+        self.0.props.get_int(prop::NOTE_NUM)
+    }
+
+    /// The citation as written in the source.
+    pub fn content(&self) -> &'a [Node] {
+        &self.0.children
+    }
+}
+
+/// An `image` node.
+#[derive(Debug, Clone, Copy)]
+pub struct Image<'a>(&'a Node);
+
+impl<'a> Image<'a> {
+    /// The underlying node.
+    pub fn node(&self) -> &'a Node {
+        self.0
+    }
+
+    /// Image source (empty if missing).
+    pub fn url(&self) -> &'a str {
+        self.0.props.get_str(prop::URL).unwrap_or("")
+    }
+
+    /// Alternative text (empty if missing).
+    pub fn alt(&self) -> &'a str {
+        self.0.props.get_str(prop::ALT).unwrap_or("")
+    }
+
+    /// Image title, if any.
+    pub fn title(&self) -> Option<&'a str> {
+        non_empty(self.0, prop::TITLE)
+    }
+
+    /// Embedded resource holding the image data, if any.
+    pub fn resource(&self) -> Option<&'a str> {
+        non_empty(self.0, prop::RESOURCE_ID)
+    }
+}
+
+/// A `code_block` node.
+#[derive(Debug, Clone, Copy)]
+pub struct CodeBlock<'a>(&'a Node);
+
+impl<'a> CodeBlock<'a> {
+    /// The underlying node.
+    pub fn node(&self) -> &'a Node {
+        self.0
+    }
+
+    /// Code text (empty if missing).
+    pub fn content(&self) -> &'a str {
+        self.0.props.get_str(prop::CONTENT).unwrap_or("")
+    }
+
+    /// Language of the code, if given.
+    pub fn language(&self) -> Option<&'a str> {
+        non_empty(self.0, prop::LANGUAGE)
+    }
+}
+
+/// A `list` node.
+#[derive(Debug, Clone, Copy)]
+pub struct List<'a>(&'a Node);
+
+impl<'a> List<'a> {
+    /// The underlying node.
+    pub fn node(&self) -> &'a Node {
+        self.0
+    }
+
+    /// Whether the list is numbered (default false).
+    pub fn ordered(&self) -> bool {
+        self.0.props.get_bool(prop::ORDERED).unwrap_or(false)
+    }
+
+    /// Number of the first item (default 1).
+    pub fn start(&self) -> i64 {
+        self.0.props.get_int(prop::START).unwrap_or(1)
+    }
+
+    /// Whether items are rendered without paragraph spacing (default true).
+    pub fn tight(&self) -> bool {
+        self.0.props.get_bool(prop::TIGHT).unwrap_or(true)
+    }
+
+    /// The list items.
+    pub fn items(&self) -> impl Iterator<Item = &'a Node> {
+        self.0
+            .children
+            .iter()
+            .filter(|c| c.kind.as_str() == node::LIST_ITEM)
+    }
+}
+
+/// Horizontal alignment of a table column or cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Alignment {
+    /// No explicit alignment.
+    #[default]
+    Default,
+    Left,
+    Center,
+    Right,
+}
+
+impl Alignment {
+    /// Parse an alignment value (`left`, `center`, `right`).
+    pub fn parse(value: &str) -> Self {
+        match value.trim() {
+            "left" => Alignment::Left,
+            "center" | "centre" => Alignment::Center,
+            "right" => Alignment::Right,
+            _ => Alignment::Default,
+        }
+    }
+
+    /// The property value for this alignment, if any.
+    pub fn as_str(&self) -> Option<&'static str> {
+        match self {
+            Alignment::Default => None,
+            Alignment::Left => Some("left"),
+            Alignment::Center => Some("center"),
+            Alignment::Right => Some("right"),
+        }
+    }
+}
+
+/// Section of a table a row belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableSection {
+    Head,
+    Body,
+    Foot,
+}
+
+/// A `table` node.
+///
+/// Rows may sit directly in the table or in `table_head`, `table_body` and
+/// `table_foot` sections; [`rows`](Self::rows) flattens them in order.
+#[derive(Debug, Clone, Copy)]
+pub struct Table<'a>(&'a Node);
+
+impl<'a> Table<'a> {
+    /// The underlying node.
+    pub fn node(&self) -> &'a Node {
+        self.0
+    }
+
+    /// The table caption, if any.
+    pub fn caption(&self) -> Option<&'a Node> {
+        self.0
+            .children
+            .iter()
+            .find(|c| c.kind.as_str() == node::CAPTION)
+    }
+
+    /// All rows, in document order.
+    ///
+    /// Rows outside a section are in the body, except that a leading row
+    /// made only of `table_header` cells is treated as the head.
+    pub fn rows(&self) -> Vec<TableRow<'a>> {
+        let mut rows = Vec::new();
+        for child in &self.0.children {
+            let section = match child.kind.as_str() {
+                node::TABLE_HEAD => TableSection::Head,
+                node::TABLE_BODY => TableSection::Body,
+                node::TABLE_FOOT => TableSection::Foot,
+                node::TABLE_ROW => {
+                    let section = if rows.is_empty() && is_header_row(child) {
+                        TableSection::Head
+                    } else {
+                        TableSection::Body
+                    };
+                    rows.push(TableRow {
+                        node: child,
+                        section,
+                    });
+                    continue;
+                }
+                _ => continue,
+            };
+            rows.extend(
+                child
+                    .children
+                    .iter()
+                    .filter(|r| r.kind.as_str() == node::TABLE_ROW)
+                    .map(|node| TableRow { node, section }),
+            );
+        }
+        rows
+    }
+
+    /// Number of columns, counting column spans.
+    pub fn columns(&self) -> usize {
+        self.rows()
+            .iter()
+            .map(|row| row.cells().map(|c| c.colspan()).sum())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Alignment of each column.
+    ///
+    /// Taken from the table's `column_alignments` property if present,
+    /// otherwise from the cells of the first row.
+    pub fn alignments(&self) -> Vec<Alignment> {
+        if let Some(alignments) = self.0.props.get_str(prop::COLUMN_ALIGNMENTS) {
+            return alignments.split(',').map(Alignment::parse).collect();
+        }
+        let rows = self.rows();
+        let Some(first) = rows.first() else {
+            return Vec::new();
+        };
+        let mut alignments = Vec::new();
+        for cell in first.cells() {
+            alignments.extend(std::iter::repeat_n(cell.align(), cell.colspan()));
+        }
+        alignments
+    }
+}
+
+fn is_header_row(row: &Node) -> bool {
+    !row.children.is_empty()
+        && row
+            .children
+            .iter()
+            .all(|c| c.kind.as_str() == node::TABLE_HEADER)
+}
+
+/// A row of a [`Table`].
+#[derive(Debug, Clone, Copy)]
+pub struct TableRow<'a> {
+    node: &'a Node,
+    section: TableSection,
+}
+
+impl<'a> TableRow<'a> {
+    /// The underlying node.
+    pub fn node(&self) -> &'a Node {
+        self.node
+    }
+
+    /// The section this row belongs to.
+    pub fn section(&self) -> TableSection {
+        self.section
+    }
+
+    /// Whether this is a header row.
+    pub fn is_header(&self) -> bool {
+        self.section == TableSection::Head
+    }
+
+    /// The cells of this row.
+    pub fn cells(&self) -> impl Iterator<Item = TableCell<'a>> {
+        self.node
+            .children
+            .iter()
+            .filter(|c| matches!(c.kind.as_str(), node::TABLE_CELL | node::TABLE_HEADER))
+            .map(TableCell)
+    }
+}
+
+/// A `table_cell` or `table_header` node.
+#[derive(Debug, Clone, Copy)]
+pub struct TableCell<'a>(&'a Node);
+
+impl<'a> TableCell<'a> {
+    /// The underlying node.
+    pub fn node(&self) -> &'a Node {
+        self.0
+    }
+
+    /// Whether this is a header cell.
+    pub fn is_header(&self) -> bool {
+        self.0.kind.as_str() == node::TABLE_HEADER
+    }
+
+    /// Columns spanned (at least 1).
+    pub fn colspan(&self) -> usize {
+        self.0.props.get_int(prop::COLSPAN).unwrap_or(1).max(1) as usize
+    }
+
+    /// Rows spanned (at least 1).
+    pub fn rowspan(&self) -> usize {
+        self.0.props.get_int(prop::ROWSPAN).unwrap_or(1).max(1) as usize
+    }
+
+    /// Cell alignment.
+    pub fn align(&self) -> Alignment {
+        self.0
+            .props
+            .get_str(prop::ALIGN)
+            .map(Alignment::parse)
+            .unwrap_or_default()
+    }
+
+    /// Cell content.
+    pub fn content(&self) -> &'a [Node] {
+        &self.0.children
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::doc;
+
+    #[test]
+    fn test_heading_defaults() {
+        let heading = Node::new(node::HEADING);
+        assert_eq!(heading.as_heading().unwrap().level(), 1);
+        let deep = Node::new(node::HEADING).prop(prop::LEVEL, 9);
+        assert_eq!(deep.as_heading().unwrap().level(), 6);
+        assert!(Node::new(node::PARAGRAPH).as_heading().is_none());
+    }
+
+    #[test]
+    fn test_section() {
+        let heading = Node::new(node::HEADING)
+            .prop(prop::LEVEL, 2)
+            .prop(prop::ID, "intro");
+        let section = Node::new(node::DIV)
+            .prop(prop::CLASSES, "section level2")
+            .child(heading)
+            .child(Node::new(node::PARAGRAPH));
+        let view = section.as_section().unwrap();
+        assert_eq!((view.level(), view.id()), (2, Some("intro")));
+        assert_eq!(view.body().len(), 1);
+        assert!(section.has_class("level2"));
+        assert!(!section.has_class("level"));
+        assert!(Node::new(node::DIV).as_section().is_none());
+    }
+
+    #[test]
+    fn test_link_image_code() {
+        let document = doc(|d| {
+            d.para(|i| {
+                i.link("https://example.com", |i| i.text("x"))
+                    .image("a.png", "A")
+            })
+            .code_block("plain")
+        });
+        let para = &document.content.children[0];
+        let link = para.children[0].as_link().unwrap();
+        assert_eq!(link.url(), "https://example.com");
+        assert_eq!(link.title(), None);
+        let image = para.children[1].as_image().unwrap();
+        assert_eq!((image.url(), image.alt()), ("a.png", "A"));
+        let code = document.content.children[1].as_code_block().unwrap();
+        assert_eq!(code.content(), "plain");
+        assert_eq!(code.language(), None);
+    }
+
+    #[test]
+    fn test_list() {
+        let document = doc(|d| d.ordered_list_from(3, |l| l.item(|i| i.text("a"))));
+        let list = document.content.children[0].as_list().unwrap();
+        assert!(list.ordered());
+        assert_eq!(list.start(), 3);
+        assert!(list.tight());
+        assert_eq!(list.items().count(), 1);
+    }
+
+    #[test]
+    fn test_table_rows_and_alignment() {
+        let document = doc(|d| {
+            d.table(|t| {
+                t.header(|r| r.cell(|i| i.text("A")).cell(|i| i.text("B")))
+                    .row(|r| r.cell(|i| i.text("1")).cell(|i| i.text("2")))
+            })
+        });
+        let table = document.content.children[0].as_table().unwrap();
+        let rows = table.rows();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].is_header());
+        assert!(!rows[1].is_header());
+        assert_eq!(table.columns(), 2);
+        assert_eq!(table.alignments(), [Alignment::Default; 2]);
+
+        let sectioned = Node::new(node::TABLE)
+            .prop(prop::COLUMN_ALIGNMENTS, "left,none,right")
+            .child(
+                Node::new(node::TABLE_HEAD).child(
+                    Node::new(node::TABLE_ROW)
+                        .child(Node::new(node::TABLE_CELL).prop(prop::COLSPAN, 3)),
+                ),
+            )
+            .child(Node::new(node::TABLE_BODY).child(Node::new(node::TABLE_ROW)));
+        let table = sectioned.as_table().unwrap();
+        let sections: Vec<_> = table.rows().iter().map(|r| r.section()).collect();
+        assert_eq!(sections, [TableSection::Head, TableSection::Body]);
+        assert_eq!(table.columns(), 3);
+        assert_eq!(
+            table.alignments(),
+            [Alignment::Left, Alignment::Default, Alignment::Right]
+        );
+    }
+}
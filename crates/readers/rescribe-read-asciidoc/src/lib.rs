@@ -9,7 +9,7 @@ use rescribe_core::{
     ConversionResult, Document, FidelityWarning, ParseError, ParseOptions, Severity, Span,
     WarningKind,
 };
-use rescribe_std::{Node, node, prop};
+use rescribe_std::{Citation, CitationMode, Node, node, prop};
 
 /// Parse AsciiDoc text into a rescribe Document.
 pub fn parse(input: &str) -> Result<ConversionResult<Document>, ParseError> {
@@ -883,6 +883,15 @@ impl<'a> Parser<'a> {
                 }
             }
 
+            // Citation: cite:[key(locator)] or citenp:[key]
+            if chars[pos] == 'c' {
+                if let Some((end, node)) = self.parse_cite_macro(&chars, pos) {
+                    nodes.push(node);
+                    pos = end;
+                    continue;
+                }
+            }
+
             if pos + 2 < chars.len() && chars[pos] == '<' && chars[pos + 1] == '<' {
                 if let Some((end, node)) = self.parse_xref(&chars, pos) {
                     nodes.push(node);
@@ -914,11 +923,13 @@ impl<'a> Parser<'a> {
                 {
                     break;
                 }
-                // Check for image: or link:
-                if c == 'i' || c == 'l' || c == 'h' {
+                // Check for image:, link: or cite:
+                if c == 'i' || c == 'l' || c == 'h' || c == 'c' {
                     let remaining: String = chars[pos..].iter().take(8).collect();
                     if remaining.starts_with("image:")
                         || remaining.starts_with("link:")
+                        || remaining.starts_with("cite:[")
+                        || remaining.starts_with("citenp:[")
                         || remaining.starts_with("https://")
                         || remaining.starts_with("http://")
                     {
@@ -1094,6 +1105,40 @@ impl<'a> Parser<'a> {
         Some((pos, node))
     }
 
+    fn parse_cite_macro(&self, chars: &[char], start: usize) -> Option<(usize, Node)> {
+        // asciidoctor-bibtex: cite:[key1(page), key2] or citenp:[key]
+        let remaining: String = chars[start..].iter().collect();
+        let (mode, args) = if let Some(args) = remaining.strip_prefix("cite:[") {
+            (CitationMode::Normal, args)
+        } else {
+            (
+                CitationMode::AuthorInText,
+                remaining.strip_prefix("citenp:[")?,
+            )
+        };
+        let close = args.find(']')?;
+
+        let mut citations = Vec::new();
+        for item in args[..close].split(',') {
+            let item = item.trim();
+            let (key, locator) = match item.split_once('(') {
+                Some((key, locator)) => (key, locator.strip_suffix(')')?),
+                None => (item, ""),
+            };
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return None;
+            }
+            citations.push(Citation::new(key).with_suffix(locator).with_mode(mode));
+        }
+
+        let source_len = remaining.len() - args.len() + close + 1;
+        let source = &remaining[..source_len];
+        let node = Node::new(node::CITE)
+            .prop(prop::CITATIONS, Citation::list(citations))
+            .child(Node::new(node::TEXT).prop(prop::CONTENT, source));
+        Some((start + source.chars().count(), node))
+    }
+
     fn parse_xref(&self, chars: &[char], start: usize) -> Option<(usize, Node)> {
         // <<anchor>> or <<anchor,text>>
         let anchor_start = start + 2; // Skip "<<"
@@ -1273,6 +1318,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_citations() {
+        use rescribe_std::NodeExt;
+
+        let input = "As cite:[doe99(45-46), roe] and citenp:[poe] show.";
+        let doc = parse(input).unwrap().value;
+        let para = &root_children(&doc)[0];
+        let cites: Vec<_> = para.children.iter().filter_map(|n| n.as_cite()).collect();
+        assert_eq!(cites.len(), 2);
+
+        let group = cites[0].citations();
+        assert_eq!(group[0].id, "doe99");
+        assert_eq!(group[0].locator.as_deref(), Some("45-46"));
+        assert_eq!(group[1].id, "roe");
+        assert_eq!(cites[1].citations()[0].mode, CitationMode::AuthorInText);
+    }
+
     #[test]
     fn test_parse_block_image() {
         let input = "image::path/to/image.png[Alt text]";
@@ -1,11 +1,14 @@
 //! BibTeX reader for rescribe.
 //!
 //! Parses BibTeX/BibLaTeX bibliography files into rescribe's document IR.
-//! Each entry is converted to a structured bibliography node.
+//! Each entry becomes a `bib:entry` node (see [`rescribe_bib`]): entry types
+//! and fields are mapped to their CSL equivalents, and fields without one
+//! are kept in `bib:custom` as `bibtex:<field>`.
 //!
 //! # Example
 //!
 //! ```
+//! use rescribe_bib::{Entry, prop};
 //! use rescribe_read_bibtex::parse;
 //!
 //! let bibtex = r#"
@@ -19,170 +22,407 @@
 //!
 //! let result = parse(bibtex).unwrap();
 //! let doc = result.value;
+//! let entry = Entry::from_node(&doc.content.children[0]).unwrap();
+//! assert_eq!(entry.entry_type(), "article-journal");
+//! assert_eq!(entry.text(prop::CONTAINER_TITLE), Some("Nature"));
 //! ```
 
-use biblatex::{Bibliography, ChunksExt};
-use rescribe_core::{ConversionResult, Document, FidelityWarning, Node, ParseError, Properties};
-use rescribe_std::{node, prop};
+use biblatex::{
+    Bibliography, ChunksExt, DateValue, EditorType, EntryType, PermissiveType, Person,
+    RetrievalError,
+};
+use rescribe_bib::{Date, DateParts, Name, prop};
+use rescribe_core::{ConversionResult, Document, Node, ParseError, PropValue, Properties};
+use rescribe_std::node;
+use std::collections::{HashMap, HashSet};
+
+/// Entry types the BibTeX and BibLaTeX writers produce for some CSL type.
+/// Other entry types are kept as the `bibtex:entrytype` custom field so that
+/// they survive a round trip.
+const WRITTEN_TYPES: &[&str] = &[
+    "article",
+    "book",
+    "booklet",
+    "dataset",
+    "incollection",
+    "inproceedings",
+    "inreference",
+    "mastersthesis",
+    "misc",
+    "online",
+    "patent",
+    "periodical",
+    "phdthesis",
+    "report",
+    "software",
+    "techreport",
+    "thesis",
+    "unpublished",
+];
 
 /// Parse BibTeX text into a document.
 pub fn parse(input: &str) -> Result<ConversionResult<Document>, ParseError> {
     let bibliography = Bibliography::parse(input)
         .map_err(|e| ParseError::Invalid(format!("BibTeX parse error: {:?}", e)))?;
 
-    let mut warnings = Vec::new();
-    let mut entries = Vec::new();
-
-    for entry in bibliography.iter() {
-        let entry_node = convert_entry(entry, &mut warnings);
-        entries.push(entry_node);
-    }
-
-    // Wrap entries in a definition list for semantic structure
-    let content = if entries.is_empty() {
-        Node::new(node::DOCUMENT)
-    } else {
-        Node::new(node::DOCUMENT).child(Node::new(node::DEFINITION_LIST).children(entries))
-    };
+    let entries: Vec<Node> = bibliography.iter().map(convert_entry).collect();
 
     let document = Document {
-        content,
+        content: Node::new(node::DOCUMENT).children(entries),
         resources: Default::default(),
         metadata: Properties::new(),
         source: None,
     };
 
-    Ok(ConversionResult::with_warnings(document, warnings))
+    Ok(ConversionResult::ok(document))
 }
 
-fn convert_entry(entry: &biblatex::Entry, _warnings: &mut Vec<FidelityWarning>) -> Node {
-    let key = entry.key.clone();
-    let entry_type = format!("{:?}", entry.entry_type).to_lowercase();
-
-    // Create the term (citation key)
-    let term = Node::new(node::DEFINITION_TERM)
-        .child(Node::new(node::CODE).prop(prop::CONTENT, key.clone()));
-
-    // Build the description content
-    let mut desc_children = Vec::new();
-
-    // Entry type badge
-    let type_text = format!("[{}] ", entry_type);
-    desc_children.push(
-        Node::new(node::SPAN)
-            .prop("html:class", "bibtex-type")
-            .child(Node::new(node::TEXT).prop(prop::CONTENT, type_text)),
-    );
+/// The fields of an entry, marked as used as they are mapped.
+struct Fields<'a> {
+    entry: &'a biblatex::Entry,
+    used: HashSet<&'static str>,
+}
 
-    // Authors
-    if let Ok(authors) = entry.author() {
-        let author_text = authors
-            .iter()
-            .map(format_person)
-            .collect::<Vec<_>>()
-            .join("; ");
-        if !author_text.is_empty() {
-            desc_children.push(
-                Node::new(node::STRONG)
-                    .child(Node::new(node::TEXT).prop(prop::CONTENT, author_text)),
-            );
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
+impl<'a> Fields<'a> {
+    fn new(entry: &'a biblatex::Entry) -> Self {
+        Self {
+            entry,
+            used: HashSet::new(),
         }
     }
 
-    // Title - get raw field and format it
-    if let Ok(title_chunks) = entry.title() {
-        let title_str = title_chunks.format_verbatim();
-        desc_children.push(
-            Node::new(node::EMPHASIS).child(Node::new(node::TEXT).prop(prop::CONTENT, title_str)),
-        );
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
+    /// A field as plain text, without marking it as used.
+    fn peek(&self, field: &str) -> Option<String> {
+        let text = self.entry.get(field)?.format_verbatim();
+        let text = text.trim();
+        (!text.is_empty()).then(|| text.to_string())
     }
 
-    // Journal
-    if let Ok(journal_chunks) = entry.journal() {
-        let journal_str = journal_chunks.format_verbatim();
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, journal_str));
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
-    } else if let Ok(booktitle_chunks) = entry.book_title() {
-        let booktitle_str = booktitle_chunks.format_verbatim();
-        desc_children
-            .push(Node::new(node::TEXT).prop(prop::CONTENT, format!("In: {}", booktitle_str)));
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
+    /// Take a field as plain text.
+    fn text(&mut self, field: &'static str) -> Option<String> {
+        self.used.insert(field);
+        self.peek(field)
     }
 
-    // Volume - use debug format for PermissiveType
-    if let Ok(volume) = entry.volume() {
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, format!("{:?}.", volume)));
+    /// Take the first of `fields` that is present.
+    fn first(&mut self, fields: &[&'static str]) -> Option<String> {
+        fields.iter().find_map(|field| {
+            let text = self.peek(field)?;
+            self.used.insert(field);
+            Some(text)
+        })
     }
 
-    // Year/Date - extract from PermissiveType
-    if let Ok(date) = entry.date() {
-        // Use debug format since Date is wrapped in PermissiveType
-        let year_text = format!(" ({:?})", date);
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, year_text));
+    /// Take a list of names.
+    fn names(&mut self, field: &'static str) -> Vec<Name> {
+        self.used.insert(field);
+        self.entry
+            .get_as::<Vec<Person>>(field)
+            .map(|persons| persons.iter().map(convert_person).collect())
+            .unwrap_or_default()
     }
 
-    // Pages
-    if let Ok(pages) = entry.pages() {
-        let pages_str = format!("{:?}", pages);
-        if !pages_str.is_empty() {
-            desc_children
-                .push(Node::new(node::TEXT).prop(prop::CONTENT, format!(", pp. {}", pages_str)));
+    /// Take a date given by a `date` field or by `year`, `month` and `day`
+    /// fields. Dates that do not parse are kept as literals.
+    fn date(
+        &mut self,
+        fields: [&'static str; 4],
+        value: Result<PermissiveType<biblatex::Date>, RetrievalError>,
+    ) -> Option<Date> {
+        let literal = self.peek(fields[0]).or_else(|| self.peek(fields[1]));
+        self.used.extend(fields);
+        match value {
+            Ok(PermissiveType::Typed(date)) => Some(convert_date(date)),
+            _ => literal.map(Date::literal),
         }
     }
 
-    // DOI
-    if let Ok(doi) = entry.doi() {
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
-        desc_children.push(
-            Node::new(node::LINK)
-                .prop(prop::URL, format!("https://doi.org/{}", doi))
-                .child(Node::new(node::TEXT).prop(prop::CONTENT, format!("doi:{}", doi))),
-        );
+    /// The fields that have not been used, as custom fields.
+    fn rest(&self) -> HashMap<String, PropValue> {
+        self.entry
+            .fields
+            .iter()
+            .filter(|(field, _)| !self.used.contains(field.as_str()))
+            .map(|(field, chunks)| {
+                (
+                    format!("bibtex:{field}"),
+                    PropValue::String(chunks.format_verbatim()),
+                )
+            })
+            .collect()
     }
+}
 
-    // URL
-    if let Ok(url) = entry.url() {
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
-        desc_children.push(
-            Node::new(node::LINK)
-                .prop(prop::URL, url.clone())
-                .child(Node::new(node::TEXT).prop(prop::CONTENT, url)),
-        );
+fn convert_entry(entry: &biblatex::Entry) -> Node {
+    let mut fields = Fields::new(entry);
+    let mut node = Node::new(rescribe_bib::node::ENTRY).prop(prop::ID, entry.key.clone());
+
+    let type_name = match &entry.entry_type {
+        EntryType::Unknown(name) => name.to_lowercase(),
+        known => known.to_string(),
+    };
+    let subtype = fields.peek("entrysubtype");
+    let (csl_type, default_genre) = csl_type(&entry.entry_type, subtype.as_deref());
+    node.props.set(prop::TYPE, csl_type);
+    if csl_type.starts_with("article-") {
+        fields.used.insert("entrysubtype");
     }
 
-    let desc = Node::new(node::DEFINITION_DESC)
-        .prop("bibtex:key", key)
-        .prop("bibtex:type", entry_type)
-        .child(Node::new(node::PARAGRAPH).children(desc_children));
+    // Names
+    set_names(&mut node, prop::AUTHOR, fields.names("author"));
+    if let Ok(editors) = entry.editors() {
+        for (persons, editor_type) in editors {
+            let key = match editor_type {
+                EditorType::Compiler => "bib:compiler",
+                EditorType::Director => "bib:director",
+                EditorType::Organizer => "bib:organizer",
+                _ => prop::EDITOR,
+            };
+            let mut names = node.props.get(key).map(Name::from_list).unwrap_or_default();
+            names.extend(persons.iter().map(convert_person));
+            set_names(&mut node, key, names);
+        }
+    }
+    fields.used.extend([
+        "editor",
+        "editora",
+        "editorb",
+        "editorc",
+        "editortype",
+        "editoratype",
+        "editorbtype",
+        "editorctype",
+    ]);
+    set_names(&mut node, prop::TRANSLATOR, fields.names("translator"));
+    set_names(
+        &mut node,
+        prop::CONTAINER_AUTHOR,
+        fields.names("bookauthor"),
+    );
 
-    // Return term and description as a pair in a wrapper
-    Node::new("bibtex:entry").children(vec![term, desc])
+    // Titles. The subtitle stays a custom field too, so that writers can
+    // split the title again.
+    let title = fields
+        .text("title")
+        .map(|title| match fields.peek("subtitle") {
+            Some(subtitle) => format!("{title}: {subtitle}"),
+            None => title,
+        });
+    set_text(&mut node, prop::TITLE, title);
+    set_text(&mut node, prop::TITLE_SHORT, fields.text("shorttitle"));
+    set_text(
+        &mut node,
+        prop::CONTAINER_TITLE,
+        fields.first(&["journaltitle", "journal", "booktitle"]),
+    );
+    set_text(
+        &mut node,
+        prop::CONTAINER_TITLE_SHORT,
+        fields.text("shortjournal"),
+    );
+    let series = fields.text("series");
+    let has_series = series.is_some();
+    set_text(&mut node, prop::COLLECTION_TITLE, series);
+    set_text(&mut node, "bib:event-title", fields.text("eventtitle"));
+    set_text(&mut node, "bib:event-place", fields.text("venue"));
+
+    // Locators
+    let number_key = if csl_type.starts_with("article") {
+        prop::ISSUE
+    } else if has_series {
+        prop::COLLECTION_NUMBER
+    } else {
+        prop::NUMBER
+    };
+    set_text(&mut node, number_key, fields.text("number"));
+    set_text(&mut node, prop::VOLUME, fields.text("volume"));
+    set_text(&mut node, prop::NUMBER_OF_VOLUMES, fields.text("volumes"));
+    set_text(&mut node, prop::EDITION, fields.text("edition"));
+    // The parser turns `--` into a dash; CSL page ranges use a hyphen.
+    set_text(
+        &mut node,
+        prop::PAGE,
+        fields
+            .text("pages")
+            .map(|pages| pages.replace(['–', '—'], "-")),
+    );
+    set_text(&mut node, prop::NUMBER_OF_PAGES, fields.text("pagetotal"));
+    set_text(&mut node, prop::CHAPTER_NUMBER, fields.text("chapter"));
+
+    // Dates
+    let issued = fields.date(["date", "year", "month", "day"], entry.date());
+    set_date(&mut node, prop::ISSUED, issued);
+    let accessed = fields.date(
+        ["urldate", "urlyear", "urlmonth", "urlday"],
+        entry.url_date(),
+    );
+    set_date(&mut node, prop::ACCESSED, accessed);
+    let event_date = fields.date(
+        ["eventdate", "eventyear", "eventmonth", "eventday"],
+        entry.event_date(),
+    );
+    set_date(&mut node, prop::EVENT_DATE, event_date);
+    let original_date = fields.date(
+        ["origdate", "origyear", "origmonth", "origday"],
+        entry.orig_date(),
+    );
+    set_date(&mut node, prop::ORIGINAL_DATE, original_date);
+
+    // Publication details
+    set_text(
+        &mut node,
+        prop::PUBLISHER,
+        fields.first(&["publisher", "institution", "school", "organization"]),
+    );
+    set_text(
+        &mut node,
+        prop::PUBLISHER_PLACE,
+        fields.first(&["location", "address"]),
+    );
+    let genre = fields.text("type").map(|genre| match genre.as_str() {
+        "phdthesis" => "PhD thesis".to_string(),
+        "mathesis" | "mastersthesis" => "Master's thesis".to_string(),
+        _ => genre,
+    });
+    set_text(
+        &mut node,
+        prop::GENRE,
+        genre.or(default_genre.map(str::to_string)),
+    );
+    set_text(&mut node, prop::VERSION, fields.text("version"));
+    set_text(&mut node, prop::STATUS, fields.text("pubstate"));
+
+    // Identifiers and notes
+    set_text(&mut node, prop::DOI, fields.text("doi"));
+    set_text(&mut node, prop::URL, fields.text("url"));
+    set_text(&mut node, prop::ISBN, fields.text("isbn"));
+    set_text(&mut node, prop::ISSN, fields.text("issn"));
+    set_text(&mut node, prop::ABSTRACT, fields.text("abstract"));
+    set_text(&mut node, prop::KEYWORD, fields.text("keywords"));
+    set_text(&mut node, prop::NOTE, fields.text("note"));
+    set_text(
+        &mut node,
+        prop::ANNOTE,
+        fields.first(&["annotation", "annote"]),
+    );
+    set_text(&mut node, prop::LANGUAGE, fields.text("language"));
+
+    let mut custom = fields.rest();
+    if !WRITTEN_TYPES.contains(&type_name.as_str()) {
+        custom.insert("bibtex:entrytype".to_string(), type_name.into());
+    }
+    if !custom.is_empty() {
+        node.props.set(prop::CUSTOM, PropValue::Map(custom));
+    }
+
+    node
 }
 
-fn format_person(person: &biblatex::Person) -> String {
-    let mut parts = Vec::new();
+/// The CSL type of an entry type, with the genre it implies.
+fn csl_type(entry_type: &EntryType, subtype: Option<&str>) -> (&'static str, Option<&'static str>) {
+    let csl_type = match entry_type {
+        EntryType::Article => match subtype {
+            Some("magazine") => "article-magazine",
+            Some("newspaper") => "article-newspaper",
+            _ => "article-journal",
+        },
+        EntryType::Book
+        | EntryType::MvBook
+        | EntryType::Collection
+        | EntryType::MvCollection
+        | EntryType::Proceedings
+        | EntryType::MvProceedings
+        | EntryType::Reference
+        | EntryType::MvReference
+        | EntryType::Manual => "book",
+        EntryType::InBook
+        | EntryType::BookInBook
+        | EntryType::SuppBook
+        | EntryType::InCollection
+        | EntryType::SuppCollection => "chapter",
+        EntryType::InReference => "entry-encyclopedia",
+        EntryType::InProceedings => "paper-conference",
+        EntryType::Booklet => "pamphlet",
+        EntryType::MastersThesis => return ("thesis", Some("Master's thesis")),
+        EntryType::PhdThesis => return ("thesis", Some("PhD thesis")),
+        EntryType::Thesis => "thesis",
+        EntryType::TechReport | EntryType::Report => "report",
+        EntryType::Online => "webpage",
+        EntryType::Unpublished => "manuscript",
+        EntryType::Patent => "patent",
+        EntryType::Software => "software",
+        EntryType::Dataset => "dataset",
+        EntryType::Periodical | EntryType::SuppPeriodical => "periodical",
+        EntryType::Misc | EntryType::Set | EntryType::XData | EntryType::Unknown(_) => "document",
+    };
+    (csl_type, None)
+}
 
-    if !person.given_name.is_empty() {
-        parts.push(person.given_name.clone());
+/// Convert a person. Names that BibTeX cannot split, such as a braced
+/// organisation, become literals.
+fn convert_person(person: &Person) -> Name {
+    if person.given_name.is_empty()
+        && person.prefix.is_empty()
+        && person.suffix.is_empty()
+        && person.name.contains(' ')
+    {
+        return Name::literal(person.name.clone());
     }
-    if !person.prefix.is_empty() {
-        parts.push(person.prefix.clone());
+    let part = |s: &str| (!s.is_empty()).then(|| s.to_string());
+    Name {
+        family: part(&person.name),
+        given: part(&person.given_name),
+        non_dropping_particle: part(&person.prefix),
+        suffix: part(&person.suffix),
+        ..Name::default()
     }
-    if !person.name.is_empty() {
-        parts.push(person.name.clone());
+}
+
+fn convert_date(date: biblatex::Date) -> Date {
+    // Months and days are counted from zero.
+    let parts = |at: biblatex::Datetime| {
+        DateParts::new(at.year, at.month.map(|m| m + 1), at.day.map(|d| d + 1))
+    };
+    let (start, end) = match date.value {
+        DateValue::At(at) | DateValue::After(at) | DateValue::Before(at) => (at, None),
+        DateValue::Between(start, end) => (start, Some(end)),
+    };
+    Date {
+        start: Some(parts(start)),
+        end: end.map(parts),
+        literal: None,
+        circa: date.approximate || date.uncertain,
     }
-    if !person.suffix.is_empty() {
-        parts.push(person.suffix.clone());
+}
+
+fn set_text(node: &mut Node, key: &str, value: Option<String>) {
+    if let Some(value) = value {
+        node.props.set(key, value);
+    }
+}
+
+fn set_names(node: &mut Node, key: &str, names: Vec<Name>) {
+    if !names.is_empty() {
+        node.props.set(key, Name::list(names));
     }
+}
 
-    parts.join(" ")
+fn set_date(node: &mut Node, key: &str, date: Option<Date>) {
+    if let Some(date) = date {
+        node.props.set(key, date);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rescribe_bib::Entry;
+
+    fn parse_entry(bibtex: &str) -> Node {
+        let doc = parse(bibtex).unwrap().value;
+        assert_eq!(doc.content.children.len(), 1);
+        doc.content.children[0].clone()
+    }
 
     #[test]
     fn test_parse_article() {
@@ -223,4 +463,56 @@ mod tests {
         let doc = result.value;
         assert!(doc.content.children.is_empty());
     }
+
+    #[test]
+    fn test_fields() {
+        let node = parse_entry(
+            r#"
+@article{vangogh1890,
+  author = {van Gogh, Vincent and {World Health Organization}},
+  title = {Letters},
+  journal = {Art Review},
+  volume = {12},
+  number = {3},
+  pages = {45--67},
+  year = {1890},
+  month = may,
+  doi = {10.1000/xyz},
+  eprint = {2101.00001},
+}
+"#,
+        );
+        let entry = Entry::from_node(&node).unwrap();
+        assert_eq!(entry.id(), "vangogh1890");
+        assert_eq!(entry.entry_type(), "article-journal");
+
+        let authors = entry.names(prop::AUTHOR);
+        assert_eq!(authors[0].family.as_deref(), Some("Gogh"));
+        assert_eq!(authors[0].non_dropping_particle.as_deref(), Some("van"));
+        assert_eq!(authors[1], Name::literal("World Health Organization"));
+
+        assert_eq!(entry.text(prop::ISSUE), Some("3"));
+        assert_eq!(entry.text(prop::PAGE), Some("45-67"));
+        assert_eq!(entry.text(prop::DOI), Some("10.1000/xyz"));
+        assert_eq!(
+            entry.date(prop::ISSUED).unwrap().start,
+            Some(DateParts::new(1890, Some(5), None))
+        );
+        assert_eq!(entry.custom("bibtex:eprint"), Some("2101.00001"));
+    }
+
+    #[test]
+    fn test_entry_types() {
+        let node = parse_entry("@phdthesis{a, title = {T}, school = {MIT}, year = 2001}");
+        let entry = Entry::from_node(&node).unwrap();
+        assert_eq!(entry.entry_type(), "thesis");
+        assert_eq!(entry.text(prop::GENRE), Some("PhD thesis"));
+        assert_eq!(entry.text(prop::PUBLISHER), Some("MIT"));
+        assert_eq!(entry.custom("bibtex:entrytype"), None);
+
+        let node = parse_entry("@manual{b, title = {T}}");
+        let entry = Entry::from_node(&node).unwrap();
+        assert_eq!(entry.entry_type(), "book");
+        assert_eq!(entry.custom("bibtex:entrytype"), Some("manual"));
+    }
 }
@@ -1,11 +1,15 @@
 //! CSL JSON reader for rescribe.
 //!
 //! Parses CSL JSON (Citation Style Language JSON) into rescribe's document IR.
-//! Each citation item is converted to a structured bibliography node.
+//! Each citation item becomes a `bib:entry` node (see [`rescribe_bib`]) whose
+//! properties are the item's variables. Values CSL does not define, such as
+//! nested objects under an unknown key, are kept in `bib:custom` as
+//! `csl:<key>`.
 //!
 //! # Example
 //!
 //! ```
+//! use rescribe_bib::{Entry, prop};
 //! use rescribe_read_csl_json::parse;
 //!
 //! let csl = r#"[{
@@ -18,33 +22,33 @@
 //!
 //! let result = parse(csl).unwrap();
 //! let doc = result.value;
+//! let entry = Entry::from_node(&doc.content.children[0]).unwrap();
+//! assert_eq!(entry.names(prop::AUTHOR)[0].given.as_deref(), Some("John"));
 //! ```
 
-use rescribe_core::{ConversionResult, Document, Node, ParseError, Properties};
-use rescribe_std::{node, prop};
-use serde::Deserialize;
+use rescribe_bib::{Date, Name, PREFIX, is_date_variable, is_name_variable, prop};
+use rescribe_core::{
+    ConversionResult, Document, FidelityWarning, Node, ParseError, PropValue, Properties, Severity,
+    WarningKind,
+};
+use rescribe_std::node;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
 
 /// Parse CSL JSON text into a document.
 pub fn parse(input: &str) -> Result<ConversionResult<Document>, ParseError> {
-    let items: Vec<CslItem> = serde_json::from_str(input)
+    let items: Vec<Map<String, Value>> = serde_json::from_str(input)
         .map_err(|e| ParseError::Invalid(format!("CSL JSON parse error: {}", e)))?;
 
-    let warnings = Vec::new();
+    let mut warnings = Vec::new();
     let mut entries = Vec::new();
 
     for item in &items {
-        let entry_node = convert_item(item);
-        entries.push(entry_node);
+        entries.push(convert_item(item, &mut warnings)?);
     }
 
-    let content = if entries.is_empty() {
-        Node::new(node::DOCUMENT)
-    } else {
-        Node::new(node::DOCUMENT).child(Node::new(node::DEFINITION_LIST).children(entries))
-    };
-
     let document = Document {
-        content,
+        content: Node::new(node::DOCUMENT).children(entries),
         resources: Default::default(),
         metadata: Properties::new(),
         source: None,
@@ -53,216 +57,107 @@ pub fn parse(input: &str) -> Result<ConversionResult<Document>, ParseError> {
     Ok(ConversionResult::with_warnings(document, warnings))
 }
 
-#[derive(Debug, Deserialize)]
-#[allow(dead_code)]
-struct CslItem {
-    id: String,
-    #[serde(rename = "type")]
-    item_type: Option<String>,
-    title: Option<String>,
-    author: Option<Vec<CslName>>,
-    editor: Option<Vec<CslName>>,
-    issued: Option<CslDate>,
-    #[serde(rename = "container-title")]
-    container_title: Option<String>,
-    #[serde(rename = "collection-title")]
-    collection_title: Option<String>,
-    publisher: Option<String>,
-    #[serde(rename = "publisher-place")]
-    publisher_place: Option<String>,
-    volume: Option<StringOrInt>,
-    issue: Option<StringOrInt>,
-    page: Option<String>,
-    #[serde(rename = "DOI")]
-    doi: Option<String>,
-    #[serde(rename = "URL")]
-    url: Option<String>,
-    #[serde(rename = "ISBN")]
-    isbn: Option<String>,
-    #[serde(rename = "ISSN")]
-    issn: Option<String>,
-    #[serde(rename = "abstract")]
-    abstract_text: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-struct CslName {
-    family: Option<String>,
-    given: Option<String>,
-    literal: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-struct CslDate {
-    #[serde(rename = "date-parts")]
-    date_parts: Option<Vec<Vec<i32>>>,
-    literal: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(untagged)]
-enum StringOrInt {
-    String(String),
-    Int(i64),
-}
-
-impl std::fmt::Display for StringOrInt {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            StringOrInt::String(s) => write!(f, "{}", s),
-            StringOrInt::Int(i) => write!(f, "{}", i),
+fn convert_item(
+    item: &Map<String, Value>,
+    warnings: &mut Vec<FidelityWarning>,
+) -> Result<Node, ParseError> {
+    let id = match item.get("id") {
+        Some(Value::String(id)) => id.clone(),
+        Some(Value::Number(id)) => id.to_string(),
+        _ => {
+            return Err(ParseError::Invalid(
+                "CSL JSON parse error: item without an id".to_string(),
+            ));
         }
-    }
-}
-
-fn convert_item(item: &CslItem) -> Node {
-    let key = item.id.clone();
+    };
     let item_type = item
-        .item_type
-        .clone()
-        .unwrap_or_else(|| "unknown".to_string());
-
-    // Create the term (citation key)
-    let term = Node::new(node::DEFINITION_TERM)
-        .child(Node::new(node::CODE).prop(prop::CONTENT, key.clone()));
-
-    // Build the description content
-    let mut desc_children = Vec::new();
-
-    // Item type badge
-    let type_text = format!("[{}] ", item_type);
-    desc_children.push(
-        Node::new(node::SPAN)
-            .prop("html:class", "csl-type")
-            .child(Node::new(node::TEXT).prop(prop::CONTENT, type_text)),
-    );
-
-    // Authors
-    if let Some(authors) = &item.author {
-        let author_text = authors
-            .iter()
-            .map(format_name)
-            .collect::<Vec<_>>()
-            .join("; ");
-        if !author_text.is_empty() {
-            desc_children.push(
-                Node::new(node::STRONG)
-                    .child(Node::new(node::TEXT).prop(prop::CONTENT, author_text)),
-            );
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
-        }
-    }
-
-    // Title
-    if let Some(title) = &item.title {
-        desc_children.push(
-            Node::new(node::EMPHASIS)
-                .child(Node::new(node::TEXT).prop(prop::CONTENT, title.clone())),
-        );
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
-    }
-
-    // Container title (journal, book, etc.)
-    if let Some(container) = &item.container_title {
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, container.clone()));
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
-    }
-
-    // Volume/Issue
-    if let Some(volume) = &item.volume {
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, format!("{}", volume)));
-        if let Some(issue) = &item.issue {
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, format!("({})", issue)));
-        }
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
-    }
-
-    // Date
-    if let Some(date) = &item.issued {
-        let date_str = format_date(date);
-        if !date_str.is_empty() {
-            desc_children
-                .push(Node::new(node::TEXT).prop(prop::CONTENT, format!(" ({})", date_str)));
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or("document");
+
+    let mut node = Node::new(rescribe_bib::node::ENTRY)
+        .prop(prop::ID, id.clone())
+        .prop(prop::TYPE, item_type);
+    let mut custom = HashMap::new();
+
+    for (key, value) in item {
+        let prop_key = format!("{PREFIX}{key}");
+        match (key.as_str(), value) {
+            ("id" | "type", _) | (_, Value::Null) => {}
+            ("custom", Value::Object(fields)) => {
+                for (name, value) in fields {
+                    custom.insert(name.clone(), PropValue::String(scalar_text(value)));
+                }
+            }
+            (name, value) if is_name_variable(name) => {
+                let names = to_prop(value)
+                    .map(|v| Name::from_list(&v))
+                    .unwrap_or_default();
+                if names.is_empty() {
+                    warnings.push(invalid(&id, key));
+                } else {
+                    node.props.set(prop_key, Name::list(names));
+                }
+            }
+            (name, value) if is_date_variable(name) => {
+                match to_prop(value).as_ref().and_then(Date::from_prop) {
+                    Some(date) => node.props.set(prop_key, date),
+                    None => warnings.push(invalid(&id, key)),
+                }
+            }
+            (_, Value::String(_) | Value::Number(_) | Value::Bool(_)) => {
+                node.props.set(prop_key, scalar_text(value));
+            }
+            _ => {
+                custom.insert(format!("csl:{key}"), PropValue::String(value.to_string()));
+            }
         }
     }
 
-    // Pages
-    if let Some(page) = &item.page {
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, format!(", pp. {}", page)));
+    if !custom.is_empty() {
+        node.props.set(prop::CUSTOM, PropValue::Map(custom));
     }
-
-    // Publisher
-    if let Some(publisher) = &item.publisher {
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, format!(". {}", publisher)));
-        if let Some(place) = &item.publisher_place {
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, format!(", {}", place)));
-        }
-    }
-
-    // DOI
-    if let Some(doi) = &item.doi {
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
-        desc_children.push(
-            Node::new(node::LINK)
-                .prop(prop::URL, format!("https://doi.org/{}", doi))
-                .child(Node::new(node::TEXT).prop(prop::CONTENT, format!("doi:{}", doi))),
-        );
-    }
-
-    // URL
-    if let Some(url) = &item.url {
-        desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
-        desc_children.push(
-            Node::new(node::LINK)
-                .prop(prop::URL, url.clone())
-                .child(Node::new(node::TEXT).prop(prop::CONTENT, url.clone())),
-        );
-    }
-
-    let desc = Node::new(node::DEFINITION_DESC)
-        .prop("csl:id", key)
-        .prop("csl:type", item_type)
-        .child(Node::new(node::PARAGRAPH).children(desc_children));
-
-    Node::new("csl:item").children(vec![term, desc])
+    Ok(node)
 }
 
-fn format_name(name: &CslName) -> String {
-    if let Some(literal) = &name.literal {
-        return literal.clone();
-    }
-
-    let mut parts = Vec::new();
-    if let Some(given) = &name.given {
-        parts.push(given.clone());
-    }
-    if let Some(family) = &name.family {
-        parts.push(family.clone());
+/// A scalar value as text; other values as JSON.
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
     }
-    parts.join(" ")
 }
 
-fn format_date(date: &CslDate) -> String {
-    if let Some(literal) = &date.literal {
-        return literal.clone();
-    }
-
-    if let Some(parts) = &date.date_parts
-        && let Some(first) = parts.first()
-    {
-        return first
-            .iter()
-            .map(|n| n.to_string())
-            .collect::<Vec<_>>()
-            .join("-");
-    }
+fn to_prop(value: &Value) -> Option<PropValue> {
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(b) => PropValue::Bool(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => PropValue::Int(i),
+            None => PropValue::Float(n.as_f64()?),
+        },
+        Value::String(s) => PropValue::String(s.clone()),
+        Value::Array(items) => PropValue::List(items.iter().filter_map(to_prop).collect()),
+        Value::Object(map) => PropValue::Map(
+            map.iter()
+                .filter_map(|(k, v)| Some((k.clone(), to_prop(v)?)))
+                .collect(),
+        ),
+    })
+}
 
-    String::new()
+fn invalid(id: &str, key: &str) -> FidelityWarning {
+    FidelityWarning::new(
+        Severity::Minor,
+        WarningKind::UnsupportedProperty(key.to_string()),
+        format!("Ignoring invalid CSL variable '{key}' in item '{id}'"),
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rescribe_bib::{DateParts, Entry};
 
     #[test]
     fn test_parse_article() {
@@ -303,4 +198,42 @@ mod tests {
         let doc = result.value;
         assert!(doc.content.children.is_empty());
     }
+
+    #[test]
+    fn test_variables() {
+        let csl = r#"[{
+            "id": 7,
+            "type": "chapter",
+            "editor": [{"family": "Gogh", "non-dropping-particle": "van"}, {"literal": "WHO"}],
+            "volume": 3,
+            "issued": {"date-parts": [["2019", "3"], [2020]], "circa": true},
+            "accessed": {"raw": "2024-01-15"},
+            "custom": {"ris:M3": "Print"},
+            "extra-data": {"a": 1},
+            "submitted": "soon"
+        }]"#;
+
+        let result = parse(csl).unwrap();
+        let doc = result.value;
+        let entry = Entry::from_node(&doc.content.children[0]).unwrap();
+        assert_eq!(entry.id(), "7");
+        assert_eq!(entry.text(prop::VOLUME), Some("3"));
+        let editors = entry.names(prop::EDITOR);
+        assert_eq!(editors[0].family_part().as_deref(), Some("van Gogh"));
+        assert_eq!(editors[1], Name::literal("WHO"));
+
+        let issued = entry.date(prop::ISSUED).unwrap();
+        assert_eq!(issued.start, Some(DateParts::new(2019, Some(3), None)));
+        assert!(issued.circa);
+        assert_eq!(
+            entry.date(prop::ACCESSED).unwrap().to_edtf().as_deref(),
+            Some("2024-01-15")
+        );
+        assert_eq!(
+            entry.date("bib:submitted").unwrap().literal.as_deref(),
+            Some("soon")
+        );
+        assert_eq!(entry.custom("ris:M3"), Some("Print"));
+        assert_eq!(entry.custom("csl:extra-data"), Some(r#"{"a":1}"#));
+    }
 }
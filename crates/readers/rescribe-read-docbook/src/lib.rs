@@ -20,7 +20,10 @@
 
 use quick_xml::Reader;
 use quick_xml::events::{BytesStart, Event};
-use rescribe_core::{ConversionResult, Document, FidelityWarning, Node, ParseError, Properties};
+use rescribe_core::{
+    ConversionResult, Document, FidelityWarning, Node, ParseError, Properties, Severity,
+    WarningKind,
+};
 use rescribe_std::{node, prop};
 
 /// Parse DocBook XML into a document.
@@ -29,7 +32,7 @@ pub fn parse(input: &str) -> Result<ConversionResult<Document>, ParseError> {
     reader.config_mut().trim_text(true);
 
     let mut converter = Converter::new();
-    converter.parse(&mut reader)?;
+    converter.parse(input, &mut reader)?;
 
     let document = Document {
         content: Node::new(node::DOCUMENT).children(converter.result),
@@ -78,11 +81,21 @@ impl Converter {
         }
     }
 
-    fn parse(&mut self, reader: &mut Reader<&[u8]>) -> Result<(), ParseError> {
+    fn parse(&mut self, input: &str, reader: &mut Reader<&[u8]>) -> Result<(), ParseError> {
         let mut buf = Vec::new();
 
         loop {
+            let start = reader.buffer_position() as usize;
             match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) if e.local_name().as_ref() == b"math" => {
+                    // MathML is read as a whole by rescribe-math
+                    self.flush_text();
+                    reader
+                        .read_to_end(e.name())
+                        .map_err(|e| ParseError::Invalid(format!("XML parse error: {}", e)))?;
+                    let end = reader.buffer_position() as usize;
+                    self.handle_math(&input[start..end]);
+                }
                 Ok(Event::Start(e)) => {
                     self.flush_text();
                     self.handle_start(&e)?;
@@ -204,6 +217,20 @@ impl Converter {
         Ok(())
     }
 
+    fn handle_math(&mut self, xml: &str) {
+        match rescribe_math::mathml::parse(xml.trim_start()) {
+            Ok(math) => match self.stack.last_mut() {
+                Some(frame) => frame.children.push(math),
+                None => self.result.push(math),
+            },
+            Err(e) => self.warnings.push(FidelityWarning::new(
+                Severity::Minor,
+                WarningKind::FeatureLost("math".to_string()),
+                e.to_string(),
+            )),
+        }
+    }
+
     fn handle_end(&mut self, e: &quick_xml::events::BytesEnd<'_>) -> Result<(), ParseError> {
         let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
 
@@ -396,6 +423,10 @@ impl Converter {
                 None
             }
 
+            // Equations
+            "equation" | "informalequation" => Some(equation("math_display", &frame.children)),
+            "inlineequation" => Some(equation("math_inline", &frame.children)),
+
             // Line break
             "sbr" => Some(Node::new(node::LINE_BREAK)),
 
@@ -438,6 +469,29 @@ fn extract_text(nodes: &[Node]) -> String {
     text
 }
 
+/// A `kind` math node for an equation: the MathML among `children` if there
+/// is any, with the text of its `<alt>` or `<mathphrase>` as its source.
+fn equation(kind: &str, children: &[Node]) -> Node {
+    let is_math = |n: &&Node| matches!(n.kind.as_str(), "math_inline" | "math_display");
+    let mut math = match children.iter().find(is_math) {
+        Some(mathml) => Node {
+            kind: kind.into(),
+            ..mathml.clone()
+        },
+        None => Node::new(kind),
+    };
+    let text = children
+        .iter()
+        .find(|n| n.kind.as_str() == node::TEXT)
+        .and_then(|n| n.props.get_str(prop::CONTENT));
+    if let Some(text) = text {
+        math = math.prop("math:source", text.trim());
+    } else if math.children.is_empty() {
+        math = math.prop("math:source", "");
+    }
+    math
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -508,4 +562,24 @@ mod tests {
         let doc = result.value;
         assert!(!doc.content.children.is_empty());
     }
+
+    #[test]
+    fn test_parse_equations() {
+        let xml = r#"<article xmlns="http://docbook.org/ns/docbook" xmlns:mml="http://www.w3.org/1998/Math/MathML">
+  <para>Let <inlineequation><mathphrase role="tex">x^2</mathphrase></inlineequation>.</para>
+  <equation><title>Root</title><alt role="tex">\sqrt{2}</alt><mml:math display="block"><mml:msqrt><mml:mn>2</mml:mn></mml:msqrt></mml:math></equation>
+</article>"#;
+        let result = parse(xml).unwrap();
+        assert!(result.warnings.is_empty());
+        let content = &result.value.content.children[0].children;
+
+        let inline = &content[0].children[1];
+        assert_eq!(inline.kind.as_str(), "math_inline");
+        assert!(inline.children.is_empty());
+        assert_eq!(inline.props.get_str("math:source"), Some("x^2"));
+
+        assert_eq!(content[1].kind.as_str(), "math_display");
+        assert_eq!(content[1].children[0].kind.as_str(), "math:root");
+        assert_eq!(content[1].props.get_str("math:source"), Some(r"\sqrt{2}"));
+    }
 }
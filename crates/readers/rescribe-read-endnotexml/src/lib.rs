@@ -1,21 +1,79 @@
 //! EndNote XML reader for rescribe.
 //!
 //! Parses EndNote XML bibliography files into rescribe's document IR.
+//! Each `<record>` becomes a `bib:entry` node (see [`rescribe_bib`]).
+//! Elements without a CSL variable are kept in `bib:custom` as
+//! `endnote:<path>`, where the path is relative to the record, e.g.
+//! `endnote:urls/pdf-urls/url`.
 //!
 //! # Example
 //!
-//! ```ignore
+//! ```
+//! use rescribe_bib::{Entry, prop};
 //! use rescribe_read_endnotexml::parse;
 //!
 //! let xml = r#"<?xml version="1.0"?>
-//! <xml><records><record>...</record></records></xml>"#;
+//! <xml><records><record>
+//!   <ref-type name="Journal Article">17</ref-type>
+//!   <titles><title><style face="normal">A Great Paper</style></title></titles>
+//! </record></records></xml>"#;
 //! let result = parse(xml).unwrap();
+//! let entry = Entry::from_node(&result.value.content.children[0]).unwrap();
+//! assert_eq!(entry.text(prop::TITLE), Some("A Great Paper"));
 //! ```
 
 use quick_xml::Reader;
 use quick_xml::events::Event;
-use rescribe_core::{ConversionResult, Document, Node, ParseError, ParseOptions, Properties};
-use rescribe_std::{node, prop};
+use rescribe_bib::{Date, DateParts, Name, prop};
+use rescribe_core::{
+    ConversionResult, Document, Node, ParseError, ParseOptions, PropValue, Properties,
+};
+use rescribe_std::node;
+use std::collections::{HashMap, HashSet};
+
+/// EndNote reference types: name, number and CSL type. The first row for a
+/// CSL type is the one the EndNote XML writer produces; records of the other
+/// types keep their type as the `endnote:ref-type` custom field.
+const REF_TYPES: &[(&str, u8, &str)] = &[
+    ("Journal Article", 17, "article-journal"),
+    ("Magazine Article", 19, "article-magazine"),
+    ("Newspaper Article", 23, "article-newspaper"),
+    ("Book", 6, "book"),
+    ("Book Section", 5, "chapter"),
+    ("Conference Paper", 47, "paper-conference"),
+    ("Thesis", 32, "thesis"),
+    ("Report", 27, "report"),
+    ("Web Page", 12, "webpage"),
+    ("Blog", 56, "post-weblog"),
+    ("Computer Program", 9, "software"),
+    ("Dataset", 59, "dataset"),
+    ("Patent", 25, "patent"),
+    ("Manuscript", 36, "manuscript"),
+    ("Pamphlet", 24, "pamphlet"),
+    ("Dictionary", 52, "entry-dictionary"),
+    ("Encyclopedia", 53, "entry-encyclopedia"),
+    ("Map", 20, "map"),
+    ("Artwork", 2, "graphic"),
+    ("Film or Broadcast", 21, "motion_picture"),
+    ("Bill", 4, "bill"),
+    ("Case", 7, "legal_case"),
+    ("Statute", 31, "legislation"),
+    ("Hearing", 14, "hearing"),
+    ("Standard", 58, "standard"),
+    ("Personal Communication", 26, "personal_communication"),
+    ("Generic", 13, "document"),
+    ("Conference Proceedings", 10, "paper-conference"),
+    ("Edited Book", 28, "book"),
+    ("Electronic Article", 43, "article-journal"),
+    ("Electronic Book", 44, "book"),
+    ("Electronic Book Section", 60, "chapter"),
+    ("Unpublished Work", 34, "manuscript"),
+    ("Government Document", 46, "report"),
+    ("Audiovisual Material", 3, "motion_picture"),
+];
+
+/// Library bookkeeping that is not part of the reference.
+const IGNORED: &[&str] = &["rec-number", "database", "source-app", "foreign-keys/key"];
 
 /// Parse EndNote XML into a document.
 pub fn parse(input: &str) -> Result<ConversionResult<Document>, ParseError> {
@@ -27,116 +85,78 @@ pub fn parse_with_options(
     input: &str,
     _options: &ParseOptions,
 ) -> Result<ConversionResult<Document>, ParseError> {
+    // Text is trimmed per field: spaces between `<style>` runs matter.
     let mut reader = Reader::from_str(input);
-    reader.config_mut().trim_text(true);
 
     let mut entries = Vec::new();
     let mut buf = Vec::new();
     let mut current_entry: Option<EndNoteEntry> = None;
-    let mut _current_element = String::new();
-    let mut current_text = String::new();
-    let mut in_authors = false;
-    let mut in_keywords = false;
+    // Elements open inside the current record, with their text so far.
+    // `<style>` elements only format text, so their text goes to the parent.
+    let mut stack: Vec<(String, String)> = Vec::new();
 
     loop {
         match reader.read_event_into(&mut buf) {
             Ok(Event::Start(e)) => {
-                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                _current_element = name.clone();
-                current_text.clear();
-
-                match name.as_str() {
-                    "record" => {
-                        current_entry = Some(EndNoteEntry::new());
-                    }
-                    "authors" | "contributors" => {
-                        in_authors = true;
-                    }
-                    "keywords" => {
-                        in_keywords = true;
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                match (&mut current_entry, name.as_str()) {
+                    (None, "record") => current_entry = Some(EndNoteEntry::default()),
+                    (None, _) | (Some(_), "style") => {}
+                    (Some(entry), _) => {
+                        if name == "ref-type"
+                            && let Some(attr) = e.try_get_attribute("name").ok().flatten()
+                        {
+                            entry.ref_type_name = Some(String::from_utf8_lossy(&attr.value).into());
+                        }
+                        stack.push((name, String::new()));
                     }
-                    _ => {}
                 }
             }
             Ok(Event::End(e)) => {
-                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
-
-                if let Some(ref mut entry) = current_entry {
-                    match name.as_str() {
-                        "record" => {
-                            if let Some(e) = current_entry.take() {
-                                entries.push(e.into_node());
-                            }
-                        }
-                        "authors" | "contributors" => {
-                            in_authors = false;
-                        }
-                        "keywords" => {
-                            in_keywords = false;
-                        }
-                        "author" if in_authors => {
-                            if !current_text.is_empty() {
-                                entry.authors.push(current_text.clone());
-                            }
-                        }
-                        "keyword" if in_keywords => {
-                            if !current_text.is_empty() {
-                                entry.keywords.push(current_text.clone());
-                            }
-                        }
-                        "ref-type" => {
-                            entry.ref_type = current_text.clone();
-                        }
-                        "title" | "secondary-title" | "tertiary-title" => {
-                            if entry.title.is_empty() && name == "title" {
-                                entry.title = current_text.clone();
-                            } else if name == "secondary-title" {
-                                entry.journal = current_text.clone();
-                            }
-                        }
-                        "year" => {
-                            entry.year = current_text.clone();
-                        }
-                        "volume" => {
-                            entry.volume = current_text.clone();
-                        }
-                        "number" => {
-                            entry.number = current_text.clone();
-                        }
-                        "pages" => {
-                            entry.pages = current_text.clone();
-                        }
-                        "publisher" => {
-                            entry.publisher = current_text.clone();
-                        }
-                        "isbn" | "issn" => {
-                            entry.isbn = current_text.clone();
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                match (&mut current_entry, name.as_str()) {
+                    (Some(_), "record") => {
+                        if let Some(entry) = current_entry.take() {
+                            entries.push(entry.into_node());
                         }
-                        "electronic-resource-num" => {
-                            // This is typically the DOI
-                            entry.doi = current_text.clone();
-                        }
-                        "url" | "web-urls" => {
-                            if entry.url.is_empty() {
-                                entry.url = current_text.clone();
-                            }
-                        }
-                        "abstract" => {
-                            entry.abstract_text = current_text.clone();
-                        }
-                        "label" | "rec-number" => {
-                            if entry.key.is_empty() {
-                                entry.key = current_text.clone();
-                            }
+                        stack.clear();
+                    }
+                    (None, _) | (Some(_), "style") => {}
+                    (Some(entry), _) => {
+                        let path = stack
+                            .iter()
+                            .map(|(name, _)| name.as_str())
+                            .collect::<Vec<_>>()
+                            .join("/");
+                        if let Some((_, text)) = stack.pop()
+                            && !text.trim().is_empty()
+                        {
+                            entry.add_field(path, text.trim());
                         }
-                        _ => {}
                     }
                 }
-                current_text.clear();
             }
             Ok(Event::Text(e)) => {
-                let text = String::from_utf8_lossy(e.as_ref()).to_string();
-                current_text.push_str(&text);
+                if let Some((_, text)) = stack.last_mut() {
+                    text.push_str(&String::from_utf8_lossy(e.as_ref()));
+                }
+            }
+            Ok(Event::CData(e)) => {
+                if let Some((_, text)) = stack.last_mut() {
+                    text.push_str(&String::from_utf8_lossy(e.as_ref()));
+                }
+            }
+            Ok(Event::GeneralRef(e)) => {
+                if let Some((_, text)) = stack.last_mut() {
+                    let name = String::from_utf8_lossy(e.as_ref()).to_string();
+                    match e.resolve_char_ref().ok().flatten() {
+                        Some(ch) => text.push(ch),
+                        None => match quick_xml::escape::resolve_xml_entity(&name) {
+                            Some(entity) => text.push_str(entity),
+                            None => text.push_str(&format!("&{name};")),
+                        },
+                    }
+                }
             }
             Ok(Event::Eof) => break,
             Err(e) => return Err(ParseError::Invalid(format!("XML error: {}", e))),
@@ -145,14 +165,8 @@ pub fn parse_with_options(
         buf.clear();
     }
 
-    let content = if entries.is_empty() {
-        Node::new(node::DOCUMENT)
-    } else {
-        Node::new(node::DOCUMENT).child(Node::new(node::DEFINITION_LIST).children(entries))
-    };
-
     Ok(ConversionResult::ok(Document {
-        content,
+        content: Node::new(node::DOCUMENT).children(entries),
         resources: Default::default(),
         metadata: Properties::new(),
         source: None,
@@ -160,189 +174,237 @@ pub fn parse_with_options(
 }
 
 /// EndNote entry being parsed.
+#[derive(Default)]
 struct EndNoteEntry {
-    ref_type: String,
-    key: String,
-    title: String,
-    authors: Vec<String>,
-    journal: String,
-    year: String,
-    volume: String,
-    number: String,
-    pages: String,
-    publisher: String,
-    isbn: String,
-    doi: String,
-    url: String,
-    abstract_text: String,
-    keywords: Vec<String>,
+    /// The `name` attribute of `<ref-type>`.
+    ref_type_name: Option<String>,
+    /// Element text by path below `<record>`, in document order.
+    fields: Vec<(String, String)>,
+    /// Indices of fields that have been mapped to CSL variables.
+    used: HashSet<usize>,
 }
 
 impl EndNoteEntry {
-    fn new() -> Self {
-        Self {
-            ref_type: String::new(),
-            key: String::new(),
-            title: String::new(),
-            authors: Vec::new(),
-            journal: String::new(),
-            year: String::new(),
-            volume: String::new(),
-            number: String::new(),
-            pages: String::new(),
-            publisher: String::new(),
-            isbn: String::new(),
-            doi: String::new(),
-            url: String::new(),
-            abstract_text: String::new(),
-            keywords: Vec::new(),
-        }
+    fn add_field(&mut self, path: String, value: &str) {
+        self.fields.push((path, value.to_string()));
     }
 
-    fn into_node(self) -> Node {
-        let cite_key = if self.key.is_empty() {
-            self.generate_cite_key()
-        } else {
-            self.key.clone()
-        };
-
-        let bibtex_type = endnote_type_to_bibtex(&self.ref_type);
-
-        // Create the term (citation key)
-        let term = Node::new(node::DEFINITION_TERM)
-            .child(Node::new(node::CODE).prop(prop::CONTENT, cite_key.clone()));
-
-        // Build the description content
-        let mut desc_children = Vec::new();
-
-        // Entry type badge
-        let type_text = format!("[{}] ", bibtex_type);
-        desc_children.push(
-            Node::new(node::SPAN)
-                .prop("html:class", "endnote-type")
-                .child(Node::new(node::TEXT).prop(prop::CONTENT, type_text)),
-        );
-
-        // Authors
-        if !self.authors.is_empty() {
-            let author_text = self.authors.join("; ");
-            desc_children.push(
-                Node::new(node::STRONG)
-                    .child(Node::new(node::TEXT).prop(prop::CONTENT, author_text)),
-            );
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
+    /// Take all values of `path`.
+    fn take_all(&mut self, path: &str) -> Vec<String> {
+        let mut values = Vec::new();
+        for (i, (p, value)) in self.fields.iter().enumerate() {
+            if p == path && !self.used.contains(&i) {
+                self.used.insert(i);
+                values.push(value.clone());
+            }
         }
+        values
+    }
 
-        // Title
-        if !self.title.is_empty() {
-            desc_children.push(
-                Node::new(node::EMPHASIS)
-                    .child(Node::new(node::TEXT).prop(prop::CONTENT, self.title.clone())),
-            );
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
+    /// Set `key` from the first value of the first of `paths` that is
+    /// present. Later values that repeat it are dropped; others stay custom.
+    fn take_into(&mut self, node: &mut Node, key: &str, paths: &[&str]) {
+        for (i, (path, value)) in self.fields.iter().enumerate() {
+            if !paths.contains(&path.as_str()) || self.used.contains(&i) {
+                continue;
+            }
+            match node.props.get_str(key) {
+                None => node.props.set(key, value.clone()),
+                Some(existing) if existing != value => continue,
+                Some(_) => {}
+            }
+            self.used.insert(i);
         }
+    }
 
-        // Journal
-        if !self.journal.is_empty() {
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, self.journal.clone()));
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
+    fn into_node(mut self) -> Node {
+        let ref_type = self.take_all("ref-type").into_iter().next();
+        let row = REF_TYPES.iter().find(|(name, number, _)| {
+            let matches = |t: &str| t.eq_ignore_ascii_case(name) || t == number.to_string();
+            self.ref_type_name.as_deref().is_some_and(matches)
+                || ref_type.as_deref().is_some_and(matches)
+        });
+        let csl_type = row.map_or("document", |(_, _, csl)| csl);
+
+        let mut node = Node::new(rescribe_bib::node::ENTRY).prop(prop::TYPE, csl_type);
+        let mut custom = HashMap::new();
+        match row {
+            // Keep types the writer would not restore
+            Some((name, _, _)) => {
+                if REF_TYPES.iter().find(|(_, _, csl)| *csl == csl_type) != row {
+                    custom.insert("endnote:ref-type".to_string(), PropValue::from(*name));
+                }
+            }
+            None => {
+                if let Some(name) = self.ref_type_name.clone().or(ref_type) {
+                    custom.insert("endnote:ref-type".to_string(), PropValue::from(name));
+                }
+            }
         }
 
-        // Volume and number
-        if !self.volume.is_empty() {
-            let vol_text = if !self.number.is_empty() {
-                format!("{}({}).", self.volume, self.number)
-            } else {
-                format!("{}.", self.volume)
-            };
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, vol_text));
+        let names = [
+            (prop::AUTHOR, "contributors/authors/author"),
+            (prop::EDITOR, "contributors/secondary-authors/author"),
+            (
+                prop::COLLECTION_EDITOR,
+                "contributors/tertiary-authors/author",
+            ),
+            (prop::TRANSLATOR, "contributors/translated-authors/author"),
+        ];
+        for (key, path) in names {
+            let names: Vec<Name> = self.take_all(path).iter().map(|n| parse_name(n)).collect();
+            if !names.is_empty() {
+                node.props.set(key, Name::list(names));
+            }
         }
 
-        // Year
-        if !self.year.is_empty() {
-            desc_children
-                .push(Node::new(node::TEXT).prop(prop::CONTENT, format!(" ({})", self.year)));
+        let texts = [
+            (prop::TITLE, &["titles/title"][..]),
+            (
+                prop::CONTAINER_TITLE,
+                &["titles/secondary-title", "periodical/full-title"],
+            ),
+            (
+                prop::CONTAINER_TITLE_SHORT,
+                &["titles/alt-title", "periodical/abbr-1"],
+            ),
+            (prop::COLLECTION_TITLE, &["titles/tertiary-title"]),
+            (prop::TITLE_SHORT, &["titles/short-title"]),
+            (prop::PAGE, &["pages"]),
+            (prop::VOLUME, &["volume"]),
+            (prop::ISSUE, &["number"]),
+            (prop::EDITION, &["edition"]),
+            (prop::NUMBER_OF_VOLUMES, &["num-vols"]),
+            (prop::PUBLISHER, &["publisher"]),
+            (prop::PUBLISHER_PLACE, &["pub-location"]),
+            (prop::DOI, &["electronic-resource-num"]),
+            (prop::URL, &["urls/related-urls/url", "urls/web-urls/url"]),
+            (prop::ABSTRACT, &["abstract"]),
+            (prop::NOTE, &["notes"]),
+            (prop::LANGUAGE, &["language"]),
+            (prop::CALL_NUMBER, &["call-num"]),
+            (prop::GENRE, &["work-type"]),
+            (prop::ID, &["label"]),
+        ];
+        for (key, paths) in texts {
+            self.take_into(&mut node, key, paths);
         }
 
-        // Pages
-        if !self.pages.is_empty() {
-            desc_children
-                .push(Node::new(node::TEXT).prop(prop::CONTENT, format!(", pp. {}", self.pages)));
+        // `<isbn>` holds ISBNs for books and ISSNs for serials.
+        let (first, second) = match csl_type {
+            "book" | "chapter" | "entry-dictionary" | "entry-encyclopedia" | "pamphlet" => {
+                (prop::ISBN, prop::ISSN)
+            }
+            _ => (prop::ISSN, prop::ISBN),
+        };
+        self.take_into(&mut node, first, &["isbn"]);
+        self.take_into(&mut node, second, &["isbn"]);
+
+        let keywords = self.take_all("keywords/keyword");
+        if !keywords.is_empty() {
+            node.props.set(prop::KEYWORD, keywords.join(", "));
         }
 
-        // DOI
-        if !self.doi.is_empty() {
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
-            desc_children.push(
-                Node::new(node::LINK)
-                    .prop(prop::URL, format!("https://doi.org/{}", self.doi))
-                    .child(Node::new(node::TEXT).prop(prop::CONTENT, format!("doi:{}", self.doi))),
-            );
+        // `<year>` holds the year, `<pub-dates>` the full date in any form.
+        let year = self.take_all("dates/year").into_iter().next();
+        let full = self
+            .fields
+            .iter()
+            .position(|(path, _)| path == "dates/pub-dates/date")
+            .and_then(|i| {
+                let date = Date::parse(&self.fields[i].1)?;
+                let same_year = year
+                    .as_deref()
+                    .is_none_or(|y| date.year().is_some_and(|year| year.to_string() == y));
+                same_year.then(|| {
+                    self.used.insert(i);
+                    date
+                })
+            });
+        let issued = full.or_else(|| {
+            year.map(|y| match y.parse() {
+                Ok(year) => Date::new(DateParts::new(year, None, None)),
+                Err(_) => Date::literal(y),
+            })
+        });
+        if let Some(issued) = issued {
+            node.props.set(prop::ISSUED, issued);
+        }
+        if let Some(accessed) = self.take_all("access-date").into_iter().next() {
+            let date = Date::parse(&accessed).unwrap_or_else(|| Date::literal(accessed));
+            node.props.set(prop::ACCESSED, date);
         }
 
-        // URL
-        if !self.url.is_empty() {
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
-            desc_children.push(
-                Node::new(node::LINK)
-                    .prop(prop::URL, self.url.clone())
-                    .child(Node::new(node::TEXT).prop(prop::CONTENT, self.url.clone())),
-            );
+        if node.props.get(prop::ID).is_none() {
+            node.props.set(prop::ID, cite_key(&node));
         }
 
-        let desc = Node::new(node::DEFINITION_DESC)
-            .prop("endnote:type", self.ref_type)
-            .prop("endnote:key", cite_key)
-            .child(Node::new(node::PARAGRAPH).children(desc_children));
+        for (i, (path, value)) in self.fields.iter().enumerate() {
+            if self.used.contains(&i) || IGNORED.contains(&path.as_str()) {
+                continue;
+            }
+            custom
+                .entry(format!("endnote:{path}"))
+                .and_modify(|existing: &mut PropValue| {
+                    if let PropValue::String(s) = existing {
+                        s.push('\n');
+                        s.push_str(value);
+                    }
+                })
+                .or_insert_with(|| PropValue::from(value.as_str()));
+        }
+        if !custom.is_empty() {
+            node.props.set(prop::CUSTOM, PropValue::Map(custom));
+        }
 
-        Node::new("endnote:entry").children(vec![term, desc])
+        node
     }
+}
 
-    fn generate_cite_key(&self) -> String {
-        let author_part = self
-            .authors
-            .first()
-            .map(|a| {
-                a.split(',')
-                    .next()
-                    .unwrap_or(a)
-                    .chars()
-                    .filter(|c| c.is_alphanumeric())
-                    .take(8)
-                    .collect::<String>()
-                    .to_lowercase()
-            })
-            .unwrap_or_else(|| "unknown".to_string());
-
-        format!("{}{}", author_part, self.year)
-    }
+/// Generate a citation key from the first author's family name and the year.
+fn cite_key(node: &Node) -> String {
+    let author_part = node
+        .props
+        .get(prop::AUTHOR)
+        .map(Name::from_list)
+        .and_then(|names| names.into_iter().next())
+        .and_then(|name| name.family.or(name.literal))
+        .map(|family| {
+            family
+                .chars()
+                .filter(|c| c.is_alphanumeric())
+                .take(8)
+                .collect::<String>()
+                .to_lowercase()
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let year_part = node
+        .props
+        .get(prop::ISSUED)
+        .and_then(Date::from_prop)
+        .and_then(|date| date.year())
+        .map(|year| year.to_string())
+        .unwrap_or_default();
+
+    format!("{author_part}{year_part}")
 }
 
-/// Map EndNote reference types to BibTeX types.
-fn endnote_type_to_bibtex(endnote_type: &str) -> &'static str {
-    // EndNote uses numeric types, but also accepts names
-    match endnote_type.to_lowercase().as_str() {
-        "journal article" | "0" | "17" => "article",
-        "book" | "6" => "book",
-        "book section" | "5" => "incollection",
-        "conference paper" | "conference proceedings" | "10" | "47" => "inproceedings",
-        "thesis" | "32" => "phdthesis",
-        "report" | "27" => "techreport",
-        "web page" | "electronic source" | "12" | "16" => "online",
-        "computer program" | "9" => "software",
-        "dataset" | "59" => "dataset",
-        "magazine article" | "19" => "article",
-        "newspaper article" | "23" => "article",
-        "manuscript" | "36" => "unpublished",
-        "edited book" | "28" => "book",
-        "patent" | "25" => "misc",
-        _ => "misc",
+/// Parse a name written `Last, First, Suffix`. Names without a comma are
+/// taken as literals.
+fn parse_name(name: &str) -> Name {
+    let parts: Vec<&str> = name.splitn(3, ',').map(str::trim).collect();
+    match parts.as_slice() {
+        [last, given] => Name::inverted(last, given, ""),
+        [last, given, suffix] => Name::inverted(last, given, suffix),
+        _ => Name::literal(name),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rescribe_bib::Entry;
 
     #[test]
     fn test_parse_basic() {
@@ -369,7 +431,12 @@ mod tests {
 
         let result = parse(xml).unwrap();
         let doc = result.value;
-        assert!(!doc.content.children.is_empty());
+        let entry = Entry::from_node(&doc.content.children[0]).unwrap();
+        assert_eq!(entry.id(), "smith2020");
+        assert_eq!(entry.entry_type(), "article-journal");
+        assert_eq!(entry.text(prop::TITLE), Some("A Great Paper"));
+        assert_eq!(entry.text(prop::CONTAINER_TITLE), Some("Nature"));
+        assert_eq!(entry.date(prop::ISSUED).unwrap().year(), Some(2020));
     }
 
     #[test]
@@ -405,6 +472,46 @@ mod tests {
 
         let result = parse(xml).unwrap();
         let doc = result.value;
-        assert!(!doc.content.children.is_empty());
+        let entry = Entry::from_node(&doc.content.children[0]).unwrap();
+        let authors = entry.names(prop::AUTHOR);
+        assert_eq!(authors.len(), 2);
+        assert_eq!(authors[1].given.as_deref(), Some("Jane"));
+    }
+
+    #[test]
+    fn test_parse_endnote_export() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xml><records><record>
+  <database name="My.enl">My.enl</database>
+  <rec-number>4</rec-number>
+  <ref-type name="Edited Book">28</ref-type>
+  <contributors><secondary-authors>
+    <author><style face="normal" font="default" size="100%">King, Martin Luther, Jr.</style></author>
+  </secondary-authors></contributors>
+  <titles><title><style face="normal">Letters </style><style face="italic">&amp; Notes</style></title></titles>
+  <dates><year>2019</year><pub-dates><date>2019-03</date></pub-dates></dates>
+  <isbn>978-0-00-000000-0</isbn>
+  <urls><related-urls><url>https://example.org</url></related-urls><pdf-urls><url>file.pdf</url></pdf-urls></urls>
+  <keywords><keyword>art</keyword><keyword>letters</keyword></keywords>
+  <custom1>extra</custom1>
+</record></records></xml>"#;
+
+        let result = parse(xml).unwrap();
+        let doc = result.value;
+        let entry = Entry::from_node(&doc.content.children[0]).unwrap();
+        assert_eq!(entry.entry_type(), "book");
+        assert_eq!(entry.custom("endnote:ref-type"), Some("Edited Book"));
+        assert_eq!(entry.names(prop::EDITOR)[0].suffix.as_deref(), Some("Jr."));
+        assert_eq!(entry.text(prop::TITLE), Some("Letters & Notes"));
+        assert_eq!(
+            entry.date(prop::ISSUED).unwrap().start,
+            Some(DateParts::new(2019, Some(3), None))
+        );
+        assert_eq!(entry.text(prop::ISBN), Some("978-0-00-000000-0"));
+        assert_eq!(entry.text(prop::URL), Some("https://example.org"));
+        assert_eq!(entry.text(prop::KEYWORD), Some("art, letters"));
+        assert_eq!(entry.custom("endnote:urls/pdf-urls/url"), Some("file.pdf"));
+        assert_eq!(entry.custom("endnote:custom1"), Some("extra"));
+        assert_eq!(entry.custom("endnote:database"), None);
     }
 }
@@ -153,6 +153,11 @@ fn convert_element(
     options: &ParseOptions,
 ) -> Vec<Node> {
     let tag = name.local.as_ref();
+    if tag == "math"
+        && let Some(math) = convert_math(handle, warnings)
+    {
+        return vec![math];
+    }
     let children = convert_children(handle, warnings, resources, options);
 
     let node = match tag {
@@ -348,6 +353,65 @@ fn convert_element(
     vec![node]
 }
 
+/// Convert a MathML `<math>` element, written back out as XML, into a math
+/// node. MathML that cannot be read is left to be converted as HTML, with a
+/// warning.
+fn convert_math(handle: &Handle, warnings: &mut Vec<FidelityWarning>) -> Option<Node> {
+    let mut xml = String::new();
+    write_xml(handle, &mut xml);
+    match rescribe_math::mathml::parse(&xml) {
+        Ok(math) => Some(math),
+        Err(e) => {
+            warnings.push(FidelityWarning::new(
+                Severity::Minor,
+                WarningKind::FeatureLost("math".to_string()),
+                e.to_string(),
+            ));
+            None
+        }
+    }
+}
+
+/// Write a DOM subtree as XML.
+fn write_xml(handle: &Handle, out: &mut String) {
+    match &handle.data {
+        NodeData::Text { contents } => out.push_str(&escape_xml(&contents.borrow())),
+        NodeData::Element { name, attrs, .. } => {
+            out.push('<');
+            out.push_str(&name.local);
+            for attr in attrs.borrow().iter() {
+                out.push(' ');
+                out.push_str(&attr.name.local);
+                out.push_str("=\"");
+                out.push_str(&escape_xml(&attr.value));
+                out.push('"');
+            }
+            out.push('>');
+            for child in handle.children.borrow().iter() {
+                write_xml(child, out);
+            }
+            out.push_str("</");
+            out.push_str(&name.local);
+            out.push('>');
+        }
+        _ => {}
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
 /// Get an attribute value by name.
 fn get_attr(attrs: &[Attribute], name: &str) -> Option<String> {
     attrs
@@ -433,6 +433,27 @@ mod tests {
         assert_eq!(resource.mime_type, "image/png");
     }
 
+    #[test]
+    fn test_parse_mathml() {
+        let result = parse(
+            r#"<p>Let <math><msup><mi>x</mi><mn>2</mn></msup></math>.</p><math display="block"><mfrac><mi>a</mi><mi>b</mi></mfrac></math>"#,
+        )
+        .unwrap();
+        assert!(result.warnings.is_empty());
+        let children = root_children(&result.value);
+
+        let inline = &children[0].children[1];
+        assert_eq!(inline.kind.as_str(), "math_inline");
+        assert_eq!(inline.children[0].kind.as_str(), "math:sup");
+        assert_eq!(inline.props.get_str("math:source"), Some("x^2"));
+
+        assert_eq!(children[1].kind.as_str(), "math_display");
+        assert_eq!(
+            children[1].props.get_str("math:source"),
+            Some(r"\frac{a}{b}")
+        );
+    }
+
     #[test]
     #[cfg(feature = "html5ever")]
     fn test_data_uri_roundtrip() {
@@ -118,7 +118,12 @@ impl<'a> Converter<'a> {
                 )]
             }
 
-            "element" | "script_element" | "style_element" => self.convert_element(tsnode),
+            "element" => match self.convert_math(tsnode) {
+                Some(math) => vec![math],
+                None => self.convert_element(tsnode),
+            },
+
+            "script_element" | "style_element" => self.convert_element(tsnode),
 
             "self_closing_tag" => self.convert_self_closing(tsnode),
 
@@ -155,6 +160,29 @@ impl<'a> Converter<'a> {
         self.create_element(&tag_name, &attrs, content_children, tsnode)
     }
 
+    /// Convert a MathML `<math>` element, from its source, into a math node.
+    /// Other elements, and MathML that cannot be read, are left to
+    /// [`Self::convert_element`], the latter with a warning.
+    fn convert_math(&mut self, tsnode: &tree_sitter::Node) -> Option<Node> {
+        let start_tag = tsnode.child(0).filter(|c| c.kind() == "start_tag")?;
+        let mut tag_name = String::new();
+        self.parse_tag(&start_tag, &mut tag_name, &mut Vec::new());
+        if tag_name != "math" {
+            return None;
+        }
+        match rescribe_math::mathml::parse(self.node_text(tsnode)) {
+            Ok(math) => Some(self.with_span(math, tsnode)),
+            Err(e) => {
+                self.warnings.push(FidelityWarning::new(
+                    Severity::Minor,
+                    WarningKind::FeatureLost("math".to_string()),
+                    e.to_string(),
+                ));
+                None
+            }
+        }
+    }
+
     fn convert_self_closing(&mut self, tsnode: &tree_sitter::Node) -> Vec<Node> {
         let mut tag_name = String::new();
         let mut attrs = Vec::new();
@@ -5,8 +5,11 @@
 
 use quick_xml::Reader;
 use quick_xml::events::{BytesStart, Event};
-use rescribe_core::{ConversionResult, Document, FidelityWarning, Node, ParseError, Properties};
-use rescribe_std::{node, prop};
+use rescribe_core::{
+    ConversionResult, Document, FidelityWarning, Node, ParseError, Properties, Severity,
+    WarningKind,
+};
+use rescribe_std::{Citation, node, prop};
 
 /// Parse JATS XML into a document.
 pub fn parse(input: &str) -> Result<ConversionResult<Document>, ParseError> {
@@ -14,7 +17,7 @@ pub fn parse(input: &str) -> Result<ConversionResult<Document>, ParseError> {
     reader.config_mut().trim_text(true);
 
     let mut converter = Converter::new();
-    converter.parse(&mut reader)?;
+    converter.parse(input, &mut reader)?;
 
     let document = Document {
         content: Node::new(node::DOCUMENT).children(converter.result),
@@ -48,6 +51,7 @@ struct StackFrame {
 struct FrameAttrs {
     id: Option<String>,
     rid: Option<String>,
+    ref_type: Option<String>,
     href: Option<String>,
     specific_use: Option<String>,
     content_type: Option<String>,
@@ -65,11 +69,21 @@ impl Converter {
         }
     }
 
-    fn parse(&mut self, reader: &mut Reader<&[u8]>) -> Result<(), ParseError> {
+    fn parse(&mut self, input: &str, reader: &mut Reader<&[u8]>) -> Result<(), ParseError> {
         let mut buf = Vec::new();
 
         loop {
+            let start = reader.buffer_position() as usize;
             match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) if e.local_name().as_ref() == b"math" => {
+                    // MathML is read as a whole by rescribe-math
+                    self.flush_text();
+                    reader
+                        .read_to_end(e.name())
+                        .map_err(|e| ParseError::Invalid(format!("XML parse error: {}", e)))?;
+                    let end = reader.buffer_position() as usize;
+                    self.handle_math(&input[start..end]);
+                }
                 Ok(Event::Start(e)) => {
                     self.flush_text();
                     self.handle_start(&e)?;
@@ -128,6 +142,7 @@ impl Converter {
             match key.as_str() {
                 "id" => attrs.id = Some(value),
                 "rid" => attrs.rid = Some(value),
+                "ref-type" => attrs.ref_type = Some(value),
                 "href" | "xlink:href" => attrs.href = Some(value),
                 "specific-use" => attrs.specific_use = Some(value),
                 "content-type" => attrs.content_type = Some(value),
@@ -172,6 +187,10 @@ impl Converter {
                     }
                 }
                 rid.map(|r| {
+                    if ref_type.as_deref() == Some("bibr") {
+                        let text = Node::new(node::TEXT).prop(prop::CONTENT, r.clone());
+                        return bibliography_cite(&r, vec![text]);
+                    }
                     let mut n = Node::new(node::LINK)
                         .prop(prop::URL, format!("#{}", r.clone()))
                         .child(Node::new(node::TEXT).prop(prop::CONTENT, r));
@@ -196,6 +215,20 @@ impl Converter {
         Ok(())
     }
 
+    fn handle_math(&mut self, xml: &str) {
+        match rescribe_math::mathml::parse(xml.trim_start()) {
+            Ok(math) => match self.stack.last_mut() {
+                Some(frame) => frame.children.push(math),
+                None => self.result.push(math),
+            },
+            Err(e) => self.warnings.push(FidelityWarning::new(
+                Severity::Minor,
+                WarningKind::FeatureLost("math".to_string()),
+                e.to_string(),
+            )),
+        }
+    }
+
     fn handle_end(&mut self, e: &quick_xml::events::BytesEnd<'_>) -> Result<(), ParseError> {
         let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
 
@@ -326,6 +359,10 @@ impl Converter {
                 }
                 Some(node)
             }
+            "xref" if frame.attrs.ref_type.as_deref() == Some("bibr") => {
+                let rid = frame.attrs.rid.as_deref().unwrap_or("");
+                Some(bibliography_cite(rid, frame.children.clone()))
+            }
             "xref" => {
                 let mut node = Node::new(node::LINK).children(frame.children.clone());
                 if let Some(rid) = &frame.attrs.rid {
@@ -367,15 +404,9 @@ impl Converter {
             "td" => Some(Node::new(node::TABLE_CELL).children(frame.children.clone())),
 
             // Math
-            "disp-formula" => {
-                let text = extract_text(&frame.children);
-                Some(Node::new("math_display").prop("math:source", text))
-            }
-            "inline-formula" => {
-                let text = extract_text(&frame.children);
-                Some(Node::new("math_inline").prop("math:source", text))
-            }
-            "tex-math" | "mml:math" => {
+            "disp-formula" => Some(formula("math_display", &frame.children)),
+            "inline-formula" => Some(formula("math_inline", &frame.children)),
+            "tex-math" => {
                 // Already captured by parent formula element
                 None
             }
@@ -435,6 +466,36 @@ fn extract_text(nodes: &[Node]) -> String {
     text
 }
 
+/// A `kind` math node for a formula: the MathML among `children` if there is
+/// any, with the `<tex-math>` text as its source if there is that.
+fn formula(kind: &str, children: &[Node]) -> Node {
+    let is_math = |n: &&Node| matches!(n.kind.as_str(), "math_inline" | "math_display");
+    let mut math = match children.iter().find(is_math) {
+        Some(mathml) => Node {
+            kind: kind.into(),
+            ..mathml.clone()
+        },
+        None => Node::new(kind),
+    };
+    let tex: Vec<Node> = children.iter().filter(|n| !is_math(n)).cloned().collect();
+    let text = extract_text(&tex);
+    if !text.is_empty() || math.children.is_empty() {
+        math = math.prop("math:source", text);
+    }
+    math
+}
+
+/// A citation of the bibliography entries `rid`, which lists IDs separated
+/// by spaces.
+fn bibliography_cite(rid: &str, children: Vec<Node>) -> Node {
+    Node::new(node::CITE)
+        .prop(
+            prop::CITATIONS,
+            Citation::list(rid.split_whitespace().map(Citation::new)),
+        )
+        .children(children)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -530,4 +591,46 @@ mod tests {
         let doc = result.value;
         assert!(!doc.content.children.is_empty());
     }
+
+    #[test]
+    fn test_parse_bibr_xref() {
+        use rescribe_std::NodeExt;
+
+        let jats = r#"<article><body>
+  <p>As shown <xref ref-type="bibr" rid="doe99 roe">(Doe 1999; Roe)</xref>.</p>
+</body></article>"#;
+        let doc = parse(jats).unwrap().value;
+        let para = &doc.content.children[0].children[0];
+        let cite = para.children.iter().find_map(|n| n.as_cite()).unwrap();
+        let ids: Vec<_> = cite.citations().into_iter().map(|c| c.id).collect();
+        assert_eq!(ids, ["doe99", "roe"]);
+        assert_eq!(
+            cite.content()[0].props.get_str(prop::CONTENT),
+            Some("(Doe 1999; Roe)")
+        );
+    }
+
+    #[test]
+    fn test_parse_formulas() {
+        let xml = r#"<article xmlns:mml="http://www.w3.org/1998/Math/MathML"><body>
+            <p>Let <inline-formula><mml:math><mml:msup><mml:mi>x</mml:mi><mml:mn>2</mml:mn></mml:msup></mml:math></inline-formula>.</p>
+            <disp-formula><alternatives><tex-math>a + b</tex-math><mml:math display="block"><mml:mi>a</mml:mi><mml:mo>+</mml:mo><mml:mi>b</mml:mi></mml:math></alternatives></disp-formula>
+            <disp-formula><tex-math>\sqrt{2}</tex-math></disp-formula>
+        </body></article>"#;
+        let result = parse(xml).unwrap();
+        assert!(result.warnings.is_empty());
+        let body = &result.value.content.children[0].children;
+
+        let inline = &body[0].children[1];
+        assert_eq!(inline.kind.as_str(), "math_inline");
+        assert_eq!(inline.children[0].kind.as_str(), "math:sup");
+        assert_eq!(inline.props.get_str("math:source"), Some("x^2"));
+
+        assert_eq!(body[1].kind.as_str(), "math_display");
+        assert_eq!(body[1].children.len(), 3);
+        assert_eq!(body[1].props.get_str("math:source"), Some("a + b"));
+
+        assert!(body[2].children.is_empty());
+        assert_eq!(body[2].props.get_str("math:source"), Some(r"\sqrt{2}"));
+    }
 }
@@ -0,0 +1,276 @@
+//! rescribe JSON reader.
+//!
+//! Parses "rescribe JSON", the canonical serialized form of a [`Document`]
+//! written by `rescribe-write-json`. Documents from a newer format version
+//! are rejected.
+//!
+//! Resources may be embedded as base64 (`"data"`) or stored externally and
+//! referenced by relative path (`"href"`). External resources are loaded by
+//! [`parse_file`] and [`parse_with_loader`]; plain [`parse`] leaves them empty
+//! and reports a warning.
+
+use rescribe_core::{
+    ConversionResult, Document, FidelityWarning, ParseError, ParseOptions, ResourceId, Severity,
+    WarningKind,
+};
+use serde_json::Value;
+use std::io;
+use std::path::{Component, Path};
+
+/// Parse rescribe JSON into a document.
+pub fn parse(input: &str) -> Result<ConversionResult<Document>, ParseError> {
+    parse_with_options(input, &ParseOptions::default())
+}
+
+/// Parse rescribe JSON with custom options.
+pub fn parse_with_options(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<ConversionResult<Document>, ParseError> {
+    parse_with_loader(input, options, |href| {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("external resource '{href}' needs a loader"),
+        ))
+    })
+}
+
+/// Parse a rescribe JSON file, loading external resources relative to it.
+///
+/// Only relative `href`s that stay inside the file's directory are loaded;
+/// absolute paths and `..` are reported as failed resources.
+pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<ConversionResult<Document>, ParseError> {
+    let path = path.as_ref();
+    let input = std::fs::read_to_string(path)?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    parse_with_loader(&input, &ParseOptions::default(), |href| {
+        let relative = Path::new(href);
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "path leaves the document's directory",
+            ));
+        }
+        std::fs::read(base.join(relative))
+    })
+}
+
+/// Parse rescribe JSON, resolving external resources with `loader`.
+///
+/// The loader receives each resource's `href`. Resources that fail to load
+/// are kept with empty data and reported as warnings.
+pub fn parse_with_loader(
+    input: &str,
+    _options: &ParseOptions,
+    mut loader: impl FnMut(&str) -> io::Result<Vec<u8>>,
+) -> Result<ConversionResult<Document>, ParseError> {
+    let mut value: Value =
+        serde_json::from_str(input).map_err(|e| ParseError::Invalid(e.to_string()))?;
+
+    let external = take_external_resources(&mut value);
+
+    let mut doc: Document =
+        serde_json::from_value(value).map_err(|e| ParseError::Invalid(e.to_string()))?;
+
+    let mut warnings = Vec::new();
+    for (id, href) in external {
+        let id = ResourceId::from_string(id);
+        let Some(resource) = doc.resources.get_mut(&id) else {
+            continue;
+        };
+        match loader(&href) {
+            Ok(data) => resource.data = data,
+            Err(e) => {
+                resource.metadata.set("href", href.as_str());
+                warnings.push(FidelityWarning::new(
+                    Severity::Major,
+                    WarningKind::ResourceFailed(id),
+                    format!("Could not load resource '{href}': {e}"),
+                ));
+            }
+        }
+    }
+
+    Ok(ConversionResult::with_warnings(doc, warnings))
+}
+
+/// Replace `"href"` references with empty data so the document deserializes,
+/// returning the `(id, href)` pairs to load.
+fn take_external_resources(value: &mut Value) -> Vec<(String, String)> {
+    let mut external = Vec::new();
+    let Some(Value::Object(resources)) = value.get_mut("resources") else {
+        return external;
+    };
+    for (id, entry) in resources.iter_mut() {
+        let Value::Object(entry) = entry else {
+            continue;
+        };
+        if entry.contains_key("data") {
+            continue;
+        }
+        if let Some(Value::String(href)) = entry.remove("href") {
+            entry.insert("data".to_string(), Value::String(String::new()));
+            external.push((id.clone(), href));
+        }
+    }
+    external
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rescribe_core::{EmitOptions, PropValue, Properties, Resource, SourceInfo, Span};
+    use rescribe_std::builder::doc;
+    use rescribe_std::{Node, node, prop};
+    use std::collections::HashMap;
+
+    fn roundtrip(doc: &Document) -> Document {
+        let json = rescribe_write_json::emit(doc).unwrap().value;
+        parse(std::str::from_utf8(&json).unwrap()).unwrap().value
+    }
+
+    #[test]
+    fn test_parse_minimal() {
+        let doc = parse(r#"{"version":1,"content":{"kind":"document"}}"#)
+            .unwrap()
+            .value;
+        assert_eq!(doc.content.kind.as_str(), node::DOCUMENT);
+        assert!(doc.content.children.is_empty());
+        assert!(doc.resources.is_empty());
+    }
+
+    #[test]
+    fn test_roundtrip_preserves_ir() {
+        let mut document = doc(|d| {
+            d.heading(1, |i| i.text("Title"))
+                .para(|i| i.text("Some ").strong(|i| i.text("bold")).text(" text"))
+        });
+        document.content.children[0].span = Some(Span { start: 0, end: 7 });
+        document.content.children[1].props.set("ratio", 0.5);
+        document.content.children[1].props.set(
+            "extra",
+            PropValue::Map(HashMap::from([(
+                "list".to_string(),
+                PropValue::List(vec![PropValue::Int(1), PropValue::Bool(true)]),
+            )])),
+        );
+        document.metadata.set("title", "Doc");
+        let mut source_meta = Properties::new();
+        source_meta.set("flavor", "gfm");
+        document.source = Some(SourceInfo {
+            format: "markdown".to_string(),
+            metadata: source_meta,
+        });
+        let id = document.embed(Resource::png(vec![0, 1, 2, 254, 255]).with_name("dot.png"));
+
+        let parsed = roundtrip(&document);
+
+        let heading = &parsed.content.children[0];
+        assert_eq!(heading.props.get_int(prop::LEVEL), Some(1));
+        assert_eq!(heading.span, Some(Span { start: 0, end: 7 }));
+        let para = &parsed.content.children[1];
+        assert_eq!(para.props.get("ratio"), Some(&PropValue::Float(0.5)));
+        assert_eq!(
+            para.props.get("extra"),
+            document.content.children[1].props.get("extra")
+        );
+        assert_eq!(para.children[1].kind.as_str(), node::STRONG);
+        assert_eq!(parsed.metadata.get_str("title"), Some("Doc"));
+        let resource = parsed.resource(&id).unwrap();
+        assert_eq!(resource.data, vec![0, 1, 2, 254, 255]);
+        assert_eq!(resource.name.as_deref(), Some("dot.png"));
+        let source = parsed.source.unwrap();
+        assert_eq!(source.format, "markdown");
+        assert_eq!(source.metadata.get_str("flavor"), Some("gfm"));
+    }
+
+    #[test]
+    fn test_rejects_newer_version() {
+        let err = parse(r#"{"version":999,"content":{"kind":"document"}}"#);
+        assert!(matches!(err, Err(ParseError::Invalid(msg)) if msg.contains("version 999")));
+    }
+
+    #[test]
+    fn test_rejects_pandoc_json() {
+        let err = parse(r#"{"pandoc-api-version":[1,23],"meta":{},"blocks":[]}"#);
+        assert!(matches!(err, Err(ParseError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_external_resources() {
+        let mut document = Document::new();
+        let id = document.embed(Resource::png(vec![9, 8, 7]));
+        document.content = Node::new(node::DOCUMENT);
+        let output =
+            rescribe_write_json::emit_external(&document, &EmitOptions::default(), "media")
+                .unwrap()
+                .value;
+        let json = std::str::from_utf8(&output.json).unwrap();
+        let files: HashMap<_, _> = output
+            .resources
+            .into_iter()
+            .map(|r| (r.filename, r.content))
+            .collect();
+
+        let loaded = parse_with_loader(json, &ParseOptions::default(), |href| {
+            files
+                .get(href)
+                .cloned()
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+        })
+        .unwrap();
+        assert!(loaded.warnings.is_empty());
+        assert_eq!(loaded.value.resource(&id).unwrap().data, vec![9, 8, 7]);
+
+        let unresolved = parse(json).unwrap();
+        assert_eq!(unresolved.warnings.len(), 1);
+        let resource = unresolved.value.resource(&id).unwrap();
+        assert!(resource.data.is_empty());
+        assert!(resource.metadata.get_str("href").is_some());
+    }
+
+    #[test]
+    fn test_parse_file_rejects_escaping_hrefs() {
+        let dir = std::env::temp_dir().join(format!("rescribe-json-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("doc")).unwrap();
+        std::fs::write(dir.join("secret.bin"), [1, 2, 3]).unwrap();
+        std::fs::write(dir.join("doc/ok.bin"), [4, 5]).unwrap();
+        let secret = dir.join("secret.bin");
+        let json = serde_json::json!({
+            "version": 1,
+            "content": {"kind": "document"},
+            "resources": {
+                "ok": {"mime_type": "application/octet-stream", "href": "ok.bin"},
+                "up": {"mime_type": "application/octet-stream", "href": "../secret.bin"},
+                "abs": {"mime_type": "application/octet-stream", "href": secret},
+            },
+        });
+        let path = dir.join("doc/doc.json");
+        std::fs::write(&path, json.to_string()).unwrap();
+
+        let result = parse_file(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let data = |id: &str| {
+            result
+                .value
+                .resource(&ResourceId::from_string(id))
+                .unwrap()
+                .data
+                .clone()
+        };
+        assert_eq!(data("ok"), vec![4, 5]);
+        assert!(data("up").is_empty());
+        assert!(data("abs").is_empty());
+        assert_eq!(result.warnings.len(), 2);
+        assert!(
+            result
+                .warnings
+                .iter()
+                .all(|w| matches!(w.kind, WarningKind::ResourceFailed(_)))
+        );
+    }
+}
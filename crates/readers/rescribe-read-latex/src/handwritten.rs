@@ -4,7 +4,7 @@ use rescribe_core::{
     ConversionResult, Document, FidelityWarning, ParseError, ParseOptions, Severity, Span,
     WarningKind,
 };
-use rescribe_std::{Node, node, prop};
+use rescribe_std::{Citation, CitationMode, Node, node, prop};
 
 /// Parse LaTeX text into a rescribe Document.
 pub fn parse(input: &str) -> Result<ConversionResult<Document>, ParseError> {
@@ -685,6 +685,18 @@ impl<'a> Parser<'a> {
         if self.starts_with("\\url{") {
             return Some(self.parse_url());
         }
+        if self.starts_with("\\cite")
+            || self.starts_with("\\parencite")
+            || self.starts_with("\\textcite")
+            || self.starts_with("\\autocite")
+            || self.starts_with("\\footcite")
+        {
+            let start = self.pos;
+            if let Some(cite) = self.parse_cite() {
+                return Some(cite);
+            }
+            self.pos = start;
+        }
         if self.starts_with("\\(") {
             return Some(self.parse_inline_math_paren());
         }
@@ -765,6 +777,106 @@ impl<'a> Parser<'a> {
             .children(vec![Node::new(node::TEXT).prop(prop::CONTENT, url)])
     }
 
+    /// Parse a natbib or biblatex citation command such as
+    /// `\parencite[see][p.~4]{doe99,roe}`, keeping the command as raw
+    /// LaTeX content. The multicite forms (`\parencites{a}[p.~4]{b}`) take
+    /// an optional argument pair per key group.
+    fn parse_cite(&mut self) -> Option<Node> {
+        let start = self.pos;
+        self.advance(1);
+        let name: String = self
+            .remaining()
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect();
+        self.advance(name.len());
+        let starred = self.starts_with("*");
+        if starred {
+            self.advance(1);
+        }
+
+        let (base, multi) = match CITE_COMMANDS.iter().find(|(n, _)| *n == name) {
+            Some(&(_, mode)) => (mode, false),
+            None => {
+                let single = name.strip_suffix('s')?;
+                let &(_, mode) = CITE_COMMANDS.iter().find(|(n, _)| *n == single)?;
+                (mode, true)
+            }
+        };
+        let mode = match base {
+            CitationMode::Normal if starred && !name.starts_with("cite") => {
+                CitationMode::SuppressAuthor
+            }
+            mode => mode,
+        };
+
+        let mut citations = Vec::new();
+        if multi {
+            // Global pre- and postnotes in parentheses are not citations.
+            while self.starts_with("(") {
+                self.skip_until(')');
+                self.advance(1);
+            }
+        }
+        loop {
+            let notes = self.parse_optional_args();
+            if !self.starts_with("{") {
+                break;
+            }
+            self.advance(1);
+            let keys = self.parse_until_closing_brace();
+            let (prefix, suffix) = match notes.as_slice() {
+                [post] => ("", post.as_str()),
+                [pre, post, ..] => (pre.as_str(), post.as_str()),
+                [] => ("", ""),
+            };
+            let mut group: Vec<Citation> = keys
+                .split(',')
+                .map(str::trim)
+                .filter(|k| !k.is_empty())
+                .map(|k| Citation::new(k).with_mode(mode))
+                .collect();
+            if let Some(first) = group.first_mut() {
+                *first = std::mem::take(first).with_prefix(&latex_note(prefix));
+            }
+            if let Some(last) = group.last_mut() {
+                *last = std::mem::take(last).with_suffix(&latex_note(suffix));
+            }
+            citations.append(&mut group);
+            if !multi {
+                break;
+            }
+        }
+        if citations.is_empty() {
+            return None;
+        }
+
+        let source = &self.input[start..self.pos];
+        Some(
+            self.with_span(
+                Node::new(node::CITE)
+                    .prop(prop::CITATIONS, Citation::list(citations))
+                    .child(
+                        Node::new(node::RAW_INLINE)
+                            .prop(prop::FORMAT, "latex")
+                            .prop(prop::CONTENT, source),
+                    ),
+                start,
+            ),
+        )
+    }
+
+    /// Parse consecutive `[...]` arguments.
+    fn parse_optional_args(&mut self) -> Vec<String> {
+        let mut args = Vec::new();
+        while self.starts_with("[") {
+            self.advance(1);
+            args.push(self.parse_until(']'));
+            self.advance(1);
+        }
+        args
+    }
+
     fn parse_inline_math(&mut self) -> Node {
         self.advance(1); // skip $
         let mut content = String::new();
@@ -870,6 +982,25 @@ impl<'a> Parser<'a> {
     }
 }
 
+/// Citation commands of natbib and biblatex and the citation mode they
+/// give; the starred biblatex forms suppress the author.
+const CITE_COMMANDS: &[(&str, CitationMode)] = &[
+    ("cite", CitationMode::Normal),
+    ("citep", CitationMode::Normal),
+    ("parencite", CitationMode::Normal),
+    ("autocite", CitationMode::Normal),
+    ("footcite", CitationMode::Normal),
+    ("textcite", CitationMode::AuthorInText),
+    ("citet", CitationMode::AuthorInText),
+    ("citeyear", CitationMode::SuppressAuthor),
+    ("citeyearpar", CitationMode::SuppressAuthor),
+];
+
+/// Plain text of a pre- or postnote: `~` is a space and braces are dropped.
+fn latex_note(note: &str) -> String {
+    note.replace('~', " ").replace(['{', '}'], "")
+}
+
 /// Merge adjacent text nodes.
 fn merge_text_nodes(nodes: &mut Vec<Node>) {
     let mut i = 0;
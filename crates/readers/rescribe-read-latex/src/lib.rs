@@ -138,4 +138,33 @@ fn main() {
         assert_eq!(code.kind.as_str(), node::CODE_BLOCK);
         assert!(code.props.get_str(prop::CONTENT).is_some());
     }
+
+    #[test]
+    #[cfg(feature = "handwritten")]
+    fn test_parse_citations() {
+        use rescribe_std::{CitationMode, NodeExt};
+
+        let input = r"As \textcite[12]{doe99} and \parencite[see][chap.~2]{roe, poe} show, \parencite*{doe99}.";
+        let doc = parse(input).unwrap().value;
+        let para = &root_children(&doc)[0];
+        let cites: Vec<_> = para.children.iter().filter_map(|n| n.as_cite()).collect();
+        assert_eq!(cites.len(), 3);
+
+        let doe = &cites[0].citations()[0];
+        assert_eq!(doe.mode, CitationMode::AuthorInText);
+        assert_eq!(
+            (doe.label.as_deref(), doe.locator.as_deref()),
+            (Some("page"), Some("12"))
+        );
+        assert_eq!(
+            cites[0].content()[0].props.get_str(prop::CONTENT),
+            Some(r"\textcite[12]{doe99}")
+        );
+
+        let group = cites[1].citations();
+        assert_eq!(group.len(), 2);
+        assert_eq!(group[0].prefix.as_deref(), Some("see"));
+        assert_eq!(group[1].label.as_deref(), Some("chapter"));
+        assert_eq!(cites[2].citations()[0].mode, CitationMode::SuppressAuthor);
+    }
 }
@@ -0,0 +1,396 @@
+//! Pandoc citation syntax, shared by both backends.
+//!
+//! Citations are found in the text after parsing: `[see @doe, p. 4; -@roe]`
+//! is a bracketed group of citations, `@doe` an author-in-text citation,
+//! optionally followed by a bracketed locator as in `@doe [p. 4]`.
+
+use rescribe_core::Span;
+use rescribe_std::{Citation, CitationMode, Node, node, prop};
+use std::ops::Range;
+
+/// Replace citations in the text of `root` and its descendants with `cite`
+/// nodes.
+pub(crate) fn parse_citations(root: &mut Node) {
+    if matches!(
+        root.kind.as_str(),
+        node::CODE
+            | node::CODE_BLOCK
+            | node::RAW_INLINE
+            | node::RAW_BLOCK
+            | node::LINK
+            | node::IMAGE
+            | node::CITE
+            | "math_inline"
+            | "math_display"
+    ) {
+        return;
+    }
+    for child in &mut root.children {
+        parse_citations(child);
+    }
+    if !root.children.iter().any(|c| {
+        c.kind.as_str() == node::TEXT
+            && c.props
+                .get_str(prop::CONTENT)
+                .is_some_and(|t| t.contains('@'))
+    }) {
+        return;
+    }
+
+    let mut children = Vec::with_capacity(root.children.len());
+    let mut run: Vec<Node> = Vec::new();
+    for child in std::mem::take(&mut root.children) {
+        if child.kind.as_str() == node::TEXT {
+            run.push(child);
+        } else {
+            flush_run(&mut run, &mut children);
+            children.push(child);
+        }
+    }
+    flush_run(&mut run, &mut children);
+    root.children = children;
+}
+
+/// Split a run of text nodes at citations. Runs without citations are kept
+/// as they are; the pieces of a split run keep the source spans they came
+/// from.
+fn flush_run(run: &mut Vec<Node>, out: &mut Vec<Node>) {
+    let text: String = run
+        .iter()
+        .filter_map(|n| n.props.get_str(prop::CONTENT))
+        .collect();
+    let cites = find_citations(&text);
+    if cites.is_empty() {
+        out.append(run);
+        return;
+    }
+    let mut pieces = Vec::with_capacity(run.len());
+    let mut start = 0;
+    for n in run.drain(..) {
+        let len = n.props.get_str(prop::CONTENT).map_or(0, str::len);
+        pieces.push((start..start + len, n.span));
+        start += len;
+    }
+    let text_node = |range: Range<usize>| {
+        with_span(
+            Node::new(node::TEXT).prop(prop::CONTENT, &text[range.clone()]),
+            source_span(&pieces, range),
+        )
+    };
+
+    let mut pos = 0;
+    for (range, citations) in cites {
+        if range.start > pos {
+            out.push(text_node(pos..range.start));
+        }
+        out.push(with_span(
+            Node::new(node::CITE)
+                .prop(prop::CITATIONS, Citation::list(citations))
+                .child(text_node(range.clone())),
+            source_span(&pieces, range.clone()),
+        ));
+        pos = range.end;
+    }
+    if pos < text.len() {
+        out.push(text_node(pos..text.len()));
+    }
+}
+
+/// The source span of `range` of a run's text, given the range and span of
+/// each of its text nodes. Where `range` ends inside a node whose text is its
+/// source, the span ends there too; otherwise it takes in the whole node.
+fn source_span(pieces: &[(Range<usize>, Option<Span>)], range: Range<usize>) -> Option<Span> {
+    let mut touched = pieces
+        .iter()
+        .filter(|(r, _)| r.start < range.end && range.start < r.end);
+    let first = touched.next()?;
+    let last = touched.next_back().unwrap_or(first);
+    let (first, first_span) = (&first.0, first.1?);
+    let (last, last_span) = (&last.0, last.1?);
+    let verbatim = |r: &Range<usize>, span: Span| span.end - span.start == r.len();
+    Some(Span {
+        start: if verbatim(first, first_span) {
+            first_span.start + range.start.max(first.start) - first.start
+        } else {
+            first_span.start
+        },
+        end: if verbatim(last, last_span) {
+            last_span.start + range.end.min(last.end) - last.start
+        } else {
+            last_span.end
+        },
+    })
+}
+
+/// `node` with its span set to `span`.
+fn with_span(mut node: Node, span: Option<Span>) -> Node {
+    node.span = span;
+    node
+}
+
+/// Citations in `text` with their byte ranges.
+fn find_citations(text: &str) -> Vec<(Range<usize>, Vec<Citation>)> {
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        let at_word_start = text[..i]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric());
+        if rest.starts_with('[')
+            && let Some((len, citations)) = bracketed(rest)
+        {
+            found.push((i..i + len, citations));
+            i += len;
+        } else if rest.starts_with('@')
+            && at_word_start
+            && let Some((len, citation)) = in_text(rest)
+        {
+            found.push((i..i + len, vec![citation]));
+            i += len;
+        } else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    found
+}
+
+/// Whether `text` is exactly one bracketed citation group.
+#[cfg(feature = "tree-sitter")]
+pub(crate) fn is_bracketed(text: &str) -> bool {
+    bracketed(text).is_some_and(|(len, _)| len == text.len())
+}
+
+/// Whether `text` ends with an author-in-text citation and a space, so
+/// that a following `[...]` is its locator.
+#[cfg(feature = "tree-sitter")]
+pub(crate) fn ends_with_in_text(text: &str) -> bool {
+    let Some(text) = text.strip_suffix(' ') else {
+        return false;
+    };
+    text.rfind('@').is_some_and(|at| {
+        text[..at]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric())
+            && key(&text[at + 1..]).is_some_and(|(_, len)| at + 1 + len == text.len())
+    })
+}
+
+/// `[prefix @key suffix; ...]` at the start of `text`.
+fn bracketed(text: &str) -> Option<(usize, Vec<Citation>)> {
+    let end = closing_bracket(text)?;
+    // `[@key](url)` and `[@key][ref]` are links.
+    if text[end + 1..].starts_with(['(', '[']) {
+        return None;
+    }
+    let citations = split_top_level(&text[1..end])
+        .into_iter()
+        .map(citation)
+        .collect::<Option<Vec<_>>>()?;
+    Some((end + 1, citations))
+}
+
+/// One citation of a bracketed group.
+fn citation(part: &str) -> Option<Citation> {
+    let mut search = 0;
+    let at = loop {
+        let at = search + part[search..].find('@')?;
+        let before = part[..at].chars().next_back();
+        if before.is_none_or(|c| c.is_whitespace() || c == '-') {
+            break at;
+        }
+        search = at + 1;
+    };
+    let (id, len) = key(&part[at + 1..])?;
+    let suppress = part[..at].ends_with('-');
+    let prefix = &part[..if suppress { at - 1 } else { at }];
+    let mode = if suppress {
+        CitationMode::SuppressAuthor
+    } else {
+        CitationMode::Normal
+    };
+    Some(
+        Citation::new(id)
+            .with_prefix(prefix)
+            .with_suffix(&part[at + 1 + len..])
+            .with_mode(mode),
+    )
+}
+
+/// `@key` at the start of `text`, with an optional ` [locator]`.
+fn in_text(text: &str) -> Option<(usize, Citation)> {
+    let (id, len) = key(&text[1..])?;
+    let mut citation = Citation::new(id).with_mode(CitationMode::AuthorInText);
+    let mut end = 1 + len;
+    let after = &text[end..];
+    if let Some(locator) = after.strip_prefix(" [")
+        && let Some(close) = closing_bracket(&after[1..])
+        && !locator[..close - 1].contains('@')
+        && !after[close + 2..].starts_with(['(', '['])
+    {
+        citation = citation.with_suffix(&locator[..close - 1]);
+        end += close + 2;
+    }
+    Some((end, citation))
+}
+
+/// A citation key at the start of `text` and its length in bytes.
+///
+/// Keys start with a letter, digit or `_` and may contain punctuation
+/// between such characters; `{...}` allows any key.
+fn key(text: &str) -> Option<(&str, usize)> {
+    if let Some(braced) = text.strip_prefix('{') {
+        let close = braced.find('}')?;
+        return (close > 0).then(|| (&braced[..close], close + 2));
+    }
+    let word = |c: char| c.is_alphanumeric() || c == '_';
+    if !text.starts_with(word) {
+        return None;
+    }
+    let mut end = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if word(c) {
+            end = i + c.len_utf8();
+        } else if ":.#$%&-+?<>~/".contains(c) && chars.peek().is_some_and(|&(_, next)| word(next)) {
+            continue;
+        } else {
+            break;
+        }
+    }
+    Some((&text[..end], end))
+}
+
+/// Byte index of the `]` closing the `[` at the start of `text`.
+fn closing_bracket(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '[' | '{' => depth += 1,
+            ']' | '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return (c == ']').then_some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split on `;` outside braces.
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth -= 1,
+            ';' if depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rescribe_std::NodeExt;
+
+    fn cites(text: &str) -> Vec<(String, Vec<Citation>)> {
+        let mut para =
+            Node::new(node::PARAGRAPH).child(Node::new(node::TEXT).prop(prop::CONTENT, text));
+        parse_citations(&mut para);
+        para.children
+            .iter()
+            .filter_map(|n| n.as_cite())
+            .map(|c| {
+                let source = c.content()[0]
+                    .props
+                    .get_str(prop::CONTENT)
+                    .unwrap()
+                    .to_string();
+                (source, c.citations())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bracketed() {
+        let found = cites("As shown [see @doe99, pp. 33-35; also -@smith04, chap. 1].");
+        assert_eq!(found.len(), 1);
+        let (source, citations) = &found[0];
+        assert_eq!(source, "[see @doe99, pp. 33-35; also -@smith04, chap. 1]");
+        assert_eq!(citations[0].id, "doe99");
+        assert_eq!(citations[0].prefix.as_deref(), Some("see"));
+        assert_eq!(citations[0].locator.as_deref(), Some("33-35"));
+        assert_eq!(citations[1].id, "smith04");
+        assert_eq!(citations[1].prefix.as_deref(), Some("also"));
+        assert_eq!(citations[1].mode, CitationMode::SuppressAuthor);
+        assert_eq!(citations[1].label.as_deref(), Some("chapter"));
+    }
+
+    #[test]
+    fn test_in_text() {
+        let found = cites("@smith04 [p. 33] says so, as does @{doe 99}.");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].0, "@smith04 [p. 33]");
+        assert_eq!(found[0].1[0].mode, CitationMode::AuthorInText);
+        assert_eq!(found[0].1[0].locator.as_deref(), Some("33"));
+        assert_eq!(found[1].1[0].id, "doe 99");
+    }
+
+    #[test]
+    fn test_not_citations() {
+        assert!(cites("mail john@example.com or [a link] or [x @]").is_empty());
+    }
+
+    #[test]
+    fn test_split_keeps_spans() {
+        let text = |content: &str, start, end| {
+            Node::new(node::TEXT)
+                .prop(prop::CONTENT, content)
+                .span(rescribe_core::Span { start, end })
+        };
+        // `&amp;` was decoded, so its node's text is not its source.
+        let mut para = Node::new(node::PARAGRAPH)
+            .child(text("See @doe99 ", 0, 11))
+            .child(text("&", 11, 16))
+            .child(text(" [@roe].", 16, 24));
+        parse_citations(&mut para);
+        let spans: Vec<_> = para
+            .children
+            .iter()
+            .map(|n| n.span.map(|s| (s.start, s.end)))
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                Some((0, 4)),
+                Some((4, 10)),
+                Some((10, 17)),
+                Some((17, 23)),
+                Some((23, 24))
+            ]
+        );
+        assert_eq!(para.children[1].children[0].span, para.children[1].span);
+    }
+
+    #[test]
+    #[cfg(feature = "tree-sitter")]
+    fn test_shortcut_link_citations() {
+        assert!(is_bracketed("[@doe99, p. 4]"));
+        assert!(!is_bracketed("[a link]"));
+        assert!(ends_with_in_text("see @doe99 "));
+        assert!(!ends_with_in_text("see doe@example.com "));
+    }
+}
@@ -5,15 +5,74 @@
 //! This crate supports multiple parser backends:
 //! - `pulldown` (default) - Uses pulldown-cmark, pure Rust, CommonMark compliant
 //! - `tree-sitter` - Uses tree-sitter-md, better error recovery and precise spans
+//!
+//! Syntax extensions are switched with the options in [`options`]. The
+//! tree-sitter backend only supports `citations`; [`options::ALL`] lists
+//! what the default backend supports, and the tree-sitter backend warns
+//! about any other option that is set.
 
 use rescribe_core::{ConversionResult, Document, ParseError, ParseOptions};
 
+mod cite;
+
 #[cfg(feature = "pulldown")]
 mod pulldown;
 
 #[cfg(feature = "tree-sitter")]
 mod treesitter;
 
+/// Reader options, set through [`ParseOptions::format`].
+pub mod options {
+    use rescribe_core::OptionSpec;
+
+    /// Pipe tables.
+    pub const PIPE_TABLES: OptionSpec = OptionSpec::flag("pipe_tables", true, "Pipe tables");
+    /// Footnote references and definitions.
+    pub const FOOTNOTES: OptionSpec = OptionSpec::flag("footnotes", true, "Footnotes");
+    /// `~~strikeout~~`.
+    pub const STRIKEOUT: OptionSpec =
+        OptionSpec::flag("strikeout", true, "Strikeout with ~~text~~");
+    /// `- [ ]` task list items.
+    pub const TASK_LISTS: OptionSpec = OptionSpec::flag("task_lists", true, "Task lists");
+    /// Leading YAML metadata block.
+    pub const YAML_METADATA_BLOCK: OptionSpec =
+        OptionSpec::flag("yaml_metadata_block", true, "YAML metadata block");
+    /// GitHub-style `> [!NOTE]` alerts.
+    pub const ALERTS: OptionSpec = OptionSpec::flag("alerts", true, "GitHub-style alerts");
+    /// Curly quotes, dashes and ellipses.
+    pub const SMART: OptionSpec =
+        OptionSpec::flag("smart", false, "Curly quotes, dashes and ellipses");
+    /// `$inline$` and `$$display$$` math.
+    pub const TEX_MATH_DOLLARS: OptionSpec =
+        OptionSpec::flag("tex_math_dollars", false, "TeX math between $ and $$");
+    /// Pandoc citations: `[see @doe, p. 4]` and `@doe [p. 4]`.
+    pub const CITATIONS: OptionSpec = OptionSpec::flag("citations", false, "Pandoc citations");
+
+    /// All reader options the default backend supports.
+    #[cfg(feature = "pulldown")]
+    pub const ALL: &[OptionSpec] = PULLDOWN;
+
+    /// All reader options the default backend supports.
+    #[cfg(not(feature = "pulldown"))]
+    pub const ALL: &[OptionSpec] = TREE_SITTER;
+
+    /// The options the tree-sitter backend supports.
+    pub const TREE_SITTER: &[OptionSpec] = &[CITATIONS];
+
+    /// The options the pulldown-cmark backend supports.
+    pub const PULLDOWN: &[OptionSpec] = &[
+        PIPE_TABLES,
+        FOOTNOTES,
+        STRIKEOUT,
+        TASK_LISTS,
+        YAML_METADATA_BLOCK,
+        ALERTS,
+        SMART,
+        TEX_MATH_DOLLARS,
+        CITATIONS,
+    ];
+}
+
 /// Parse markdown text into a rescribe Document.
 ///
 /// Uses the default parser backend (pulldown-cmark if available, else tree-sitter).
@@ -243,4 +302,69 @@ author:
         assert_eq!(item2.kind.as_str(), node::LIST_ITEM);
         assert_eq!(item2.props.get_bool(prop::CHECKED), Some(true));
     }
+
+    #[test]
+    #[cfg(feature = "pulldown")]
+    fn test_extension_options() {
+        let input = "\"Hi\"[^1]\n\n[^1]: Note";
+        let options = ParseOptions {
+            format: rescribe_core::FormatOptions::parse(options::ALL, "+smart-footnotes").unwrap(),
+            ..Default::default()
+        };
+        let doc = parse_with_options(input, &options).unwrap().value;
+        let para = &root_children(&doc)[0];
+        assert!(
+            para.children
+                .iter()
+                .all(|c| c.kind.as_str() != node::FOOTNOTE_REF)
+        );
+        let text: String = para
+            .children
+            .iter()
+            .filter_map(|c| c.props.get_str(prop::CONTENT))
+            .collect();
+        assert!(text.starts_with("\u{201c}Hi\u{201d}"));
+
+        let doc = parse(input).unwrap().value;
+        assert!(
+            root_children(&doc)
+                .iter()
+                .any(|n| n.kind.as_str() == node::FOOTNOTE_DEF)
+        );
+    }
+
+    #[test]
+    fn test_parse_citations() {
+        use rescribe_std::NodeExt;
+
+        let input = "Blah [see @doe99, p. 4; -@roe]. @smith04 [chap. 2] says, mail a@b.c.";
+        let options = ParseOptions {
+            format: rescribe_core::FormatOptions::parse(options::ALL, "+citations").unwrap(),
+            ..Default::default()
+        };
+        let doc = parse_with_options(input, &options).unwrap().value;
+        let para = &root_children(&doc)[0];
+        let cites: Vec<_> = para.children.iter().filter_map(|n| n.as_cite()).collect();
+        assert_eq!(cites.len(), 2);
+        let ids: Vec<_> = cites[0].citations().into_iter().map(|c| c.id).collect();
+        assert_eq!(ids, ["doe99", "roe"]);
+        let smith = &cites[1].citations()[0];
+        assert_eq!(smith.mode, rescribe_std::CitationMode::AuthorInText);
+        assert_eq!(smith.locator.as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn test_mentions_are_text_by_default() {
+        let doc = parse("Thanks @alice [and @bob] for the fix.")
+            .unwrap()
+            .value;
+        let para = &root_children(&doc)[0];
+        assert!(para.children.iter().all(|n| n.kind.as_str() != node::CITE));
+        let text: String = para
+            .children
+            .iter()
+            .filter_map(|c| c.props.get_str(prop::CONTENT))
+            .collect();
+        assert_eq!(text, "Thanks @alice [and @bob] for the fix.");
+    }
 }
@@ -11,6 +11,8 @@ use rescribe_core::{
 };
 use rescribe_std::{Node, node, prop};
 
+use crate::options as ext;
+
 /// Parse markdown text into a rescribe Document.
 pub fn parse(input: &str) -> Result<ConversionResult<Document>, ParseError> {
     parse_with_options(input, &ParseOptions::default())
@@ -24,16 +26,7 @@ pub fn parse_with_options(
     let mut warnings = Vec::new();
     let mut metadata = Properties::new();
 
-    // Enable common extensions including full GFM support
-    let mut opts = Options::empty();
-    opts.insert(Options::ENABLE_TABLES);
-    opts.insert(Options::ENABLE_FOOTNOTES);
-    opts.insert(Options::ENABLE_STRIKETHROUGH);
-    opts.insert(Options::ENABLE_TASKLISTS);
-    opts.insert(Options::ENABLE_YAML_STYLE_METADATA_BLOCKS);
-    opts.insert(Options::ENABLE_GFM); // GitHub-style blockquotes like [!NOTE]
-
-    let parser = Parser::new_ext(input, opts);
+    let parser = Parser::new_ext(input, extensions(options));
     // Collect events with source ranges for span tracking
     let events: Vec<_> = parser.into_offset_iter().collect();
 
@@ -45,11 +38,32 @@ pub fn parse_with_options(
     );
 
     // Wrap children in a document root node
-    let root = Node::new(node::DOCUMENT).children(children);
+    let mut root = Node::new(node::DOCUMENT).children(children);
+    if options.format.flag(&ext::CITATIONS) {
+        crate::cite::parse_citations(&mut root);
+    }
     let doc = Document::new().with_content(root).with_metadata(metadata);
     Ok(ConversionResult::with_warnings(doc, warnings))
 }
 
+/// Map reader options to pulldown-cmark extensions.
+fn extensions(options: &ParseOptions) -> Options {
+    let format = &options.format;
+    let mut opts = Options::empty();
+    opts.set(Options::ENABLE_TABLES, format.flag(&ext::PIPE_TABLES));
+    opts.set(Options::ENABLE_FOOTNOTES, format.flag(&ext::FOOTNOTES));
+    opts.set(Options::ENABLE_STRIKETHROUGH, format.flag(&ext::STRIKEOUT));
+    opts.set(Options::ENABLE_TASKLISTS, format.flag(&ext::TASK_LISTS));
+    opts.set(
+        Options::ENABLE_YAML_STYLE_METADATA_BLOCKS,
+        format.flag(&ext::YAML_METADATA_BLOCK),
+    );
+    opts.set(Options::ENABLE_GFM, format.flag(&ext::ALERTS));
+    opts.set(Options::ENABLE_SMART_PUNCTUATION, format.flag(&ext::SMART));
+    opts.set(Options::ENABLE_MATH, format.flag(&ext::TEX_MATH_DOLLARS));
+    opts
+}
+
 /// Parse a slice of events into nodes.
 fn parse_events(
     events: &[(Event<'_>, Range<usize>)],
@@ -296,15 +310,17 @@ fn parse_tag(
                 .collect();
             Some(with_span(
                 Node::new(node::TABLE)
-                    .prop("column_alignments", align_strs.join(","))
+                    .prop(prop::COLUMN_ALIGNMENTS, align_strs.join(","))
                     .children(children),
                 &tag_range,
                 preserve_spans,
             ))
         }
 
+        // pulldown-cmark puts header cells directly in the head; wrap them in
+        // a row so the head has the same shape as a body.
         Tag::TableHead => Some(with_span(
-            Node::new(node::TABLE_HEAD).children(children),
+            Node::new(node::TABLE_HEAD).child(Node::new(node::TABLE_ROW).children(children)),
             &tag_range,
             preserve_spans,
         )),
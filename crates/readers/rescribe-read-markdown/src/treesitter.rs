@@ -90,15 +90,35 @@ pub fn parse_with_options(
 
     // Use normalized source for text extraction (byte offsets match the tree)
     // but original input length for span clamping
+    let citations = options.format.flag(&crate::options::CITATIONS);
     let mut converter = Converter::new(
         normalized.as_ref(),
         options.preserve_source_info,
         input.len(),
         inline_trees,
+        citations,
     );
+    for (name, _) in options.format.iter() {
+        if !crate::options::TREE_SITTER
+            .iter()
+            .any(|spec| spec.name == name)
+        {
+            converter.warnings.push(FidelityWarning::new(
+                Severity::Minor,
+                WarningKind::FeatureLost(format!("md:{}", name)),
+                format!(
+                    "The tree-sitter markdown backend ignores the {} option",
+                    name
+                ),
+            ));
+        }
+    }
     let children = converter.convert_block_tree(&block_tree);
 
-    let root = Node::new(node::DOCUMENT).children(children);
+    let mut root = Node::new(node::DOCUMENT).children(children);
+    if citations {
+        crate::cite::parse_citations(&mut root);
+    }
     let doc = Document::new().with_content(root);
 
     Ok(ConversionResult::with_warnings(doc, converter.warnings))
@@ -112,6 +132,8 @@ struct Converter<'a> {
     original_len: usize,
     /// Parsed inline trees keyed by (start, end) byte positions
     inline_trees: InlineTrees,
+    /// Keep bracketed citations as text for the citation pass.
+    citations: bool,
     warnings: Vec<FidelityWarning>,
 }
 
@@ -121,12 +143,14 @@ impl<'a> Converter<'a> {
         preserve_spans: bool,
         original_len: usize,
         inline_trees: InlineTrees,
+        citations: bool,
     ) -> Self {
         Self {
             source,
             preserve_spans,
             original_len,
             inline_trees,
+            citations,
             warnings: Vec::new(),
         }
     }
@@ -413,6 +437,15 @@ impl<'a> Converter<'a> {
                 ))
             }
 
+            "shortcut_link" if self.citations && self.is_citation(tsnode, offset) => {
+                let text = self.inline_text(tsnode, offset).to_string();
+                Some(self.with_inline_span(
+                    Node::new(node::TEXT).prop(prop::CONTENT, text),
+                    tsnode,
+                    offset,
+                ))
+            }
+
             "inline_link"
             | "full_reference_link"
             | "collapsed_reference_link"
@@ -466,6 +499,14 @@ impl<'a> Converter<'a> {
         }
     }
 
+    /// Whether a shortcut link is a citation group or the locator of an
+    /// author-in-text citation.
+    fn is_citation(&self, tsnode: &tree_sitter::Node, offset: usize) -> bool {
+        let before = &self.source[offset..offset + tsnode.start_byte()];
+        crate::cite::is_bracketed(self.inline_text(tsnode, offset))
+            || crate::cite::ends_with_in_text(before)
+    }
+
     fn process_inline_children(&self, parent: &tree_sitter::Node, offset: usize) -> Vec<Node> {
         let mut nodes = Vec::new();
 
@@ -897,6 +938,22 @@ mod tests {
         &doc.content.children
     }
 
+    #[test]
+    fn test_unsupported_options_warn() {
+        let options = ParseOptions {
+            format: rescribe_core::FormatOptions::new()
+                .with("smart", true)
+                .with("citations", false),
+            ..Default::default()
+        };
+        let result = parse_with_options("\"Hi\" -- @doe", &options).unwrap();
+        assert_eq!(result.warnings.len(), 1);
+        assert!(matches!(
+            &result.warnings[0].kind,
+            WarningKind::FeatureLost(name) if name == "md:smart"
+        ));
+    }
+
     #[test]
     fn test_parse_heading() {
         let result = parse("# Hello").unwrap();
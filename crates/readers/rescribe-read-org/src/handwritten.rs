@@ -4,7 +4,7 @@ use rescribe_core::{
     ConversionResult, Document, FidelityWarning, ParseError, ParseOptions, Properties, Severity,
     Span, WarningKind,
 };
-use rescribe_std::{Node, node, prop};
+use rescribe_std::{Citation, CitationMode, Node, node, prop};
 
 /// Parse Org-mode text into a rescribe Document.
 pub fn parse(input: &str) -> Result<ConversionResult<Document>, ParseError> {
@@ -495,8 +495,13 @@ impl<'a> Parser<'a> {
                         continue;
                     }
                 }
-                // Link: [[url]] or [[url][description]]
+                // Link: [[url]] or [[url][description]]; citation: [cite:@key]
                 '[' => {
+                    if let Some((cite, end)) = parse_cite(&chars, pos) {
+                        nodes.push(cite);
+                        pos = end;
+                        continue;
+                    }
                     if pos + 1 < chars.len()
                         && chars[pos + 1] == '['
                         && let Some((link_node, end)) = self.parse_link(&chars, pos)
@@ -603,6 +608,76 @@ impl<'a> Parser<'a> {
     }
 }
 
+/// Parse an Org citation, `[cite/style:prefix @key suffix; @key2]`, at
+/// `start`. Parts of the list without a key are a global prefix or suffix;
+/// the `t` (text) style makes author-in-text citations and `na` (noauthor)
+/// suppresses the author.
+fn parse_cite(chars: &[char], start: usize) -> Option<(Node, usize)> {
+    let rest: String = chars[start..].iter().collect();
+    let body = rest.strip_prefix("[cite")?;
+    let colon = body.find(':')?;
+    let style = &body[..colon];
+    if !(style.is_empty() || style.starts_with('/')) || style.contains([' ', '[', ']']) {
+        return None;
+    }
+    let close = body.find(']')?;
+    let list = &body[colon + 1..close];
+    let mode = match style.trim_start_matches('/').split('/').next() {
+        Some("t" | "text") => CitationMode::AuthorInText,
+        Some("na" | "noauthor") => CitationMode::SuppressAuthor,
+        _ => CitationMode::Normal,
+    };
+
+    let mut global_prefix = "";
+    let mut global_suffix = "";
+    let mut citations = Vec::new();
+    let parts: Vec<&str> = list.split(';').collect();
+    for (i, part) in parts.iter().enumerate() {
+        let Some(at) = part.find('@') else {
+            if i == 0 {
+                global_prefix = part;
+            } else if i == parts.len() - 1 {
+                global_suffix = part;
+            }
+            continue;
+        };
+        let key: String = part[at + 1..]
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || "-.:?!`'/*@+|(){}<>&_^$#%~".contains(*c))
+            .collect();
+        if key.is_empty() {
+            return None;
+        }
+        citations.push(
+            Citation::new(key.as_str())
+                .with_prefix(&part[..at])
+                .with_suffix(&part[at + 1 + key.len()..])
+                .with_mode(mode),
+        );
+    }
+    if let Some(first) = citations.first_mut()
+        && !global_prefix.trim().is_empty()
+    {
+        let prefix = format!("{global_prefix} {}", first.prefix.as_deref().unwrap_or(""));
+        *first = std::mem::take(first).with_prefix(&prefix);
+    }
+    if let Some(last) = citations.last_mut()
+        && !global_suffix.trim().is_empty()
+    {
+        let suffix = last.suffix.take().unwrap_or_default();
+        last.suffix = Some(format!("{suffix}{global_suffix}"));
+    }
+    if citations.is_empty() {
+        return None;
+    }
+
+    let source = &rest[..5 + close + 1];
+    let cite = Node::new(node::CITE)
+        .prop(prop::CITATIONS, Citation::list(citations))
+        .child(Node::new(node::TEXT).prop(prop::CONTENT, source));
+    Some((cite, start + source.chars().count()))
+}
+
 /// Merge adjacent text nodes.
 fn merge_text_nodes(nodes: &mut Vec<Node>) {
     let mut i = 0;
@@ -83,6 +83,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_citations() {
+        use rescribe_std::{CitationMode, NodeExt};
+
+        let input = "As [cite:see @doe99 p. 4; @roe] and [cite/t:@poe] show.";
+        let doc = parse(input).unwrap().value;
+        let para = &root_children(&doc)[0];
+        let cites: Vec<_> = para.children.iter().filter_map(|n| n.as_cite()).collect();
+        assert_eq!(cites.len(), 2);
+
+        let group = cites[0].citations();
+        assert_eq!(group[0].prefix.as_deref(), Some("see"));
+        assert_eq!(group[0].locator.as_deref(), Some("4"));
+        assert_eq!(group[1].id, "roe");
+        assert_eq!(cites[1].citations()[0].mode, CitationMode::AuthorInText);
+        assert_eq!(
+            cites[1].content()[0].props.get_str(prop::CONTENT),
+            Some("[cite/t:@poe]")
+        );
+    }
+
     #[test]
     fn test_parse_list() {
         let input = "- First item\n- Second item";
@@ -7,7 +7,7 @@ use rescribe_core::{
     ConversionResult, Document, FidelityWarning, ParseError, ParseOptions, Properties, Severity,
     WarningKind,
 };
-use rescribe_std::{Node, node, prop};
+use rescribe_std::{Citation, CitationMode, Node, node, prop};
 use serde::Deserialize;
 use serde_json::Value;
 
@@ -123,6 +123,33 @@ impl Converter {
         text
     }
 
+    fn convert_citation(&self, citation: &Value) -> Option<Citation> {
+        let id = citation.get("citationId")?.as_str()?;
+        let text = |key: &str| {
+            citation
+                .get(key)
+                .and_then(|v| v.as_array())
+                .map(|inlines| self.inlines_to_text(inlines))
+                .unwrap_or_default()
+        };
+        let mode = match citation
+            .get("citationMode")
+            .and_then(|m| m.get("t"))
+            .and_then(|t| t.as_str())
+        {
+            Some("AuthorInText") => CitationMode::AuthorInText,
+            Some("SuppressAuthor") => CitationMode::SuppressAuthor,
+            _ => CitationMode::Normal,
+        };
+        // Pandoc keeps the locator as the start of the suffix.
+        Some(
+            Citation::new(id)
+                .with_prefix(&text("citationPrefix"))
+                .with_suffix(&text("citationSuffix"))
+                .with_mode(mode),
+        )
+    }
+
     fn convert_blocks(&mut self, blocks: &[Value]) -> Vec<Node> {
         blocks
             .iter()
@@ -651,11 +678,26 @@ impl Converter {
                 Some(span)
             }
             "Cite" => {
-                // Citation - just extract the text for now
+                // [[Citation], [Inline]]
                 let arr = c?.as_array()?;
+                let citations = arr.first()?.as_array()?;
                 let inlines = arr.get(1)?.as_array()?;
                 let children = self.convert_inlines(inlines);
-                Some(Node::new(node::CITE).children(children))
+                let mut cite = Node::new(node::CITE)
+                    .prop(
+                        prop::CITATIONS,
+                        Citation::list(citations.iter().filter_map(|c| self.convert_citation(c))),
+                    )
+                    .children(children);
+                let note_num = citations
+                    .first()
+                    .and_then(|c| c.get("citationNoteNum"))
+                    .and_then(|n| n.as_i64())
+                    .filter(|&n| n > 0);
+                if let Some(n) = note_num {
+                    cite = cite.prop(prop::NOTE_NUM, n);
+                }
+                Some(cite)
             }
             _ => {
                 self.warn(
@@ -671,6 +713,7 @@ impl Converter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rescribe_std::NodeExt;
 
     #[test]
     fn test_parse_simple_paragraph() {
@@ -802,4 +845,39 @@ mod tests {
         assert_eq!(doc.metadata.get_str("title"), Some("My Title"));
         assert_eq!(doc.metadata.get_str("author"), Some("John Doe"));
     }
+
+    #[test]
+    fn test_parse_cite() {
+        let json = r#"{
+            "pandoc-api-version": [1, 23],
+            "meta": {},
+            "blocks": [
+                {"t": "Para", "c": [{"t": "Cite", "c": [
+                    [
+                        {"citationId": "smith2020",
+                         "citationPrefix": [{"t": "Str", "c": "see"}],
+                         "citationSuffix": [{"t": "Str", "c": ","}, {"t": "Space"}, {"t": "Str", "c": "p."}, {"t": "Space"}, {"t": "Str", "c": "4"}],
+                         "citationMode": {"t": "NormalCitation"},
+                         "citationNoteNum": 1, "citationHash": 0},
+                        {"citationId": "doe", "citationPrefix": [], "citationSuffix": [],
+                         "citationMode": {"t": "SuppressAuthor"},
+                         "citationNoteNum": 1, "citationHash": 0}
+                    ],
+                    [{"t": "Str", "c": "[see"}, {"t": "Space"}, {"t": "Str", "c": "@smith2020,"}]
+                ]}]}
+            ]
+        }"#;
+
+        let doc = parse(json).unwrap().value;
+        let cite = doc.content.children[0].children[0].as_cite().unwrap();
+        let citations = cite.citations();
+        assert_eq!(citations.len(), 2);
+        assert_eq!(citations[0].id, "smith2020");
+        assert_eq!(citations[0].prefix.as_deref(), Some("see"));
+        assert_eq!(citations[0].locator.as_deref(), Some("4"));
+        assert_eq!(citations[0].label.as_deref(), Some("page"));
+        assert_eq!(citations[1].mode, CitationMode::SuppressAuthor);
+        assert_eq!(cite.note_num(), Some(1));
+        assert!(!cite.content().is_empty());
+    }
 }
@@ -1,7 +1,9 @@
 //! RIS (Research Information Systems) reader for rescribe.
 //!
 //! Parses RIS bibliography files into rescribe's document IR.
-//! RIS is a standardized tag format for bibliographic citations.
+//! RIS is a standardized tag format for bibliographic citations. Each
+//! reference becomes a `bib:entry` node (see [`rescribe_bib`]); tags without
+//! a CSL variable are kept in `bib:custom` as `ris:<tag>`.
 //!
 //! # Example
 //!
@@ -13,9 +15,18 @@
 //! let doc = result.value;
 //! ```
 
-use rescribe_core::{ConversionResult, Document, Node, ParseError, Properties};
-use rescribe_std::{node, prop};
-use std::collections::HashMap;
+use rescribe_bib::{Date, DateParts, Name, prop};
+use rescribe_core::{ConversionResult, Document, Node, ParseError, PropValue, Properties};
+use rescribe_std::node;
+use std::collections::{HashMap, HashSet};
+
+/// Reference types the RIS writer produces for some CSL type. Other types
+/// are kept as the `ris:TY` custom field so that they survive a round trip.
+const WRITTEN_TYPES: &[&str] = &[
+    "ART", "BILL", "BLOG", "BOOK", "CASE", "CHAP", "COMP", "CPAPER", "DATA", "DICT", "ELEC",
+    "ENCYC", "GEN", "HEAR", "JOUR", "MANSCPT", "MAP", "MGZN", "NEWS", "PAMP", "PAT", "RPRT",
+    "SOUND", "STAND", "STAT", "THES", "VIDEO",
+];
 
 /// Parse RIS text into a document.
 pub fn parse(input: &str) -> Result<ConversionResult<Document>, ParseError> {
@@ -36,30 +47,29 @@ pub fn parse_with_options(
             continue;
         }
 
-        // RIS format: TAG  - VALUE (tag is 2-4 chars, followed by two spaces, dash, space, value)
-        if line.len() >= 6 && &line[4..6] == "- " {
-            let tag = line[0..2].trim();
-            let value = line[6..].trim();
-
-            match tag {
-                "TY" => {
-                    // Start of new entry
-                    if let Some(entry) = current_entry.take() {
-                        entries.push(entry.into_node());
-                    }
-                    current_entry = Some(RisEntry::new(value));
+        // RIS format: TAG  - VALUE (two-character tag, two spaces, dash, space, value)
+        let Some((tag, value)) = split_line(line) else {
+            continue;
+        };
+
+        match tag {
+            "TY" => {
+                // Start of new entry
+                if let Some(entry) = current_entry.take() {
+                    entries.push(entry.into_node());
                 }
-                "ER" => {
-                    // End of entry
-                    if let Some(entry) = current_entry.take() {
-                        entries.push(entry.into_node());
-                    }
+                current_entry = Some(RisEntry::new(value));
+            }
+            "ER" => {
+                // End of entry
+                if let Some(entry) = current_entry.take() {
+                    entries.push(entry.into_node());
                 }
-                _ => {
-                    // Add field to current entry
-                    if let Some(ref mut entry) = current_entry {
-                        entry.add_field(tag, value);
-                    }
+            }
+            _ => {
+                // Add field to current entry
+                if let Some(ref mut entry) = current_entry {
+                    entry.add_field(tag, value);
                 }
             }
         }
@@ -70,24 +80,31 @@ pub fn parse_with_options(
         entries.push(entry.into_node());
     }
 
-    let content = if entries.is_empty() {
-        Node::new(node::DOCUMENT)
-    } else {
-        Node::new(node::DOCUMENT).child(Node::new(node::DEFINITION_LIST).children(entries))
-    };
-
     Ok(ConversionResult::ok(Document {
-        content,
+        content: Node::new(node::DOCUMENT).children(entries),
         resources: Default::default(),
         metadata: Properties::new(),
         source: None,
     }))
 }
 
+/// Split a line into its tag and value.
+fn split_line(line: &str) -> Option<(&str, &str)> {
+    let tag = line.get(..2).filter(|tag| {
+        tag.bytes()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+    })?;
+    let rest = line.get(2..)?.trim_start_matches(' ');
+    let value = rest.strip_prefix('-')?;
+    Some((tag, value.trim()))
+}
+
 /// RIS entry being parsed.
 struct RisEntry {
     entry_type: String,
     fields: HashMap<String, Vec<String>>,
+    /// Tags that have been mapped to CSL variables.
+    used: HashSet<&'static str>,
 }
 
 impl RisEntry {
@@ -95,6 +112,7 @@ impl RisEntry {
         Self {
             entry_type: entry_type.to_string(),
             fields: HashMap::new(),
+            used: HashSet::new(),
         }
     }
 
@@ -109,119 +127,133 @@ impl RisEntry {
         self.fields
             .get(tag)
             .and_then(|v| v.first().map(|s| s.as_str()))
+            .filter(|s| !s.is_empty())
     }
 
-    fn get_all(&self, tag: &str) -> Vec<&str> {
-        self.fields
-            .get(tag)
-            .map(|v| v.iter().map(|s| s.as_str()).collect())
-            .unwrap_or_default()
+    /// Take the first value of the first of `tags` that is present.
+    fn take(&mut self, tags: &[&'static str]) -> Option<String> {
+        let (tag, value) = tags
+            .iter()
+            .find_map(|tag| Some((*tag, self.get_first(tag)?.to_string())))?;
+        self.used.insert(tag);
+        Some(value)
     }
 
-    fn into_node(self) -> Node {
-        // Generate a cite key from first author and year
-        let cite_key = self.generate_cite_key();
-        let bibtex_type = ris_type_to_bibtex(&self.entry_type);
-
-        // Create the term (citation key)
-        let term = Node::new(node::DEFINITION_TERM)
-            .child(Node::new(node::CODE).prop(prop::CONTENT, cite_key.clone()));
-
-        // Build the description content
-        let mut desc_children = Vec::new();
+    /// Take all values of `tags`.
+    fn take_all(&mut self, tags: &[&'static str]) -> Vec<String> {
+        self.used.extend(tags);
+        tags.iter()
+            .filter_map(|tag| self.fields.get(*tag))
+            .flatten()
+            .filter(|value| !value.is_empty())
+            .cloned()
+            .collect()
+    }
 
-        // Entry type badge
-        let type_text = format!("[{}] ", bibtex_type);
-        desc_children.push(
-            Node::new(node::SPAN)
-                .prop("html:class", "ris-type")
-                .child(Node::new(node::TEXT).prop(prop::CONTENT, type_text)),
-        );
+    fn take_names(&mut self, tags: &[&'static str]) -> Vec<Name> {
+        self.take_all(tags).iter().map(|n| parse_name(n)).collect()
+    }
 
-        // Authors (AU tag, can be multiple)
-        let authors = self.get_all("AU");
-        if !authors.is_empty() {
-            let author_text = authors.join("; ");
-            desc_children.push(
-                Node::new(node::STRONG)
-                    .child(Node::new(node::TEXT).prop(prop::CONTENT, author_text)),
-            );
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
+    fn into_node(mut self) -> Node {
+        let csl_type = csl_type(&self.entry_type);
+        let mut node = Node::new(rescribe_bib::node::ENTRY)
+            .prop(prop::ID, self.cite_key())
+            .prop(prop::TYPE, csl_type);
+        self.used.insert("ID");
+
+        let names = [
+            (prop::AUTHOR, &["AU", "A1"][..]),
+            (prop::EDITOR, &["A2", "ED"]),
+            (prop::COLLECTION_EDITOR, &["A3"]),
+            (prop::TRANSLATOR, &["A4"]),
+        ];
+        for (key, tags) in names {
+            let names = self.take_names(tags);
+            if !names.is_empty() {
+                node.props.set(key, Name::list(names));
+            }
         }
 
-        // Title (TI or T1)
-        let title = self.get_first("TI").or(self.get_first("T1"));
-        if let Some(t) = title {
-            desc_children.push(
-                Node::new(node::EMPHASIS)
-                    .child(Node::new(node::TEXT).prop(prop::CONTENT, t.to_string())),
-            );
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
+        let texts = [
+            (prop::TITLE, &["TI", "T1"][..]),
+            (prop::CONTAINER_TITLE, &["T2", "JF", "JO", "BT"]),
+            (prop::CONTAINER_TITLE_SHORT, &["J2", "JA"]),
+            (prop::COLLECTION_TITLE, &["T3"]),
+            (prop::TITLE_SHORT, &["ST"]),
+            (prop::VOLUME, &["VL"]),
+            (prop::ISSUE, &["IS"]),
+            (prop::EDITION, &["ET"]),
+            (prop::NUMBER_OF_VOLUMES, &["NV"]),
+            (prop::PUBLISHER, &["PB"]),
+            (prop::PUBLISHER_PLACE, &["CY", "PP"]),
+            (prop::DOI, &["DO"]),
+            (prop::URL, &["UR"]),
+            (prop::ABSTRACT, &["AB", "N2"]),
+            (prop::NOTE, &["N1"]),
+            (prop::LANGUAGE, &["LA"]),
+            (prop::CALL_NUMBER, &["CN"]),
+            (prop::MEDIUM, &["M3"]),
+        ];
+        for (key, tags) in texts {
+            if let Some(value) = self.take(tags) {
+                node.props.set(key, value);
+            }
         }
 
-        // Journal/Publication (JO, JF, or T2)
-        let journal = self
-            .get_first("JO")
-            .or(self.get_first("JF"))
-            .or(self.get_first("T2"));
-        if let Some(j) = journal {
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, j.to_string()));
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
+        // Standard numbers are ISBNs for books and ISSNs for serials.
+        if let Some(number) = self.take(&["SN"]) {
+            let key = match csl_type {
+                "book" | "chapter" | "entry-dictionary" | "entry-encyclopedia" | "pamphlet" => {
+                    prop::ISBN
+                }
+                _ => prop::ISSN,
+            };
+            node.props.set(key, number);
         }
 
-        // Volume (VL)
-        if let Some(vol) = self.get_first("VL") {
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, format!("{}.", vol)));
+        if let Some(start) = self.take(&["SP"]) {
+            let page = match self.take(&["EP"]) {
+                Some(end) => format!("{start}-{end}"),
+                None => start,
+            };
+            node.props.set(prop::PAGE, page);
         }
 
-        // Year (PY or Y1)
-        let year = self.get_first("PY").or(self.get_first("Y1"));
-        if let Some(y) = year {
-            // Extract just the year part (format might be YYYY/MM/DD)
-            let year_str = y.split('/').next().unwrap_or(y);
-            desc_children
-                .push(Node::new(node::TEXT).prop(prop::CONTENT, format!(" ({})", year_str)));
+        let keywords = self.take_all(&["KW"]);
+        if !keywords.is_empty() {
+            node.props.set(prop::KEYWORD, keywords.join(", "));
         }
 
-        // Pages (SP - start page, EP - end page)
-        if let Some(sp) = self.get_first("SP") {
-            let pages = if let Some(ep) = self.get_first("EP") {
-                format!(", pp. {}-{}", sp, ep)
-            } else {
-                format!(", p. {}", sp)
-            };
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, pages));
+        // DA holds the full date; PY often only the year.
+        if let Some(issued) = self.take(&["DA", "PY", "Y1"]).map(|d| parse_date(&d)) {
+            self.used.extend(["DA", "PY", "Y1"]);
+            node.props.set(prop::ISSUED, issued);
         }
-
-        // DOI (DO)
-        if let Some(doi) = self.get_first("DO") {
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
-            desc_children.push(
-                Node::new(node::LINK)
-                    .prop(prop::URL, format!("https://doi.org/{}", doi))
-                    .child(Node::new(node::TEXT).prop(prop::CONTENT, format!("doi:{}", doi))),
-            );
+        if let Some(accessed) = self.take(&["Y2"]) {
+            node.props.set(prop::ACCESSED, parse_date(&accessed));
         }
 
-        // URL (UR)
-        if let Some(url) = self.get_first("UR") {
-            desc_children.push(Node::new(node::TEXT).prop(prop::CONTENT, ". "));
-            desc_children.push(
-                Node::new(node::LINK)
-                    .prop(prop::URL, url.to_string())
-                    .child(Node::new(node::TEXT).prop(prop::CONTENT, url.to_string())),
-            );
+        let mut custom: HashMap<String, PropValue> = self
+            .fields
+            .iter()
+            .filter(|(tag, _)| !self.used.contains(tag.as_str()))
+            .map(|(tag, values)| (format!("ris:{tag}"), PropValue::String(values.join("\n"))))
+            .collect();
+        if !WRITTEN_TYPES.contains(&self.entry_type.as_str()) {
+            custom.insert("ris:TY".to_string(), self.entry_type.clone().into());
+        }
+        if !custom.is_empty() {
+            node.props.set(prop::CUSTOM, PropValue::Map(custom));
         }
 
-        let desc = Node::new(node::DEFINITION_DESC)
-            .prop("ris:type", self.entry_type.clone())
-            .prop("ris:key", cite_key)
-            .child(Node::new(node::PARAGRAPH).children(desc_children));
-
-        Node::new("ris:entry").children(vec![term, desc])
+        node
     }
 
-    fn generate_cite_key(&self) -> String {
+    fn cite_key(&self) -> String {
+        if let Some(id) = self.get_first("ID") {
+            return id.to_string();
+        }
+
         let author_part = self
             .get_first("AU")
             .map(|a| {
@@ -247,34 +279,72 @@ impl RisEntry {
     }
 }
 
-/// Map RIS reference types to BibTeX types.
-fn ris_type_to_bibtex(ris_type: &str) -> &'static str {
+/// Parse a name written `Last, First, Suffix`. Names without a comma are
+/// taken as literals.
+fn parse_name(name: &str) -> Name {
+    let parts: Vec<&str> = name.splitn(3, ',').map(str::trim).collect();
+    match parts.as_slice() {
+        [last, given] => Name::inverted(last, given, ""),
+        [last, given, suffix] => Name::inverted(last, given, suffix),
+        _ => Name::literal(name),
+    }
+}
+
+/// Parse a date written `YYYY/MM/DD/other`, where all but the year may be
+/// empty. Dates in other forms are taken as literals.
+fn parse_date(text: &str) -> Date {
+    let mut parts = text.split('/').map(str::trim);
+    let number = |part: Option<&str>| part.filter(|p| !p.is_empty()).map(str::parse::<u8>);
+    let year = parts.next().and_then(|y| y.parse::<i32>().ok());
+    let month = number(parts.next());
+    let day = number(parts.next());
+    match (year, &month, &day) {
+        (Some(year), None | Some(Ok(1..=12)), None | Some(Ok(1..=31))) => {
+            let month = month.and_then(Result::ok);
+            let day = month.and(day.and_then(Result::ok));
+            Date::new(DateParts::new(year, month, day))
+        }
+        _ => Date::literal(text),
+    }
+}
+
+/// Map RIS reference types to CSL types.
+fn csl_type(ris_type: &str) -> &'static str {
     match ris_type {
-        "JOUR" => "article",
-        "BOOK" => "book",
-        "CHAP" | "SECT" => "incollection",
-        "CONF" | "CPAPER" => "inproceedings",
-        "THES" => "phdthesis",
-        "RPRT" => "techreport",
-        "MGZN" | "NEWS" => "article",
-        "ELEC" | "WEB" => "online",
+        "JOUR" | "JFULL" | "ABST" | "INPR" | "EJOUR" => "article-journal",
+        "MGZN" => "article-magazine",
+        "NEWS" => "article-newspaper",
+        "BOOK" | "EBOOK" | "EDBOOK" | "SER" => "book",
+        "CHAP" | "ECHAP" | "SECT" => "chapter",
+        "CONF" | "CPAPER" => "paper-conference",
+        "THES" => "thesis",
+        "RPRT" => "report",
+        "ELEC" | "WEB" => "webpage",
+        "BLOG" => "post-weblog",
         "COMP" => "software",
         "DATA" => "dataset",
-        "ABST" | "INPR" | "JFULL" => "article",
-        "EDBOOK" => "book",
-        "GEN" | "CTLG" | "ENCYC" | "DICT" => "misc",
-        "MANSCPT" | "UNPB" => "unpublished",
-        "PAMP" => "booklet",
-        "PAT" => "misc",
-        "SER" => "book",
-        "SLIDE" | "VIDEO" | "SOUND" | "MAP" | "ADVS" | "ART" => "misc",
-        _ => "misc",
+        "PAT" => "patent",
+        "MANSCPT" | "UNPB" => "manuscript",
+        "PAMP" => "pamphlet",
+        "ENCYC" => "entry-encyclopedia",
+        "DICT" => "entry-dictionary",
+        "MAP" => "map",
+        "ART" => "graphic",
+        "VIDEO" | "MPCT" => "motion_picture",
+        "SOUND" | "MUSIC" => "song",
+        "HEAR" => "hearing",
+        "BILL" => "bill",
+        "CASE" => "legal_case",
+        "STAT" => "legislation",
+        "STAND" => "standard",
+        _ => "document",
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rescribe_bib::Entry;
 
     #[test]
     fn test_parse_article() {
@@ -293,6 +363,14 @@ ER  -"#;
         let result = parse(ris).unwrap();
         let doc = result.value;
         assert!(!doc.content.children.is_empty());
+
+        let entry = Entry::from_node(&doc.content.children[0]).unwrap();
+        assert_eq!(entry.id(), "smith2020");
+        assert_eq!(entry.entry_type(), "article-journal");
+        assert_eq!(entry.names(prop::AUTHOR)[1].given.as_deref(), Some("Jane"));
+        assert_eq!(entry.text(prop::CONTAINER_TITLE), Some("Nature"));
+        assert_eq!(entry.text(prop::PAGE), Some("45-67"));
+        assert_eq!(entry.date(prop::ISSUED).unwrap().year(), Some(2020));
     }
 
     #[test]
@@ -321,8 +399,7 @@ ER  -"#;
 
         let result = parse(ris).unwrap();
         let doc = result.value;
-        let def_list = &doc.content.children[0];
-        assert_eq!(def_list.children.len(), 2);
+        assert_eq!(doc.content.children.len(), 2);
     }
 
     #[test]
@@ -332,4 +409,38 @@ ER  -"#;
         let doc = result.value;
         assert!(doc.content.children.is_empty());
     }
+
+    #[test]
+    fn test_parse_details() {
+        let ris = r#"TY  - UNPB
+ID  - who2021
+AU  - World Health Organization
+AU  - King, Martin Luther, Jr.
+DA  - 2021/05/
+Y2  - Spring 2022
+KW  - health
+KW  - policy
+M1  - 42
+M1  - 43
+ER  -"#;
+
+        let doc = parse(ris).unwrap().value;
+        let entry = Entry::from_node(&doc.content.children[0]).unwrap();
+        assert_eq!(entry.id(), "who2021");
+        assert_eq!(entry.entry_type(), "manuscript");
+        let authors = entry.names(prop::AUTHOR);
+        assert_eq!(authors[0], Name::literal("World Health Organization"));
+        assert_eq!(authors[1].suffix.as_deref(), Some("Jr."));
+        assert_eq!(
+            entry.date(prop::ISSUED).unwrap().start,
+            Some(DateParts::new(2021, Some(5), None))
+        );
+        assert_eq!(
+            entry.date(prop::ACCESSED).unwrap().literal.as_deref(),
+            Some("Spring 2022")
+        );
+        assert_eq!(entry.text(prop::KEYWORD), Some("health, policy"));
+        assert_eq!(entry.custom("ris:M1"), Some("42\n43"));
+        assert_eq!(entry.custom("ris:TY"), Some("UNPB"));
+    }
 }
@@ -9,7 +9,7 @@ use rescribe_core::{
     ConversionResult, Document, FidelityWarning, ParseError, ParseOptions, Severity, Span,
     WarningKind,
 };
-use rescribe_std::{Node, node, prop};
+use rescribe_std::{Citation, Node, class, node, prop};
 
 /// Parse RST text into a rescribe Document.
 pub fn parse(input: &str) -> Result<ConversionResult<Document>, ParseError> {
@@ -403,8 +403,14 @@ impl<'a> Parser<'a> {
                 Some(node)
             }
             "contents" | "toc" => {
-                // Table of contents - represent as a div with class
-                let mut node = Node::new(node::DIV).prop("class", "toc".to_string());
+                // Table of contents marker, filled in by the GenerateToc transform
+                let mut node = Node::new(node::DIV).prop(prop::CLASSES, class::TOC);
+                if !argument.is_empty() {
+                    node = node.prop(prop::TITLE, argument.to_string());
+                }
+                if let Some(depth) = options.get("depth").and_then(|d| d.parse::<i64>().ok()) {
+                    node = node.prop(prop::DEPTH, depth);
+                }
                 node.span = self.make_span(start_line, self.line_idx);
                 Some(node)
             }
@@ -986,6 +992,22 @@ impl<'a> Parser<'a> {
                 }
             }
 
+            // Citation reference: [CIT2002]_
+            if chars[pos] == '[' {
+                if let Some((end, label)) = self.find_closing_char(&chars, pos + 1, ']') {
+                    if chars.get(end + 1) == Some(&'_') && is_citation_label(&label) {
+                        let source: String = chars[pos..end + 2].iter().collect();
+                        nodes.push(
+                            Node::new(node::CITE)
+                                .prop(prop::CITATIONS, Citation::list([Citation::new(label)]))
+                                .child(Node::new(node::TEXT).prop(prop::CONTENT, source)),
+                        );
+                        pos = end + 2;
+                        continue;
+                    }
+                }
+            }
+
             // Simple reference link: word_
             if chars[pos].is_alphanumeric() {
                 let mut word_end = pos;
@@ -1048,9 +1070,12 @@ impl<'a> Parser<'a> {
                 pos += 1;
             }
 
-            if !text.is_empty() {
-                nodes.push(Node::new(node::TEXT).prop(prop::CONTENT, text));
+            if text.is_empty() {
+                // A markup character that starts no markup
+                text.push(chars[pos]);
+                pos += 1;
             }
+            nodes.push(Node::new(node::TEXT).prop(prop::CONTENT, text));
         }
 
         // Merge adjacent text nodes
@@ -1103,6 +1128,16 @@ impl<'a> Parser<'a> {
     }
 }
 
+/// Whether a bracketed label is a citation rather than a footnote label
+/// (`1`, `#`, `#name` or `*`): a reference name that is not a number.
+fn is_citation_label(label: &str) -> bool {
+    label.starts_with(char::is_alphanumeric)
+        && !label.chars().all(|c| c.is_ascii_digit())
+        && label
+            .chars()
+            .all(|c| c.is_alphanumeric() || "-_.:+".contains(c))
+}
+
 /// Merge adjacent text nodes.
 fn merge_text_nodes(nodes: &mut Vec<Node>) {
     let mut i = 0;
@@ -1249,6 +1284,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_citation_reference() {
+        use rescribe_std::NodeExt;
+
+        let input = "As shown [CIT2002]_, not [1]_ or [a link].";
+        let doc = parse(input).unwrap().value;
+        let para = &root_children(&doc)[0];
+        let cites: Vec<_> = para.children.iter().filter_map(|n| n.as_cite()).collect();
+        assert_eq!(cites.len(), 1);
+        assert_eq!(cites[0].citations()[0].id, "CIT2002");
+        let text: String = para
+            .children
+            .iter()
+            .filter_map(|n| n.props.get_str(prop::CONTENT))
+            .collect();
+        assert_eq!(text, "As shown , not [1]_ or [a link].");
+    }
+
     #[test]
     fn test_parse_directive() {
         let input = ".. code-block:: python\n\n   print('hello')";
@@ -1260,4 +1313,16 @@ mod tests {
         assert_eq!(children[0].kind.as_str(), node::CODE_BLOCK);
         assert_eq!(children[0].props.get_str(prop::LANGUAGE), Some("python"));
     }
+
+    #[test]
+    fn test_parse_contents_directive() {
+        let input = ".. contents:: Table of Contents\n   :depth: 2\n\nTitle\n=====\n";
+        let doc = parse(input).unwrap().value;
+        let toc = &root_children(&doc)[0];
+        assert_eq!(toc.kind.as_str(), node::DIV);
+        assert_eq!(toc.props.get_str(prop::CLASSES), Some(class::TOC));
+        assert_eq!(toc.props.get_str(prop::TITLE), Some("Table of Contents"));
+        assert_eq!(toc.props.get_int(prop::DEPTH), Some(2));
+        assert!(toc.children.is_empty());
+    }
 }
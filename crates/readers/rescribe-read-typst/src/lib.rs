@@ -401,7 +401,12 @@ impl<'a> Parser<'a> {
                         "math_inline"
                     };
                     let math_content = if is_display { content.trim() } else { &content };
-                    nodes.push(Node::new(kind).prop("math:source", math_content));
+                    // Typst math syntax is not TeX
+                    nodes.push(
+                        Node::new(kind)
+                            .prop("math:format", "typst")
+                            .prop("math:source", math_content),
+                    );
                     i = end + 1;
                     continue;
                 }
@@ -1,13 +1,18 @@
 //! Rescribe CLI - Universal document converter.
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use rescribe::{
-    Document, djot, docx, epub, html, ipynb, latex, markdown, mediawiki, opml, org, pdf, plaintext,
-    xlsx,
+    ConversionResult, DiffOptions, Document, EmitOptions, FidelityWarning, FormatEntry,
+    FormatSpecError, OptionKind, OptionSpec, ParseOptions, Registry, Transformer,
+};
+use rescribe_transforms::{
+    CheckLinks, Citeproc, Dumbify, EmbedImages, ExtractMedia, FootnotePlacement, GenerateToc,
+    Locale, LuaFilter, NormalizeFootnotes, PandocFilter, ParseMath, Pipeline, QuoteLocale,
+    ResolveUrls, Smartify, Style,
 };
 use std::fs;
 use std::io::{self, Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(name = "rescribe")]
@@ -28,118 +33,220 @@ enum Commands {
         #[arg(short, long)]
         output: Option<PathBuf>,
 
-        /// Input format (auto-detected from extension if not specified)
+        /// Input format, with optional modifiers such as `markdown+smart`
+        /// (auto-detected from extension or content if not specified)
         #[arg(short, long)]
-        from: Option<Format>,
+        from: Option<String>,
 
-        /// Output format (required if output is stdout or has no extension)
+        /// Output format, with optional modifiers such as `html+section_divs`
+        /// (required if output is stdout or has no extension)
         #[arg(short, long)]
-        to: Option<Format>,
+        to: Option<String>,
+
+        #[command(flatten)]
+        transforms: Transforms,
+    },
+
+    /// Show structural differences between two documents
+    Diff {
+        /// Original document
+        old: PathBuf,
+
+        /// Changed document
+        new: PathBuf,
+
+        /// Input format for both documents (auto-detected if not specified)
+        #[arg(short, long)]
+        from: Option<String>,
+
+        /// Also report changed source positions
+        #[arg(long)]
+        spans: bool,
     },
 
     /// List available formats
     Formats,
+
+    /// List the reader and writer options of a format
+    Options {
+        /// Format name
+        format: String,
+    },
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
-enum Format {
-    Markdown,
-    Html,
-    Latex,
-    Org,
-    Plaintext,
-    Pdf,
-    Docx,
-    Ipynb,
-    Xlsx,
-    Epub,
-    Djot,
-    Opml,
-    Mediawiki,
+/// Transforms applied between reading and writing.
+#[derive(Args)]
+struct Transforms {
+    /// Parse TeX math into structured math nodes, before filters
+    #[arg(long)]
+    parse_math: bool,
+
+    /// Run a filter over the document: a Lua script (`.lua`) or a Pandoc
+    /// JSON filter program (repeatable, applied in order)
+    #[arg(long = "filter", value_name = "PROGRAM")]
+    filters: Vec<PathBuf>,
+
+    /// Format citations and append a bibliography, after filters (Markdown
+    /// citations are read with `-f markdown+citations`)
+    #[arg(short = 'C', long)]
+    citeproc: bool,
+
+    /// Bibliography for `--citeproc`: BibLaTeX (`.bib`), BibTeX
+    /// (`.bibtex`), CSL JSON (`.json`), RIS or EndNote XML (repeatable)
+    #[arg(long = "bibliography", value_name = "FILE")]
+    bibliographies: Vec<PathBuf>,
+
+    /// Citation style for `--citeproc`: a CSL file or a bundled style
+    /// (chicago-author-date, chicago-note or ieee; default
+    /// chicago-author-date)
+    #[arg(long, value_name = "STYLE")]
+    csl: Option<String>,
+
+    /// Locale for `--citeproc`: a CSL locale file or a bundled language
+    /// (en-US or de-DE; default from the document's `lang`)
+    #[arg(long, value_name = "LOCALE")]
+    citation_locale: Option<String>,
+
+    /// Generate a table of contents at `[TOC]` or `.. contents::`
+    /// markers, or at the start of the document
+    #[arg(long)]
+    toc: bool,
+
+    /// Deepest heading level listed in the table of contents
+    #[arg(long, value_name = "LEVEL", default_value_t = 3,
+          value_parser = clap::value_parser!(u8).range(1..=6))]
+    toc_depth: u8,
+
+    /// Convert straight quotes, dashes and ellipses to typographic ones,
+    /// with the quotation marks of a language (en, de or fr; default en)
+    #[arg(long, value_name = "LANG", require_equals = true, value_parser = parse_quote_locale)]
+    smart: Option<Option<QuoteLocale>>,
+
+    /// Resolve relative link and image URLs against a base path or URL
+    #[arg(long, value_name = "URL")]
+    base_url: Option<String>,
+
+    /// Embed local images into the document, for writers that support
+    /// embedded resources (such as HTML data URIs)
+    #[arg(long)]
+    embed_resources: bool,
+
+    /// Write embedded images and other resources to a directory and link
+    /// to the written files
+    #[arg(long, value_name = "DIR")]
+    extract_media: Option<PathBuf>,
+
+    /// Warn about links to fragments that match no identifier
+    #[arg(long)]
+    check_links: bool,
+
+    /// Replace typographic quotes, dashes and ellipses by ASCII
+    #[arg(long)]
+    ascii: bool,
 }
 
-impl Format {
-    fn from_extension(ext: &str) -> Option<Self> {
-        match ext.to_lowercase().as_str() {
-            "md" | "markdown" => Some(Format::Markdown),
-            "html" | "htm" => Some(Format::Html),
-            "tex" | "latex" => Some(Format::Latex),
-            "org" => Some(Format::Org),
-            "txt" | "text" => Some(Format::Plaintext),
-            "pdf" => Some(Format::Pdf),
-            "docx" => Some(Format::Docx),
-            "ipynb" => Some(Format::Ipynb),
-            "xlsx" => Some(Format::Xlsx),
-            "epub" => Some(Format::Epub),
-            "dj" | "djot" => Some(Format::Djot),
-            "opml" => Some(Format::Opml),
-            "mediawiki" | "wiki" => Some(Format::Mediawiki),
-            _ => None,
+impl Transforms {
+    /// The pipeline to run on a document read from `input`, telling JSON
+    /// filters the output format as Pandoc does.
+    fn pipeline(
+        &self,
+        registry: &Registry,
+        input: &Path,
+        target: &str,
+    ) -> Result<Pipeline, Box<dyn std::error::Error>> {
+        let mut pipeline = Pipeline::new();
+        if self.parse_math {
+            pipeline = pipeline.then(ParseMath);
         }
-    }
-
-    fn name(&self) -> &'static str {
-        match self {
-            Format::Markdown => "markdown",
-            Format::Html => "html",
-            Format::Latex => "latex",
-            Format::Org => "org",
-            Format::Plaintext => "plaintext",
-            Format::Pdf => "pdf",
-            Format::Docx => "docx",
-            Format::Ipynb => "ipynb",
-            Format::Xlsx => "xlsx",
-            Format::Epub => "epub",
-            Format::Djot => "djot",
-            Format::Opml => "opml",
-            Format::Mediawiki => "mediawiki",
+        if let Some(locale) = self.smart {
+            pipeline = pipeline.then(Smartify::new(locale.unwrap_or_default()));
         }
-    }
-
-    fn extensions(&self) -> &'static [&'static str] {
-        match self {
-            Format::Markdown => &["md", "markdown"],
-            Format::Html => &["html", "htm"],
-            Format::Latex => &["tex", "latex"],
-            Format::Org => &["org"],
-            Format::Plaintext => &["txt", "text"],
-            Format::Pdf => &["pdf"],
-            Format::Docx => &["docx"],
-            Format::Ipynb => &["ipynb"],
-            Format::Xlsx => &["xlsx"],
-            Format::Epub => &["epub"],
-            Format::Djot => &["dj", "djot"],
-            Format::Opml => &["opml"],
-            Format::Mediawiki => &["mediawiki", "wiki"],
+        if self.embed_resources {
+            // Images of stdin input are relative to the working directory
+            let dir = input.parent().unwrap_or(Path::new(""));
+            pipeline = pipeline.then(EmbedImages::new(dir));
         }
+        if let Some(base) = &self.base_url {
+            pipeline = pipeline.then(ResolveUrls::new(base));
+        }
+        for program in &self.filters {
+            pipeline = if program.extension().is_some_and(|ext| ext == "lua") {
+                pipeline.then(LuaFilter::from_file(program)?)
+            } else {
+                pipeline.then(PandocFilter::new(program).target_format(target))
+            };
+        }
+        if self.citeproc {
+            let citeproc = self.citation_processor(registry)?;
+            // Notes made for citations go where the writer expects notes
+            let notes = citeproc.style().is_note_style();
+            pipeline = pipeline.then(citeproc);
+            if notes {
+                let placement = FootnotePlacement::for_format(target);
+                pipeline = pipeline.then(NormalizeFootnotes::new(placement));
+            }
+        }
+        if self.toc {
+            pipeline = pipeline.then(GenerateToc::new().with_depth(self.toc_depth));
+        }
+        if self.check_links {
+            pipeline = pipeline.then(CheckLinks::new());
+        }
+        if let Some(dir) = &self.extract_media {
+            pipeline = pipeline.then(ExtractMedia::new(dir));
+        }
+        if self.ascii {
+            pipeline = pipeline.then(Dumbify);
+        }
+        Ok(pipeline)
     }
 
-    fn can_read(&self) -> bool {
-        matches!(
-            self,
-            Format::Markdown
-                | Format::Html
-                | Format::Pdf
-                | Format::Docx
-                | Format::Ipynb
-                | Format::Xlsx
-                | Format::Epub
-                | Format::Djot
-                | Format::Opml
-                | Format::Mediawiki
-        )
-    }
-
-    fn can_write(&self) -> bool {
-        !matches!(self, Format::Pdf | Format::Xlsx) // PDF and XLSX have no writer
+    /// The citation processor for `--citeproc`, with its bibliographies
+    /// read.
+    fn citation_processor(
+        &self,
+        registry: &Registry,
+    ) -> Result<Citeproc, Box<dyn std::error::Error>> {
+        let style = match &self.csl {
+            Some(name) => match Style::bundled(name) {
+                Some(style) => style,
+                None => {
+                    Style::parse(&fs::read_to_string(name)?).map_err(|e| format!("{name}: {e}"))?
+                }
+            },
+            None => Style::bundled("chicago-author-date").expect("style is bundled"),
+        };
+        let mut citeproc = Citeproc::new(style);
+        if let Some(name) = &self.citation_locale {
+            let locale = match Locale::bundled(name) {
+                Some(locale) => locale,
+                None => {
+                    Locale::parse(&fs::read_to_string(name)?).map_err(|e| format!("{name}: {e}"))?
+                }
+            };
+            citeproc = citeproc.with_locale(locale);
+        }
+        for path in &self.bibliographies {
+            // `.bib` is BibLaTeX, as for Pandoc; other extensions name their
+            // format, and anything else is detected from the content.
+            let format = match path.extension().and_then(|e| e.to_str()) {
+                Some("bib") => Some("biblatex"),
+                Some("bibtex") => Some("bibtex"),
+                Some("json") => Some("csl-json"),
+                Some("ris") => Some("ris"),
+                Some("xml") => Some("endnotexml"),
+                _ => None,
+            };
+            let bibliography = read_document(registry, path, format)?;
+            report_warnings(&bibliography.warnings);
+            citeproc = citeproc.with_bibliography(&bibliography.value);
+        }
+        Ok(citeproc)
     }
+}
 
-    fn is_binary(&self) -> bool {
-        matches!(
-            self,
-            Format::Pdf | Format::Docx | Format::Xlsx | Format::Epub
-        )
-    }
+fn parse_quote_locale(lang: &str) -> Result<QuoteLocale, String> {
+    QuoteLocale::for_language(lang).ok_or_else(|| format!("no quotation rules for '{lang}'"))
 }
 
 fn main() {
@@ -151,6 +258,7 @@ fn main() {
 
 fn run() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
+    let registry = Registry::builtin();
 
     match cli.command {
         Commands::Convert {
@@ -158,11 +266,25 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
             output,
             from,
             to,
+            transforms,
         } => {
-            convert(input, output, from, to)?;
+            convert(&registry, input, output, from, to, &transforms)?;
+        }
+        Commands::Diff {
+            old,
+            new,
+            from,
+            spans,
+        } => {
+            if !diff(&registry, &old, &new, from.as_deref(), spans)? {
+                std::process::exit(1);
+            }
         }
         Commands::Formats => {
-            list_formats();
+            list_formats(&registry);
+        }
+        Commands::Options { format } => {
+            list_options(lookup_format(&registry, &format)?);
         }
     }
 
@@ -170,181 +292,234 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 fn convert(
+    registry: &Registry,
     input: PathBuf,
     output: Option<PathBuf>,
-    from: Option<Format>,
-    to: Option<Format>,
+    from: Option<String>,
+    to: Option<String>,
+    transforms: &Transforms,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Determine input format
-    let input_format = from
-        .or_else(|| {
-            if input.as_os_str() == "-" {
-                None
-            } else {
-                input
-                    .extension()
-                    .and_then(|e| e.to_str())
-                    .and_then(Format::from_extension)
-            }
-        })
-        .ok_or("Cannot determine input format. Use --from to specify.")?;
-
-    if !input_format.can_read() {
-        return Err(format!("No reader available for {} format", input_format.name()).into());
-    }
-
     // Determine output format
-    let output_format = to
-        .or_else(|| {
-            output.as_ref().and_then(|p| {
-                if p.as_os_str() == "-" {
-                    None
-                } else {
-                    p.extension()
-                        .and_then(|e| e.to_str())
-                        .and_then(Format::from_extension)
-                }
-            })
-        })
-        .ok_or("Cannot determine output format. Use --to to specify.")?;
-
-    // Read input (binary for PDF, text for others)
-    let doc = if input_format.is_binary() {
-        let input_bytes = if input.as_os_str() == "-" {
-            let mut buf = Vec::new();
-            io::stdin().read_to_end(&mut buf)?;
-            buf
-        } else {
-            fs::read(&input)?
-        };
-        parse_binary(&input_bytes, input_format)?
-    } else {
-        let input_text = if input.as_os_str() == "-" {
-            let mut buf = String::new();
-            io::stdin().read_to_string(&mut buf)?;
-            buf
-        } else {
-            fs::read_to_string(&input)?
-        };
-        parse_text(&input_text, input_format)?
+    let (emitter, emit_options) = match to {
+        Some(spec) => registry.emitter_for_spec(&spec).map_err(spec_error)?,
+        None => {
+            let output_format = output
+                .as_deref()
+                .and_then(|p| detect_format(registry, p))
+                .ok_or("Cannot determine output format. Use --to to specify.")?;
+            let emitter = output_format.emitter().ok_or_else(|| {
+                format!("No writer available for {} format", output_format.name())
+            })?;
+            (emitter, EmitOptions::default())
+        }
     };
 
-    // Emit
-    let output_bytes = emit(&doc, output_format)?;
-
-    // Write output
-    match output {
-        Some(path) if path.as_os_str() != "-" => {
-            fs::write(&path, &output_bytes)?;
-        }
+    // Read and parse input
+    let parsed = read_document(registry, &input, from.as_deref())?;
+    let mut warnings = parsed.warnings;
+
+    // Transform
+    let target = emitter.formats().first().copied().unwrap_or_default();
+    let transformed = transforms
+        .pipeline(registry, &input, target)?
+        .transform(parsed.value)?;
+    warnings.extend(transformed.warnings);
+
+    // Emit, streaming to the output
+    let emit = |out: &mut dyn Write| emitter.emit_to(&transformed.value, &emit_options, out);
+    let emitted = match output {
+        Some(path) if path.as_os_str() != "-" => write_replacing(&path, emit)?,
         _ => {
-            io::stdout().write_all(&output_bytes)?;
+            let mut out = io::BufWriter::new(io::stdout().lock());
+            let emitted = emit(&mut out)?;
+            out.flush()?;
+            emitted
         }
-    }
+    };
+    warnings.extend(tag_warnings(emitted.warnings, target));
 
+    report_warnings(&warnings);
     Ok(())
 }
 
-fn parse_text(input: &str, format: Format) -> Result<Document, Box<dyn std::error::Error>> {
-    let result = match format {
-        Format::Markdown => markdown::parse(input)?,
-        Format::Html => html::parse(input)?,
-        Format::Ipynb => ipynb::parse(input)?,
-        Format::Djot => djot::parse(input)?,
-        Format::Opml => opml::parse(input)?,
-        Format::Mediawiki => mediawiki::parse(input)?,
-        Format::Latex
-        | Format::Org
-        | Format::Plaintext
-        | Format::Pdf
-        | Format::Docx
-        | Format::Xlsx
-        | Format::Epub => {
-            return Err(format!("No text reader for {} format", format.name()).into());
-        }
+/// Write `path` through a temporary file beside it, renamed into place once
+/// `write` and the flush succeed, so a failed conversion leaves an existing
+/// file as it was and no partial output behind.
+fn write_replacing<T, E: std::error::Error + 'static>(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> Result<T, E>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let name = path.file_name().ok_or("Output path has no file name")?;
+    let temp = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    let written = (|| -> Result<T, Box<dyn std::error::Error>> {
+        let mut out = io::BufWriter::new(fs::File::create(&temp)?);
+        let value = write(&mut out)?;
+        out.flush()?;
+        Ok(value)
+    })()
+    .and_then(|value| {
+        fs::rename(&temp, path)?;
+        Ok(value)
+    });
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written
+}
+
+/// Compare two documents, printing one line per edit. Returns whether they
+/// are structurally equal.
+fn diff(
+    registry: &Registry,
+    old: &Path,
+    new: &Path,
+    from: Option<&str>,
+    spans: bool,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let old = read_document(registry, old, from)?;
+    let new = read_document(registry, new, from)?;
+    report_warnings(&old.warnings);
+    report_warnings(&new.warnings);
+    let (old, new) = (old.value, new.value);
+    let options = DiffOptions {
+        compare_spans: spans,
+        ..Default::default()
     };
 
-    // Report warnings to stderr
-    for warning in &result.warnings {
-        eprintln!("warning: {}", warning.message);
+    let edits = rescribe::diff(&old, &new, &options);
+    for edit in &edits {
+        println!("{edit}");
     }
-
-    Ok(result.value)
+    Ok(edits.is_empty())
 }
 
-fn parse_binary(input: &[u8], format: Format) -> Result<Document, Box<dyn std::error::Error>> {
-    let result = match format {
-        Format::Pdf => pdf::parse(input)?,
-        Format::Docx => docx::parse_bytes(input)?,
-        Format::Xlsx => xlsx::parse_bytes(input)?,
-        Format::Epub => epub::parse_bytes(input)?,
-        _ => {
-            return Err(format!("No binary reader for {} format", format.name()).into());
+/// Read and parse a document, choosing the reader from `from`, the file
+/// extension, or the content (for stdin and files without a recognised
+/// extension).
+fn read_document(
+    registry: &Registry,
+    input: &Path,
+    from: Option<&str>,
+) -> Result<ConversionResult<Document>, Box<dyn std::error::Error>> {
+    let input_bytes = if input.as_os_str() == "-" {
+        let mut buf = Vec::new();
+        io::stdin().read_to_end(&mut buf)?;
+        buf
+    } else {
+        fs::read(input)?
+    };
+
+    let (parser, parse_options) = match from {
+        Some(spec) => registry.parser_for_spec(spec).map_err(spec_error)?,
+        None => {
+            let input_format = detect_format(registry, input)
+                .or_else(|| registry.detect(&input_bytes).map(|(format, _)| format))
+                .ok_or("Cannot determine input format. Use --from to specify.")?;
+            let parser = input_format
+                .parser()
+                .ok_or_else(|| format!("No reader available for {} format", input_format.name()))?;
+            (parser, ParseOptions::default())
         }
     };
 
-    // Report warnings to stderr
-    for warning in &result.warnings {
-        eprintln!("warning: {}", warning.message);
-    }
+    let parsed = parser.parse(&input_bytes, &parse_options)?;
+    let format = parser.formats().first().copied().unwrap_or_default();
+    let warnings = tag_warnings(parsed.warnings, format);
+    Ok(ConversionResult::with_warnings(parsed.value, warnings))
+}
 
-    Ok(result.value)
+fn lookup_format<'a>(registry: &'a Registry, name: &str) -> Result<&'a FormatEntry, String> {
+    registry.format(name).ok_or_else(|| {
+        format!("Unknown format '{name}'. Run `rescribe formats` to list available formats.")
+    })
 }
 
-fn emit(doc: &Document, format: Format) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-    let result = match format {
-        Format::Markdown => markdown::emit(doc)?,
-        Format::Html => html::emit(doc)?,
-        Format::Latex => latex::emit(doc)?,
-        Format::Org => org::emit(doc)?,
-        Format::Plaintext => plaintext::emit(doc)?,
-        Format::Docx => docx::emit(doc)?,
-        Format::Ipynb => ipynb::emit(doc)?,
-        Format::Epub => epub::emit(doc)?,
-        Format::Djot => djot::emit(doc)?,
-        Format::Opml => opml::emit(doc)?,
-        Format::Mediawiki => mediawiki::emit(doc)?,
-        Format::Pdf => {
-            return Err("PDF writer is not available (PDF is read-only)".into());
+fn spec_error(err: FormatSpecError) -> String {
+    match err {
+        FormatSpecError::UnknownFormat(name) => {
+            format!("Unknown format '{name}'. Run `rescribe formats` to list available formats.")
         }
-        Format::Xlsx => {
-            return Err("XLSX writer is not available (XLSX is read-only)".into());
+        FormatSpecError::Option { format, source } => {
+            format!("{format}: {source}. Run `rescribe options {format}` to list its options.")
         }
-    };
+        err => err.to_string(),
+    }
+}
 
-    // Report warnings to stderr
-    for warning in &result.warnings {
-        eprintln!("warning: {}", warning.message);
+fn detect_format<'a>(registry: &'a Registry, path: &Path) -> Option<&'a FormatEntry> {
+    if path.as_os_str() == "-" {
+        None
+    } else {
+        registry.format_for_path(path)
     }
+}
+
+/// Tag warnings with the reader or writer that reported them.
+fn tag_warnings(warnings: Vec<FidelityWarning>, origin: &str) -> Vec<FidelityWarning> {
+    warnings
+        .into_iter()
+        .map(|w| match w.origin {
+            Some(_) => w,
+            None => w.with_origin(origin),
+        })
+        .collect()
+}
 
-    Ok(result.value)
+fn report_warnings(warnings: &[FidelityWarning]) {
+    for warning in warnings {
+        match &warning.origin {
+            Some(origin) => eprintln!("warning: [{origin}] {}", warning.message),
+            None => eprintln!("warning: {}", warning.message),
+        }
+    }
 }
 
-fn list_formats() {
+fn list_formats(registry: &Registry) {
     println!("Available formats:\n");
-    println!("  {:12} {:6} {:6}  EXTENSIONS", "FORMAT", "READ", "WRITE");
-    println!("  {:12} {:6} {:6}  ----------", "------", "----", "-----");
-
-    let formats = [
-        Format::Markdown,
-        Format::Html,
-        Format::Latex,
-        Format::Org,
-        Format::Plaintext,
-        Format::Pdf,
-        Format::Docx,
-        Format::Ipynb,
-        Format::Xlsx,
-        Format::Epub,
-        Format::Djot,
-        Format::Opml,
-        Format::Mediawiki,
+    println!("  {:16} {:6} {:6}  EXTENSIONS", "FORMAT", "READ", "WRITE");
+    println!("  {:16} {:6} {:6}  ----------", "------", "----", "-----");
+
+    for format in registry.formats() {
+        let read = if format.can_read() { "yes" } else { "-" };
+        let write = if format.can_write() { "yes" } else { "-" };
+        let exts = format.info().extensions.join(", ");
+        println!("  {:16} {:6} {:6}  {}", format.name(), read, write, exts);
+    }
+}
+
+fn list_options(format: &FormatEntry) {
+    let sections = [
+        ("Reader", format.parser().map(|p| p.options())),
+        ("Writer", format.emitter().map(|e| e.options())),
     ];
+    for (title, options) in sections {
+        let Some(options) = options else {
+            continue;
+        };
+        println!("{title} options for {}:\n", format.name());
+        if options.is_empty() {
+            println!("  (none)");
+        }
+        for option in options {
+            println!(
+                "  {:22} {:10} {}",
+                option.name,
+                default_label(option),
+                option.description
+            );
+        }
+        println!();
+    }
+}
 
-    for fmt in formats {
-        let read = if fmt.can_read() { "yes" } else { "-" };
-        let write = if fmt.can_write() { "yes" } else { "-" };
-        let exts = fmt.extensions().join(", ");
-        println!("  {:12} {:6} {:6}  {}", fmt.name(), read, write, exts);
+fn default_label(option: &OptionSpec) -> String {
+    match option.kind {
+        OptionKind::Flag(true) => "+".to_string(),
+        OptionKind::Flag(false) => "-".to_string(),
+        _ => format!("={}", option.default_value()),
     }
 }
@@ -0,0 +1,689 @@
+//! Structural comparison of documents.
+//!
+//! [`diff`] compares two documents and returns the [`Edit`]s that turn the
+//! old one into the new one. Children are aligned with a longest common
+//! subsequence, so an inserted paragraph shows up as one insertion rather
+//! than a cascade of changes. By default spans are ignored and adjacent text
+//! nodes are merged before comparing, so documents that differ only in how a
+//! parser split its text compare equal.
+
+use crate::{Document, Node, NodePath, PropValue, Properties, Resource, ResourceId, Span};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Kind and property name of text nodes, as defined by `rescribe-std`.
+const TEXT: &str = "text";
+const CONTENT: &str = "content";
+
+/// Options controlling document comparison.
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    /// Report differing source spans.
+    pub compare_spans: bool,
+    /// Merge adjacent text nodes and drop empty ones before comparing.
+    pub normalize_text: bool,
+    /// Compare document metadata.
+    pub compare_metadata: bool,
+    /// Compare embedded resources.
+    pub compare_resources: bool,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            compare_spans: false,
+            normalize_text: true,
+            compare_metadata: true,
+            compare_resources: true,
+        }
+    }
+}
+
+/// A single difference between two documents.
+///
+/// Paths of removed nodes refer to the old document; all other paths refer
+/// to the new one. When text normalisation is enabled, paths index into the
+/// normalised trees.
+#[derive(Debug, Clone)]
+pub enum Edit {
+    /// A node present only in the new document.
+    NodeInserted { path: NodePath, node: Node },
+    /// A node present only in the old document.
+    NodeRemoved { path: NodePath, node: Node },
+    /// A node replaced by an unrelated one; subtrees are not compared.
+    NodeChanged {
+        path: NodePath,
+        old: Node,
+        new: Node,
+    },
+    /// A property added (`old` is `None`), removed (`new` is `None`) or changed.
+    PropChanged {
+        path: NodePath,
+        key: String,
+        old: Option<PropValue>,
+        new: Option<PropValue>,
+    },
+    /// A node's source span changed (only with [`DiffOptions::compare_spans`]).
+    SpanChanged {
+        path: NodePath,
+        old: Option<Span>,
+        new: Option<Span>,
+    },
+    /// A document metadata entry added, removed or changed.
+    MetadataChanged {
+        key: String,
+        old: Option<PropValue>,
+        new: Option<PropValue>,
+    },
+    /// An embedded resource added, removed or changed.
+    ResourceChanged {
+        id: ResourceId,
+        old: Option<Resource>,
+        new: Option<Resource>,
+    },
+}
+
+/// Compare two documents.
+///
+/// Source information is not compared.
+pub fn diff(old: &Document, new: &Document, options: &DiffOptions) -> Vec<Edit> {
+    let mut edits = Vec::new();
+
+    if options.compare_metadata {
+        for (key, old, new) in prop_changes(&old.metadata, &new.metadata) {
+            edits.push(Edit::MetadataChanged { key, old, new });
+        }
+    }
+
+    if options.compare_resources {
+        let ids: BTreeSet<&ResourceId> = old.resources.keys().chain(new.resources.keys()).collect();
+        for id in ids {
+            let (a, b) = (old.resources.get(id), new.resources.get(id));
+            if !resources_equal(a, b) {
+                edits.push(Edit::ResourceChanged {
+                    id: id.clone(),
+                    old: a.cloned(),
+                    new: b.cloned(),
+                });
+            }
+        }
+    }
+
+    edits.extend(diff_nodes(&old.content, &new.content, options));
+    edits
+}
+
+/// Compare two node trees, with paths relative to the given roots.
+pub fn diff_nodes(old: &Node, new: &Node, options: &DiffOptions) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    if options.normalize_text {
+        let (old, new) = (normalized(old), normalized(new));
+        Differ { options }.node(&old, &new, NodePath::root(), &mut edits);
+    } else {
+        Differ { options }.node(old, new, NodePath::root(), &mut edits);
+    }
+    edits
+}
+
+impl Document {
+    /// Check whether two documents are structurally equal under `options`.
+    pub fn structural_eq(&self, other: &Document, options: &DiffOptions) -> bool {
+        diff(self, other, options).is_empty()
+    }
+}
+
+impl Node {
+    /// Check whether two node trees are structurally equal under `options`.
+    pub fn structural_eq(&self, other: &Node, options: &DiffOptions) -> bool {
+        diff_nodes(self, other, options).is_empty()
+    }
+}
+
+struct Differ<'a> {
+    options: &'a DiffOptions,
+}
+
+impl Differ<'_> {
+    fn node(&self, old: &Node, new: &Node, path: NodePath, edits: &mut Vec<Edit>) {
+        if old.kind != new.kind {
+            edits.push(Edit::NodeChanged {
+                path,
+                old: old.clone(),
+                new: new.clone(),
+            });
+            return;
+        }
+
+        for (key, a, b) in prop_changes(&old.props, &new.props) {
+            edits.push(Edit::PropChanged {
+                path: path.clone(),
+                key,
+                old: a,
+                new: b,
+            });
+        }
+
+        if self.options.compare_spans && old.span != new.span {
+            edits.push(Edit::SpanChanged {
+                path: path.clone(),
+                old: old.span,
+                new: new.span,
+            });
+        }
+
+        self.children(&old.children, &new.children, &path, edits);
+    }
+
+    /// Align two child lists: exact matches first, then nodes of the same
+    /// kind within each unmatched gap, which are compared recursively.
+    fn children(&self, old: &[Node], new: &[Node], path: &NodePath, edits: &mut Vec<Edit>) {
+        let anchors = lcs(old, new, |a, b| self.equal(a, b));
+        let (mut i, mut j) = (0, 0);
+        for (ai, aj) in anchors.into_iter().chain([(old.len(), new.len())]) {
+            let pairs = lcs(&old[i..ai], &new[j..aj], |a, b| a.kind == b.kind);
+            let (mut gi, mut gj) = (i, j);
+            for (pi, pj) in pairs.into_iter().chain([(ai - i, aj - j)]) {
+                self.replace(&old[gi..i + pi], gi, &new[gj..j + pj], gj, path, edits);
+                if i + pi < ai {
+                    self.node(&old[i + pi], &new[j + pj], path.child(j + pj), edits);
+                }
+                gi = i + pi + 1;
+                gj = j + pj + 1;
+            }
+            i = ai + 1;
+            j = aj + 1;
+        }
+    }
+
+    /// Report unmatched runs: nodes at the same offset become changes, the
+    /// rest removals or insertions.
+    fn replace(
+        &self,
+        removed: &[Node],
+        old_start: usize,
+        inserted: &[Node],
+        new_start: usize,
+        path: &NodePath,
+        edits: &mut Vec<Edit>,
+    ) {
+        let common = removed.len().min(inserted.len());
+        for k in 0..common {
+            edits.push(Edit::NodeChanged {
+                path: path.child(new_start + k),
+                old: removed[k].clone(),
+                new: inserted[k].clone(),
+            });
+        }
+        for (k, node) in removed.iter().enumerate().skip(common) {
+            edits.push(Edit::NodeRemoved {
+                path: path.child(old_start + k),
+                node: node.clone(),
+            });
+        }
+        for (k, node) in inserted.iter().enumerate().skip(common) {
+            edits.push(Edit::NodeInserted {
+                path: path.child(new_start + k),
+                node: node.clone(),
+            });
+        }
+    }
+
+    fn equal(&self, a: &Node, b: &Node) -> bool {
+        a.kind == b.kind
+            && a.props == b.props
+            && (!self.options.compare_spans || a.span == b.span)
+            && a.children.len() == b.children.len()
+            && a.children
+                .iter()
+                .zip(&b.children)
+                .all(|(x, y)| self.equal(x, y))
+    }
+}
+
+/// Index pairs of a longest common subsequence of `a` and `b`.
+///
+/// Uses Myers' algorithm, splitting at the middle of the edit path, so time
+/// grows with the number of edits rather than the product of the lengths
+/// and memory stays linear.
+fn lcs<T>(a: &[T], b: &[T], eq: impl Fn(&T, &T) -> bool) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    common(a, b, (0, 0), &eq, &mut pairs);
+    pairs
+}
+
+/// Push the pairs of `a` and `b`, offset by `start`: the common prefix and
+/// suffix directly, and what lies between split at the middle of its
+/// shortest edit path.
+fn common<T>(
+    a: &[T],
+    b: &[T],
+    start: (usize, usize),
+    eq: &impl Fn(&T, &T) -> bool,
+    pairs: &mut Vec<(usize, usize)>,
+) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| eq(x, y)).count();
+    pairs.extend((0..prefix).map(|k| (start.0 + k, start.1 + k)));
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| eq(x, y))
+        .count();
+    let (n, m) = (a.len() - suffix, b.len() - suffix);
+    let start = (start.0 + prefix, start.1 + prefix);
+
+    if n > 0
+        && m > 0
+        && let Some((x, y)) = middle(&a[..n], &b[..m], eq)
+    {
+        common(&a[..x], &b[..y], start, eq, pairs);
+        common(&a[x..n], &b[y..m], (start.0 + x, start.1 + y), eq, pairs);
+    }
+    pairs.extend((0..suffix).map(|k| (start.0 + n + k, start.1 + m + k)));
+}
+
+/// Where the forward and backward searches for the shortest edit path from
+/// `a` to `b` meet, or `None` if they have nothing in common.
+fn middle<T>(a: &[T], b: &[T], eq: &impl Fn(&T, &T) -> bool) -> Option<(usize, usize)> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m + 1) / 2;
+    // The furthest `x` reached on each diagonal `k = x - y` (offset by
+    // `max`), from the start and, mirrored, from the end.
+    let mut forward = vec![-1; 2 * max as usize + 2];
+    let mut backward = forward.clone();
+    forward[max as usize + 1] = 0;
+    backward[max as usize + 1] = 0;
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let (mut k1_start, mut k1_end, mut k2_start, mut k2_end) = (0, 0, 0, 0);
+    for d in 0..max {
+        let mut k1 = -d + k1_start;
+        while k1 <= d - k1_end {
+            let i = (max + k1) as usize;
+            let mut x = if k1 == -d || (k1 != d && forward[i - 1] < forward[i + 1]) {
+                forward[i + 1]
+            } else {
+                forward[i - 1] + 1
+            };
+            let mut y = x - k1;
+            while x < n && y < m && eq(&a[x as usize], &b[y as usize]) {
+                x += 1;
+                y += 1;
+            }
+            forward[i] = x;
+            if x > n {
+                k1_end += 2;
+            } else if y > m {
+                k1_start += 2;
+            } else if odd {
+                let j = max + delta - k1;
+                if (0..backward.len() as isize).contains(&j)
+                    && backward[j as usize] != -1
+                    && x >= n - backward[j as usize]
+                {
+                    return Some((x as usize, y as usize));
+                }
+            }
+            k1 += 2;
+        }
+
+        let mut k2 = -d + k2_start;
+        while k2 <= d - k2_end {
+            let i = (max + k2) as usize;
+            let mut x = if k2 == -d || (k2 != d && backward[i - 1] < backward[i + 1]) {
+                backward[i + 1]
+            } else {
+                backward[i - 1] + 1
+            };
+            let mut y = x - k2;
+            while x < n && y < m && eq(&a[(n - x - 1) as usize], &b[(m - y - 1) as usize]) {
+                x += 1;
+                y += 1;
+            }
+            backward[i] = x;
+            if x > n {
+                k2_end += 2;
+            } else if y > m {
+                k2_start += 2;
+            } else if !odd {
+                let j = max + delta - k2;
+                if (0..forward.len() as isize).contains(&j) && forward[j as usize] != -1 {
+                    let x1 = forward[j as usize];
+                    let y1 = max + x1 - j;
+                    if x1 >= n - x {
+                        return Some((x1 as usize, y1 as usize));
+                    }
+                }
+            }
+            k2 += 2;
+        }
+    }
+    None
+}
+
+/// Added, removed and changed properties, sorted by key.
+fn prop_changes(
+    old: &Properties,
+    new: &Properties,
+) -> Vec<(String, Option<PropValue>, Option<PropValue>)> {
+    let keys: BTreeSet<&String> = old.iter().chain(new.iter()).map(|(k, _)| k).collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let (a, b) = (old.get(key), new.get(key));
+            (a != b).then(|| (key.clone(), a.cloned(), b.cloned()))
+        })
+        .collect()
+}
+
+fn resources_equal(a: Option<&Resource>, b: Option<&Resource>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            a.name == b.name
+                && a.mime_type == b.mime_type
+                && a.data == b.data
+                && a.metadata == b.metadata
+        }
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Copy of `node` with adjacent text children merged and empty ones dropped.
+fn normalized(node: &Node) -> Node {
+    let mut children: Vec<Node> = Vec::with_capacity(node.children.len());
+    for child in &node.children {
+        let child = normalized(child);
+        if let Some(text) = text_content(&child) {
+            if text.is_empty() {
+                continue;
+            }
+            if let Some(prev) = children.last_mut()
+                && let Some(prev_text) = text_content(prev)
+            {
+                let merged = format!("{prev_text}{text}");
+                prev.props.set(CONTENT, merged);
+                prev.span = match (prev.span, child.span) {
+                    (Some(a), Some(b)) => Some(Span {
+                        start: a.start,
+                        end: b.end,
+                    }),
+                    _ => None,
+                };
+                continue;
+            }
+        }
+        children.push(child);
+    }
+    Node {
+        kind: node.kind.clone(),
+        props: node.props.clone(),
+        children,
+        span: node.span,
+    }
+}
+
+/// Content of a plain text node: kind `text`, no children and no properties
+/// other than `content`.
+fn text_content(node: &Node) -> Option<&str> {
+    if node.kind.as_str() != TEXT || !node.children.is_empty() {
+        return None;
+    }
+    let content = node.props.get_str(CONTENT)?;
+    (node.props.len() == 1).then_some(content)
+}
+
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Edit::NodeInserted { path, node } => write!(f, "+ {path} {}", NodeSummary(node)),
+            Edit::NodeRemoved { path, node } => write!(f, "- {path} {}", NodeSummary(node)),
+            Edit::NodeChanged { path, old, new } => {
+                write!(f, "~ {path} {} -> {}", NodeSummary(old), NodeSummary(new))
+            }
+            Edit::PropChanged {
+                path,
+                key,
+                old,
+                new,
+            } => write!(f, "~ {path} {key}: {} -> {}", OptValue(old), OptValue(new)),
+            Edit::SpanChanged { path, old, new } => {
+                write!(f, "~ {path} span: {} -> {}", OptSpan(old), OptSpan(new))
+            }
+            Edit::MetadataChanged { key, old, new } => {
+                write!(
+                    f,
+                    "~ metadata {key}: {} -> {}",
+                    OptValue(old),
+                    OptValue(new)
+                )
+            }
+            Edit::ResourceChanged { id, old, new } => {
+                let symbol = match (old, new) {
+                    (None, _) => '+',
+                    (_, None) => '-',
+                    _ => '~',
+                };
+                write!(f, "{symbol} resource {}", id.as_str())
+            }
+        }
+    }
+}
+
+/// Node kind, plus the content of text nodes.
+struct NodeSummary<'a>(&'a Node);
+
+impl fmt::Display for NodeSummary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.kind)?;
+        if let Some(content) = self.0.props.get_str(CONTENT) {
+            write!(f, " {content:?}")?;
+        }
+        Ok(())
+    }
+}
+
+struct OptValue<'a>(&'a Option<PropValue>);
+
+impl fmt::Display for OptValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(value) => write_value(f, value),
+            None => f.write_str("(none)"),
+        }
+    }
+}
+
+fn write_value(f: &mut fmt::Formatter<'_>, value: &PropValue) -> fmt::Result {
+    match value {
+        PropValue::String(s) => write!(f, "{s:?}"),
+        PropValue::Int(i) => write!(f, "{i}"),
+        PropValue::Float(x) => write!(f, "{x}"),
+        PropValue::Bool(b) => write!(f, "{b}"),
+        PropValue::List(items) => {
+            f.write_str("[")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write_value(f, item)?;
+            }
+            f.write_str("]")
+        }
+        PropValue::Map(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            f.write_str("{")?;
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{key}: ")?;
+                write_value(f, item)?;
+            }
+            f.write_str("}")
+        }
+    }
+}
+
+struct OptSpan<'a>(&'a Option<Span>);
+
+impl fmt::Display for OptSpan<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(span) => write!(f, "{}..{}", span.start, span.end),
+            None => f.write_str("(none)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(content: &str) -> Node {
+        Node::new(TEXT).prop(CONTENT, content)
+    }
+
+    fn para(texts: &[&str]) -> Node {
+        Node::new("paragraph").children(texts.iter().map(|t| text(t)))
+    }
+
+    fn document(children: Vec<Node>) -> Document {
+        Document::new().with_content(Node::new("document").children(children))
+    }
+
+    fn render(edits: &[Edit]) -> Vec<String> {
+        edits.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_equal_documents() {
+        let a = document(vec![para(&["Hello"]), para(&["World"])]);
+        assert!(a.structural_eq(&a.clone(), &DiffOptions::default()));
+    }
+
+    #[test]
+    fn test_spans_ignored_by_default() {
+        let a = document(vec![para(&["Hi"]).span(Span { start: 0, end: 2 })]);
+        let b = document(vec![para(&["Hi"]).span(Span { start: 5, end: 7 })]);
+        assert!(a.structural_eq(&b, &DiffOptions::default()));
+
+        let options = DiffOptions {
+            compare_spans: true,
+            ..Default::default()
+        };
+        assert_eq!(render(&diff(&a, &b, &options)), ["~ /0 span: 0..2 -> 5..7"]);
+    }
+
+    #[test]
+    fn test_adjacent_text_normalised() {
+        let a = document(vec![para(&["Hello, ", "", "world"])]);
+        let b = document(vec![para(&["Hello, world"])]);
+        assert!(a.structural_eq(&b, &DiffOptions::default()));
+
+        let options = DiffOptions {
+            normalize_text: false,
+            ..Default::default()
+        };
+        assert!(!a.structural_eq(&b, &options));
+    }
+
+    #[test]
+    fn test_insert_and_remove_align() {
+        let a = document(vec![para(&["one"]), para(&["two"]), para(&["three"])]);
+        let b = document(vec![
+            para(&["one"]),
+            Node::new("horizontal_rule"),
+            para(&["three"]),
+        ]);
+        assert_eq!(
+            render(&diff(&a, &b, &DiffOptions::default())),
+            ["~ /1 paragraph -> horizontal_rule"]
+        );
+
+        let c = document(vec![para(&["zero"]), para(&["one"]), para(&["three"])]);
+        assert_eq!(
+            render(&diff(&a, &c, &DiffOptions::default())),
+            ["+ /0 paragraph", "- /1 paragraph"]
+        );
+
+        let d = document(vec![para(&["one"]), para(&["three"])]);
+        assert_eq!(
+            render(&diff(&a, &d, &DiffOptions::default())),
+            ["- /1 paragraph"]
+        );
+        assert_eq!(
+            render(&diff(&d, &a, &DiffOptions::default())),
+            ["+ /1 paragraph"]
+        );
+    }
+
+    #[test]
+    fn test_prop_and_metadata_changes() {
+        let mut a = document(vec![Node::new("heading").prop("level", 1)]);
+        let mut b = document(vec![
+            Node::new("heading").prop("level", 2).prop("id", "intro"),
+        ]);
+        a.metadata.set("title", "Old");
+        b.metadata.set("title", "New");
+
+        assert_eq!(
+            render(&diff(&a, &b, &DiffOptions::default())),
+            [
+                "~ metadata title: \"Old\" -> \"New\"",
+                "~ /0 id: (none) -> \"intro\"",
+                "~ /0 level: 1 -> 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resources() {
+        let mut a = document(vec![]);
+        let mut b = document(vec![]);
+        let id = ResourceId::from_string("img");
+        a.resources.insert(id.clone(), Resource::png(vec![1]));
+        b.resources.insert(id.clone(), Resource::png(vec![2]));
+        assert_eq!(
+            render(&diff(&a, &b, &DiffOptions::default())),
+            ["~ resource img"]
+        );
+
+        let options = DiffOptions {
+            compare_resources: false,
+            ..Default::default()
+        };
+        assert!(a.structural_eq(&b, &options));
+    }
+
+    #[test]
+    fn test_lcs() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        let (a, b) = (chars("xABCBDABy"), chars("xBDCABAy"));
+        let pairs = lcs(&a, &b, |x, y| x == y);
+        assert_eq!(pairs.len(), 6);
+        assert!(pairs.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1));
+        assert!(pairs.iter().all(|&(i, j)| a[i] == b[j]));
+
+        // Long lists with a few edits need no quadratic table.
+        let a: Vec<usize> = (0..200_000).collect();
+        let mut b = a.clone();
+        b.remove(70_000);
+        b.insert(120_000, 0);
+        assert_eq!(lcs(&a, &b, |x, y| x == y).len(), 199_999);
+    }
+
+    #[test]
+    fn test_node_path() {
+        let root = Node::new("document").child(para(&["a", "b"]));
+        let path = NodePath::root().child(0).child(1);
+        assert_eq!(path.to_string(), "/0/1");
+        assert_eq!(
+            root.at(&path).and_then(|n| n.props.get_str(CONTENT)),
+            Some("b")
+        );
+        assert_eq!(path.parent(), Some(NodePath::from(vec![0])));
+        assert!(root.at(&NodePath::from(vec![3])).is_none());
+    }
+}
@@ -3,6 +3,10 @@
 use crate::{Node, Properties, Resource, ResourceId, ResourceMap};
 
 /// A document with content and embedded resources.
+///
+/// With the `serde` feature, documents serialize with a `version` field
+/// (see [`FORMAT_VERSION`](crate::FORMAT_VERSION)); newer versions are
+/// rejected on deserialization.
 #[derive(Debug, Clone)]
 pub struct Document {
     /// Root content node.
@@ -17,10 +21,15 @@ pub struct Document {
 
 /// Information about the source format, for better roundtrip fidelity.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SourceInfo {
     /// Source format identifier (e.g., "markdown", "html", "docx").
     pub format: String,
     /// Format-specific metadata preserved for roundtrip.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Properties::is_empty")
+    )]
     pub metadata: Properties,
 }
 
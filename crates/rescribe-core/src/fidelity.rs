@@ -1,6 +1,6 @@
 //! Fidelity tracking - know what was lost in conversion.
 
-use crate::{ResourceId, Span};
+use crate::{NodePath, ResourceId, Span};
 
 /// Result of a conversion operation, including fidelity warnings.
 #[derive(Debug)]
@@ -55,6 +55,11 @@ pub struct FidelityWarning {
     pub message: String,
     /// Where in the source this occurred.
     pub span: Option<Span>,
+    /// Which node in the document tree this concerns.
+    pub path: Option<NodePath>,
+    /// Which stage of a conversion reported this, such as the name of a
+    /// transform in a pipeline.
+    pub origin: Option<String>,
 }
 
 impl FidelityWarning {
@@ -65,6 +70,8 @@ impl FidelityWarning {
             kind,
             message: message.into(),
             span: None,
+            path: None,
+            origin: None,
         }
     }
 
@@ -73,6 +80,24 @@ impl FidelityWarning {
         self.span = Some(span);
         self
     }
+
+    /// Set the path of the node this concerns.
+    pub fn at_path(mut self, path: NodePath) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    /// Set the stage that reported this warning.
+    pub fn with_origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = Some(origin.into());
+        self
+    }
+
+    /// Change the severity.
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
 }
 
 /// Severity of a fidelity warning.
@@ -101,4 +126,6 @@ pub enum WarningKind {
     ResourceFailed(ResourceId),
     /// Format-specific feature lost.
     FeatureLost(String),
+    /// Node does not match the schema for its kind.
+    InvalidStructure(String),
 }
@@ -2,17 +2,33 @@
 //!
 //! This crate provides the core types for representing documents in a
 //! format-agnostic way, enabling lossless conversion between formats.
+//!
+//! # Features
+//!
+//! - `serde` - Serialize and deserialize the document IR with serde
 
+mod diff;
 mod document;
 mod fidelity;
 mod node;
+mod options;
+mod path;
 mod properties;
 mod resource;
+mod schema;
+#[cfg(feature = "serde")]
+mod serialize;
 mod traits;
 
+pub use diff::*;
 pub use document::*;
 pub use fidelity::*;
 pub use node::*;
+pub use options::*;
+pub use path::*;
 pub use properties::*;
 pub use resource::*;
+pub use schema::*;
+#[cfg(feature = "serde")]
+pub use serialize::FORMAT_VERSION;
 pub use traits::*;
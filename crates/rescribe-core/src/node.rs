@@ -4,14 +4,27 @@ use crate::Properties;
 
 /// A content node in the document tree.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Node {
     /// Node type (e.g., "paragraph", "heading", "table").
     pub kind: NodeKind,
     /// Extensible properties for this node.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Properties::is_empty")
+    )]
     pub props: Properties,
     /// Child nodes.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
     pub children: Vec<Node>,
     /// Source location for error reporting.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub span: Option<Span>,
 }
 
@@ -20,10 +33,16 @@ pub struct Node {
 /// This is a newtype wrapper around String to allow any node kind.
 /// Standard node kinds are defined in `rescribe-std`.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct NodeKind(pub String);
 
 /// Source span for error reporting.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Span {
     pub start: usize,
     pub end: usize,
@@ -0,0 +1,343 @@
+//! Per-format reader and writer options.
+//!
+//! Readers and writers declare the options they understand as a list of
+//! [`OptionSpec`]s. Values chosen by the caller travel in a
+//! [`FormatOptions`] set on [`ParseOptions`](crate::ParseOptions) and
+//! [`EmitOptions`](crate::EmitOptions), and can be parsed from Pandoc-style
+//! modifier strings:
+//!
+//! ```rust
+//! use rescribe_core::{FormatOptions, OptionSpec};
+//!
+//! const SMART: OptionSpec = OptionSpec::flag("smart", false, "Curly quotes and dashes");
+//! const WIDTH: OptionSpec = OptionSpec::int("width", 72, "Line width");
+//!
+//! let options = FormatOptions::parse(&[SMART, WIDTH], "+smart+width=60").unwrap();
+//! assert!(options.flag(&SMART));
+//! assert_eq!(options.int(&WIDTH), 60);
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Type and default value of an option.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OptionKind {
+    /// On/off switch, toggled with `+name` and `-name`.
+    Flag(bool),
+    /// Integer, set with `+name=42`.
+    Int(i64),
+    /// Free-form string, set with `+name=value`.
+    String(&'static str),
+}
+
+/// Declaration of an option understood by a reader or writer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OptionSpec {
+    /// Option name, in `snake_case`.
+    pub name: &'static str,
+    /// Type and default value.
+    pub kind: OptionKind,
+    /// One-line description.
+    pub description: &'static str,
+}
+
+impl OptionSpec {
+    /// Declare a flag.
+    pub const fn flag(name: &'static str, default: bool, description: &'static str) -> Self {
+        Self {
+            name,
+            kind: OptionKind::Flag(default),
+            description,
+        }
+    }
+
+    /// Declare an integer option.
+    pub const fn int(name: &'static str, default: i64, description: &'static str) -> Self {
+        Self {
+            name,
+            kind: OptionKind::Int(default),
+            description,
+        }
+    }
+
+    /// Declare a string option.
+    pub const fn string(
+        name: &'static str,
+        default: &'static str,
+        description: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            kind: OptionKind::String(default),
+            description,
+        }
+    }
+
+    /// Get the default value.
+    pub fn default_value(&self) -> OptionValue {
+        match self.kind {
+            OptionKind::Flag(b) => OptionValue::Bool(b),
+            OptionKind::Int(i) => OptionValue::Int(i),
+            OptionKind::String(s) => OptionValue::String(s.to_string()),
+        }
+    }
+
+    /// Parse a value given as text.
+    pub fn parse_value(&self, value: &str) -> Result<OptionValue, OptionError> {
+        let invalid = |expected| OptionError::InvalidValue {
+            name: self.name.to_string(),
+            value: value.to_string(),
+            expected,
+        };
+        match self.kind {
+            OptionKind::Flag(_) => match value {
+                "true" | "yes" | "on" => Ok(OptionValue::Bool(true)),
+                "false" | "no" | "off" => Ok(OptionValue::Bool(false)),
+                _ => Err(invalid("a boolean")),
+            },
+            OptionKind::Int(_) => value
+                .parse()
+                .map(OptionValue::Int)
+                .map_err(|_| invalid("an integer")),
+            OptionKind::String(_) => Ok(OptionValue::String(value.to_string())),
+        }
+    }
+}
+
+/// Value of an option.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionValue {
+    Bool(bool),
+    Int(i64),
+    String(String),
+}
+
+impl fmt::Display for OptionValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionValue::Bool(b) => write!(f, "{b}"),
+            OptionValue::Int(i) => write!(f, "{i}"),
+            OptionValue::String(s) => f.write_str(s),
+        }
+    }
+}
+
+impl From<bool> for OptionValue {
+    fn from(b: bool) -> Self {
+        OptionValue::Bool(b)
+    }
+}
+
+impl From<i64> for OptionValue {
+    fn from(i: i64) -> Self {
+        OptionValue::Int(i)
+    }
+}
+
+impl From<&str> for OptionValue {
+    fn from(s: &str) -> Self {
+        OptionValue::String(s.to_string())
+    }
+}
+
+impl From<String> for OptionValue {
+    fn from(s: String) -> Self {
+        OptionValue::String(s)
+    }
+}
+
+/// Error in an option string or value.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum OptionError {
+    #[error("unknown option '{0}'")]
+    Unknown(String),
+    #[error("option '{name}' expects {expected}, found '{value}'")]
+    InvalidValue {
+        name: String,
+        value: String,
+        expected: &'static str,
+    },
+    #[error("option '{0}' needs a value: use +{0}=VALUE")]
+    MissingValue(String),
+    #[error("invalid option syntax: '{0}'")]
+    Syntax(String),
+}
+
+/// Option values chosen for a reader or writer.
+///
+/// Only explicitly set options are stored; the typed getters fall back to
+/// the default declared in the [`OptionSpec`] (also when the stored value
+/// has the wrong type).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FormatOptions {
+    values: BTreeMap<String, OptionValue>,
+}
+
+impl FormatOptions {
+    /// Create an empty option set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse modifiers such as `+smart-footnotes+width=60` against the
+    /// declared options.
+    ///
+    /// `+name` enables a flag, `-name` disables it, and `+name=value` sets
+    /// any option. A value runs up to the next `+`.
+    pub fn parse(specs: &[OptionSpec], modifiers: &str) -> Result<Self, OptionError> {
+        let mut options = Self::new();
+        options.apply(specs, modifiers)?;
+        Ok(options)
+    }
+
+    /// Apply modifiers on top of the current values. See [`parse`](Self::parse).
+    pub fn apply(&mut self, specs: &[OptionSpec], modifiers: &str) -> Result<(), OptionError> {
+        let mut rest = modifiers;
+        while let Some(sign) = rest.chars().next() {
+            let enable = match sign {
+                '+' => true,
+                '-' => false,
+                _ => return Err(OptionError::Syntax(rest.to_string())),
+            };
+            let body = &rest[1..];
+            let name_len = body
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(body.len());
+            let name = &body[..name_len];
+            if name.is_empty() {
+                return Err(OptionError::Syntax(rest.to_string()));
+            }
+            let spec = specs
+                .iter()
+                .find(|s| s.name == name)
+                .ok_or_else(|| OptionError::Unknown(name.to_string()))?;
+
+            rest = &body[name_len..];
+            let value = match rest.strip_prefix('=') {
+                Some(tail) if enable => {
+                    let end = tail.find('+').unwrap_or(tail.len());
+                    rest = &tail[end..];
+                    spec.parse_value(&tail[..end])?
+                }
+                Some(_) => return Err(OptionError::Syntax(format!("{sign}{body}"))),
+                None => match spec.kind {
+                    OptionKind::Flag(_) => OptionValue::Bool(enable),
+                    _ => return Err(OptionError::MissingValue(name.to_string())),
+                },
+            };
+            self.values.insert(name.to_string(), value);
+        }
+        Ok(())
+    }
+
+    /// Set an option.
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<OptionValue>) {
+        self.values.insert(name.into(), value.into());
+    }
+
+    /// Set an option (builder style).
+    pub fn with(mut self, name: impl Into<String>, value: impl Into<OptionValue>) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    /// Get an explicitly set value.
+    pub fn get(&self, name: &str) -> Option<&OptionValue> {
+        self.values.get(name)
+    }
+
+    /// Get a flag, or its default.
+    pub fn flag(&self, spec: &OptionSpec) -> bool {
+        match (self.get(spec.name), spec.kind) {
+            (Some(OptionValue::Bool(b)), _) => *b,
+            (_, OptionKind::Flag(default)) => default,
+            _ => false,
+        }
+    }
+
+    /// Get an integer option, or its default.
+    pub fn int(&self, spec: &OptionSpec) -> i64 {
+        match (self.get(spec.name), spec.kind) {
+            (Some(OptionValue::Int(i)), _) => *i,
+            (_, OptionKind::Int(default)) => default,
+            _ => 0,
+        }
+    }
+
+    /// Get a string option, or its default.
+    pub fn string<'a>(&'a self, spec: &OptionSpec) -> &'a str {
+        match (self.get(spec.name), spec.kind) {
+            (Some(OptionValue::String(s)), _) => s,
+            (_, OptionKind::String(default)) => default,
+            _ => "",
+        }
+    }
+
+    /// Iterate over explicitly set options, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &OptionValue)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Check if no option is set.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMART: OptionSpec = OptionSpec::flag("smart", false, "Smart punctuation");
+    const FOOTNOTES: OptionSpec = OptionSpec::flag("footnotes", true, "Footnotes");
+    const WIDTH: OptionSpec = OptionSpec::int("width", 72, "Line width");
+    const CLASS: OptionSpec = OptionSpec::string("document_class", "article", "Class");
+    const SPECS: &[OptionSpec] = &[SMART, FOOTNOTES, WIDTH, CLASS];
+
+    #[test]
+    fn test_defaults() {
+        let options = FormatOptions::new();
+        assert!(!options.flag(&SMART));
+        assert!(options.flag(&FOOTNOTES));
+        assert_eq!(options.int(&WIDTH), 72);
+        assert_eq!(options.string(&CLASS), "article");
+    }
+
+    #[test]
+    fn test_parse_modifiers() {
+        let options =
+            FormatOptions::parse(SPECS, "+smart-footnotes+document_class=scr-book+width=60")
+                .unwrap();
+        assert!(options.flag(&SMART));
+        assert!(!options.flag(&FOOTNOTES));
+        assert_eq!(options.int(&WIDTH), 60);
+        assert_eq!(options.string(&CLASS), "scr-book");
+        assert_eq!(options.iter().count(), 4);
+        assert!(FormatOptions::parse(SPECS, "").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = |m| FormatOptions::parse(SPECS, m).unwrap_err();
+        assert_eq!(err("+fancy"), OptionError::Unknown("fancy".into()));
+        assert_eq!(err("+width"), OptionError::MissingValue("width".into()));
+        assert_eq!(err("smart"), OptionError::Syntax("smart".into()));
+        assert_eq!(err("-width=3"), OptionError::Syntax("-width=3".into()));
+        assert_eq!(err("+"), OptionError::Syntax("+".into()));
+        assert_eq!(
+            err("+width=wide").to_string(),
+            "option 'width' expects an integer, found 'wide'"
+        );
+        assert!(FormatOptions::parse(SPECS, "+smart=off").is_ok());
+    }
+
+    #[test]
+    fn test_wrong_type_falls_back_to_default() {
+        let options = FormatOptions::new()
+            .with("width", "wide")
+            .with("smart", true);
+        assert_eq!(options.int(&WIDTH), 72);
+        assert!(options.flag(&SMART));
+    }
+}
@@ -0,0 +1,84 @@
+//! Addressing nodes within a document tree.
+
+use crate::Node;
+use std::fmt;
+
+/// Address of a node, as the child indices leading to it from a root node.
+///
+/// The empty path is the root itself. Paths display as `/0/2/1` (root: `/`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct NodePath(Vec<usize>);
+
+impl NodePath {
+    /// The path of the root node.
+    pub fn root() -> Self {
+        Self(Vec::new())
+    }
+
+    /// The path of this node's child at `index`.
+    pub fn child(&self, index: usize) -> Self {
+        let mut indices = self.0.clone();
+        indices.push(index);
+        Self(indices)
+    }
+
+    /// The path of this node's parent, or `None` for the root.
+    pub fn parent(&self) -> Option<Self> {
+        let (_, parent) = self.0.split_last()?;
+        Some(Self(parent.to_vec()))
+    }
+
+    /// The child indices from the root.
+    pub fn indices(&self) -> &[usize] {
+        &self.0
+    }
+
+    /// Number of steps from the root.
+    pub fn depth(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Check if this is the root path.
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Vec<usize>> for NodePath {
+    fn from(indices: Vec<usize>) -> Self {
+        Self(indices)
+    }
+}
+
+impl fmt::Display for NodePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("/");
+        }
+        for index in &self.0 {
+            write!(f, "/{index}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Node {
+    /// Get the descendant at `path`, relative to this node.
+    pub fn at(&self, path: &NodePath) -> Option<&Node> {
+        path.0
+            .iter()
+            .try_fold(self, |node, &index| node.children.get(index))
+    }
+
+    /// Get the descendant at `path` mutably, relative to this node.
+    pub fn at_mut(&mut self, path: &NodePath) -> Option<&mut Node> {
+        path.0
+            .iter()
+            .try_fold(self, |node, &index| node.children.get_mut(index))
+    }
+}
@@ -3,18 +3,36 @@
 use std::collections::HashMap;
 
 /// A collection of properties (key-value pairs).
-#[derive(Debug, Clone, Default)]
-pub struct Properties(HashMap<String, PropValue>);
+///
+/// With the `serde` feature, properties serialize as a map sorted by key so
+/// output is deterministic.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(transparent))]
+pub struct Properties(pub(crate) HashMap<String, PropValue>);
 
 /// A property value.
+///
+/// With the `serde` feature, values are externally tagged (`{"int": 2}`) so
+/// integers, floats and strings survive any serde format unchanged.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum PropValue {
     String(String),
     Int(i64),
     Float(f64),
     Bool(bool),
     List(Vec<PropValue>),
-    Map(HashMap<String, PropValue>),
+    Map(
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::serialize::sorted_map")
+        )]
+        HashMap<String, PropValue>,
+    ),
 }
 
 impl Properties {
@@ -4,22 +4,40 @@ use crate::Properties;
 use std::collections::HashMap;
 
 /// Unique identifier for an embedded resource.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct ResourceId(String);
 
 /// Map of resource IDs to resources.
 pub type ResourceMap = HashMap<ResourceId, Resource>;
 
 /// An embedded resource (image, font, data file, etc.).
+///
+/// With the `serde` feature, `data` is written as base64 in human-readable
+/// formats and as raw bytes otherwise.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Resource {
     /// Original filename or identifier.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub name: Option<String>,
     /// MIME type.
     pub mime_type: String,
     /// Raw data.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::base64"))]
     pub data: Vec<u8>,
     /// Resource metadata.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Properties::is_empty")
+    )]
     pub metadata: Properties,
 }
 
@@ -41,6 +59,29 @@ impl ResourceId {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// The ID reduced to something usable as a file name: anything but
+    /// letters, digits, `-` and `_` (path separators and dots included)
+    /// becomes `_`, so IDs from untrusted input cannot name a path outside
+    /// the directory a resource is written to.
+    pub fn safe_stem(&self) -> String {
+        let stem: String = self
+            .0
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        if stem.is_empty() {
+            "resource".to_string()
+        } else {
+            stem
+        }
+    }
 }
 
 impl Default for ResourceId {
@@ -0,0 +1,437 @@
+//! Declarative schemas for node kinds and their properties.
+//!
+//! A [`Schema`] describes, per node kind, which children it may contain,
+//! how many, and which properties it requires. Node vocabularies such as
+//! `rescribe-std` publish their schema so documents can be checked before
+//! they reach a writer.
+
+use crate::{Document, FidelityWarning, Node, NodePath, PropValue, Severity, WarningKind};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Expected type of a property value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropType {
+    String,
+    Int,
+    Float,
+    /// An integer or a float.
+    Number,
+    Bool,
+    List,
+    Map,
+    /// A string or a list (e.g. `classes`).
+    StringOrList,
+    Any,
+}
+
+impl PropType {
+    /// Check whether `value` has this type.
+    pub fn accepts(self, value: &PropValue) -> bool {
+        matches!(
+            (self, value),
+            (PropType::Any, _)
+                | (PropType::String, PropValue::String(_))
+                | (PropType::Int, PropValue::Int(_))
+                | (PropType::Float, PropValue::Float(_))
+                | (PropType::Number, PropValue::Int(_) | PropValue::Float(_))
+                | (PropType::Bool, PropValue::Bool(_))
+                | (PropType::List, PropValue::List(_))
+                | (PropType::Map, PropValue::Map(_))
+                | (
+                    PropType::StringOrList,
+                    PropValue::String(_) | PropValue::List(_)
+                )
+        )
+    }
+}
+
+impl fmt::Display for PropType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PropType::String => "string",
+            PropType::Int => "int",
+            PropType::Float => "float",
+            PropType::Number => "number",
+            PropType::Bool => "bool",
+            PropType::List => "list",
+            PropType::Map => "map",
+            PropType::StringOrList => "string or list",
+            PropType::Any => "any",
+        })
+    }
+}
+
+fn value_type(value: &PropValue) -> &'static str {
+    match value {
+        PropValue::String(_) => "string",
+        PropValue::Int(_) => "int",
+        PropValue::Float(_) => "float",
+        PropValue::Bool(_) => "bool",
+        PropValue::List(_) => "list",
+        PropValue::Map(_) => "map",
+    }
+}
+
+/// A declared property of a node kind.
+#[derive(Debug, Clone)]
+pub struct PropSpec {
+    pub key: String,
+    pub ty: PropType,
+    pub required: bool,
+}
+
+/// Schema for a single node kind.
+///
+/// Children are constrained by name: each entry of [`children`](Self::children)
+/// is either a node kind or a category (such as `"block"` or `"inline"`)
+/// that kinds declare with [`category`](Self::category).
+#[derive(Debug, Clone, Default)]
+pub struct KindSchema {
+    categories: Vec<String>,
+    /// Allowed child kinds or categories; `None` allows anything.
+    children: Option<Vec<String>>,
+    min_children: usize,
+    max_children: Option<usize>,
+    props: Vec<PropSpec>,
+}
+
+impl KindSchema {
+    /// A kind that allows any children and declares no properties.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add this kind to a category.
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.categories.push(category.into());
+        self
+    }
+
+    /// Restrict children to the given kinds and categories.
+    pub fn children(mut self, allowed: &[&str]) -> Self {
+        self.children = Some(allowed.iter().map(|s| s.to_string()).collect());
+        self
+    }
+
+    /// Disallow children.
+    pub fn leaf(mut self) -> Self {
+        self.children = Some(Vec::new());
+        self.max_children = Some(0);
+        self
+    }
+
+    /// Require between `min` and `max` children (inclusive).
+    pub fn arity(mut self, min: usize, max: Option<usize>) -> Self {
+        self.min_children = min;
+        self.max_children = max;
+        self
+    }
+
+    /// Declare a required property.
+    pub fn required(mut self, key: impl Into<String>, ty: PropType) -> Self {
+        self.props.push(PropSpec {
+            key: key.into(),
+            ty,
+            required: true,
+        });
+        self
+    }
+
+    /// Declare an optional property, checked only when present.
+    pub fn optional(mut self, key: impl Into<String>, ty: PropType) -> Self {
+        self.props.push(PropSpec {
+            key: key.into(),
+            ty,
+            required: false,
+        });
+        self
+    }
+
+    /// Categories this kind belongs to.
+    pub fn categories(&self) -> &[String] {
+        &self.categories
+    }
+
+    /// Declared properties.
+    pub fn props(&self) -> &[PropSpec] {
+        &self.props
+    }
+}
+
+/// A set of node kind schemas.
+///
+/// Kinds the schema does not know are accepted anywhere and not checked,
+/// unless the schema is [closed](Self::closed). Undeclared properties are
+/// always allowed.
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    kinds: HashMap<String, KindSchema>,
+    root: Option<String>,
+    closed: bool,
+}
+
+impl Schema {
+    /// Create an empty schema.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace the schema for a node kind.
+    pub fn kind(mut self, kind: impl Into<String>, schema: KindSchema) -> Self {
+        self.kinds.insert(kind.into(), schema);
+        self
+    }
+
+    /// Require the document root to be of this kind.
+    pub fn root(mut self, kind: impl Into<String>) -> Self {
+        self.root = Some(kind.into());
+        self
+    }
+
+    /// Report node kinds the schema does not define.
+    pub fn closed(mut self) -> Self {
+        self.closed = true;
+        self
+    }
+
+    /// Add all kinds from another schema, replacing duplicates.
+    pub fn merge(mut self, other: Schema) -> Self {
+        self.kinds.extend(other.kinds);
+        if other.root.is_some() {
+            self.root = other.root;
+        }
+        self.closed |= other.closed;
+        self
+    }
+
+    /// Get the schema for a node kind.
+    pub fn get(&self, kind: &str) -> Option<&KindSchema> {
+        self.kinds.get(kind)
+    }
+
+    /// Check a document, returning one warning per violation.
+    pub fn validate(&self, doc: &Document) -> Vec<FidelityWarning> {
+        let mut warnings = Vec::new();
+        if let Some(root) = &self.root
+            && doc.content.kind.as_str() != root
+        {
+            warnings.push(violation(
+                &doc.content,
+                NodePath::root(),
+                format!(
+                    "document root is '{}', expected '{root}'",
+                    doc.content.kind.as_str()
+                ),
+            ));
+        }
+        self.check(&doc.content, NodePath::root(), &mut warnings);
+        sort_by_path(&mut warnings);
+        warnings
+    }
+
+    /// Check a node tree, with paths relative to `node`.
+    pub fn validate_node(&self, node: &Node) -> Vec<FidelityWarning> {
+        let mut warnings = Vec::new();
+        self.check(node, NodePath::root(), &mut warnings);
+        sort_by_path(&mut warnings);
+        warnings
+    }
+
+    fn check(&self, node: &Node, path: NodePath, warnings: &mut Vec<FidelityWarning>) {
+        let kind = node.kind.as_str();
+        match self.kinds.get(kind) {
+            Some(schema) => self.check_kind(node, schema, &path, warnings),
+            None if self.closed => warnings.push(
+                violation(node, path.clone(), format!("unknown node kind '{kind}'"))
+                    .with_severity(Severity::Minor),
+            ),
+            None => {}
+        }
+
+        for (index, child) in node.children.iter().enumerate() {
+            self.check(child, path.child(index), warnings);
+        }
+    }
+
+    fn check_kind(
+        &self,
+        node: &Node,
+        schema: &KindSchema,
+        path: &NodePath,
+        warnings: &mut Vec<FidelityWarning>,
+    ) {
+        let kind = node.kind.as_str();
+
+        for spec in &schema.props {
+            match node.props.get(&spec.key) {
+                None if spec.required => warnings.push(violation(
+                    node,
+                    path.clone(),
+                    format!("{kind} is missing required property '{}'", spec.key),
+                )),
+                Some(value) if !spec.ty.accepts(value) => warnings.push(violation(
+                    node,
+                    path.clone(),
+                    format!(
+                        "property '{}' of {kind} should be {}, found {}",
+                        spec.key,
+                        spec.ty,
+                        value_type(value)
+                    ),
+                )),
+                _ => {}
+            }
+        }
+
+        let count = node.children.len();
+        if count < schema.min_children || schema.max_children.is_some_and(|max| count > max) {
+            let expected = match (schema.min_children, schema.max_children) {
+                (_, Some(0)) => "no children".to_string(),
+                (min, Some(max)) if min == max => format!("{min} children"),
+                (min, Some(max)) => format!("{min} to {max} children"),
+                (min, None) => format!("at least {min} children"),
+            };
+            warnings.push(violation(
+                node,
+                path.clone(),
+                format!("{kind} expects {expected}, found {count}"),
+            ));
+        }
+
+        let Some(allowed) = &schema.children else {
+            return;
+        };
+        if allowed.is_empty() {
+            return;
+        }
+        for (index, child) in node.children.iter().enumerate() {
+            if !self.allows(allowed, child.kind.as_str()) {
+                warnings.push(violation(
+                    child,
+                    path.child(index),
+                    format!("{} is not allowed in {kind}", child.kind.as_str()),
+                ));
+            }
+        }
+    }
+
+    fn allows(&self, allowed: &[String], kind: &str) -> bool {
+        let Some(schema) = self.kinds.get(kind) else {
+            return !self.closed;
+        };
+        allowed
+            .iter()
+            .any(|name| name == kind || schema.categories.contains(name))
+    }
+}
+
+/// Put warnings in document order.
+fn sort_by_path(warnings: &mut [FidelityWarning]) {
+    warnings.sort_by(|a, b| a.path.cmp(&b.path));
+}
+
+fn violation(node: &Node, path: NodePath, message: String) -> FidelityWarning {
+    let kind = WarningKind::InvalidStructure(node.kind.as_str().to_string());
+    let warning = FidelityWarning::new(Severity::Major, kind, message).at_path(path);
+    match node.span {
+        Some(span) => warning.at(span),
+        None => warning,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::new()
+            .root("document")
+            .kind("document", KindSchema::new().children(&["block"]))
+            .kind(
+                "heading",
+                KindSchema::new()
+                    .category("block")
+                    .children(&["inline"])
+                    .required("level", PropType::Int),
+            )
+            .kind("row", KindSchema::new().children(&["cell"]))
+            .kind("cell", KindSchema::new().children(&["inline"]))
+            .kind(
+                "text",
+                KindSchema::new()
+                    .category("inline")
+                    .leaf()
+                    .required("content", PropType::String),
+            )
+            .kind(
+                "pair",
+                KindSchema::new().category("block").arity(2, Some(2)),
+            )
+    }
+
+    fn messages(warnings: &[FidelityWarning]) -> Vec<String> {
+        warnings
+            .iter()
+            .map(|w| format!("{} {}", w.path.as_ref().unwrap(), w.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_document() {
+        let doc = Document::new().with_content(
+            Node::new("document").child(
+                Node::new("heading")
+                    .prop("level", 1)
+                    .child(Node::new("text").prop("content", "Hi")),
+            ),
+        );
+        assert!(schema().validate(&doc).is_empty());
+    }
+
+    #[test]
+    fn test_violations_are_path_addressed() {
+        let doc = Document::new().with_content(
+            Node::new("document")
+                .child(Node::new("heading").child(Node::new("text")))
+                .child(Node::new("row"))
+                .child(Node::new("pair").child(Node::new("text").prop("content", 3))),
+        );
+        assert_eq!(
+            messages(&schema().validate(&doc)),
+            [
+                "/0 heading is missing required property 'level'",
+                "/0/0 text is missing required property 'content'",
+                "/1 row is not allowed in document",
+                "/2 pair expects 2 children, found 1",
+                "/2/0 property 'content' of text should be string, found int",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_kinds() {
+        let doc = Document::new()
+            .with_content(Node::new("document").child(Node::new("custom").child(Node::new("row"))));
+        assert!(schema().validate(&doc).is_empty());
+
+        let warnings = schema().closed().validate(&doc);
+        assert_eq!(
+            messages(&warnings),
+            [
+                "/0 custom is not allowed in document",
+                "/0 unknown node kind 'custom'"
+            ]
+        );
+        assert_eq!(warnings[1].severity, Severity::Minor);
+    }
+
+    #[test]
+    fn test_root_kind() {
+        let doc = Document::new().with_content(Node::new("heading").prop("level", 1));
+        assert_eq!(
+            messages(&schema().validate(&doc)),
+            ["/ document root is 'heading', expected 'document'"]
+        );
+    }
+}
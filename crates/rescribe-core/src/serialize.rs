@@ -0,0 +1,149 @@
+//! Serde support for the document IR (enabled by the `serde` feature).
+//!
+//! Most types derive their implementations; this module holds the pieces that
+//! need custom handling: deterministic map ordering, base64 resource data and
+//! the versioned [`Document`] envelope.
+
+use crate::{Document, Node, Properties, Resource, ResourceId, ResourceMap, SourceInfo};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+
+/// Version of the serialized document layout.
+///
+/// Bumped whenever the serialized form changes incompatibly. Documents with a
+/// newer version are rejected rather than misread.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Serialize a hash map with its keys in sorted order.
+pub(crate) fn sorted_map<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Serialize + Ord,
+    V: Serialize,
+    S: Serializer,
+{
+    serializer.collect_map(map.iter().collect::<BTreeMap<_, _>>())
+}
+
+impl Serialize for Properties {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        sorted_map(&self.0, serializer)
+    }
+}
+
+/// Resource data as base64 text for human-readable formats, raw bytes otherwise.
+pub(crate) mod base64 {
+    use ::base64::Engine as _;
+    use ::base64::engine::general_purpose::STANDARD;
+    use serde::de::{self, SeqAccess, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&STANDARD.encode(data))
+        } else {
+            serializer.serialize_bytes(data)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(DataVisitor)
+        } else {
+            deserializer.deserialize_byte_buf(DataVisitor)
+        }
+    }
+
+    struct DataVisitor;
+
+    impl<'de> Visitor<'de> for DataVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("base64 string or bytes")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Vec<u8>, E> {
+            STANDARD
+                .decode(v)
+                .map_err(|e| E::custom(format!("invalid base64 resource data: {e}")))
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+            Ok(v.to_vec())
+        }
+
+        fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+            Ok(v)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+            let mut data = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(byte) = seq.next_element()? {
+                data.push(byte);
+            }
+            Ok(data)
+        }
+    }
+}
+
+/// Borrowed view of a document, as written.
+#[derive(Serialize)]
+struct DocumentRef<'a> {
+    version: u32,
+    #[serde(skip_serializing_if = "Properties::is_empty")]
+    metadata: &'a Properties,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: &'a Option<SourceInfo>,
+    #[serde(
+        serialize_with = "sorted_map",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    resources: &'a ResourceMap,
+    content: &'a Node,
+}
+
+/// Owned document, as read.
+#[derive(Deserialize)]
+struct DocumentRepr {
+    version: u32,
+    #[serde(default)]
+    metadata: Properties,
+    #[serde(default)]
+    source: Option<SourceInfo>,
+    #[serde(default)]
+    resources: HashMap<ResourceId, Resource>,
+    content: Node,
+}
+
+impl Serialize for Document {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        DocumentRef {
+            version: FORMAT_VERSION,
+            metadata: &self.metadata,
+            source: &self.source,
+            resources: &self.resources,
+            content: &self.content,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Document {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = DocumentRepr::deserialize(deserializer)?;
+        if repr.version > FORMAT_VERSION {
+            return Err(D::Error::custom(format!(
+                "unsupported document version {} (this build reads up to {FORMAT_VERSION})",
+                repr.version
+            )));
+        }
+        Ok(Document {
+            content: repr.content,
+            resources: repr.resources,
+            metadata: repr.metadata,
+            source: repr.source,
+        })
+    }
+}
@@ -1,6 +1,7 @@
 //! Parser, Emitter, and Transformer traits.
 
-use crate::{ConversionResult, Document};
+use crate::{ConversionResult, Document, FormatOptions, OptionSpec};
+use std::io::Write;
 
 /// Options for parsing.
 #[derive(Debug, Clone, Default)]
@@ -9,6 +10,8 @@ pub struct ParseOptions {
     pub preserve_source_info: bool,
     /// Embed external resources (images, etc.).
     pub embed_resources: bool,
+    /// Reader-specific options, as declared by [`Parser::options`].
+    pub format: FormatOptions,
 }
 
 /// Options for emitting.
@@ -18,6 +21,8 @@ pub struct EmitOptions {
     pub pretty: bool,
     /// Include format-specific properties from source.
     pub use_source_info: bool,
+    /// Writer-specific options, as declared by [`Emitter::options`].
+    pub format: FormatOptions,
 }
 
 /// Error during parsing.
@@ -54,6 +59,11 @@ pub trait Parser: Send + Sync {
     /// Formats this parser can handle.
     fn formats(&self) -> &[&str];
 
+    /// Format-specific options this parser understands.
+    fn options(&self) -> &[OptionSpec] {
+        &[]
+    }
+
     /// Parse bytes into a document.
     fn parse(
         &self,
@@ -67,12 +77,32 @@ pub trait Emitter: Send + Sync {
     /// Formats this emitter can produce.
     fn formats(&self) -> &[&str];
 
+    /// Format-specific options this emitter understands.
+    fn options(&self) -> &[OptionSpec] {
+        &[]
+    }
+
     /// Emit a document to bytes.
     fn emit(
         &self,
         doc: &Document,
         options: &EmitOptions,
     ) -> Result<ConversionResult<Vec<u8>>, EmitError>;
+
+    /// Emit a document to a writer.
+    ///
+    /// The default implementation emits to memory and then copies the
+    /// output; emitters that can stream override it.
+    fn emit_to(
+        &self,
+        doc: &Document,
+        options: &EmitOptions,
+        out: &mut dyn Write,
+    ) -> Result<ConversionResult<()>, EmitError> {
+        let result = self.emit(doc, options)?;
+        out.write_all(&result.value)?;
+        Ok(ConversionResult::with_warnings((), result.warnings))
+    }
 }
 
 /// Transform a document (same IR, modified content).
@@ -81,5 +111,8 @@ pub trait Transformer: Send + Sync {
     fn name(&self) -> &str;
 
     /// Transform a document.
-    fn transform(&self, doc: Document) -> Result<Document, TransformError>;
+    ///
+    /// Lossy or suspicious changes are reported as warnings on the result;
+    /// errors are for transforms that cannot produce a document at all.
+    fn transform(&self, doc: Document) -> Result<ConversionResult<Document>, TransformError>;
 }
@@ -0,0 +1,1130 @@
+//! Citation processing.
+//!
+//! [`Citeproc`] formats the `cite` nodes of a document with a CSL
+//! [`Style`]: each becomes a `span` with the `citation` class holding the
+//! formatted citation, or for note styles a footnote. Entries come from
+//! bibliographies read by any of the bibliography readers (BibTeX,
+//! BibLaTeX, CSL JSON, RIS, EndNote XML), and the entries cited are listed
+//! in a bibliography appended to the document.
+//!
+//! ```
+//! use rescribe_core::{Document, Transformer};
+//! use rescribe_std::builder::doc;
+//! use rescribe_std::{Citation, Node};
+//! use rescribe_transforms::{Citeproc, stringify};
+//! use rescribe_transforms::csl::Style;
+//!
+//! let entry = Node::new("bib:entry")
+//!     .prop("bib:id", "doe")
+//!     .prop("bib:type", "book")
+//!     .prop("bib:author", "Jane Doe")
+//!     .prop("bib:title", "A Book")
+//!     .prop("bib:issued", "2020");
+//! let bibliography = Document::new().with_content(Node::new("document").child(entry));
+//!
+//! let document = doc(|d| {
+//!     d.para(|i| i.text("As shown ").cite([Citation::new("doe")], |c| c.text("[@doe]")))
+//! });
+//! let citeproc = Citeproc::new(Style::bundled("chicago-author-date").unwrap())
+//!     .with_bibliography(&bibliography);
+//! let document = citeproc.transform(document).unwrap().value;
+//! assert_eq!(stringify(&document.content.children[0]), "As shown (Jane Doe 2020)");
+//! ```
+//!
+//! The document's metadata is consulted too: `lang` picks the locale when
+//! none is given, `nocite` lists further entries to include (`@key`, or
+//! `@*` for all), `suppress-bibliography` leaves the bibliography out,
+//! `reference-section-title` gives it a heading and `link-citations` links
+//! citations to their entries. An existing `div` with the ID `refs` marks
+//! where the bibliography goes.
+
+use crate::csl::{
+    self, AuthorMode, CiteContext, Fmt, Item, Locale, Out, Position, Processor, SortKey, Style,
+};
+use rescribe_bib::Entry;
+use rescribe_core::{
+    ConversionResult, Document, FidelityWarning, PropValue, Severity, Span, TransformError,
+    Transformer, WarningKind,
+};
+use rescribe_std::{Citation, CitationMode, Node, NodeExt, class, node, prop};
+use std::collections::{HashMap, HashSet};
+
+/// Format citations and append a bibliography with a CSL style.
+///
+/// Cites of keys missing from the bibliography are rendered as the key in
+/// bold followed by "?" and reported as warnings.
+///
+/// In a note style a citation in the body becomes a footnote, and
+/// punctuation following it moves before the note mark; citations already
+/// in footnotes are formatted in place. Positions for "ibid." and short
+/// forms follow the order citations are read in, with footnotes read at
+/// their reference.
+#[derive(Debug, Clone)]
+pub struct Citeproc {
+    style: Style,
+    locale: Option<Locale>,
+    references: Vec<Node>,
+    link_citations: bool,
+}
+
+impl Citeproc {
+    /// Create a processor formatting citations in `style`.
+    pub fn new(style: Style) -> Self {
+        Self {
+            style,
+            locale: None,
+            references: Vec::new(),
+            link_citations: false,
+        }
+    }
+
+    /// Use `locale` rather than the one the document or style asks for.
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = Some(locale);
+        self
+    }
+
+    /// Add the entries of a bibliography read by a bibliography reader.
+    ///
+    /// May be called repeatedly; for keys given more than once the first
+    /// entry is used.
+    pub fn with_bibliography(mut self, bibliography: &Document) -> Self {
+        let entries = rescribe_bib::entries(&bibliography.content);
+        self.references
+            .extend(entries.into_iter().map(|e| e.node().clone()));
+        self
+    }
+
+    /// Link citations to their bibliography entries.
+    pub fn with_link_citations(mut self, link: bool) -> Self {
+        self.link_citations = link;
+        self
+    }
+
+    /// The style citations are formatted in.
+    pub fn style(&self) -> &Style {
+        &self.style
+    }
+
+    /// The locale to use: the configured one, the document's language, the
+    /// style's default or US English.
+    fn locale(&self, doc: &Document, warnings: &mut Vec<FidelityWarning>) -> Locale {
+        if let Some(locale) = &self.locale {
+            return locale.clone();
+        }
+        let lang = doc
+            .metadata
+            .get_str("lang")
+            .or_else(|| doc.metadata.get_str("language"))
+            .or_else(|| self.style.default_locale());
+        if let Some(lang) = lang {
+            match Locale::bundled(lang) {
+                Some(locale) => return locale,
+                None => warnings.push(FidelityWarning::new(
+                    Severity::Minor,
+                    WarningKind::FeatureLost(format!("locale:{lang}")),
+                    format!("no bundled CSL locale for '{lang}'; using en-US"),
+                )),
+            }
+        }
+        Locale::bundled("en-US").expect("en-US is bundled")
+    }
+}
+
+impl Default for Citeproc {
+    fn default() -> Self {
+        Self::new(Style::bundled("chicago-author-date").expect("style is bundled"))
+    }
+}
+
+impl Transformer for Citeproc {
+    fn name(&self) -> &str {
+        "citeproc"
+    }
+
+    fn transform(&self, mut doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        let mut warnings = Vec::new();
+        let locale = self.locale(&doc, &mut warnings);
+        let fallback = Locale::bundled("en-US").expect("en-US is bundled");
+        let proc = Processor::new(&self.style, &locale, &fallback);
+
+        let mut entries: HashMap<&str, Entry> = HashMap::new();
+        for entry in self.references.iter().filter_map(Entry::from_node) {
+            if entries.contains_key(entry.id()) {
+                warnings.push(warning(
+                    entry.id(),
+                    entry.node().span,
+                    format!("duplicate bibliography entry '{}' was ignored", entry.id()),
+                ));
+            } else {
+                entries.insert(entry.id(), entry);
+            }
+        }
+
+        let mut collector = Collector {
+            note_style: proc.is_note(),
+            notes: 0,
+            labels: HashMap::new(),
+            current: None,
+            sites: Vec::new(),
+        };
+        collector.visit(&doc.content);
+        let sites = collector.sites;
+        let mut order: Vec<usize> = (0..sites.len()).collect();
+        order.sort_by_key(|&i| sites[i].order);
+        let contexts = positions(&proc, &sites, &order);
+
+        // Items in the order first cited, then those only listed in
+        // `nocite`.
+        let nocite = nocite(&doc, &self.references);
+        let cited = order
+            .iter()
+            .flat_map(|&i| sites[i].citations.iter().map(|c| c.id.as_str()));
+        let mut seen = HashSet::new();
+        let mut items: Vec<Item> = Vec::new();
+        for id in cited.chain(nocite.iter().map(String::as_str)) {
+            if let Some(&entry) = entries.get(id)
+                && seen.insert(id)
+            {
+                items.push(Item {
+                    entry,
+                    number: items.len() + 1,
+                    year_suffix: None,
+                });
+            }
+        }
+        let keys: Vec<Option<SortKey>> = items
+            .iter()
+            .map(|item| proc.bibliography_sort_key(item))
+            .collect();
+        if keys.iter().any(Option::is_some) {
+            let mut keyed: Vec<_> = keys.into_iter().zip(items).collect();
+            keyed.sort_by(|a, b| a.0.cmp(&b.0));
+            items = keyed.into_iter().map(|(_, item)| item).collect();
+            for (i, item) in items.iter_mut().enumerate() {
+                item.number = i + 1;
+            }
+        }
+        if proc.citation_option("disambiguate-add-year-suffix") == Some("true") {
+            add_year_suffixes(&proc, &mut items);
+        }
+        let index: HashMap<&str, usize> = items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (item.entry.id(), i))
+            .collect();
+
+        let link = (self.link_citations || flag(&doc, "link-citations")) && proc.has_bibliography();
+        let citations = Citations {
+            proc: &proc,
+            items: &items,
+            index: &index,
+            link,
+        };
+        let mut rendered: Vec<Option<Rendered>> = sites
+            .iter()
+            .zip(&contexts)
+            .map(|(site, contexts)| Some(citations.render(site, contexts, &mut warnings)))
+            .collect();
+        replace_cites(&mut doc.content, &mut rendered, &mut 0);
+
+        if proc.has_bibliography() && !items.is_empty() && !flag(&doc, "suppress-bibliography") {
+            let bibliography = bibliography(&proc, &items);
+            let title = doc
+                .metadata
+                .get_str("reference-section-title")
+                .map(str::to_string);
+            insert_bibliography(&mut doc.content, bibliography, title, &proc);
+        }
+
+        Ok(ConversionResult::with_warnings(doc, warnings))
+    }
+}
+
+/// A `cite` node as found in the document.
+struct Site {
+    citations: Vec<Citation>,
+    /// Number of the note the citation is in, or becomes in a note style.
+    note: Option<usize>,
+    /// Whether the citation is already in a footnote.
+    in_note: bool,
+    /// Sorts citations into reading order: by the note read last, notes
+    /// before the text following their reference, then by position.
+    order: (usize, bool, usize),
+    span: Option<Span>,
+}
+
+/// Finds the `cite` nodes of a document and the notes they are in.
+struct Collector {
+    note_style: bool,
+    /// Notes so far.
+    notes: usize,
+    /// Note numbers by footnote label.
+    labels: HashMap<String, usize>,
+    /// The note being visited.
+    current: Option<usize>,
+    sites: Vec<Site>,
+}
+
+impl Collector {
+    fn visit(&mut self, node: &Node) {
+        match node.kind.as_str() {
+            node::CITE => {
+                let index = self.sites.len();
+                let (note, in_note, order) = match self.current {
+                    Some(note) => (Some(note), true, (note, false, index)),
+                    None if self.note_style => {
+                        self.notes += 1;
+                        (Some(self.notes), false, (self.notes, false, index))
+                    }
+                    None => (None, false, (self.notes, true, index)),
+                };
+                let citations = node.as_cite().map(|c| c.citations()).unwrap_or_default();
+                self.sites.push(Site {
+                    citations,
+                    note,
+                    in_note,
+                    order,
+                    span: node.span,
+                });
+                return;
+            }
+            node::FOOTNOTE_REF => {
+                if let Some(label) = node.props.get_str(prop::LABEL) {
+                    self.note_number(Some(label));
+                }
+                return;
+            }
+            node::FOOTNOTE_DEF => {
+                let number = self.note_number(node.props.get_str(prop::LABEL));
+                let outer = self.current.replace(number);
+                for child in &node.children {
+                    self.visit(child);
+                }
+                self.current = outer;
+                return;
+            }
+            _ => {}
+        }
+        for child in &node.children {
+            self.visit(child);
+        }
+    }
+
+    /// The number of the note labelled `label`, numbering it if it is new;
+    /// unlabelled notes are always new.
+    fn note_number(&mut self, label: Option<&str>) -> usize {
+        if let Some(number) = label.and_then(|l| self.labels.get(l)) {
+            return *number;
+        }
+        self.notes += 1;
+        if let Some(label) = label {
+            self.labels.insert(label.to_string(), self.notes);
+        }
+        self.notes
+    }
+}
+
+/// The context of each cite of each citation, from the citations before
+/// it in reading order.
+fn positions(proc: &Processor, sites: &[Site], order: &[usize]) -> Vec<Vec<CiteContext>> {
+    let distance: usize = proc
+        .citation_option("near-note-distance")
+        .and_then(|d| d.parse().ok())
+        .unwrap_or(5);
+    let mut contexts: Vec<Vec<CiteContext>> = vec![Vec::new(); sites.len()];
+    let mut first_notes: HashMap<&str, Option<usize>> = HashMap::new();
+    let mut last_notes: HashMap<&str, usize> = HashMap::new();
+    let mut previous: Option<&[Citation]> = None;
+    for &i in order {
+        let site = &sites[i];
+        for (j, citation) in site.citations.iter().enumerate() {
+            // The cite just before: in the same citation, or the whole of
+            // the previous citation if it cites one item.
+            let before = match j {
+                0 => previous.filter(|p| p.len() == 1).map(|p| &p[0]),
+                _ => Some(&site.citations[j - 1]),
+            };
+            let position = match first_notes.get(citation.id.as_str()) {
+                None => Position::First,
+                Some(_) => match before.filter(|b| b.id == citation.id) {
+                    Some(b) if b.locator == citation.locator => Position::Ibid,
+                    Some(b) if citation.locator.is_some() || b.locator.is_none() => {
+                        Position::IbidWithLocator
+                    }
+                    _ => Position::Subsequent,
+                },
+            };
+            let near_note = match (site.note, last_notes.get(citation.id.as_str())) {
+                (Some(note), Some(last)) => note - last <= distance,
+                _ => false,
+            };
+            let first_note = *first_notes.entry(citation.id.as_str()).or_insert(site.note);
+            if let Some(note) = site.note {
+                last_notes.insert(citation.id.as_str(), note);
+            }
+            contexts[i].push(CiteContext {
+                locator: citation.locator.clone(),
+                label: citation.label.clone(),
+                position,
+                near_note,
+                first_note,
+            });
+        }
+        previous = Some(&site.citations);
+    }
+    contexts
+}
+
+/// Entry keys listed in the `nocite` metadata, with `@*` standing for all.
+fn nocite(doc: &Document, references: &[Node]) -> Vec<String> {
+    let keys: Vec<&str> = match doc.metadata.get("nocite") {
+        Some(PropValue::String(s)) => vec![s.as_str()],
+        Some(PropValue::List(items)) => items
+            .iter()
+            .filter_map(|item| match item {
+                PropValue::String(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let mut ids = Vec::new();
+    for key in keys.iter().flat_map(|k| k.split([',', ';', ' '])) {
+        match key.trim().strip_prefix('@') {
+            Some("*") => ids.extend(
+                references
+                    .iter()
+                    .filter_map(Entry::from_node)
+                    .map(|e| e.id().to_string()),
+            ),
+            Some(id) if !id.is_empty() => ids.push(id.to_string()),
+            _ => {}
+        }
+    }
+    ids
+}
+
+/// Assign "a", "b", ... to items that would otherwise cite the same, in
+/// bibliography order.
+fn add_year_suffixes(proc: &Processor, items: &mut [Item]) {
+    let keys: Vec<String> = items
+        .iter()
+        .map(|item| {
+            let rendered = proc.render_cite(item, &CiteContext::default(), AuthorMode::Normal);
+            csl::plain(&rendered.out)
+        })
+        .collect();
+    let mut groups: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, key) in keys.iter().enumerate() {
+        groups.entry(key.as_str()).or_default().push(i);
+    }
+    for group in groups.values().filter(|g| g.len() > 1) {
+        for (n, &i) in group.iter().enumerate() {
+            items[i].year_suffix = Some(letters(n));
+        }
+    }
+}
+
+/// "a" to "z", then "aa", "ab", ...
+fn letters(mut n: usize) -> String {
+    let mut text = String::new();
+    loop {
+        text.insert(0, char::from(b'a' + (n % 26) as u8));
+        if n < 26 {
+            return text;
+        }
+        n = n / 26 - 1;
+    }
+}
+
+/// A formatted citation: a `span`, and whether it holds a footnote that
+/// punctuation should precede.
+struct Rendered {
+    node: Node,
+    note: bool,
+}
+
+/// One cite of a citation being rendered.
+struct Cite<'c> {
+    citation: &'c Citation,
+    context: &'c CiteContext,
+    item: Option<&'c Item<'c>>,
+    /// The author as rendered, for collapsing.
+    author: Option<String>,
+    out: Vec<Out>,
+    /// Whether the cite joins the one before, as a year of the same author.
+    grouped: bool,
+}
+
+/// Renders the citations of a document.
+struct Citations<'c> {
+    proc: &'c Processor<'c>,
+    items: &'c [Item<'c>],
+    index: &'c HashMap<&'c str, usize>,
+    link: bool,
+}
+
+impl<'c> Citations<'c> {
+    fn render(
+        &self,
+        site: &'c Site,
+        contexts: &'c [CiteContext],
+        warnings: &mut Vec<FidelityWarning>,
+    ) -> Rendered {
+        let proc = self.proc;
+        let mut cites: Vec<Cite> = site
+            .citations
+            .iter()
+            .zip(contexts)
+            .map(|(citation, context)| {
+                let item = self
+                    .index
+                    .get(citation.id.as_str())
+                    .map(|&i| &self.items[i]);
+                if item.is_none() {
+                    warnings.push(warning(
+                        &citation.id,
+                        site.span,
+                        format!("citation '{}' has no bibliography entry", citation.id),
+                    ));
+                }
+                Cite {
+                    citation,
+                    context,
+                    item,
+                    author: None,
+                    out: Vec::new(),
+                    grouped: false,
+                }
+            })
+            .collect();
+
+        let note = proc.is_note() && !site.in_note;
+        if let [cite] = cites.as_slice()
+            && cite.citation.mode == CitationMode::AuthorInText
+            && let Some(item) = cite.item
+            && let Some(author) = self.author(item, cite.context)
+        {
+            let author = self.linked(item, author);
+            let node = if note {
+                let full = self.render_cite(cite, item, AuthorMode::Normal);
+                let full = proc.wrap_citation(full);
+                self.span(author, Some(full))
+            } else {
+                // "Doe (2020)" in the text, "Doe, Title" within a note.
+                let rest = self.render_cite(cite, item, AuthorMode::Suppress);
+                let rest = proc.wrap_citation(rest);
+                let delimiter = if proc.is_note() { ", " } else { " " };
+                self.span(csl::join(vec![author, rest], delimiter), None)
+            };
+            return Rendered { node, note };
+        }
+
+        if let Some(keys) = cites
+            .iter()
+            .map(|c| proc.citation_sort_key(c.item?, c.context))
+            .collect::<Option<Vec<SortKey>>>()
+        {
+            let mut keyed: Vec<_> = keys.into_iter().zip(cites).collect();
+            keyed.sort_by(|a, b| a.0.cmp(&b.0));
+            cites = keyed.into_iter().map(|(_, cite)| cite).collect();
+        }
+
+        let collapse = proc.citation_option("collapse");
+        for cite in &mut cites {
+            let Some(item) = cite.item else {
+                cite.out = vec![Out::Styled(
+                    Fmt::Bold,
+                    vec![Out::Text(format!("{}?", cite.citation.id))],
+                )];
+                continue;
+            };
+            let rendered = proc.render_cite(item, cite.context, self.author_mode(cite));
+            cite.author = rendered.author.as_deref().map(csl::plain);
+            cite.out = rendered.out;
+        }
+        if collapse.is_some_and(|c| c.starts_with("year")) {
+            cites = self.collapse_years(cites);
+        }
+
+        let mut pieces: Vec<(Vec<Out>, bool)> = Vec::new();
+        let mut i = 0;
+        while i < cites.len() {
+            let run = if collapse == Some("citation-number") {
+                number_run(&cites[i..])
+            } else {
+                1
+            };
+            if run >= 3 {
+                let first = std::mem::take(&mut cites[i].out);
+                let last = std::mem::take(&mut cites[i + run - 1].out);
+                let first = self.linked(cites[i].item.expect("runs are of items"), first);
+                let last = self.linked(cites[i + run - 1].item.expect("runs are of items"), last);
+                pieces.push((csl::join(vec![first, last], "\u{2013}"), false));
+                i += run;
+                continue;
+            }
+            let cite = &mut cites[i];
+            let out = std::mem::take(&mut cite.out);
+            let out = match cite.item {
+                Some(item) => self.linked(item, out),
+                None => out,
+            };
+            let prefix = cite.citation.prefix.as_deref().map(|p| format!("{p} "));
+            let out = csl::affix(out, prefix.as_deref(), cite.citation.suffix.as_deref());
+            pieces.push((out, cite.grouped));
+            i += 1;
+        }
+
+        let delimiter = proc.citation_delimiter();
+        let group_delimiter = proc.citation_option("cite-group-delimiter").unwrap_or(", ");
+        let mut out = Vec::new();
+        for (k, (piece, grouped)) in pieces.into_iter().enumerate() {
+            if k > 0 {
+                csl::push_text(&mut out, if grouped { group_delimiter } else { delimiter });
+            }
+            for part in piece {
+                csl::push(&mut out, part);
+            }
+        }
+        let out = proc.wrap_citation(out);
+        let node = if note {
+            self.span(Vec::new(), Some(out))
+        } else {
+            self.span(out, None)
+        };
+        Rendered { node, note }
+    }
+
+    fn author_mode(&self, cite: &Cite) -> AuthorMode {
+        match cite.citation.mode {
+            CitationMode::SuppressAuthor => AuthorMode::Suppress,
+            _ => AuthorMode::Normal,
+        }
+    }
+
+    fn render_cite(&self, cite: &Cite, item: &Item, author: AuthorMode) -> Vec<Out> {
+        let out = self.proc.render_cite(item, cite.context, author).out;
+        csl::affix(out, None, cite.citation.suffix.as_deref())
+    }
+
+    /// The author of `item` as a citation names it, or as the bibliography
+    /// does for styles whose citations name no author.
+    fn author(&self, item: &Item, context: &CiteContext) -> Option<Vec<Out>> {
+        let rendered = self.proc.render_cite(item, context, AuthorMode::Normal);
+        rendered.author.or_else(|| {
+            self.proc
+                .has_bibliography()
+                .then(|| self.proc.render_entry(item, AuthorMode::Normal).author)
+                .flatten()
+        })
+    }
+
+    /// Group cites by the same author, leaving out the author after the
+    /// first: "(Doe 2019, 2020; Roe 2018)".
+    fn collapse_years(&self, cites: Vec<Cite<'c>>) -> Vec<Cite<'c>> {
+        let mut grouped: Vec<Cite> = Vec::with_capacity(cites.len());
+        for mut cite in cites {
+            let same = cite.author.is_some()
+                && cite.citation.prefix.is_none()
+                && cite.citation.mode == CitationMode::Normal;
+            let position = same
+                .then(|| grouped.iter().rposition(|c| c.author == cite.author))
+                .flatten();
+            let Some(position) = position else {
+                grouped.push(cite);
+                continue;
+            };
+            let item = cite.item.expect("cites with authors have items");
+            cite.out = self
+                .proc
+                .render_cite(item, cite.context, AuthorMode::Suppress)
+                .out;
+            cite.grouped = true;
+            grouped.insert(position + 1, cite);
+        }
+        grouped
+    }
+
+    fn linked(&self, item: &Item, out: Vec<Out>) -> Vec<Out> {
+        if !self.link || out.is_empty() {
+            return out;
+        }
+        let url = format!("#ref-{}", item.entry.id());
+        vec![Out::Styled(Fmt::Link(url), out)]
+    }
+
+    /// A `span` holding the citation, with `note` as a footnote.
+    fn span(&self, out: Vec<Out>, note: Option<Vec<Out>>) -> Node {
+        let mut span = Node::new(node::SPAN)
+            .prop(prop::CLASSES, "citation")
+            .children(self.proc.finish(out));
+        if let Some(mut note) = note {
+            csl::capitalize_first(&mut note);
+            let para = Node::new(node::PARAGRAPH).children(self.proc.finish(note));
+            span.children
+                .push(Node::new(node::FOOTNOTE_DEF).child(para));
+        }
+        span
+    }
+}
+
+/// How many cites from the start have consecutive numbers, none of them
+/// with a locator, prefix or suffix.
+fn number_run(cites: &[Cite]) -> usize {
+    let plain = |c: &Cite| {
+        c.context.locator.is_none() && c.citation.prefix.is_none() && c.citation.suffix.is_none()
+    };
+    let number = |c: &Cite| c.item.filter(|_| plain(c)).map(|item| item.number);
+    let Some(mut last) = cites.first().and_then(number) else {
+        return 1;
+    };
+    let mut run = 1;
+    for cite in &cites[1..] {
+        match number(cite) {
+            Some(n) if n == last + 1 => {
+                last = n;
+                run += 1;
+            }
+            _ => break,
+        }
+    }
+    run
+}
+
+/// Replace the `cite` nodes under `node` with their renderings, in
+/// document order.
+fn replace_cites(node: &mut Node, rendered: &mut [Option<Rendered>], next: &mut usize) {
+    for i in 0..node.children.len() {
+        if node.children[i].kind.as_str() != node::CITE {
+            replace_cites(&mut node.children[i], rendered, next);
+            continue;
+        }
+        let Some(Rendered {
+            node: mut span,
+            note,
+        }) = rendered[*next].take()
+        else {
+            continue;
+        };
+        *next += 1;
+        // A citation ending in a period absorbs the one after it.
+        if ends_with_period(&span)
+            && let Some(text) = node.children.get(i + 1).and_then(text_content)
+            && let Some(rest) = text.strip_prefix('.')
+        {
+            let rest = rest.to_string();
+            node.children[i + 1].props.set(prop::CONTENT, rest);
+        }
+        if note {
+            // "text [@doe]." becomes "text." and the note mark.
+            if i > 0
+                && let Some(text) = text_content(&node.children[i - 1])
+            {
+                let text = text.trim_end().to_string();
+                node.children[i - 1].props.set(prop::CONTENT, text);
+            }
+            if let Some(text) = node.children.get(i + 1).and_then(text_content) {
+                let rest = text.trim_start_matches(['.', ',', ';', ':', '!', '?']);
+                let moved = text[..text.len() - rest.len()].to_string();
+                if !moved.is_empty() {
+                    let rest = rest.to_string();
+                    node.children[i + 1].props.set(prop::CONTENT, rest);
+                    let at = span.children.len() - 1;
+                    span.children
+                        .insert(at, Node::new(node::TEXT).prop(prop::CONTENT, moved));
+                }
+            }
+        }
+        node.children[i] = span;
+    }
+}
+
+fn ends_with_period(node: &Node) -> bool {
+    match node.children.last() {
+        Some(last) if last.kind.as_str() == node::FOOTNOTE_DEF => false,
+        Some(last) => ends_with_period(last),
+        None => text_content(node).is_some_and(|t| t.ends_with('.')),
+    }
+}
+
+fn text_content(node: &Node) -> Option<&str> {
+    (node.kind.as_str() == node::TEXT)
+        .then(|| node.props.get_str(prop::CONTENT))
+        .flatten()
+}
+
+/// The bibliography: a `div` for each entry, with the ID `ref-<key>`.
+fn bibliography(proc: &Processor, items: &[Item]) -> Vec<Node> {
+    let substitute = proc.bibliography_option("subsequent-author-substitute");
+    let mut previous: Option<String> = None;
+    let mut entries = Vec::new();
+    for item in items {
+        let rendered = proc.render_entry(item, AuthorMode::Normal);
+        let author = rendered.author.as_deref().map(csl::plain);
+        let out = match substitute {
+            Some(substitute) if author.is_some() && author == previous => {
+                let mode = AuthorMode::Substitute(substitute.to_string());
+                proc.render_entry(item, mode).out
+            }
+            _ => rendered.out,
+        };
+        previous = author;
+        let para = Node::new(node::PARAGRAPH).children(proc.finish(out));
+        entries.push(
+            Node::new(node::DIV)
+                .prop(prop::ID, format!("ref-{}", item.entry.id()))
+                .prop(prop::CLASSES, "csl-entry")
+                .child(para),
+        );
+    }
+    entries
+}
+
+/// Put the bibliography in the `refs` div, or append it in a new one.
+fn insert_bibliography(
+    root: &mut Node,
+    entries: Vec<Node>,
+    title: Option<String>,
+    proc: &Processor,
+) {
+    let mut classes = String::from("references csl-bib-body");
+    if proc.bibliography_option("hanging-indent") == Some("true") {
+        classes.push_str(" hanging-indent");
+    }
+    if let Some(refs) = find_refs(root) {
+        if refs.props.get_str(prop::CLASSES).is_none() {
+            refs.props.set(prop::CLASSES, classes);
+        }
+        refs.children.extend(entries);
+        return;
+    }
+    if let Some(title) = title {
+        let mut heading = Node::new(node::HEADING)
+            .prop(prop::LEVEL, 1i64)
+            .prop(prop::CLASSES, class::UNNUMBERED)
+            .child(Node::new(node::TEXT).prop(prop::CONTENT, title));
+        heading.props.set(prop::ID, "bibliography");
+        root.children.push(heading);
+    }
+    root.children.push(
+        Node::new(node::DIV)
+            .prop(prop::ID, "refs")
+            .prop(prop::CLASSES, classes)
+            .children(entries),
+    );
+}
+
+fn find_refs(node: &mut Node) -> Option<&mut Node> {
+    if node.kind.as_str() == node::DIV && node.props.get_str(prop::ID) == Some("refs") {
+        return Some(node);
+    }
+    node.children.iter_mut().find_map(find_refs)
+}
+
+/// Whether a metadata flag is set, as a boolean or the text "true".
+fn flag(doc: &Document, key: &str) -> bool {
+    match doc.metadata.get(key) {
+        Some(PropValue::Bool(b)) => *b,
+        Some(PropValue::String(s)) => s == "true",
+        _ => false,
+    }
+}
+
+fn warning(id: &str, span: Option<Span>, message: String) -> FidelityWarning {
+    let warning = FidelityWarning::new(
+        Severity::Minor,
+        WarningKind::InvalidStructure(format!("cite:{id}")),
+        message,
+    );
+    match span {
+        Some(span) => warning.at(span),
+        None => warning,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stringify;
+    use rescribe_bib::{Date, Name, node as bib_node, prop as bib_prop};
+    use rescribe_std::builder::doc;
+
+    fn entry(id: &str, kind: &str, authors: &[&str], year: &str, title: &str) -> Node {
+        Node::new(bib_node::ENTRY)
+            .prop(bib_prop::ID, id)
+            .prop(bib_prop::TYPE, kind)
+            .prop(
+                bib_prop::AUTHOR,
+                Name::list(authors.iter().map(|a| Name::parse(a))),
+            )
+            .prop(bib_prop::ISSUED, Date::parse(year).unwrap())
+            .prop(bib_prop::TITLE, title)
+    }
+
+    fn bibliography() -> Document {
+        let article = entry(
+            "doe",
+            "article-journal",
+            &["Doe, John"],
+            "2020-05",
+            "A study",
+        )
+        .prop(bib_prop::CONTAINER_TITLE, "Journal of Studies")
+        .prop(bib_prop::VOLUME, "12")
+        .prop(bib_prop::ISSUE, "3")
+        .prop(bib_prop::PAGE, "45-67")
+        .prop(bib_prop::DOI, "10.1000/xyz");
+        let book = entry("roe", "book", &["Roe, Jane", "Poe, Ed"], "2018", "The book")
+            .prop(bib_prop::PUBLISHER, "Press")
+            .prop(bib_prop::PUBLISHER_PLACE, "Chicago");
+        let later = entry("doe2", "book", &["Doe, John"], "2020-09", "Another study");
+        let content = Node::new(node::DOCUMENT).children([article, book, later]);
+        Document::new().with_content(content)
+    }
+
+    fn cite(citations: impl IntoIterator<Item = Citation>) -> Node {
+        Node::new(node::CITE).prop(prop::CITATIONS, Citation::list(citations))
+    }
+
+    fn run(style: &str, content: Vec<Node>) -> ConversionResult<Document> {
+        let citeproc =
+            Citeproc::new(Style::bundled(style).unwrap()).with_bibliography(&bibliography());
+        let document = Document::new().with_content(Node::new(node::DOCUMENT).children(content));
+        citeproc.transform(document).unwrap()
+    }
+
+    fn para(children: Vec<Node>) -> Node {
+        Node::new(node::PARAGRAPH).children(children)
+    }
+
+    fn text(content: &str) -> Node {
+        Node::new(node::TEXT).prop(prop::CONTENT, content)
+    }
+
+    /// The text of `node`, with quotation marks.
+    fn text_of(node: &Node) -> String {
+        let content: String = node.children.iter().map(text_of).collect();
+        match node.kind.as_str() {
+            node::TEXT => node
+                .props
+                .get_str(prop::CONTENT)
+                .unwrap_or_default()
+                .to_string(),
+            node::QUOTED => {
+                let open = node.props.get_str(prop::QUOTE_OPEN).unwrap();
+                let close = node.props.get_str(prop::QUOTE_CLOSE).unwrap();
+                format!("{open}{content}{close}")
+            }
+            _ => content,
+        }
+    }
+
+    fn entries(document: &Document) -> Vec<String> {
+        let refs = document.content.children.last().unwrap();
+        assert_eq!(refs.props.get_str(prop::ID), Some("refs"));
+        refs.children.iter().map(text_of).collect()
+    }
+
+    #[test]
+    fn test_author_date() {
+        let result = run(
+            "chicago-author-date",
+            vec![para(vec![
+                text("See "),
+                cite([
+                    Citation::new("doe").with_suffix(", p. 46"),
+                    Citation::new("roe"),
+                ]),
+                text(" and "),
+                cite([Citation::new("doe").with_mode(CitationMode::AuthorInText)]),
+                text(" or "),
+                cite([Citation::new("roe").with_mode(CitationMode::SuppressAuthor)]),
+                text(" and "),
+                cite([Citation::new("doe2").with_prefix("cf.")]),
+                text("."),
+            ])],
+        );
+        let document = result.value;
+        assert_eq!(
+            stringify(&document.content.children[0]),
+            "See (Doe 2020a, 46; Roe and Poe 2018) and Doe (2020a) or (2018) and (cf. Doe 2020b)."
+        );
+        assert_eq!(
+            entries(&document),
+            [
+                "Doe, John. 2020a. “A Study.” Journal of Studies 12 (3): 45–67. https://doi.org/10.1000/xyz.",
+                "———. 2020b. Another Study.",
+                "Roe, Jane, and Ed Poe. 2018. The Book. Chicago: Press.",
+            ]
+        );
+        assert!(
+            document.content.children[1]
+                .props
+                .get_str(prop::CLASSES)
+                .is_some_and(|c| c.contains("hanging-indent"))
+        );
+    }
+
+    #[test]
+    fn test_collapse_years_and_links() {
+        let citeproc = Citeproc::default()
+            .with_bibliography(&bibliography())
+            .with_link_citations(true);
+        let document = doc(|d| {
+            d.para(|i| {
+                i.cite(
+                    [
+                        Citation::new("doe"),
+                        Citation::new("roe"),
+                        Citation::new("doe2"),
+                    ],
+                    |c| c,
+                )
+            })
+        });
+        let document = citeproc.transform(document).unwrap().value;
+        let para = &document.content.children[0];
+        assert_eq!(stringify(para), "(Doe 2020a, 2020b; Roe and Poe 2018)");
+        let span = &para.children[0];
+        assert!(span.has_class("citation"));
+        assert_eq!(span.children[1].props.get_str(prop::URL), Some("#ref-doe"));
+    }
+
+    #[test]
+    fn test_note_style() {
+        let result = run(
+            "chicago-note",
+            vec![
+                para(vec![
+                    text("Claim "),
+                    cite([Citation::new("roe").with_suffix(", 4")]),
+                    text(". Again"),
+                    cite([Citation::new("roe").with_suffix(", 4")]),
+                    text(", and"),
+                    cite([Citation::new("doe")]),
+                    text(" then"),
+                    cite([Citation::new("roe").with_suffix(", 9")]),
+                    text("."),
+                ]),
+                para(vec![
+                    text("Later"),
+                    Node::new(node::FOOTNOTE_REF).prop(prop::LABEL, "n"),
+                ]),
+                Node::new(node::FOOTNOTE_DEF)
+                    .prop(prop::LABEL, "n")
+                    .child(para(vec![
+                        text("See "),
+                        cite([Citation::new("roe")]),
+                        text("."),
+                    ])),
+            ],
+        );
+        let document = result.value;
+        let first = &document.content.children[0];
+        let notes: Vec<String> = first
+            .children
+            .iter()
+            .filter(|c| c.kind.as_str() == node::SPAN)
+            .map(|span| {
+                assert_eq!(
+                    span.children.last().unwrap().kind.as_str(),
+                    node::FOOTNOTE_DEF
+                );
+                text_of(span.children.last().unwrap())
+            })
+            .collect();
+        assert_eq!(
+            notes,
+            [
+                "Jane Roe and Ed Poe, The Book (Chicago: Press, 2018), 4.",
+                "Ibid.",
+                "John Doe, “A Study,” Journal of Studies 12, no. 3 (2020): 45–67, https://doi.org/10.1000/xyz.",
+                "Roe and Poe, The Book, 9.",
+            ]
+        );
+        // Punctuation moves before the note; spaces before it go.
+        assert_eq!(
+            first.children[0].props.get_str(prop::CONTENT),
+            Some("Claim")
+        );
+        assert_eq!(
+            first.children[2].props.get_str(prop::CONTENT),
+            Some(" Again")
+        );
+        // Citations in notes stay in place.
+        assert_eq!(
+            stringify(&document.content.children[2]),
+            "See Roe and Poe, The Book."
+        );
+        assert_eq!(
+            entries(&document)[1],
+            "Roe, Jane, and Ed Poe. The Book. Chicago: Press, 2018."
+        );
+    }
+
+    #[test]
+    fn test_numeric() {
+        let result = run(
+            "ieee",
+            vec![para(vec![
+                cite([Citation::new("roe")]),
+                cite([
+                    Citation::new("doe2"),
+                    Citation::new("doe"),
+                    Citation::new("roe"),
+                ]),
+                cite([Citation::new("doe").with_suffix(", p. 50")]),
+            ])],
+        );
+        let document = result.value;
+        assert_eq!(
+            stringify(&document.content.children[0]),
+            "[1][1]–[3][3, p. 50]"
+        );
+        assert_eq!(
+            entries(&document),
+            [
+                "[1] J. Roe and E. Poe, The book, Chicago: Press, 2018.",
+                "[2] J. Doe, Another study, Sep. 2020.",
+                "[3] J. Doe, “A study,” Journal of Studies, vol. 12, no. 3, pp. 45–67, May 2020, doi: 10.1000/xyz.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_entry() {
+        let mut content = vec![para(vec![cite([Citation::new("nobody")])])];
+        content[0].children[0].span = Some(Span { start: 3, end: 12 });
+        let result = run("chicago-author-date", content);
+        assert_eq!(stringify(&result.value.content.children[0]), "(nobody?)");
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(result.warnings[0].span, Some(Span { start: 3, end: 12 }));
+        // Nothing was cited, so there is no bibliography.
+        assert_eq!(result.value.content.children.len(), 1);
+    }
+
+    #[test]
+    fn test_metadata() {
+        let mut document = doc(|d| d.para(|i| i.cite([Citation::new("doe")], |c| c)));
+        document.metadata.set("lang", "de");
+        document.metadata.set("nocite", "@roe");
+        document
+            .metadata
+            .set("reference-section-title", "Literatur");
+        let result = Citeproc::new(Style::bundled("chicago-note").unwrap())
+            .with_bibliography(&bibliography())
+            .transform(document)
+            .unwrap();
+        let document = result.value;
+        assert_eq!(
+            text_of(&document.content.children[0]),
+            "John Doe, „A study“, Journal of Studies 12, no. 3 (2020): 45–67, https://doi.org/10.1000/xyz."
+        );
+        let heading = document.content.children[1].as_heading().unwrap();
+        assert_eq!(stringify(&document.content.children[1]), "Literatur");
+        assert!(heading.node().has_class(class::UNNUMBERED));
+        assert_eq!(entries(&document).len(), 2);
+    }
+}
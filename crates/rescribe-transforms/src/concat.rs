@@ -0,0 +1,312 @@
+//! Merging several documents into one.
+//!
+//! [`Concat`] appends the content of documents in order, as when a book is
+//! assembled from chapter files. Identifiers that are only meant to be
+//! unique within one document are renamed where they collide: resource IDs
+//! and footnote labels. Metadata is merged key by key, with either the
+//! earlier or the later document winning.
+//!
+//! ```
+//! use rescribe_std::builder::doc;
+//! use rescribe_transforms::Concat;
+//!
+//! let intro = doc(|d| d.heading(1, |i| i.text("Intro")));
+//! let usage = doc(|d| d.heading(1, |i| i.text("Usage")));
+//! let book = Concat::new().shift_headings(1).concat([intro, usage]).unwrap();
+//! assert_eq!(book.value.content.children.len(), 2);
+//! ```
+
+use crate::ShiftHeadings;
+use rescribe_core::{
+    ConversionResult, Document, FidelityWarning, ResourceId, Severity, TransformError, Transformer,
+    WarningKind,
+};
+use rescribe_std::{Node, node, prop};
+use std::collections::{HashMap, HashSet};
+
+/// Which document's value a metadata key keeps when documents disagree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MetadataPrecedence {
+    /// The first document that sets the key wins, so the first document
+    /// can act as the title page.
+    #[default]
+    First,
+    /// The last document that sets the key wins.
+    Last,
+}
+
+/// Concatenate documents.
+#[derive(Debug, Clone, Default)]
+pub struct Concat {
+    shift: i64,
+    precedence: MetadataPrecedence,
+}
+
+impl Concat {
+    /// Create a concatenation with headings unchanged and the first
+    /// document's metadata taking precedence.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shift the heading levels of every document by `delta`, as
+    /// [`ShiftHeadings`] does.
+    pub fn shift_headings(mut self, delta: i64) -> Self {
+        self.shift = delta;
+        self
+    }
+
+    /// Set which document's metadata wins when documents disagree.
+    pub fn metadata(mut self, precedence: MetadataPrecedence) -> Self {
+        self.precedence = precedence;
+        self
+    }
+
+    /// Merge `docs` in order.
+    ///
+    /// Conflicting metadata values are reported as warnings. The result's
+    /// source information is that of the first document.
+    pub fn concat(
+        &self,
+        docs: impl IntoIterator<Item = Document>,
+    ) -> Result<ConversionResult<Document>, TransformError> {
+        let mut merged = Document::new();
+        let mut labels = HashSet::new();
+        let mut warnings = Vec::new();
+
+        for (index, doc) in docs.into_iter().enumerate() {
+            let mut doc = if self.shift != 0 {
+                ShiftHeadings::new(self.shift).transform(doc)?.value
+            } else {
+                doc
+            };
+            if index == 0 {
+                merged.source = doc.source.take();
+            }
+
+            // Resource IDs that clash with an earlier document's are renamed,
+            // to IDs neither document uses: stored documents keep the IDs
+            // they were written with, so a generated ID can already be taken.
+            let own: HashSet<_> = doc.resources.keys().cloned().collect();
+            let mut renamed = HashMap::new();
+            for (id, resource) in doc.resources {
+                let id = if merged.resources.contains_key(&id) {
+                    let new_id = std::iter::repeat_with(ResourceId::new)
+                        .find(|new_id| {
+                            !merged.resources.contains_key(new_id) && !own.contains(new_id)
+                        })
+                        .unwrap();
+                    renamed.insert(id.as_str().to_string(), new_id.clone());
+                    new_id
+                } else {
+                    id
+                };
+                merged.resources.insert(id, resource);
+            }
+            rename_footnotes(&mut doc.content, &mut labels, index + 1);
+            crate::walk_mut(&mut doc.content, &mut |node| {
+                if let Some(new_id) = node
+                    .props
+                    .get_str(prop::RESOURCE_ID)
+                    .and_then(|id| renamed.get(id))
+                {
+                    node.props.set(prop::RESOURCE_ID, new_id.as_str());
+                }
+            });
+
+            let mut keys: Vec<_> = doc.metadata.iter().collect();
+            keys.sort_by(|a, b| a.0.cmp(b.0));
+            for (key, value) in keys {
+                match merged.metadata.get(key) {
+                    Some(existing) if existing != value => {
+                        let message = match self.precedence {
+                            MetadataPrecedence::First => format!(
+                                "metadata '{key}' of document {} was dropped in favour of an \
+                                 earlier document's",
+                                index + 1
+                            ),
+                            MetadataPrecedence::Last => {
+                                merged.metadata.set(key.clone(), value.clone());
+                                format!(
+                                    "metadata '{key}' of an earlier document was replaced by \
+                                     document {}'s",
+                                    index + 1
+                                )
+                            }
+                        };
+                        warnings.push(FidelityWarning::new(
+                            Severity::Info,
+                            WarningKind::FeatureLost(format!("metadata:{key}")),
+                            message,
+                        ));
+                    }
+                    Some(_) => {}
+                    None => merged.metadata.set(key.clone(), value.clone()),
+                }
+            }
+
+            merged.content.children.extend(doc.content.children);
+        }
+        Ok(ConversionResult::with_warnings(merged, warnings))
+    }
+}
+
+/// Rename the footnote labels of one document that are already in `taken`,
+/// suffixing them with the document's number, and record its labels.
+fn rename_footnotes(content: &mut Node, taken: &mut HashSet<String>, number: usize) {
+    let mut own = HashSet::new();
+    crate::walk(content, &mut |node| {
+        if matches!(node.kind.as_str(), node::FOOTNOTE_REF | node::FOOTNOTE_DEF)
+            && let Some(label) = node.props.get_str(prop::LABEL)
+        {
+            own.insert(label.to_string());
+        }
+    });
+
+    let mut renamed = HashMap::new();
+    for label in &own {
+        if taken.contains(label) {
+            let mut new_label = format!("{label}-{number}");
+            let mut n = 1;
+            while taken.contains(&new_label) || own.contains(&new_label) {
+                n += 1;
+                new_label = format!("{label}-{number}-{n}");
+            }
+            taken.insert(new_label.clone());
+            renamed.insert(label.clone(), new_label);
+        } else {
+            taken.insert(label.clone());
+        }
+    }
+    if renamed.is_empty() {
+        return;
+    }
+    crate::walk_mut(content, &mut |node| {
+        if matches!(node.kind.as_str(), node::FOOTNOTE_REF | node::FOOTNOTE_DEF)
+            && let Some(new_label) = node
+                .props
+                .get_str(prop::LABEL)
+                .and_then(|label| renamed.get(label))
+        {
+            node.props.set(prop::LABEL, new_label.as_str());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rescribe_core::Resource;
+    use rescribe_std::NodeExt;
+    use rescribe_std::builder::doc;
+
+    fn chapter(title: &str, note: &str) -> Document {
+        let mut chapter = doc(|d| {
+            d.heading(1, |i| i.text(title))
+                .para(|i| i.text("See").footnote_ref("1"))
+        });
+        chapter
+            .content
+            .children
+            .push(Node::new(node::FOOTNOTE_DEF).prop(prop::LABEL, "1").child(
+                Node::new(node::PARAGRAPH).child(Node::new(node::TEXT).prop(prop::CONTENT, note)),
+            ));
+        chapter
+    }
+
+    fn labels(doc: &Document) -> Vec<String> {
+        let mut labels = Vec::new();
+        crate::walk(&doc.content, &mut |node| {
+            if let Some(label) = node.props.get_str(prop::LABEL) {
+                labels.push(label.to_string());
+            }
+        });
+        labels
+    }
+
+    #[test]
+    fn test_concat() {
+        let mut one = chapter("One", "first");
+        one.metadata.set("title", "Book");
+        one.metadata.set("author", "A");
+        let image = ResourceId::from_string("image1");
+        one.resources.insert(image.clone(), Resource::png(vec![1]));
+        let mut two = chapter("Two", "second");
+        two.metadata.set("title", "Chapter Two");
+        two.metadata.set("lang", "en");
+        two.resources.insert(image.clone(), Resource::png(vec![2]));
+        two.content
+            .children
+            .push(Node::new(node::IMAGE).prop(prop::RESOURCE_ID, image.as_str()));
+
+        let result = Concat::new()
+            .shift_headings(1)
+            .concat([one, two, chapter("Three", "third")])
+            .unwrap();
+        let book = result.value;
+
+        assert_eq!(book.content.children.len(), 10);
+        let heading = book.content.children[3].as_heading().unwrap();
+        assert_eq!(heading.level(), 2);
+        assert_eq!(labels(&book), ["1", "1", "1-2", "1-2", "1-3", "1-3"]);
+
+        assert_eq!(book.metadata.get_str("title"), Some("Book"));
+        assert_eq!(book.metadata.get_str("author"), Some("A"));
+        assert_eq!(book.metadata.get_str("lang"), Some("en"));
+        let messages: Vec<_> = result.warnings.iter().map(|w| &w.message).collect();
+        assert_eq!(
+            messages,
+            ["metadata 'title' of document 2 was dropped in favour of an earlier document's"]
+        );
+
+        assert_eq!(book.resources.len(), 2);
+        let moved = book.content.children[6]
+            .props
+            .get_str(prop::RESOURCE_ID)
+            .unwrap();
+        assert_ne!(moved, image.as_str());
+        assert_eq!(book.resources[&ResourceId::from_string(moved)].data, [2]);
+        assert_eq!(book.resources[&image].data, [1]);
+    }
+
+    #[test]
+    fn test_concat_metadata_last() {
+        let mut one = doc(|d| d.para(|i| i.text("a")));
+        one.metadata.set("title", "One");
+        let mut two = doc(|d| d.para(|i| i.text("b")));
+        two.metadata.set("title", "Two");
+        let merged = Concat::new()
+            .metadata(MetadataPrecedence::Last)
+            .concat([one, two])
+            .unwrap()
+            .value;
+        assert_eq!(merged.metadata.get_str("title"), Some("Two"));
+    }
+
+    #[test]
+    fn test_concat_stored_resource_ids() {
+        // As if read back from rescribe JSON: both documents use `res_0`
+        // and the IDs the counter is about to generate.
+        let next: usize = ResourceId::new().as_str()[4..].parse().unwrap();
+        let stored = |data: u8| {
+            let mut doc = doc(|d| d.para(|i| i.text("x")));
+            for n in 0..next + 4 {
+                let id = ResourceId::from_string(format!("res_{n}"));
+                doc.resources.insert(id.clone(), Resource::png(vec![data]));
+                doc.content.children[0]
+                    .children
+                    .push(Node::new(node::IMAGE).prop(prop::RESOURCE_ID, id.as_str()));
+            }
+            doc
+        };
+
+        let book = Concat::new().concat([stored(1), stored(2)]).unwrap().value;
+        assert_eq!(book.resources.len(), 2 * (next + 4));
+        for (para, data) in book.content.children.iter().zip([1, 2]) {
+            for image in &para.children[1..] {
+                let id = image.props.get_str(prop::RESOURCE_ID).unwrap();
+                assert_eq!(book.resources[&ResourceId::from_string(id)].data, [data]);
+            }
+        }
+    }
+}
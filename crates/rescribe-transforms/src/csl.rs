@@ -0,0 +1,2276 @@
+//! Citation Style Language styles and locales.
+//!
+//! A [`Style`] is a CSL 1.0 style, the XML format Zotero, Mendeley and
+//! Pandoc use to describe how citations and bibliography entries look. It
+//! takes terms such as "edited by" and the names of months from a
+//! [`Locale`]. A few styles and locales are bundled, so citations can be
+//! formatted without fetching anything; see [`Style::bundled`] and
+//! [`Locale::bundled`]. The [`Citeproc`](crate::Citeproc) transform applies
+//! a style to a document.
+//!
+//! ```
+//! use rescribe_transforms::csl::{Locale, Style};
+//!
+//! let style = Style::bundled("chicago-author-date").unwrap();
+//! assert!(!style.is_note_style());
+//! assert!(Style::parse("<style/>").is_err());
+//! assert_eq!(Locale::bundled("de").unwrap().lang(), "de-DE");
+//! ```
+//!
+//! The processor covers what common styles use: macros, conditionals,
+//! groups, names with initials, et-al and substitution, localized and
+//! custom dates and date ranges, numbers, labels, text case, page range
+//! formats, sorting, cite positions (for "ibid." and short forms),
+//! disambiguation by year suffix and collapsing of cite numbers and years.
+//! Disambiguation by adding names or given names is not supported, nor is
+//! the `display` attribute.
+
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use rescribe_bib::{DateParts, Entry, Name, PropValue};
+use rescribe_std::{Node, node, prop};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+/// Styles bundled with the crate, by ID.
+const STYLES: &[(&str, &str)] = &[
+    (
+        "chicago-author-date",
+        include_str!("../csl/chicago-author-date.csl"),
+    ),
+    ("chicago-note", include_str!("../csl/chicago-note.csl")),
+    ("ieee", include_str!("../csl/ieee.csl")),
+];
+
+/// Locales bundled with the crate, by language tag.
+const LOCALES: &[(&str, &str)] = &[
+    ("en-US", include_str!("../csl/locales-en-US.xml")),
+    ("de-DE", include_str!("../csl/locales-de-DE.xml")),
+];
+
+/// Error from parsing a CSL style or locale.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid CSL: {message}")]
+pub struct CslError {
+    pub message: String,
+}
+
+impl CslError {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+/// An element of a style or locale.
+#[derive(Debug, Clone, Default)]
+struct El {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<El>,
+    text: String,
+}
+
+impl El {
+    fn attr(&self, key: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn set_attr(&mut self, key: &str, value: &str) {
+        match self.attrs.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.attrs.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    fn child(&self, name: &str) -> Option<&El> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    /// Parse an XML document into its root element, with namespace
+    /// prefixes dropped from names.
+    fn parse(xml: &str) -> Result<El, CslError> {
+        let mut reader = Reader::from_str(xml);
+        let mut stack: Vec<El> = Vec::new();
+        loop {
+            let event = reader
+                .read_event()
+                .map_err(|e| CslError::new(format!("XML error: {e}")))?;
+            let done = match event {
+                Event::Start(e) => {
+                    stack.push(El::open(&e, &reader)?);
+                    None
+                }
+                Event::Empty(e) => Some(El::open(&e, &reader)?),
+                Event::End(_) => stack.pop(),
+                Event::Text(e) => {
+                    if let Some(el) = stack.last_mut() {
+                        let text = e.decode().map_err(|e| CslError::new(e.to_string()))?;
+                        el.text.push_str(&text);
+                    }
+                    None
+                }
+                Event::CData(e) => {
+                    if let Some(el) = stack.last_mut() {
+                        let text = e.decode().map_err(|e| CslError::new(e.to_string()))?;
+                        el.text.push_str(&text);
+                    }
+                    None
+                }
+                Event::GeneralRef(e) => {
+                    if let Some(el) = stack.last_mut() {
+                        let name = String::from_utf8_lossy(e.as_ref()).to_string();
+                        match e.resolve_char_ref().ok().flatten() {
+                            Some(ch) => el.text.push(ch),
+                            None => match quick_xml::escape::resolve_xml_entity(&name) {
+                                Some(entity) => el.text.push_str(entity),
+                                None => {
+                                    return Err(CslError::new(format!("unknown entity &{name};")));
+                                }
+                            },
+                        }
+                    }
+                    None
+                }
+                Event::Eof => return Err(CslError::new("unexpected end of document")),
+                _ => None,
+            };
+            if let Some(el) = done {
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(el),
+                    None => return Ok(el),
+                }
+            }
+        }
+    }
+
+    fn open(e: &BytesStart, reader: &Reader<&[u8]>) -> Result<El, CslError> {
+        let mut el = El {
+            name: String::from_utf8_lossy(e.local_name().as_ref()).to_string(),
+            ..El::default()
+        };
+        for attr in e.attributes() {
+            let attr = attr.map_err(|e| CslError::new(format!("XML error: {e}")))?;
+            let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
+            let value = attr
+                .decode_and_unescape_value(reader.decoder())
+                .map_err(|e| CslError::new(format!("XML error: {e}")))?;
+            el.attrs.push((key, value.into_owned()));
+        }
+        Ok(el)
+    }
+}
+
+/// A CSL style.
+#[derive(Debug, Clone)]
+pub struct Style {
+    root: El,
+    macros: HashMap<String, El>,
+    /// Locale overrides in the style.
+    locales: Vec<Locale>,
+    /// Whether the style places the year suffix itself, rather than after
+    /// the first rendered year.
+    explicit_year_suffix: bool,
+}
+
+impl Style {
+    /// Parse a style from its XML.
+    pub fn parse(xml: &str) -> Result<Self, CslError> {
+        let root = El::parse(xml)?;
+        if root.name != "style" {
+            return Err(CslError::new(format!(
+                "expected <style>, found <{}>",
+                root.name
+            )));
+        }
+        if root
+            .child("citation")
+            .and_then(|c| c.child("layout"))
+            .is_none()
+        {
+            return Err(CslError::new("style has no citation layout"));
+        }
+        let macros: HashMap<String, El> = root
+            .children
+            .iter()
+            .filter(|c| c.name == "macro")
+            .filter_map(|c| Some((c.attr("name")?.to_string(), c.clone())))
+            .collect();
+        if let Some(name) = find_macro_call(&root, &|name| !macros.contains_key(name)) {
+            return Err(CslError::new(format!("unknown macro '{name}'")));
+        }
+        let locales = root
+            .children
+            .iter()
+            .filter(|c| c.name == "locale")
+            .map(Locale::from_el)
+            .collect();
+        let explicit_year_suffix = uses_variable(&root, "year-suffix");
+        Ok(Self {
+            root,
+            macros,
+            locales,
+            explicit_year_suffix,
+        })
+    }
+
+    /// A bundled style by ID: `chicago-author-date`, `chicago-note` or
+    /// `ieee`.
+    ///
+    /// These are abridged versions of the well-known styles, covering
+    /// articles, books, chapters, theses and web pages.
+    pub fn bundled(id: &str) -> Option<Self> {
+        let (_, xml) = STYLES.iter().find(|(name, _)| *name == id)?;
+        Some(Self::parse(xml).expect("bundled style is valid"))
+    }
+
+    /// IDs of the bundled styles.
+    pub fn bundled_ids() -> impl Iterator<Item = &'static str> {
+        STYLES.iter().map(|(id, _)| *id)
+    }
+
+    /// The title from the style's `<info>`.
+    pub fn title(&self) -> Option<&str> {
+        let title = self.root.child("info")?.child("title")?;
+        Some(title.text.trim())
+    }
+
+    /// Whether citations go in footnotes rather than in the text.
+    pub fn is_note_style(&self) -> bool {
+        self.root.attr("class") == Some("note")
+    }
+
+    /// The language the style is written for, if it names one.
+    pub fn default_locale(&self) -> Option<&str> {
+        self.root.attr("default-locale")
+    }
+
+    fn citation(&self) -> &El {
+        self.root.child("citation").expect("checked when parsing")
+    }
+
+    fn bibliography(&self) -> Option<&El> {
+        self.root
+            .child("bibliography")
+            .filter(|b| b.child("layout").is_some())
+    }
+}
+
+/// The first macro called under `el` for which `matches` holds.
+fn find_macro_call(el: &El, matches: &dyn Fn(&str) -> bool) -> Option<String> {
+    if let Some(name) = el.attr("macro")
+        && el.name != "macro"
+        && matches(name)
+    {
+        return Some(name.to_string());
+    }
+    el.children
+        .iter()
+        .find_map(|child| find_macro_call(child, matches))
+}
+
+fn uses_variable(el: &El, variable: &str) -> bool {
+    el.attr("variable")
+        .is_some_and(|v| v.split_whitespace().any(|v| v == variable))
+        || el.children.iter().any(|c| uses_variable(c, variable))
+}
+
+/// Singular and plural forms of a term.
+#[derive(Debug, Clone)]
+struct Term {
+    single: String,
+    multiple: String,
+}
+
+/// Terms, date formats and punctuation rules of a language.
+#[derive(Debug, Clone)]
+pub struct Locale {
+    lang: String,
+    /// Terms by name and form.
+    terms: HashMap<(String, String), Term>,
+    /// Date formats by form (`text` or `numeric`).
+    dates: HashMap<String, El>,
+    punctuation_in_quote: Option<bool>,
+    limit_day_ordinals: Option<bool>,
+}
+
+impl Locale {
+    /// Parse a locale from its XML.
+    pub fn parse(xml: &str) -> Result<Self, CslError> {
+        let root = El::parse(xml)?;
+        if root.name != "locale" {
+            return Err(CslError::new(format!(
+                "expected <locale>, found <{}>",
+                root.name
+            )));
+        }
+        Ok(Self::from_el(&root))
+    }
+
+    /// A bundled locale by language tag, such as `en-US` or `de`; a bare
+    /// language matches its bundled variant.
+    pub fn bundled(tag: &str) -> Option<Self> {
+        let tag = tag.replace('_', "-");
+        let language = tag.split('-').next().unwrap_or_default();
+        let (_, xml) = LOCALES
+            .iter()
+            .find(|(lang, _)| lang.eq_ignore_ascii_case(&tag))
+            .or_else(|| {
+                LOCALES.iter().find(|(lang, _)| {
+                    lang.split('-')
+                        .next()
+                        .is_some_and(|l| l.eq_ignore_ascii_case(language))
+                })
+            })?;
+        Some(Self::parse(xml).expect("bundled locale is valid"))
+    }
+
+    /// Language tags of the bundled locales.
+    pub fn bundled_tags() -> impl Iterator<Item = &'static str> {
+        LOCALES.iter().map(|(tag, _)| *tag)
+    }
+
+    /// The language tag, such as `en-US`; empty for locale overrides in a
+    /// style that apply to any language.
+    pub fn lang(&self) -> &str {
+        &self.lang
+    }
+
+    fn from_el(el: &El) -> Self {
+        let mut locale = Self {
+            lang: el.attr("lang").unwrap_or_default().to_string(),
+            terms: HashMap::new(),
+            dates: HashMap::new(),
+            punctuation_in_quote: None,
+            limit_day_ordinals: None,
+        };
+        for child in &el.children {
+            match child.name.as_str() {
+                "style-options" => {
+                    let flag = |key: &str| child.attr(key).map(|v| v == "true");
+                    locale.punctuation_in_quote = flag("punctuation-in-quote");
+                    locale.limit_day_ordinals = flag("limit-day-ordinals-to-day-1");
+                }
+                "date" => {
+                    if let Some(form) = child.attr("form") {
+                        locale.dates.insert(form.to_string(), child.clone());
+                    }
+                }
+                "terms" => {
+                    for term in child.children.iter().filter(|t| t.name == "term") {
+                        let Some(name) = term.attr("name") else {
+                            continue;
+                        };
+                        let form = term.attr("form").unwrap_or("long");
+                        let text = |part: &str| term.child(part).map(|p| p.text.trim().to_string());
+                        let single = text("single").unwrap_or_else(|| term.text.trim().to_string());
+                        let multiple = text("multiple").unwrap_or_else(|| single.clone());
+                        locale.terms.insert(
+                            (name.to_string(), form.to_string()),
+                            Term { single, multiple },
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        locale
+    }
+
+    fn term(&self, name: &str, form: &str) -> Option<&Term> {
+        self.terms.get(&(name.to_string(), form.to_string()))
+    }
+}
+
+/// Where a cite stands relative to earlier cites of the same item.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Position {
+    #[default]
+    First,
+    Subsequent,
+    /// The same item as the cite just before.
+    Ibid,
+    /// The same item as the cite just before, at a different locator.
+    IbidWithLocator,
+}
+
+/// A bibliography entry being cited, with what the processor assigned it.
+pub(crate) struct Item<'a> {
+    pub(crate) entry: Entry<'a>,
+    /// Position in the bibliography, from 1.
+    pub(crate) number: usize,
+    /// Letter telling apart items that would otherwise cite the same.
+    pub(crate) year_suffix: Option<String>,
+}
+
+/// One cite of an item in the text.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CiteContext {
+    pub(crate) locator: Option<String>,
+    pub(crate) label: Option<String>,
+    pub(crate) position: Position,
+    /// Whether the item was cited in one of the last few notes.
+    pub(crate) near_note: bool,
+    /// Number of the note the item was first cited in.
+    pub(crate) first_note: Option<usize>,
+}
+
+/// What to do with the author, that is the first names rendered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum AuthorMode {
+    Normal,
+    /// Leave it out, for "(2020)" after "Smith" in the text.
+    Suppress,
+    /// Replace it, for "———" in place of a repeated author.
+    Substitute(String),
+}
+
+/// Output of rendering an item.
+pub(crate) struct Rendered {
+    pub(crate) out: Vec<Out>,
+    /// The author as it was rendered, before any suppression.
+    pub(crate) author: Option<Vec<Out>>,
+}
+
+/// Formatted text, converted to nodes once complete.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Out {
+    Text(String),
+    Styled(Fmt, Vec<Out>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Fmt {
+    Italic,
+    Bold,
+    SmallCaps,
+    Underline,
+    Superscript,
+    Subscript,
+    Quoted,
+    Link(String),
+    /// Protected from text case changes.
+    NoCase,
+}
+
+/// Formatting attributes and the formatting they apply.
+const FORMATTING: &[(&str, &str, Fmt)] = &[
+    ("font-style", "italic", Fmt::Italic),
+    ("font-style", "oblique", Fmt::Italic),
+    ("font-weight", "bold", Fmt::Bold),
+    ("font-variant", "small-caps", Fmt::SmallCaps),
+    ("text-decoration", "underline", Fmt::Underline),
+    ("vertical-align", "sup", Fmt::Superscript),
+    ("vertical-align", "sub", Fmt::Subscript),
+];
+
+/// Words title case leaves in lowercase.
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "but", "by", "down", "for", "from", "in", "into", "nor", "of",
+    "on", "onto", "or", "over", "so", "the", "till", "to", "up", "via", "with", "yet",
+];
+
+/// Append `piece`, dropping a period that would follow other end
+/// punctuation and a space that would double one.
+pub(crate) fn push(out: &mut Vec<Out>, piece: Out) {
+    match piece {
+        Out::Text(text) => push_text(out, &text),
+        styled => out.push(styled),
+    }
+}
+
+/// Append text as by [`push`].
+pub(crate) fn push_text(out: &mut Vec<Out>, text: &str) {
+    let text = match last_char(out) {
+        Some('.' | '!' | '?') if text.starts_with('.') => &text[1..],
+        Some(' ') if text.starts_with(' ') => &text[1..],
+        _ => text,
+    };
+    if text.is_empty() {
+        return;
+    }
+    match out.last_mut() {
+        Some(Out::Text(last)) => last.push_str(text),
+        _ => out.push(Out::Text(text.to_string())),
+    }
+}
+
+fn extend(out: &mut Vec<Out>, pieces: Vec<Out>) {
+    for piece in pieces {
+        push(out, piece);
+    }
+}
+
+fn last_char(out: &[Out]) -> Option<char> {
+    match out.last()? {
+        Out::Text(text) => text.chars().next_back(),
+        Out::Styled(Fmt::Quoted, _) => Some('"'),
+        Out::Styled(_, children) => last_char(children),
+    }
+}
+
+/// Join the non-empty parts with `delimiter`.
+pub(crate) fn join(parts: Vec<Vec<Out>>, delimiter: &str) -> Vec<Out> {
+    let mut out = Vec::new();
+    for part in parts.into_iter().filter(|p| !p.is_empty()) {
+        if !out.is_empty() {
+            push_text(&mut out, delimiter);
+        }
+        extend(&mut out, part);
+    }
+    out
+}
+
+/// Wrap `out` in a prefix and suffix, if it is not empty.
+pub(crate) fn affix(out: Vec<Out>, prefix: Option<&str>, suffix: Option<&str>) -> Vec<Out> {
+    if out.is_empty() {
+        return out;
+    }
+    let mut result = Vec::new();
+    if let Some(prefix) = prefix {
+        push_text(&mut result, prefix);
+    }
+    extend(&mut result, out);
+    if let Some(suffix) = suffix {
+        push_text(&mut result, suffix);
+    }
+    result
+}
+
+/// The plain text of `out`.
+pub(crate) fn plain(out: &[Out]) -> String {
+    let mut text = String::new();
+    for piece in out {
+        match piece {
+            Out::Text(t) => text.push_str(t),
+            Out::Styled(_, children) => text.push_str(&plain(children)),
+        }
+    }
+    text
+}
+
+/// Uppercase the first letter, unless protected.
+pub(crate) fn capitalize_first(out: &mut [Out]) {
+    fn go(out: &mut [Out]) -> bool {
+        for piece in out {
+            match piece {
+                Out::Text(text) => {
+                    if let Some((i, c)) = text.char_indices().find(|(_, c)| c.is_alphanumeric()) {
+                        if c.is_lowercase() {
+                            let upper: String = c.to_uppercase().collect();
+                            text.replace_range(i..i + c.len_utf8(), &upper);
+                        }
+                        return true;
+                    }
+                }
+                Out::Styled(Fmt::NoCase, _) => return true,
+                Out::Styled(_, children) => {
+                    if go(children) {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+    go(out);
+}
+
+fn map_text(out: &mut [Out], f: &dyn Fn(&str) -> String) {
+    for piece in out {
+        match piece {
+            Out::Text(text) => *text = f(text),
+            Out::Styled(Fmt::NoCase, _) => {}
+            Out::Styled(_, children) => map_text(children, f),
+        }
+    }
+}
+
+/// Title case, keeping words that are not all lowercase as they are.
+fn title_case(out: &mut [Out], first: &mut bool) {
+    for piece in out {
+        match piece {
+            Out::Text(text) => {
+                let mut result = String::with_capacity(text.len());
+                let mut word = String::new();
+                let flush = |word: &mut String, result: &mut String, first: &mut bool| {
+                    if word.is_empty() {
+                        return;
+                    }
+                    let lower = word.to_lowercase();
+                    if !*first && STOP_WORDS.contains(&lower.as_str()) {
+                        result.push_str(&lower);
+                    } else if *word == lower {
+                        let mut chars = word.chars();
+                        if let Some(c) = chars.next() {
+                            result.extend(c.to_uppercase());
+                            result.push_str(chars.as_str());
+                        }
+                    } else {
+                        result.push_str(word);
+                    }
+                    *first = false;
+                    word.clear();
+                };
+                for c in text.chars() {
+                    if c.is_alphanumeric() || c == '\'' || c == '\u{2019}' {
+                        word.push(c);
+                    } else {
+                        flush(&mut word, &mut result, first);
+                        if c == ':' {
+                            *first = true;
+                        }
+                        result.push(c);
+                    }
+                }
+                flush(&mut word, &mut result, first);
+                *text = result;
+            }
+            Out::Styled(Fmt::NoCase, _) => *first = false,
+            Out::Styled(_, children) => title_case(children, first),
+        }
+    }
+}
+
+/// Read inline markup in a variable: `<i>`, `<b>`, `<sup>`, `<sub>`,
+/// `<sc>` and `<span class="nocase">`, as found in CSL JSON.
+fn parse_markup(text: &str) -> Vec<Out> {
+    /// An opening tag, its closing tag and the formatting they apply.
+    type Tag = (&'static str, &'static str, Fmt);
+    const TAGS: &[Tag] = &[
+        ("<i>", "</i>", Fmt::Italic),
+        ("<b>", "</b>", Fmt::Bold),
+        ("<sup>", "</sup>", Fmt::Superscript),
+        ("<sub>", "</sub>", Fmt::Subscript),
+        ("<sc>", "</sc>", Fmt::SmallCaps),
+        ("<span class=\"nocase\">", "</span>", Fmt::NoCase),
+        (
+            "<span style=\"font-variant:small-caps;\">",
+            "</span>",
+            Fmt::SmallCaps,
+        ),
+    ];
+    if !text.contains('<') {
+        return vec![Out::Text(text.to_string())];
+    }
+
+    let append = |out: &mut Vec<Out>, s: &str| match out.last_mut() {
+        Some(Out::Text(last)) => last.push_str(s),
+        _ => out.push(Out::Text(s.to_string())),
+    };
+    let mut stack: Vec<(Option<&Tag>, Vec<Out>)> = vec![(None, Vec::new())];
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some(tag) = TAGS.iter().find(|(open, _, _)| rest.starts_with(open)) {
+            stack.push((Some(tag), Vec::new()));
+            rest = &rest[tag.0.len()..];
+            continue;
+        }
+        if let Some((Some((_, close, _)), _)) = stack.last()
+            && rest.starts_with(close)
+        {
+            rest = &rest[close.len()..];
+            let (tag, children) = stack.pop().expect("tag is open");
+            let fmt = tag.expect("tag is open").2.clone();
+            stack
+                .last_mut()
+                .expect("root is never popped")
+                .1
+                .push(Out::Styled(fmt, children));
+            continue;
+        }
+        append(
+            &mut stack.last_mut().expect("root is never popped").1,
+            &rest[..c.len_utf8()],
+        );
+        rest = &rest[c.len_utf8()..];
+    }
+    // Unclosed tags format the rest of the text.
+    while stack.len() > 1 {
+        let (tag, children) = stack.pop().expect("length checked");
+        let fmt = tag.expect("only the root has no tag").2.clone();
+        let parent = stack.last_mut().expect("root is never popped");
+        parent.1.push(Out::Styled(fmt, children));
+    }
+    stack.pop().map(|(_, out)| out).unwrap_or_default()
+}
+
+/// Whether a variable holds numbers, such as "12", "3-5", "L2" or "2nd".
+fn is_numeric(value: &str) -> bool {
+    let mut tokens = value
+        .split(|c: char| matches!(c, '-' | '–' | ',' | '&') || c.is_whitespace())
+        .filter(|t| !t.is_empty())
+        .peekable();
+    tokens.peek().is_some()
+        && tokens.all(|token| {
+            let digits = token.trim_start_matches(|c: char| c.is_alphabetic());
+            let prefix = token.len() - digits.len();
+            let rest = digits.trim_start_matches(|c: char| c.is_ascii_digit());
+            let number = digits.len() - rest.len();
+            prefix <= 1 && number > 0 && rest.len() <= 2 && rest.chars().all(char::is_alphabetic)
+        })
+}
+
+/// Whether a value names more than one thing, such as "3-5" or "2, 4".
+fn is_plural(value: &str) -> bool {
+    match value.trim().parse::<u64>() {
+        Ok(_) => false,
+        Err(_) => value.trim().contains(['-', '–', ',', '&']),
+    }
+}
+
+fn roman(mut n: u64) -> String {
+    const NUMERALS: &[(u64, &str)] = &[
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    if n == 0 || n >= 4000 {
+        return n.to_string();
+    }
+    let mut text = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= *value {
+            text.push_str(numeral);
+            n -= value;
+        }
+    }
+    text
+}
+
+/// The end of a page range as `format` abbreviates it, given its start.
+fn range_end(start: &str, end: &str, format: &str) -> String {
+    let all_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    if !all_digits(start) || !all_digits(end) {
+        return end.to_string();
+    }
+    // "321-8" is short for "321-328".
+    let expanded = if end.len() < start.len() {
+        format!("{}{end}", &start[..start.len() - end.len()])
+    } else {
+        end.to_string()
+    };
+    if expanded.len() != start.len() {
+        return expanded;
+    }
+    let minimal = |keep: usize| {
+        let common = start
+            .chars()
+            .zip(expanded.chars())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let cut = common.min(expanded.len().saturating_sub(keep));
+        expanded[cut..].to_string()
+    };
+    match format {
+        "expanded" => expanded,
+        "minimal" => minimal(1),
+        "minimal-two" => minimal(2),
+        "chicago" | "chicago-15" | "chicago-16" => {
+            let first: u64 = start.parse().unwrap_or_default();
+            let differing = start
+                .chars()
+                .zip(expanded.chars())
+                .skip_while(|(a, b)| a == b)
+                .count();
+            if first < 100 || first.is_multiple_of(100) || (first >= 1000 && differing >= 3) {
+                expanded
+            } else if first % 100 < 10 {
+                minimal(1)
+            } else {
+                minimal(2)
+            }
+        }
+        _ => end.to_string(),
+    }
+}
+
+/// Sort key of an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SortKey(Vec<(Option<SortValue>, bool)>);
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum SortValue {
+    Number(i64),
+    Text(String),
+}
+
+impl Ord for SortKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for ((a, descending), (b, _)) in self.0.iter().zip(&other.0) {
+            // Empty values sort last either way.
+            let ordering = match (a, b) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) if *descending => b.cmp(a),
+                (Some(a), Some(b)) => a.cmp(b),
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+impl PartialOrd for SortKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A style with the locales to look terms up in.
+pub(crate) struct Processor<'a> {
+    style: &'a Style,
+    /// Style overrides, the chosen locale and the fallback, in order.
+    locales: Vec<&'a Locale>,
+}
+
+impl<'a> Processor<'a> {
+    /// A processor using `locale`, with terms it lacks taken from
+    /// `fallback`.
+    pub(crate) fn new(style: &'a Style, locale: &'a Locale, fallback: &'a Locale) -> Self {
+        let language = locale.lang.split('-').next().unwrap_or_default();
+        let mut locales: Vec<&Locale> = Vec::new();
+        // Overrides for the exact language first, then for the bare
+        // language, then for any.
+        for lang in [locale.lang.as_str(), language, ""] {
+            locales.extend(style.locales.iter().filter(|l| l.lang == lang));
+        }
+        locales.push(locale);
+        locales.push(fallback);
+        Self { style, locales }
+    }
+
+    pub(crate) fn is_note(&self) -> bool {
+        self.style.is_note_style()
+    }
+
+    pub(crate) fn has_bibliography(&self) -> bool {
+        self.style.bibliography().is_some()
+    }
+
+    /// An attribute of the style's `<citation>`.
+    pub(crate) fn citation_option(&self, key: &str) -> Option<&'a str> {
+        self.style.citation().attr(key)
+    }
+
+    /// An attribute of the style's `<bibliography>`.
+    pub(crate) fn bibliography_option(&self, key: &str) -> Option<&'a str> {
+        self.style.bibliography()?.attr(key)
+    }
+
+    /// Delimiter between the cites of a citation.
+    pub(crate) fn citation_delimiter(&self) -> &'a str {
+        self.citation_layout().attr("delimiter").unwrap_or_default()
+    }
+
+    fn citation_layout(&self) -> &'a El {
+        self.style
+            .citation()
+            .child("layout")
+            .expect("checked when parsing")
+    }
+
+    /// A term, falling back to shorter or longer forms and to other locales.
+    pub(crate) fn term(&self, name: &str, form: &str, plural: bool) -> Option<&'a str> {
+        let forms: &[&str] = match form {
+            "short" => &["short", "long"],
+            "verb" => &["verb", "long"],
+            "verb-short" => &["verb-short", "verb", "long"],
+            "symbol" => &["symbol", "short", "long"],
+            _ => &["long"],
+        };
+        forms.iter().find_map(|form| {
+            self.locales
+                .iter()
+                .find_map(|l| l.term(name, form))
+                .map(|t| {
+                    if plural {
+                        t.multiple.as_str()
+                    } else {
+                        t.single.as_str()
+                    }
+                })
+        })
+    }
+
+    fn date_format(&self, form: &str) -> Option<&'a El> {
+        self.locales.iter().find_map(|l| l.dates.get(form))
+    }
+
+    fn is_english(&self) -> bool {
+        self.locales
+            .iter()
+            .find(|l| !l.lang.is_empty())
+            .is_none_or(|l| l.lang.starts_with("en"))
+    }
+
+    /// Render one cite of `item` with the citation layout, without the
+    /// layout's affixes.
+    pub(crate) fn render_cite(
+        &self,
+        item: &Item,
+        cite: &CiteContext,
+        author: AuthorMode,
+    ) -> Rendered {
+        let mut renderer = Renderer::new(self, item, Some(cite), self.style.citation(), author);
+        let out = renderer.render_children(&self.citation_layout().children);
+        Rendered {
+            out,
+            author: renderer.author,
+        }
+    }
+
+    /// Render the bibliography entry of `item`, with the layout's affixes.
+    pub(crate) fn render_entry(&self, item: &Item, author: AuthorMode) -> Rendered {
+        let bibliography = self.style.bibliography().expect("style has a bibliography");
+        let layout = bibliography.child("layout").expect("checked");
+        let mut renderer = Renderer::new(self, item, None, bibliography, author);
+        let out = renderer.render_children(&layout.children);
+        let out = renderer.decorate(layout, out);
+        Rendered {
+            out,
+            author: renderer.author,
+        }
+    }
+
+    /// Apply the citation layout's formatting and affixes to a citation.
+    pub(crate) fn wrap_citation(&self, out: Vec<Out>) -> Vec<Out> {
+        let layout = self.citation_layout();
+        let out = formatted(layout, out);
+        affix(out, layout.attr("prefix"), layout.attr("suffix"))
+    }
+
+    /// Sort key of a cite by the citation's `<sort>`, if it has one.
+    pub(crate) fn citation_sort_key(&self, item: &Item, cite: &CiteContext) -> Option<SortKey> {
+        let sort = self.style.citation().child("sort")?;
+        Some(self.sort_key(sort, self.style.citation(), item, Some(cite)))
+    }
+
+    /// Sort key of an entry by the bibliography's `<sort>`, if it has one.
+    pub(crate) fn bibliography_sort_key(&self, item: &Item) -> Option<SortKey> {
+        let bibliography = self.style.bibliography()?;
+        let sort = bibliography.child("sort")?;
+        Some(self.sort_key(sort, bibliography, item, None))
+    }
+
+    fn sort_key(&self, sort: &El, layout: &El, item: &Item, cite: Option<&CiteContext>) -> SortKey {
+        let values = sort
+            .children
+            .iter()
+            .filter(|k| k.name == "key")
+            .map(|key| {
+                let mut renderer = Renderer::new(self, item, cite, layout, AuthorMode::Normal);
+                renderer.sorting = true;
+                let number = |attr: &str| key.attr(attr).and_then(|v| v.parse().ok());
+                if let (Some(min), Some(first)) = (number("names-min"), number("names-use-first")) {
+                    renderer.et_al = Some((min, first));
+                }
+                let descending = key.attr("sort") == Some("descending");
+                (renderer.sort_value(key), descending)
+            })
+            .collect();
+        SortKey(values)
+    }
+
+    /// Convert finished output to nodes, moving punctuation into quotes
+    /// where the locale asks for it.
+    pub(crate) fn finish(&self, mut out: Vec<Out>) -> Vec<Node> {
+        let in_quote = self
+            .locales
+            .iter()
+            .find_map(|l| l.punctuation_in_quote)
+            .unwrap_or(false);
+        if in_quote {
+            punctuation_in_quote(&mut out);
+        }
+        self.to_nodes(out, 0)
+    }
+
+    fn to_nodes(&self, out: Vec<Out>, quote_depth: usize) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        for piece in out {
+            let (kind, children) = match piece {
+                Out::Text(text) => {
+                    nodes.push(Node::new(node::TEXT).prop(prop::CONTENT, text));
+                    continue;
+                }
+                Out::Styled(Fmt::NoCase, children) => {
+                    nodes.extend(self.to_nodes(children, quote_depth));
+                    continue;
+                }
+                Out::Styled(Fmt::Quoted, children) => {
+                    let inner = quote_depth % 2 == 1;
+                    let (open, close, kind) = if inner {
+                        ("open-inner-quote", "close-inner-quote", "single")
+                    } else {
+                        ("open-quote", "close-quote", "double")
+                    };
+                    let mut quoted = Node::new(node::QUOTED).prop(prop::QUOTE_TYPE, kind);
+                    if let Some(open) = self.term(open, "long", false) {
+                        quoted = quoted.prop(prop::QUOTE_OPEN, open);
+                    }
+                    if let Some(close) = self.term(close, "long", false) {
+                        quoted = quoted.prop(prop::QUOTE_CLOSE, close);
+                    }
+                    nodes.push(quoted.children(self.to_nodes(children, quote_depth + 1)));
+                    continue;
+                }
+                Out::Styled(Fmt::Link(url), children) => {
+                    let link = Node::new(node::LINK).prop(prop::URL, url);
+                    nodes.push(link.children(self.to_nodes(children, quote_depth)));
+                    continue;
+                }
+                Out::Styled(Fmt::Italic, children) => (node::EMPHASIS, children),
+                Out::Styled(Fmt::Bold, children) => (node::STRONG, children),
+                Out::Styled(Fmt::SmallCaps, children) => (node::SMALL_CAPS, children),
+                Out::Styled(Fmt::Underline, children) => (node::UNDERLINE, children),
+                Out::Styled(Fmt::Superscript, children) => (node::SUPERSCRIPT, children),
+                Out::Styled(Fmt::Subscript, children) => (node::SUBSCRIPT, children),
+            };
+            nodes.push(Node::new(kind).children(self.to_nodes(children, quote_depth)));
+        }
+        nodes
+    }
+}
+
+/// Move a period or comma that follows a quotation into it.
+fn punctuation_in_quote(out: &mut Vec<Out>) {
+    let mut i = 0;
+    while i < out.len() {
+        if let Out::Styled(_, children) = &mut out[i] {
+            punctuation_in_quote(children);
+        }
+        let mark = match (&out[i], out.get(i + 1)) {
+            (Out::Styled(Fmt::Quoted, _), Some(Out::Text(next)))
+                if next.starts_with(['.', ',']) =>
+            {
+                next.chars().next()
+            }
+            _ => None,
+        };
+        if let Some(mark) = mark {
+            if let Out::Text(next) = &mut out[i + 1] {
+                next.remove(0);
+                if next.is_empty() {
+                    out.remove(i + 1);
+                }
+            }
+            if let Out::Styled(_, children) = &mut out[i]
+                && !(mark == '.' && matches!(last_char(children), Some('.' | '!' | '?')))
+            {
+                push_text(children, &mark.to_string());
+            }
+        }
+        i += 1;
+    }
+}
+
+/// Apply formatting attributes of `el` to `out`.
+fn formatted(el: &El, mut out: Vec<Out>) -> Vec<Out> {
+    if out.is_empty() {
+        return out;
+    }
+    for (attr, value, fmt) in FORMATTING {
+        if el.attr(attr) == Some(*value) {
+            out = vec![Out::Styled(fmt.clone(), out)];
+        }
+    }
+    out
+}
+
+/// The property holding a CSL variable.
+fn bib_key(variable: &str) -> String {
+    format!("{}{variable}", rescribe_bib::PREFIX)
+}
+
+/// A string or number variable of an entry.
+fn entry_value(entry: Entry, variable: &str) -> Option<String> {
+    match entry.node().props.get(&bib_key(variable))? {
+        PropValue::String(s) if !s.trim().is_empty() => Some(s.clone()),
+        PropValue::Int(n) => Some(n.to_string()),
+        PropValue::Float(f) => Some(f.to_string()),
+        _ => None,
+    }
+}
+
+/// Rendering state for one item.
+struct Renderer<'a> {
+    proc: &'a Processor<'a>,
+    item: &'a Item<'a>,
+    cite: Option<&'a CiteContext>,
+    /// `<citation>` or `<bibliography>`, for inherited name options.
+    layout: &'a El,
+    author_mode: AuthorMode,
+    author: Option<Vec<Out>>,
+    author_done: bool,
+    /// Depth of nested `<names>`, which are substitutes.
+    names_depth: usize,
+    /// The `<names>` whose substitute is being rendered.
+    names_parent: Option<&'a El>,
+    /// Variables already used as substitutes.
+    suppressed: HashSet<String>,
+    /// Variables rendered so far.
+    used: Vec<String>,
+    year_suffix_done: bool,
+    /// Variables called and rendered, for suppressing empty groups.
+    called: usize,
+    rendered: usize,
+    /// Rendering a sort key: names inverted, for comparison.
+    sorting: bool,
+    /// Et-al minimum and count of names shown, overriding the style.
+    et_al: Option<(usize, usize)>,
+    macro_depth: usize,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(
+        proc: &'a Processor<'a>,
+        item: &'a Item<'a>,
+        cite: Option<&'a CiteContext>,
+        layout: &'a El,
+        author_mode: AuthorMode,
+    ) -> Self {
+        Self {
+            proc,
+            item,
+            cite,
+            layout,
+            author_mode,
+            author: None,
+            author_done: false,
+            names_depth: 0,
+            names_parent: None,
+            suppressed: HashSet::new(),
+            used: Vec::new(),
+            year_suffix_done: false,
+            called: 0,
+            rendered: 0,
+            sorting: false,
+            et_al: None,
+            macro_depth: 0,
+        }
+    }
+
+    fn render_children(&mut self, children: &'a [El]) -> Vec<Out> {
+        let mut out = Vec::new();
+        for child in children {
+            let rendered = self.render(child);
+            extend(&mut out, rendered);
+        }
+        out
+    }
+
+    fn render(&mut self, el: &'a El) -> Vec<Out> {
+        match el.name.as_str() {
+            "text" => self.render_text(el),
+            "number" => self.render_number(el),
+            "label" => self.render_label(el),
+            "date" => self.render_date(el),
+            "names" => self.render_names(el),
+            "group" => self.render_group(el),
+            "choose" => self.render_choose(el),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Apply text case, quotes, formatting and affixes of `el`.
+    fn decorate(&self, el: &El, out: Vec<Out>) -> Vec<Out> {
+        let out = self.styled(el, out);
+        affix(out, el.attr("prefix"), el.attr("suffix"))
+    }
+
+    fn styled(&self, el: &El, mut out: Vec<Out>) -> Vec<Out> {
+        if out.is_empty() {
+            return out;
+        }
+        if el.attr("strip-periods") == Some("true") {
+            map_text(&mut out, &|s| s.replace('.', ""));
+        }
+        match el.attr("text-case") {
+            Some("lowercase") => map_text(&mut out, &|s| s.to_lowercase()),
+            Some("uppercase") => map_text(&mut out, &|s| s.to_uppercase()),
+            Some("capitalize-first") => capitalize_first(&mut out),
+            Some("capitalize-all") => title_case(&mut out, &mut true),
+            Some("sentence") => {
+                if plain(&out).chars().all(|c| !c.is_lowercase()) {
+                    map_text(&mut out, &|s| s.to_lowercase());
+                }
+                capitalize_first(&mut out);
+            }
+            Some("title") if self.proc.is_english() => title_case(&mut out, &mut true),
+            _ => {}
+        }
+        if el.attr("quotes") == Some("true") {
+            out = vec![Out::Styled(Fmt::Quoted, out)];
+        }
+        formatted(el, out)
+    }
+
+    fn render_text(&mut self, el: &'a El) -> Vec<Out> {
+        let out = if let Some(variable) = el.attr("variable") {
+            let out = self.variable(variable, el.attr("form").unwrap_or("long"));
+            // A DOI prefixed with its resolver is linked as a whole.
+            if variable == "DOI"
+                && let Some(prefix) = el.attr("prefix").filter(|p| p.contains("doi.org/"))
+                && let [Out::Styled(Fmt::Link(url), _)] = out.as_slice()
+            {
+                let doi = plain(&out);
+                let link = Out::Styled(
+                    Fmt::Link(url.clone()),
+                    vec![Out::Text(format!("{prefix}{doi}"))],
+                );
+                let out = self.styled(el, vec![link]);
+                return affix(out, None, el.attr("suffix"));
+            }
+            out
+        } else if let Some(name) = el.attr("macro") {
+            self.render_macro(name)
+        } else if let Some(term) = el.attr("term") {
+            let plural = el.attr("plural") == Some("true");
+            let form = el.attr("form").unwrap_or("long");
+            self.proc
+                .term(term, form, plural)
+                .filter(|t| !t.is_empty())
+                .map(|t| vec![Out::Text(t.to_string())])
+                .unwrap_or_default()
+        } else if let Some(value) = el.attr("value") {
+            vec![Out::Text(value.to_string())]
+        } else {
+            Vec::new()
+        };
+        self.decorate(el, out)
+    }
+
+    fn render_macro(&mut self, name: &str) -> Vec<Out> {
+        let Some(definition) = self.proc.style.macros.get(name) else {
+            return Vec::new();
+        };
+        // Styles calling macros in a cycle are not worth a stack overflow.
+        if self.macro_depth > 32 {
+            return Vec::new();
+        }
+        self.macro_depth += 1;
+        let out = self.render_children(&definition.children);
+        self.macro_depth -= 1;
+        out
+    }
+
+    /// A string or number variable, formatted for display.
+    fn variable(&mut self, name: &str, form: &str) -> Vec<Out> {
+        self.called += 1;
+        let Some(value) = self.value(name, form) else {
+            return Vec::new();
+        };
+        self.rendered += 1;
+        self.used.push(name.to_string());
+        match name {
+            "URL" => vec![Out::Styled(
+                Fmt::Link(value.clone()),
+                vec![Out::Text(value)],
+            )],
+            "DOI" => {
+                let url = if value.starts_with("http") {
+                    value.clone()
+                } else {
+                    format!("https://doi.org/{value}")
+                };
+                vec![Out::Styled(Fmt::Link(url), vec![Out::Text(value)])]
+            }
+            "page" => vec![Out::Text(self.page_range(&value))],
+            "locator"
+                if self
+                    .cite
+                    .and_then(|c| c.label.as_deref())
+                    .is_none_or(|l| l == "page") =>
+            {
+                vec![Out::Text(self.page_range(&value))]
+            }
+            _ => parse_markup(&value),
+        }
+    }
+
+    /// The raw value of a string or number variable.
+    fn value(&mut self, name: &str, form: &str) -> Option<String> {
+        if self.suppressed.contains(name) {
+            return None;
+        }
+        match name {
+            "locator" => self.cite?.locator.clone(),
+            "citation-number" => Some(self.item.number.to_string()),
+            "first-reference-note-number" => self.cite?.first_note.map(|n| n.to_string()),
+            "year-suffix" => {
+                self.year_suffix_done = true;
+                self.item.year_suffix.clone()
+            }
+            "citation-label" => {
+                let names = self.item.entry.names(&bib_key("author"));
+                let family = names.first().and_then(Name::family_part)?;
+                let year = self.item.entry.date(&bib_key("issued"))?.year()?;
+                let short: String = family
+                    .chars()
+                    .filter(|c| c.is_alphabetic())
+                    .take(4)
+                    .collect();
+                Some(format!("{short}{:02}", year.rem_euclid(100)))
+            }
+            _ => {
+                if form == "short"
+                    && let Some(short) = entry_value(self.item.entry, &format!("{name}-short"))
+                {
+                    return Some(short);
+                }
+                entry_value(self.item.entry, name)
+            }
+        }
+    }
+
+    /// Format the page ranges in `value` with the style's
+    /// `page-range-format` and the locale's range delimiter.
+    fn page_range(&self, value: &str) -> String {
+        let delimiter = self
+            .proc
+            .term("page-range-delimiter", "long", false)
+            .unwrap_or("\u{2013}");
+        let format = self.proc.style.root.attr("page-range-format");
+        value
+            .split(',')
+            .map(|part| {
+                let trimmed = part.trim_start();
+                let lead = &part[..part.len() - trimmed.len()];
+                match trimmed.split_once(['-', '\u{2013}']) {
+                    Some((start, end)) => {
+                        let start = start.trim();
+                        let end = end.trim_start_matches(['-', '\u{2013}']).trim();
+                        let end = match format {
+                            Some(format) => range_end(start, end, format),
+                            None => end.to_string(),
+                        };
+                        format!("{lead}{start}{delimiter}{end}")
+                    }
+                    None => part.to_string(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    fn render_number(&mut self, el: &'a El) -> Vec<Out> {
+        let Some(variable) = el.attr("variable") else {
+            return Vec::new();
+        };
+        self.called += 1;
+        let Some(value) = self.value(variable, "long") else {
+            return Vec::new();
+        };
+        self.rendered += 1;
+        self.used.push(variable.to_string());
+        let form = el.attr("form").unwrap_or("numeric");
+        let text = if is_numeric(&value) {
+            self.format_numbers(&value, form)
+        } else {
+            value
+        };
+        self.decorate(el, vec![Out::Text(text)])
+    }
+
+    /// Format each number in `value`, with hyphens between numbers as en
+    /// dashes.
+    fn format_numbers(&self, value: &str, form: &str) -> String {
+        let mut text = String::new();
+        let mut token = String::new();
+        let flush = |token: &mut String, text: &mut String| {
+            match token.parse::<u64>() {
+                Ok(n) => text.push_str(&self.format_number(n, form)),
+                Err(_) => text.push_str(token),
+            }
+            token.clear();
+        };
+        for c in value.chars() {
+            if c.is_alphanumeric() {
+                token.push(c);
+            } else {
+                flush(&mut token, &mut text);
+                text.push(if c == '-' { '\u{2013}' } else { c });
+            }
+        }
+        flush(&mut token, &mut text);
+        text
+    }
+
+    fn format_number(&self, n: u64, form: &str) -> String {
+        match form {
+            "ordinal" => format!("{n}{}", self.ordinal_suffix(n)),
+            "long-ordinal" if (1..=10).contains(&n) => self
+                .proc
+                .term(&format!("long-ordinal-{n:02}"), "long", false)
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("{n}{}", self.ordinal_suffix(n))),
+            "long-ordinal" => format!("{n}{}", self.ordinal_suffix(n)),
+            "roman" => roman(n),
+            _ => n.to_string(),
+        }
+    }
+
+    /// The ordinal suffix of `n`: a term for its last two digits, then for
+    /// its last digit, then the generic one.
+    fn ordinal_suffix(&self, n: u64) -> &'a str {
+        let term = |name: String| self.proc.term(&name, "long", false);
+        (n % 100 >= 10)
+            .then(|| term(format!("ordinal-{:02}", n % 100)))
+            .flatten()
+            .or_else(|| term(format!("ordinal-{:02}", n % 10)))
+            .or_else(|| term("ordinal".to_string()))
+            .unwrap_or_default()
+    }
+
+    fn render_label(&mut self, el: &'a El) -> Vec<Out> {
+        let Some(variable) = el.attr("variable") else {
+            return Vec::new();
+        };
+        let (term, value) = if variable == "locator" {
+            let Some(cite) = self.cite else {
+                return Vec::new();
+            };
+            let Some(locator) = &cite.locator else {
+                return Vec::new();
+            };
+            (cite.label.as_deref().unwrap_or("page"), locator.clone())
+        } else {
+            let Some(value) = self.value(variable, "long") else {
+                return Vec::new();
+            };
+            let term = match variable {
+                "number-of-pages" => "page",
+                "chapter-number" => "chapter",
+                "collection-number" | "number" => "issue",
+                other => other,
+            };
+            (term, value)
+        };
+        let plural = match el.attr("plural") {
+            Some("always") => true,
+            Some("never") => false,
+            _ if variable == "number-of-pages" => value.trim().parse::<u64>().is_ok_and(|n| n > 1),
+            _ => is_plural(&value),
+        };
+        let form = el.attr("form").unwrap_or("long");
+        match self.proc.term(term, form, plural) {
+            Some(text) if !text.is_empty() => self.decorate(el, vec![Out::Text(text.to_string())]),
+            _ => Vec::new(),
+        }
+    }
+
+    fn render_date(&mut self, el: &'a El) -> Vec<Out> {
+        let Some(variable) = el.attr("variable") else {
+            return Vec::new();
+        };
+        self.called += 1;
+        if self.suppressed.contains(variable) {
+            return Vec::new();
+        }
+        let Some(date) = self.item.entry.date(&bib_key(variable)) else {
+            return Vec::new();
+        };
+        let out = match (date.start, &date.literal) {
+            (Some(start), _) => self.date_parts(el, variable, start, date.end),
+            (None, Some(literal)) => parse_markup(literal),
+            (None, None) => Vec::new(),
+        };
+        if out.is_empty() {
+            return out;
+        }
+        self.rendered += 1;
+        self.used.push(variable.to_string());
+        self.decorate(el, out)
+    }
+
+    fn date_parts(
+        &mut self,
+        el: &'a El,
+        variable: &str,
+        start: DateParts,
+        end: Option<DateParts>,
+    ) -> Vec<Out> {
+        let (parts, delimiter): (Vec<El>, &str) = match el.attr("form") {
+            Some(form) => {
+                let Some(format) = self.proc.date_format(form) else {
+                    return Vec::new();
+                };
+                let wanted = match el.attr("date-parts") {
+                    Some("year") => 1,
+                    Some("year-month") => 2,
+                    _ => 3,
+                };
+                let parts = format
+                    .children
+                    .iter()
+                    .filter(|p| match p.attr("name") {
+                        Some("year") => true,
+                        Some("month") => wanted >= 2,
+                        Some("day") => wanted >= 3,
+                        _ => false,
+                    })
+                    .map(|p| {
+                        // The style may restyle the locale's parts.
+                        let mut part = p.clone();
+                        let own = el
+                            .children
+                            .iter()
+                            .find(|o| o.name == "date-part" && o.attr("name") == p.attr("name"));
+                        for (key, value) in own.map(|o| o.attrs.as_slice()).unwrap_or_default() {
+                            part.set_attr(key, value);
+                        }
+                        part
+                    })
+                    .collect();
+                (parts, format.attr("delimiter").unwrap_or_default())
+            }
+            None => (
+                el.children
+                    .iter()
+                    .filter(|p| p.name == "date-part")
+                    .cloned()
+                    .collect(),
+                el.attr("delimiter").unwrap_or_default(),
+            ),
+        };
+
+        let Some(end) = end.filter(|end| *end != start) else {
+            return self.render_date_parts(&parts, start, variable, delimiter);
+        };
+        let differing = if start.year != end.year {
+            "year"
+        } else if start.month != end.month {
+            "month"
+        } else {
+            "day"
+        };
+        let range_delimiter = parts
+            .iter()
+            .find(|p| p.attr("name") == Some(differing))
+            .and_then(|p| p.attr("range-delimiter"))
+            .unwrap_or("\u{2013}")
+            .to_string();
+        if differing == "year" {
+            let from = self.render_date_parts(&parts, start, variable, delimiter);
+            let to = self.render_date_parts(&parts, end, variable, delimiter);
+            return join(vec![from, to], &range_delimiter);
+        }
+
+        // Parts that differ are ranged, the others are given once:
+        // "May 3–June 5, 2020".
+        let ranged = |p: &El| match p.attr("name") {
+            Some("day") => true,
+            Some("month") => differing == "month",
+            _ => false,
+        };
+        let (Some(first), Some(last)) = (
+            parts.iter().position(ranged),
+            parts.iter().rposition(ranged),
+        ) else {
+            return self.render_date_parts(&parts, start, variable, delimiter);
+        };
+        let mut from_parts = parts[first..=last].to_vec();
+        from_parts
+            .last_mut()
+            .expect("non-empty")
+            .set_attr("suffix", "");
+        let mut to_parts = parts[first..=last].to_vec();
+        to_parts[0].set_attr("prefix", "");
+        let before = self.render_date_parts(&parts[..first], start, variable, delimiter);
+        let from = self.render_date_parts(&from_parts, start, variable, delimiter);
+        let to = self.render_date_parts(&to_parts, end, variable, delimiter);
+        let after = self.render_date_parts(&parts[last + 1..], start, variable, delimiter);
+        join(
+            vec![before, join(vec![from, to], &range_delimiter), after],
+            delimiter,
+        )
+    }
+
+    fn render_date_parts(
+        &mut self,
+        parts: &[El],
+        date: DateParts,
+        variable: &str,
+        delimiter: &str,
+    ) -> Vec<Out> {
+        let rendered = parts
+            .iter()
+            .map(|part| self.date_part(part, date, variable))
+            .collect();
+        join(rendered, delimiter)
+    }
+
+    fn date_part(&mut self, part: &El, date: DateParts, variable: &str) -> Vec<Out> {
+        let form = part.attr("form");
+        let text = match part.attr("name") {
+            Some("year") => {
+                let mut text = match form {
+                    Some("short") => format!("{:02}", date.year.rem_euclid(100)),
+                    _ => date.year.unsigned_abs().to_string(),
+                };
+                if date.year < 0 {
+                    text.push_str(self.proc.term("bc", "long", false).unwrap_or("BC"));
+                }
+                // Without an explicit year-suffix variable, the suffix
+                // follows the first year of the issued date.
+                if variable == "issued"
+                    && !self.year_suffix_done
+                    && !self.proc.style.explicit_year_suffix
+                    && let Some(suffix) = &self.item.year_suffix
+                {
+                    text.push_str(suffix);
+                    self.year_suffix_done = true;
+                }
+                text
+            }
+            Some("month") => {
+                let Some(month) = date.month else {
+                    return Vec::new();
+                };
+                match form {
+                    Some("numeric") => month.to_string(),
+                    Some("numeric-leading-zeros") => format!("{month:02}"),
+                    Some("short") => self
+                        .proc
+                        .term(&format!("month-{month:02}"), "short", false)
+                        .map_or_else(|| month.to_string(), str::to_string),
+                    _ => self
+                        .proc
+                        .term(&format!("month-{month:02}"), "long", false)
+                        .map_or_else(|| month.to_string(), str::to_string),
+                }
+            }
+            Some("day") => {
+                let Some(day) = date.day else {
+                    return Vec::new();
+                };
+                let limit = self
+                    .proc
+                    .locales
+                    .iter()
+                    .find_map(|l| l.limit_day_ordinals)
+                    .unwrap_or(false);
+                match form {
+                    Some("numeric-leading-zeros") => format!("{day:02}"),
+                    Some("ordinal") if !limit || day == 1 => {
+                        format!("{day}{}", self.ordinal_suffix(day.into()))
+                    }
+                    _ => day.to_string(),
+                }
+            }
+            _ => return Vec::new(),
+        };
+        self.decorate(part, vec![Out::Text(text)])
+    }
+
+    fn render_names(&mut self, el: &'a El) -> Vec<Out> {
+        // The first `<names>` reached gives the author, even if it is
+        // empty or rendered by a substitute.
+        let is_author = self.names_depth == 0 && !self.author_done;
+        self.author_done |= is_author;
+        self.names_depth += 1;
+        let out = self.names(el);
+        self.names_depth -= 1;
+        if !is_author || out.is_empty() {
+            return out;
+        }
+        self.author = Some(out.clone());
+        match &self.author_mode {
+            AuthorMode::Normal => out,
+            AuthorMode::Suppress => Vec::new(),
+            AuthorMode::Substitute(text) => vec![Out::Text(text.clone())],
+        }
+    }
+
+    fn names(&mut self, el: &'a El) -> Vec<Out> {
+        // A `<names>` in a substitute without its own `<name>`, `<et-al>`
+        // and `<label>` uses those of the `<names>` it substitutes for.
+        let inherited = |name: &str| el.child(name).or_else(|| self.names_parent?.child(name));
+        let name_el = inherited("name");
+        let et_al_el = inherited("et-al");
+        let label_el = inherited("label");
+        let label_first = match (label_el, name_el) {
+            (Some(label), Some(name)) => {
+                let index = |target: &El| {
+                    let owner = if el.child(&target.name).is_some() {
+                        el
+                    } else {
+                        self.names_parent.unwrap_or(el)
+                    };
+                    owner.children.iter().position(|c| std::ptr::eq(c, target))
+                };
+                index(label) < index(name)
+            }
+            _ => false,
+        };
+        let count_only = self.name_option(name_el, "form") == Some("count");
+
+        let mut parts = Vec::new();
+        let mut count = 0;
+        for variable in el.attr("variable").unwrap_or_default().split_whitespace() {
+            self.called += 1;
+            if self.suppressed.contains(variable) {
+                continue;
+            }
+            let names = self.item.entry.names(&bib_key(variable));
+            if names.is_empty() {
+                continue;
+            }
+            self.rendered += 1;
+            self.used.push(variable.to_string());
+            if count_only {
+                count += self.shown_names(name_el, names.len()).0;
+                continue;
+            }
+            let list = self.name_list(&names, name_el, et_al_el);
+            let label = label_el
+                .map(|label| self.names_label(label, variable, names.len()))
+                .unwrap_or_default();
+            parts.push(if label_first {
+                join(vec![label, list], "")
+            } else {
+                join(vec![list, label], "")
+            });
+        }
+        if count_only && count > 0 {
+            return self.decorate(el, vec![Out::Text(count.to_string())]);
+        }
+        if !parts.is_empty() {
+            let delimiter = el
+                .attr("delimiter")
+                .or_else(|| self.inherited_option("names-delimiter"))
+                .unwrap_or_default();
+            let out = join(parts, delimiter);
+            return self.decorate(el, out);
+        }
+
+        // Nothing to render: try the substitutes in order, suppressing the
+        // variables of the one used in the rest of the output.
+        let Some(substitute) = el.child("substitute") else {
+            return Vec::new();
+        };
+        let saved = self.names_parent.replace(el);
+        let mut out = Vec::new();
+        for child in &substitute.children {
+            let (called, rendered, used) = (self.called, self.rendered, self.used.len());
+            out = self.render(child);
+            if !out.is_empty() {
+                let used: Vec<String> = self.used[used..].to_vec();
+                self.suppressed.extend(used);
+                break;
+            }
+            self.called = called;
+            self.rendered = rendered;
+        }
+        self.names_parent = saved;
+        self.decorate(el, out)
+    }
+
+    /// A name option from `<name>`, or inherited from the layout or style.
+    fn name_option(&self, name_el: Option<&'a El>, key: &str) -> Option<&'a str> {
+        if let Some(value) = name_el.and_then(|n| n.attr(key)) {
+            return Some(value);
+        }
+        let inherited = match key {
+            "delimiter" => "name-delimiter",
+            "form" => "name-form",
+            other => other,
+        };
+        self.inherited_option(inherited)
+    }
+
+    fn inherited_option(&self, key: &str) -> Option<&'a str> {
+        self.layout
+            .attr(key)
+            .or_else(|| self.proc.style.root.attr(key))
+    }
+
+    /// How many of `total` names are shown, and whether "et al." follows.
+    fn shown_names(&self, name_el: Option<&'a El>, total: usize) -> (usize, bool) {
+        let number = |key: &str| {
+            self.name_option(name_el, key)
+                .and_then(|v| v.parse::<usize>().ok())
+        };
+        let subsequent = self.cite.is_some_and(|c| c.position != Position::First);
+        let (min, first) = match self.et_al {
+            Some((min, first)) => (Some(min), Some(first)),
+            None if subsequent && number("et-al-subsequent-min").is_some() => (
+                number("et-al-subsequent-min"),
+                number("et-al-subsequent-use-first"),
+            ),
+            None => (number("et-al-min"), number("et-al-use-first")),
+        };
+        match (min, first) {
+            (Some(min), Some(first)) if total >= min && first < total => (first, true),
+            _ => (total, false),
+        }
+    }
+
+    fn name_list(
+        &self,
+        names: &[Name],
+        name_el: Option<&'a El>,
+        et_al_el: Option<&'a El>,
+    ) -> Vec<Out> {
+        let (shown, truncated) = self.shown_names(name_el, names.len());
+        let delimiter = self.name_option(name_el, "delimiter").unwrap_or(", ");
+        let and = match self.name_option(name_el, "and") {
+            Some("text") => self.proc.term("and", "long", false),
+            Some("symbol") => Some("&"),
+            _ => None,
+        };
+        let formatted: Vec<(Vec<Out>, bool)> = names[..shown]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| self.format_name(name, i, name_el))
+            .collect();
+
+        let mut out = Vec::new();
+        for (i, (name, _)) in formatted.iter().enumerate() {
+            if i > 0 {
+                let last = i == formatted.len() - 1 && !truncated;
+                match and.filter(|_| last) {
+                    Some(and) => {
+                        let precedes = match self.name_option(name_el, "delimiter-precedes-last") {
+                            Some("always") => true,
+                            Some("never") => false,
+                            Some("after-inverted-name") => formatted[i - 1].1,
+                            _ => formatted.len() >= 3,
+                        };
+                        let connector = if precedes {
+                            format!("{delimiter}{and} ")
+                        } else {
+                            format!(" {and} ")
+                        };
+                        push_text(&mut out, &connector);
+                    }
+                    None => push_text(&mut out, delimiter),
+                }
+            }
+            extend(&mut out, name.clone());
+        }
+        if !truncated {
+            return out;
+        }
+
+        if self.name_option(name_el, "et-al-use-last") == Some("true") && names.len() > shown + 1 {
+            push_text(&mut out, &format!("{delimiter}\u{2026} "));
+            let (last, _) = self.format_name(&names[names.len() - 1], names.len() - 1, name_el);
+            extend(&mut out, last);
+            return out;
+        }
+        let term = et_al_el.and_then(|e| e.attr("term")).unwrap_or("et-al");
+        let Some(et_al) = self
+            .proc
+            .term(term, "long", false)
+            .filter(|t| !t.is_empty())
+        else {
+            return out;
+        };
+        let precedes = match self.name_option(name_el, "delimiter-precedes-et-al") {
+            Some("always") => true,
+            Some("never") => false,
+            Some("after-inverted-name") => formatted.last().is_some_and(|(_, inverted)| *inverted),
+            _ => shown > 1,
+        };
+        push_text(&mut out, if precedes { delimiter } else { " " });
+        let et_al = vec![Out::Text(et_al.to_string())];
+        let et_al = match et_al_el {
+            Some(el) => self.decorate(el, et_al),
+            None => et_al,
+        };
+        extend(&mut out, et_al);
+        out
+    }
+
+    /// A name, and whether it was written family name first.
+    fn format_name(&self, name: &Name, index: usize, name_el: Option<&'a El>) -> (Vec<Out>, bool) {
+        let part = |part: &str, text: String| -> Vec<Out> {
+            if text.is_empty() {
+                return Vec::new();
+            }
+            let out = parse_markup(&text);
+            match name_el.and_then(|n| {
+                n.children
+                    .iter()
+                    .find(|c| c.name == "name-part" && c.attr("name") == Some(part))
+            }) {
+                Some(el) => self.decorate(el, out),
+                None => out,
+            }
+        };
+        if let Some(literal) = &name.literal {
+            return (part("family", literal.clone()), false);
+        }
+        let words = |parts: &[&Option<String>]| -> String {
+            parts
+                .iter()
+                .filter_map(|p| p.as_deref())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let particle = &name.non_dropping_particle;
+        let family = &name.family;
+        if self.name_option(name_el, "form") == Some("short") && !self.sorting {
+            return (part("family", words(&[particle, family])), false);
+        }
+
+        let given = name
+            .given
+            .as_deref()
+            .map(|given| self.initialize(given, name_el));
+        let order = self.name_option(name_el, "name-as-sort-order");
+        let inverted =
+            self.sorting || order == Some("all") || (order == Some("first") && index == 0);
+        let separator = self.name_option(name_el, "sort-separator").unwrap_or(", ");
+        let suffix = name
+            .suffix
+            .clone()
+            .map(|s| vec![Out::Text(s)])
+            .unwrap_or_default();
+        if !inverted {
+            let given = part("given", words(&[&given, &name.dropping_particle]));
+            let family = part("family", words(&[particle, family]));
+            let out = join(vec![given, family, suffix], " ");
+            return (out, false);
+        }
+        let demote = self
+            .proc
+            .style
+            .root
+            .attr("demote-non-dropping-particle")
+            .unwrap_or("display-and-sort");
+        let (given, family) = if demote == "never" || (demote == "sort-only" && !self.sorting) {
+            (
+                part("given", words(&[&given, &name.dropping_particle])),
+                part("family", words(&[particle, family])),
+            )
+        } else {
+            (
+                part("given", words(&[&given, &name.dropping_particle, particle])),
+                part("family", words(&[family])),
+            )
+        };
+        (join(vec![family, given, suffix], separator), true)
+    }
+
+    /// Given names with `initialize-with`: "John Ronald" as "J. R.".
+    fn initialize(&self, given: &str, name_el: Option<&'a El>) -> String {
+        let Some(with) = self.name_option(name_el, "initialize-with") else {
+            return given.to_string();
+        };
+        let all = self.name_option(name_el, "initialize") != Some("false");
+        let hyphen = self.proc.style.root.attr("initialize-with-hyphen") != Some("false");
+        let mut text = String::new();
+        for word in given.split_whitespace() {
+            for (i, part) in word.split('-').enumerate() {
+                let pieces: Vec<&str> = part.split('.').filter(|p| !p.is_empty()).collect();
+                for (j, piece) in pieces.iter().enumerate() {
+                    if i > 0 && j == 0 && hyphen {
+                        text.truncate(text.trim_end().len());
+                        text.push('-');
+                    }
+                    let initial = piece.chars().count() == 1 || part.contains('.');
+                    if all || initial {
+                        let first = piece.chars().next().expect("pieces are not empty");
+                        text.extend(first.to_uppercase());
+                        text.push_str(with);
+                    } else {
+                        text.push_str(piece);
+                        text.push(' ');
+                    }
+                }
+            }
+        }
+        text.trim_end().to_string()
+    }
+
+    fn names_label(&self, label: &El, variable: &str, count: usize) -> Vec<Out> {
+        let plural = match label.attr("plural") {
+            Some("always") => true,
+            Some("never") => false,
+            _ => count > 1,
+        };
+        let form = label.attr("form").unwrap_or("long");
+        match self.proc.term(variable, form, plural) {
+            Some(text) if !text.is_empty() => {
+                self.decorate(label, vec![Out::Text(text.to_string())])
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Render the children, or nothing if they call variables and all of
+    /// those are empty.
+    fn render_group(&mut self, el: &'a El) -> Vec<Out> {
+        let (called, rendered) = (self.called, self.rendered);
+        let parts: Vec<Vec<Out>> = el.children.iter().map(|child| self.render(child)).collect();
+        if self.called > called && self.rendered == rendered {
+            return Vec::new();
+        }
+        let out = join(parts, el.attr("delimiter").unwrap_or_default());
+        self.decorate(el, out)
+    }
+
+    fn render_choose(&mut self, el: &'a El) -> Vec<Out> {
+        for branch in &el.children {
+            let taken = match branch.name.as_str() {
+                "if" | "else-if" => self.test(branch),
+                "else" => true,
+                _ => false,
+            };
+            if taken {
+                return self.render_children(&branch.children);
+            }
+        }
+        Vec::new()
+    }
+
+    fn test(&self, branch: &El) -> bool {
+        let mut results = Vec::new();
+        for (attr, values) in &branch.attrs {
+            for value in values.split_whitespace() {
+                let result = match attr.as_str() {
+                    "type" => self.item.entry.entry_type() == value,
+                    "variable" => self.has_variable(value),
+                    "is-numeric" => entry_value(self.item.entry, value)
+                        .or_else(|| {
+                            (value == "locator")
+                                .then(|| self.cite.and_then(|c| c.locator.clone()))
+                                .flatten()
+                        })
+                        .is_some_and(|v| is_numeric(&v)),
+                    "is-uncertain-date" => self
+                        .item
+                        .entry
+                        .date(&bib_key(value))
+                        .is_some_and(|d| d.circa),
+                    "locator" => self.cite.is_some_and(|c| {
+                        c.locator.is_some() && c.label.as_deref().unwrap_or("page") == value
+                    }),
+                    "position" => self.cite.is_some_and(|c| match value {
+                        "first" => c.position == Position::First,
+                        "subsequent" => c.position != Position::First,
+                        "ibid" => matches!(c.position, Position::Ibid | Position::IbidWithLocator),
+                        "ibid-with-locator" => c.position == Position::IbidWithLocator,
+                        "near-note" => c.position != Position::First && c.near_note,
+                        _ => false,
+                    }),
+                    "disambiguate" => false,
+                    _ => continue,
+                };
+                results.push(result);
+            }
+        }
+        match branch.attr("match") {
+            Some("any") => results.iter().any(|r| *r),
+            Some("none") => !results.iter().any(|r| *r),
+            _ => results.iter().all(|r| *r),
+        }
+    }
+
+    fn has_variable(&self, variable: &str) -> bool {
+        if self.suppressed.contains(variable) {
+            return false;
+        }
+        let entry = self.item.entry;
+        match variable {
+            "locator" => self.cite.is_some_and(|c| c.locator.is_some()),
+            "citation-number" => true,
+            "year-suffix" => self.item.year_suffix.is_some(),
+            "first-reference-note-number" => self.cite.is_some_and(|c| c.first_note.is_some()),
+            v if rescribe_bib::is_name_variable(v) => !entry.names(&bib_key(v)).is_empty(),
+            v if rescribe_bib::is_date_variable(v) => entry.date(&bib_key(v)).is_some(),
+            v => entry_value(entry, v).is_some(),
+        }
+    }
+
+    /// The value of a sort key: names inverted, dates as numbers.
+    fn sort_value(&mut self, key: &'a El) -> Option<SortValue> {
+        let entry = self.item.entry;
+        let text = if let Some(variable) = key.attr("variable") {
+            if rescribe_bib::is_name_variable(variable) {
+                let names = entry.names(&bib_key(variable));
+                let name_el = None;
+                plain(&self.name_list(&names, name_el, None))
+            } else if rescribe_bib::is_date_variable(variable) {
+                let start = entry.date(&bib_key(variable))?.start?;
+                let number = i64::from(start.year) * 10_000
+                    + i64::from(start.month.unwrap_or(0)) * 100
+                    + i64::from(start.day.unwrap_or(0));
+                return Some(SortValue::Number(number));
+            } else {
+                let value = match variable {
+                    "citation-number" => self.item.number.to_string(),
+                    _ => self.value(variable, "long")?,
+                };
+                if let Ok(number) = value.trim().parse() {
+                    return Some(SortValue::Number(number));
+                }
+                value
+            }
+        } else if let Some(name) = key.attr("macro") {
+            plain(&self.render_macro(name))
+        } else {
+            return None;
+        };
+        let text: String = text
+            .trim_start_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase();
+        (!text.is_empty()).then_some(SortValue::Text(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rescribe_bib::{Date, Name, node as bib_node, prop as bib_prop};
+
+    const STYLE: &str = r#"<style xmlns="http://purl.org/net/xbiblio/csl" class="in-text" version="1.0" page-range-format="minimal">
+      <macro name="author">
+        <names variable="author">
+          <name initialize-with=". " and="text" delimiter=", " et-al-min="4" et-al-use-first="2" name-as-sort-order="first"/>
+          <label form="short" prefix=" (" suffix=")"/>
+          <substitute><names variable="editor"/><text variable="title"/></substitute>
+        </names>
+      </macro>
+      <citation><layout>
+        <group delimiter=", ">
+          <text macro="author"/>
+          <text variable="title" text-case="title" quotes="true"/>
+          <text variable="editor"/>
+          <date variable="issued" form="text"/>
+          <group delimiter=" "><text term="page" form="short"/><text variable="page"/></group>
+          <group delimiter=" "><text value="vol."/><text variable="volume"/></group>
+          <number variable="edition" form="ordinal"/>
+        </group>
+      </layout></citation>
+    </style>"#;
+
+    fn render(entry: Node) -> String {
+        let style = Style::parse(STYLE).unwrap();
+        let locale = Locale::bundled("en-US").unwrap();
+        let proc = Processor::new(&style, &locale, &locale);
+        let item = Item {
+            entry: Entry::from_node(&entry).unwrap(),
+            number: 1,
+            year_suffix: None,
+        };
+        let rendered = proc.render_cite(&item, &CiteContext::default(), AuthorMode::Normal);
+        plain(&rendered.out)
+    }
+
+    fn entry() -> Node {
+        Node::new(bib_node::ENTRY)
+            .prop(bib_prop::ID, "a")
+            .prop(bib_prop::TYPE, "book")
+    }
+
+    fn names(names: &[&str]) -> PropValue {
+        Name::list(names.iter().map(|n| Name::parse(n)))
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(
+            Style::parse("<locale/>")
+                .unwrap_err()
+                .message
+                .contains("<style>")
+        );
+        assert!(Style::parse("<style><citation/></style>").is_err());
+        let unknown = "<style><citation><layout><text macro=\"x\"/></layout></citation></style>";
+        assert_eq!(
+            Style::parse(unknown).unwrap_err().message,
+            "unknown macro 'x'"
+        );
+        assert!(Style::parse("<style><citation>").is_err());
+        for id in Style::bundled_ids() {
+            assert!(Style::bundled(id).unwrap().title().is_some(), "{id}");
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        let one = entry().prop(bib_prop::AUTHOR, names(&["Ronald Reuel Tolkien"]));
+        assert_eq!(render(one), "Tolkien, R. R.");
+
+        let two = entry().prop(
+            bib_prop::AUTHOR,
+            names(&["Doe, Jean-Paul", "Ludwig van Beethoven"]),
+        );
+        assert_eq!(render(two), "Doe, J.-P. and L. van Beethoven");
+
+        let many = entry().prop(
+            bib_prop::AUTHOR,
+            names(&["Anne A", "Bob B", "Cat C", "Dan D"]),
+        );
+        assert_eq!(render(many), "A, A., B. B, et al.");
+
+        // An editor stands in for the author and is not repeated.
+        let edited = entry().prop(bib_prop::EDITOR, names(&["Jane Doe", "John Roe"]));
+        assert_eq!(render(edited), "Doe, J. and J. Roe (eds.)");
+
+        // As does the title, without quotes.
+        let anonymous = entry().prop(bib_prop::TITLE, "the lord of the rings");
+        assert_eq!(render(anonymous), "the lord of the rings");
+    }
+
+    #[test]
+    fn test_text_dates_and_numbers() {
+        let node = entry()
+            .prop(bib_prop::AUTHOR, names(&["Anne A"]))
+            .prop(bib_prop::TITLE, "the <i>lord</i> of the rings: a tale")
+            .prop(
+                bib_prop::ISSUED,
+                Date::parse("2020-05-03/2020-06-05").unwrap(),
+            )
+            .prop(bib_prop::PAGE, "321-328")
+            .prop(bib_prop::VOLUME, "")
+            .prop(bib_prop::EDITION, "2");
+        assert_eq!(
+            render(node),
+            "A, A., The Lord of the Rings: A Tale, May 3–June 5, 2020, p. 321–8, 2nd"
+        );
+    }
+
+    #[test]
+    fn test_helpers() {
+        assert_eq!(range_end("321", "28", "expanded"), "328");
+        assert_eq!(range_end("1496", "1504", "chicago"), "1504");
+        assert_eq!(range_end("101", "108", "chicago"), "8");
+        assert_eq!(range_end("321", "328", "chicago"), "28");
+        assert_eq!(range_end("42", "45", "minimal-two"), "45");
+        assert!(is_numeric("12") && is_numeric("3-5, 7") && is_numeric("2nd"));
+        assert!(!is_numeric("second") && !is_numeric(""));
+        assert_eq!(roman(1984), "mcmlxxxiv");
+
+        let out = parse_markup("a <i>b <b>c</b></i> <span class=\"nocase\">d</span>");
+        assert_eq!(plain(&out), "a b c d");
+        assert!(matches!(&out[1], Out::Styled(Fmt::Italic, children) if children.len() == 2));
+
+        let mut out = vec![Out::Styled(Fmt::Quoted, vec![Out::Text("Title".into())])];
+        push_text(&mut out, ", next");
+        punctuation_in_quote(&mut out);
+        assert_eq!(plain(&out), "Title, next");
+        assert!(matches!(&out[0], Out::Styled(_, c) if plain(c) == "Title,"));
+    }
+}
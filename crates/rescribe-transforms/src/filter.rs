@@ -0,0 +1,274 @@
+//! Pandoc JSON filters.
+//!
+//! A [`PandocFilter`] runs an external program the way `pandoc --filter`
+//! does: the document is written to the program's stdin as Pandoc JSON
+//! (API version 1.23), and the program's stdout is parsed back as the
+//! transformed document. Existing filters written with `pandocfilters`,
+//! `panflute` or similar libraries work unchanged.
+//!
+//! ```no_run
+//! use rescribe_core::Transformer;
+//! use rescribe_std::builder::doc;
+//! use rescribe_transforms::PandocFilter;
+//!
+//! let document = doc(|d| d.para(|i| i.text("Hello")));
+//! let filter = PandocFilter::new("./behead.py").target_format("html");
+//! let result = filter.transform(document).unwrap();
+//! for warning in &result.warnings {
+//!     eprintln!("{}", warning.message);
+//! }
+//! ```
+
+use rescribe_core::{
+    ConversionResult, Document, EmitOptions, FidelityWarning, ParseOptions, Severity,
+    TransformError, Transformer, WarningKind,
+};
+use rescribe_std::Node;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Run an external Pandoc JSON filter over a document.
+#[derive(Debug, Clone)]
+pub struct PandocFilter {
+    program: PathBuf,
+    args: Vec<String>,
+    target_format: Option<String>,
+}
+
+impl PandocFilter {
+    /// Create a filter running `program`.
+    ///
+    /// Scripts with a known extension (`.py`, `.rb`, `.pl`, `.js`, `.php`,
+    /// `.hs`, `.r`) are run through their interpreter, as Pandoc does.
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            target_format: None,
+        }
+    }
+
+    /// Pass an extra argument to the program.
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Set the output format name, passed to the program as its first
+    /// argument (filters use it to emit format-specific raw content).
+    pub fn target_format(mut self, format: impl Into<String>) -> Self {
+        self.target_format = Some(format.into());
+        self
+    }
+
+    /// Pipe `input` through the program and collect its stdout.
+    fn run(&self, input: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut command = match interpreter(&self.program) {
+            Some(interpreter) => {
+                let mut command = Command::new(interpreter);
+                command.arg(&self.program);
+                command
+            }
+            None => Command::new(&self.program),
+        };
+        command
+            .args(&self.target_format)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit());
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| TransformError::Failed(format!("cannot run {}: {e}", self.display())))?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+
+        // Feed stdin from another thread so a filter that writes before it
+        // has read all its input cannot deadlock against us.
+        let output = std::thread::scope(|scope| {
+            let writer = scope.spawn(move || stdin.write_all(input));
+            let output = child.wait_with_output();
+            // A filter may exit without reading its input; that is its call.
+            let _ = writer.join();
+            output
+        })
+        .map_err(|e| TransformError::Failed(format!("{}: {e}", self.display())))?;
+
+        if !output.status.success() {
+            return Err(TransformError::Failed(format!(
+                "{} failed: {}",
+                self.display(),
+                output.status
+            )));
+        }
+        Ok(output.stdout)
+    }
+
+    fn display(&self) -> String {
+        format!("filter '{}'", self.program.display())
+    }
+}
+
+impl Transformer for PandocFilter {
+    fn name(&self) -> &str {
+        "pandoc-filter"
+    }
+
+    /// Run the filter.
+    ///
+    /// Besides the warnings of the Pandoc JSON writer and reader, the result
+    /// reports node kinds and properties of `doc` that Pandoc JSON cannot
+    /// carry, and that therefore never reached the filter.
+    fn transform(&self, doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        let emitted = rescribe_write_pandoc_json::emit_with_options(&doc, &EmitOptions::default())
+            .map_err(|e| TransformError::Failed(format!("cannot serialize document: {e}")))?;
+        let mut warnings = emitted.warnings;
+        warnings.extend(losses(&doc, &emitted.value)?);
+
+        let output = self.run(&emitted.value)?;
+        let output = std::str::from_utf8(&output).map_err(|e| {
+            TransformError::Failed(format!("{}: output is not UTF-8: {e}", self.display()))
+        })?;
+        let parsed =
+            rescribe_read_pandoc_json::parse_with_options(output, &ParseOptions::default())
+                .map_err(|e| TransformError::Failed(format!("{}: {e}", self.display())))?;
+        warnings.extend(parsed.warnings);
+
+        Ok(ConversionResult::with_warnings(parsed.value, warnings))
+    }
+}
+
+/// Compare `doc` with its Pandoc JSON serialization read back.
+fn losses(doc: &Document, json: &[u8]) -> Result<Vec<FidelityWarning>, TransformError> {
+    let json = String::from_utf8_lossy(json);
+    let roundtrip = rescribe_read_pandoc_json::parse(&json)
+        .map_err(|e| TransformError::Failed(format!("cannot serialize document: {e}")))?
+        .value;
+    let before = Inventory::of(&doc.content);
+    let after = Inventory::of(&roundtrip.content);
+
+    let kinds = before.kinds.difference(&after.kinds).map(|kind| {
+        FidelityWarning::new(
+            Severity::Major,
+            WarningKind::UnsupportedNode(kind.clone()),
+            format!("{kind} nodes cannot be passed to a Pandoc filter and were lost"),
+        )
+    });
+    let props = before
+        .props
+        .difference(&after.props)
+        .filter(|(kind, _)| after.kinds.contains(kind))
+        .map(|(kind, key)| {
+            FidelityWarning::new(
+                Severity::Minor,
+                WarningKind::UnsupportedProperty(key.clone()),
+                format!(
+                    "property '{key}' of {kind} cannot be passed to a Pandoc filter and was lost"
+                ),
+            )
+        });
+    Ok(kinds.chain(props).collect())
+}
+
+/// Interpreter for scripts, by extension.
+fn interpreter(program: &Path) -> Option<&'static str> {
+    let ext = program.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match ext.as_str() {
+        "py" => "python3",
+        "rb" => "ruby",
+        "pl" => "perl",
+        "js" => "node",
+        "php" => "php",
+        "hs" => "runhaskell",
+        "r" => "Rscript",
+        _ => return None,
+    })
+}
+
+/// Node kinds and `(kind, property)` pairs present in a tree.
+#[derive(Default)]
+struct Inventory {
+    kinds: BTreeSet<String>,
+    props: BTreeSet<(String, String)>,
+}
+
+impl Inventory {
+    fn of(root: &Node) -> Self {
+        let mut inventory = Self::default();
+        crate::walk(root, &mut |node| {
+            let kind = node.kind.as_str();
+            inventory.kinds.insert(kind.to_string());
+            for (key, _) in node.props.iter() {
+                inventory.props.insert((kind.to_string(), key.to_string()));
+            }
+        });
+        inventory
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use rescribe_std::builder::doc;
+    use rescribe_std::{node, prop};
+
+    #[test]
+    fn test_identity_filter() {
+        let document = doc(|d| d.heading(1, |i| i.text("Title")).para(|i| i.text("Body")));
+        let result = PandocFilter::new("cat")
+            .transform(document.clone())
+            .unwrap();
+        assert!(result.warnings.is_empty());
+        let edits = rescribe_core::diff(&document, &result.value, &Default::default());
+        assert!(edits.is_empty(), "{edits:?}");
+    }
+
+    #[test]
+    fn test_filter_output_is_parsed() {
+        let document = doc(|d| d.para(|i| i.text("Hello")));
+        let filtered = PandocFilter::new("sed")
+            .arg("s/Hello/Goodbye/")
+            .transform(document)
+            .unwrap()
+            .value;
+        let text = &filtered.content.children[0].children[0];
+        assert_eq!(text.props.get_str(prop::CONTENT), Some("Goodbye"));
+    }
+
+    #[test]
+    fn test_losses_are_reported() {
+        let mut document = doc(|d| d.para(|i| i.text("Hello")));
+        document.content.children[0].props.set("x-note", "kept?");
+        document
+            .content
+            .children
+            .push(Node::new("custom:widget").child(Node::new(node::TEXT)));
+
+        let warnings = PandocFilter::new("cat")
+            .transform(document)
+            .unwrap()
+            .warnings;
+        let messages: Vec<_> = warnings.iter().map(|w| w.message.as_str()).collect();
+        assert!(
+            messages
+                .contains(&"custom:widget nodes cannot be passed to a Pandoc filter and were lost")
+        );
+        assert!(messages.contains(
+            &"property 'x-note' of paragraph cannot be passed to a Pandoc filter and was lost"
+        ));
+    }
+
+    #[test]
+    fn test_failing_filter() {
+        let document = doc(|d| d.para(|i| i.text("Hello")));
+        let err = PandocFilter::new("false")
+            .transform(document.clone())
+            .unwrap_err();
+        assert!(err.to_string().contains("filter 'false' failed"));
+
+        let err = PandocFilter::new("/nonexistent/filter").transform(document);
+        assert!(err.unwrap_err().to_string().contains("cannot run"));
+    }
+}
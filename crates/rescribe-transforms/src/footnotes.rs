@@ -0,0 +1,438 @@
+//! Footnote normalisation.
+//!
+//! Readers place footnotes differently: markdown keeps definitions where
+//! they were written and links them to references by label, while formats
+//! such as Pandoc JSON carry the note inline, as a `footnote_def` at the
+//! point of reference. [`NormalizeFootnotes`] rewrites either form into one
+//! canonical layout chosen by [`FootnotePlacement`], numbering notes `1`,
+//! `2`, ... in reading order.
+//!
+//! ```
+//! use rescribe_core::Transformer;
+//! use rescribe_std::builder::doc;
+//! use rescribe_std::{Node, node, prop};
+//! use rescribe_transforms::{FootnotePlacement, NormalizeFootnotes};
+//!
+//! let mut document = doc(|d| d.para(|i| i.text("Claim").footnote_ref("src")));
+//! document.content.children.push(
+//!     Node::new(node::FOOTNOTE_DEF)
+//!         .prop(prop::LABEL, "src")
+//!         .child(Node::new(node::PARAGRAPH)),
+//! );
+//!
+//! let placement = FootnotePlacement::for_format("latex");
+//! let result = NormalizeFootnotes::new(placement).transform(document).unwrap();
+//! let para = &result.value.content.children[0];
+//! assert_eq!(para.children[1].kind.as_str(), node::FOOTNOTE_DEF);
+//! assert_eq!(para.children[1].props.get_str(prop::LABEL), Some("1"));
+//! ```
+
+use rescribe_core::{
+    ConversionResult, Document, FidelityWarning, Severity, Span, TransformError, Transformer,
+    WarningKind,
+};
+use rescribe_std::{Node, NodeExt, node, prop};
+use std::collections::HashMap;
+
+/// Where footnote definitions go.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FootnotePlacement {
+    /// In place of the first reference, as an inline `footnote_def`.
+    Inline,
+    /// After the content of the section holding the first reference.
+    EndOfSection,
+    /// After the content of the document.
+    #[default]
+    EndOfDocument,
+}
+
+impl FootnotePlacement {
+    /// The placement a writer for `format` expects.
+    ///
+    /// Formats with native footnotes (LaTeX, DocBook, ODT, Pandoc JSON, ...)
+    /// take notes inline; the others get them at the end of the document.
+    pub fn for_format(format: &str) -> Self {
+        match format {
+            "latex" | "beamer" | "context" | "typst" | "docx" | "odt" | "rtf" | "icml"
+            | "docbook" | "jats" | "tei" | "texinfo" | "man" | "ms" | "pandoc-json" => {
+                FootnotePlacement::Inline
+            }
+            _ => FootnotePlacement::EndOfDocument,
+        }
+    }
+}
+
+/// Canonicalise footnotes.
+///
+/// Definitions are matched to references by label and renumbered in the
+/// order they are first referenced; further references to the same note
+/// get the same number. A `footnote_def` without a label inside inline
+/// content is a note in its own right. References without a definition are
+/// left alone, and definitions nobody references are dropped; both are
+/// reported as warnings.
+///
+/// With [`FootnotePlacement::EndOfSection`], sections are top-level section
+/// `div`s or, in flat documents, runs of blocks starting at a heading of the
+/// highest level used at the top of the document.
+#[derive(Debug, Clone, Default)]
+pub struct NormalizeFootnotes {
+    placement: FootnotePlacement,
+}
+
+impl NormalizeFootnotes {
+    /// Create a normaliser placing definitions according to `placement`.
+    pub fn new(placement: FootnotePlacement) -> Self {
+        Self { placement }
+    }
+}
+
+impl Transformer for NormalizeFootnotes {
+    fn name(&self) -> &str {
+        "normalize_footnotes"
+    }
+
+    fn transform(&self, mut doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        let mut notes = Notes {
+            placement: self.placement,
+            defs: HashMap::new(),
+            order: Vec::new(),
+            numbers: HashMap::new(),
+            count: 0,
+            pending: Vec::new(),
+            warnings: Vec::new(),
+        };
+        notes.collect(&doc.content);
+
+        let by_section = self.placement == FootnotePlacement::EndOfSection;
+        let top_level = doc
+            .content
+            .children
+            .iter()
+            .filter_map(|c| c.as_heading().map(|h| h.level()))
+            .min();
+
+        let mut out = Vec::new();
+        for mut block in std::mem::take(&mut doc.content.children) {
+            if by_section {
+                if block.as_section().is_some() {
+                    out.append(&mut notes.pending);
+                    notes.rewrite_children(&mut block);
+                    block.children.append(&mut notes.pending);
+                    out.push(block);
+                    continue;
+                }
+                if block.as_heading().map(|h| h.level()) == top_level {
+                    out.append(&mut notes.pending);
+                }
+            }
+            notes.rewrite(block, true, &mut out);
+        }
+        out.append(&mut notes.pending);
+        doc.content.children = out;
+
+        for label in &notes.order {
+            if !notes.numbers.contains_key(label) {
+                let def = &notes.defs[label];
+                notes.warnings.push(warning(
+                    def,
+                    format!("footnote '{label}' is never referenced and was dropped"),
+                ));
+            }
+        }
+        Ok(ConversionResult::with_warnings(doc, notes.warnings))
+    }
+}
+
+/// Renumbering state.
+struct Notes {
+    placement: FootnotePlacement,
+    /// Labelled definitions, the first one for each label.
+    defs: HashMap<String, Node>,
+    /// Labels of `defs` in document order.
+    order: Vec<String>,
+    /// New number of each label referenced so far.
+    numbers: HashMap<String, usize>,
+    /// Notes numbered so far, labelled or not.
+    count: usize,
+    /// Definitions waiting to be placed at the end of a section or document.
+    pending: Vec<Node>,
+    warnings: Vec<FidelityWarning>,
+}
+
+impl Notes {
+    fn collect(&mut self, node: &Node) {
+        for child in &node.children {
+            if child.kind.as_str() == node::FOOTNOTE_DEF
+                && let Some(label) = label(child)
+            {
+                if self.defs.contains_key(label) {
+                    self.warnings.push(warning(
+                        child,
+                        format!("duplicate definition of footnote '{label}' was dropped"),
+                    ));
+                } else {
+                    self.defs.insert(label.to_string(), child.clone());
+                    self.order.push(label.to_string());
+                }
+            }
+            self.collect(child);
+        }
+    }
+
+    fn rewrite_children(&mut self, node: &mut Node) {
+        let in_block = holds_blocks(node);
+        for child in std::mem::take(&mut node.children) {
+            self.rewrite(child, in_block, &mut node.children);
+        }
+    }
+
+    /// Rewrite `node`, pushing what replaces it to `out`. `in_block` tells
+    /// whether `node` sits among blocks rather than inline content.
+    fn rewrite(&mut self, mut node: Node, in_block: bool, out: &mut Vec<Node>) {
+        match node.kind.as_str() {
+            node::FOOTNOTE_REF => match label(&node) {
+                Some(_) => self.reference(node, out),
+                None => out.push(node),
+            },
+            node::FOOTNOTE_DEF => match label(&node) {
+                // Placed at its first reference, or dropped if unused.
+                Some(_) if in_block => {}
+                // A note in place is its own reference.
+                Some(_) => self.reference(node, out),
+                None if !in_block => {
+                    let span = node.span;
+                    self.place(node, span, out);
+                }
+                None => {
+                    self.rewrite_children(&mut node);
+                    out.push(node);
+                }
+            },
+            _ => {
+                self.rewrite_children(&mut node);
+                out.push(node);
+            }
+        }
+    }
+
+    /// Resolve a labelled reference, or a labelled note in place.
+    fn reference(&mut self, node: Node, out: &mut Vec<Node>) {
+        let label = label(&node).unwrap_or_default().to_string();
+        if let Some(&number) = self.numbers.get(&label) {
+            out.push(footnote_ref(number, node.span));
+            return;
+        }
+        let Some(def) = self.defs.get(&label).cloned() else {
+            self.warnings.push(warning(
+                &node,
+                format!("footnote reference '{label}' has no definition"),
+            ));
+            out.push(node);
+            return;
+        };
+        self.numbers.insert(label, self.count + 1);
+        self.place(def, node.span, out);
+    }
+
+    /// Number the note `def` and place it, referenced from where `span`
+    /// points.
+    fn place(&mut self, mut def: Node, span: Option<Span>, out: &mut Vec<Node>) {
+        self.count += 1;
+        let number = self.count;
+        def.props.set(prop::LABEL, number.to_string());
+        if self.placement == FootnotePlacement::Inline {
+            self.rewrite_children(&mut def);
+            out.push(def);
+            return;
+        }
+        out.push(footnote_ref(number, span));
+        // Reserve the slot first so that notes nested in this one follow it.
+        let slot = self.pending.len();
+        self.pending.push(Node::new(node::FOOTNOTE_DEF));
+        self.rewrite_children(&mut def);
+        self.pending[slot] = def;
+    }
+}
+
+fn label(node: &Node) -> Option<&str> {
+    node.props.get_str(prop::LABEL).filter(|l| !l.is_empty())
+}
+
+fn footnote_ref(number: usize, span: Option<Span>) -> Node {
+    let mut node = Node::new(node::FOOTNOTE_REF).prop(prop::LABEL, number.to_string());
+    node.span = span;
+    node
+}
+
+/// Whether the children of `node` are blocks.
+fn holds_blocks(node: &Node) -> bool {
+    matches!(
+        node.kind.as_str(),
+        node::DOCUMENT
+            | node::DIV
+            | node::BLOCKQUOTE
+            | node::LIST
+            | node::LIST_ITEM
+            | node::DEFINITION_LIST
+            | node::DEFINITION_DESC
+            | node::FOOTNOTE_DEF
+    )
+}
+
+fn warning(node: &Node, message: String) -> FidelityWarning {
+    let label = node.props.get_str(prop::LABEL).unwrap_or_default();
+    let warning = FidelityWarning::new(
+        Severity::Minor,
+        WarningKind::InvalidStructure(format!("footnote:{label}")),
+        message,
+    );
+    match node.span {
+        Some(span) => warning.at(span),
+        None => warning,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rescribe_std::builder::doc;
+
+    fn def(label: &str, text: &str) -> Node {
+        let mut note = Node::new(node::FOOTNOTE_DEF).child(
+            Node::new(node::PARAGRAPH).child(Node::new(node::TEXT).prop(prop::CONTENT, text)),
+        );
+        if !label.is_empty() {
+            note = note.prop(prop::LABEL, label);
+        }
+        note
+    }
+
+    /// Kind and label of each node, with the text of definitions.
+    fn outline(nodes: &[Node]) -> Vec<String> {
+        nodes
+            .iter()
+            .map(|n| {
+                let label = n.props.get_str(prop::LABEL).unwrap_or("");
+                match n.kind.as_str() {
+                    node::FOOTNOTE_REF => format!("ref {label}"),
+                    node::FOOTNOTE_DEF => format!("def {label} {}", crate::stringify(n)),
+                    kind => kind.to_string(),
+                }
+            })
+            .collect()
+    }
+
+    fn labelled() -> Document {
+        let mut document = doc(|d| {
+            d.para(|i| {
+                i.text("x")
+                    .footnote_ref("a")
+                    .footnote_ref("b")
+                    .footnote_ref("a")
+                    .footnote_ref("missing")
+            })
+        });
+        let children = &mut document.content.children;
+        children.push(def("b", "Bee"));
+        children.push(def("a", "Ay"));
+        children.push(def("unused", "Never"));
+        children.push(def("a", "Again"));
+        document
+    }
+
+    #[test]
+    fn test_end_of_document() {
+        let result = NormalizeFootnotes::new(FootnotePlacement::EndOfDocument)
+            .transform(labelled())
+            .unwrap();
+        let children = &result.value.content.children;
+        assert_eq!(
+            outline(&children[0].children),
+            ["text", "ref 1", "ref 2", "ref 1", "ref missing"]
+        );
+        assert_eq!(outline(&children[1..]), ["def 1 Ay", "def 2 Bee"]);
+
+        let messages: Vec<_> = result.warnings.iter().map(|w| &w.message).collect();
+        assert_eq!(
+            messages,
+            [
+                "duplicate definition of footnote 'a' was dropped",
+                "footnote reference 'missing' has no definition",
+                "footnote 'unused' is never referenced and was dropped",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inline() {
+        let transform = NormalizeFootnotes::new(FootnotePlacement::Inline);
+        let result = transform.transform(labelled()).unwrap();
+        let children = &result.value.content.children;
+        assert_eq!(children.len(), 1);
+        assert_eq!(
+            outline(&children[0].children),
+            ["text", "def 1 Ay", "def 2 Bee", "ref 1", "ref missing"]
+        );
+
+        // Inline notes are already canonical.
+        let again = transform.transform(result.value.clone()).unwrap();
+        assert!(rescribe_core::diff(&result.value, &again.value, &Default::default()).is_empty());
+    }
+
+    #[test]
+    fn test_inline_notes_moved_to_end() {
+        // As read from Pandoc JSON: unlabelled notes inside the text.
+        let mut document = doc(|d| d.para(|i| i.text("one")).para(|i| i.text("two")));
+        document.content.children[0].children.push(def("", "First"));
+        document.content.children[1]
+            .children
+            .push(def("", "Second"));
+
+        let result = NormalizeFootnotes::default().transform(document).unwrap();
+        assert!(result.warnings.is_empty());
+        let children = &result.value.content.children;
+        assert_eq!(outline(&children[0].children), ["text", "ref 1"]);
+        assert_eq!(outline(&children[1].children), ["text", "ref 2"]);
+        assert_eq!(outline(&children[2..]), ["def 1 First", "def 2 Second"]);
+    }
+
+    #[test]
+    fn test_end_of_section() {
+        let mut document = doc(|d| {
+            d.heading(2, |i| i.text("A"))
+                .para(|i| i.text("a").footnote_ref("1"))
+                .heading(3, |i| i.text("A.1"))
+                .para(|i| i.text("b").footnote_ref("2"))
+                .heading(2, |i| i.text("B"))
+                .para(|i| i.text("c").footnote_ref("3"))
+        });
+        for (label, text) in [("1", "One"), ("2", "Two"), ("3", "Three")] {
+            document.content.children.push(def(label, text));
+        }
+
+        let transform = NormalizeFootnotes::new(FootnotePlacement::EndOfSection);
+        let result = transform.transform(document.clone()).unwrap().value;
+        assert_eq!(
+            outline(&result.content.children),
+            [
+                "heading",
+                "paragraph",
+                "heading",
+                "paragraph",
+                "def 1 One",
+                "def 2 Two",
+                "heading",
+                "paragraph",
+                "def 3 Three",
+            ]
+        );
+
+        let sections = crate::Sectionize.transform(document).unwrap().value;
+        let result = transform.transform(sections).unwrap().value;
+        let first = &result.content.children[0];
+        assert_eq!(
+            outline(&first.children),
+            ["heading", "paragraph", "div", "def 1 One", "def 2 Two"]
+        );
+    }
+}
@@ -4,9 +4,58 @@
 //! - Heading level adjustment
 //! - Empty node removal
 //! - Document structure normalization
+//! - Schema validation
 //! - Visitor utilities for custom transforms
-
-use rescribe_core::{Document, TransformError, Transformer};
+//! - Footnote numbering and placement (see [`footnotes`])
+//! - Section identifiers, numbering and nesting (see [`sections`])
+//! - Table of contents generation (see [`toc`])
+//! - URL rebasing, cross-document link rewriting and link checking (see
+//!   [`links`])
+//! - Image embedding and media extraction (see [`media`])
+//! - Concatenating and splitting documents (see [`concat`] and [`split`])
+//! - Smart quotes, dashes and ellipses (see [`typography`])
+//! - Citation and bibliography formatting with CSL styles (see [`citeproc`]
+//!   and [`csl`])
+//! - Parsing TeX math into structured math nodes (see [`math`])
+//! - Selector queries over node trees (see [`select`](mod@select))
+//! - External Pandoc JSON filters (see [`filter`])
+//! - Lua filters, with the `lua` feature (see [`lua`](mod@lua))
+
+pub mod citeproc;
+pub mod concat;
+pub mod csl;
+pub mod filter;
+pub mod footnotes;
+pub mod links;
+#[cfg(feature = "lua")]
+pub mod lua;
+pub mod math;
+pub mod media;
+pub mod sections;
+pub mod select;
+pub mod split;
+pub mod toc;
+pub mod typography;
+
+pub use citeproc::Citeproc;
+pub use concat::{Concat, MetadataPrecedence};
+pub use csl::{CslError, Locale, Style};
+pub use filter::PandocFilter;
+pub use footnotes::{FootnotePlacement, NormalizeFootnotes};
+pub use links::{CheckLinks, ResolveUrls, RewriteLinks};
+#[cfg(feature = "lua")]
+pub use lua::LuaFilter;
+pub use math::ParseMath;
+pub use media::{EmbedImages, ExtractMedia};
+pub use sections::{AutoIdentifiers, NumberSections, Sectionize, SlugStyle};
+pub use select::{Selector, SelectorError, select, select_mut};
+pub use split::SplitAt;
+pub use toc::GenerateToc;
+pub use typography::{Dumbify, QuoteLocale, Smartify};
+
+use rescribe_core::{
+    ConversionResult, Document, FidelityWarning, Schema, TransformError, Transformer,
+};
 use rescribe_std::{Node, node, prop};
 
 /// Shift all heading levels by a fixed amount.
@@ -66,14 +115,14 @@ impl Transformer for ShiftHeadings {
         "shift_headings"
     }
 
-    fn transform(&self, doc: Document) -> Result<Document, TransformError> {
+    fn transform(&self, doc: Document) -> Result<ConversionResult<Document>, TransformError> {
         let content = self.transform_node(doc.content);
-        Ok(Document {
+        Ok(ConversionResult::ok(Document {
             content,
             metadata: doc.metadata,
             resources: doc.resources,
             source: doc.source,
-        })
+        }))
     }
 }
 
@@ -109,14 +158,14 @@ impl Transformer for StripEmpty {
         "strip_empty"
     }
 
-    fn transform(&self, doc: Document) -> Result<Document, TransformError> {
+    fn transform(&self, doc: Document) -> Result<ConversionResult<Document>, TransformError> {
         let content = Self::transform_node(doc.content);
-        Ok(Document {
+        Ok(ConversionResult::ok(Document {
             content,
             metadata: doc.metadata,
             resources: doc.resources,
             source: doc.source,
-        })
+        }))
     }
 }
 
@@ -158,14 +207,14 @@ impl Transformer for MergeText {
         "merge_text"
     }
 
-    fn transform(&self, doc: Document) -> Result<Document, TransformError> {
+    fn transform(&self, doc: Document) -> Result<ConversionResult<Document>, TransformError> {
         let content = Self::transform_node(doc.content);
-        Ok(Document {
+        Ok(ConversionResult::ok(Document {
             content,
             metadata: doc.metadata,
             resources: doc.resources,
             source: doc.source,
-        })
+        }))
     }
 }
 
@@ -203,18 +252,84 @@ impl Transformer for UnwrapSingleChild {
         "unwrap_single_child"
     }
 
-    fn transform(&self, doc: Document) -> Result<Document, TransformError> {
+    fn transform(&self, doc: Document) -> Result<ConversionResult<Document>, TransformError> {
         let content = Self::transform_node(doc.content);
-        Ok(Document {
+        Ok(ConversionResult::ok(Document {
             content,
             metadata: doc.metadata,
             resources: doc.resources,
             source: doc.source,
-        })
+        }))
+    }
+}
+
+/// Check a document against a [`Schema`].
+///
+/// By default validation only reports: the document passes through the
+/// transform unchanged, with the violations as path-addressed warnings
+/// (also available from [`check`](Self::check)). In strict mode the
+/// transform fails on any violation, which is useful for asserting reader
+/// output in tests.
+pub struct Validate {
+    schema: Schema,
+    strict: bool,
+}
+
+impl Validate {
+    /// Validate against a custom schema.
+    pub fn new(schema: Schema) -> Self {
+        Self {
+            schema,
+            strict: false,
+        }
+    }
+
+    /// Validate against the standard and math node schemas.
+    pub fn standard() -> Self {
+        Self::new(rescribe_std::schema().merge(rescribe_math::schema()))
+    }
+
+    /// Fail the transform on any violation.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Check a document, returning one warning per violation.
+    pub fn check(&self, doc: &Document) -> Vec<FidelityWarning> {
+        self.schema.validate(doc)
+    }
+}
+
+impl Transformer for Validate {
+    fn name(&self) -> &str {
+        "validate"
+    }
+
+    fn transform(&self, doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        let warnings = self.check(&doc);
+        if self.strict && !warnings.is_empty() {
+            let violations: Vec<String> = warnings
+                .iter()
+                .map(|w| match &w.path {
+                    Some(path) => format!("{path}: {}", w.message),
+                    None => w.message.clone(),
+                })
+                .collect();
+            return Err(TransformError::Failed(format!(
+                "document does not match schema:\n{}",
+                violations.join("\n")
+            )));
+        }
+        Ok(ConversionResult::with_warnings(doc, warnings))
     }
 }
 
 /// A transform pipeline that applies multiple transforms in sequence.
+///
+/// The warnings of all transforms are collected in order, each tagged with
+/// the name of the transform that reported it (unless a nested pipeline
+/// already tagged it).
 pub struct Pipeline {
     transforms: Vec<Box<dyn Transformer>>,
 }
@@ -245,11 +360,17 @@ impl Transformer for Pipeline {
         "pipeline"
     }
 
-    fn transform(&self, mut doc: Document) -> Result<Document, TransformError> {
+    fn transform(&self, mut doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        let mut warnings = Vec::new();
         for transform in &self.transforms {
-            doc = transform.transform(doc)?;
+            let result = transform.transform(doc)?;
+            warnings.extend(result.warnings.into_iter().map(|w| match w.origin {
+                Some(_) => w,
+                None => w.with_origin(transform.name()),
+            }));
+            doc = result.value;
         }
-        Ok(doc)
+        Ok(ConversionResult::with_warnings(doc, warnings))
     }
 }
 
@@ -275,6 +396,22 @@ where
     }
 }
 
+/// Concatenate the text content of a node, with line breaks as spaces.
+pub fn stringify(node: &Node) -> String {
+    fn go(node: &Node, out: &mut String) {
+        match node.kind.as_str() {
+            node::TEXT | node::CODE => {
+                out.push_str(node.props.get_str(prop::CONTENT).unwrap_or(""))
+            }
+            node::SOFT_BREAK | node::LINE_BREAK => out.push(' '),
+            _ => node.children.iter().for_each(|child| go(child, out)),
+        }
+    }
+    let mut out = String::new();
+    go(node, &mut out);
+    out
+}
+
 /// Map a function over all nodes in a tree, building a new tree.
 pub fn map<F>(node: Node, f: &mut F) -> Node
 where
@@ -290,6 +427,33 @@ mod tests {
     use super::*;
     use rescribe_std::builder::doc;
 
+    #[test]
+    fn test_validate() {
+        let valid = doc(|d| d.heading(1, |i| i.text("Title")));
+        assert!(Validate::standard().check(&valid).is_empty());
+        assert!(Validate::standard().strict().transform(valid).is_ok());
+
+        let mut invalid = doc(|d| d.para(|i| i.text("x")));
+        invalid.content.children[0]
+            .children
+            .push(Node::new(rescribe_math::node::FRACTION));
+        let warnings = Validate::standard().check(&invalid);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].path.as_ref().unwrap().to_string(), "/0/1");
+
+        // Non-strict validation passes the document through, with warnings.
+        let result = Validate::standard().transform(invalid.clone()).unwrap();
+        assert_eq!(result.warnings.len(), 2);
+        let err = Validate::standard()
+            .strict()
+            .transform(invalid)
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("/0/1: math:fraction is not allowed in paragraph")
+        );
+    }
+
     #[test]
     fn test_shift_headings_positive() {
         let document = doc(|d| {
@@ -298,7 +462,7 @@ mod tests {
         });
 
         let transform = ShiftHeadings::new(1);
-        let result = transform.transform(document).unwrap();
+        let result = transform.transform(document).unwrap().value;
 
         assert_eq!(
             result.content.children[0].props.get_int(prop::LEVEL),
@@ -315,7 +479,7 @@ mod tests {
         let document = doc(|d| d.heading(6, |i| i.text("Deep")));
 
         let transform = ShiftHeadings::new(2);
-        let result = transform.transform(document).unwrap();
+        let result = transform.transform(document).unwrap().value;
 
         // Should be clamped to max (6)
         assert_eq!(
@@ -335,7 +499,7 @@ mod tests {
         assert_eq!(document.content.children.len(), 3);
 
         let transform = StripEmpty;
-        let result = transform.transform(document).unwrap();
+        let result = transform.transform(document).unwrap().value;
 
         assert_eq!(result.content.children.len(), 2);
     }
@@ -348,7 +512,7 @@ mod tests {
         assert_eq!(document.content.children[0].children.len(), 2);
 
         let transform = MergeText;
-        let result = transform.transform(document).unwrap();
+        let result = transform.transform(document).unwrap().value;
 
         // Should be merged to one text node
         assert_eq!(result.content.children[0].children.len(), 1);
@@ -374,7 +538,7 @@ mod tests {
 
         let pipeline = Pipeline::new().then(StripEmpty).then(ShiftHeadings::new(1));
 
-        let result = pipeline.transform(document).unwrap();
+        let result = pipeline.transform(document).unwrap().value;
 
         // Empty paragraph removed
         assert_eq!(result.content.children.len(), 2);
@@ -385,6 +549,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_pipeline_warnings() {
+        let mut document = doc(|d| d.para(|i| i.text("x").footnote_ref("missing")));
+        document
+            .content
+            .children
+            .push(Node::new(rescribe_math::node::FRACTION));
+
+        let inner = Pipeline::new().then(Validate::standard());
+        let pipeline = Pipeline::new()
+            .then(inner)
+            .then(NormalizeFootnotes::default());
+        let result = pipeline.transform(document).unwrap();
+
+        let (last, validation) = result.warnings.split_last().unwrap();
+        assert!(!validation.is_empty());
+        assert!(
+            validation
+                .iter()
+                .all(|w| w.origin.as_deref() == Some("validate"))
+        );
+        assert_eq!(last.origin.as_deref(), Some("normalize_footnotes"));
+        assert_eq!(
+            last.message,
+            "footnote reference 'missing' has no definition"
+        );
+    }
+
     #[test]
     fn test_walk() {
         let document = doc(|d| d.para(|i| i.text("Hello").em(|i| i.text("World"))));
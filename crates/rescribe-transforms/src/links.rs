@@ -0,0 +1,442 @@
+//! Link and image URL handling.
+//!
+//! - [`ResolveUrls`] rebases relative `link` and `image` URLs against a base
+//!   path or URL, for documents moved away from the files they refer to.
+//! - [`RewriteLinks`] changes the extension of links to sibling documents,
+//!   so that `guide.md#setup` still works once every file of a tree is
+//!   converted to `guide.html`.
+//! - [`CheckLinks`] reports links to fragments that do not exist, within the
+//!   document or in other documents of the tree.
+//!
+//! URLs are resolved as RFC 3986 describes, without further normalization.
+//!
+//! ```
+//! use rescribe_core::Transformer;
+//! use rescribe_std::builder::doc;
+//! use rescribe_std::prop;
+//! use rescribe_transforms::{ResolveUrls, RewriteLinks};
+//!
+//! let document = doc(|d| d.para(|i| i.link("../api.md#parse", |i| i.text("parse"))));
+//! let document = RewriteLinks::new()
+//!     .extension("md", "html")
+//!     .transform(document)
+//!     .unwrap()
+//!     .value;
+//! let document = ResolveUrls::new("https://example.com/docs/guide/")
+//!     .transform(document)
+//!     .unwrap()
+//!     .value;
+//!
+//! let link = &document.content.children[0].children[0];
+//! assert_eq!(
+//!     link.props.get_str(prop::URL),
+//!     Some("https://example.com/docs/api.html#parse")
+//! );
+//! ```
+
+use rescribe_core::{
+    ConversionResult, Document, FidelityWarning, NodePath, Severity, TransformError, Transformer,
+    WarningKind,
+};
+use rescribe_std::{Node, node, prop};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Resolve relative link and image URLs against a base.
+///
+/// The base is a URL (`https://example.com/docs/`) or a path
+/// (`chapters/intro.md`). As in HTML, a base that does not end in `/` names
+/// a file, and references are resolved against its directory. Absolute
+/// URLs, fragment-only links and, when the base is a path, absolute paths
+/// are left alone.
+#[derive(Debug, Clone)]
+pub struct ResolveUrls {
+    base: String,
+}
+
+impl ResolveUrls {
+    /// Create a transform resolving against `base`.
+    pub fn new(base: impl Into<String>) -> Self {
+        Self { base: base.into() }
+    }
+}
+
+impl Transformer for ResolveUrls {
+    fn name(&self) -> &str {
+        "resolve_urls"
+    }
+
+    fn transform(&self, mut doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        crate::walk_mut(&mut doc.content, &mut |node| {
+            if matches!(node.kind.as_str(), node::LINK | node::IMAGE)
+                && let Some(url) = node.props.get_str(prop::URL)
+            {
+                let resolved = resolve(&self.base, url);
+                node.props.set(prop::URL, resolved);
+            }
+        });
+        Ok(ConversionResult::ok(doc))
+    }
+}
+
+/// Change the extension of relative links to other documents.
+///
+/// Only links are rewritten; images and absolute URLs are left alone.
+/// Extensions match case-insensitively.
+#[derive(Debug, Clone, Default)]
+pub struct RewriteLinks {
+    extensions: Vec<(String, String)>,
+}
+
+impl RewriteLinks {
+    /// Create a transform rewriting nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rewrite links to `.from` files into links to `.to` files.
+    pub fn extension(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.extensions.push((from.into(), to.into()));
+        self
+    }
+
+    fn rewrite(&self, url: &str) -> Option<String> {
+        if !is_relative(url) || url.starts_with('#') {
+            return None;
+        }
+        let (path, suffix) = split_suffix(url);
+        let name_start = path.rfind('/').map_or(0, |i| i + 1);
+        let (stem, ext) = path[name_start..].rsplit_once('.')?;
+        let (_, to) = self
+            .extensions
+            .iter()
+            .find(|(from, _)| from.eq_ignore_ascii_case(ext))?;
+        Some(format!("{}{stem}.{to}{suffix}", &path[..name_start]))
+    }
+}
+
+impl Transformer for RewriteLinks {
+    fn name(&self) -> &str {
+        "rewrite_links"
+    }
+
+    fn transform(&self, mut doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        crate::walk_mut(&mut doc.content, &mut |node| {
+            if node.kind.as_str() == node::LINK
+                && let Some(url) = node.props.get_str(prop::URL)
+                && let Some(rewritten) = self.rewrite(url)
+            {
+                node.props.set(prop::URL, rewritten);
+            }
+        });
+        Ok(ConversionResult::ok(doc))
+    }
+}
+
+/// Report links whose fragment matches no identifier.
+///
+/// `#fragment` links are checked against the `id`s in the document. Links
+/// to documents registered with [`with_document`](Self::with_document) are
+/// checked against that document's `id`s; links to other documents are not
+/// checked. Headings only have an `id` once one is assigned, so run
+/// [`AutoIdentifiers`](crate::AutoIdentifiers) first if the writer would
+/// generate them.
+///
+/// The document is passed through unchanged; each dangling link yields a
+/// warning pointing at the link.
+#[derive(Debug, Clone, Default)]
+pub struct CheckLinks {
+    documents: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl CheckLinks {
+    /// Create a checker for links within the document.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also check links into `document`, found at `path` relative to the
+    /// checked document.
+    ///
+    /// Check links before rewriting them with [`RewriteLinks`], since `path`
+    /// is compared with the link as written.
+    pub fn with_document(mut self, path: &str, document: &Document) -> Self {
+        self.documents
+            .insert(normalize_path(path), identifiers(&document.content));
+        self
+    }
+
+    /// Why `url` is dangling, if it is.
+    fn check(&self, url: &str, ids: &BTreeSet<String>) -> Option<String> {
+        if let Some(fragment) = url.strip_prefix('#') {
+            return (!fragment.is_empty() && !ids.contains(fragment))
+                .then(|| format!("link target '{url}' does not exist"));
+        }
+        if !is_relative(url) {
+            return None;
+        }
+        let (path, suffix) = split_suffix(url);
+        let fragment = suffix.split_once('#')?.1;
+        let targets = self.documents.get(&normalize_path(path))?;
+        (!fragment.is_empty() && !targets.contains(fragment))
+            .then(|| format!("link target '{url}' does not exist in '{path}'"))
+    }
+
+    fn check_node(
+        &self,
+        node: &Node,
+        path: NodePath,
+        ids: &BTreeSet<String>,
+        warnings: &mut Vec<FidelityWarning>,
+    ) {
+        if node.kind.as_str() == node::LINK
+            && let Some(url) = node.props.get_str(prop::URL)
+            && let Some(message) = self.check(url, ids)
+        {
+            let warning = FidelityWarning::new(
+                Severity::Minor,
+                WarningKind::InvalidStructure(format!("link:{url}")),
+                message,
+            )
+            .at_path(path.clone());
+            warnings.push(match node.span {
+                Some(span) => warning.at(span),
+                None => warning,
+            });
+        }
+        for (i, child) in node.children.iter().enumerate() {
+            self.check_node(child, path.child(i), ids, warnings);
+        }
+    }
+}
+
+impl Transformer for CheckLinks {
+    fn name(&self) -> &str {
+        "check_links"
+    }
+
+    fn transform(&self, doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        let ids = identifiers(&doc.content);
+        let mut warnings = Vec::new();
+        self.check_node(&doc.content, NodePath::root(), &ids, &mut warnings);
+        Ok(ConversionResult::with_warnings(doc, warnings))
+    }
+}
+
+/// Every `id` in a tree.
+fn identifiers(root: &Node) -> BTreeSet<String> {
+    let mut ids = BTreeSet::new();
+    crate::walk(root, &mut |node| {
+        if let Some(id) = node.props.get_str(prop::ID) {
+            ids.insert(id.to_string());
+        }
+    });
+    ids
+}
+
+/// Resolve `reference` against `base` (RFC 3986, section 5.2).
+fn resolve(base: &str, reference: &str) -> String {
+    if reference.is_empty() || !is_relative(reference) || reference.starts_with('#') {
+        return reference.to_string();
+    }
+    let (origin, base_path) = split_origin(base);
+    let (base_path, _) = split_suffix(base_path);
+    let (path, suffix) = split_suffix(reference);
+
+    let merged = if path.starts_with('/') {
+        if origin.is_empty() {
+            return reference.to_string();
+        }
+        path.to_string()
+    } else if path.is_empty() {
+        base_path.to_string()
+    } else {
+        let directory = base_path.rfind('/').map_or("", |i| &base_path[..=i]);
+        match (origin.is_empty(), directory.is_empty()) {
+            (false, true) => format!("/{path}"),
+            _ => format!("{directory}{path}"),
+        }
+    };
+    format!("{origin}{}{suffix}", remove_dot_segments(&merged))
+}
+
+/// Whether `url` is relative: no scheme and no authority.
+pub(crate) fn is_relative(url: &str) -> bool {
+    scheme_len(url).is_none() && !url.starts_with("//")
+}
+
+/// Length of `url`'s scheme, if it has one. Windows drive letters count as
+/// schemes, which makes paths such as `C:/images` absolute.
+fn scheme_len(url: &str) -> Option<usize> {
+    let end = url.find(':')?;
+    let scheme = &url[..end];
+    (scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)))
+    .then_some(end)
+}
+
+/// Split a URL into its scheme and authority, and the rest.
+fn split_origin(url: &str) -> (&str, &str) {
+    let Some(scheme) = scheme_len(url) else {
+        return ("", url);
+    };
+    let rest = &url[scheme + 1..];
+    let end = match rest.strip_prefix("//") {
+        Some(authority) => 2 + authority.find(['/', '?', '#']).unwrap_or(authority.len()),
+        None => 0,
+    };
+    url.split_at(scheme + 1 + end)
+}
+
+/// Split a reference into its path and its query and fragment.
+pub(crate) fn split_suffix(url: &str) -> (&str, &str) {
+    url.split_at(url.find(['?', '#']).unwrap_or(url.len()))
+}
+
+/// Resolve `.` and `..` segments. Leading `..` segments of a relative path
+/// are kept.
+fn remove_dot_segments(path: &str) -> String {
+    let absolute = path.starts_with('/');
+    let segments: Vec<&str> = path.split('/').collect();
+    let last = segments.len() - 1;
+    let mut out: Vec<&str> = Vec::new();
+    for (i, segment) in segments.into_iter().enumerate() {
+        match segment {
+            "." => {}
+            ".." => {
+                if out.last().is_some_and(|s| !s.is_empty() && *s != "..") {
+                    out.pop();
+                } else if !absolute {
+                    out.push("..");
+                }
+            }
+            segment => {
+                out.push(segment);
+                continue;
+            }
+        }
+        // A path ending in a dot segment names a directory.
+        if i == last {
+            out.push("");
+        }
+    }
+    out.join("/")
+}
+
+/// A relative path in the form used to look up registered documents.
+fn normalize_path(path: &str) -> String {
+    let path = remove_dot_segments(path);
+    path.strip_prefix("./").unwrap_or(&path).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rescribe_std::builder::doc;
+
+    #[test]
+    fn test_resolve() {
+        let cases = [
+            ("https://a.org/b/c/d", "g", "https://a.org/b/c/g"),
+            ("https://a.org/b/c/d", "./g/", "https://a.org/b/c/g/"),
+            ("https://a.org/b/c/d", "/g", "https://a.org/g"),
+            (
+                "https://a.org/b/c/d",
+                "../../g?x=1#s",
+                "https://a.org/g?x=1#s",
+            ),
+            ("https://a.org/b/c/d", "../../../g", "https://a.org/g"),
+            ("https://a.org", "g", "https://a.org/g"),
+            ("https://a.org/b/c/d", "#s", "#s"),
+            ("https://a.org/b/c/d", "mailto:x@a.org", "mailto:x@a.org"),
+            ("https://a.org/b/c/d", "//cdn.org/g", "//cdn.org/g"),
+            ("docs/guide/", "img/a.png", "docs/guide/img/a.png"),
+            ("docs/guide/index.md", "../api.md", "docs/api.md"),
+            ("docs/index.md", "../../g", "../g"),
+            ("index.md", "g", "g"),
+            ("docs/", "/abs/g", "/abs/g"),
+            ("docs/", "C:/img/a.png", "C:/img/a.png"),
+            ("C:/docs/", "/img/a.png", "C:/img/a.png"),
+        ];
+        for (base, reference, expected) in cases {
+            assert_eq!(resolve(base, reference), expected, "{base} + {reference}");
+        }
+    }
+
+    #[test]
+    fn test_resolve_urls() {
+        let document = doc(|d| {
+            d.para(|i| {
+                i.link("a.html", |i| i.text("a"))
+                    .image("img/b.png", "b")
+                    .link("#top", |i| i.text("top"))
+            })
+        });
+        let result = ResolveUrls::new("../site/")
+            .transform(document)
+            .unwrap()
+            .value;
+        let urls: Vec<_> = result.content.children[0]
+            .children
+            .iter()
+            .map(|n| n.props.get_str(prop::URL).unwrap())
+            .collect();
+        assert_eq!(urls, ["../site/a.html", "../site/img/b.png", "#top"]);
+    }
+
+    #[test]
+    fn test_rewrite_links() {
+        let rewrite = RewriteLinks::new()
+            .extension("md", "html")
+            .extension("markdown", "html");
+        assert_eq!(
+            rewrite.rewrite("foo.md#bar").as_deref(),
+            Some("foo.html#bar")
+        );
+        assert_eq!(
+            rewrite.rewrite("../a.b/Guide.MARKDOWN?x").as_deref(),
+            Some("../a.b/Guide.html?x")
+        );
+        assert_eq!(rewrite.rewrite("a.b/README"), None);
+        assert_eq!(rewrite.rewrite("https://example.com/a.md"), None);
+        assert_eq!(rewrite.rewrite("#a.md"), None);
+    }
+
+    #[test]
+    fn test_check_links() {
+        let mut document = doc(|d| {
+            d.heading(1, |i| i.text("Intro")).para(|i| {
+                i.link("#intro", |i| i.text("ok"))
+                    .link("#missing", |i| i.text("dangling"))
+                    .link("./other.md#part", |i| i.text("ok"))
+                    .link("other.md#gone", |i| i.text("dangling"))
+                    .link("unknown.md#x", |i| i.text("unchecked"))
+                    .link("https://example.com/#x", |i| i.text("unchecked"))
+            })
+        });
+        document.content.children[0].props.set(prop::ID, "intro");
+        let mut other = doc(|d| d.para(|i| i.text("Part")));
+        other.content.children[0].props.set(prop::ID, "part");
+
+        let result = CheckLinks::new()
+            .with_document("other.md", &other)
+            .transform(document)
+            .unwrap();
+        let warnings: Vec<_> = result
+            .warnings
+            .iter()
+            .map(|w| (w.path.as_ref().unwrap().to_string(), w.message.as_str()))
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                ("/1/1".to_string(), "link target '#missing' does not exist"),
+                (
+                    "/1/3".to_string(),
+                    "link target 'other.md#gone' does not exist in 'other.md'"
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,485 @@
+//! Lua filters over the document IR.
+//!
+//! A [`LuaFilter`] runs a Lua 5.4 script in the spirit of Pandoc Lua
+//! filters. The script defines functions named after node kinds, either as
+//! globals or in a returned table (which also allows namespaced kinds such
+//! as `["math:fraction"]`). A script may instead return a list of such
+//! tables, which are applied one after another.
+//!
+//! ```lua
+//! -- Demote every heading and drop horizontal rules
+//! function heading(n)
+//!   n.props.level = n.props.level + 1
+//!   return n
+//! end
+//!
+//! function horizontal_rule(n)
+//!   return {}
+//! end
+//! ```
+//!
+//! Nodes are passed as tables with `kind`, `props` and `children` fields
+//! (and `span`, if the node has one). Callbacks run bottom-up, after the
+//! node's children have been filtered, and return:
+//!
+//! - `nil` to keep the node unchanged,
+//! - a node table to replace it,
+//! - a list of node tables to replace it with several nodes (`{}` deletes it).
+//!
+//! A `metadata` function receives the document metadata as a table and may
+//! return a replacement. The `rescribe` global provides helpers:
+//! `rescribe.node(kind, props, children)`, `rescribe.text(content)`,
+//! `rescribe.stringify(node)` and a read-only copy of the metadata in
+//! `rescribe.metadata`.
+//!
+//! Scripts run in a sandbox: only the base, `string`, `table`, `math` and
+//! `utf8` libraries are available (without `dofile` and `loadfile`), and
+//! memory use and the number of executed instructions are limited.
+
+use mlua::{HookTriggers, Lua, LuaOptions, StdLib, Table, Value};
+use rescribe_core::{
+    ConversionResult, Document, PropValue, Properties, Span, TransformError, Transformer,
+};
+use rescribe_std::{Node, node, prop};
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::Path;
+
+/// Callback receiving the document metadata.
+const METADATA_CALLBACK: &str = "metadata";
+
+/// Run a Lua filter script over a document.
+#[derive(Debug, Clone)]
+pub struct LuaFilter {
+    name: String,
+    source: String,
+    memory_limit: usize,
+    instruction_limit: u64,
+}
+
+impl LuaFilter {
+    /// Create a filter from Lua source.
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            name: "filter".to_string(),
+            source: source.into(),
+            memory_limit: 256 * 1024 * 1024,
+            instruction_limit: 1_000_000_000,
+        }
+    }
+
+    /// Load a filter script from a file.
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+        Ok(Self::new(source).with_name(path.display().to_string()))
+    }
+
+    /// Set the name used in error messages.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Limit the memory the script may allocate, in bytes.
+    pub fn memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = bytes;
+        self
+    }
+
+    /// Limit the number of Lua instructions the script may execute.
+    pub fn instruction_limit(mut self, instructions: u64) -> Self {
+        self.instruction_limit = instructions;
+        self
+    }
+
+    fn run(&self, mut doc: Document) -> mlua::Result<Document> {
+        let lua = self.sandbox()?;
+        let globals = lua.globals();
+        let builtins: HashSet<String> = globals
+            .clone()
+            .pairs::<String, Value>()
+            .filter_map(|pair| pair.ok().map(|(key, _)| key))
+            .collect();
+        install_helpers(&lua, &doc.metadata)?;
+
+        let returned: Value = lua
+            .load(&self.source)
+            .set_name(format!("@{}", self.name))
+            .eval()?;
+        let filters = match returned {
+            Value::Table(table) if is_callback_table(&table)? => vec![table],
+            Value::Table(table) => table.sequence_values::<Table>().collect::<Result<_, _>>()?,
+            Value::Nil => {
+                // Use the functions the script defined as globals
+                let filter = lua.create_table()?;
+                for pair in globals.clone().pairs::<String, Value>() {
+                    let (key, value) = pair?;
+                    if !builtins.contains(&key) && key != "rescribe" {
+                        filter.set(key, value)?;
+                    }
+                }
+                vec![filter]
+            }
+            other => {
+                return Err(mlua::Error::runtime(format!(
+                    "script must return nil, a filter table or a list of filter tables, got {}",
+                    other.type_name()
+                )));
+            }
+        };
+
+        for filter in filters {
+            let callbacks = Callbacks::from_table(&filter)?;
+            let mut roots = filter_node(&lua, &callbacks, doc.content)?;
+            if roots.len() != 1 {
+                return Err(mlua::Error::runtime(format!(
+                    "the document root must be replaced by exactly one node, got {}",
+                    roots.len()
+                )));
+            }
+            doc.content = roots.remove(0);
+
+            if let Some(callback) = filter.get::<_, Option<mlua::Function>>(METADATA_CALLBACK)? {
+                let meta = props_to_lua(&lua, &doc.metadata)?;
+                if let Value::Table(meta) = callback.call::<_, Value>(meta)? {
+                    doc.metadata = lua_to_props(&meta)?;
+                }
+            }
+        }
+        Ok(doc)
+    }
+
+    fn sandbox(&self) -> mlua::Result<Lua> {
+        let libs = StdLib::STRING | StdLib::TABLE | StdLib::MATH | StdLib::UTF8;
+        let lua = Lua::new_with(libs, LuaOptions::default())?;
+        for unsafe_global in ["dofile", "loadfile"] {
+            lua.globals().set(unsafe_global, Value::Nil)?;
+        }
+        lua.set_memory_limit(self.memory_limit)?;
+
+        const STEP: u32 = 10_000;
+        let remaining = Cell::new(self.instruction_limit);
+        lua.set_hook(
+            HookTriggers::new().every_nth_instruction(STEP),
+            move |_, _| match remaining.get().checked_sub(u64::from(STEP)) {
+                Some(left) => {
+                    remaining.set(left);
+                    Ok(())
+                }
+                None => Err(mlua::Error::runtime("instruction limit exceeded")),
+            },
+        );
+        Ok(lua)
+    }
+}
+
+impl Transformer for LuaFilter {
+    fn name(&self) -> &str {
+        "lua-filter"
+    }
+
+    fn transform(&self, doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        self.run(doc)
+            .map(ConversionResult::ok)
+            .map_err(|e| TransformError::Failed(format!("{}: {e}", self.name)))
+    }
+}
+
+/// A table is a filter (rather than a list of filters) if it has any
+/// string-keyed function.
+fn is_callback_table(table: &Table) -> mlua::Result<bool> {
+    for pair in table.clone().pairs::<Value, Value>() {
+        if let (Value::String(_), Value::Function(_)) = pair? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Per-kind callbacks of one filter.
+struct Callbacks<'lua> {
+    by_kind: HashMap<String, mlua::Function<'lua>>,
+}
+
+impl<'lua> Callbacks<'lua> {
+    fn from_table(table: &Table<'lua>) -> mlua::Result<Self> {
+        let mut by_kind = HashMap::new();
+        for pair in table.clone().pairs::<Value, Value>() {
+            if let (Value::String(key), Value::Function(f)) = pair? {
+                let key = key.to_str()?;
+                if key != METADATA_CALLBACK {
+                    by_kind.insert(key.to_string(), f);
+                }
+            }
+        }
+        Ok(Self { by_kind })
+    }
+}
+
+/// Filter a node bottom-up, returning its replacements.
+fn filter_node(lua: &Lua, callbacks: &Callbacks, mut node: Node) -> mlua::Result<Vec<Node>> {
+    if callbacks.by_kind.is_empty() {
+        return Ok(vec![node]);
+    }
+    let mut children = Vec::with_capacity(node.children.len());
+    for child in std::mem::take(&mut node.children) {
+        children.extend(filter_node(lua, callbacks, child)?);
+    }
+    node.children = children;
+
+    let Some(callback) = callbacks.by_kind.get(node.kind.as_str()) else {
+        return Ok(vec![node]);
+    };
+    let kind = node.kind.as_str().to_string();
+    match callback.call::<_, Value>(node_to_lua(lua, &node)?)? {
+        Value::Nil => Ok(vec![node]),
+        Value::Table(table) if table.contains_key("kind")? => Ok(vec![lua_to_node(&table)?]),
+        Value::Table(table) => table
+            .sequence_values::<Table>()
+            .map(|item| lua_to_node(&item?))
+            .collect(),
+        other => Err(mlua::Error::runtime(format!(
+            "callback for {kind} must return nil, a node or a list of nodes, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+fn install_helpers(lua: &Lua, metadata: &Properties) -> mlua::Result<()> {
+    let module = lua.create_table()?;
+    module.set(
+        "node",
+        lua.create_function(
+            |lua, (kind, props, children): (String, Option<Table>, Option<Table>)| {
+                let node = lua.create_table()?;
+                node.set("kind", kind)?;
+                node.set("props", props.map_or_else(|| lua.create_table(), Ok)?)?;
+                node.set("children", children.map_or_else(|| lua.create_table(), Ok)?)?;
+                Ok(node)
+            },
+        )?,
+    )?;
+    module.set(
+        "text",
+        lua.create_function(|lua, content: String| {
+            node_to_lua(lua, &Node::new(node::TEXT).prop(prop::CONTENT, content))
+        })?,
+    )?;
+    module.set(
+        "stringify",
+        lua.create_function(|_, node: Table| Ok(crate::stringify(&lua_to_node(&node)?)))?,
+    )?;
+    module.set("metadata", props_to_lua(lua, metadata)?)?;
+    lua.globals().set("rescribe", module)
+}
+
+fn node_to_lua<'lua>(lua: &'lua Lua, node: &Node) -> mlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    table.set("kind", node.kind.as_str())?;
+    table.set("props", props_to_lua(lua, &node.props)?)?;
+    let children = lua.create_table_with_capacity(node.children.len(), 0)?;
+    for child in &node.children {
+        children.push(node_to_lua(lua, child)?)?;
+    }
+    table.set("children", children)?;
+    if let Some(span) = node.span {
+        let t = lua.create_table()?;
+        t.set("start", span.start)?;
+        t.set("end", span.end)?;
+        table.set("span", t)?;
+    }
+    Ok(table)
+}
+
+fn lua_to_node(table: &Table) -> mlua::Result<Node> {
+    let kind: String = table.get("kind")?;
+    let mut node = Node::new(kind);
+    if let Some(props) = table.get::<_, Option<Table>>("props")? {
+        node.props = lua_to_props(&props)?;
+    }
+    if let Some(children) = table.get::<_, Option<Table>>("children")? {
+        for child in children.sequence_values::<Table>() {
+            node.children.push(lua_to_node(&child?)?);
+        }
+    }
+    if let Some(span) = table.get::<_, Option<Table>>("span")? {
+        node.span = Some(Span {
+            start: span.get("start")?,
+            end: span.get("end")?,
+        });
+    }
+    Ok(node)
+}
+
+fn props_to_lua<'lua>(lua: &'lua Lua, props: &Properties) -> mlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    for (key, value) in props.iter() {
+        table.set(key.as_str(), prop_to_lua(lua, value)?)?;
+    }
+    Ok(table)
+}
+
+fn lua_to_props(table: &Table) -> mlua::Result<Properties> {
+    let mut props = Properties::new();
+    for pair in table.clone().pairs::<String, Value>() {
+        let (key, value) = pair?;
+        props.set(key, lua_to_prop(value)?);
+    }
+    Ok(props)
+}
+
+fn prop_to_lua<'lua>(lua: &'lua Lua, value: &PropValue) -> mlua::Result<Value<'lua>> {
+    Ok(match value {
+        PropValue::String(s) => Value::String(lua.create_string(s)?),
+        PropValue::Int(i) => Value::Integer(*i),
+        PropValue::Float(f) => Value::Number(*f),
+        PropValue::Bool(b) => Value::Boolean(*b),
+        PropValue::List(items) => {
+            let table = lua.create_table_with_capacity(items.len(), 0)?;
+            for item in items {
+                table.push(prop_to_lua(lua, item)?)?;
+            }
+            Value::Table(table)
+        }
+        PropValue::Map(map) => {
+            let table = lua.create_table_with_capacity(0, map.len())?;
+            for (key, item) in map {
+                table.set(key.as_str(), prop_to_lua(lua, item)?)?;
+            }
+            Value::Table(table)
+        }
+    })
+}
+
+/// Convert a Lua value to a property value. Tables with a sequence part
+/// (and empty tables) become lists, other tables become maps.
+fn lua_to_prop(value: Value) -> mlua::Result<PropValue> {
+    Ok(match value {
+        Value::String(s) => PropValue::String(s.to_str()?.to_string()),
+        Value::Integer(i) => PropValue::Int(i),
+        Value::Number(f) => PropValue::Float(f),
+        Value::Boolean(b) => PropValue::Bool(b),
+        Value::Table(table) if table.raw_len() > 0 || is_empty(&table)? => PropValue::List(
+            table
+                .sequence_values::<Value>()
+                .map(|item| lua_to_prop(item?))
+                .collect::<mlua::Result<_>>()?,
+        ),
+        Value::Table(table) => {
+            let mut map = HashMap::new();
+            for pair in table.pairs::<String, Value>() {
+                let (key, item) = pair?;
+                map.insert(key, lua_to_prop(item)?);
+            }
+            PropValue::Map(map)
+        }
+        other => {
+            return Err(mlua::Error::runtime(format!(
+                "cannot store a {} in a property",
+                other.type_name()
+            )));
+        }
+    })
+}
+
+fn is_empty(table: &Table) -> mlua::Result<bool> {
+    Ok(table.clone().pairs::<Value, Value>().next().is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rescribe_std::builder::doc;
+
+    fn run(script: &str, document: Document) -> Result<Document, TransformError> {
+        LuaFilter::new(script).transform(document).map(|r| r.value)
+    }
+
+    #[test]
+    fn test_global_callbacks() {
+        let document = doc(|d| {
+            d.heading(1, |i| i.text("Title"))
+                .hr()
+                .para(|i| i.text("Body"))
+        });
+        let script = r#"
+            function heading(n)
+              n.props.level = n.props.level + 1
+              n.props.id = rescribe.stringify(n):lower()
+              return n
+            end
+            function horizontal_rule(n) return {} end
+            function text(n)
+              if n.props.content == "Body" then
+                return { n, rescribe.text("!") }
+              end
+            end
+        "#;
+        let result = run(script, document).unwrap();
+        let children = &result.content.children;
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].props.get_int(prop::LEVEL), Some(2));
+        assert_eq!(children[0].props.get_str(prop::ID), Some("title"));
+        assert_eq!(children[1].children.len(), 2);
+    }
+
+    #[test]
+    fn test_returned_filters_and_metadata() {
+        let mut document = doc(|d| d.para(|i| i.em(|i| i.text("x"))));
+        document.metadata.set("title", "Draft");
+        let script = r#"
+            return {
+              { emphasis = function(n) return rescribe.node("strong", {}, n.children) end },
+              {
+                ["strong"] = function(n) n.props.classes = { "a", "b" } return n end,
+                metadata = function(m)
+                  m.title = m.title .. " (" .. rescribe.metadata.title .. ")"
+                  m.tags = { "x" }
+                  return m
+                end,
+              },
+            }
+        "#;
+        let result = run(script, document).unwrap();
+        let strong = &result.content.children[0].children[0];
+        assert_eq!(strong.kind.as_str(), node::STRONG);
+        assert_eq!(
+            strong.props.get(prop::CLASSES),
+            Some(&PropValue::List(vec!["a".into(), "b".into()]))
+        );
+        assert_eq!(result.metadata.get_str("title"), Some("Draft (Draft)"));
+        assert!(matches!(
+            result.metadata.get("tags"),
+            Some(PropValue::List(_))
+        ));
+    }
+
+    #[test]
+    fn test_sandbox() {
+        let document = doc(|d| d.para(|i| i.text("x")));
+        for script in [
+            "os.exit(1)",
+            "io.open('/etc/passwd')",
+            "dofile('/etc/passwd')",
+            "require('os')",
+            "while true do end",
+        ] {
+            let filter = LuaFilter::new(script).instruction_limit(1_000_000);
+            assert!(filter.transform(document.clone()).is_err(), "{script}");
+        }
+    }
+
+    #[test]
+    fn test_errors_name_the_script() {
+        let document = doc(|d| d.para(|i| i.text("x")));
+        let err = LuaFilter::new("function paragraph(n) return 1 end")
+            .with_name("rules.lua")
+            .transform(document)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("rules.lua"));
+        assert!(err.contains("callback for paragraph must return nil, a node or a list of nodes"));
+    }
+}
@@ -0,0 +1,128 @@
+//! Structured math from TeX sources.
+//!
+//! Readers store math as its TeX source in `math:source`. [`ParseMath`]
+//! parses those sources with [`rescribe_math::tex`] into `math:` children
+//! (fractions, roots, scripts, matrices and so on), which writers such as
+//! MathML ones and filters can work with.
+
+use rescribe_core::{
+    ConversionResult, Document, FidelityWarning, NodePath, Severity, TransformError, Transformer,
+    WarningKind,
+};
+use rescribe_math::{node, prop, tex};
+use rescribe_std::Node;
+
+/// Parse the TeX source of `math_inline` and `math_display` nodes into
+/// structured math children, keeping `math:source`.
+///
+/// Math that already has children, or whose `math:format` is other than
+/// `latex`, is left alone. Sources that fail to parse are left as they are
+/// with a warning.
+///
+/// ```
+/// use rescribe_core::{Document, Node, Transformer};
+/// use rescribe_transforms::ParseMath;
+///
+/// let math = Node::new("math_inline").prop("math:source", r"\sqrt{x}");
+/// let doc = Document::new().with_content(Node::new("document").child(math));
+///
+/// let doc = ParseMath.transform(doc).unwrap().value;
+/// assert_eq!(doc.content.children[0].children[0].kind.as_str(), "math:root");
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseMath;
+
+impl ParseMath {
+    fn parse_node(node: &mut Node, path: NodePath, warnings: &mut Vec<FidelityWarning>) {
+        let kind = node.kind.as_str();
+        if kind == node::MATH_INLINE || kind == node::MATH_DISPLAY {
+            if node.children.is_empty()
+                && node.props.get_str(prop::MATH_FORMAT).unwrap_or("latex") == "latex"
+                && let Some(source) = node.props.get_str(prop::MATH_SOURCE)
+            {
+                match tex::parse(source) {
+                    Ok(children) => node.children = children,
+                    Err(e) => {
+                        let warning = FidelityWarning::new(
+                            Severity::Minor,
+                            WarningKind::FeatureLost("math".to_string()),
+                            e.to_string(),
+                        )
+                        .at_path(path);
+                        warnings.push(match node.span {
+                            Some(span) => warning.at(span),
+                            None => warning,
+                        });
+                    }
+                }
+            }
+            return;
+        }
+        for (i, child) in node.children.iter_mut().enumerate() {
+            Self::parse_node(child, path.child(i), warnings);
+        }
+    }
+}
+
+impl Transformer for ParseMath {
+    fn name(&self) -> &str {
+        "parse_math"
+    }
+
+    fn transform(&self, mut doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        let mut warnings = Vec::new();
+        Self::parse_node(&mut doc.content, NodePath::root(), &mut warnings);
+        Ok(ConversionResult::with_warnings(doc, warnings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn math(kind: &str, source: &str) -> Node {
+        Node::new(kind).prop(prop::MATH_SOURCE, source)
+    }
+
+    #[test]
+    fn test_parse_math() {
+        let doc = Document::new().with_content(
+            Node::new("document")
+                .child(Node::new("paragraph").child(math(node::MATH_INLINE, "x^2")))
+                .child(math(node::MATH_DISPLAY, r"\frac{a}{b}"))
+                .child(math(node::MATH_DISPLAY, "x^2").prop(prop::MATH_FORMAT, "typst")),
+        );
+        let result = ParseMath.transform(doc).unwrap();
+        assert!(result.warnings.is_empty());
+        let content = &result.value.content;
+
+        let inline = &content.children[0].children[0];
+        assert_eq!(inline.children[0].kind.as_str(), node::MATH_SUP);
+        assert_eq!(inline.props.get_str(prop::MATH_SOURCE), Some("x^2"));
+        assert_eq!(
+            content.children[1].children[0].kind.as_str(),
+            node::FRACTION
+        );
+        assert!(content.children[2].children.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_source() {
+        let doc = Document::new().with_content(
+            Node::new("document")
+                .child(Node::new("paragraph").child(math(node::MATH_INLINE, r"\frac{a"))),
+        );
+        let result = ParseMath.transform(doc).unwrap();
+        assert!(
+            result.value.content.children[0].children[0]
+                .children
+                .is_empty()
+        );
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(result.warnings[0].path.as_ref().unwrap().indices(), &[0, 0]);
+        assert_eq!(
+            result.warnings[0].message,
+            "invalid TeX math at offset 7: expected '}'"
+        );
+    }
+}
@@ -0,0 +1,493 @@
+//! Moving images between files on disk and the document's resources.
+//!
+//! [`EmbedImages`] loads the local files that `image` nodes point at into
+//! [`Document::resources`], so that a document can be written as a single
+//! self-contained file. [`ExtractMedia`] does the reverse, like Pandoc's
+//! `--extract-media`: every resource is written to a directory and the nodes
+//! referring to it point at the written file instead.
+//!
+//! ```no_run
+//! use rescribe_core::Transformer;
+//! use rescribe_std::builder::doc;
+//! use rescribe_transforms::{EmbedImages, ExtractMedia};
+//!
+//! let document = doc(|d| d.para(|i| i.image("figures/plot.png", "Plot")));
+//! let embedded = EmbedImages::new("chapters").transform(document).unwrap();
+//! assert!(embedded.warnings.is_empty());
+//! assert_eq!(embedded.value.resources.len(), 1);
+//!
+//! let extracted = ExtractMedia::new("media").transform(embedded.value).unwrap();
+//! assert!(extracted.value.resources.is_empty());
+//! ```
+
+use rescribe_core::{
+    ConversionResult, Document, FidelityWarning, NodePath, Resource, ResourceId, Severity,
+    TransformError, Transformer, WarningKind,
+};
+use rescribe_std::{Node, node, prop};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Embed the local image files referenced by `image` nodes.
+///
+/// Relative URLs are resolved against the base directory; `file:` URLs and
+/// absolute paths are used as they are. Remote URLs, and images that
+/// already refer to a resource, are left alone. The MIME type is sniffed
+/// from the file's content, falling back to its extension.
+///
+/// Embedded images keep their URL, for writers that cannot embed. A file
+/// referenced several times is embedded once. Files that cannot be read
+/// are reported as warnings.
+#[derive(Debug, Clone)]
+pub struct EmbedImages {
+    base: PathBuf,
+}
+
+impl EmbedImages {
+    /// Create a transform resolving relative URLs against `base`, usually
+    /// the directory of the input file.
+    pub fn new(base: impl Into<PathBuf>) -> Self {
+        Self { base: base.into() }
+    }
+
+    /// The file an image URL refers to, if it is local.
+    fn local_path(&self, url: &str) -> Option<PathBuf> {
+        let path = match url.strip_prefix("file://") {
+            Some(path) => path,
+            None if crate::links::is_relative(url) || url.starts_with('/') => url,
+            // A Windows drive letter, which looks like a scheme
+            None if url.as_bytes().get(1) == Some(&b':') => url,
+            None => return None,
+        };
+        let (path, _) = crate::links::split_suffix(path);
+        let path = PathBuf::from(percent_decode(path));
+        Some(if path.is_absolute() {
+            path
+        } else {
+            self.base.join(path)
+        })
+    }
+}
+
+impl Transformer for EmbedImages {
+    fn name(&self) -> &str {
+        "embed_images"
+    }
+
+    fn transform(&self, mut doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        let mut embedded: HashMap<PathBuf, ResourceId> = HashMap::new();
+        let mut warnings = Vec::new();
+        let mut resources = std::mem::take(&mut doc.resources);
+
+        let mut embed = |node: &mut Node, path: NodePath| {
+            if node.kind.as_str() != node::IMAGE || node.props.get_str(prop::RESOURCE_ID).is_some()
+            {
+                return;
+            }
+            let Some(url) = node.props.get_str(prop::URL) else {
+                return;
+            };
+            let Some(file) = self.local_path(url) else {
+                return;
+            };
+            let id = match embedded.get(&file) {
+                Some(id) => id.clone(),
+                None => match std::fs::read(&file) {
+                    Ok(data) => {
+                        let mime_type = sniff_mime_type(&data)
+                            .or_else(|| mime_type_for_path(&file))
+                            .unwrap_or("application/octet-stream");
+                        let mut resource = Resource::new(mime_type, data);
+                        if let Some(name) = file.file_name() {
+                            resource = resource.with_name(name.to_string_lossy());
+                        }
+                        let id = ResourceId::new();
+                        resources.insert(id.clone(), resource);
+                        embedded.insert(file, id.clone());
+                        id
+                    }
+                    Err(e) => {
+                        let warning = FidelityWarning::new(
+                            Severity::Minor,
+                            WarningKind::ResourceFailed(ResourceId::from_string(url)),
+                            format!("cannot embed image '{url}': {e}"),
+                        )
+                        .at_path(path);
+                        warnings.push(match node.span {
+                            Some(span) => warning.at(span),
+                            None => warning,
+                        });
+                        return;
+                    }
+                },
+            };
+            node.props.set(prop::RESOURCE_ID, id.as_str());
+        };
+        walk_with_path(&mut doc.content, NodePath::root(), &mut embed);
+
+        doc.resources = resources;
+        Ok(ConversionResult::with_warnings(doc, warnings))
+    }
+}
+
+/// Write every resource to a directory and point nodes at the files.
+///
+/// Files are named after the resource's original name when it has one that
+/// no other resource uses, and after its ID otherwise. Either is reduced to
+/// a single file name, so resources are only ever written inside the
+/// directory. Nodes referring to a resource get the written file's path as
+/// their URL, the directory included and percent-encoded, so a relative
+/// directory should be given relative to the output file. The resources are
+/// then removed from the document.
+#[derive(Debug, Clone)]
+pub struct ExtractMedia {
+    dir: PathBuf,
+}
+
+impl ExtractMedia {
+    /// Create a transform writing resources to `dir`, which is created if
+    /// needed.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// A file name for each resource, in a stable order.
+    fn file_names(doc: &Document) -> BTreeMap<&ResourceId, String> {
+        // Generated IDs count up, so order them by length first to keep
+        // `res_2` before `res_10`.
+        let mut ids: Vec<&ResourceId> = doc.resources.keys().collect();
+        ids.sort_by_key(|id| (id.as_str().len(), *id));
+        let mut taken = HashSet::new();
+        let mut names = BTreeMap::new();
+        for id in ids {
+            let resource = &doc.resources[id];
+            let original = resource
+                .name
+                .as_deref()
+                .and_then(|name| Path::new(name).file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+                .filter(|name| !name.starts_with('.'));
+            let name = match original {
+                Some(name) if !taken.contains(&name) => name,
+                _ => {
+                    let stem = id.safe_stem();
+                    let extension = extension_for(&resource.mime_type);
+                    let mut name = format!("{stem}.{extension}");
+                    let mut n = 1;
+                    while taken.contains(&name) {
+                        n += 1;
+                        name = format!("{stem}-{n}.{extension}");
+                    }
+                    name
+                }
+            };
+            taken.insert(name.clone());
+            names.insert(id, name);
+        }
+        names
+    }
+}
+
+impl Transformer for ExtractMedia {
+    fn name(&self) -> &str {
+        "extract_media"
+    }
+
+    fn transform(&self, mut doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        if doc.resources.is_empty() {
+            return Ok(ConversionResult::ok(doc));
+        }
+        let failed = |e: std::io::Error| {
+            TransformError::Failed(format!(
+                "cannot extract media to {}: {e}",
+                self.dir.display()
+            ))
+        };
+        std::fs::create_dir_all(&self.dir).map_err(failed)?;
+
+        let mut urls = HashMap::new();
+        for (id, name) in Self::file_names(&doc) {
+            let file = self.dir.join(&name);
+            std::fs::write(&file, &doc.resources[id].data).map_err(failed)?;
+            urls.insert(
+                id.as_str().to_string(),
+                percent_encode_path(&file.to_string_lossy().replace('\\', "/")),
+            );
+        }
+
+        crate::walk_mut(&mut doc.content, &mut |node| {
+            if let Some(url) = node
+                .props
+                .get_str(prop::RESOURCE_ID)
+                .and_then(|id| urls.get(id))
+            {
+                node.props.set(prop::URL, url.as_str());
+                node.props.remove(prop::RESOURCE_ID);
+            }
+        });
+        doc.resources.clear();
+        Ok(ConversionResult::ok(doc))
+    }
+}
+
+fn walk_with_path(node: &mut Node, path: NodePath, f: &mut impl FnMut(&mut Node, NodePath)) {
+    f(node, path.clone());
+    for (i, child) in node.children.iter_mut().enumerate() {
+        walk_with_path(child, path.child(i), f);
+    }
+}
+
+/// Escape a path for use as a URL: everything but unreserved characters,
+/// sub-delimiters, `:`, `@` and the `/` between segments becomes `%XX`.
+fn percent_encode_path(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@/".contains(&byte) {
+            out.push(char::from(byte));
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+/// Decode `%XX` escapes, leaving malformed ones as they are.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// MIME type of a file, from its first bytes.
+fn sniff_mime_type(data: &[u8]) -> Option<&'static str> {
+    let head = &data[..data.len().min(512)];
+    Some(match head {
+        [0x89, b'P', b'N', b'G', ..] => "image/png",
+        [0xFF, 0xD8, 0xFF, ..] => "image/jpeg",
+        [b'G', b'I', b'F', b'8', ..] => "image/gif",
+        [
+            b'R',
+            b'I',
+            b'F',
+            b'F',
+            _,
+            _,
+            _,
+            _,
+            b'W',
+            b'E',
+            b'B',
+            b'P',
+            ..,
+        ] => "image/webp",
+        [b'B', b'M', ..] => "image/bmp",
+        [b'I', b'I', 0x2A, 0x00, ..] | [b'M', b'M', 0x00, 0x2A, ..] => "image/tiff",
+        [b'%', b'P', b'D', b'F', ..] => "application/pdf",
+        [b'%', b'!', b'P', b'S', ..] => "application/postscript",
+        _ if String::from_utf8_lossy(head).contains("<svg") => "image/svg+xml",
+        _ => return None,
+    })
+}
+
+/// MIME type of a file, from its extension.
+fn mime_type_for_path(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "tif" | "tiff" => "image/tiff",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "eps" | "ps" => "application/postscript",
+        _ => return None,
+    })
+}
+
+/// File extension for a MIME type.
+fn extension_for(mime_type: &str) -> &'static str {
+    match mime_type {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/bmp" => "bmp",
+        "image/tiff" => "tiff",
+        "image/svg+xml" => "svg",
+        "application/pdf" => "pdf",
+        "application/postscript" => "eps",
+        _ => "bin",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rescribe_std::builder::doc;
+
+    const PNG: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+    /// A fresh, empty directory for one test.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("rescribe-media-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_sniff_mime_type() {
+        assert_eq!(sniff_mime_type(PNG), Some("image/png"));
+        assert_eq!(sniff_mime_type(b"\xFF\xD8\xFF\xE0"), Some("image/jpeg"));
+        assert_eq!(sniff_mime_type(b"RIFF\0\0\0\0WEBPVP8"), Some("image/webp"));
+        assert_eq!(
+            sniff_mime_type(b"<?xml version=\"1.0\"?>\n<svg xmlns=\"\">"),
+            Some("image/svg+xml")
+        );
+        assert_eq!(sniff_mime_type(b"plain"), None);
+        assert_eq!(
+            mime_type_for_path(Path::new("a/B.JPEG")),
+            Some("image/jpeg")
+        );
+    }
+
+    #[test]
+    fn test_embed_images() {
+        let dir = scratch_dir("embed");
+        std::fs::create_dir(dir.join("img")).unwrap();
+        std::fs::write(dir.join("img/a plot.dat"), PNG).unwrap();
+
+        let document = doc(|d| {
+            d.para(|i| {
+                i.image("img/a%20plot.dat", "a")
+                    .image("./img/a plot.dat", "again")
+                    .image("missing.png", "missing")
+                    .image("https://example.com/b.png", "remote")
+            })
+        });
+        let result = EmbedImages::new(&dir).transform(document).unwrap();
+        let document = result.value;
+
+        assert_eq!(document.resources.len(), 1);
+        let (id, resource) = document.resources.iter().next().unwrap();
+        assert_eq!(resource.mime_type, "image/png");
+        assert_eq!(resource.name.as_deref(), Some("a plot.dat"));
+
+        let images = &document.content.children[0].children;
+        assert_eq!(
+            images[0].props.get_str(prop::RESOURCE_ID),
+            Some(id.as_str())
+        );
+        assert_eq!(images[0].props.get_str(prop::URL), Some("img/a%20plot.dat"));
+        assert_eq!(
+            images[1].props.get_str(prop::RESOURCE_ID),
+            Some(id.as_str())
+        );
+        assert!(images[2].props.get_str(prop::RESOURCE_ID).is_none());
+        assert!(images[3].props.get_str(prop::RESOURCE_ID).is_none());
+
+        assert_eq!(result.warnings.len(), 1);
+        assert!(
+            result.warnings[0]
+                .message
+                .starts_with("cannot embed image 'missing.png'")
+        );
+        assert_eq!(
+            result.warnings[0].path.as_ref().unwrap().to_string(),
+            "/0/2"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_extract_media() {
+        let dir = scratch_dir("extract");
+        let mut document = doc(|d| d.para(|i| i.text("x")));
+        let named = document.embed(Resource::png(PNG.to_vec()).with_name("../plot.png"));
+        let unnamed = document.embed(Resource::jpeg(vec![0xFF, 0xD8, 0xFF]));
+        let clash = document.embed(Resource::png(vec![1]).with_name("plot.png"));
+        let spaced = document.embed(Resource::png(vec![2]).with_name("a plot.png"));
+        let hashed = document.embed(Resource::png(vec![3]).with_name("fig#1.png"));
+        for id in [&named, &unnamed, &clash, &spaced, &hashed] {
+            let image = Node::new(node::IMAGE).prop(prop::RESOURCE_ID, id.as_str());
+            document.content.children[0].children.push(image);
+        }
+
+        let media = dir.join("media");
+        let result = ExtractMedia::new(&media).transform(document).unwrap().value;
+        assert!(result.resources.is_empty());
+
+        let urls: Vec<_> = result.content.children[0].children[1..]
+            .iter()
+            .map(|image| {
+                assert!(image.props.get_str(prop::RESOURCE_ID).is_none());
+                image.props.get_str(prop::URL).unwrap().to_string()
+            })
+            .collect();
+        let url = |name: &str| format!("{}/{name}", media.to_string_lossy());
+        assert_eq!(urls[0], url("plot.png"));
+        assert_eq!(urls[1], url(&format!("{}.jpg", unnamed.as_str())));
+        assert_eq!(urls[2], url(&format!("{}.png", clash.as_str())));
+        assert_eq!(urls[3], url("a%20plot.png"));
+        assert_eq!(urls[4], url("fig%231.png"));
+        assert_eq!(std::fs::read(&urls[0]).unwrap(), PNG);
+        assert_eq!(std::fs::read(&urls[2]).unwrap(), [1]);
+        assert_eq!(std::fs::read(media.join("a plot.png")).unwrap(), [2]);
+        assert_eq!(std::fs::read(percent_decode(&urls[4])).unwrap(), [3]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_extract_media_hostile_ids() {
+        let dir = scratch_dir("hostile");
+        let mut document = doc(|d| d.para(|i| i.text("x")));
+        for id in ["../../escaped", "/etc/passwd", "..", "a/b"] {
+            let id = ResourceId::from_string(id);
+            document
+                .resources
+                .insert(id.clone(), Resource::png(PNG.to_vec()));
+            let image = Node::new(node::IMAGE).prop(prop::RESOURCE_ID, id.as_str());
+            document.content.children[0].children.push(image);
+        }
+
+        let media = dir.join("media");
+        let result = ExtractMedia::new(&media).transform(document).unwrap().value;
+
+        let mut names: Vec<_> = result.content.children[0].children[1..]
+            .iter()
+            .map(|image| {
+                let url = PathBuf::from(image.props.get_str(prop::URL).unwrap());
+                assert_eq!(url.parent(), Some(media.as_path()));
+                assert!(std::fs::read(&url).is_ok());
+                url.file_name().unwrap().to_string_lossy().into_owned()
+            })
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            ["__.png", "______escaped.png", "_etc_passwd.png", "a_b.png"]
+        );
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
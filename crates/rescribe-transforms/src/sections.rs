@@ -0,0 +1,413 @@
+//! Section identifiers, numbering and nesting.
+//!
+//! Readers such as markdown, reStructuredText and AsciiDoc produce headings
+//! without identifiers, as flat siblings of the content they introduce.
+//! These transforms fill in what writers need for anchors, tables of
+//! contents and nested section elements:
+//!
+//! - [`AutoIdentifiers`] gives every heading a unique slug `id`.
+//! - [`NumberSections`] stores hierarchical numbers (`1`, `1.2`, `1.2.3`) in
+//!   the heading's `number` property.
+//! - [`Sectionize`] wraps each heading and its content in a section `div`.
+//!
+//! ```
+//! use rescribe_core::Transformer;
+//! use rescribe_std::builder::doc;
+//! use rescribe_std::NodeExt;
+//! use rescribe_transforms::{AutoIdentifiers, NumberSections, Pipeline, Sectionize};
+//!
+//! let document = doc(|d| {
+//!     d.heading(1, |i| i.text("Getting started"))
+//!         .para(|i| i.text("..."))
+//!         .heading(2, |i| i.text("Installation"))
+//! });
+//! let document = Pipeline::new()
+//!     .then(AutoIdentifiers::default())
+//!     .then(NumberSections)
+//!     .then(Sectionize)
+//!     .transform(document)
+//!     .unwrap()
+//!     .value;
+//!
+//! let section = document.content.children[0].as_section().unwrap();
+//! assert_eq!(section.id(), Some("getting-started"));
+//! let nested = section.body()[1].as_section().unwrap();
+//! assert_eq!(nested.heading().unwrap().number(), Some("1.1"));
+//! ```
+
+use rescribe_core::{ConversionResult, Document, TransformError, Transformer};
+use rescribe_std::{Node, NodeExt, class, node, prop};
+use std::collections::HashSet;
+
+/// Algorithm used to turn heading text into an identifier.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SlugStyle {
+    /// Pandoc's `auto_identifiers`: keep alphanumerics, `_`, `-` and `.`,
+    /// join words with `-`, lowercase, and drop everything before the first
+    /// letter.
+    #[default]
+    Pandoc,
+    /// GitHub's heading anchors: lowercase, keep letters, digits, `_` and
+    /// `-`, and turn each space into `-`.
+    GitHub,
+}
+
+impl SlugStyle {
+    /// Slug for `text`. May be empty.
+    pub fn slug(&self, text: &str) -> String {
+        match self {
+            SlugStyle::Pandoc => {
+                let kept: String = text
+                    .chars()
+                    .filter(|c| c.is_alphanumeric() || c.is_whitespace() || "_-.".contains(*c))
+                    .collect();
+                let joined = kept.split_whitespace().collect::<Vec<_>>().join("-");
+                joined
+                    .trim_start_matches(|c: char| !c.is_alphabetic())
+                    .to_lowercase()
+            }
+            SlugStyle::GitHub => text
+                .trim()
+                .to_lowercase()
+                .chars()
+                .filter_map(|c| match c {
+                    ' ' => Some('-'),
+                    c if c.is_alphanumeric() || c == '_' || c == '-' => Some(c),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Give headings without an `id` a slug of their text.
+///
+/// Identifiers are unique within the document: a slug that is already taken,
+/// by an earlier heading or by any existing `id`, gets a `-1`, `-2`, ...
+/// suffix. Headings whose slug is empty get `section`.
+#[derive(Debug, Clone, Default)]
+pub struct AutoIdentifiers {
+    style: SlugStyle,
+    prefix: String,
+}
+
+impl AutoIdentifiers {
+    /// Create an identifier generator using `style`.
+    pub fn new(style: SlugStyle) -> Self {
+        Self {
+            style,
+            prefix: String::new(),
+        }
+    }
+
+    /// Prepend `prefix` to every generated identifier.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    fn identifier(&self, heading: &Node, taken: &mut HashSet<String>) -> String {
+        let mut base = self.style.slug(&crate::stringify(heading));
+        if base.is_empty() {
+            base = "section".to_string();
+        }
+        let base = format!("{}{}", self.prefix, base);
+        let mut id = base.clone();
+        let mut n = 0;
+        while taken.contains(&id) {
+            n += 1;
+            id = format!("{base}-{n}");
+        }
+        taken.insert(id.clone());
+        id
+    }
+}
+
+impl Transformer for AutoIdentifiers {
+    fn name(&self) -> &str {
+        "auto_identifiers"
+    }
+
+    fn transform(&self, mut doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        let mut taken = HashSet::new();
+        crate::walk(&doc.content, &mut |node| {
+            if let Some(id) = node.props.get_str(prop::ID) {
+                taken.insert(id.to_string());
+            }
+        });
+        crate::walk_mut(&mut doc.content, &mut |node| {
+            if let Some(heading) = node.as_heading()
+                && heading.id().is_none()
+            {
+                let id = self.identifier(node, &mut taken);
+                node.props.set(prop::ID, id);
+            }
+        });
+        Ok(ConversionResult::ok(doc))
+    }
+}
+
+/// Number section headings hierarchically (`1`, `1.2`, `1.2.3`).
+///
+/// The number is stored in the heading's `number` property. Only headings
+/// at the top level or inside `div`s are section headings; those in block
+/// quotes, lists and the like are left alone, as are headings with the
+/// `unnumbered` class, which do not advance the count either. Skipped levels
+/// count as zero, so a level 3 heading right after a level 1 heading is
+/// `1.0.1`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NumberSections;
+
+impl NumberSections {
+    fn number(node: &mut Node, counters: &mut [u32; 6]) {
+        for child in &mut node.children {
+            match child.kind.as_str() {
+                node::HEADING if !child.has_class(class::UNNUMBERED) => {
+                    let level = child.as_heading().map_or(1, |h| h.level()) as usize;
+                    counters[level - 1] += 1;
+                    counters[level..].fill(0);
+                    let number: Vec<_> = counters[..level].iter().map(u32::to_string).collect();
+                    child.props.set(prop::NUMBER, number.join("."));
+                }
+                node::DIV => Self::number(child, counters),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Transformer for NumberSections {
+    fn name(&self) -> &str {
+        "number_sections"
+    }
+
+    fn transform(&self, mut doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        Self::number(&mut doc.content, &mut [0; 6]);
+        Ok(ConversionResult::ok(doc))
+    }
+}
+
+/// Wrap each heading and the content it introduces in a section `div`.
+///
+/// A section runs up to the next heading of the same or a higher level, so
+/// deeper headings become nested sections. Section divs have the classes
+/// `section level<n>` and a `level` property; the heading keeps its `id`.
+/// Headings inside other `div`s are sectionized within that div, and
+/// existing sections are left as they are, so the transform is idempotent.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sectionize;
+
+impl Sectionize {
+    fn sectionize(blocks: Vec<Node>) -> Vec<Node> {
+        // Open sections, outermost first.
+        let mut open: Vec<Node> = Vec::new();
+        let mut out = Vec::new();
+
+        fn close(open: &mut Vec<Node>, out: &mut Vec<Node>, level: u8) {
+            while let Some(section) = open.pop_if(|s| s.as_section().unwrap().level() >= level) {
+                match open.last_mut() {
+                    Some(parent) => parent.children.push(section),
+                    None => out.push(section),
+                }
+            }
+        }
+
+        for mut block in blocks {
+            if let Some(heading) = block.as_heading() {
+                let level = heading.level();
+                close(&mut open, &mut out, level);
+                open.push(
+                    Node::new(node::DIV)
+                        .prop(prop::CLASSES, format!("{} level{level}", class::SECTION))
+                        .prop(prop::LEVEL, level as i64)
+                        .child(block),
+                );
+                continue;
+            }
+
+            if let Some(section) = block.as_section() {
+                let level = section.level();
+                let skip = section.node().children.len() - section.body().len();
+                let body = block.children.split_off(skip);
+                block.children.extend(Self::sectionize(body));
+                close(&mut open, &mut out, level);
+            } else if block.kind.as_str() == node::DIV {
+                block.children = Self::sectionize(std::mem::take(&mut block.children));
+            }
+            match open.last_mut() {
+                Some(parent) => parent.children.push(block),
+                None => out.push(block),
+            }
+        }
+        close(&mut open, &mut out, 1);
+        out
+    }
+}
+
+impl Transformer for Sectionize {
+    fn name(&self) -> &str {
+        "sectionize"
+    }
+
+    fn transform(&self, mut doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        doc.content.children = Self::sectionize(std::mem::take(&mut doc.content.children));
+        Ok(ConversionResult::ok(doc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rescribe_std::builder::doc;
+
+    fn ids(doc: &Document) -> Vec<String> {
+        let mut ids = Vec::new();
+        crate::walk(&doc.content, &mut |node| {
+            if let Some(id) = node.as_heading().and_then(|h| h.id()) {
+                ids.push(id.to_string());
+            }
+        });
+        ids
+    }
+
+    #[test]
+    fn test_slug_styles() {
+        let pandoc = SlugStyle::Pandoc;
+        assert_eq!(
+            pandoc.slug("Heading  identifiers in HTML"),
+            "heading-identifiers-in-html"
+        );
+        assert_eq!(pandoc.slug("Maître d'hôtel"), "maître-dhôtel");
+        assert_eq!(pandoc.slug("*Dogs*?--in *my* house?"), "dogs--in-my-house");
+        assert_eq!(pandoc.slug("3. Applications"), "applications");
+        assert_eq!(pandoc.slug("v1.2 notes"), "v1.2-notes");
+        assert_eq!(pandoc.slug("33"), "");
+
+        let github = SlugStyle::GitHub;
+        assert_eq!(github.slug("Hello, World!"), "hello-world");
+        assert_eq!(github.slug("3. Applications"), "3-applications");
+        assert_eq!(github.slug("a  b_c"), "a--b_c");
+        assert_eq!(github.slug("v1.2 notes"), "v12-notes");
+    }
+
+    #[test]
+    fn test_auto_identifiers_dedupe() {
+        let mut document = doc(|d| {
+            d.heading(1, |i| i.text("Intro"))
+                .heading(2, |i| i.text("Intro"))
+                .heading(2, |i| i.strong(|i| i.text("Intro")))
+                .heading(2, |i| i.text("Kept"))
+                .heading(2, |i| i.text("?"))
+                .blockquote(|q| q.heading(3, |i| i.text("Quoted")))
+        });
+        document.content.children[3].props.set(prop::ID, "intro-2");
+
+        let result = AutoIdentifiers::default()
+            .transform(document)
+            .unwrap()
+            .value;
+        assert_eq!(
+            ids(&result),
+            [
+                "intro", "intro-1", "intro-3", "intro-2", "section", "quoted"
+            ]
+        );
+
+        let document = doc(|d| d.heading(1, |i| i.text("3 Wishes")));
+        let result = AutoIdentifiers::new(SlugStyle::GitHub)
+            .with_prefix("doc-")
+            .transform(document)
+            .unwrap()
+            .value;
+        assert_eq!(ids(&result), ["doc-3-wishes"]);
+    }
+
+    #[test]
+    fn test_number_sections() {
+        let mut document = doc(|d| {
+            d.heading(1, |i| i.text("A"))
+                .heading(2, |i| i.text("A.1"))
+                .heading(2, |i| i.text("Unnumbered"))
+                .heading(2, |i| i.text("A.2"))
+                .heading(1, |i| i.text("B"))
+                .heading(3, |i| i.text("B.0.1"))
+                .blockquote(|q| q.heading(1, |i| i.text("Quoted")))
+        });
+        document.content.children[2]
+            .props
+            .set(prop::CLASSES, class::UNNUMBERED);
+
+        let result = NumberSections.transform(document).unwrap().value;
+        let numbers: Vec<_> = result
+            .content
+            .children
+            .iter()
+            .map(|n| n.as_heading().and_then(|h| h.number()))
+            .collect();
+        assert_eq!(
+            numbers,
+            [
+                Some("1"),
+                Some("1.1"),
+                None,
+                Some("1.2"),
+                Some("2"),
+                Some("2.0.1"),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sectionize_nesting() {
+        let document = doc(|d| {
+            d.para(|i| i.text("preamble"))
+                .heading(1, |i| i.text("A"))
+                .para(|i| i.text("a"))
+                .heading(2, |i| i.text("A.1"))
+                .para(|i| i.text("a1"))
+                .heading(3, |i| i.text("A.1.1"))
+                .heading(2, |i| i.text("A.2"))
+                .heading(1, |i| i.text("B"))
+        });
+        let result = Sectionize.transform(document).unwrap().value;
+
+        let top = &result.content.children;
+        assert_eq!(top.len(), 3);
+        assert_eq!(top[0].kind.as_str(), node::PARAGRAPH);
+        let a = top[1].as_section().unwrap();
+        assert_eq!(a.level(), 1);
+        assert!(top[1].has_class("level1"));
+        assert_eq!(a.body().len(), 3);
+        let a1 = a.body()[1].as_section().unwrap();
+        assert_eq!(a1.level(), 2);
+        assert_eq!(a1.body().len(), 2);
+        assert_eq!(a1.body()[1].as_section().unwrap().level(), 3);
+        assert!(a.body()[2].as_section().is_some());
+        assert!(top[2].as_section().unwrap().body().is_empty());
+
+        // Sectionizing again changes nothing.
+        let again = Sectionize.transform(result.clone()).unwrap().value;
+        let edits = rescribe_core::diff(&result, &again, &Default::default());
+        assert!(edits.is_empty(), "{edits:?}");
+    }
+
+    #[test]
+    fn test_sectionize_inside_div() {
+        let mut document = doc(|d| {
+            d.heading(1, |i| i.text("Outer"))
+                .heading(2, |i| i.text("Inner"))
+                .para(|i| i.text("x"))
+                .para(|i| i.text("after"))
+        });
+        let inner: Vec<_> = document.content.children.drain(1..3).collect();
+        let div = Node::new(node::DIV).children(inner);
+        document.content.children.insert(1, div);
+        let result = Sectionize.transform(document).unwrap().value;
+        let outer = result.content.children[0].as_section().unwrap();
+        assert_eq!(outer.body().len(), 2);
+        let div = &outer.body()[0];
+        assert!(div.as_section().is_none());
+        assert_eq!(div.children[0].as_section().unwrap().body().len(), 1);
+    }
+}
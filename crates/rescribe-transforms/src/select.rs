@@ -0,0 +1,828 @@
+//! CSS-like selectors over node trees.
+//!
+//! Selectors match nodes by kind, properties and position:
+//!
+//! | Syntax | Matches |
+//! |--------|---------|
+//! | `heading`, `math:fraction`, `*` | node kind (any kind) |
+//! | `[url]` | nodes with a `url` property |
+//! | `[level=2]`, `[level!=2]` | property equal / not equal to a value |
+//! | `[url^="http"]`, `[url$=".png"]`, `[url*="example"]` | prefix, suffix, substring |
+//! | `[classes~=note]`, `[lang\|=en]` | word in list, `en` or `en-*` |
+//! | `.note`, `#intro` | class in `classes`, `id` equal |
+//! | `:first-child`, `:last-child`, `:only-child` | position among siblings |
+//! | `:nth-child(2)`, `:nth-child(2n+1)`, `:nth-last-child(odd)` | position formulas |
+//! | `:empty`, `:root`, `:not(...)` | no children, the root, negation |
+//! | `a > b`, `a b`, `a + b`, `a ~ b` | child, descendant, next sibling, later sibling |
+//! | `a, b` | either selector |
+//!
+//! Property values compare as text, so `[level=2]` matches the integer `2`
+//! and `[checked=true]` matches the boolean `true`.
+//!
+//! ```
+//! use rescribe_std::builder::doc;
+//! use rescribe_transforms::select;
+//!
+//! let document = doc(|d| d.heading(2, |i| i.text("Intro")).para(|i| i.text("Body")));
+//! let matches = select(&document.content, "heading[level=2] > text").unwrap();
+//! assert_eq!(matches.len(), 1);
+//! assert_eq!(matches[0].0.to_string(), "/0/0");
+//! ```
+
+use rescribe_core::NodePath;
+use rescribe_std::{Node, PropValue, prop};
+use std::str::FromStr;
+
+/// Error from parsing a selector.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid selector at offset {position}: {message}")]
+pub struct SelectorError {
+    /// Byte offset into the selector text.
+    pub position: usize,
+    pub message: String,
+}
+
+/// A parsed selector (or comma-separated list of selectors).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    alternatives: Vec<Complex>,
+}
+
+/// Compounds joined by combinators; `combinators[i]` sits between
+/// `compounds[i]` and `compounds[i + 1]`.
+#[derive(Debug, Clone, PartialEq)]
+struct Complex {
+    compounds: Vec<Compound>,
+    combinators: Vec<Combinator>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    Descendant,
+    Child,
+    NextSibling,
+    LaterSibling,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Compound {
+    /// Required node kind; `None` for `*` or no kind.
+    kind: Option<String>,
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Has(String),
+    Attr {
+        name: String,
+        op: AttrOp,
+        value: String,
+    },
+    Class(String),
+    Id(String),
+    /// Matches when the 1-based position equals `a*n + b` for some `n >= 0`.
+    Nth {
+        a: i64,
+        b: i64,
+        from_end: bool,
+    },
+    Empty,
+    Root,
+    Not(Selector),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttrOp {
+    Equals,
+    NotEquals,
+    Prefix,
+    Suffix,
+    Contains,
+    Word,
+    Lang,
+}
+
+/// A node with its index among its siblings, from the root down.
+type Chain<'a> = [(&'a Node, usize)];
+
+impl Selector {
+    /// Parse a selector.
+    pub fn parse(input: &str) -> Result<Self, SelectorError> {
+        let mut parser = SelectorParser { input, pos: 0 };
+        let selector = parser.parse_list()?;
+        parser.skip_whitespace();
+        if parser.pos < input.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(selector)
+    }
+
+    /// Find all matching nodes under (and including) `root`, in document order.
+    pub fn select<'a>(&self, root: &'a Node) -> Vec<(NodePath, &'a Node)> {
+        let mut matches = Vec::new();
+        let mut chain = vec![(root, 0)];
+        self.collect(&mut chain, &mut matches);
+        matches
+    }
+
+    /// Paths of all matching nodes under (and including) `root`.
+    pub fn select_paths(&self, root: &Node) -> Vec<NodePath> {
+        self.select(root)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect()
+    }
+
+    /// Call `f` on every matching node, returning the number of matches.
+    ///
+    /// Nodes are visited in reverse document order, so `f` may restructure
+    /// the subtree of the node it is given without invalidating the paths
+    /// of nodes still to be visited.
+    pub fn select_mut<F>(&self, root: &mut Node, mut f: F) -> usize
+    where
+        F: FnMut(&NodePath, &mut Node),
+    {
+        let paths = self.select_paths(root);
+        for path in paths.iter().rev() {
+            if let Some(node) = root.at_mut(path) {
+                f(path, node);
+            }
+        }
+        paths.len()
+    }
+
+    /// Check whether the node at `path` under `root` matches.
+    pub fn matches(&self, root: &Node, path: &NodePath) -> bool {
+        let mut chain = vec![(root, 0)];
+        for &index in path.indices() {
+            let Some(child) = chain[chain.len() - 1].0.children.get(index) else {
+                return false;
+            };
+            chain.push((child, index));
+        }
+        self.matches_chain(&chain)
+    }
+
+    fn collect<'a>(
+        &self,
+        chain: &mut Vec<(&'a Node, usize)>,
+        matches: &mut Vec<(NodePath, &'a Node)>,
+    ) {
+        let node = chain[chain.len() - 1].0;
+        if self.matches_chain(chain) {
+            let path = NodePath::from(chain[1..].iter().map(|&(_, i)| i).collect::<Vec<_>>());
+            matches.push((path, node));
+        }
+        for (index, child) in node.children.iter().enumerate() {
+            chain.push((child, index));
+            self.collect(chain, matches);
+            chain.pop();
+        }
+    }
+
+    fn matches_chain(&self, chain: &Chain) -> bool {
+        self.alternatives
+            .iter()
+            .any(|complex| complex.matches(complex.compounds.len() - 1, chain))
+    }
+}
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Find all nodes under (and including) `root` matching `selector`.
+pub fn select<'a>(
+    root: &'a Node,
+    selector: &str,
+) -> Result<Vec<(NodePath, &'a Node)>, SelectorError> {
+    Ok(Selector::parse(selector)?.select(root))
+}
+
+/// Call `f` on every node under (and including) `root` matching `selector`,
+/// returning the number of matches. See [`Selector::select_mut`].
+pub fn select_mut<F>(root: &mut Node, selector: &str, f: F) -> Result<usize, SelectorError>
+where
+    F: FnMut(&NodePath, &mut Node),
+{
+    Ok(Selector::parse(selector)?.select_mut(root, f))
+}
+
+impl Complex {
+    /// Match `compounds[..=index]` with the last one against the end of `chain`.
+    fn matches(&self, index: usize, chain: &Chain) -> bool {
+        if !self.compounds[index].matches(chain) {
+            return false;
+        }
+        if index == 0 {
+            return true;
+        }
+
+        let len = chain.len();
+        match self.combinators[index - 1] {
+            Combinator::Child => len > 1 && self.matches(index - 1, &chain[..len - 1]),
+            Combinator::Descendant => (1..len)
+                .rev()
+                .any(|end| self.matches(index - 1, &chain[..end])),
+            Combinator::NextSibling => {
+                let position = chain[len - 1].1;
+                position > 0 && self.matches(index - 1, &with_sibling(chain, position - 1))
+            }
+            Combinator::LaterSibling => {
+                let position = chain[len - 1].1;
+                (0..position).any(|sibling| self.matches(index - 1, &with_sibling(chain, sibling)))
+            }
+        }
+    }
+}
+
+/// `chain` with its last node replaced by the sibling at `index`.
+fn with_sibling<'a>(chain: &Chain<'a>, index: usize) -> Vec<(&'a Node, usize)> {
+    let len = chain.len();
+    let parent = chain[len - 2].0;
+    let mut sibling = chain[..len - 1].to_vec();
+    sibling.push((&parent.children[index], index));
+    sibling
+}
+
+impl Compound {
+    fn matches(&self, chain: &Chain) -> bool {
+        let (node, _) = chain[chain.len() - 1];
+        if let Some(kind) = &self.kind
+            && node.kind.as_str() != kind
+        {
+            return false;
+        }
+        self.filters.iter().all(|filter| filter.matches(chain))
+    }
+}
+
+impl Filter {
+    fn matches(&self, chain: &Chain) -> bool {
+        let (node, index) = chain[chain.len() - 1];
+        match self {
+            Filter::Has(name) => node.props.contains(name),
+            Filter::Attr { name, op, value } => node
+                .props
+                .get(name)
+                .is_some_and(|prop| attr_matches(prop, *op, value)),
+            Filter::Class(class) => match node.props.get(prop::CLASSES) {
+                Some(PropValue::String(classes)) => classes.split_whitespace().any(|c| c == class),
+                Some(PropValue::List(classes)) => classes
+                    .iter()
+                    .any(|c| matches!(c, PropValue::String(c) if c == class)),
+                _ => false,
+            },
+            Filter::Id(id) => node.props.get_str(prop::ID) == Some(id.as_str()),
+            Filter::Nth { a, b, from_end } => {
+                let siblings = match chain.len() {
+                    1 => 1,
+                    len => chain[len - 2].0.children.len(),
+                };
+                let position = if *from_end {
+                    siblings - index
+                } else {
+                    index + 1
+                };
+                nth_matches(*a, *b, position as i64)
+            }
+            Filter::Empty => node.children.is_empty(),
+            Filter::Root => chain.len() == 1,
+            Filter::Not(selector) => !selector.matches_chain(chain),
+        }
+    }
+}
+
+fn nth_matches(a: i64, b: i64, position: i64) -> bool {
+    if a == 0 {
+        return position == b;
+    }
+    let offset = position - b;
+    offset % a == 0 && offset / a >= 0
+}
+
+/// Text form of a scalar property value.
+fn prop_text(value: &PropValue) -> Option<String> {
+    match value {
+        PropValue::String(s) => Some(s.clone()),
+        PropValue::Int(i) => Some(i.to_string()),
+        PropValue::Float(f) => Some(f.to_string()),
+        PropValue::Bool(b) => Some(b.to_string()),
+        PropValue::List(_) | PropValue::Map(_) => None,
+    }
+}
+
+fn attr_matches(prop: &PropValue, op: AttrOp, value: &str) -> bool {
+    if op == AttrOp::Word {
+        return match prop {
+            PropValue::List(items) => items
+                .iter()
+                .any(|item| prop_text(item).as_deref() == Some(value)),
+            other => {
+                prop_text(other).is_some_and(|text| text.split_whitespace().any(|w| w == value))
+            }
+        };
+    }
+
+    let Some(text) = prop_text(prop) else {
+        return op == AttrOp::NotEquals;
+    };
+    match op {
+        AttrOp::Equals => text == value,
+        AttrOp::NotEquals => text != value,
+        AttrOp::Prefix => !value.is_empty() && text.starts_with(value),
+        AttrOp::Suffix => !value.is_empty() && text.ends_with(value),
+        AttrOp::Contains => !value.is_empty() && text.contains(value),
+        AttrOp::Lang => {
+            text == value
+                || text
+                    .strip_prefix(value)
+                    .is_some_and(|rest| rest.starts_with('-'))
+        }
+        AttrOp::Word => unreachable!(),
+    }
+}
+
+/// Pseudo-classes recognised after a `:`; anything else after a kind name is
+/// treated as part of a namespaced kind such as `math:fraction`.
+const PSEUDO_CLASSES: &[&str] = &[
+    "first-child",
+    "last-child",
+    "only-child",
+    "nth-child",
+    "nth-last-child",
+    "empty",
+    "root",
+    "not",
+];
+
+struct SelectorParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl SelectorParser<'_> {
+    fn error(&self, message: impl Into<String>) -> SelectorError {
+        SelectorError {
+            position: self.pos,
+            message: message.into(),
+        }
+    }
+
+    fn rest(&self) -> &str {
+        &self.input[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SelectorError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{c}'")))
+        }
+    }
+
+    /// Skip whitespace, returning whether any was skipped.
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    fn parse_list(&mut self) -> Result<Selector, SelectorError> {
+        let mut alternatives = Vec::new();
+        loop {
+            self.skip_whitespace();
+            alternatives.push(self.parse_complex()?);
+            self.skip_whitespace();
+            if !self.eat(',') {
+                return Ok(Selector { alternatives });
+            }
+        }
+    }
+
+    fn parse_complex(&mut self) -> Result<Complex, SelectorError> {
+        let mut compounds = vec![self.parse_compound()?];
+        let mut combinators = Vec::new();
+        loop {
+            let spaced = self.skip_whitespace();
+            let combinator = match self.peek() {
+                None | Some(',') | Some(')') => break,
+                Some('>') => Combinator::Child,
+                Some('+') => Combinator::NextSibling,
+                Some('~') => Combinator::LaterSibling,
+                Some(_) if spaced => Combinator::Descendant,
+                Some(_) => return Err(self.error("unexpected character")),
+            };
+            if combinator != Combinator::Descendant {
+                self.pos += 1;
+                self.skip_whitespace();
+            }
+            combinators.push(combinator);
+            compounds.push(self.parse_compound()?);
+        }
+        Ok(Complex {
+            compounds,
+            combinators,
+        })
+    }
+
+    fn parse_compound(&mut self) -> Result<Compound, SelectorError> {
+        let start = self.pos;
+        let kind = if self.eat('*') {
+            None
+        } else if self.peek().is_some_and(is_ident_char) {
+            Some(self.parse_kind())
+        } else {
+            None
+        };
+
+        let mut filters = Vec::new();
+        loop {
+            match self.peek() {
+                Some('[') => filters.push(self.parse_attr()?),
+                Some('.') => {
+                    self.pos += 1;
+                    filters.push(Filter::Class(self.parse_ident("class name")?));
+                }
+                Some('#') => {
+                    self.pos += 1;
+                    filters.push(Filter::Id(self.parse_ident("id")?));
+                }
+                Some(':') => self.parse_pseudo(&mut filters)?,
+                _ => break,
+            }
+        }
+
+        if self.pos == start {
+            return Err(self.error("expected a selector"));
+        }
+        Ok(Compound { kind, filters })
+    }
+
+    /// A node kind, including `namespace:` prefixes.
+    fn parse_kind(&mut self) -> String {
+        let mut kind = self.take_ident().to_string();
+        while self.rest().starts_with(':') {
+            let after = &self.rest()[1..];
+            let word_len = after.find(|c| !is_ident_char(c)).unwrap_or(after.len());
+            let word = &after[..word_len];
+            if word.is_empty() || PSEUDO_CLASSES.contains(&word) {
+                break;
+            }
+            kind.push(':');
+            kind.push_str(word);
+            self.pos += 1 + word_len;
+        }
+        kind
+    }
+
+    fn take_ident(&mut self) -> &str {
+        let rest = &self.input[self.pos..];
+        let len = rest.find(|c| !is_ident_char(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn parse_ident(&mut self, what: &str) -> Result<String, SelectorError> {
+        let ident = self.take_ident();
+        if ident.is_empty() {
+            return Err(self.error(format!("expected {what}")));
+        }
+        Ok(ident.to_string())
+    }
+
+    fn parse_attr(&mut self) -> Result<Filter, SelectorError> {
+        self.expect('[')?;
+        self.skip_whitespace();
+        let mut name = self.parse_ident("property name")?;
+        // Allow namespaced property names such as `math:format`.
+        while self.rest().starts_with(':') {
+            self.pos += 1;
+            name.push(':');
+            name.push_str(&self.parse_ident("property name")?);
+        }
+        self.skip_whitespace();
+
+        if self.eat(']') {
+            return Ok(Filter::Has(name));
+        }
+
+        let ops = [
+            ("!=", AttrOp::NotEquals),
+            ("^=", AttrOp::Prefix),
+            ("$=", AttrOp::Suffix),
+            ("*=", AttrOp::Contains),
+            ("~=", AttrOp::Word),
+            ("|=", AttrOp::Lang),
+            ("=", AttrOp::Equals),
+        ];
+        let Some(&(token, op)) = ops.iter().find(|(token, _)| self.rest().starts_with(token))
+        else {
+            return Err(self.error("expected an operator or ']'"));
+        };
+        self.pos += token.len();
+        self.skip_whitespace();
+
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        self.expect(']')?;
+        Ok(Filter::Attr { name, op, value })
+    }
+
+    fn parse_value(&mut self) -> Result<String, SelectorError> {
+        let Some(quote) = self.peek().filter(|c| *c == '"' || *c == '\'') else {
+            let rest = self.rest();
+            let len = rest
+                .find(|c: char| c.is_whitespace() || c == ']')
+                .unwrap_or(rest.len());
+            if len == 0 {
+                return Err(self.error("expected a value"));
+            }
+            let value = rest[..len].to_string();
+            self.pos += len;
+            return Ok(value);
+        };
+
+        self.pos += 1;
+        let mut value = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    if let Some((_, escaped)) = chars.next() {
+                        value.push(escaped);
+                    }
+                }
+                c if c == quote => {
+                    self.pos += offset + 1;
+                    return Ok(value);
+                }
+                c => value.push(c),
+            }
+        }
+        self.pos = self.input.len();
+        Err(self.error("unterminated string"))
+    }
+
+    fn parse_pseudo(&mut self, filters: &mut Vec<Filter>) -> Result<(), SelectorError> {
+        self.expect(':')?;
+        let start = self.pos;
+        let name = self.parse_ident("pseudo-class")?;
+        let filter = match name.as_str() {
+            "first-child" => Filter::Nth {
+                a: 0,
+                b: 1,
+                from_end: false,
+            },
+            "last-child" => Filter::Nth {
+                a: 0,
+                b: 1,
+                from_end: true,
+            },
+            "only-child" => {
+                filters.push(Filter::Nth {
+                    a: 0,
+                    b: 1,
+                    from_end: false,
+                });
+                Filter::Nth {
+                    a: 0,
+                    b: 1,
+                    from_end: true,
+                }
+            }
+            "nth-child" | "nth-last-child" => {
+                self.expect('(')?;
+                let (a, b) = self.parse_nth()?;
+                self.expect(')')?;
+                Filter::Nth {
+                    a,
+                    b,
+                    from_end: name == "nth-last-child",
+                }
+            }
+            "empty" => Filter::Empty,
+            "root" => Filter::Root,
+            "not" => {
+                self.expect('(')?;
+                let selector = self.parse_list()?;
+                self.skip_whitespace();
+                self.expect(')')?;
+                Filter::Not(selector)
+            }
+            _ => {
+                self.pos = start;
+                return Err(self.error(format!("unknown pseudo-class ':{name}'")));
+            }
+        };
+        filters.push(filter);
+        Ok(())
+    }
+
+    /// Parse an `an+b` formula, `odd` or `even`.
+    fn parse_nth(&mut self) -> Result<(i64, i64), SelectorError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let len = self.rest().find(')').unwrap_or(self.rest().len());
+        let text: String = self.rest()[..len]
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        self.pos += len;
+
+        let invalid = || SelectorError {
+            position: start,
+            message: format!("invalid position formula '{text}'"),
+        };
+        let number = |s: &str| s.parse::<i64>().map_err(|_| invalid());
+
+        match text.as_str() {
+            "odd" => return Ok((2, 1)),
+            "even" => return Ok((2, 0)),
+            _ => {}
+        }
+        let Some((a, b)) = text.split_once('n') else {
+            return Ok((0, number(&text)?));
+        };
+        let a = match a {
+            "" | "+" => 1,
+            "-" => -1,
+            a => number(a)?,
+        };
+        let b = match b {
+            "" => 0,
+            b if b.starts_with(['+', '-']) => number(b.trim_start_matches('+'))?,
+            _ => return Err(invalid()),
+        };
+        Ok((a, b))
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rescribe_std::builder::doc;
+    use rescribe_std::node;
+
+    fn sample() -> Node {
+        let mut document = doc(|d| {
+            d.heading(1, |i| i.text("Title"))
+                .para(|i| {
+                    i.text("See ")
+                        .link("https://example.com", |i| i.text("site"))
+                        .text(" and ")
+                        .link("/local", |i| i.text("here"))
+                })
+                .heading(2, |i| i.text("Section"))
+                .para(|i| i.em(|i| i.text("End")))
+        });
+        document.content.children.push(
+            Node::new(node::DIV)
+                .prop(prop::CLASSES, "note warning")
+                .prop(prop::ID, "n1")
+                .child(Node::new(node::PARAGRAPH)),
+        );
+        document.content
+    }
+
+    fn paths(root: &Node, selector: &str) -> Vec<String> {
+        select(root, selector)
+            .unwrap()
+            .into_iter()
+            .map(|(path, _)| path.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_kind_and_property() {
+        let root = sample();
+        assert_eq!(paths(&root, "heading"), ["/0", "/2"]);
+        assert_eq!(paths(&root, "heading[level=2] > text"), ["/2/0"]);
+        assert_eq!(paths(&root, "heading[level!=2]"), ["/0"]);
+        assert_eq!(paths(&root, "link[url^=\"http\"]"), ["/1/1"]);
+        assert_eq!(paths(&root, "link[url$='/local']"), ["/1/3"]);
+        assert_eq!(paths(&root, "[url*=example]"), ["/1/1"]);
+        assert_eq!(paths(&root, "div[id]"), ["/4"]);
+    }
+
+    #[test]
+    fn test_class_and_id() {
+        let root = sample();
+        assert_eq!(paths(&root, "div.note"), ["/4"]);
+        assert_eq!(paths(&root, ".warning.note"), ["/4"]);
+        assert_eq!(paths(&root, "div.tip"), Vec::<String>::new());
+        assert_eq!(paths(&root, "#n1 > paragraph:empty"), ["/4/0"]);
+        assert_eq!(paths(&root, "[classes~=warning]"), ["/4"]);
+    }
+
+    #[test]
+    fn test_combinators() {
+        let root = sample();
+        assert_eq!(
+            paths(&root, "paragraph text"),
+            ["/1/0", "/1/1/0", "/1/2", "/1/3/0", "/3/0/0"]
+        );
+        assert_eq!(paths(&root, "paragraph > text"), ["/1/0", "/1/2"]);
+        assert_eq!(paths(&root, "heading + paragraph"), ["/1", "/3"]);
+        assert_eq!(paths(&root, "heading ~ div"), ["/4"]);
+        assert_eq!(paths(&root, "heading, div"), ["/0", "/2", "/4"]);
+    }
+
+    #[test]
+    fn test_positions() {
+        let root = sample();
+        assert_eq!(paths(&root, "paragraph > :nth-child(2)"), ["/1/1"]);
+        assert_eq!(paths(&root, "paragraph > :first-child"), ["/1/0", "/3/0"]);
+        assert_eq!(paths(&root, "document > :last-child"), ["/4"]);
+        assert_eq!(
+            paths(&root, "document > :nth-child(odd)"),
+            ["/0", "/2", "/4"]
+        );
+        assert_eq!(paths(&root, "document > :nth-last-child(2n)"), ["/1", "/3"]);
+        assert_eq!(paths(&root, "emphasis:only-child"), ["/3/0"]);
+        assert_eq!(paths(&root, ":root"), ["/"]);
+        assert_eq!(paths(&root, "document > :not(heading, paragraph)"), ["/4"]);
+    }
+
+    #[test]
+    fn test_namespaced_kind() {
+        let root = Node::new(node::DOCUMENT).child(
+            Node::new("math:fraction")
+                .child(Node::new("math:number"))
+                .child(Node::new("math:number")),
+        );
+        assert_eq!(
+            paths(&root, "math:fraction > math:number:last-child"),
+            ["/0/1"]
+        );
+    }
+
+    #[test]
+    fn test_select_mut() {
+        let mut root = sample();
+        let count = select_mut(&mut root, "link[url^=http]", |_, link| {
+            link.props.set(prop::URL, "https://example.org");
+        })
+        .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(
+            root.children[1].children[1].props.get_str(prop::URL),
+            Some("https://example.org")
+        );
+
+        // Removing children inside matches does not disturb earlier matches.
+        let removed = select_mut(&mut root, "paragraph", |_, p| p.children.clear()).unwrap();
+        assert_eq!(removed, 3);
+        assert!(root.children[1].children.is_empty());
+    }
+
+    #[test]
+    fn test_matches_path() {
+        let root = sample();
+        let selector: Selector = "heading > text".parse().unwrap();
+        assert!(selector.matches(&root, &NodePath::from(vec![2, 0])));
+        assert!(!selector.matches(&root, &NodePath::from(vec![1, 0])));
+        assert!(!selector.matches(&root, &NodePath::from(vec![9])));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (input, position) in [
+            ("", 0),
+            ("heading >", 9),
+            ("[url^]", 4),
+            ("link[url=\"x]", 12),
+            (":hover", 1),
+            (":nth-child(x)", 11),
+            ("a ) b", 2),
+        ] {
+            let err = Selector::parse(input).unwrap_err();
+            assert_eq!(err.position, position, "{input}: {err}");
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! Cutting a document into several.
+//!
+//! [`SplitAt`] cuts a document before every heading at or above a level,
+//! for outputs made of several files such as chunked HTML or EPUB.
+//!
+//! ```
+//! use rescribe_std::builder::doc;
+//! use rescribe_transforms::SplitAt;
+//!
+//! let document = doc(|d| {
+//!     d.para(|i| i.text("Preface"))
+//!         .heading(1, |i| i.text("One"))
+//!         .heading(2, |i| i.text("One point one"))
+//!         .heading(1, |i| i.text("Two"))
+//! });
+//! let chunks = SplitAt::new(1).split(document);
+//! assert_eq!(chunks.len(), 3);
+//! assert_eq!(chunks[1].content.children.len(), 2);
+//! ```
+
+use rescribe_core::{Document, ResourceId, ResourceMap};
+use rescribe_std::{Node, NodeExt, class, node, prop};
+use std::collections::HashSet;
+
+/// Split a document at headings of a given level or above.
+///
+/// Each chunk starts with such a heading, except for a first chunk holding
+/// any content before the first one. Sections made by
+/// [`Sectionize`](crate::Sectionize) are split the same way: a section at
+/// the split level starts a chunk, and sections above it are unwrapped so
+/// that their subsections can.
+///
+/// Every chunk gets the document's metadata and source information, and the
+/// resources it refers to, under their original IDs. Footnote definitions
+/// at the top level move to the chunks that reference them.
+#[derive(Debug, Clone)]
+pub struct SplitAt {
+    level: u8,
+}
+
+impl SplitAt {
+    /// Split before headings of `level` (1-6) or above.
+    pub fn new(level: u8) -> Self {
+        Self {
+            level: level.clamp(1, 6),
+        }
+    }
+
+    /// Cut `doc` into chunks. A document without headings to split at
+    /// yields a single chunk.
+    pub fn split(&self, doc: Document) -> Vec<Document> {
+        let mut blocks = Vec::new();
+        self.flatten(doc.content.children, &mut blocks);
+
+        // Footnote definitions follow their references.
+        let mut referenced = HashSet::new();
+        for block in &blocks {
+            crate::walk(block, &mut |node| {
+                if node.kind.as_str() == node::FOOTNOTE_REF
+                    && let Some(label) = node.props.get_str(prop::LABEL)
+                {
+                    referenced.insert(label.to_string());
+                }
+            });
+        }
+        let (notes, blocks): (Vec<Node>, Vec<Node>) = blocks.into_iter().partition(|block| {
+            block.kind.as_str() == node::FOOTNOTE_DEF
+                && block
+                    .props
+                    .get_str(prop::LABEL)
+                    .is_some_and(|label| referenced.contains(label))
+        });
+
+        let mut chunks: Vec<Vec<Node>> = vec![Vec::new()];
+        for block in blocks {
+            if self.starts_chunk(&block) && chunks.last().is_some_and(|c| !c.is_empty()) {
+                chunks.push(Vec::new());
+            }
+            chunks.last_mut().expect("a chunk").push(block);
+        }
+
+        chunks
+            .into_iter()
+            .map(|mut children| {
+                children.extend(notes_for(&children, &notes));
+                let content = Node {
+                    kind: doc.content.kind.clone(),
+                    props: doc.content.props.clone(),
+                    children,
+                    span: None,
+                };
+                Document {
+                    resources: resources_for(&content, &doc.resources),
+                    content,
+                    metadata: doc.metadata.clone(),
+                    source: doc.source.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Unwrap sections above the split level into `out`.
+    fn flatten(&self, blocks: Vec<Node>, out: &mut Vec<Node>) {
+        for block in blocks {
+            match block.as_section() {
+                Some(section) if section.level() < self.level => {
+                    self.flatten(block.children, out);
+                }
+                _ => out.push(block),
+            }
+        }
+    }
+
+    fn starts_chunk(&self, block: &Node) -> bool {
+        let level = match block.kind.as_str() {
+            node::HEADING => block.as_heading().map(|h| h.level()),
+            node::DIV if block.has_class(class::SECTION) => block.as_section().map(|s| s.level()),
+            _ => None,
+        };
+        level.is_some_and(|level| level <= self.level)
+    }
+}
+
+/// The definitions among `notes` referenced in `blocks`, in order of first
+/// reference.
+fn notes_for(blocks: &[Node], notes: &[Node]) -> Vec<Node> {
+    let mut labels = Vec::new();
+    for block in blocks {
+        crate::walk(block, &mut |node| {
+            if node.kind.as_str() == node::FOOTNOTE_REF
+                && let Some(label) = node.props.get_str(prop::LABEL)
+                && !labels.iter().any(|l| l == label)
+            {
+                labels.push(label.to_string());
+            }
+        });
+    }
+    labels
+        .iter()
+        .filter_map(|label| {
+            notes
+                .iter()
+                .find(|note| note.props.get_str(prop::LABEL) == Some(label))
+        })
+        .cloned()
+        .collect()
+}
+
+/// The resources of `all` that `content` refers to.
+fn resources_for(content: &Node, all: &ResourceMap) -> ResourceMap {
+    let mut resources = ResourceMap::new();
+    crate::walk(content, &mut |node| {
+        if let Some(id) = node.props.get_str(prop::RESOURCE_ID) {
+            let id = ResourceId::from_string(id);
+            if let Some(resource) = all.get(&id) {
+                resources.insert(id, resource.clone());
+            }
+        }
+    });
+    resources
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sectionize;
+    use rescribe_core::{Resource, Transformer};
+    use rescribe_std::builder::doc;
+
+    fn headings(chunk: &Document) -> Vec<String> {
+        let mut titles = Vec::new();
+        crate::walk(&chunk.content, &mut |node| {
+            if node.kind.as_str() == node::HEADING {
+                titles.push(crate::stringify(node));
+            }
+        });
+        titles
+    }
+
+    fn book() -> Document {
+        doc(|d| {
+            d.heading(1, |i| i.text("A"))
+                .para(|i| i.text("a").footnote_ref("n"))
+                .heading(2, |i| i.text("A.1"))
+                .heading(3, |i| i.text("A.1.1"))
+                .heading(2, |i| i.text("A.2"))
+                .heading(1, |i| i.text("B"))
+        })
+    }
+
+    #[test]
+    fn test_split_at_level() {
+        let chunks = SplitAt::new(2).split(book());
+        let titles: Vec<_> = chunks.iter().map(headings).collect();
+        assert_eq!(
+            titles,
+            [vec!["A"], vec!["A.1", "A.1.1"], vec!["A.2"], vec!["B"]]
+        );
+
+        let chunks = SplitAt::new(1).split(doc(|d| d.para(|i| i.text("x"))));
+        assert_eq!(chunks.len(), 1);
+    }
+
+    #[test]
+    fn test_split_sections() {
+        let document = Sectionize.transform(book()).unwrap().value;
+        let chunks = SplitAt::new(2).split(document);
+        let titles: Vec<_> = chunks.iter().map(headings).collect();
+        assert_eq!(
+            titles,
+            [vec!["A"], vec!["A.1", "A.1.1"], vec!["A.2"], vec!["B"]]
+        );
+        // The level 2 sections stay whole.
+        assert!(chunks[1].content.children[0].has_class(class::SECTION));
+    }
+
+    #[test]
+    fn test_split_notes_and_resources() {
+        let mut document = book();
+        let note = Node::new(node::FOOTNOTE_DEF)
+            .prop(prop::LABEL, "n")
+            .child(Node::new(node::PARAGRAPH));
+        document.content.children.push(note);
+        let image = document.embed(Resource::png(vec![1]));
+        let unused = document.embed(Resource::png(vec![2]));
+        document.content.children[5]
+            .children
+            .push(Node::new(node::IMAGE).prop(prop::RESOURCE_ID, image.as_str()));
+        document.metadata.set("title", "Book");
+
+        let chunks = SplitAt::new(1).split(document);
+        assert_eq!(chunks.len(), 2);
+        let last = chunks[0].content.children.last().unwrap();
+        assert_eq!(last.kind.as_str(), node::FOOTNOTE_DEF);
+        assert!(
+            chunks[1]
+                .content
+                .children
+                .iter()
+                .all(|n| n.kind.as_str() != node::FOOTNOTE_DEF)
+        );
+
+        assert!(chunks[0].resources.is_empty());
+        assert!(chunks[1].resources.contains_key(&image));
+        assert!(!chunks[1].resources.contains_key(&unused));
+        assert!(
+            chunks
+                .iter()
+                .all(|c| c.metadata.get_str("title") == Some("Book"))
+        );
+    }
+}
@@ -0,0 +1,386 @@
+//! Table of contents generation.
+//!
+//! [`GenerateToc`] builds a table of contents as ordinary IR: a `div` with
+//! the `toc` class holding a nested `list` of links to heading identifiers.
+//! Every writer renders it like any other list, so tables of contents look
+//! the same whatever the output format.
+//!
+//! The table goes where the document asks for it: an empty `toc` div (the
+//! reStructuredText reader produces one for `.. contents::`) or a paragraph
+//! reading `[TOC]`. Without a marker it is inserted at the start of the
+//! document.
+//!
+//! ```
+//! use rescribe_core::Transformer;
+//! use rescribe_std::builder::doc;
+//! use rescribe_std::{NodeExt, class};
+//! use rescribe_transforms::GenerateToc;
+//!
+//! let document = doc(|d| {
+//!     d.para(|i| i.text("[TOC]"))
+//!         .heading(1, |i| i.text("Usage"))
+//!         .heading(2, |i| i.text("Options"))
+//! });
+//! let document = GenerateToc::new().transform(document).unwrap().value;
+//!
+//! let toc = &document.content.children[0];
+//! assert!(toc.has_class(class::TOC));
+//! let list = toc.children[0].as_list().unwrap();
+//! assert_eq!(list.items().count(), 1);
+//! ```
+
+use crate::AutoIdentifiers;
+use rescribe_core::{ConversionResult, Document, TransformError, Transformer};
+use rescribe_std::{Node, NodeExt, class, node, prop};
+
+/// Generate a table of contents from the document's section headings.
+///
+/// Section headings are those at the top level or inside `div`s, up to the
+/// configured depth (a `depth` property on the marker overrides it).
+/// Headings with the `unlisted` class are skipped. Headings without an `id`
+/// get one from [`AutoIdentifiers`] so that they can be linked to.
+///
+/// A marker's `title` property, if any, becomes a paragraph before the list.
+#[derive(Debug, Clone)]
+pub struct GenerateToc {
+    depth: u8,
+    numbered: bool,
+    at_start: bool,
+}
+
+impl GenerateToc {
+    /// Create a generator listing headings down to level 3.
+    pub fn new() -> Self {
+        Self {
+            depth: 3,
+            numbered: false,
+            at_start: true,
+        }
+    }
+
+    /// Set the deepest heading level listed (1-6).
+    pub fn with_depth(mut self, depth: u8) -> Self {
+        self.depth = depth.clamp(1, 6);
+        self
+    }
+
+    /// Prefix entries with the heading's section number.
+    ///
+    /// Numbers come from the `number` property, so run
+    /// [`NumberSections`](crate::NumberSections) first.
+    pub fn numbered(mut self, numbered: bool) -> Self {
+        self.numbered = numbered;
+        self
+    }
+
+    /// Only fill in markers; leave documents without one unchanged.
+    pub fn markers_only(mut self) -> Self {
+        self.at_start = false;
+        self
+    }
+
+    /// Build the table of contents for `doc` without placing it, for writers
+    /// that put it somewhere of their own such as an index page or a
+    /// navigation document.
+    ///
+    /// Headings get identifiers as with [`Transformer::transform`], so the
+    /// document is returned along with the table, which is `None` if no
+    /// heading qualifies.
+    pub fn table(&self, doc: Document) -> Result<(Document, Option<Node>), TransformError> {
+        let doc = AutoIdentifiers::default().transform(doc)?.value;
+        let toc = self.toc(&section_headings(&doc), self.depth, None);
+        Ok((doc, toc))
+    }
+
+    /// Build the table for headings down to `depth`.
+    ///
+    /// Returns `None` if no heading qualifies.
+    fn toc(&self, headings: &[Node], depth: u8, title: Option<&str>) -> Option<Node> {
+        let mut entries = headings
+            .iter()
+            .filter_map(|node| {
+                let heading = node.as_heading()?;
+                (heading.level() <= depth).then(|| (heading.level(), self.entry(node)))
+            })
+            .peekable();
+        entries.peek()?;
+
+        let mut toc = Node::new(node::DIV).prop(prop::CLASSES, class::TOC);
+        if let Some(title) = title {
+            toc = toc.child(
+                Node::new(node::PARAGRAPH).child(Node::new(node::TEXT).prop(prop::CONTENT, title)),
+            );
+        }
+        Some(toc.child(nest(&mut entries, 1)))
+    }
+
+    /// A list item linking to `heading`.
+    fn entry(&self, heading: &Node) -> Node {
+        let view = heading.as_heading().expect("heading");
+        let mut content = Vec::new();
+        if self.numbered
+            && let Some(number) = view.number()
+        {
+            content.push(Node::new(node::TEXT).prop(prop::CONTENT, format!("{number} ")));
+        }
+        content.extend(view.content().iter().flat_map(link_text));
+        let link = Node::new(node::LINK)
+            .prop(prop::URL, format!("#{}", view.id().unwrap_or_default()))
+            .children(content);
+        Node::new(node::LIST_ITEM).child(Node::new(node::PARAGRAPH).child(link))
+    }
+}
+
+impl Default for GenerateToc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transformer for GenerateToc {
+    fn name(&self) -> &str {
+        "generate_toc"
+    }
+
+    fn transform(&self, doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        let mut doc = AutoIdentifiers::default().transform(doc)?.value;
+        let headings = section_headings(&doc);
+
+        // An existing table counts as a marker, so that running the
+        // transform again adds nothing.
+        let mut found = false;
+        crate::walk(&doc.content, &mut |node| {
+            found |= node.kind.as_str() == node::DIV && node.has_class(class::TOC);
+        });
+        fill_markers(&mut doc.content.children, &mut |marker| {
+            found = true;
+            let depth = marker
+                .props
+                .get_int(prop::DEPTH)
+                .map_or(self.depth, |d| d.clamp(1, 6) as u8);
+            self.toc(&headings, depth, marker.props.get_str(prop::TITLE))
+        });
+
+        if !found
+            && self.at_start
+            && let Some(toc) = self.toc(&headings, self.depth, None)
+        {
+            doc.content.children.insert(0, toc);
+        }
+        Ok(ConversionResult::ok(doc))
+    }
+}
+
+/// Whether `node` marks where a table of contents goes.
+fn is_marker(node: &Node) -> bool {
+    match node.kind.as_str() {
+        node::DIV => node.has_class(class::TOC) && node.children.is_empty(),
+        node::PARAGRAPH => crate::stringify(node).trim() == "[TOC]",
+        _ => false,
+    }
+}
+
+/// The document's section headings, in order.
+fn section_headings(doc: &Document) -> Vec<Node> {
+    let mut headings = Vec::new();
+    collect_headings(&doc.content, &mut headings);
+    headings.into_iter().cloned().collect()
+}
+
+/// Section headings in document order, skipping existing tables of contents.
+fn collect_headings<'a>(node: &'a Node, out: &mut Vec<&'a Node>) {
+    for child in &node.children {
+        match child.kind.as_str() {
+            node::HEADING if !child.has_class(class::UNLISTED) => out.push(child),
+            node::DIV if !child.has_class(class::TOC) => collect_headings(child, out),
+            _ => {}
+        }
+    }
+}
+
+/// Replace markers in `blocks` and nested `div`s by the table `make`
+/// builds, or remove them if it builds none.
+fn fill_markers(blocks: &mut Vec<Node>, make: &mut dyn FnMut(&Node) -> Option<Node>) {
+    let mut i = 0;
+    while i < blocks.len() {
+        if is_marker(&blocks[i]) {
+            match make(&blocks[i]) {
+                Some(toc) => blocks[i] = toc,
+                None => {
+                    blocks.remove(i);
+                    continue;
+                }
+            }
+        } else if blocks[i].kind.as_str() == node::DIV {
+            fill_markers(&mut blocks[i].children, make);
+        }
+        i += 1;
+    }
+}
+
+/// Heading content usable inside a link: nested links are unwrapped and
+/// footnote references dropped.
+fn link_text(node: &Node) -> Vec<Node> {
+    match node.kind.as_str() {
+        node::LINK => node.children.iter().flat_map(link_text).collect(),
+        node::FOOTNOTE_REF => Vec::new(),
+        _ => {
+            let mut node = node.clone();
+            node.children = node.children.iter().flat_map(link_text).collect();
+            vec![node]
+        }
+    }
+}
+
+/// Build a list of the entries at `level` or deeper, nesting deeper
+/// entries under the preceding one.
+fn nest(entries: &mut std::iter::Peekable<impl Iterator<Item = (u8, Node)>>, level: u8) -> Node {
+    let mut list = Node::new(node::LIST)
+        .prop(prop::ORDERED, false)
+        .prop(prop::TIGHT, true);
+    while let Some((entry_level, mut item)) = entries.next_if(|(l, _)| *l >= level) {
+        if entries.peek().is_some_and(|(l, _)| *l > entry_level) {
+            item.children.push(nest(entries, entry_level + 1));
+        }
+        list.children.push(item);
+    }
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rescribe_std::builder::doc;
+
+    /// `(url, text)` of every entry, with its nesting depth.
+    fn entries(list: &Node, depth: usize, out: &mut Vec<(usize, String, String)>) {
+        for item in &list.children {
+            let link = &item.children[0].children[0];
+            let url = link.props.get_str(prop::URL).unwrap().to_string();
+            out.push((depth, url, crate::stringify(link)));
+            if let Some(sublist) = item.children.get(1) {
+                entries(sublist, depth + 1, out);
+            }
+        }
+    }
+
+    fn toc_entries(toc: &Node) -> Vec<(usize, String, String)> {
+        let mut out = Vec::new();
+        entries(toc.children.last().unwrap(), 0, &mut out);
+        out
+    }
+
+    #[test]
+    fn test_toc_at_start() {
+        let document = doc(|d| {
+            d.heading(1, |i| i.text("Intro"))
+                .heading(2, |i| i.text("Background").footnote_ref("1"))
+                .heading(4, |i| i.text("Too deep"))
+                .heading(3, |i| i.link("https://example.com", |i| i.text("Linked")))
+                .heading(1, |i| i.em(|i| i.text("Usage")))
+                .blockquote(|q| q.heading(1, |i| i.text("Quoted")))
+        });
+        let result = GenerateToc::new().transform(document).unwrap().value;
+
+        let toc = &result.content.children[0];
+        assert!(toc.has_class(class::TOC));
+        let expected = [
+            (0, "#intro", "Intro"),
+            (1, "#background", "Background"),
+            (2, "#linked", "Linked"),
+            (0, "#usage", "Usage"),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(d, u, t)| (*d, u.to_string(), t.to_string()))
+            .collect();
+        assert_eq!(toc_entries(toc), expected);
+
+        // Running again keeps the existing table and adds no other.
+        let again = GenerateToc::new().transform(result.clone()).unwrap().value;
+        assert!(rescribe_core::diff(&result, &again, &Default::default()).is_empty());
+    }
+
+    #[test]
+    fn test_toc_markers() {
+        let mut document = doc(|d| {
+            d.heading(1, |i| i.text("Title"))
+                .para(|i| i.text("[TOC]"))
+                .heading(2, |i| i.text("Part"))
+                .heading(3, |i| i.text("Detail"))
+        });
+        let marker = Node::new(node::DIV)
+            .prop(prop::CLASSES, class::TOC)
+            .prop(prop::TITLE, "Contents")
+            .prop(prop::DEPTH, 2);
+        document.content.children.push(marker);
+        document.content.children[2]
+            .props
+            .set(prop::CLASSES, class::UNLISTED);
+
+        let result = GenerateToc::new()
+            .with_depth(6)
+            .transform(document)
+            .unwrap()
+            .value;
+        let children = &result.content.children;
+        assert_eq!(children.len(), 5);
+        assert_eq!(toc_entries(&children[1]).len(), 2);
+        let titled = &children[4];
+        assert_eq!(crate::stringify(&titled.children[0]), "Contents");
+        assert_eq!(toc_entries(titled).len(), 1);
+    }
+
+    #[test]
+    fn test_toc_numbered() {
+        let document = doc(|d| {
+            d.heading(1, |i| i.text("Intro"))
+                .heading(2, |i| i.text("Scope"))
+        });
+        let document = crate::NumberSections.transform(document).unwrap().value;
+        let result = GenerateToc::new()
+            .numbered(true)
+            .transform(document)
+            .unwrap()
+            .value;
+        let texts: Vec<_> = toc_entries(&result.content.children[0])
+            .into_iter()
+            .map(|(_, _, text)| text)
+            .collect();
+        assert_eq!(texts, ["1 Intro", "1.1 Scope"]);
+    }
+
+    #[test]
+    fn test_toc_table() {
+        let document = doc(|d| {
+            d.heading(1, |i| i.text("Intro"))
+                .heading(2, |i| i.text("Scope"))
+        });
+        let (document, toc) = GenerateToc::new().table(document).unwrap();
+        assert_eq!(document.content.children.len(), 2);
+        assert_eq!(
+            document.content.children[1].props.get_str(prop::ID),
+            Some("scope")
+        );
+        let expected = [
+            (0, "#intro".to_string(), "Intro".to_string()),
+            (1, "#scope".to_string(), "Scope".to_string()),
+        ];
+        assert_eq!(toc_entries(&toc.unwrap()), expected);
+    }
+
+    #[test]
+    fn test_toc_without_headings() {
+        let document = doc(|d| d.para(|i| i.text("[TOC]")).para(|i| i.text("Body")));
+        let result = GenerateToc::new().transform(document).unwrap().value;
+        assert_eq!(result.content.children.len(), 1);
+
+        let document = doc(|d| d.heading(1, |i| i.text("Only")));
+        let result = GenerateToc::new()
+            .markers_only()
+            .transform(document)
+            .unwrap()
+            .value;
+        assert_eq!(result.content.children.len(), 1);
+    }
+}
@@ -0,0 +1,516 @@
+//! Smart typography.
+//!
+//! [`Smartify`] turns typewriter punctuation into typographic punctuation:
+//! straight quotes become `quoted` nodes, `--` and `---` become en and em
+//! dashes, and `...` becomes an ellipsis. [`Dumbify`] goes the other way,
+//! for targets limited to ASCII.
+//!
+//! Quotation marks follow a [`QuoteLocale`]. Each `quoted` node records its
+//! marks in the `quote_open` and `quote_close` properties, so writers that
+//! print the marks themselves use the locale's instead of their default.
+//! Code, raw content and math are left alone.
+//!
+//! ```
+//! use rescribe_core::Transformer;
+//! use rescribe_std::builder::doc;
+//! use rescribe_std::{node, prop};
+//! use rescribe_transforms::{QuoteLocale, Smartify};
+//!
+//! let document = doc(|d| d.para(|i| i.text("\"Wait\" -- it's here...")));
+//! let document = Smartify::new(QuoteLocale::German)
+//!     .transform(document)
+//!     .unwrap()
+//!     .value;
+//!
+//! let para = &document.content.children[0];
+//! let quoted = &para.children[0];
+//! assert_eq!(quoted.kind.as_str(), node::QUOTED);
+//! assert_eq!(quoted.props.get_str(prop::QUOTE_OPEN), Some("\u{201E}"));
+//! assert_eq!(
+//!     rescribe_transforms::stringify(para),
+//!     "Wait \u{2013} it\u{2019}s here\u{2026}"
+//! );
+//! ```
+
+use rescribe_core::{ConversionResult, Document, TransformError, Transformer};
+use rescribe_std::{Node, node, prop};
+
+/// Quotation mark conventions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QuoteLocale {
+    /// “Double” and ‘single’ quotes.
+    #[default]
+    English,
+    /// „Double“ and ‚single‘ quotes.
+    German,
+    /// « Double » and ‹ single › quotes, with narrow no-break spaces inside
+    /// the marks.
+    French,
+}
+
+impl QuoteLocale {
+    /// The conventions for a language tag such as `de` or `fr-CA`, if known.
+    pub fn for_language(tag: &str) -> Option<Self> {
+        let language = tag.split(['-', '_']).next()?.to_ascii_lowercase();
+        match language.as_str() {
+            "en" => Some(Self::English),
+            "de" => Some(Self::German),
+            "fr" => Some(Self::French),
+            _ => None,
+        }
+    }
+
+    /// Opening and closing marks for double or single quotes.
+    pub fn marks(self, double: bool) -> (&'static str, &'static str) {
+        match (self, double) {
+            (Self::English, true) => ("\u{201C}", "\u{201D}"),
+            (Self::English, false) => ("\u{2018}", "\u{2019}"),
+            (Self::German, true) => ("\u{201E}", "\u{201C}"),
+            (Self::German, false) => ("\u{201A}", "\u{2018}"),
+            (Self::French, true) => ("\u{AB}\u{202F}", "\u{202F}\u{BB}"),
+            (Self::French, false) => ("\u{2039}\u{202F}", "\u{202F}\u{203A}"),
+        }
+    }
+}
+
+/// Convert straight quotes, dashes and ellipses to typographic ones.
+///
+/// Quotes are paired within a run of inline siblings, so a quotation may
+/// span emphasis or links but not cross into or out of them. Quotes that
+/// cannot be paired are left as they are, except single quotes, which
+/// become apostrophes. Existing `quoted` nodes get the locale's marks.
+#[derive(Debug, Clone, Default)]
+pub struct Smartify {
+    locale: QuoteLocale,
+}
+
+impl Smartify {
+    /// Create a transform using `locale`'s quotation marks.
+    pub fn new(locale: QuoteLocale) -> Self {
+        Self { locale }
+    }
+
+    fn smarten(&self, node: &mut Node) {
+        if is_verbatim(node) {
+            return;
+        }
+        match node.kind.as_str() {
+            node::TEXT => {
+                if let Some(content) = node.props.get_str(prop::CONTENT) {
+                    let content = punctuation(content);
+                    node.props.set(prop::CONTENT, content);
+                }
+                return;
+            }
+            node::QUOTED if node.props.get_str(prop::QUOTE_OPEN).is_none() => {
+                let double = node.props.get_str(prop::QUOTE_TYPE) != Some("single");
+                self.set_marks(node, double);
+            }
+            _ => {}
+        }
+        for child in &mut node.children {
+            self.smarten(child);
+        }
+        if node.children.iter().any(|c| c.kind.as_str() == node::TEXT) {
+            node.children = self.pair_quotes(std::mem::take(&mut node.children));
+        }
+    }
+
+    fn set_marks(&self, quoted: &mut Node, double: bool) {
+        let (open, close) = self.locale.marks(double);
+        quoted.props.set(prop::QUOTE_OPEN, open);
+        quoted.props.set(prop::QUOTE_CLOSE, close);
+    }
+
+    /// Replace paired quote characters in `children`'s text by `quoted`
+    /// nodes around what they enclose.
+    fn pair_quotes(&self, children: Vec<Node>) -> Vec<Node> {
+        let tokens = tokenize(children);
+        let flanking: Vec<_> = (0..tokens.len()).map(|i| flanking(&tokens, i)).collect();
+        let mut out = Vec::new();
+        let mut open: Vec<(usize, char)> = Vec::new();
+
+        for (token, (can_open, can_close)) in tokens.into_iter().zip(flanking) {
+            let (mark, template, source) = match token {
+                Token::Text(node, source) => {
+                    out.push(Item::Node(node, Some(source)));
+                    continue;
+                }
+                Token::Node(node) => {
+                    out.push(Item::Node(node, None));
+                    continue;
+                }
+                Token::Quote(mark, template, source) => (mark, template, source),
+            };
+            if can_close && let Some(pos) = open.iter().rposition(|&(_, m)| m == mark) {
+                let start = open[pos].0;
+                open.truncate(pos);
+                let inner: Vec<Item> = out.drain(start + 1..).collect();
+                out.pop();
+                let double = mark == '"';
+                let mut quoted = Node::new(node::QUOTED)
+                    .prop(prop::QUOTE_TYPE, if double { "double" } else { "single" })
+                    .children(finish(inner));
+                self.set_marks(&mut quoted, double);
+                out.push(Item::Node(quoted, None));
+            } else {
+                if can_open {
+                    open.push((out.len(), mark));
+                }
+                out.push(Item::Quote(mark, template, source));
+            }
+        }
+        finish(out)
+    }
+}
+
+impl Transformer for Smartify {
+    fn name(&self) -> &str {
+        "smartify"
+    }
+
+    fn transform(&self, mut doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        self.smarten(&mut doc.content);
+        Ok(ConversionResult::ok(doc))
+    }
+}
+
+/// Convert typographic punctuation back to ASCII.
+///
+/// `quoted` nodes become their content between straight quotes, and curly
+/// quotes, guillemets, dashes and ellipses in text are replaced by their
+/// typewriter spelling. Code, raw content and math are left alone.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Dumbify;
+
+impl Dumbify {
+    fn dumb_down(node: &mut Node) {
+        if is_verbatim(node) {
+            return;
+        }
+        if node.kind.as_str() == node::TEXT {
+            if let Some(content) = node.props.get_str(prop::CONTENT) {
+                let content = ascii(content);
+                node.props.set(prop::CONTENT, content);
+            }
+            return;
+        }
+        for child in &mut node.children {
+            Self::dumb_down(child);
+        }
+        if node
+            .children
+            .iter()
+            .any(|c| c.kind.as_str() == node::QUOTED)
+        {
+            node.children = std::mem::take(&mut node.children)
+                .into_iter()
+                .flat_map(|child| {
+                    if child.kind.as_str() != node::QUOTED {
+                        return vec![child];
+                    }
+                    let mark = match child.props.get_str(prop::QUOTE_TYPE) {
+                        Some("single") => "'",
+                        _ => "\"",
+                    };
+                    let text = || Node::new(node::TEXT).prop(prop::CONTENT, mark);
+                    let mut nodes = vec![text()];
+                    nodes.extend(child.children);
+                    nodes.push(text());
+                    nodes
+                })
+                .collect();
+        }
+    }
+}
+
+impl Transformer for Dumbify {
+    fn name(&self) -> &str {
+        "dumbify"
+    }
+
+    fn transform(&self, mut doc: Document) -> Result<ConversionResult<Document>, TransformError> {
+        Self::dumb_down(&mut doc.content);
+        Ok(ConversionResult::ok(doc))
+    }
+}
+
+/// Whether `node`'s text must be kept exactly as written.
+fn is_verbatim(node: &Node) -> bool {
+    let kind = node.kind.as_str();
+    matches!(
+        kind,
+        node::CODE
+            | node::CODE_BLOCK
+            | node::RAW_BLOCK
+            | node::RAW_INLINE
+            | rescribe_math::node::MATH_INLINE
+            | rescribe_math::node::MATH_DISPLAY
+    ) || kind.starts_with("math:")
+}
+
+/// Replace `---`, `--` and `...` by an em dash, en dash and ellipsis.
+fn punctuation(text: &str) -> String {
+    text.replace("---", "\u{2014}")
+        .replace("--", "\u{2013}")
+        .replace("...", "\u{2026}")
+}
+
+/// Replace typographic punctuation by its ASCII spelling.
+fn ascii(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2039}' | '\u{203A}' => {
+                out.push('\'')
+            }
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{AB}' | '\u{BB}' => {
+                out.push('"')
+            }
+            '\u{2013}' => out.push_str("--"),
+            '\u{2014}' => out.push_str("---"),
+            '\u{2026}' => out.push_str("..."),
+            '\u{2009}' | '\u{202F}' => out.push(' '),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// A run of inline siblings, with the quote characters in their text split
+/// out. Text pieces remember which sibling they came from.
+enum Token {
+    Text(Node, usize),
+    Quote(char, Node, usize),
+    Node(Node),
+}
+
+impl Token {
+    /// The first or last character of the token, as far as quote pairing
+    /// is concerned: breaks count as spaces, and other nodes as an object
+    /// that a quote may close before but not open after.
+    fn edge(&self, last: bool) -> Option<char> {
+        match self {
+            Token::Text(node, _) => {
+                let mut chars = node.props.get_str(prop::CONTENT).unwrap_or("").chars();
+                if last {
+                    chars.next_back()
+                } else {
+                    chars.next()
+                }
+            }
+            Token::Quote(mark, _, _) => Some(*mark),
+            Token::Node(node) => match node.kind.as_str() {
+                node::SOFT_BREAK | node::LINE_BREAK => Some(' '),
+                _ => Some('\u{FFFC}'),
+            },
+        }
+    }
+}
+
+fn tokenize(children: Vec<Node>) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    for (source, child) in children.into_iter().enumerate() {
+        if child.kind.as_str() != node::TEXT {
+            tokens.push(Token::Node(child));
+            continue;
+        }
+        let piece = |text: &str| {
+            let mut piece = child.clone();
+            piece.props.set(prop::CONTENT, text);
+            piece
+        };
+        let content = child.props.get_str(prop::CONTENT).unwrap_or("");
+        let mut rest = content;
+        while let Some(at) = rest.find(['"', '\'']) {
+            if at > 0 {
+                tokens.push(Token::Text(piece(&rest[..at]), source));
+            }
+            let mark = rest[at..].chars().next().expect("quote");
+            tokens.push(Token::Quote(mark, piece(""), source));
+            rest = &rest[at + 1..];
+        }
+        if !rest.is_empty() || rest.len() == content.len() {
+            tokens.push(Token::Text(piece(rest), source));
+        }
+    }
+    tokens
+}
+
+/// Whether the quote at `i` can open and can close a quotation.
+fn flanking(tokens: &[Token], i: usize) -> (bool, bool) {
+    let Token::Quote(mark, ..) = tokens[i] else {
+        return (false, false);
+    };
+    let prev = tokens[..i].iter().rev().find_map(|t| t.edge(true));
+    let next = tokens[i + 1..].iter().find_map(|t| t.edge(false));
+    let can_open = next.is_some_and(|c| !c.is_whitespace())
+        && prev.is_none_or(|c| c.is_whitespace() || "([{<-/\u{2013}\u{2014}\"'".contains(c))
+        // An elided year such as '90s starts with an apostrophe.
+        && !(mark == '\'' && next.is_some_and(|c| c.is_ascii_digit()));
+    let can_close =
+        prev.is_some_and(|c| !c.is_whitespace()) && next.is_none_or(|c| !c.is_alphanumeric());
+    (can_open, can_close)
+}
+
+/// Output of quote pairing: nodes, and quote characters left unpaired.
+enum Item {
+    Node(Node, Option<usize>),
+    Quote(char, Node, usize),
+}
+
+/// Turn unpaired quotes back into text (single quotes as apostrophes) and
+/// rejoin text pieces split from the same sibling.
+fn finish(items: Vec<Item>) -> Vec<Node> {
+    let mut nodes: Vec<(Node, Option<usize>)> = Vec::new();
+    for item in items {
+        let (node, source) = match item {
+            Item::Node(node, source) => (node, source),
+            Item::Quote(mark, mut text, source) => {
+                let literal = if mark == '\'' { "\u{2019}" } else { "\"" };
+                text.props.set(prop::CONTENT, literal);
+                (text, Some(source))
+            }
+        };
+        if let Some((last, last_source)) = nodes.last_mut()
+            && source.is_some()
+            && *last_source == source
+        {
+            let joined = format!(
+                "{}{}",
+                last.props.get_str(prop::CONTENT).unwrap_or(""),
+                node.props.get_str(prop::CONTENT).unwrap_or("")
+            );
+            last.props.set(prop::CONTENT, joined);
+            continue;
+        }
+        nodes.push((node, source));
+    }
+    nodes.into_iter().map(|(node, _)| node).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rescribe_std::builder::doc;
+
+    fn smarten(document: Document, locale: QuoteLocale) -> Node {
+        let result = Smartify::new(locale).transform(document).unwrap().value;
+        result.content.children.into_iter().next().unwrap()
+    }
+
+    /// Kinds and text of a node's children, with quoted nodes shown by
+    /// their marks.
+    fn render(node: &Node) -> String {
+        node.children
+            .iter()
+            .map(|child| match child.kind.as_str() {
+                node::TEXT => child.props.get_str(prop::CONTENT).unwrap().to_string(),
+                node::QUOTED => format!(
+                    "{}{}{}",
+                    child.props.get_str(prop::QUOTE_OPEN).unwrap(),
+                    render(child),
+                    child.props.get_str(prop::QUOTE_CLOSE).unwrap()
+                ),
+                kind => format!("<{kind}>{}</{kind}>", render(child)),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_smartify_english() {
+        let document = doc(|d| {
+            d.para(|i| {
+                i.text("\"It's 'fine'\" -- or is it? ")
+                    .text("Wait---\"")
+                    .em(|i| i.text("really"))
+                    .text("\"... the '90s' dogs' \"bone")
+            })
+        });
+        let para = smarten(document, QuoteLocale::English);
+        assert_eq!(
+            render(&para),
+            "\u{201C}It\u{2019}s \u{2018}fine\u{2019}\u{201D} \u{2013} or is it? \
+             Wait\u{2014}\u{201C}<emphasis>really</emphasis>\u{201D}\u{2026} \
+             the \u{2019}90s\u{2019} dogs\u{2019} \"bone"
+        );
+        let quoted = &para.children[0];
+        assert_eq!(quoted.props.get_str(prop::QUOTE_TYPE), Some("double"));
+        assert_eq!(
+            quoted.children[1].props.get_str(prop::QUOTE_TYPE),
+            Some("single")
+        );
+    }
+
+    #[test]
+    fn test_smartify_locales() {
+        let document = || doc(|d| d.para(|i| i.text("Er sagte \"'ja'\".")));
+        assert_eq!(
+            render(&smarten(document(), QuoteLocale::German)),
+            "Er sagte \u{201E}\u{201A}ja\u{2018}\u{201C}."
+        );
+        assert_eq!(
+            render(&smarten(document(), QuoteLocale::French)),
+            "Er sagte \u{AB}\u{202F}\u{2039}\u{202F}ja\u{202F}\u{203A}\u{202F}\u{BB}."
+        );
+
+        assert_eq!(
+            QuoteLocale::for_language("fr-CA"),
+            Some(QuoteLocale::French)
+        );
+        assert_eq!(
+            QuoteLocale::for_language("de_AT"),
+            Some(QuoteLocale::German)
+        );
+        assert_eq!(QuoteLocale::for_language("nl"), None);
+    }
+
+    #[test]
+    fn test_smartify_skips_verbatim() {
+        let mut document = doc(|d| {
+            d.code_block_lang("echo \"a -- b...\"", "sh")
+                .para(|i| i.text("\"x\""))
+        });
+        let para = &mut document.content.children[1];
+        para.children
+            .push(Node::new(node::CODE).prop(prop::CONTENT, "'--'"));
+        para.children.push(
+            Node::new(rescribe_math::node::MATH_INLINE)
+                .child(Node::new(node::TEXT).prop(prop::CONTENT, "f'")),
+        );
+        let result = Smartify::default()
+            .transform(document.clone())
+            .unwrap()
+            .value;
+
+        let block = &result.content.children[0];
+        assert_eq!(
+            block.props.get_str(prop::CONTENT),
+            Some("echo \"a -- b...\"")
+        );
+        let para = &result.content.children[1];
+        assert_eq!(para.children[0].kind.as_str(), node::QUOTED);
+        assert_eq!(para.children[1].props.get_str(prop::CONTENT), Some("'--'"));
+        let math = &para.children[2].children[0];
+        assert_eq!(math.props.get_str(prop::CONTENT), Some("f'"));
+    }
+
+    #[test]
+    fn test_dumbify() {
+        let text = "\"It's 'fine'\" -- or---is it... \u{AB}\u{202F}oui\u{202F}\u{BB}";
+        let document = doc(|d| d.para(|i| i.text(text).code("\u{2014}")));
+        let smart = Smartify::default().transform(document).unwrap().value;
+        let dumb = Dumbify.transform(smart).unwrap().value;
+
+        let para = &dumb.content.children[0];
+        assert!(
+            para.children
+                .iter()
+                .all(|c| c.kind.as_str() != node::QUOTED)
+        );
+        assert_eq!(
+            crate::stringify(para),
+            "\"It's 'fine'\" -- or---is it... \" oui \"\u{2014}"
+        );
+    }
+}
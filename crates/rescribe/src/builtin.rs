@@ -0,0 +1,673 @@
+//! Registration of the formats enabled by Cargo features.
+//!
+//! Formats are registered in priority order: when two formats claim the same
+//! extension or MIME type, the one listed first wins lookups.
+
+use crate::registry::{
+    EmitFn, EmitToFn, FnEmitter, FnParser, FormatInfo, ParseBytesFn, ParseTextFn, Registry,
+};
+
+#[allow(dead_code)] // unused when no text reader is enabled
+fn text_reader(registry: &mut Registry, info: FormatInfo, parse: ParseTextFn) {
+    registry.register_parser(info, FnParser::text(info.name, parse));
+}
+
+#[allow(dead_code)] // unused when no binary reader is enabled
+fn binary_reader(registry: &mut Registry, info: FormatInfo, parse: ParseBytesFn) {
+    registry.register_parser(info, FnParser::bytes(info.name, parse));
+}
+
+#[allow(dead_code)] // unused when no writer is enabled
+fn writer(registry: &mut Registry, info: FormatInfo, emit: EmitFn) {
+    registry.register_emitter(info, FnEmitter::new(info.name, emit));
+}
+
+#[allow(dead_code)] // unused when no streaming writer is enabled
+fn streaming_writer(registry: &mut Registry, info: FormatInfo, emit_to: EmitToFn) {
+    registry.register_emitter(info, FnEmitter::streaming(info.name, emit_to));
+}
+
+/// Register every enabled reader and writer.
+#[allow(unused_variables)] // `registry` is unused when no format is enabled
+pub(crate) fn register(registry: &mut Registry) {
+    #[cfg(feature = "markdown")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("markdown", "Markdown")
+            .with_aliases(&["md"])
+            .with_extensions(&["md", "markdown", "mkd", "mdown"])
+            .with_mime_types(&["text/markdown", "text/x-markdown"]);
+        registry.register_parser(
+            INFO,
+            FnParser::text(INFO.name, rescribe_read_markdown::parse_with_options)
+                .with_options(rescribe_read_markdown::options::ALL),
+        );
+        streaming_writer(registry, INFO, rescribe_write_markdown::emit_to);
+    }
+
+    #[cfg(feature = "commonmark")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("commonmark", "CommonMark")
+            .with_aliases(&["cm"])
+            .with_mime_types(&["text/markdown; variant=CommonMark"]);
+        text_reader(registry, INFO, rescribe_read_commonmark::parse_with_options);
+        writer(registry, INFO, rescribe_write_commonmark::emit_with_options);
+    }
+
+    #[cfg(feature = "gfm")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("gfm", "GitHub Flavored Markdown")
+            .with_aliases(&["github"])
+            .with_mime_types(&["text/markdown; variant=GFM"]);
+        text_reader(registry, INFO, rescribe_read_gfm::parse_with_options);
+        writer(registry, INFO, rescribe_write_gfm::emit_with_options);
+    }
+
+    #[cfg(feature = "markdown-strict")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("markdown-strict", "Markdown (original)")
+            .with_aliases(&["markdown_strict"])
+            .with_mime_types(&["text/markdown; variant=Original"]);
+        text_reader(
+            registry,
+            INFO,
+            rescribe_read_markdown_strict::parse_with_options,
+        );
+        writer(
+            registry,
+            INFO,
+            rescribe_write_markdown_strict::emit_with_options,
+        );
+    }
+
+    #[cfg(feature = "multimarkdown")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("multimarkdown", "MultiMarkdown")
+            .with_aliases(&["mmd"])
+            .with_extensions(&["mmd"])
+            .with_mime_types(&["text/markdown; variant=MultiMarkdown"]);
+        text_reader(
+            registry,
+            INFO,
+            rescribe_read_multimarkdown::parse_with_options,
+        );
+        writer(
+            registry,
+            INFO,
+            rescribe_write_multimarkdown::emit_with_options,
+        );
+    }
+
+    #[cfg(feature = "markua")]
+    {
+        const INFO: FormatInfo =
+            FormatInfo::new("markua", "Markua (Leanpub)").with_extensions(&["markua"]);
+        text_reader(registry, INFO, rescribe_read_markua::parse_with_options);
+        writer(registry, INFO, rescribe_write_markua::emit_with_options);
+    }
+
+    #[cfg(feature = "djot")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("djot", "Djot")
+            .with_extensions(&["dj", "djot"])
+            .with_mime_types(&["text/x-djot"]);
+        text_reader(registry, INFO, |input, _| rescribe_read_djot::parse(input));
+        writer(registry, INFO, |doc, _| rescribe_write_djot::emit(doc));
+    }
+
+    #[cfg(feature = "html")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("html", "HTML")
+            .with_aliases(&["html5", "htm", "xhtml"])
+            .with_extensions(&["html", "htm", "xhtml"])
+            .with_mime_types(&["text/html", "application/xhtml+xml"]);
+        text_reader(registry, INFO, rescribe_read_html::parse_with_options);
+        registry.register_emitter(
+            INFO,
+            FnEmitter::streaming(INFO.name, rescribe_write_html::emit_to)
+                .with_options(rescribe_write_html::options::ALL),
+        );
+    }
+
+    #[cfg(feature = "latex")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("latex", "LaTeX")
+            .with_aliases(&["tex"])
+            .with_extensions(&["tex", "latex", "ltx"])
+            .with_mime_types(&["application/x-latex", "text/x-tex"]);
+        text_reader(registry, INFO, rescribe_read_latex::parse_with_options);
+        registry.register_emitter(
+            INFO,
+            FnEmitter::streaming(INFO.name, rescribe_write_latex::emit_to)
+                .with_options(rescribe_write_latex::options::ALL),
+        );
+    }
+
+    #[cfg(feature = "beamer")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("beamer", "Beamer (LaTeX slides)");
+        writer(registry, INFO, rescribe_write_beamer::emit_with_options);
+    }
+
+    #[cfg(feature = "context")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("context", "ConTeXt");
+        writer(registry, INFO, rescribe_write_context::emit_with_options);
+    }
+
+    #[cfg(feature = "typst")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("typst", "Typst")
+            .with_extensions(&["typ"])
+            .with_mime_types(&["text/x-typst"]);
+        text_reader(registry, INFO, rescribe_read_typst::parse_with_options);
+        writer(registry, INFO, rescribe_write_typst::emit_with_options);
+    }
+
+    #[cfg(feature = "org")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("org", "Org-mode")
+            .with_aliases(&["orgmode", "org-mode"])
+            .with_extensions(&["org"])
+            .with_mime_types(&["text/x-org"]);
+        text_reader(registry, INFO, rescribe_read_org::parse_with_options);
+        writer(registry, INFO, rescribe_write_org::emit_with_options);
+    }
+
+    #[cfg(feature = "rst")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("rst", "reStructuredText")
+            .with_aliases(&["rest", "restructuredtext"])
+            .with_extensions(&["rst", "rest"])
+            .with_mime_types(&["text/x-rst"]);
+        text_reader(registry, INFO, rescribe_read_rst::parse_with_options);
+        writer(registry, INFO, rescribe_write_rst::emit_with_options);
+    }
+
+    #[cfg(feature = "asciidoc")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("asciidoc", "AsciiDoc")
+            .with_aliases(&["adoc", "asciidoctor"])
+            .with_extensions(&["adoc", "asciidoc", "asc"])
+            .with_mime_types(&["text/x-asciidoc"]);
+        text_reader(registry, INFO, rescribe_read_asciidoc::parse_with_options);
+        writer(registry, INFO, rescribe_write_asciidoc::emit_with_options);
+    }
+
+    #[cfg(feature = "plaintext")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("plaintext", "Plain text")
+            .with_aliases(&["plain", "text", "txt"])
+            .with_extensions(&["txt", "text"])
+            .with_mime_types(&["text/plain"]);
+        registry.register_emitter(
+            INFO,
+            FnEmitter::streaming(INFO.name, rescribe_write_plaintext::emit_to)
+                .with_options(rescribe_write_plaintext::options::ALL),
+        );
+    }
+
+    #[cfg(any(feature = "ansi", feature = "ansi-read"))]
+    {
+        const INFO: FormatInfo = FormatInfo::new("ansi", "ANSI terminal")
+            .with_aliases(&["terminal"])
+            .with_extensions(&["ans", "ansi"]);
+        #[cfg(feature = "ansi-read")]
+        text_reader(registry, INFO, rescribe_read_ansi::parse_with_options);
+        #[cfg(feature = "ansi")]
+        writer(registry, INFO, rescribe_write_ansi::emit_with_options);
+    }
+
+    #[cfg(feature = "pdf")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("pdf", "PDF")
+            .with_extensions(&["pdf"])
+            .with_mime_types(&["application/pdf"])
+            .binary();
+        binary_reader(registry, INFO, rescribe_read_pdf::parse_with_options);
+    }
+
+    #[cfg(feature = "docx")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("docx", "Microsoft Word (DOCX)")
+            .with_extensions(&["docx"])
+            .with_mime_types(&[
+                "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+            ])
+            .binary();
+        binary_reader(registry, INFO, |input, _| {
+            rescribe_read_docx::parse_bytes(input)
+        });
+        writer(registry, INFO, |doc, _| rescribe_write_docx::emit(doc));
+    }
+
+    #[cfg(feature = "odt")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("odt", "OpenDocument Text")
+            .with_extensions(&["odt"])
+            .with_mime_types(&["application/vnd.oasis.opendocument.text"])
+            .binary();
+        binary_reader(registry, INFO, rescribe_read_odt::parse_with_options);
+        writer(registry, INFO, rescribe_write_odt::emit_with_options);
+    }
+
+    #[cfg(feature = "rtf")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("rtf", "Rich Text Format")
+            .with_extensions(&["rtf"])
+            .with_mime_types(&["application/rtf", "text/rtf"]);
+        text_reader(registry, INFO, rescribe_read_rtf::parse_with_options);
+        writer(registry, INFO, rescribe_write_rtf::emit_with_options);
+    }
+
+    #[cfg(feature = "pptx")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("pptx", "Microsoft PowerPoint (PPTX)")
+            .with_extensions(&["pptx"])
+            .with_mime_types(&[
+                "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+            ])
+            .binary();
+        binary_reader(registry, INFO, rescribe_read_pptx::parse_with_options);
+        writer(registry, INFO, rescribe_write_pptx::emit_with_options);
+    }
+
+    #[cfg(feature = "xlsx")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("xlsx", "Microsoft Excel (XLSX)")
+            .with_extensions(&["xlsx"])
+            .with_mime_types(&["application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"])
+            .binary();
+        binary_reader(registry, INFO, |input, _| {
+            rescribe_read_xlsx::parse_bytes(input)
+        });
+        writer(registry, INFO, rescribe_write_xlsx::emit_with_options);
+    }
+
+    #[cfg(feature = "epub")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("epub", "EPUB")
+            .with_extensions(&["epub"])
+            .with_mime_types(&["application/epub+zip"])
+            .binary();
+        binary_reader(registry, INFO, |input, _| {
+            rescribe_read_epub::parse_bytes(input)
+        });
+        registry.register_emitter(
+            INFO,
+            FnEmitter::new(INFO.name, rescribe_write_epub::emit_with_options)
+                .with_options(rescribe_write_epub::options::ALL),
+        );
+    }
+
+    #[cfg(feature = "fb2")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("fb2", "FictionBook 2")
+            .with_aliases(&["fictionbook"])
+            .with_extensions(&["fb2"])
+            .with_mime_types(&["application/x-fictionbook+xml"]);
+        text_reader(registry, INFO, |input, options| {
+            let fb2_options = rescribe_read_fb2::ParseOptions {
+                extract_binaries: options.embed_resources,
+            };
+            rescribe_read_fb2::parse_with_options(input, &fb2_options)
+        });
+        writer(registry, INFO, rescribe_write_fb2::emit_with_options);
+    }
+
+    #[cfg(feature = "ipynb")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("ipynb", "Jupyter notebook")
+            .with_aliases(&["jupyter"])
+            .with_extensions(&["ipynb"])
+            .with_mime_types(&["application/x-ipynb+json"]);
+        text_reader(registry, INFO, |input, _| rescribe_read_ipynb::parse(input));
+        writer(registry, INFO, |doc, _| rescribe_write_ipynb::emit(doc));
+    }
+
+    #[cfg(feature = "docbook")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("docbook", "DocBook")
+            .with_aliases(&["docbook5", "docbook4"])
+            .with_extensions(&["dbk", "docbook"])
+            .with_mime_types(&["application/docbook+xml"]);
+        text_reader(registry, INFO, |input, _| {
+            rescribe_read_docbook::parse(input)
+        });
+        registry.register_emitter(
+            INFO,
+            FnEmitter::streaming(INFO.name, rescribe_write_docbook::emit_to)
+                .with_options(rescribe_write_docbook::options::ALL),
+        );
+    }
+
+    #[cfg(feature = "jats")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("jats", "JATS")
+            .with_extensions(&["jats"])
+            .with_mime_types(&["application/jats+xml"]);
+        text_reader(registry, INFO, |input, _| rescribe_read_jats::parse(input));
+        registry.register_emitter(
+            INFO,
+            FnEmitter::streaming(INFO.name, rescribe_write_jats::emit_to)
+                .with_options(rescribe_write_jats::options::ALL),
+        );
+    }
+
+    #[cfg(feature = "tei")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("tei", "TEI")
+            .with_extensions(&["tei"])
+            .with_mime_types(&["application/tei+xml"]);
+        text_reader(registry, INFO, |input, _| rescribe_read_tei::parse(input));
+        registry.register_emitter(
+            INFO,
+            FnEmitter::new(INFO.name, rescribe_write_tei::emit_with_options)
+                .with_options(rescribe_write_tei::options::ALL),
+        );
+    }
+
+    #[cfg(feature = "icml")]
+    {
+        const INFO: FormatInfo =
+            FormatInfo::new("icml", "InCopy Markup Language").with_extensions(&["icml"]);
+        writer(registry, INFO, rescribe_write_icml::emit_with_options);
+    }
+
+    #[cfg(feature = "opml")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("opml", "OPML")
+            .with_extensions(&["opml"])
+            .with_mime_types(&["text/x-opml", "text/x-opml+xml"]);
+        text_reader(registry, INFO, |input, _| rescribe_read_opml::parse(input));
+        writer(registry, INFO, |doc, _| rescribe_write_opml::emit(doc));
+    }
+
+    #[cfg(feature = "man")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("man", "Man page (roff)")
+            .with_aliases(&["roff"])
+            .with_extensions(&["man", "1", "2", "3", "4", "5", "6", "7", "8", "9"])
+            .with_mime_types(&["text/troff", "application/x-troff-man"]);
+        text_reader(registry, INFO, |input, _| rescribe_read_man::parse(input));
+        writer(registry, INFO, rescribe_write_man::emit_with_options);
+    }
+
+    #[cfg(feature = "ms")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("ms", "Groff ms")
+            .with_aliases(&["groff-ms"])
+            .with_extensions(&["ms"]);
+        writer(registry, INFO, rescribe_write_ms::emit_with_options);
+    }
+
+    #[cfg(feature = "texinfo")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("texinfo", "Texinfo")
+            .with_extensions(&["texi", "texinfo", "txi"])
+            .with_mime_types(&["application/x-texinfo"]);
+        text_reader(registry, INFO, rescribe_read_texinfo::parse_with_options);
+        writer(registry, INFO, rescribe_write_texinfo::emit_with_options);
+    }
+
+    #[cfg(feature = "pod")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("pod", "Perl POD")
+            .with_extensions(&["pod"])
+            .with_mime_types(&["text/x-pod"]);
+        text_reader(registry, INFO, rescribe_read_pod::parse_with_options);
+        writer(registry, INFO, rescribe_write_pod::emit_with_options);
+    }
+
+    #[cfg(feature = "haddock")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("haddock", "Haddock");
+        text_reader(registry, INFO, rescribe_read_haddock::parse_with_options);
+        writer(registry, INFO, rescribe_write_haddock::emit_with_options);
+    }
+
+    #[cfg(feature = "mediawiki")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("mediawiki", "MediaWiki")
+            .with_aliases(&["wikipedia"])
+            .with_extensions(&["mediawiki", "wiki"])
+            .with_mime_types(&["text/x-wiki"]);
+        text_reader(registry, INFO, |input, _| {
+            rescribe_read_mediawiki::parse(input)
+        });
+        writer(registry, INFO, |doc, _| rescribe_write_mediawiki::emit(doc));
+    }
+
+    #[cfg(feature = "dokuwiki")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("dokuwiki", "DokuWiki");
+        text_reader(registry, INFO, rescribe_read_dokuwiki::parse_with_options);
+        writer(registry, INFO, rescribe_write_dokuwiki::emit_with_options);
+    }
+
+    #[cfg(feature = "jira")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("jira", "Jira/Confluence markup")
+            .with_aliases(&["confluence"])
+            .with_extensions(&["jira"]);
+        text_reader(registry, INFO, rescribe_read_jira::parse_with_options);
+        writer(registry, INFO, rescribe_write_jira::emit_with_options);
+    }
+
+    #[cfg(feature = "creole")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("creole", "Creole").with_extensions(&["creole"]);
+        text_reader(registry, INFO, rescribe_read_creole::parse_with_options);
+        writer(registry, INFO, rescribe_write_creole::emit_with_options);
+    }
+
+    #[cfg(feature = "textile")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("textile", "Textile")
+            .with_extensions(&["textile"])
+            .with_mime_types(&["text/x-textile"]);
+        text_reader(registry, INFO, rescribe_read_textile::parse_with_options);
+        writer(registry, INFO, rescribe_write_textile::emit_with_options);
+    }
+
+    #[cfg(feature = "muse")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("muse", "Emacs Muse").with_extensions(&["muse"]);
+        text_reader(registry, INFO, rescribe_read_muse::parse_with_options);
+        writer(registry, INFO, rescribe_write_muse::emit_with_options);
+    }
+
+    #[cfg(feature = "t2t")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("t2t", "txt2tags")
+            .with_aliases(&["txt2tags"])
+            .with_extensions(&["t2t"]);
+        text_reader(registry, INFO, rescribe_read_t2t::parse_with_options);
+        writer(registry, INFO, rescribe_write_t2t::emit_with_options);
+    }
+
+    #[cfg(feature = "vimwiki")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("vimwiki", "VimWiki");
+        text_reader(registry, INFO, rescribe_read_vimwiki::parse_with_options);
+        writer(registry, INFO, rescribe_write_vimwiki::emit_with_options);
+    }
+
+    #[cfg(feature = "zimwiki")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("zimwiki", "Zim Desktop Wiki");
+        text_reader(registry, INFO, rescribe_read_zimwiki::parse_with_options);
+        writer(registry, INFO, rescribe_write_zimwiki::emit_with_options);
+    }
+
+    #[cfg(feature = "tikiwiki")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("tikiwiki", "TikiWiki");
+        text_reader(registry, INFO, rescribe_read_tikiwiki::parse_with_options);
+        writer(registry, INFO, rescribe_write_tikiwiki::emit_with_options);
+    }
+
+    #[cfg(feature = "twiki")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("twiki", "TWiki");
+        text_reader(registry, INFO, rescribe_read_twiki::parse_with_options);
+        writer(registry, INFO, rescribe_write_twiki::emit_with_options);
+    }
+
+    #[cfg(feature = "xwiki")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("xwiki", "XWiki");
+        text_reader(registry, INFO, rescribe_read_xwiki::parse_with_options);
+        writer(registry, INFO, rescribe_write_xwiki::emit_with_options);
+    }
+
+    #[cfg(feature = "bbcode")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("bbcode", "BBCode").with_extensions(&["bbcode"]);
+        text_reader(registry, INFO, rescribe_read_bbcode::parse_with_options);
+        writer(registry, INFO, rescribe_write_bbcode::emit_with_options);
+    }
+
+    #[cfg(feature = "fountain")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("fountain", "Fountain screenplay")
+            .with_extensions(&["fountain", "spmd"])
+            .with_mime_types(&["text/x-fountain"]);
+        text_reader(registry, INFO, rescribe_read_fountain::parse_with_options);
+        writer(registry, INFO, rescribe_write_fountain::emit_with_options);
+    }
+
+    #[cfg(feature = "revealjs")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("revealjs", "reveal.js slides")
+            .with_aliases(&["reveal.js"])
+            .with_mime_types(&["text/html"]);
+        writer(registry, INFO, rescribe_write_revealjs::emit_with_options);
+    }
+
+    #[cfg(feature = "slidy")]
+    {
+        const INFO: FormatInfo =
+            FormatInfo::new("slidy", "W3C Slidy slides").with_mime_types(&["text/html"]);
+        writer(registry, INFO, rescribe_write_slidy::emit_with_options);
+    }
+
+    #[cfg(feature = "s5")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("s5", "S5 slides").with_mime_types(&["text/html"]);
+        writer(registry, INFO, rescribe_write_s5::emit_with_options);
+    }
+
+    #[cfg(feature = "dzslides")]
+    {
+        const INFO: FormatInfo =
+            FormatInfo::new("dzslides", "DZSlides slides").with_mime_types(&["text/html"]);
+        writer(registry, INFO, rescribe_write_dzslides::emit_with_options);
+    }
+
+    #[cfg(feature = "slideous")]
+    {
+        const INFO: FormatInfo =
+            FormatInfo::new("slideous", "Slideous slides").with_mime_types(&["text/html"]);
+        writer(registry, INFO, rescribe_write_slideous::emit_with_options);
+    }
+
+    // `chunkedhtml` is not registered: it produces a set of files rather
+    // than a single byte stream.
+
+    #[cfg(feature = "csv")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("csv", "Comma-separated values")
+            .with_extensions(&["csv"])
+            .with_mime_types(&["text/csv"]);
+        text_reader(registry, INFO, rescribe_read_csv::parse_with_options);
+        writer(registry, INFO, rescribe_write_csv::emit_with_options);
+    }
+
+    #[cfg(feature = "tsv")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("tsv", "Tab-separated values")
+            .with_extensions(&["tsv", "tab"])
+            .with_mime_types(&["text/tab-separated-values"]);
+        text_reader(registry, INFO, rescribe_read_tsv::parse_with_options);
+        writer(registry, INFO, rescribe_write_tsv::emit_with_options);
+    }
+
+    #[cfg(feature = "bibtex")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("bibtex", "BibTeX")
+            .with_extensions(&["bib"])
+            .with_mime_types(&["application/x-bibtex"]);
+        text_reader(registry, INFO, |input, _| {
+            rescribe_read_bibtex::parse(input)
+        });
+        writer(registry, INFO, rescribe_write_bibtex::emit_with_options);
+    }
+
+    #[cfg(feature = "biblatex")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("biblatex", "BibLaTeX").with_extensions(&["bib"]);
+        text_reader(registry, INFO, rescribe_read_biblatex::parse_with_options);
+        writer(registry, INFO, rescribe_write_biblatex::emit_with_options);
+    }
+
+    #[cfg(feature = "csl-json")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("csl-json", "CSL JSON")
+            .with_aliases(&["csljson", "csl_json"])
+            .with_mime_types(&["application/vnd.citationstyles.csl+json"]);
+        text_reader(registry, INFO, |input, _| {
+            rescribe_read_csl_json::parse(input)
+        });
+        writer(registry, INFO, |doc, _| rescribe_write_csl_json::emit(doc));
+    }
+
+    #[cfg(feature = "ris")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("ris", "RIS")
+            .with_extensions(&["ris"])
+            .with_mime_types(&["application/x-research-info-systems"]);
+        text_reader(registry, INFO, rescribe_read_ris::parse_with_options);
+        writer(registry, INFO, rescribe_write_ris::emit_with_options);
+    }
+
+    #[cfg(feature = "endnotexml")]
+    {
+        const INFO: FormatInfo =
+            FormatInfo::new("endnotexml", "EndNote XML").with_aliases(&["endnote"]);
+        text_reader(registry, INFO, rescribe_read_endnotexml::parse_with_options);
+        writer(registry, INFO, rescribe_write_endnotexml::emit_with_options);
+    }
+
+    #[cfg(feature = "pandoc-json")]
+    {
+        const INFO: FormatInfo =
+            FormatInfo::new("pandoc-json", "Pandoc JSON AST").with_aliases(&["pandoc"]);
+        text_reader(
+            registry,
+            INFO,
+            rescribe_read_pandoc_json::parse_with_options,
+        );
+        writer(
+            registry,
+            INFO,
+            rescribe_write_pandoc_json::emit_with_options,
+        );
+    }
+
+    #[cfg(feature = "json")]
+    {
+        const INFO: FormatInfo = FormatInfo::new("json", "rescribe JSON")
+            .with_aliases(&["rescribe-json"])
+            .with_extensions(&["json"]);
+        text_reader(registry, INFO, rescribe_read_json::parse_with_options);
+        writer(registry, INFO, rescribe_write_json::emit_with_options);
+    }
+
+    #[cfg(feature = "native")]
+    {
+        const INFO: FormatInfo =
+            FormatInfo::new("native", "rescribe native (debug)").with_extensions(&["native"]);
+        text_reader(registry, INFO, rescribe_read_native::parse_with_options);
+        writer(registry, INFO, rescribe_write_native::emit_with_options);
+    }
+}
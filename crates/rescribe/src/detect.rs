@@ -0,0 +1,1101 @@
+//! Content-based format detection.
+//!
+//! [`detect_format`] looks at the bytes of a document and returns a ranked
+//! list of guesses. Binary containers and well-known signatures (`%PDF-`,
+//! `{\rtf`, ZIP packages, XML root elements, JSON keys) are recognised with
+//! high confidence; lightweight markup languages are scored with line-based
+//! heuristics and never reach the confidence of a real signature.
+//!
+//! ```rust
+//! use rescribe::detect::detect_format;
+//!
+//! let guesses = detect_format(b"{\\rtf1\\ansi Hello}");
+//! assert_eq!(guesses[0].format, "rtf");
+//!
+//! let guesses = detect_format(b"#+TITLE: Notes\n\n* Heading\n");
+//! assert_eq!(guesses[0].format, "org");
+//! ```
+//!
+//! Format names match those used by [`Registry::builtin`](crate::Registry::builtin),
+//! and [`Registry::detect`](crate::Registry::detect) picks the best guess that
+//! has a reader.
+
+/// How much of the input is inspected for text heuristics.
+const SAMPLE_LEN: usize = 64 * 1024;
+
+/// Confidence for magic numbers and container manifests.
+const SIGNATURE: f32 = 0.99;
+/// Confidence for structural markers such as namespaced XML roots.
+const STRUCTURAL: f32 = 0.95;
+/// Upper bound for heuristic (line-scored) guesses.
+const HEURISTIC_MAX: f32 = 0.8;
+/// Confidence given to Markdown when text matches nothing else.
+const FALLBACK: f32 = 0.05;
+
+/// A guessed input format.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Detection {
+    /// Registry name of the format (e.g. `"markdown"`, `"docx"`).
+    pub format: &'static str,
+    /// Confidence between 0.0 and 1.0.
+    pub confidence: f32,
+}
+
+impl Detection {
+    fn new(format: &'static str, confidence: f32) -> Self {
+        Self { format, confidence }
+    }
+}
+
+/// Guess the format of `input`, most likely first.
+///
+/// Returns an empty list for binary data that matches no known signature.
+/// Plain UTF-8 text that matches no heuristic is reported as low-confidence
+/// Markdown, since any text is valid Markdown.
+pub fn detect_format(input: &[u8]) -> Vec<Detection> {
+    let mut guesses = Guesses::default();
+
+    if input.starts_with(b"PK\x03\x04") || input.starts_with(b"PK\x05\x06") {
+        sniff_zip(input, &mut guesses);
+        return guesses.finish();
+    }
+
+    if starts_with_pdf(input) {
+        guesses.add(Detection::new("pdf", SIGNATURE));
+        return guesses.finish();
+    }
+
+    let Some(text) = sample_text(input) else {
+        return guesses.finish();
+    };
+    let trimmed = text.trim_start();
+
+    if trimmed.starts_with("{\\rtf") {
+        guesses.add(Detection::new("rtf", SIGNATURE));
+        return guesses.finish();
+    }
+
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        sniff_json(trimmed, &mut guesses);
+    }
+    if trimmed.starts_with('<') {
+        sniff_xml(trimmed, &mut guesses);
+    }
+    if guesses.best() < STRUCTURAL {
+        sniff_markup(text, &mut guesses);
+    }
+
+    if guesses.is_empty() && !trimmed.is_empty() {
+        guesses.add(Detection::new("markdown", FALLBACK));
+    }
+    guesses.finish()
+}
+
+/// Decode the leading sample of `input` as UTF-8, tolerating a multi-byte
+/// character cut off by the sample boundary.
+fn sample_text(input: &[u8]) -> Option<&str> {
+    let sample = &input[..input.len().min(SAMPLE_LEN)];
+    let text = match std::str::from_utf8(sample) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => {
+            std::str::from_utf8(&sample[..e.valid_up_to()]).ok()?
+        }
+        Err(_) => return None,
+    };
+    if text.contains('\0') {
+        return None;
+    }
+    Some(text.strip_prefix('\u{feff}').unwrap_or(text))
+}
+
+/// Whether `input` starts with the `%PDF-` magic, allowing for a BOM and
+/// leading whitespace.
+fn starts_with_pdf(input: &[u8]) -> bool {
+    let input = input.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(input);
+    input.trim_ascii_start().starts_with(b"%PDF-")
+}
+
+/// Collected guesses, keeping the highest confidence per format.
+#[derive(Default)]
+struct Guesses {
+    detections: Vec<Detection>,
+}
+
+impl Guesses {
+    fn add(&mut self, detection: Detection) {
+        match self
+            .detections
+            .iter_mut()
+            .find(|d| d.format == detection.format)
+        {
+            Some(existing) => existing.confidence = existing.confidence.max(detection.confidence),
+            None => self.detections.push(detection),
+        }
+    }
+
+    fn best(&self) -> f32 {
+        self.detections
+            .iter()
+            .map(|d| d.confidence)
+            .fold(0.0, f32::max)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.detections.is_empty()
+    }
+
+    fn finish(mut self) -> Vec<Detection> {
+        // Stable sort keeps insertion order (signatures first) for ties.
+        self.detections
+            .sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        self.detections
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ZIP containers
+// ---------------------------------------------------------------------------
+
+fn sniff_zip(data: &[u8], guesses: &mut Guesses) {
+    match zip_mimetype(data) {
+        Some(b"application/epub+zip") => {
+            guesses.add(Detection::new("epub", SIGNATURE));
+            return;
+        }
+        Some(b"application/vnd.oasis.opendocument.text") => {
+            guesses.add(Detection::new("odt", SIGNATURE));
+            return;
+        }
+        _ => {}
+    }
+
+    let names = zip_member_names(data);
+    let has = |name: &str| names.contains(&name.as_bytes());
+    let has_prefix = |prefix: &str| names.iter().any(|n| n.starts_with(prefix.as_bytes()));
+
+    if has("word/document.xml") {
+        guesses.add(Detection::new("docx", SIGNATURE));
+    } else if has("ppt/presentation.xml") {
+        guesses.add(Detection::new("pptx", SIGNATURE));
+    } else if has("xl/workbook.xml") {
+        guesses.add(Detection::new("xlsx", SIGNATURE));
+    } else if has("META-INF/container.xml") {
+        guesses.add(Detection::new("epub", STRUCTURAL));
+    } else if has("content.xml") && has("META-INF/manifest.xml") {
+        guesses.add(Detection::new("odt", STRUCTURAL));
+    } else if has("[Content_Types].xml") {
+        // An OOXML package whose main part was not found by its usual name.
+        if has_prefix("word/") {
+            guesses.add(Detection::new("docx", STRUCTURAL));
+        } else if has_prefix("ppt/") {
+            guesses.add(Detection::new("pptx", STRUCTURAL));
+        } else if has_prefix("xl/") {
+            guesses.add(Detection::new("xlsx", STRUCTURAL));
+        }
+    }
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<usize> {
+    let bytes = data.get(at..at + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<usize> {
+    let bytes = data.get(at..at + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+}
+
+/// The contents of a stored `mimetype` entry at the start of the archive, as
+/// required by EPUB and OpenDocument.
+fn zip_mimetype(data: &[u8]) -> Option<&[u8]> {
+    let method = read_u16(data, 8)?;
+    let size = read_u32(data, 18)?;
+    let name_len = read_u16(data, 26)?;
+    let extra_len = read_u16(data, 28)?;
+    if method != 0 || data.get(30..30 + name_len)? != b"mimetype" {
+        return None;
+    }
+    let start = 30 + name_len + extra_len;
+    data.get(start..start + size)
+}
+
+/// Member names from the central directory, falling back to walking local
+/// file headers when the archive is truncated.
+fn zip_member_names(data: &[u8]) -> Vec<&[u8]> {
+    central_directory_names(data).unwrap_or_else(|| local_header_names(data))
+}
+
+fn central_directory_names(data: &[u8]) -> Option<Vec<&[u8]>> {
+    // The end-of-central-directory record is at least 22 bytes and may be
+    // followed by a comment of up to 64 KiB.
+    let search_from = data.len().saturating_sub(22 + 0xFFFF);
+    let eocd = search_from
+        + data[search_from..]
+            .windows(4)
+            .rposition(|w| w == b"PK\x05\x06")?;
+    let count = read_u16(data, eocd + 10)?;
+    let mut pos = read_u32(data, eocd + 16)?;
+
+    let mut names = Vec::with_capacity(count);
+    for _ in 0..count {
+        if data.get(pos..pos + 4)? != b"PK\x01\x02" {
+            break;
+        }
+        let name_len = read_u16(data, pos + 28)?;
+        let extra_len = read_u16(data, pos + 30)?;
+        let comment_len = read_u16(data, pos + 32)?;
+        names.push(data.get(pos + 46..pos + 46 + name_len)?);
+        pos += 46 + name_len + extra_len + comment_len;
+    }
+    Some(names)
+}
+
+fn local_header_names(data: &[u8]) -> Vec<&[u8]> {
+    let mut names = Vec::new();
+    let mut pos = 0;
+    while data.get(pos..pos + 4) == Some(b"PK\x03\x04") {
+        let (Some(flags), Some(size), Some(name_len), Some(extra_len)) = (
+            read_u16(data, pos + 6),
+            read_u32(data, pos + 18),
+            read_u16(data, pos + 26),
+            read_u16(data, pos + 28),
+        ) else {
+            break;
+        };
+        let Some(name) = data.get(pos + 30..pos + 30 + name_len) else {
+            break;
+        };
+        names.push(name);
+        // Bit 3: sizes are stored after the data, so the next header
+        // cannot be located without decompressing.
+        if flags & 0x08 != 0 {
+            break;
+        }
+        pos += 30 + name_len + extra_len + size;
+    }
+    names
+}
+
+// ---------------------------------------------------------------------------
+// JSON
+// ---------------------------------------------------------------------------
+
+fn sniff_json(text: &str, guesses: &mut Guesses) {
+    let first_key = text.strip_prefix('{').map(str::trim_start).unwrap_or("");
+    if first_key.starts_with("\"version\"") && text.contains("\"kind\"") {
+        guesses.add(Detection::new("json", STRUCTURAL));
+    } else if text.contains("\"pandoc-api-version\"") {
+        guesses.add(Detection::new("pandoc-json", STRUCTURAL));
+    } else if text.contains("\"nbformat\"") && text.contains("\"cells\"") {
+        guesses.add(Detection::new("ipynb", STRUCTURAL));
+    } else if text.starts_with('[')
+        && text.contains("\"id\"")
+        && text.contains("\"type\"")
+        && ["\"title\"", "\"author\"", "\"issued\""]
+            .iter()
+            .any(|key| text.contains(key))
+    {
+        guesses.add(Detection::new("csl-json", HEURISTIC_MAX));
+    }
+}
+
+// ---------------------------------------------------------------------------
+// XML
+// ---------------------------------------------------------------------------
+
+/// The root element of an XML document, with its prolog.
+struct XmlRoot<'a> {
+    /// Local name, without any namespace prefix.
+    name: &'a str,
+    /// Raw attribute text of the start tag.
+    attrs: &'a str,
+    /// Raw text of the `<!DOCTYPE ...>` declaration, if any.
+    doctype: &'a str,
+}
+
+fn xml_root(text: &str) -> Option<XmlRoot<'_>> {
+    let mut rest = text;
+    let mut doctype = "";
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix("<?") {
+            rest = &after[after.find("?>")? + 2..];
+        } else if let Some(after) = rest.strip_prefix("<!--") {
+            rest = &after[after.find("-->")? + 3..];
+        } else if rest.len() >= 9 && rest[..9].eq_ignore_ascii_case("<!DOCTYPE") {
+            // An internal subset may contain '>' characters.
+            let end = match (rest.find('['), rest.find('>')) {
+                (Some(open), Some(close)) if open < close => rest.find("]>")? + 2,
+                (_, Some(close)) => close + 1,
+                _ => return None,
+            };
+            doctype = &rest[..end];
+            rest = &rest[end..];
+        } else {
+            break;
+        }
+    }
+
+    let tag = rest.strip_prefix('<')?;
+    let name_end = tag
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(tag.len());
+    let qname = &tag[..name_end];
+    let name = qname.rsplit(':').next().unwrap_or(qname);
+    if name.is_empty() {
+        return None;
+    }
+    let attrs_end = tag[name_end..]
+        .find('>')
+        .map_or(tag.len(), |i| name_end + i);
+    Some(XmlRoot {
+        name,
+        attrs: &tag[name_end..attrs_end],
+        doctype,
+    })
+}
+
+const DOCBOOK_ROOTS: &[&str] = &[
+    "book",
+    "article",
+    "chapter",
+    "part",
+    "set",
+    "section",
+    "appendix",
+    "preface",
+    "reference",
+    "refentry",
+];
+
+fn sniff_xml(text: &str, guesses: &mut Guesses) {
+    let Some(root) = xml_root(text) else {
+        return;
+    };
+    let doctype = root.doctype;
+
+    if root.name.eq_ignore_ascii_case("html") || doctype.eq_ignore_ascii_case("<!DOCTYPE html>") {
+        guesses.add(Detection::new("html", STRUCTURAL));
+        return;
+    }
+
+    match root.name {
+        "TEI" | "TEI.2" | "teiCorpus" => guesses.add(Detection::new("tei", STRUCTURAL)),
+        "FictionBook" => guesses.add(Detection::new("fb2", STRUCTURAL)),
+        "opml" => guesses.add(Detection::new("opml", STRUCTURAL)),
+        "xml" | "records" if text.contains("<record>") || text.contains("<record ") => {
+            guesses.add(Detection::new("endnotexml", 0.9))
+        }
+        "article"
+            if ["JATS", "NLM", "Archiving and Interchange"]
+                .iter()
+                .any(|id| doctype.contains(id))
+                || root.attrs.contains("dtd-version")
+                || root.attrs.contains("article-type") =>
+        {
+            guesses.add(Detection::new("jats", STRUCTURAL))
+        }
+        name if DOCBOOK_ROOTS.contains(&name) => {
+            if root.attrs.contains("docbook.org/ns/docbook") || doctype.contains("DocBook") {
+                guesses.add(Detection::new("docbook", STRUCTURAL));
+            } else if name == "article" {
+                // DocBook 4 and JATS both use a bare <article> root.
+                guesses.add(Detection::new("docbook", 0.6));
+                guesses.add(Detection::new("jats", 0.4));
+            } else {
+                guesses.add(Detection::new("docbook", 0.7));
+            }
+        }
+        _ => {}
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Lightweight markup heuristics
+// ---------------------------------------------------------------------------
+
+/// Accumulated heuristic evidence per format.
+#[derive(Default)]
+struct Scores {
+    scores: Vec<(&'static str, f32)>,
+}
+
+impl Scores {
+    fn add(&mut self, format: &'static str, weight: f32) {
+        match self.scores.iter_mut().find(|(f, _)| *f == format) {
+            Some((_, score)) => *score += weight,
+            None => self.scores.push((format, weight)),
+        }
+    }
+
+    /// Map scores onto `(0, HEURISTIC_MAX)`; more evidence approaches the cap.
+    fn into_detections(self) -> impl Iterator<Item = Detection> {
+        self.scores
+            .into_iter()
+            .filter(|(_, score)| *score > 0.0)
+            .map(|(format, score)| Detection::new(format, HEURISTIC_MAX * score / (score + 3.0)))
+    }
+}
+
+const BIBTEX_TYPES: &[&str] = &[
+    "article",
+    "book",
+    "booklet",
+    "conference",
+    "inbook",
+    "incollection",
+    "inproceedings",
+    "manual",
+    "mastersthesis",
+    "misc",
+    "phdthesis",
+    "proceedings",
+    "techreport",
+    "unpublished",
+];
+
+const BIBLATEX_TYPES: &[&str] = &[
+    "online",
+    "report",
+    "thesis",
+    "mvbook",
+    "collection",
+    "dataset",
+    "software",
+    "patent",
+];
+
+const LATEX_COMMANDS: &[&str] = &[
+    "\\section{",
+    "\\subsection{",
+    "\\chapter{",
+    "\\begin{",
+    "\\end{",
+    "\\usepackage",
+    "\\textbf{",
+    "\\emph{",
+    "\\item ",
+];
+
+const HTML_TAGS: &[&str] = &[
+    "<p>", "<p ", "<div", "<span", "<a href", "<h1", "<h2", "<h3", "<ul>", "<ol>", "<li>",
+    "<table", "<br", "<img ", "<body", "<head",
+];
+
+const BBCODE_TAGS: &[&str] = &[
+    "[b]", "[i]", "[u]", "[url", "[img]", "[quote", "[code]", "[list", "[size=", "[color=",
+];
+
+fn sniff_markup(text: &str, guesses: &mut Guesses) {
+    let first_line = text.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+
+    // Markers that only appear in one format.
+    let signatures: &[(&str, &'static str)] = &[
+        ("TY  -", "ris"),
+        ("\\input texinfo", "texinfo"),
+        ("Content-Type: text/x-zim-wiki", "zimwiki"),
+        ("\\documentclass", "latex"),
+    ];
+    for (marker, format) in signatures {
+        if first_line.starts_with(marker) {
+            guesses.add(Detection::new(format, STRUCTURAL));
+            return;
+        }
+    }
+    let mut head_len = text.len().min(1024);
+    while !text.is_char_boundary(head_len) {
+        head_len -= 1;
+    }
+    let lower_head = text[..head_len].to_ascii_lowercase();
+    if lower_head.trim_start().starts_with("<!doctype html") || lower_head.contains("<html") {
+        guesses.add(Detection::new("html", 0.9));
+        return;
+    }
+
+    let mut scores = Scores::default();
+    if text.contains("\\documentclass") || text.contains("\\begin{document}") {
+        scores.add("latex", 20.0);
+    }
+
+    let mut prev = "";
+    let mut delimited = [0usize; 2]; // comma / tab counts of the first line
+    let mut table_lines = 0usize;
+    let mut table_consistent = true;
+
+    for (index, line) in text.lines().take(2000).enumerate() {
+        let line = line.trim_end();
+        score_line(line, prev, index == 0, &mut scores);
+
+        if !line.is_empty() {
+            let counts = [line.matches(',').count(), line.matches('\t').count()];
+            if table_lines == 0 {
+                delimited = counts;
+            } else {
+                for (seen, count) in delimited.iter_mut().zip(counts) {
+                    if *seen != count {
+                        *seen = 0;
+                    }
+                }
+            }
+            table_consistent &= delimited.iter().any(|&c| c > 0);
+            table_lines += 1;
+        }
+        prev = line;
+    }
+
+    if table_lines >= 2 && table_consistent {
+        if delimited[1] > 0 {
+            guesses.add(Detection::new("tsv", 0.7));
+        } else if delimited[0] > 0 {
+            guesses.add(Detection::new("csv", 0.6));
+        }
+    }
+
+    for detection in scores.into_detections() {
+        guesses.add(detection);
+    }
+}
+
+fn score_line(line: &str, prev: &str, first: bool, scores: &mut Scores) {
+    let trimmed = line.trim_start();
+    let indented = trimmed.len() != line.len();
+
+    // --- Line-start directives -------------------------------------------
+    if let Some(rest) = line.strip_prefix('@') {
+        let kind: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let opens = rest[kind.len()..].trim_start().starts_with(['{', '(']);
+        if opens && BIBLATEX_TYPES.contains(&kind.as_str()) {
+            scores.add("biblatex", 4.0);
+        } else if opens && BIBTEX_TYPES.contains(&kind.as_str()) {
+            scores.add("bibtex", 4.0);
+        } else if [
+            "node",
+            "chapter",
+            "section",
+            "setfilename",
+            "settitle",
+            "bye",
+        ]
+        .contains(&kind.as_str())
+        {
+            scores.add("texinfo", 3.0);
+        }
+    }
+
+    if let Some(rest) = line.strip_prefix('.') {
+        let macro_name: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_uppercase())
+            .collect();
+        if [
+            "TH", "SH", "SS", "PP", "LP", "IP", "TP", "RS", "RE", "B", "I", "BR", "BI", "IR",
+        ]
+        .contains(&macro_name.as_str())
+            && rest[macro_name.len()..]
+                .chars()
+                .next()
+                .is_none_or(char::is_whitespace)
+        {
+            scores.add("man", if macro_name == "TH" { 6.0 } else { 2.0 });
+        }
+    }
+
+    if let Some(rest) = line.strip_prefix('=') {
+        let word: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        if [
+            "head1", "head2", "head3", "head4", "pod", "over", "item", "back", "cut", "encoding",
+        ]
+        .contains(&word.as_str())
+        {
+            scores.add("pod", 3.0);
+        }
+    }
+
+    if let Some(rest) = line.strip_prefix("#+") {
+        let keyword: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .collect();
+        if (!keyword.is_empty() && rest[keyword.len()..].starts_with(':'))
+            || keyword.to_ascii_uppercase().starts_with("BEGIN_")
+        {
+            scores.add("org", 3.0);
+        }
+    }
+    if trimmed == ":PROPERTIES:" || trimmed == ":END:" {
+        scores.add("org", 2.0);
+    }
+
+    if ["#set ", "#show ", "#let ", "#import ", "#include "]
+        .iter()
+        .any(|p| line.starts_with(p))
+    {
+        scores.add("typst", 3.0);
+    }
+
+    if ["#title ", "#author ", "#date ", "#desc "]
+        .iter()
+        .any(|p| line.starts_with(p))
+    {
+        scores.add("muse", 3.0);
+    }
+
+    if line.starts_with("%!") {
+        scores.add("t2t", 3.0);
+    }
+
+    if line.starts_with("---+") {
+        scores.add("twiki", 3.0);
+    }
+    if line.contains("%TOC%") {
+        scores.add("twiki", 2.0);
+    }
+
+    if ["INT. ", "EXT. ", "INT./EXT. ", "I/E "]
+        .iter()
+        .any(|p| line.starts_with(p))
+    {
+        scores.add("fountain", 3.0);
+    }
+    if line == "FADE IN:" || line.starts_with("FADE OUT") {
+        scores.add("fountain", 2.0);
+    }
+
+    // --- Headings ---------------------------------------------------------
+    let hashes = line.chars().take_while(|&c| c == '#').count();
+    if (1..=6).contains(&hashes) && line[hashes..].starts_with(' ') {
+        scores.add("markdown", 1.5);
+    }
+
+    let stars = line.chars().take_while(|&c| c == '*').count();
+    if stars >= 1 && line[stars..].starts_with(' ') {
+        // Org headings, but also Markdown and AsciiDoc bullets.
+        scores.add("org", if stars > 1 { 1.0 } else { 0.5 });
+        scores.add("markdown", 0.3);
+    }
+
+    let equals = line.chars().take_while(|&c| c == '=').count();
+    if (1..=6).contains(&equals) && line[equals..].starts_with(' ') {
+        let closing = line.len() - line.trim_end_matches('=').len();
+        if closing == equals && line.len() > 2 * equals + 1 {
+            // `== Title ==`: MediaWiki (and DokuWiki, which nests the other way).
+            scores.add("mediawiki", 1.5);
+            if equals >= 4 {
+                scores.add("dokuwiki", 1.5);
+            } else {
+                scores.add("dokuwiki", 0.5);
+            }
+        } else if closing == 0 {
+            // `== Title`: AsciiDoc or Typst.
+            scores.add("asciidoc", if first && equals == 1 { 2.0 } else { 1.0 });
+            scores.add("typst", 0.8);
+        }
+    }
+
+    if line.len() > 3
+        && line.as_bytes()[0] == b'h'
+        && (b'1'..=b'6').contains(&line.as_bytes()[1])
+        && line[2..].starts_with(". ")
+    {
+        scores.add("textile", 1.5);
+        scores.add("jira", 1.5);
+    }
+    if ["bq. ", "p. ", "bc. ", "fn1. "]
+        .iter()
+        .any(|p| line.starts_with(p))
+    {
+        scores.add("textile", 2.0);
+    }
+
+    // Setext / reStructuredText section underlines.
+    let prev_text = prev.trim();
+    if !prev_text.is_empty() && line.len() >= 3 && !indented {
+        let mut chars = line.chars();
+        let c = chars.next().unwrap_or(' ');
+        let uniform = chars.all(|d| d == c);
+        let adornment = "=-~^\"'*+#`:.".contains(c) && !c.is_alphanumeric();
+        if uniform && adornment && !prev_text.starts_with(c) {
+            if c == '=' || c == '-' {
+                scores.add("markdown", 1.0);
+                if line.chars().count() >= prev_text.chars().count() {
+                    scores.add("rst", 1.0);
+                }
+            } else if line.chars().count() >= prev_text.chars().count() {
+                scores.add("rst", 2.5);
+            }
+        }
+    }
+
+    // --- Block markers ----------------------------------------------------
+    if line.starts_with("```") || line.starts_with("~~~") {
+        scores.add("markdown", 2.0);
+    }
+    if line.starts_with("> ") || line == ">" {
+        scores.add("markdown", 0.5);
+    }
+    if (trimmed.starts_with("- ") || trimmed.starts_with("+ ")) && !indented {
+        scores.add("markdown", 0.3);
+    }
+    if line.starts_with("  * ") || line.starts_with("  - ") {
+        scores.add("dokuwiki", 0.8);
+    }
+
+    if let Some(rest) = line.strip_prefix(".. ") {
+        if rest.contains("::") {
+            scores.add("rst", 3.0);
+        } else if rest.starts_with('_') || rest.starts_with('[') || rest.starts_with('|') {
+            scores.add("rst", 2.0);
+        }
+    }
+    if line.ends_with(" ::") || (line.ends_with("::") && line.contains(' ')) {
+        scores.add("rst", 0.5);
+    }
+
+    if ["image::", "include::", "ifdef::", "toc::"]
+        .iter()
+        .any(|p| line.starts_with(p))
+    {
+        scores.add("asciidoc", 2.5);
+    }
+    if ["NOTE: ", "TIP: ", "WARNING: ", "IMPORTANT: ", "CAUTION: "]
+        .iter()
+        .any(|p| line.starts_with(p))
+    {
+        scores.add("asciidoc", 1.5);
+    }
+    if line.starts_with('[')
+        && line.ends_with(']')
+        && [
+            "[source", "[NOTE", "[TIP", "[quote", "[verse", "[listing", "[cols=",
+        ]
+        .iter()
+        .any(|p| line.starts_with(p))
+    {
+        scores.add("asciidoc", 2.0);
+    }
+    if let Some(rest) = line.strip_prefix(':') {
+        let name_len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(rest.len());
+        if name_len > 0 && rest[name_len..].starts_with(':') {
+            // AsciiDoc attribute entries; reStructuredText field lists.
+            scores.add("asciidoc", 1.0);
+            scores.add("rst", 0.5);
+        }
+    }
+
+    if line.starts_with("{|") {
+        scores.add("mediawiki", 3.0);
+    }
+    if line.starts_with('^') && line.ends_with('^') && line.len() > 2 {
+        scores.add("dokuwiki", 2.0);
+    }
+    if line.starts_with("||") {
+        scores.add("jira", 1.5);
+    }
+    if line.starts_with("|=") {
+        scores.add("creole", 2.0);
+    }
+    if ["{code", "{noformat", "{quote}", "{panel"]
+        .iter()
+        .any(|p| line.starts_with(p))
+    {
+        scores.add("jira", 3.0);
+    }
+    if line.starts_with("{{{") || line.starts_with("}}}") {
+        scores.add("creole", 1.0);
+    }
+    if line.starts_with("{{code") || line.starts_with("{{/code}}") {
+        scores.add("xwiki", 3.0);
+    }
+
+    // --- Inline markers ---------------------------------------------------
+    if let Some(open) = line.find("](")
+        && line[..open].contains('[')
+        && line[open..].contains(')')
+    {
+        scores.add("markdown", 1.0);
+    }
+    if line.contains("``") && !line.starts_with("```") {
+        scores.add("rst", 0.3);
+    }
+    if line.contains(":ref:`") || line.contains(":doc:`") || line.contains(":math:`") {
+        scores.add("rst", 2.0);
+    }
+    if line.contains("'''") {
+        scores.add("mediawiki", 1.0);
+    }
+    if line.contains("{{") && line.contains("}}") {
+        scores.add("mediawiki", 0.5);
+        scores.add("dokuwiki", 0.5);
+    }
+    if line.contains("[[") && line.contains("]]") {
+        scores.add("mediawiki", 0.5);
+        scores.add("dokuwiki", 0.5);
+        scores.add("org", 0.3);
+    }
+    if line.contains("link:") || line.contains("xref:") || line.contains("<<") {
+        scores.add("asciidoc", 0.5);
+    }
+    if line.contains("\":http") {
+        scores.add("textile", 1.5);
+    }
+    for command in LATEX_COMMANDS {
+        if line.contains(command) {
+            scores.add("latex", 1.5);
+        }
+    }
+    for tag in HTML_TAGS {
+        if line.contains(tag) {
+            scores.add("html", 1.0);
+        }
+    }
+    for tag in BBCODE_TAGS {
+        if line.contains(tag) {
+            scores.add("bbcode", 1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn best(input: &[u8]) -> Option<&'static str> {
+        detect_format(input).first().map(|d| d.format)
+    }
+
+    /// Build a minimal stored ZIP archive with the given members.
+    fn zip(members: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for (name, data) in members {
+            let offset = out.len() as u32;
+            let mut header = Vec::new();
+            header.extend_from_slice(&[0, 0, 0, 0]); // version, flags
+            header.extend_from_slice(&[0, 0]); // method: stored
+            header.extend_from_slice(&[0; 8]); // time, date, crc
+            header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            header.extend_from_slice(&[0, 0]); // extra length
+
+            out.extend_from_slice(b"PK\x03\x04");
+            out.extend_from_slice(&header);
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(data);
+
+            central.extend_from_slice(b"PK\x01\x02");
+            central.extend_from_slice(&[0, 0]); // version made by
+            central.extend_from_slice(&header);
+            central.extend_from_slice(&[0; 10]); // comment, disk, attrs
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+        let cd_offset = out.len() as u32;
+        out.extend_from_slice(&central);
+        out.extend_from_slice(b"PK\x05\x06");
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&(members.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(members.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        out.extend_from_slice(&cd_offset.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out
+    }
+
+    #[test]
+    fn test_zip_containers() {
+        let docx = zip(&[
+            ("[Content_Types].xml", b"<Types/>"),
+            ("word/document.xml", b"<w:document/>"),
+        ]);
+        assert_eq!(best(&docx), Some("docx"));
+
+        let pptx = zip(&[("ppt/presentation.xml", b"")]);
+        assert_eq!(best(&pptx), Some("pptx"));
+
+        let xlsx = zip(&[("xl/workbook.xml", b"")]);
+        assert_eq!(best(&xlsx), Some("xlsx"));
+
+        let epub = zip(&[
+            ("mimetype", b"application/epub+zip"),
+            ("META-INF/container.xml", b""),
+        ]);
+        assert_eq!(
+            detect_format(&epub),
+            vec![Detection::new("epub", SIGNATURE)]
+        );
+
+        let odt = zip(&[
+            ("mimetype", b"application/vnd.oasis.opendocument.text"),
+            ("content.xml", b""),
+        ]);
+        assert_eq!(best(&odt), Some("odt"));
+
+        assert_eq!(best(&zip(&[("readme.txt", b"hi")])), None);
+    }
+
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        haystack.windows(needle.len()).position(|w| w == needle)
+    }
+
+    #[test]
+    fn test_truncated_zip_uses_local_headers() {
+        let mut docx = zip(&[("word/document.xml", b"<w:document/>")]);
+        let cd = find(&docx, b"PK\x01\x02").unwrap();
+        docx.truncate(cd);
+        assert_eq!(best(&docx), Some("docx"));
+    }
+
+    #[test]
+    fn test_non_ascii_across_head_boundary() {
+        let mut text = "a".repeat(1023);
+        text.push_str("ä\n");
+        assert_eq!(best(text.as_bytes()), Some("markdown"));
+    }
+
+    #[test]
+    fn test_signatures() {
+        assert_eq!(best(b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n"), Some("pdf"));
+        assert_eq!(best(b"\xEF\xBB\xBF\r\n%PDF-1.4\n"), Some("pdf"));
+        assert_eq!(
+            best(b"# PDF notes\n\nA PDF file starts with the `%PDF-1.7` header.\n"),
+            Some("markdown")
+        );
+        assert_eq!(best(b"{\\rtf1\\ansi\\deff0 Hello}"), Some("rtf"));
+        assert_eq!(best(b"\xff\xd8\xff\xe0\x00\x10JFIF"), None);
+        assert_eq!(best(b""), None);
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(
+            best(b"{\n  \"version\": 1,\n  \"content\": {\"kind\": \"document\"}\n}"),
+            Some("json")
+        );
+        assert_eq!(
+            best(br#"{"pandoc-api-version":[1,23],"meta":{},"blocks":[]}"#),
+            Some("pandoc-json")
+        );
+        assert_eq!(
+            best(br#"{"cells": [], "metadata": {}, "nbformat": 4, "nbformat_minor": 5}"#),
+            Some("ipynb")
+        );
+        assert_eq!(
+            best(br#"[{"id": "doe2020", "type": "book", "title": "A Book"}]"#),
+            Some("csl-json")
+        );
+    }
+
+    #[test]
+    fn test_xml_roots() {
+        let cases: &[(&str, &str)] = &[
+            (
+                r#"<?xml version="1.0"?><book xmlns="http://docbook.org/ns/docbook" version="5.0"/>"#,
+                "docbook",
+            ),
+            (
+                "<?xml version=\"1.0\"?>\n<!DOCTYPE article PUBLIC \"-//NLM//DTD JATS (Z39.96) Journal Publishing DTD v1.2 20190208//EN\" \"JATS-journalpublishing1.dtd\">\n<article article-type=\"research-article\"/>",
+                "jats",
+            ),
+            (r#"<TEI xmlns="http://www.tei-c.org/ns/1.0"/>"#, "tei"),
+            (
+                r#"<?xml version="1.0" encoding="UTF-8"?><FictionBook xmlns="http://www.gribuser.ru/xml/fictionbook/2.0"/>"#,
+                "fb2",
+            ),
+            (
+                r#"<?xml version="1.0"?><!-- feed list --><opml version="2.0"/>"#,
+                "opml",
+            ),
+            (
+                "<xml><records><record><ref-type name=\"Book\">6</ref-type></record></records></xml>",
+                "endnotexml",
+            ),
+            (
+                "<!DOCTYPE html>\n<html><body><p>Hi</p></body></html>",
+                "html",
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(best(input.as_bytes()), Some(*expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_bare_article_prefers_docbook() {
+        let guesses = detect_format(b"<article><title>T</title></article>");
+        let formats: Vec<_> = guesses.iter().map(|d| d.format).collect();
+        assert_eq!(formats, ["docbook", "jats"]);
+    }
+
+    #[test]
+    fn test_lightweight_markup() {
+        let cases: &[(&str, &str)] = &[
+            (
+                "# Title\n\nSome *text* with a [link](http://example.com).\n\n```rust\nfn main() {}\n```\n",
+                "markdown",
+            ),
+            (
+                "Title\n=====\n\nSection\n-------\n\n.. note::\n\n   Be careful.\n",
+                "rst",
+            ),
+            (
+                "= Document Title\n:toc:\n\n== Section\n\n[source,rust]\n----\nfn main() {}\n----\n",
+                "asciidoc",
+            ),
+            (
+                "#+TITLE: Notes\n\n* Heading\n** Sub heading\n#+BEGIN_SRC sh\nls\n#+END_SRC\n",
+                "org",
+            ),
+            (
+                "\\section{Intro}\nSome \\emph{text}.\n\\begin{itemize}\n\\item One\n\\end{itemize}\n",
+                "latex",
+            ),
+            ("#set page(width: 10cm)\n= Heading\n#let x = 1\n", "typst"),
+            (
+                "== History ==\n'''Rust''' is a [[programming language]].\n{| class=\"wikitable\"\n|}\n",
+                "mediawiki",
+            ),
+            ("h1. Title\n\n{code:java}\nint x;\n{code}\n", "jira"),
+            ("h1. Title\n\nbq. A quote\n", "textile"),
+            (".TH LS 1\n.SH NAME\nls \\- list\n", "man"),
+            ("=head1 NAME\n\nFoo\n\n=cut\n", "pod"),
+            (
+                "@article{doe2020,\n  title = {A Title},\n  year = 2020\n}\n",
+                "bibtex",
+            ),
+            ("@online{site,\n  url = {http://x}\n}\n", "biblatex"),
+            ("TY  - JOUR\nTI  - A Title\nER  - \n", "ris"),
+            ("\\input texinfo\n@settitle Manual\n", "texinfo"),
+            ("name\tage\nalice\t30\nbob\t25\n", "tsv"),
+            ("name,age\nalice,30\nbob,25\n", "csv"),
+            ("INT. HOUSE - DAY\n\nJOHN\nHello.\n", "fountain"),
+            ("[b]Bold[/b] and [url=http://x]link[/url]\n", "bbcode"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(best(input.as_bytes()), Some(*expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_plain_text_falls_back_to_markdown() {
+        let guesses = detect_format(b"Just some words.\n");
+        assert_eq!(guesses, vec![Detection::new("markdown", FALLBACK)]);
+    }
+
+    #[test]
+    fn test_heuristics_stay_below_signatures() {
+        let markdown = "# A\n".repeat(500);
+        let guess = detect_format(markdown.as_bytes())[0];
+        assert_eq!(guess.format, "markdown");
+        assert!(guess.confidence < HEURISTIC_MAX);
+    }
+}
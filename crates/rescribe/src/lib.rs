@@ -33,8 +33,11 @@
 //! - `plaintext` - Plain text writer
 //! - `pdf` - PDF reader
 //! - `docx` - DOCX (Word) reader/writer
+//! - `pandoc-json` - Pandoc JSON AST reader/writer
+//! - `json` - rescribe JSON (lossless serialized IR) reader/writer
 //! - `std` - Standard node kinds (default)
 //! - `math` - Math node kinds
+//! - `bib` - Bibliography node kinds
 //! - `all` - Enable all formats
 //!
 //! # Architecture
@@ -46,10 +49,24 @@
 //!
 //! Format-specific crates implement parsers (readers) and emitters (writers)
 //! that convert between bytes and the document IR.
+//!
+//! # Format Registry
+//!
+//! [`Registry::builtin`] collects every enabled reader and writer behind the
+//! core [`Parser`] and [`Emitter`] traits, so formats can be chosen at runtime
+//! by name, alias, file extension or MIME type. When none of those is known,
+//! [`detect::detect_format`] guesses the format from the content itself.
 
 // Re-export core types
 pub use rescribe_core::*;
 
+mod builtin;
+pub mod detect;
+pub mod registry;
+
+pub use detect::{Detection, detect_format};
+pub use registry::{FormatEntry, FormatInfo, FormatSpecError, Registry};
+
 /// Standard node kinds and helpers.
 #[cfg(feature = "std")]
 pub mod std {
@@ -62,9 +79,17 @@ pub mod math {
     pub use rescribe_math::*;
 }
 
+/// Bibliography node kinds.
+#[cfg(feature = "bib")]
+pub mod bib {
+    pub use rescribe_bib::*;
+}
+
 /// Markdown format support.
 #[cfg(feature = "markdown")]
 pub mod markdown {
+    #[cfg(feature = "markdown")]
+    pub use rescribe_read_markdown::options as reader_options;
     #[cfg(feature = "markdown")]
     pub use rescribe_read_markdown::parse;
     #[cfg(feature = "markdown")]
@@ -73,6 +98,8 @@ pub mod markdown {
     #[cfg(feature = "markdown")]
     pub use rescribe_write_markdown::emit;
     #[cfg(feature = "markdown")]
+    pub use rescribe_write_markdown::emit_to;
+    #[cfg(feature = "markdown")]
     pub use rescribe_write_markdown::emit_with_options;
 }
 
@@ -89,7 +116,13 @@ pub mod html {
     #[cfg(feature = "html")]
     pub use rescribe_write_html::emit_full_document;
     #[cfg(feature = "html")]
+    pub use rescribe_write_html::emit_full_document_to;
+    #[cfg(feature = "html")]
+    pub use rescribe_write_html::emit_to;
+    #[cfg(feature = "html")]
     pub use rescribe_write_html::emit_with_options;
+    #[cfg(feature = "html")]
+    pub use rescribe_write_html::options as writer_options;
 }
 
 /// LaTeX format support.
@@ -99,7 +132,11 @@ pub mod latex {
     pub use rescribe_read_latex::parse_with_options;
     pub use rescribe_write_latex::emit;
     pub use rescribe_write_latex::emit_full_document;
+    pub use rescribe_write_latex::emit_full_document_to;
+    pub use rescribe_write_latex::emit_full_document_with_options;
+    pub use rescribe_write_latex::emit_to;
     pub use rescribe_write_latex::emit_with_options;
+    pub use rescribe_write_latex::options as writer_options;
 }
 
 /// Org-mode format support.
@@ -115,7 +152,9 @@ pub mod org {
 #[cfg(feature = "plaintext")]
 pub mod plaintext {
     pub use rescribe_write_plaintext::emit;
+    pub use rescribe_write_plaintext::emit_to;
     pub use rescribe_write_plaintext::emit_with_options;
+    pub use rescribe_write_plaintext::options as writer_options;
 }
 
 /// PDF format support (reader only).
@@ -159,6 +198,8 @@ pub mod epub {
     pub use rescribe_read_epub::parse_bytes;
     pub use rescribe_read_epub::parse_file;
     pub use rescribe_write_epub::emit;
+    pub use rescribe_write_epub::emit_with_options;
+    pub use rescribe_write_epub::options as writer_options;
 }
 
 /// Djot format support.
@@ -201,6 +242,9 @@ pub mod csl_json {
 pub mod docbook {
     pub use rescribe_read_docbook::parse;
     pub use rescribe_write_docbook::emit;
+    pub use rescribe_write_docbook::emit_to;
+    pub use rescribe_write_docbook::emit_with_options;
+    pub use rescribe_write_docbook::options as writer_options;
 }
 
 /// reStructuredText format support.
@@ -251,6 +295,9 @@ pub mod dokuwiki {
 pub mod jats {
     pub use rescribe_read_jats::parse;
     pub use rescribe_write_jats::emit;
+    pub use rescribe_write_jats::emit_to;
+    pub use rescribe_write_jats::emit_with_options;
+    pub use rescribe_write_jats::options as writer_options;
 }
 
 /// TEI (Text Encoding Initiative) format support.
@@ -258,6 +305,8 @@ pub mod jats {
 pub mod tei {
     pub use rescribe_read_tei::parse;
     pub use rescribe_write_tei::emit;
+    pub use rescribe_write_tei::emit_with_options;
+    pub use rescribe_write_tei::options as writer_options;
 }
 
 /// Man page (roff/troff) format support.
@@ -616,6 +665,28 @@ pub mod fountain {
     pub use rescribe_write_fountain::emit_with_options;
 }
 
+/// Pandoc JSON AST format support.
+#[cfg(feature = "pandoc-json")]
+pub mod pandoc_json {
+    pub use rescribe_read_pandoc_json::parse;
+    pub use rescribe_read_pandoc_json::parse_with_options;
+    pub use rescribe_write_pandoc_json::emit;
+    pub use rescribe_write_pandoc_json::emit_with_options;
+}
+
+/// rescribe JSON (lossless serialized document IR) support.
+#[cfg(feature = "json")]
+pub mod json {
+    pub use rescribe_read_json::parse;
+    pub use rescribe_read_json::parse_file;
+    pub use rescribe_read_json::parse_with_loader;
+    pub use rescribe_read_json::parse_with_options;
+    pub use rescribe_write_json::emit;
+    pub use rescribe_write_json::emit_external;
+    pub use rescribe_write_json::emit_with_options;
+    pub use rescribe_write_json::{ExternalOutput, ExternalResource};
+}
+
 /// Common imports for typical usage.
 pub mod prelude {
     pub use crate::{ConversionResult, Document, Node, PropValue, Properties};
@@ -668,4 +739,43 @@ mod tests {
 
         assert_eq!(document.content.children.len(), 2);
     }
+
+    /// Records each write so tests can check output is streamed.
+    #[derive(Default)]
+    struct Chunks(Vec<Vec<u8>>);
+
+    impl ::std::io::Write for Chunks {
+        fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+            self.0.push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> ::std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_streaming_writers() {
+        use crate::std::builder::doc;
+
+        let document = doc(|d| {
+            d.heading(1, |i| i.text("Title"))
+                .para(|i| i.text("One"))
+                .para(|i| i.text("Two"))
+        });
+        let registry = Registry::builtin();
+        let options = EmitOptions::default();
+        for format in ["markdown", "html", "latex", "plaintext", "docbook", "jats"] {
+            let Some(emitter) = registry.format(format).and_then(|f| f.emitter()) else {
+                continue;
+            };
+            let mut sink = Chunks::default();
+            emitter.emit_to(&document, &options, &mut sink).unwrap();
+            assert!(sink.0.len() > 1, "{format} output is not streamed");
+            let buffered = emitter.emit(&document, &options).unwrap().value;
+            assert_eq!(sink.0.concat(), buffered, "{format}");
+        }
+    }
 }
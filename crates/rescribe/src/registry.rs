@@ -0,0 +1,693 @@
+//! Format registry - look up readers and writers by name, extension or MIME type.
+//!
+//! Every format compiled into the crate (via Cargo features) is registered in
+//! [`Registry::builtin`], so callers can select a parser or emitter at runtime
+//! without knowing which formats were enabled:
+//!
+//! ```rust
+//! use rescribe::{ParseOptions, Registry};
+//!
+//! let registry = Registry::builtin();
+//! let parser = registry.parser_for("markdown").unwrap();
+//! let doc = parser.parse(b"# Hello", &ParseOptions::default()).unwrap();
+//! assert_eq!(doc.value.content.children.len(), 1);
+//! ```
+//!
+//! Formats can also be named with Pandoc-style option modifiers, which are
+//! checked against the options the reader or writer declares:
+//!
+//! ```rust
+//! use rescribe::Registry;
+//!
+//! let registry = Registry::builtin();
+//! let (parser, options) = registry.parser_for_spec("markdown-footnotes").unwrap();
+//! assert!(parser.options().iter().any(|o| o.name == "footnotes"));
+//! assert!(!options.format.is_empty());
+//! assert!(registry.parser_for_spec("markdown+no_such_option").is_err());
+//! ```
+
+use crate::{
+    ConversionResult, Document, EmitError, EmitOptions, Emitter, FormatOptions, OptionError,
+    OptionSpec, ParseError, ParseOptions, Parser,
+};
+use std::io::Write;
+use std::path::Path;
+
+/// Static description of a document format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatInfo {
+    /// Canonical format name (e.g., "markdown", "rst").
+    pub name: &'static str,
+    /// Human-readable format name.
+    pub description: &'static str,
+    /// Alternative names accepted by lookups.
+    pub aliases: &'static [&'static str],
+    /// File extensions, without the leading dot.
+    pub extensions: &'static [&'static str],
+    /// MIME types, most specific first.
+    pub mime_types: &'static [&'static str],
+    /// Whether the format is binary rather than UTF-8 text.
+    pub binary: bool,
+}
+
+impl FormatInfo {
+    /// Create a text format description with no aliases, extensions or MIME types.
+    pub const fn new(name: &'static str, description: &'static str) -> Self {
+        Self {
+            name,
+            description,
+            aliases: &[],
+            extensions: &[],
+            mime_types: &[],
+            binary: false,
+        }
+    }
+
+    /// Set the alternative names.
+    pub const fn with_aliases(mut self, aliases: &'static [&'static str]) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Set the file extensions.
+    pub const fn with_extensions(mut self, extensions: &'static [&'static str]) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Set the MIME types.
+    pub const fn with_mime_types(mut self, mime_types: &'static [&'static str]) -> Self {
+        self.mime_types = mime_types;
+        self
+    }
+
+    /// Mark the format as binary.
+    pub const fn binary(mut self) -> Self {
+        self.binary = true;
+        self
+    }
+
+    /// Check whether `name` is the canonical name or an alias (case-insensitive).
+    pub fn matches_name(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+            || self.aliases.iter().any(|a| a.eq_ignore_ascii_case(name))
+    }
+
+    /// Check whether `ext` is one of the format's extensions (case-insensitive,
+    /// leading dot optional).
+    pub fn matches_extension(&self, ext: &str) -> bool {
+        let ext = ext.strip_prefix('.').unwrap_or(ext);
+        self.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext))
+    }
+
+    /// Check whether `mime` is exactly one of the format's MIME types,
+    /// including parameters (case-insensitive).
+    pub fn matches_mime(&self, mime: &str) -> bool {
+        let mime = normalize_mime(mime);
+        self.mime_types.iter().any(|m| normalize_mime(m) == mime)
+    }
+
+    /// Check whether `mime` has the same type/subtype as one of the format's
+    /// MIME types, ignoring parameters.
+    fn matches_mime_essence(&self, mime: &str) -> bool {
+        let essence = mime_essence(mime);
+        self.mime_types.iter().any(|m| mime_essence(m) == essence)
+    }
+}
+
+fn normalize_mime(mime: &str) -> String {
+    mime.split(';')
+        .map(|part| part.trim().to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn mime_essence(mime: &str) -> String {
+    mime.split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// A registered format with its optional reader and writer.
+pub struct FormatEntry {
+    info: FormatInfo,
+    parser: Option<Box<dyn Parser>>,
+    emitter: Option<Box<dyn Emitter>>,
+}
+
+impl FormatEntry {
+    /// Get the format description.
+    pub fn info(&self) -> &FormatInfo {
+        &self.info
+    }
+
+    /// Get the canonical format name.
+    pub fn name(&self) -> &'static str {
+        self.info.name
+    }
+
+    /// Get the reader for this format, if one is registered.
+    pub fn parser(&self) -> Option<&dyn Parser> {
+        self.parser.as_deref()
+    }
+
+    /// Get the writer for this format, if one is registered.
+    pub fn emitter(&self) -> Option<&dyn Emitter> {
+        self.emitter.as_deref()
+    }
+
+    /// Check if this format can be read.
+    pub fn can_read(&self) -> bool {
+        self.parser.is_some()
+    }
+
+    /// Check if this format can be written.
+    pub fn can_write(&self) -> bool {
+        self.emitter.is_some()
+    }
+}
+
+/// Error resolving a format spec such as `markdown+smart`.
+#[derive(Debug, thiserror::Error)]
+pub enum FormatSpecError {
+    #[error("unknown format '{0}'")]
+    UnknownFormat(String),
+    #[error("no reader available for {0} format")]
+    NoReader(&'static str),
+    #[error("no writer available for {0} format")]
+    NoWriter(&'static str),
+    #[error("{format}: {source}")]
+    Option {
+        format: &'static str,
+        source: OptionError,
+    },
+}
+
+/// A collection of formats with their readers and writers.
+///
+/// Formats are kept in registration order; when several formats share an
+/// extension or MIME type, lookups return the first one registered.
+#[derive(Default)]
+pub struct Registry {
+    entries: Vec<FormatEntry>,
+}
+
+impl Registry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Create a registry containing every format enabled by Cargo features.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        crate::builtin::register(&mut registry);
+        registry
+    }
+
+    /// Register a reader for a format.
+    ///
+    /// If a format with the same name is already registered, its reader is
+    /// replaced and its description is kept.
+    pub fn register_parser(&mut self, info: FormatInfo, parser: impl Parser + 'static) {
+        self.entry_mut(info).parser = Some(Box::new(parser));
+    }
+
+    /// Register a writer for a format.
+    ///
+    /// If a format with the same name is already registered, its writer is
+    /// replaced and its description is kept.
+    pub fn register_emitter(&mut self, info: FormatInfo, emitter: impl Emitter + 'static) {
+        self.entry_mut(info).emitter = Some(Box::new(emitter));
+    }
+
+    fn entry_mut(&mut self, info: FormatInfo) -> &mut FormatEntry {
+        let index = match self.entries.iter().position(|e| e.info.name == info.name) {
+            Some(index) => index,
+            None => {
+                self.entries.push(FormatEntry {
+                    info,
+                    parser: None,
+                    emitter: None,
+                });
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[index]
+    }
+
+    /// Look up a format by name or alias.
+    pub fn format(&self, name: &str) -> Option<&FormatEntry> {
+        self.entries.iter().find(|e| e.info.matches_name(name))
+    }
+
+    /// Look up a format from a spec such as `markdown+smart-footnotes`,
+    /// returning the entry and the option modifiers that follow its name.
+    ///
+    /// The longest prefix naming a registered format wins, so names that
+    /// contain `-` (such as `markdown-strict`) are recognised.
+    pub fn format_for_spec<'s>(&self, spec: &'s str) -> Option<(&FormatEntry, &'s str)> {
+        let mut ends: Vec<usize> = spec.match_indices(['+', '-']).map(|(i, _)| i).collect();
+        ends.push(spec.len());
+        ends.into_iter()
+            .rev()
+            .find_map(|end| Some((self.format(&spec[..end])?, &spec[end..])))
+    }
+
+    /// Get the reader for a format spec, with its options parsed from the
+    /// spec's modifiers.
+    pub fn parser_for_spec(
+        &self,
+        spec: &str,
+    ) -> Result<(&dyn Parser, ParseOptions), FormatSpecError> {
+        let (entry, modifiers) = self.resolve_spec(spec)?;
+        let parser = entry
+            .parser()
+            .ok_or(FormatSpecError::NoReader(entry.name()))?;
+        let options = ParseOptions {
+            format: parse_modifiers(entry, parser.options(), modifiers)?,
+            ..Default::default()
+        };
+        Ok((parser, options))
+    }
+
+    /// Get the writer for a format spec, with its options parsed from the
+    /// spec's modifiers.
+    pub fn emitter_for_spec(
+        &self,
+        spec: &str,
+    ) -> Result<(&dyn Emitter, EmitOptions), FormatSpecError> {
+        let (entry, modifiers) = self.resolve_spec(spec)?;
+        let emitter = entry
+            .emitter()
+            .ok_or(FormatSpecError::NoWriter(entry.name()))?;
+        let options = EmitOptions {
+            format: parse_modifiers(entry, emitter.options(), modifiers)?,
+            ..Default::default()
+        };
+        Ok((emitter, options))
+    }
+
+    fn resolve_spec<'s>(&self, spec: &'s str) -> Result<(&FormatEntry, &'s str), FormatSpecError> {
+        self.format_for_spec(spec).ok_or_else(|| {
+            let name = spec.split(['+', '-']).next().unwrap_or(spec);
+            FormatSpecError::UnknownFormat(name.to_string())
+        })
+    }
+
+    /// Look up a format by file extension.
+    pub fn format_for_extension(&self, ext: &str) -> Option<&FormatEntry> {
+        self.entries.iter().find(|e| e.info.matches_extension(ext))
+    }
+
+    /// Look up a format from a file path's extension.
+    pub fn format_for_path(&self, path: impl AsRef<Path>) -> Option<&FormatEntry> {
+        let ext = path.as_ref().extension()?.to_str()?;
+        self.format_for_extension(ext)
+    }
+
+    /// Look up a format by MIME type.
+    ///
+    /// An exact match (including parameters such as `variant=GFM`) is
+    /// preferred; otherwise parameters are ignored.
+    pub fn format_for_mime(&self, mime: &str) -> Option<&FormatEntry> {
+        self.entries
+            .iter()
+            .find(|e| e.info.matches_mime(mime))
+            .or_else(|| {
+                self.entries
+                    .iter()
+                    .find(|e| e.info.matches_mime_essence(mime))
+            })
+    }
+
+    /// Guess the format of `input` from its content.
+    ///
+    /// Returns the most likely format that has a reader, together with the
+    /// detector's confidence. See [`detect_format`](crate::detect::detect_format).
+    pub fn detect(&self, input: &[u8]) -> Option<(&FormatEntry, f32)> {
+        crate::detect::detect_format(input)
+            .into_iter()
+            .find_map(|guess| {
+                let entry = self.format(guess.format).filter(|e| e.can_read())?;
+                Some((entry, guess.confidence))
+            })
+    }
+
+    /// Get the reader for a format name or alias.
+    pub fn parser_for(&self, name: &str) -> Option<&dyn Parser> {
+        self.format(name)?.parser()
+    }
+
+    /// Get the writer for a format name or alias.
+    pub fn emitter_for(&self, name: &str) -> Option<&dyn Emitter> {
+        self.format(name)?.emitter()
+    }
+
+    /// Iterate over all registered formats.
+    pub fn formats(&self) -> impl Iterator<Item = &FormatEntry> {
+        self.entries.iter()
+    }
+
+    /// Iterate over formats that have a reader.
+    pub fn readers(&self) -> impl Iterator<Item = &FormatEntry> {
+        self.entries.iter().filter(|e| e.can_read())
+    }
+
+    /// Iterate over formats that have a writer.
+    pub fn writers(&self) -> impl Iterator<Item = &FormatEntry> {
+        self.entries.iter().filter(|e| e.can_write())
+    }
+}
+
+fn parse_modifiers(
+    entry: &FormatEntry,
+    specs: &[OptionSpec],
+    modifiers: &str,
+) -> Result<FormatOptions, FormatSpecError> {
+    FormatOptions::parse(specs, modifiers).map_err(|source| FormatSpecError::Option {
+        format: entry.name(),
+        source,
+    })
+}
+
+/// Reader entry point taking raw bytes.
+pub type ParseBytesFn = fn(&[u8], &ParseOptions) -> Result<ConversionResult<Document>, ParseError>;
+
+/// Reader entry point taking UTF-8 text.
+pub type ParseTextFn = fn(&str, &ParseOptions) -> Result<ConversionResult<Document>, ParseError>;
+
+/// Writer entry point.
+pub type EmitFn = fn(&Document, &EmitOptions) -> Result<ConversionResult<Vec<u8>>, EmitError>;
+
+/// Streaming writer entry point.
+pub type EmitToFn =
+    fn(&Document, &EmitOptions, &mut dyn Write) -> Result<ConversionResult<()>, EmitError>;
+
+/// A [`Parser`] backed by a reader function.
+pub struct FnParser {
+    formats: [&'static str; 1],
+    options: &'static [OptionSpec],
+    func: ParseFnKind,
+}
+
+enum ParseFnKind {
+    Bytes(ParseBytesFn),
+    Text(ParseTextFn),
+}
+
+impl FnParser {
+    /// Wrap a reader that takes raw bytes.
+    pub fn bytes(format: &'static str, func: ParseBytesFn) -> Self {
+        Self {
+            formats: [format],
+            options: &[],
+            func: ParseFnKind::Bytes(func),
+        }
+    }
+
+    /// Wrap a reader that takes text; input is decoded as UTF-8 and a
+    /// leading byte order mark is dropped.
+    pub fn text(format: &'static str, func: ParseTextFn) -> Self {
+        Self {
+            formats: [format],
+            options: &[],
+            func: ParseFnKind::Text(func),
+        }
+    }
+
+    /// Declare the options the reader understands.
+    pub fn with_options(mut self, options: &'static [OptionSpec]) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl Parser for FnParser {
+    fn formats(&self) -> &[&str] {
+        &self.formats
+    }
+
+    fn options(&self) -> &[OptionSpec] {
+        self.options
+    }
+
+    fn parse(
+        &self,
+        input: &[u8],
+        options: &ParseOptions,
+    ) -> Result<ConversionResult<Document>, ParseError> {
+        match self.func {
+            ParseFnKind::Bytes(func) => func(input, options),
+            ParseFnKind::Text(func) => {
+                let text = std::str::from_utf8(input)
+                    .map_err(|e| ParseError::Invalid(format!("input is not valid UTF-8: {e}")))?;
+                func(text.strip_prefix('\u{feff}').unwrap_or(text), options)
+            }
+        }
+    }
+}
+
+/// An [`Emitter`] backed by a writer function.
+pub struct FnEmitter {
+    formats: [&'static str; 1],
+    options: &'static [OptionSpec],
+    func: EmitFnKind,
+}
+
+enum EmitFnKind {
+    Buffered(EmitFn),
+    Streaming(EmitToFn),
+}
+
+impl FnEmitter {
+    /// Wrap a writer function.
+    pub fn new(format: &'static str, func: EmitFn) -> Self {
+        Self {
+            formats: [format],
+            options: &[],
+            func: EmitFnKind::Buffered(func),
+        }
+    }
+
+    /// Wrap a writer that streams to an [`io::Write`](std::io::Write).
+    pub fn streaming(format: &'static str, func: EmitToFn) -> Self {
+        Self {
+            formats: [format],
+            options: &[],
+            func: EmitFnKind::Streaming(func),
+        }
+    }
+
+    /// Declare the options the writer understands.
+    pub fn with_options(mut self, options: &'static [OptionSpec]) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl Emitter for FnEmitter {
+    fn formats(&self) -> &[&str] {
+        &self.formats
+    }
+
+    fn options(&self) -> &[OptionSpec] {
+        self.options
+    }
+
+    fn emit(
+        &self,
+        doc: &Document,
+        options: &EmitOptions,
+    ) -> Result<ConversionResult<Vec<u8>>, EmitError> {
+        match self.func {
+            EmitFnKind::Buffered(func) => func(doc, options),
+            EmitFnKind::Streaming(func) => {
+                let mut output = Vec::new();
+                let result = func(doc, options, &mut output)?;
+                Ok(ConversionResult::with_warnings(output, result.warnings))
+            }
+        }
+    }
+
+    fn emit_to(
+        &self,
+        doc: &Document,
+        options: &EmitOptions,
+        out: &mut dyn Write,
+    ) -> Result<ConversionResult<()>, EmitError> {
+        match self.func {
+            EmitFnKind::Buffered(func) => {
+                let result = func(doc, options)?;
+                out.write_all(&result.value)?;
+                Ok(ConversionResult::with_warnings((), result.warnings))
+            }
+            EmitFnKind::Streaming(func) => func(doc, options, out),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEMO: FormatInfo = FormatInfo::new("demo", "Demo format")
+        .with_aliases(&["dm"])
+        .with_extensions(&["demo", "dmo"])
+        .with_mime_types(&["text/x-demo; variant=strict", "text/x-demo"]);
+
+    const OTHER: FormatInfo = FormatInfo::new("other", "Other format")
+        .with_extensions(&["demo"])
+        .with_mime_types(&["text/x-demo; variant=loose"]);
+
+    fn parse_demo(
+        input: &str,
+        _options: &ParseOptions,
+    ) -> Result<ConversionResult<Document>, ParseError> {
+        let mut doc = Document::new();
+        doc.metadata.set("input", input);
+        Ok(ConversionResult::ok(doc))
+    }
+
+    fn emit_demo(
+        doc: &Document,
+        _options: &EmitOptions,
+    ) -> Result<ConversionResult<Vec<u8>>, EmitError> {
+        let input = doc.metadata.get_str("input").unwrap_or_default();
+        Ok(ConversionResult::ok(input.as_bytes().to_vec()))
+    }
+
+    fn demo_registry() -> Registry {
+        let mut registry = Registry::new();
+        registry.register_parser(DEMO, FnParser::text("demo", parse_demo));
+        registry.register_emitter(DEMO, FnEmitter::new("demo", emit_demo));
+        registry.register_emitter(OTHER, FnEmitter::new("other", emit_demo));
+        registry
+    }
+
+    #[test]
+    fn test_lookup_by_name_and_alias() {
+        let registry = demo_registry();
+        assert_eq!(registry.format("demo").map(|f| f.name()), Some("demo"));
+        assert_eq!(registry.format("DM").map(|f| f.name()), Some("demo"));
+        assert!(registry.format("missing").is_none());
+        assert!(registry.parser_for("dm").is_some());
+        assert!(registry.parser_for("other").is_none());
+        assert!(registry.emitter_for("other").is_some());
+    }
+
+    #[test]
+    fn test_lookup_by_extension_prefers_first_registered() {
+        let registry = demo_registry();
+        assert_eq!(
+            registry.format_for_extension(".DEMO").map(|f| f.name()),
+            Some("demo")
+        );
+        assert_eq!(
+            registry.format_for_path("notes.dmo").map(|f| f.name()),
+            Some("demo")
+        );
+        assert!(registry.format_for_path("notes").is_none());
+    }
+
+    #[test]
+    fn test_lookup_by_mime() {
+        let registry = demo_registry();
+        let by_mime = |m| registry.format_for_mime(m).map(|f| f.name());
+        assert_eq!(by_mime("text/x-demo"), Some("demo"));
+        assert_eq!(by_mime("text/x-demo;variant=loose"), Some("other"));
+        assert_eq!(by_mime("text/x-demo; charset=utf-8"), Some("demo"));
+        assert_eq!(by_mime("text/plain"), None);
+    }
+
+    #[test]
+    fn test_register_merges_reader_and_writer() {
+        let registry = demo_registry();
+        assert_eq!(registry.formats().count(), 2);
+        assert_eq!(registry.readers().count(), 1);
+        assert_eq!(registry.writers().count(), 2);
+    }
+
+    #[test]
+    fn test_format_spec() {
+        const WIDTH: OptionSpec = OptionSpec::int("width", 0, "Width");
+        let mut registry = demo_registry();
+        registry.register_emitter(
+            FormatInfo::new("demo-strict", "Strict demo"),
+            FnEmitter::new("demo-strict", emit_demo).with_options(&[WIDTH]),
+        );
+
+        let (entry, modifiers) = registry.format_for_spec("dm+x-y").unwrap();
+        assert_eq!((entry.name(), modifiers), ("demo", "+x-y"));
+
+        let (emitter, options) = registry.emitter_for_spec("demo-strict+width=3").unwrap();
+        assert_eq!(emitter.formats(), &["demo-strict"]);
+        assert_eq!(options.format.int(&WIDTH), 3);
+
+        let err = |spec| registry.emitter_for_spec(spec).err().unwrap().to_string();
+        assert_eq!(
+            err("demo-strict+height=3"),
+            "demo-strict: unknown option 'height'"
+        );
+        assert_eq!(err("demo+width=3"), "demo: unknown option 'width'");
+        assert_eq!(err("missing+width=3"), "unknown format 'missing'");
+        assert!(matches!(
+            registry.parser_for_spec("other"),
+            Err(FormatSpecError::NoReader("other"))
+        ));
+    }
+
+    #[test]
+    fn test_text_parser_decodes_utf8() {
+        let registry = demo_registry();
+        let parser = registry.parser_for("demo").unwrap();
+        assert_eq!(parser.formats(), &["demo"]);
+
+        let doc = parser
+            .parse("\u{feff}héllo".as_bytes(), &ParseOptions::default())
+            .unwrap()
+            .value;
+        assert_eq!(doc.metadata.get_str("input"), Some("héllo"));
+
+        let err = parser.parse(&[0xff, 0xfe], &ParseOptions::default());
+        assert!(matches!(err, Err(ParseError::Invalid(_))));
+    }
+
+    #[test]
+    #[cfg(all(feature = "markdown", feature = "html"))]
+    fn test_builtin_roundtrip() {
+        let registry = Registry::builtin();
+        let doc = registry
+            .parser_for("md")
+            .unwrap()
+            .parse(b"# Hello", &ParseOptions::default())
+            .unwrap()
+            .value;
+        let html = registry
+            .emitter_for("html")
+            .unwrap()
+            .emit(&doc, &EmitOptions::default())
+            .unwrap()
+            .value;
+        assert!(String::from_utf8(html).unwrap().contains("<h1>"));
+        assert_eq!(
+            registry.format_for_mime("text/html").map(|f| f.name()),
+            Some("html")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "org")]
+    fn test_detect_skips_formats_without_reader() {
+        let registry = Registry::builtin();
+        let (entry, confidence) = registry.detect(b"#+TITLE: Notes\n\n* Heading\n").unwrap();
+        assert_eq!(entry.name(), "org");
+        assert!(confidence > 0.0);
+
+        assert!(demo_registry().detect(b"#+TITLE: Notes\n").is_none());
+    }
+}
@@ -0,0 +1,115 @@
+//! Bibliography formats share the `bib:entry` model, so entries survive
+//! conversion between them.
+
+use rescribe_bib::Entry;
+use rescribe_core::Document;
+
+const BIBTEX: &str = r#"
+@article{gogh2020,
+  author = {van Gogh, Vincent and Smith, Jr., John and {World Health Organization}},
+  title = {Letters on Colour},
+  journal = {Journal of Art},
+  volume = {12},
+  number = {3},
+  pages = {45--67},
+  year = {2020},
+  month = may,
+  doi = {10.1000/xyz123},
+  url = {https://example.org/letters},
+  issn = {1234-5678},
+  keywords = {art, letters},
+  language = {english},
+  note = {Translated from Dutch},
+}
+
+@book{knuth1984,
+  author = {Knuth, Donald E.},
+  editor = {Doe, Jane},
+  title = {The TeXbook},
+  series = {Computers and Typesetting},
+  edition = {2},
+  publisher = {Addison-Wesley},
+  address = {Reading, MA},
+  year = {1984},
+  isbn = {0-201-13447-0},
+  abstract = {All about TeX.},
+}
+"#;
+
+fn entries(doc: &Document) -> Vec<Entry<'_>> {
+    rescribe_bib::entries(&doc.content)
+}
+
+#[test]
+fn bibtex_to_csl_json_to_ris() {
+    let original = rescribe_read_bibtex::parse(BIBTEX).unwrap().value;
+
+    let csl = rescribe_write_csl_json::emit(&original).unwrap().value;
+    let csl = std::str::from_utf8(&csl).unwrap();
+    let from_csl = rescribe_read_csl_json::parse(csl).unwrap().value;
+
+    let ris = rescribe_write_ris::emit(&from_csl).unwrap();
+    assert!(ris.warnings.is_empty(), "{:?}", ris.warnings);
+    let ris = std::str::from_utf8(&ris.value).unwrap();
+    let from_ris = rescribe_read_ris::parse(ris).unwrap().value;
+
+    let expected = entries(&original);
+    let actual = entries(&from_ris);
+    assert_eq!(expected.len(), 2);
+    assert_eq!(actual.len(), expected.len());
+    for (expected, actual) in expected.iter().zip(&actual) {
+        assert_eq!(actual.id(), expected.id());
+        assert_eq!(actual.entry_type(), expected.entry_type());
+        assert_eq!(actual.variables(), expected.variables());
+    }
+}
+
+#[test]
+fn ris_to_endnote_xml_and_back() {
+    let ris = "TY  - JOUR\nID  - smith2020\nAU  - Smith, John\nTI  - A Great Paper\n\
+               JO  - Nature\nPY  - 2020\nDA  - 2020/05/04/\nSP  - 1\nEP  - 9\n\
+               M1  - 42\nER  - \n";
+    let original = rescribe_read_ris::parse(ris).unwrap().value;
+
+    // M1 has no CSL variable, so it is kept only for RIS.
+    let xml = rescribe_write_endnotexml::emit(&original).unwrap();
+    assert_eq!(xml.warnings.len(), 1);
+    assert!(xml.warnings[0].message.contains("ris:M1"));
+    let xml = std::str::from_utf8(&xml.value).unwrap();
+    let from_xml = rescribe_read_endnotexml::parse(xml).unwrap().value;
+
+    let expected = entries(&original);
+    let actual = entries(&from_xml);
+    assert_eq!(actual[0].id(), expected[0].id());
+    assert_eq!(actual[0].entry_type(), expected[0].entry_type());
+    assert_eq!(actual[0].variables(), expected[0].variables());
+}
+
+#[test]
+fn markdown_citations_formatted_from_bibtex() {
+    use rescribe_core::Transformer;
+    use rescribe_transforms::{Citeproc, Style};
+
+    let bibliography = rescribe_read_bibtex::parse(BIBTEX).unwrap().value;
+    let markdown = "Colour [@gogh2020, p. 50] and type [@knuth1984].\n";
+    let options = rescribe_core::ParseOptions {
+        format: rescribe_core::FormatOptions::new().with("citations", true),
+        ..Default::default()
+    };
+    let doc = rescribe_read_markdown::parse_with_options(markdown, &options)
+        .unwrap()
+        .value;
+
+    let citeproc = Citeproc::new(Style::bundled("ieee").unwrap()).with_bibliography(&bibliography);
+    let result = citeproc.transform(doc).unwrap();
+    assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+
+    let html = rescribe_write_html::emit(&result.value).unwrap().value;
+    let html = String::from_utf8(html).unwrap();
+    assert!(html.contains(
+        r#"Colour <span class="citation">[1, p. 50]</span> and type <span class="citation">[2]</span>."#
+    ));
+    assert!(html.contains(
+        r#"<p>[2] D. E. Knuth, <em>The TeXbook</em>, 2nd ed., Reading, MA: Addison-Wesley, 1984.</p>"#
+    ));
+}
@@ -0,0 +1,68 @@
+//! Reader output must match the standard node schema.
+
+use rescribe_core::Transformer;
+use rescribe_read_html as html;
+use rescribe_read_markdown as markdown;
+use rescribe_transforms::Validate;
+
+const MARKDOWN: &str = r#"# Title
+
+Some *emphasis*, **strong**, `code`, ~~struck~~ and a [link](https://example.com "Example").
+An ![image](img.png) and a footnote[^1].
+
+> A quote with
+> two lines.
+
+- tight
+- list
+
+1. ordered
+
+   with paragraphs
+2. [ ] task
+
+| A | B |
+|:--|--:|
+| 1 | 2 |
+
+```rust
+fn main() {}
+```
+
+---
+
+<div>raw html</div>
+
+[^1]: The footnote.
+"#;
+
+const HTML: &str = r#"<html><body>
+<h2 id="intro">Intro</h2>
+<p>Text with <em>em</em>, <strong>strong</strong>, <code>code</code>, <sub>sub</sub>,
+<sup>sup</sup>, <a href="/x" title="X">a link</a> and <img src="a.png" alt="A">.<br>Next line.</p>
+<ul><li>One</li><li><p>Two</p></li></ul>
+<ol start="3"><li>Three</li></ol>
+<blockquote><p>Quoted</p></blockquote>
+<pre><code class="language-rust">fn main() {}</code></pre>
+<table><thead><tr><th>H</th></tr></thead><tbody><tr><td>C</td></tr></tbody></table>
+<dl><dt>Term</dt><dd>Description</dd></dl>
+<figure><img src="f.png"><figcaption>Caption</figcaption></figure>
+<div class="note"><p>Note</p></div>
+<hr>
+</body></html>"#;
+
+fn assert_valid(doc: rescribe_core::Document) {
+    if let Err(e) = Validate::standard().strict().transform(doc) {
+        panic!("{e}");
+    }
+}
+
+#[test]
+fn test_markdown_reader_output_is_valid() {
+    assert_valid(markdown::parse(MARKDOWN).unwrap().value);
+}
+
+#[test]
+fn test_html_reader_output_is_valid() {
+    assert_valid(html::parse(HTML).unwrap().value);
+}
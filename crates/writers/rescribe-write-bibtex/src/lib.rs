@@ -1,16 +1,36 @@
 //! BibTeX writer for rescribe.
 //!
 //! Emits documents as BibTeX source. This writer expects documents containing
-//! bibliographic entries with specific properties.
+//! `bib:entry` nodes (see [`rescribe_bib`]): CSL types and variables are
+//! mapped to BibTeX entry types and fields, and `bibtex:` custom fields are
+//! written back as they are. The BibLaTeX writer shares this code through
+//! [`emit_dialect`].
 
+use rescribe_bib::{Date, Entry, Name, prop};
 use rescribe_core::{
     ConversionResult, Document, EmitError, EmitOptions, FidelityWarning, Node, Severity,
     WarningKind,
 };
-use rescribe_std::prop;
+use std::collections::HashSet;
+
+/// Flavour of `.bib` file to write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// Classic BibTeX: `journal`, `address`, `year` and `month` fields.
+    BibTeX,
+    /// BibLaTeX: `journaltitle`, `location` and EDTF `date` fields, and the
+    /// BibLaTeX entry types.
+    BibLaTeX,
+}
 
-/// BibTeX entry types.
-const BIBTEX_ENTRY: &str = "bibtex:entry";
+impl Dialect {
+    fn name(self) -> &'static str {
+        match self {
+            Dialect::BibTeX => "BibTeX",
+            Dialect::BibLaTeX => "BibLaTeX",
+        }
+    }
+}
 
 /// Emit a document as BibTeX.
 pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
@@ -22,7 +42,15 @@ pub fn emit_with_options(
     doc: &Document,
     _options: &EmitOptions,
 ) -> Result<ConversionResult<Vec<u8>>, EmitError> {
-    let mut ctx = EmitContext::new();
+    emit_dialect(doc, Dialect::BibTeX)
+}
+
+/// Emit a document in the given dialect.
+pub fn emit_dialect(
+    doc: &Document,
+    dialect: Dialect,
+) -> Result<ConversionResult<Vec<u8>>, EmitError> {
+    let mut ctx = EmitContext::new(dialect);
 
     emit_nodes(&doc.content.children, &mut ctx);
 
@@ -36,13 +64,15 @@ pub fn emit_with_options(
 struct EmitContext {
     output: String,
     warnings: Vec<FidelityWarning>,
+    dialect: Dialect,
 }
 
 impl EmitContext {
-    fn new() -> Self {
+    fn new(dialect: Dialect) -> Self {
         Self {
             output: String::new(),
             warnings: Vec::new(),
+            dialect,
         }
     }
 
@@ -60,239 +90,374 @@ fn emit_nodes(nodes: &[Node], ctx: &mut EmitContext) {
 
 /// Emit a single node.
 fn emit_node(node: &Node, ctx: &mut EmitContext) {
+    if let Some(entry) = Entry::from_node(node) {
+        emit_entry(entry, ctx);
+        return;
+    }
     match node.kind.as_str() {
         "document" => emit_nodes(&node.children, ctx),
 
-        BIBTEX_ENTRY => emit_entry(node, ctx),
-
-        // For backwards compatibility, also support generic citation entries
-        "citation_entry" => emit_citation_entry(node, ctx),
-
         _ => {
-            // Check if it might be a bibtex-like entry type
-            if is_bibtex_type(node.kind.as_str()) {
-                emit_typed_entry(node, ctx);
-            } else {
-                ctx.warnings.push(FidelityWarning::new(
-                    Severity::Minor,
-                    WarningKind::UnsupportedNode(node.kind.as_str().to_string()),
-                    format!("Unknown node type for BibTeX: {}", node.kind.as_str()),
-                ));
-                emit_nodes(&node.children, ctx);
-            }
+            ctx.warnings.push(FidelityWarning::new(
+                Severity::Minor,
+                WarningKind::UnsupportedNode(node.kind.as_str().to_string()),
+                format!(
+                    "Unknown node type for {}: {}",
+                    ctx.dialect.name(),
+                    node.kind.as_str()
+                ),
+            ));
+            emit_nodes(&node.children, ctx);
         }
     }
 }
 
-/// Check if a string is a known BibTeX entry type.
-fn is_bibtex_type(s: &str) -> bool {
-    matches!(
-        s.to_lowercase().as_str(),
-        "article"
-            | "book"
-            | "booklet"
-            | "conference"
-            | "inbook"
-            | "incollection"
-            | "inproceedings"
-            | "manual"
-            | "mastersthesis"
-            | "misc"
-            | "phdthesis"
-            | "proceedings"
-            | "techreport"
-            | "unpublished"
-            | "online"
-            | "software"
-            | "dataset"
-    )
+/// The fields of an entry, collected in output order.
+struct Fields<'a> {
+    entry: Entry<'a>,
+    /// Properties that have been written.
+    used: HashSet<&'a str>,
+    /// Field names and their values, delimiters included.
+    fields: Vec<(String, String)>,
 }
 
-/// Emit a BibTeX entry.
-fn emit_entry(node: &Node, ctx: &mut EmitContext) {
-    let entry_type = node
-        .props
-        .get_str("bibtex:type")
-        .unwrap_or("misc")
-        .to_lowercase();
-    let cite_key = node.props.get_str("bibtex:key").unwrap_or("unknown");
+impl<'a> Fields<'a> {
+    fn new(entry: Entry<'a>) -> Self {
+        Self {
+            entry,
+            used: HashSet::new(),
+            fields: Vec::new(),
+        }
+    }
 
-    ctx.write("@");
-    ctx.write(&entry_type);
-    ctx.write("{");
-    ctx.write(cite_key);
-    ctx.write(",\n");
+    /// Take a string variable.
+    fn text(&mut self, key: &'a str) -> Option<&'a str> {
+        self.used.insert(key);
+        self.entry.text(key)
+    }
 
-    // Emit all bibtex: prefixed properties as fields
-    emit_bibtex_fields(node, ctx);
+    /// Take the first of `keys` that is set.
+    fn first(&mut self, keys: &[&'a str]) -> Option<&'a str> {
+        let (key, value) = keys
+            .iter()
+            .find_map(|key| Some((*key, self.entry.text(key)?)))?;
+        self.used.insert(key);
+        Some(value)
+    }
 
-    ctx.write("}\n\n");
-}
+    /// Add a field with a braced, escaped value.
+    fn field(&mut self, name: &str, value: Option<&str>) {
+        if let Some(value) = value {
+            self.raw(name, format!("{{{}}}", escape_bibtex(value)));
+        }
+    }
 
-/// Emit a typed entry (where the node kind is the entry type).
-fn emit_typed_entry(node: &Node, ctx: &mut EmitContext) {
-    let entry_type = node.kind.as_str().to_lowercase();
-    let cite_key = node
-        .props
-        .get_str("key")
-        .or(node.props.get_str(prop::ID))
-        .unwrap_or("unknown");
+    /// Add a field with a braced value that is not escaped, such as a URL.
+    fn verbatim(&mut self, name: &str, value: Option<&str>) {
+        if let Some(value) = value {
+            self.raw(name, format!("{{{value}}}"));
+        }
+    }
 
-    ctx.write("@");
-    ctx.write(&entry_type);
-    ctx.write("{");
-    ctx.write(cite_key);
-    ctx.write(",\n");
+    /// Add a field whose value already has its delimiters.
+    fn raw(&mut self, name: &str, value: String) {
+        self.fields.push((name.to_string(), value));
+    }
 
-    emit_standard_fields(node, ctx);
+    /// Add a name list field from a name variable.
+    fn names(&mut self, name: &str, key: &'a str) {
+        self.used.insert(key);
+        let names = self.entry.names(key);
+        if !names.is_empty() {
+            let names: Vec<String> = names.iter().map(format_name).collect();
+            self.raw(name, format!("{{{}}}", names.join(" and ")));
+        }
+    }
 
-    ctx.write("}\n\n");
+    /// Add a date field from a date variable, in EDTF notation.
+    fn date(&mut self, name: &str, key: &'a str) {
+        self.used.insert(key);
+        if let Some(date) = self.entry.date(key) {
+            let text = date.to_edtf().or(date.literal);
+            self.field(name, text.as_deref());
+        }
+    }
 }
 
-/// Emit a citation entry with CSL-like properties.
-fn emit_citation_entry(node: &Node, ctx: &mut EmitContext) {
-    // Map CSL type to BibTeX type
-    let csl_type = node.props.get_str("type").unwrap_or("misc");
-    let entry_type = csl_to_bibtex_type(csl_type);
-    let cite_key = node.props.get_str(prop::ID).unwrap_or("unknown");
+/// Emit a bibliography entry.
+fn emit_entry(entry: Entry, ctx: &mut EmitContext) {
+    let dialect = ctx.dialect;
+    let entry_type = entry_type(entry, dialect);
+    let csl_type = entry.entry_type();
+    let mut fields = Fields::new(entry);
+
+    // Names
+    fields.names("author", prop::AUTHOR);
+    fields.names("editor", prop::EDITOR);
+    if dialect == Dialect::BibLaTeX {
+        let roles = [
+            ("bib:compiler", "compiler"),
+            ("bib:director", "director"),
+            ("bib:organizer", "organizer"),
+        ];
+        let present = roles
+            .into_iter()
+            .filter(|(key, _)| !entry.names(key).is_empty());
+        for ((key, role), field) in present.zip(["editora", "editorb", "editorc"]) {
+            fields.names(field, key);
+            fields.field(&format!("{field}type"), Some(role));
+        }
+    }
+    fields.names("translator", prop::TRANSLATOR);
+    fields.names("bookauthor", prop::CONTAINER_AUTHOR);
+
+    // Titles. A subtitle read from BibTeX is split off the title again.
+    let full_title = fields.text(prop::TITLE);
+    let main_title = full_title
+        .zip(entry.custom("bibtex:subtitle"))
+        .and_then(|(title, subtitle)| title.strip_suffix(subtitle)?.strip_suffix(": "));
+    let title = main_title.or(full_title);
+    fields.field("title", title);
+    let shorttitle = fields.text(prop::TITLE_SHORT);
+    fields.field("shorttitle", shorttitle);
+    let container = fields.text(prop::CONTAINER_TITLE);
+    let container_field = match (csl_type.starts_with("article"), dialect) {
+        (true, Dialect::BibTeX) => "journal",
+        (true, Dialect::BibLaTeX) => "journaltitle",
+        (false, _) => "booktitle",
+    };
+    fields.field(container_field, container);
+    let shortjournal = fields.text(prop::CONTAINER_TITLE_SHORT);
+    fields.field("shortjournal", shortjournal);
+    let series = fields.text(prop::COLLECTION_TITLE);
+    fields.field("series", series);
+    let eventtitle = fields.text("bib:event-title");
+    fields.field("eventtitle", eventtitle);
+    let venue = fields.text("bib:event-place");
+    fields.field("venue", venue);
+
+    // Locators
+    let volume = fields.text(prop::VOLUME);
+    fields.field("volume", volume);
+    let number = fields.first(&[prop::ISSUE, prop::NUMBER, prop::COLLECTION_NUMBER]);
+    fields.field("number", number);
+    let pages = fields.text(prop::PAGE).map(page_range);
+    fields.field("pages", pages.as_deref());
+    let pagetotal = fields.text(prop::NUMBER_OF_PAGES);
+    fields.field("pagetotal", pagetotal);
+    let chapter = fields.text(prop::CHAPTER_NUMBER);
+    fields.field("chapter", chapter);
+    let edition = fields.text(prop::EDITION);
+    fields.field("edition", edition);
+    let volumes = fields.text(prop::NUMBER_OF_VOLUMES);
+    fields.field("volumes", volumes);
+
+    // Publication details
+    let publisher = fields.text(prop::PUBLISHER);
+    let publisher_field = match (csl_type, dialect) {
+        ("thesis", Dialect::BibTeX) => "school",
+        ("thesis" | "report", _) => "institution",
+        _ => "publisher",
+    };
+    fields.field(publisher_field, publisher);
+    let place = fields.text(prop::PUBLISHER_PLACE);
+    let place_field = match dialect {
+        Dialect::BibTeX => "address",
+        Dialect::BibLaTeX => "location",
+    };
+    fields.field(place_field, place);
+
+    // Dates
+    match dialect {
+        Dialect::BibTeX => {
+            fields.used.insert(prop::ISSUED);
+            if let Some(date) = entry.date(prop::ISSUED) {
+                emit_year_month(&date, &mut fields);
+            }
+        }
+        Dialect::BibLaTeX => fields.date("date", prop::ISSUED),
+    }
+    fields.date("urldate", prop::ACCESSED);
+    fields.date("eventdate", prop::EVENT_DATE);
+    fields.date("origdate", prop::ORIGINAL_DATE);
+
+    let genre = fields.text(prop::GENRE);
+    let genre = match (csl_type, dialect, genre) {
+        ("thesis", Dialect::BibTeX, Some("PhD thesis" | "Master's thesis")) => None,
+        ("thesis", Dialect::BibLaTeX, Some("PhD thesis")) => Some("phdthesis"),
+        ("thesis", Dialect::BibLaTeX, Some("Master's thesis")) => Some("mathesis"),
+        (_, _, genre) => genre,
+    };
+    fields.field("type", genre);
+    let subtype = match csl_type {
+        "article-magazine" => Some("magazine"),
+        "article-newspaper" => Some("newspaper"),
+        _ => None,
+    };
+    fields.field("entrysubtype", subtype);
+    let version = fields.text(prop::VERSION);
+    fields.field("version", version);
+    let pubstate = fields.text(prop::STATUS);
+    fields.field("pubstate", pubstate);
+    let language = fields.text(prop::LANGUAGE);
+    fields.field("language", language);
+
+    // Identifiers and notes
+    let isbn = fields.text(prop::ISBN);
+    fields.field("isbn", isbn);
+    let issn = fields.text(prop::ISSN);
+    fields.field("issn", issn);
+    let doi = fields.text(prop::DOI);
+    fields.verbatim("doi", doi);
+    let url = fields.text(prop::URL);
+    fields.verbatim("url", url);
+    let keywords = fields.text(prop::KEYWORD);
+    fields.field("keywords", keywords);
+    let abstract_ = fields.text(prop::ABSTRACT);
+    fields.field("abstract", abstract_);
+    let note = fields.text(prop::NOTE);
+    fields.field("note", note);
+    let annote = fields.text(prop::ANNOTE);
+    let annote_field = match dialect {
+        Dialect::BibTeX => "annote",
+        Dialect::BibLaTeX => "annotation",
+    };
+    fields.field(annote_field, annote);
+
+    // Fields read from BibTeX that have no CSL variable
+    let mut lost = Vec::new();
+    for (key, value) in entry.custom_fields() {
+        match key.strip_prefix("bibtex:") {
+            Some("entrytype") => {}
+            Some("subtitle") if main_title.is_none() => {}
+            Some(name) if !fields.fields.iter().any(|(field, _)| field == name) => {
+                fields.field(name, Some(value));
+            }
+            Some(_) => {}
+            None => lost.push(key),
+        }
+    }
+    lost.extend(
+        entry
+            .variables()
+            .into_iter()
+            .filter(|(name, _)| !fields.used.contains(format!("bib:{name}").as_str()))
+            .map(|(name, _)| name),
+    );
+    if !lost.is_empty() {
+        ctx.warnings.push(FidelityWarning::new(
+            Severity::Minor,
+            WarningKind::UnsupportedProperty(lost.join(", ")),
+            format!(
+                "{} has no field for {} in entry '{}'",
+                dialect.name(),
+                lost.join(", "),
+                entry.id()
+            ),
+        ));
+    }
 
     ctx.write("@");
-    ctx.write(entry_type);
+    ctx.write(&entry_type);
     ctx.write("{");
-    ctx.write(cite_key);
+    ctx.write(entry.id());
     ctx.write(",\n");
 
-    emit_csl_fields(node, ctx);
+    for (name, value) in &fields.fields {
+        emit_field(name, value, ctx);
+    }
 
     ctx.write("}\n\n");
 }
 
-/// Map CSL types to BibTeX types.
-fn csl_to_bibtex_type(csl_type: &str) -> &'static str {
-    match csl_type {
-        "article-journal" | "article-magazine" | "article-newspaper" => "article",
-        "book" => "book",
-        "chapter" => "incollection",
-        "paper-conference" => "inproceedings",
-        "thesis" => "phdthesis",
-        "report" => "techreport",
-        "webpage" | "post-weblog" => "online",
-        "software" => "software",
-        "dataset" => "dataset",
-        _ => "misc",
-    }
-}
-
-/// Emit fields from bibtex: prefixed properties.
-fn emit_bibtex_fields(node: &Node, ctx: &mut EmitContext) {
-    let mut fields: Vec<(&str, String)> = Vec::new();
-
-    // Collect all bibtex: prefixed properties
-    for (key, value) in node.props.iter() {
-        if let Some(field_name) = key.strip_prefix("bibtex:")
-            && field_name != "type"
-            && field_name != "key"
-            && let rescribe_core::PropValue::String(s) = value
-        {
-            fields.push((field_name, s.clone()));
-        }
-    }
-
-    // Sort fields for consistent output
-    fields.sort_by(|a, b| a.0.cmp(b.0));
-
-    for (name, value) in fields {
-        emit_field(name, &value, ctx);
+/// The entry type for an entry.
+fn entry_type(entry: Entry, dialect: Dialect) -> String {
+    if let Some(entry_type) = entry.custom("bibtex:entrytype") {
+        return entry_type.to_string();
     }
+    let genre = entry.text(prop::GENRE).unwrap_or_default().to_lowercase();
+    let entry_type = match (entry.entry_type(), dialect) {
+        ("article-journal" | "article-magazine" | "article-newspaper" | "article", _) => "article",
+        ("book", _) => "book",
+        ("chapter", _) => "incollection",
+        ("entry-encyclopedia" | "entry-dictionary" | "entry", Dialect::BibLaTeX) => "inreference",
+        ("entry-encyclopedia" | "entry-dictionary" | "entry", Dialect::BibTeX) => "incollection",
+        ("paper-conference", _) => "inproceedings",
+        ("pamphlet", _) => "booklet",
+        ("thesis", Dialect::BibTeX) if genre.contains("master") => "mastersthesis",
+        ("thesis", Dialect::BibTeX) => "phdthesis",
+        ("thesis", Dialect::BibLaTeX) => "thesis",
+        ("report", Dialect::BibTeX) => "techreport",
+        ("report", Dialect::BibLaTeX) => "report",
+        ("webpage" | "post" | "post-weblog", _) => "online",
+        ("manuscript", _) => "unpublished",
+        ("software", _) => "software",
+        ("dataset", _) => "dataset",
+        ("patent", _) => "patent",
+        ("periodical", _) => "periodical",
+        _ => "misc",
+    };
+    entry_type.to_string()
 }
 
-/// Emit standard BibTeX fields from properties.
-fn emit_standard_fields(node: &Node, ctx: &mut EmitContext) {
-    // Standard BibTeX fields
-    let field_mappings = [
-        ("author", "author"),
-        ("title", "title"),
-        ("journal", "journal"),
-        ("booktitle", "booktitle"),
-        ("year", "year"),
-        ("volume", "volume"),
-        ("number", "number"),
-        ("pages", "pages"),
-        ("publisher", "publisher"),
-        ("address", "address"),
-        ("edition", "edition"),
-        ("editor", "editor"),
-        ("series", "series"),
-        ("month", "month"),
-        ("note", "note"),
-        ("doi", "doi"),
-        ("url", "url"),
-        ("isbn", "isbn"),
-        ("issn", "issn"),
-        ("abstract", "abstract"),
-        ("keywords", "keywords"),
-        ("institution", "institution"),
-        ("school", "school"),
-        ("howpublished", "howpublished"),
-        ("organization", "organization"),
-        ("chapter", "chapter"),
+/// Add `year` and `month` fields, and a `date` field if the date has more
+/// detail than those can hold.
+fn emit_year_month(date: &Date, fields: &mut Fields) {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
     ];
-
-    for (prop_name, field_name) in field_mappings {
-        if let Some(value) = node.props.get_str(prop_name) {
-            emit_field(field_name, value, ctx);
-        }
+    let Some(start) = date.start else {
+        fields.field("year", date.literal.as_deref());
+        return;
+    };
+    fields.field("year", Some(&start.year.to_string()));
+    if let Some(month) = start.month {
+        fields.raw("month", MONTHS[usize::from(month - 1)].to_string());
     }
-}
-
-/// Emit CSL fields mapped to BibTeX fields.
-fn emit_csl_fields(node: &Node, ctx: &mut EmitContext) {
-    // CSL to BibTeX field mappings
-    if let Some(title) = node.props.get_str("title") {
-        emit_field("title", title, ctx);
+    if start.day.is_some() || date.end.is_some() || date.circa {
+        fields.field("date", date.to_edtf().as_deref());
     }
+}
 
-    // Handle authors (could be array or string)
-    if let Some(author) = node.props.get_str("author") {
-        emit_field("author", author, ctx);
+/// Format a name as `von Last, Jr, First`, or a braced literal.
+fn format_name(name: &Name) -> String {
+    if let Some(literal) = &name.literal {
+        return format!("{{{}}}", escape_bibtex(literal));
     }
-
-    // Container title maps to journal/booktitle depending on type
-    if let Some(container) = node.props.get_str("container-title") {
-        let csl_type = node.props.get_str("type").unwrap_or("");
-        if csl_type == "article-journal" {
-            emit_field("journal", container, ctx);
+    let family = name.family.as_deref().map(|family| {
+        if family.contains(' ') {
+            format!("{{{}}}", escape_bibtex(family))
         } else {
-            emit_field("booktitle", container, ctx);
+            escape_bibtex(family)
         }
+    });
+    let last: Vec<String> = [
+        name.dropping_particle.as_deref().map(escape_bibtex),
+        name.non_dropping_particle.as_deref().map(escape_bibtex),
+        family,
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let last = last.join(" ");
+    match (&name.given, &name.suffix) {
+        (Some(given), Some(suffix)) => format!(
+            "{last}, {}, {}",
+            escape_bibtex(suffix),
+            escape_bibtex(given)
+        ),
+        (Some(given), None) => format!("{last}, {}", escape_bibtex(given)),
+        (None, Some(suffix)) => format!("{last}, {}, ", escape_bibtex(suffix)),
+        (None, None) => last,
     }
+}
 
-    // Date handling
-    if let Some(year) = node.props.get_str("issued") {
-        // Try to extract just the year
-        let year_str = year.split('-').next().unwrap_or(year);
-        emit_field("year", year_str, ctx);
-    }
-
-    // Other direct mappings
-    let direct_mappings = [
-        ("volume", "volume"),
-        ("issue", "number"),
-        ("page", "pages"),
-        ("publisher", "publisher"),
-        ("publisher-place", "address"),
-        ("DOI", "doi"),
-        ("URL", "url"),
-        ("ISBN", "isbn"),
-        ("ISSN", "issn"),
-        ("abstract", "abstract"),
-        ("note", "note"),
-    ];
-
-    for (csl_name, bibtex_name) in direct_mappings {
-        if let Some(value) = node.props.get_str(csl_name) {
-            emit_field(bibtex_name, value, ctx);
-        }
+/// Write a CSL page range with BibTeX's `--`.
+fn page_range(pages: &str) -> String {
+    if pages.contains("--") {
+        pages.to_string()
+    } else {
+        pages.replace(['-', '–'], "--")
     }
 }
 
@@ -300,9 +465,9 @@ fn emit_csl_fields(node: &Node, ctx: &mut EmitContext) {
 fn emit_field(name: &str, value: &str, ctx: &mut EmitContext) {
     ctx.write("  ");
     ctx.write(name);
-    ctx.write(" = {");
-    ctx.write(&escape_bibtex(value));
-    ctx.write("},\n");
+    ctx.write(" = ");
+    ctx.write(value);
+    ctx.write(",\n");
 }
 
 /// Escape special BibTeX characters.
@@ -326,19 +491,25 @@ fn escape_bibtex(text: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rescribe_core::{Document, NodeKind};
+    use rescribe_bib::node;
+    use rescribe_core::{Document, NodeKind, PropValue};
+    use std::collections::HashMap;
 
     fn emit_str(doc: &Document) -> String {
         let result = emit(doc).unwrap();
         String::from_utf8(result.value).unwrap()
     }
 
-    fn make_entry(entry_type: &str, key: &str, fields: Vec<(&str, &str)>) -> Node {
-        let mut node = Node::new(NodeKind::from(BIBTEX_ENTRY))
-            .prop("bibtex:type", entry_type)
-            .prop("bibtex:key", key);
+    fn make_entry(csl_type: &str, key: &str, fields: Vec<(&str, &str)>) -> Node {
+        let mut node = Node::new(node::ENTRY)
+            .prop(prop::TYPE, csl_type)
+            .prop(prop::ID, key);
         for (name, value) in fields {
-            node = node.prop(format!("bibtex:{}", name), value);
+            node = match name {
+                "author" => node.prop(prop::AUTHOR, Name::list([Name::parse(value)])),
+                "issued" => node.prop(prop::ISSUED, Date::parse(value).unwrap()),
+                _ => node.prop(format!("bib:{}", name), value),
+            };
         }
         node
     }
@@ -346,13 +517,13 @@ mod tests {
     #[test]
     fn test_emit_article() {
         let entry = make_entry(
-            "article",
+            "article-journal",
             "smith2024",
             vec![
                 ("author", "John Smith"),
                 ("title", "A Great Paper"),
-                ("journal", "Nature"),
-                ("year", "2024"),
+                ("container-title", "Nature"),
+                ("issued", "2024"),
             ],
         );
 
@@ -361,7 +532,7 @@ mod tests {
         let output = emit_str(&doc);
 
         assert!(output.contains("@article{smith2024,"));
-        assert!(output.contains("author = {John Smith},"));
+        assert!(output.contains("author = {Smith, John},"));
         assert!(output.contains("title = {A Great Paper},"));
         assert!(output.contains("journal = {Nature},"));
         assert!(output.contains("year = {2024},"));
@@ -376,7 +547,7 @@ mod tests {
                 ("author", "Donald Knuth"),
                 ("title", "The Art of Computer Programming"),
                 ("publisher", "Addison-Wesley"),
-                ("year", "1997"),
+                ("issued", "1997"),
             ],
         );
 
@@ -385,13 +556,13 @@ mod tests {
         let output = emit_str(&doc);
 
         assert!(output.contains("@book{knuth1997,"));
-        assert!(output.contains("author = {Donald Knuth},"));
+        assert!(output.contains("author = {Knuth, Donald},"));
     }
 
     #[test]
     fn test_escape_special_chars() {
         let entry = make_entry(
-            "misc",
+            "document",
             "test",
             vec![("title", "100% Pure & Simple: A $10 Solution")],
         );
@@ -404,23 +575,65 @@ mod tests {
     }
 
     #[test]
-    fn test_emit_typed_entry() {
-        let entry = Node::new(NodeKind::from("article"))
-            .prop("key", "test2024")
-            .prop("author", "Test Author")
-            .prop("title", "Test Title")
-            .prop("year", "2024");
+    fn test_emit_names_and_dates() {
+        let entry = make_entry(
+            "thesis",
+            "test2024",
+            vec![
+                ("genre", "Master's thesis"),
+                ("publisher", "MIT"),
+                ("page", "45-67"),
+                ("issued", "2024-05-15"),
+                ("URL", "https://example.com/a_b"),
+            ],
+        )
+        .prop(
+            prop::AUTHOR,
+            Name::list([
+                Name::parse("van der Waals, Jr, Johannes"),
+                Name::literal("WHO"),
+            ]),
+        );
         let doc = Document::new()
             .with_content(Node::new(NodeKind::from("document")).children(vec![entry]));
         let output = emit_str(&doc);
 
-        assert!(output.contains("@article{test2024,"));
-        assert!(output.contains("author = {Test Author},"));
+        assert!(output.contains("@mastersthesis{test2024,"));
+        assert!(output.contains("author = {van der Waals, Jr, Johannes and {WHO}},"));
+        assert!(output.contains("school = {MIT},"));
+        assert!(output.contains("pages = {45--67},"));
+        assert!(output.contains("year = {2024},\n  month = may,\n"));
+        assert!(output.contains("date = {2024-05-15},"));
+        assert!(output.contains("url = {https://example.com/a_b},"));
+        assert!(!output.contains("type ="));
+    }
+
+    #[test]
+    fn test_emit_custom_fields() {
+        let custom = HashMap::from([
+            ("bibtex:entrytype".to_string(), PropValue::from("manual")),
+            ("bibtex:subtitle".to_string(), PropValue::from("A Guide")),
+            ("ris:M3".to_string(), PropValue::from("Print")),
+        ]);
+        let entry = make_entry("book", "m", vec![("title", "Tools: A Guide")])
+            .prop("bib:medium", "Print")
+            .prop(prop::CUSTOM, PropValue::Map(custom));
+        let doc = Document::new()
+            .with_content(Node::new(NodeKind::from("document")).children(vec![entry]));
+        let result = emit(&doc).unwrap();
+        let output = String::from_utf8(result.value).unwrap();
+
+        assert!(output.contains("@manual{m,"));
+        assert!(output.contains("title = {Tools},\n"));
+        assert!(output.contains("subtitle = {A Guide},"));
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].message.contains("medium"));
+        assert!(result.warnings[0].message.contains("ris:M3"));
     }
 
     #[test]
     fn test_emit_multiple_entries() {
-        let entry1 = make_entry("article", "first", vec![("title", "First")]);
+        let entry1 = make_entry("article-journal", "first", vec![("title", "First")]);
         let entry2 = make_entry("book", "second", vec![("title", "Second")]);
 
         let doc = Document::new()
@@ -4,7 +4,9 @@
 //! Returns a zip-like structure with multiple files.
 
 use rescribe_core::{ConversionResult, Document, EmitError, EmitOptions, Node};
-use rescribe_std::{node, prop};
+use rescribe_std::{NodeExt, node, prop};
+use rescribe_transforms::{GenerateToc, SplitAt};
+use std::collections::HashMap;
 
 /// A chunk of HTML output.
 #[derive(Debug, Clone)]
@@ -20,6 +22,9 @@ pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<HtmlChunk>>, EmitErro
 }
 
 /// Emit a document to chunked HTML with options.
+///
+/// The index page lists the table of contents built by [`GenerateToc`],
+/// with its links pointing into the chunks.
 pub fn emit_with_options(
     doc: &Document,
     _options: &EmitOptions,
@@ -29,59 +34,52 @@ pub fn emit_with_options(
         .get_str("title")
         .unwrap_or("Document")
         .to_string();
-    let mut chunks = Vec::new();
-    let mut current_content = Vec::new();
-    let mut current_title = doc_title.clone();
-    let mut chunk_index = 0;
-
-    // Collect table of contents
-    let toc = collect_toc(&doc.content.children);
-
-    for child in &doc.content.children {
-        if child.kind.as_str() == node::HEADING {
-            let level = child.props.get_int(prop::LEVEL).unwrap_or(1);
-            if level == 1 {
-                // Save previous chunk if it has content
-                if !current_content.is_empty() {
-                    let html = generate_html_page(
-                        &current_title,
-                        &current_content,
-                        &toc,
-                        chunk_index,
-                        chunks.len(),
-                    );
-                    chunks.push(HtmlChunk {
-                        filename: format!("chunk{:03}.html", chunk_index),
-                        title: current_title.clone(),
-                        content: html.into_bytes(),
-                    });
-                    chunk_index += 1;
-                    current_content.clear();
-                }
-                current_title = get_text_content(child);
-            }
-        }
-        current_content.push(child.clone());
+    let (doc, mut toc) = GenerateToc::new()
+        .table(doc.clone())
+        .map_err(|e| EmitError::Io(std::io::Error::other(format!("TOC error: {}", e))))?;
+    let mut parts: Vec<Document> = SplitAt::new(1)
+        .split(doc)
+        .into_iter()
+        .filter(|part| !part.content.children.is_empty())
+        .collect();
+
+    // Links to identifiers in other chunks need the chunk's file name
+    let chunk_of = chunk_ids(&parts);
+    for node in toc
+        .iter_mut()
+        .chain(parts.iter_mut().map(|part| &mut part.content))
+    {
+        link_to_chunks(node, &chunk_of);
     }
 
-    // Save final chunk
-    if !current_content.is_empty() {
-        let html = generate_html_page(
-            &current_title,
-            &current_content,
-            &toc,
-            chunk_index,
-            chunk_index + 1,
-        );
+    // Chapters are titled by their heading, content before the first one
+    // by the document
+    let headings: Vec<Option<String>> = parts
+        .iter()
+        .map(|part| {
+            let first = &part.content.children[0];
+            first
+                .as_heading()
+                .filter(|heading| heading.level() == 1)
+                .map(|_| get_text_content(first))
+        })
+        .collect();
+
+    let mut chunks = Vec::new();
+    for (chunk_index, part) in parts.iter().enumerate() {
+        let title = headings[chunk_index]
+            .clone()
+            .unwrap_or_else(|| doc_title.clone());
+        let html = generate_html_page(&title, &part.content.children, &headings, chunk_index);
         chunks.push(HtmlChunk {
-            filename: format!("chunk{:03}.html", chunk_index),
-            title: current_title,
+            filename: chunk_file(chunk_index),
+            title,
             content: html.into_bytes(),
         });
     }
 
     // Generate index page
-    let index_html = generate_index_page(&doc_title, &toc);
+    let index_html = generate_index_page(&doc_title, toc.as_ref());
     chunks.insert(
         0,
         HtmlChunk {
@@ -94,25 +92,45 @@ pub fn emit_with_options(
     Ok(ConversionResult::ok(chunks))
 }
 
-fn collect_toc(nodes: &[Node]) -> Vec<(i64, String, usize)> {
-    let mut toc = Vec::new();
-    let mut chunk_index = 0;
+fn chunk_file(chunk_index: usize) -> String {
+    format!("chunk{:03}.html", chunk_index)
+}
 
-    for child in nodes {
-        if child.kind.as_str() == node::HEADING {
-            let level = child.props.get_int(prop::LEVEL).unwrap_or(1);
-            let title = get_text_content(child);
-            if level == 1 {
-                chunk_index += 1;
-            }
-            toc.push((level, title, chunk_index.max(1) - 1));
+/// The chunk each identifier is in.
+fn chunk_ids(parts: &[Document]) -> HashMap<String, usize> {
+    fn collect(node: &Node, chunk_index: usize, out: &mut HashMap<String, usize>) {
+        if let Some(id) = node.props.get_str(prop::ID) {
+            out.entry(id.to_string()).or_insert(chunk_index);
         }
+        for child in &node.children {
+            collect(child, chunk_index, out);
+        }
+    }
+    let mut ids = HashMap::new();
+    for (chunk_index, part) in parts.iter().enumerate() {
+        collect(&part.content, chunk_index, &mut ids);
     }
+    ids
+}
 
-    toc
+/// Point `#id` links under `node` at the chunk holding the identifier.
+fn link_to_chunks(node: &mut Node, chunk_of: &HashMap<String, usize>) {
+    if node.kind.as_str() == node::LINK
+        && let Some(id) = node
+            .props
+            .get_str(prop::URL)
+            .and_then(|u| u.strip_prefix('#'))
+        && let Some(&chunk_index) = chunk_of.get(id)
+    {
+        let url = format!("{}#{}", chunk_file(chunk_index), id);
+        node.props.set(prop::URL, url);
+    }
+    for child in &mut node.children {
+        link_to_chunks(child, chunk_of);
+    }
 }
 
-fn generate_index_page(title: &str, toc: &[(i64, String, usize)]) -> String {
+fn generate_index_page(title: &str, toc: Option<&Node>) -> String {
     let mut html = String::new();
     html.push_str(&format!(
         r#"<!DOCTYPE html>
@@ -131,34 +149,27 @@ a:hover {{ text-decoration: underline; }}
 </head>
 <body>
 <h1>{}</h1>
-<h2>Table of Contents</h2>
-<ul>
 "#,
         escape_html(title),
         escape_html(title)
     ));
 
-    for (level, section_title, chunk_idx) in toc {
-        let indent = "  ".repeat(*level as usize);
-        html.push_str(&format!(
-            "{}<li><a href=\"chunk{:03}.html\">{}</a></li>\n",
-            indent,
-            chunk_idx,
-            escape_html(section_title)
-        ));
+    if let Some(toc) = toc {
+        html.push_str("<h2>Table of Contents</h2>\n");
+        emit_node(toc, &mut html);
     }
 
-    html.push_str("</ul>\n</body>\n</html>\n");
+    html.push_str("</body>\n</html>\n");
     html
 }
 
 fn generate_html_page(
     title: &str,
     nodes: &[Node],
-    toc: &[(i64, String, usize)],
+    headings: &[Option<String>],
     current_chunk: usize,
-    total_chunks: usize,
 ) -> String {
+    let total_chunks = headings.len();
     let mut html = String::new();
     html.push_str(&format!(
         r#"<!DOCTYPE html>
@@ -211,11 +222,7 @@ th, td {{ border: 1px solid #ddd; padding: 8px; }}
         "<nav style=\"margin-top: 40px; border-top: 1px solid #ddd; padding-top: 10px;\">\n",
     );
     if current_chunk > 0 {
-        let prev_title = toc
-            .iter()
-            .find(|(l, _, idx)| *l == 1 && *idx == current_chunk - 1)
-            .map(|(_, t, _)| t.as_str())
-            .unwrap_or("Previous");
+        let prev_title = headings[current_chunk - 1].as_deref().unwrap_or("Previous");
         html.push_str(&format!(
             "<a href=\"chunk{:03}.html\">&laquo; {}</a> ",
             current_chunk - 1,
@@ -223,11 +230,7 @@ th, td {{ border: 1px solid #ddd; padding: 8px; }}
         ));
     }
     if current_chunk + 1 < total_chunks {
-        let next_title = toc
-            .iter()
-            .find(|(l, _, idx)| *l == 1 && *idx == current_chunk + 1)
-            .map(|(_, t, _)| t.as_str())
-            .unwrap_or("Next");
+        let next_title = headings[current_chunk + 1].as_deref().unwrap_or("Next");
         html.push_str(&format!(
             "<a href=\"chunk{:03}.html\">{} &raquo;</a>",
             current_chunk + 1,
@@ -249,7 +252,10 @@ fn emit_node(node: &Node, output: &mut String) {
     match node.kind.as_str() {
         node::HEADING => {
             let level = node.props.get_int(prop::LEVEL).unwrap_or(1).clamp(1, 6);
-            output.push_str(&format!("<h{}>", level));
+            match node.props.get_str(prop::ID) {
+                Some(id) => output.push_str(&format!("<h{} id=\"{}\">", level, escape_html(id))),
+                None => output.push_str(&format!("<h{}>", level)),
+            }
             emit_inline_nodes(&node.children, output);
             output.push_str(&format!("</h{}>\n", level));
         }
@@ -422,14 +428,23 @@ mod tests {
 
     #[test]
     fn test_index_generation() {
-        let toc = vec![
-            (1, "Chapter 1".to_string(), 0),
-            (2, "Section 1.1".to_string(), 0),
-            (1, "Chapter 2".to_string(), 1),
-        ];
-        let index = generate_index_page("Test", &toc);
-        assert!(index.contains("Chapter 1"));
-        assert!(index.contains("Chapter 2"));
-        assert!(index.contains("chunk000.html"));
+        let document = doc(|d| {
+            d.heading(1, |h| h.text("Chapter 1"))
+                .heading(2, |h| h.text("Section 1.1"))
+                .para(|p| p.link("#chapter-2", |l| l.text("see")))
+                .heading(1, |h| h.text("Chapter 2"))
+        });
+        let chunks = emit(&document).unwrap().value;
+        let page = |i: usize| String::from_utf8(chunks[i].content.clone()).unwrap();
+
+        let index = page(0);
+        assert!(index.contains(r##"<a href="chunk000.html#chapter-1">Chapter 1</a>"##));
+        assert!(index.contains(r##"<a href="chunk000.html#section-1.1">Section 1.1</a>"##));
+        assert!(index.contains(r##"<a href="chunk001.html#chapter-2">Chapter 2</a>"##));
+
+        let first = page(1);
+        assert!(first.contains(r#"<h2 id="section-1.1">Section 1.1</h2>"#));
+        assert!(first.contains(r##"<a href="chunk001.html#chapter-2">see</a>"##));
+        assert!(first.contains("Chapter 2 &raquo;"));
     }
 }
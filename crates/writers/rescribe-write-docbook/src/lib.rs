@@ -21,14 +21,49 @@
 
 use quick_xml::Writer;
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
-use rescribe_core::{ConversionResult, Document, EmitError, Node};
-use rescribe_std::{node, prop};
-use std::io::Cursor;
+use rescribe_core::{ConversionResult, Document, EmitError, EmitOptions, Node};
+use rescribe_std::{NodeExt, node, prop};
+use std::io::Write;
+
+/// Writer options, set through [`EmitOptions::format`].
+pub mod options {
+    use rescribe_core::OptionSpec;
+
+    /// Write equations as MathML, with their TeX source as `<alt>`, rather
+    /// than as TeX in `<mathphrase>`.
+    pub const MATHML: OptionSpec = OptionSpec::flag("mathml", false, "Write equations as MathML");
+
+    /// All writer options.
+    pub const ALL: &[OptionSpec] = &[MATHML];
+}
 
 /// Emit a document to DocBook XML.
 pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
+    emit_with_options(doc, &EmitOptions::default())
+}
+
+/// Emit a document to DocBook XML with custom options.
+pub fn emit_with_options(
+    doc: &Document,
+    options: &EmitOptions,
+) -> Result<ConversionResult<Vec<u8>>, EmitError> {
+    let mut output = Vec::new();
+    let result = emit_to(doc, options, &mut output)?;
+    Ok(ConversionResult::with_warnings(output, result.warnings))
+}
+
+/// Emit a document to DocBook XML, streaming it to `out`.
+///
+/// Output is written as it is produced; wrap unbuffered sinks such as files
+/// in a [`std::io::BufWriter`].
+pub fn emit_to(
+    doc: &Document,
+    options: &EmitOptions,
+    out: &mut dyn Write,
+) -> Result<ConversionResult<()>, EmitError> {
     let warnings = Vec::new();
-    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let mathml = options.format.flag(&options::MATHML);
+    let mut writer = Writer::new(out);
 
     // XML declaration
     writer
@@ -50,7 +85,7 @@ pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
 
     // Write content
     for child in &doc.content.children {
-        write_node(&mut writer, child)?;
+        write_node(&mut writer, child, mathml)?;
     }
 
     // End article
@@ -58,12 +93,11 @@ pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
         .write_event(Event::End(BytesEnd::new("article")))
         .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
 
-    let result = writer.into_inner().into_inner();
-    Ok(ConversionResult::with_warnings(result, warnings))
+    Ok(ConversionResult::with_warnings((), warnings))
 }
 
 fn write_element(
-    writer: &mut Writer<Cursor<Vec<u8>>>,
+    writer: &mut Writer<&mut dyn Write>,
     tag: &str,
     text: &str,
 ) -> Result<(), EmitError> {
@@ -79,11 +113,110 @@ fn write_element(
     Ok(())
 }
 
-fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), EmitError> {
+/// Write math as an `<informalequation>` or `<inlineequation>`: MathML if
+/// `mathml` is set and the source can be written as MathML, and otherwise
+/// the TeX source as a `<mathphrase>`.
+fn write_equation(
+    writer: &mut Writer<&mut dyn Write>,
+    node: &Node,
+    mathml: bool,
+) -> Result<(), EmitError> {
+    let element = if node.kind.as_str() == "math_display" {
+        "informalequation"
+    } else {
+        "inlineequation"
+    };
+    let source = node.props.get_str("math:source");
+    let math = if mathml {
+        rescribe_math::mathml::emit_prefixed(node, "mml").ok()
+    } else {
+        None
+    };
+
+    writer
+        .write_event(Event::Start(BytesStart::new(element)))
+        .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+    match (math, source) {
+        (Some(math), source) => {
+            if let Some(source) = source {
+                let mut alt = BytesStart::new("alt");
+                alt.push_attribute(("role", "tex"));
+                write_text_element(writer, alt, source)?;
+            }
+            writer
+                .write_event(Event::Text(BytesText::from_escaped(math)))
+                .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+        }
+        (None, source) => {
+            let mut phrase = BytesStart::new("mathphrase");
+            phrase.push_attribute(("role", "tex"));
+            write_text_element(writer, phrase, source.unwrap_or_default())?;
+        }
+    }
+    writer
+        .write_event(Event::End(BytesEnd::new(element)))
+        .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+    Ok(())
+}
+
+fn write_text_element(
+    writer: &mut Writer<&mut dyn Write>,
+    start: BytesStart<'_>,
+    text: &str,
+) -> Result<(), EmitError> {
+    let end = start.to_end().into_owned();
+    writer
+        .write_event(Event::Start(start))
+        .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+    writer
+        .write_event(Event::Text(BytesText::new(text)))
+        .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+    writer
+        .write_event(Event::End(end))
+        .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+    Ok(())
+}
+
+fn write_node(
+    writer: &mut Writer<&mut dyn Write>,
+    node: &Node,
+    mathml: bool,
+) -> Result<(), EmitError> {
+    if let Some(section) = node.as_section() {
+        let mut start = BytesStart::new("section");
+        if let Some(id) = section.id() {
+            start.push_attribute(("xml:id", id));
+        }
+        if let Some(number) = section.heading().and_then(|h| h.number()) {
+            start.push_attribute(("label", number));
+        }
+        writer
+            .write_event(Event::Start(start))
+            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+        if let Some(heading) = section.heading() {
+            writer
+                .write_event(Event::Start(BytesStart::new("title")))
+                .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+            for child in heading.content() {
+                write_inline(writer, child, mathml)?;
+            }
+            writer
+                .write_event(Event::End(BytesEnd::new("title")))
+                .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+        }
+        for child in section.body() {
+            write_node(writer, child, mathml)?;
+        }
+        writer
+            .write_event(Event::End(BytesEnd::new("section")))
+            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+        return Ok(());
+    }
+
     match node.kind.as_str() {
         node::DOCUMENT | node::DIV => {
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
         }
 
@@ -109,7 +242,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
 
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
 
             writer
@@ -126,7 +259,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("para")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("para")))
@@ -138,7 +271,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("blockquote")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("blockquote")))
@@ -157,7 +290,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new(tag)))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new(tag)))
@@ -169,7 +302,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("listitem")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("listitem")))
@@ -191,10 +324,10 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                     })?;
 
                 if i < node.children.len() {
-                    write_node(writer, &node.children[i])?;
+                    write_node(writer, &node.children[i], mathml)?;
                 }
                 if i + 1 < node.children.len() {
-                    write_node(writer, &node.children[i + 1])?;
+                    write_node(writer, &node.children[i + 1], mathml)?;
                 }
 
                 writer
@@ -216,7 +349,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("term")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("term")))
@@ -228,7 +361,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("listitem")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("listitem")))
@@ -269,7 +402,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
 
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
 
             writer
@@ -288,7 +421,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("row")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("row")))
@@ -300,7 +433,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("entry")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("entry")))
@@ -312,7 +445,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("figure")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("figure")))
@@ -352,7 +485,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("footnote")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("footnote")))
@@ -365,16 +498,18 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
             writer
                 .write_event(Event::Start(BytesStart::new("para")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-            write_inline(writer, node)?;
+            write_inline(writer, node, mathml)?;
             writer
                 .write_event(Event::End(BytesEnd::new("para")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
         }
 
+        "math_display" => write_equation(writer, node, mathml)?,
+
         _ => {
             // Unknown block - recurse into children
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
         }
     }
@@ -382,7 +517,11 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
     Ok(())
 }
 
-fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), EmitError> {
+fn write_inline(
+    writer: &mut Writer<&mut dyn Write>,
+    node: &Node,
+    mathml: bool,
+) -> Result<(), EmitError> {
     match node.kind.as_str() {
         node::TEXT => {
             if let Some(content) = node.props.get_str(prop::CONTENT) {
@@ -399,7 +538,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(BytesStart::new("emphasis")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("emphasis")))
@@ -413,7 +552,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(emphasis))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("emphasis")))
@@ -432,7 +571,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                     })?;
             }
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("code")))
@@ -448,7 +587,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(link))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("link")))
@@ -460,7 +599,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(BytesStart::new("subscript")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("subscript")))
@@ -472,7 +611,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(BytesStart::new("superscript")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("superscript")))
@@ -516,10 +655,12 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
         }
 
+        "math_inline" | "math_display" => write_equation(writer, node, mathml)?,
+
         _ => {
             // Unknown inline - recurse
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
         }
     }
@@ -532,6 +673,52 @@ mod tests {
     use super::*;
     use rescribe_core::Properties;
 
+    #[test]
+    fn test_emit_to_writer() {
+        let doc = Document::new().with_content(Node::new(node::DOCUMENT).child(
+            Node::new(node::PARAGRAPH).child(Node::new(node::TEXT).prop(prop::CONTENT, "One")),
+        ));
+
+        let mut out = Vec::new();
+        emit_to(&doc, &EmitOptions::default(), &mut out).unwrap();
+        assert!(String::from_utf8_lossy(&out).contains("<para>One</para>"));
+        assert_eq!(out, emit(&doc).unwrap().value);
+
+        let mut full = [0u8; 16];
+        let err = emit_to(&doc, &EmitOptions::default(), &mut &mut full[..]).unwrap_err();
+        assert!(matches!(err, EmitError::Io(_)));
+    }
+
+    #[test]
+    fn test_emit_nested_sections() {
+        let heading = |level: i64, id: &str, number: &str, text: &str| {
+            Node::new(node::HEADING)
+                .prop(prop::LEVEL, level)
+                .prop(prop::ID, id)
+                .prop(prop::NUMBER, number)
+                .child(Node::new(node::TEXT).prop(prop::CONTENT, text))
+        };
+        let section =
+            |level: i64| Node::new(node::DIV).prop(prop::CLASSES, format!("section level{level}"));
+        let doc = Document::new().with_content(
+            Node::new(node::DOCUMENT).child(
+                section(1)
+                    .child(heading(1, "intro", "1", "Intro"))
+                    .child(
+                        Node::new(node::PARAGRAPH)
+                            .child(Node::new(node::TEXT).prop(prop::CONTENT, "Text")),
+                    )
+                    .child(section(2).child(heading(2, "details", "1.1", "Details"))),
+            ),
+        );
+
+        let xml = String::from_utf8(emit(&doc).unwrap().value).unwrap();
+        assert!(xml.contains(
+            "<section xml:id=\"intro\" label=\"1\"><title>Intro</title><para>Text</para>\
+             <section xml:id=\"details\" label=\"1.1\"><title>Details</title></section></section>"
+        ));
+    }
+
     #[test]
     fn test_emit_empty() {
         let doc = Document {
@@ -580,4 +767,35 @@ mod tests {
         let xml = String::from_utf8(result.value).unwrap();
         assert!(xml.contains("<title>Test Document</title>"));
     }
+
+    #[test]
+    fn test_emit_equations() {
+        let doc = Document::new().with_content(
+            Node::new(node::DOCUMENT)
+                .child(
+                    Node::new(node::PARAGRAPH)
+                        .child(Node::new("math_inline").prop("math:source", "a<b")),
+                )
+                .child(Node::new("math_display").prop("math:source", r"\sqrt{2}")),
+        );
+
+        let xml = String::from_utf8(emit(&doc).unwrap().value).unwrap();
+        assert!(xml.contains(
+            r#"<para><inlineequation><mathphrase role="tex">a&lt;b</mathphrase></inlineequation></para>"#
+        ));
+        assert!(xml.contains(
+            r#"<informalequation><mathphrase role="tex">\sqrt{2}</mathphrase></informalequation>"#
+        ));
+
+        let options = EmitOptions {
+            format: rescribe_core::FormatOptions::new().with("mathml", true),
+            ..Default::default()
+        };
+        let xml = String::from_utf8(emit_with_options(&doc, &options).unwrap().value).unwrap();
+        assert!(xml.contains(
+            "<informalequation><alt role=\"tex\">\\sqrt{2}</alt>\
+             <mml:math xmlns:mml=\"http://www.w3.org/1998/Math/MathML\" display=\"block\">"
+        ));
+        assert!(xml.contains("<mml:msqrt><mml:mn>2</mml:mn></mml:msqrt>"));
+    }
 }
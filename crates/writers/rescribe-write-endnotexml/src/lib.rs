@@ -1,17 +1,56 @@
 //! EndNote XML writer for rescribe.
 //!
-//! Emits documents as EndNote XML bibliography files.
+//! Emits documents as EndNote XML bibliography files. Each `bib:entry` node
+//! (see [`rescribe_bib`]) becomes a `<record>`; `endnote:<path>` custom
+//! fields are written back as elements at that path.
 
 use quick_xml::Writer;
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
-use rescribe_core::{ConversionResult, Document, EmitError, EmitOptions, FidelityWarning, Node};
+use rescribe_bib::{Date, Entry, Name, prop};
+use rescribe_core::{
+    ConversionResult, Document, EmitError, EmitOptions, FidelityWarning, Severity, WarningKind,
+};
+use std::collections::HashSet;
 use std::io::Cursor;
 
-/// EndNote entry node type.
-const ENDNOTE_ENTRY: &str = "endnote:entry";
-const BIBTEX_ENTRY: &str = "bibtex:entry";
-const RIS_ENTRY: &str = "ris:entry";
-const CITATION_ENTRY: &str = "citation_entry";
+/// EndNote reference types: name, number and CSL type.
+const REF_TYPES: &[(&str, u8, &str)] = &[
+    ("Journal Article", 17, "article-journal"),
+    ("Magazine Article", 19, "article-magazine"),
+    ("Newspaper Article", 23, "article-newspaper"),
+    ("Book", 6, "book"),
+    ("Book Section", 5, "chapter"),
+    ("Conference Paper", 47, "paper-conference"),
+    ("Thesis", 32, "thesis"),
+    ("Report", 27, "report"),
+    ("Web Page", 12, "webpage"),
+    ("Blog", 56, "post-weblog"),
+    ("Computer Program", 9, "software"),
+    ("Dataset", 59, "dataset"),
+    ("Patent", 25, "patent"),
+    ("Manuscript", 36, "manuscript"),
+    ("Pamphlet", 24, "pamphlet"),
+    ("Dictionary", 52, "entry-dictionary"),
+    ("Encyclopedia", 53, "entry-encyclopedia"),
+    ("Map", 20, "map"),
+    ("Artwork", 2, "graphic"),
+    ("Film or Broadcast", 21, "motion_picture"),
+    ("Bill", 4, "bill"),
+    ("Case", 7, "legal_case"),
+    ("Statute", 31, "legislation"),
+    ("Hearing", 14, "hearing"),
+    ("Standard", 58, "standard"),
+    ("Personal Communication", 26, "personal_communication"),
+    ("Generic", 13, "document"),
+    ("Conference Proceedings", 10, "paper-conference"),
+    ("Edited Book", 28, "book"),
+    ("Electronic Article", 43, "article-journal"),
+    ("Electronic Book", 44, "book"),
+    ("Electronic Book Section", 60, "chapter"),
+    ("Unpublished Work", 34, "manuscript"),
+    ("Government Document", 46, "report"),
+    ("Audiovisual Material", 3, "motion_picture"),
+];
 
 /// Emit a document as EndNote XML.
 pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
@@ -29,6 +68,47 @@ pub fn emit_with_options(
     Ok(ConversionResult::with_warnings(ctx.finish(), warnings))
 }
 
+/// An element of a record, built before it is written so that fields that
+/// share a parent element end up in it.
+struct Element {
+    name: String,
+    attrs: Vec<(&'static str, String)>,
+    text: Option<String>,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            attrs: Vec::new(),
+            text: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Add an element with `text` at `path`, which is relative to this
+    /// element and separated by `/`. Parent elements are shared.
+    fn add(&mut self, path: &str, text: &str) -> &mut Element {
+        let (parents, name) = path.rsplit_once('/').unwrap_or(("", path));
+        let mut parent = self;
+        for part in parents.split('/').filter(|p| !p.is_empty()) {
+            let index = match parent.children.iter().rposition(|c| c.name == part) {
+                Some(index) => index,
+                None => {
+                    parent.children.push(Element::new(part));
+                    parent.children.len() - 1
+                }
+            };
+            parent = &mut parent.children[index];
+        }
+        let mut element = Element::new(name);
+        element.text = Some(text.to_string());
+        parent.children.push(element);
+        parent.children.last_mut().unwrap()
+    }
+}
+
 struct EmitContext {
     writer: Writer<Cursor<Vec<u8>>>,
     warnings: Vec<FidelityWarning>,
@@ -44,372 +124,162 @@ impl EmitContext {
         }
     }
 
-    fn write_document(&mut self, doc: &Document) -> Result<(), EmitError> {
-        // XML declaration
-        self.writer
-            .write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        // Root element
-        self.writer
-            .write_event(Event::Start(BytesStart::new("xml")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        // Records element
-        self.writer
-            .write_event(Event::Start(BytesStart::new("records")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        // Write all entries
-        self.write_nodes(&doc.content.children)?;
-
-        // Close records
-        self.writer
-            .write_event(Event::End(BytesEnd::new("records")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        // Close root
-        self.writer
-            .write_event(Event::End(BytesEnd::new("xml")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        Ok(())
-    }
-
-    fn write_nodes(&mut self, nodes: &[Node]) -> Result<(), EmitError> {
-        for node in nodes {
-            self.write_node(node)?;
-        }
-        Ok(())
-    }
-
-    fn write_node(&mut self, node: &Node) -> Result<(), EmitError> {
-        match node.kind.as_str() {
-            "document" | "definition_list" => self.write_nodes(&node.children)?,
-            ENDNOTE_ENTRY => self.write_endnote_entry(node)?,
-            BIBTEX_ENTRY => self.write_bibtex_entry(node)?,
-            RIS_ENTRY => self.write_ris_entry(node)?,
-            CITATION_ENTRY => self.write_citation_entry(node)?,
-            _ => {
-                if is_bibtex_type(node.kind.as_str()) {
-                    self.write_typed_entry(node)?;
-                } else {
-                    self.write_nodes(&node.children)?;
-                }
-            }
-        }
-        Ok(())
-    }
-
-    fn write_endnote_entry(&mut self, node: &Node) -> Result<(), EmitError> {
-        self.record_count += 1;
-
-        self.writer
-            .write_event(Event::Start(BytesStart::new("record")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        // Record number
-        self.write_element("rec-number", &self.record_count.to_string())?;
-
-        // Reference type
-        if let Some(ref_type) = node.props.get_str("endnote:type") {
-            self.write_element("ref-type", ref_type)?;
-        }
-
-        // Write all endnote: prefixed properties
-        for (key, value) in node.props.iter() {
-            if let Some(field) = key.strip_prefix("endnote:")
-                && field != "type"
-                && field != "key"
-                && let rescribe_core::PropValue::String(s) = value
-            {
-                self.write_element(field, s)?;
-            }
-        }
-
-        self.writer
-            .write_event(Event::End(BytesEnd::new("record")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        Ok(())
-    }
-
-    fn write_bibtex_entry(&mut self, node: &Node) -> Result<(), EmitError> {
-        self.record_count += 1;
-
+    fn write_event(&mut self, event: Event) -> Result<(), EmitError> {
         self.writer
-            .write_event(Event::Start(BytesStart::new("record")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        self.write_element("rec-number", &self.record_count.to_string())?;
-
-        // Convert bibtex type to EndNote type
-        if let Some(bibtex_type) = node.props.get_str("bibtex:type") {
-            let endnote_type = bibtex_to_endnote_type(bibtex_type);
-            self.write_element("ref-type", endnote_type)?;
-        }
-
-        // Map bibtex fields
-        self.write_bibtex_fields(node)?;
-
-        self.writer
-            .write_event(Event::End(BytesEnd::new("record")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        Ok(())
+            .write_event(event)
+            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))
     }
 
-    fn write_bibtex_fields(&mut self, node: &Node) -> Result<(), EmitError> {
-        // Titles
-        if let Some(title) = node.props.get_str("bibtex:title") {
-            self.write_nested_element("titles", "title", title)?;
-        }
-
-        // Authors
-        if let Some(author) = node.props.get_str("bibtex:author") {
-            self.write_authors(author)?;
-        }
-
-        // Year
-        if let Some(year) = node.props.get_str("bibtex:year") {
-            self.write_nested_element("dates", "year", year)?;
-        }
-
-        // Journal -> secondary-title
-        if let Some(journal) = node.props.get_str("bibtex:journal") {
-            self.write_nested_element("periodical", "full-title", journal)?;
-        }
-
-        // Volume
-        if let Some(volume) = node.props.get_str("bibtex:volume") {
-            self.write_element("volume", volume)?;
-        }
-
-        // Number
-        if let Some(number) = node.props.get_str("bibtex:number") {
-            self.write_element("number", number)?;
-        }
-
-        // Pages
-        if let Some(pages) = node.props.get_str("bibtex:pages") {
-            self.write_element("pages", pages)?;
-        }
-
-        // Publisher
-        if let Some(publisher) = node.props.get_str("bibtex:publisher") {
-            self.write_element("publisher", publisher)?;
-        }
-
-        // DOI
-        if let Some(doi) = node.props.get_str("bibtex:doi") {
-            self.write_element("electronic-resource-num", doi)?;
-        }
-
-        // URL
-        if let Some(url) = node.props.get_str("bibtex:url") {
-            self.write_nested_element("urls", "web-urls", url)?;
-        }
+    fn write_document(&mut self, doc: &Document) -> Result<(), EmitError> {
+        self.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+        self.write_event(Event::Start(BytesStart::new("xml")))?;
+        self.write_event(Event::Start(BytesStart::new("records")))?;
 
-        // Abstract
-        if let Some(abs) = node.props.get_str("bibtex:abstract") {
-            self.write_element("abstract", abs)?;
+        for entry in rescribe_bib::entries(&doc.content) {
+            let record = self.convert_entry(entry);
+            self.write_element(&record)?;
         }
 
+        self.write_event(Event::End(BytesEnd::new("records")))?;
+        self.write_event(Event::End(BytesEnd::new("xml")))?;
         Ok(())
     }
 
-    fn write_ris_entry(&mut self, node: &Node) -> Result<(), EmitError> {
+    fn convert_entry(&mut self, entry: Entry) -> Element {
         self.record_count += 1;
-
-        self.writer
-            .write_event(Event::Start(BytesStart::new("record")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        self.write_element("rec-number", &self.record_count.to_string())?;
-
-        // Convert RIS type to EndNote type
-        if let Some(ris_type) = node.props.get_str("ris:type") {
-            let endnote_type = ris_to_endnote_type(ris_type);
-            self.write_element("ref-type", endnote_type)?;
-        }
-
-        // Map RIS fields
-        for (key, value) in node.props.iter() {
-            if let Some(tag) = key.strip_prefix("ris:")
-                && tag != "type"
-                && tag != "key"
-                && let rescribe_core::PropValue::String(s) = value
-                && let Some(endnote_field) = ris_tag_to_endnote(tag)
-            {
-                self.write_element(endnote_field, s)?;
+        let mut record = Record {
+            entry,
+            used: HashSet::new(),
+            element: Element::new("record"),
+        };
+        record
+            .element
+            .add("rec-number", &self.record_count.to_string());
+
+        let csl_type = entry.entry_type();
+        let (name, number) = match entry.custom("endnote:ref-type") {
+            Some(custom) => REF_TYPES
+                .iter()
+                .find(|(name, _, _)| *name == custom)
+                .map_or((custom, 13), |(name, number, _)| (*name, *number)),
+            None => REF_TYPES
+                .iter()
+                .find(|(_, _, csl)| *csl == csl_type)
+                .map_or(("Generic", 13), |(name, number, _)| (*name, *number)),
+        };
+        record
+            .element
+            .add("ref-type", &number.to_string())
+            .attrs
+            .push(("name", name.to_string()));
+
+        record.names("contributors/authors/author", prop::AUTHOR);
+        record.names("contributors/secondary-authors/author", prop::EDITOR);
+        record.names(
+            "contributors/tertiary-authors/author",
+            prop::COLLECTION_EDITOR,
+        );
+        record.names("contributors/translated-authors/author", prop::TRANSLATOR);
+        record.text("titles/title", prop::TITLE);
+        record.text("titles/secondary-title", prop::CONTAINER_TITLE);
+        record.text("titles/tertiary-title", prop::COLLECTION_TITLE);
+        record.text("titles/short-title", prop::TITLE_SHORT);
+        record.text("titles/alt-title", prop::CONTAINER_TITLE_SHORT);
+        // EndNote repeats journal titles in `<periodical>`.
+        if csl_type.starts_with("article") {
+            record.text("periodical/full-title", prop::CONTAINER_TITLE);
+            record.text("periodical/abbr-1", prop::CONTAINER_TITLE_SHORT);
+        }
+        record.text("pages", prop::PAGE);
+        record.text("volume", prop::VOLUME);
+        record.text("number", prop::ISSUE);
+        record.text("edition", prop::EDITION);
+        record.text("num-vols", prop::NUMBER_OF_VOLUMES);
+        record.used.insert(prop::KEYWORD);
+        if let Some(keywords) = entry.text(prop::KEYWORD) {
+            for keyword in keywords.split(',').map(str::trim) {
+                record.element.add("keywords/keyword", keyword);
             }
         }
 
-        self.writer
-            .write_event(Event::End(BytesEnd::new("record")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        Ok(())
-    }
-
-    fn write_citation_entry(&mut self, node: &Node) -> Result<(), EmitError> {
-        self.record_count += 1;
-
-        self.writer
-            .write_event(Event::Start(BytesStart::new("record")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        self.write_element("rec-number", &self.record_count.to_string())?;
-
-        // Convert CSL type to EndNote type
-        if let Some(csl_type) = node.props.get_str("type") {
-            let endnote_type = csl_to_endnote_type(csl_type);
-            self.write_element("ref-type", endnote_type)?;
-        }
-
-        // Map CSL fields
-        if let Some(title) = node.props.get_str("title") {
-            self.write_nested_element("titles", "title", title)?;
-        }
-        if let Some(author) = node.props.get_str("author") {
-            self.write_authors(author)?;
-        }
-        if let Some(issued) = node.props.get_str("issued") {
-            self.write_nested_element("dates", "year", issued)?;
-        }
-        if let Some(container) = node.props.get_str("container-title") {
-            self.write_nested_element("periodical", "full-title", container)?;
-        }
-        if let Some(volume) = node.props.get_str("volume") {
-            self.write_element("volume", volume)?;
-        }
-        if let Some(page) = node.props.get_str("page") {
-            self.write_element("pages", page)?;
-        }
-        if let Some(doi) = node.props.get_str("DOI") {
-            self.write_element("electronic-resource-num", doi)?;
+        record.used.insert(prop::ISSUED);
+        if let Some(date) = entry.date(prop::ISSUED) {
+            match date.year() {
+                Some(year) => record.element.add("dates/year", &year.to_string()),
+                None => record.element.add("dates/year", &format_date(&date)),
+            };
+            if date.start.is_some_and(|start| start.month.is_some()) {
+                record
+                    .element
+                    .add("dates/pub-dates/date", &format_date(&date));
+            }
         }
-        if let Some(url) = node.props.get_str("URL") {
-            self.write_nested_element("urls", "web-urls", url)?;
+        record.text("pub-location", prop::PUBLISHER_PLACE);
+        record.text("publisher", prop::PUBLISHER);
+        record.text("isbn", prop::ISBN);
+        record.text("isbn", prop::ISSN);
+        record.text("call-num", prop::CALL_NUMBER);
+        record.text("work-type", prop::GENRE);
+        record.used.insert(prop::ID);
+        record.element.add("label", entry.id());
+        record.text("urls/related-urls/url", prop::URL);
+        record.text("electronic-resource-num", prop::DOI);
+        record.text("abstract", prop::ABSTRACT);
+        record.text("notes", prop::NOTE);
+        record.text("language", prop::LANGUAGE);
+        record.used.insert(prop::ACCESSED);
+        if let Some(date) = entry.date(prop::ACCESSED) {
+            record.element.add("access-date", &format_date(&date));
+        }
+
+        // Elements read from EndNote XML that have no CSL variable
+        let mut lost = Vec::new();
+        for (key, value) in entry.custom_fields() {
+            match key.strip_prefix("endnote:") {
+                Some("ref-type") => {}
+                Some(path) => {
+                    for value in value.lines() {
+                        record.element.add(path, value);
+                    }
+                }
+                None => lost.push(key),
+            }
         }
-
-        self.writer
-            .write_event(Event::End(BytesEnd::new("record")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        Ok(())
+        lost.extend(
+            entry
+                .variables()
+                .into_iter()
+                .filter(|(name, _)| !record.used.contains(format!("bib:{name}").as_str()))
+                .map(|(name, _)| name),
+        );
+        if !lost.is_empty() {
+            self.warnings.push(FidelityWarning::new(
+                Severity::Minor,
+                WarningKind::UnsupportedProperty(lost.join(", ")),
+                format!(
+                    "EndNote XML has no element for {} in entry '{}'",
+                    lost.join(", "),
+                    entry.id()
+                ),
+            ));
+        }
+
+        record.element
     }
 
-    fn write_typed_entry(&mut self, node: &Node) -> Result<(), EmitError> {
-        self.record_count += 1;
-
-        self.writer
-            .write_event(Event::Start(BytesStart::new("record")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        self.write_element("rec-number", &self.record_count.to_string())?;
-
-        let endnote_type = bibtex_to_endnote_type(node.kind.as_str());
-        self.write_element("ref-type", endnote_type)?;
-
-        // Standard fields
-        if let Some(title) = node.props.get_str("title") {
-            self.write_nested_element("titles", "title", title)?;
-        }
-        if let Some(author) = node.props.get_str("author") {
-            self.write_authors(author)?;
-        }
-        if let Some(year) = node.props.get_str("year") {
-            self.write_nested_element("dates", "year", year)?;
-        }
-        if let Some(journal) = node.props.get_str("journal") {
-            self.write_nested_element("periodical", "full-title", journal)?;
-        }
-        if let Some(volume) = node.props.get_str("volume") {
-            self.write_element("volume", volume)?;
-        }
-        if let Some(number) = node.props.get_str("number") {
-            self.write_element("number", number)?;
-        }
-        if let Some(pages) = node.props.get_str("pages") {
-            self.write_element("pages", pages)?;
+    fn write_element(&mut self, element: &Element) -> Result<(), EmitError> {
+        let mut start = BytesStart::new(element.name.as_str());
+        for (key, value) in &element.attrs {
+            start.push_attribute((*key, value.as_str()));
         }
-        if let Some(publisher) = node.props.get_str("publisher") {
-            self.write_element("publisher", publisher)?;
+        self.write_event(Event::Start(start))?;
+        if let Some(text) = &element.text {
+            self.write_event(Event::Text(BytesText::new(text)))?;
         }
-        if let Some(doi) = node.props.get_str("doi") {
-            self.write_element("electronic-resource-num", doi)?;
+        for child in &element.children {
+            self.write_element(child)?;
         }
-        if let Some(url) = node.props.get_str("url") {
-            self.write_nested_element("urls", "web-urls", url)?;
-        }
-        if let Some(abs) = node.props.get_str("abstract") {
-            self.write_element("abstract", abs)?;
-        }
-
-        self.writer
-            .write_event(Event::End(BytesEnd::new("record")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        Ok(())
-    }
-
-    fn write_element(&mut self, name: &str, value: &str) -> Result<(), EmitError> {
-        self.writer
-            .write_event(Event::Start(BytesStart::new(name)))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-        self.writer
-            .write_event(Event::Text(BytesText::new(value)))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-        self.writer
-            .write_event(Event::End(BytesEnd::new(name)))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-        Ok(())
-    }
-
-    fn write_nested_element(
-        &mut self,
-        parent: &str,
-        child: &str,
-        value: &str,
-    ) -> Result<(), EmitError> {
-        self.writer
-            .write_event(Event::Start(BytesStart::new(parent)))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-        self.write_element(child, value)?;
-        self.writer
-            .write_event(Event::End(BytesEnd::new(parent)))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-        Ok(())
-    }
-
-    fn write_authors(&mut self, authors: &str) -> Result<(), EmitError> {
-        self.writer
-            .write_event(Event::Start(BytesStart::new("contributors")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-        self.writer
-            .write_event(Event::Start(BytesStart::new("authors")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        // Split by " and " or ";"
-        let author_list: Vec<&str> = if authors.contains(" and ") {
-            authors.split(" and ").collect()
-        } else {
-            authors.split(';').collect()
-        };
-
-        for author in author_list {
-            self.write_element("author", author.trim())?;
-        }
-
-        self.writer
-            .write_event(Event::End(BytesEnd::new("authors")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-        self.writer
-            .write_event(Event::End(BytesEnd::new("contributors")))
-            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-
-        Ok(())
+        self.write_event(Event::End(BytesEnd::new(element.name.as_str())))
     }
 
     fn finish(self) -> Vec<u8> {
@@ -417,104 +287,62 @@ impl EmitContext {
     }
 }
 
-fn is_bibtex_type(s: &str) -> bool {
-    matches!(
-        s.to_lowercase().as_str(),
-        "article"
-            | "book"
-            | "booklet"
-            | "conference"
-            | "inbook"
-            | "incollection"
-            | "inproceedings"
-            | "manual"
-            | "mastersthesis"
-            | "misc"
-            | "phdthesis"
-            | "proceedings"
-            | "techreport"
-            | "unpublished"
-            | "online"
-            | "software"
-            | "dataset"
-    )
+/// A record being built from an entry.
+struct Record<'a> {
+    entry: Entry<'a>,
+    /// Properties that have been written.
+    used: HashSet<&'a str>,
+    element: Element,
 }
 
-fn bibtex_to_endnote_type(bibtex: &str) -> &'static str {
-    match bibtex.to_lowercase().as_str() {
-        "article" => "Journal Article",
-        "book" => "Book",
-        "incollection" | "inbook" => "Book Section",
-        "inproceedings" | "conference" => "Conference Paper",
-        "phdthesis" => "Thesis",
-        "mastersthesis" => "Thesis",
-        "techreport" => "Report",
-        "online" => "Web Page",
-        "software" => "Computer Program",
-        "dataset" => "Dataset",
-        "unpublished" => "Manuscript",
-        "booklet" => "Book",
-        "proceedings" => "Conference Proceedings",
-        "manual" => "Book",
-        _ => "Generic",
+impl<'a> Record<'a> {
+    /// Add an element from a string variable.
+    fn text(&mut self, path: &str, key: &'a str) {
+        self.used.insert(key);
+        if let Some(value) = self.entry.text(key) {
+            self.element.add(path, value);
+        }
     }
-}
 
-fn ris_to_endnote_type(ris: &str) -> &'static str {
-    match ris {
-        "JOUR" => "Journal Article",
-        "BOOK" => "Book",
-        "CHAP" | "SECT" => "Book Section",
-        "CONF" | "CPAPER" => "Conference Paper",
-        "THES" => "Thesis",
-        "RPRT" => "Report",
-        "ELEC" | "WEB" => "Web Page",
-        "COMP" => "Computer Program",
-        "DATA" => "Dataset",
-        "MGZN" | "NEWS" => "Magazine Article",
-        "UNPB" => "Manuscript",
-        _ => "Generic",
+    /// Add an element per name of a name variable.
+    fn names(&mut self, path: &str, key: &'a str) {
+        self.used.insert(key);
+        for name in self.entry.names(key) {
+            self.element.add(path, &format_name(&name));
+        }
     }
 }
 
-fn csl_to_endnote_type(csl: &str) -> &'static str {
-    match csl {
-        "article-journal" | "article-magazine" | "article-newspaper" => "Journal Article",
-        "book" => "Book",
-        "chapter" => "Book Section",
-        "paper-conference" => "Conference Paper",
-        "thesis" => "Thesis",
-        "report" => "Report",
-        "webpage" | "post-weblog" => "Web Page",
-        "software" => "Computer Program",
-        "dataset" => "Dataset",
-        _ => "Generic",
+/// Format a name as `Last, First, Suffix`.
+fn format_name(name: &Name) -> String {
+    if let Some(literal) = &name.literal {
+        return literal.clone();
     }
+    let mut text = name.family_part().unwrap_or_default();
+    if let Some(given) = &name.given {
+        text.push_str(", ");
+        text.push_str(given);
+    }
+    if let Some(suffix) = &name.suffix {
+        text.push_str(", ");
+        text.push_str(suffix);
+    }
+    text
 }
 
-fn ris_tag_to_endnote(tag: &str) -> Option<&'static str> {
-    match tag.to_uppercase().as_str() {
-        "TI" | "T1" => Some("title"),
-        "AU" | "A1" => Some("author"),
-        "PY" | "Y1" => Some("year"),
-        "JO" | "JF" | "T2" => Some("secondary-title"),
-        "VL" => Some("volume"),
-        "IS" => Some("number"),
-        "SP" | "EP" => Some("pages"),
-        "PB" => Some("publisher"),
-        "DO" => Some("electronic-resource-num"),
-        "UR" => Some("url"),
-        "AB" => Some("abstract"),
-        "KW" => Some("keyword"),
-        "SN" => Some("isbn"),
-        _ => None,
-    }
+/// Format a date as EDTF, or as its literal text.
+fn format_date(date: &Date) -> String {
+    date.to_edtf()
+        .or_else(|| date.literal.clone())
+        .unwrap_or_default()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rescribe_core::NodeKind;
+    use rescribe_bib::node;
+    use rescribe_core::{Node, NodeKind, PropValue};
+    use std::collections::HashMap;
 
     fn emit_str(doc: &Document) -> String {
         String::from_utf8(emit(doc).unwrap().value).unwrap()
@@ -530,33 +358,80 @@ mod tests {
     }
 
     #[test]
-    fn test_emit_typed_entry() {
-        let entry = Node::new(NodeKind::from("article"))
-            .prop("author", "Smith, John")
-            .prop("title", "A Great Paper")
-            .prop("journal", "Nature")
-            .prop("year", "2020");
+    fn test_emit_entry() {
+        let entry = Node::new(node::ENTRY)
+            .prop(prop::ID, "smith2020")
+            .prop(prop::TYPE, "article-journal")
+            .prop(prop::AUTHOR, Name::list([Name::parse("Smith, John")]))
+            .prop(prop::TITLE, "A Great Paper")
+            .prop(prop::CONTAINER_TITLE, "Nature")
+            .prop(prop::ISSUED, Date::parse("2020").unwrap());
 
         let doc = Document::new().with_content(Node::new(NodeKind::from("document")).child(entry));
 
         let output = emit_str(&doc);
         assert!(output.contains("<record>"));
-        assert!(output.contains("<ref-type>Journal Article</ref-type>"));
+        assert!(output.contains(r#"<ref-type name="Journal Article">17</ref-type>"#));
         assert!(output.contains("<author>Smith, John</author>"));
-        assert!(output.contains("<title>A Great Paper</title>"));
-        assert!(output.contains("<year>2020</year>"));
+        assert!(output.contains("<title>A Great Paper</title><secondary-title>Nature"));
+        assert!(output.contains("<periodical><full-title>Nature</full-title></periodical>"));
+        assert!(output.contains("<dates><year>2020</year></dates>"));
+        assert!(output.contains("<label>smith2020</label>"));
     }
 
     #[test]
     fn test_emit_multiple_authors() {
-        let entry = Node::new(NodeKind::from("article"))
-            .prop("author", "Smith, John and Doe, Jane")
-            .prop("title", "Collaborative Work");
+        let entry = Node::new(node::ENTRY)
+            .prop(prop::ID, "smith2021")
+            .prop(prop::TYPE, "book")
+            .prop(
+                prop::AUTHOR,
+                Name::list([Name::parse("Smith, John"), Name::parse("Doe, Jane")]),
+            )
+            .prop(prop::TITLE, "Collaborative Work");
 
         let doc = Document::new().with_content(Node::new(NodeKind::from("document")).child(entry));
 
         let output = emit_str(&doc);
-        assert!(output.contains("<author>Smith, John</author>"));
-        assert!(output.contains("<author>Doe, Jane</author>"));
+        assert!(
+            output.contains(
+                "<authors><author>Smith, John</author><author>Doe, Jane</author></authors>"
+            )
+        );
+        assert!(!output.contains("<periodical>"));
+    }
+
+    #[test]
+    fn test_emit_custom_fields() {
+        let custom = HashMap::from([
+            (
+                "endnote:ref-type".to_string(),
+                PropValue::from("Edited Book"),
+            ),
+            (
+                "endnote:urls/pdf-urls/url".to_string(),
+                PropValue::from("file.pdf"),
+            ),
+            ("bibtex:eprint".to_string(), PropValue::from("2101.00001")),
+        ]);
+        let entry = Node::new(node::ENTRY)
+            .prop(prop::ID, "letters")
+            .prop(prop::TYPE, "book")
+            .prop(prop::URL, "https://example.org")
+            .prop(prop::ISSUED, Date::parse("2019-03").unwrap())
+            .prop(prop::CUSTOM, PropValue::Map(custom));
+
+        let doc = Document::new().with_content(Node::new(NodeKind::from("document")).child(entry));
+
+        let result = emit(&doc).unwrap();
+        let output = String::from_utf8(result.value).unwrap();
+        assert!(output.contains(r#"<ref-type name="Edited Book">28</ref-type>"#));
+        assert!(output.contains("<pub-dates><date>2019-03</date></pub-dates>"));
+        assert!(output.contains(
+            "<urls><related-urls><url>https://example.org</url></related-urls>\
+             <pdf-urls><url>file.pdf</url></pdf-urls></urls>"
+        ));
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].message.contains("bibtex:eprint"));
     }
 }
@@ -12,12 +12,34 @@
 //! // result.value contains EPUB bytes
 //! ```
 
-use epub_builder::{EpubBuilder, EpubContent, ReferenceType, ZipLibrary};
-use rescribe_core::{ConversionResult, Document, EmitError, FidelityWarning, Node};
-use rescribe_std::{node, prop};
+use epub_builder::{EpubBuilder, EpubContent, ReferenceType, TocElement, ZipLibrary};
+use rescribe_core::{ConversionResult, Document, EmitError, EmitOptions, FidelityWarning, Node};
+use rescribe_std::{NodeExt, node, prop};
+use rescribe_transforms::{GenerateToc, SplitAt};
+use std::collections::HashMap;
+
+/// Writer options, set through [`EmitOptions::format`].
+pub mod options {
+    use rescribe_core::OptionSpec;
+
+    /// Write math as MathML, which EPUB 3 reading systems render natively,
+    /// rather than as TeX for MathJax.
+    pub const MATHML: OptionSpec = OptionSpec::flag("mathml", true, "Write math as MathML");
+
+    /// All writer options.
+    pub const ALL: &[OptionSpec] = &[MATHML];
+}
 
 /// Emit a document as an EPUB file.
 pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
+    emit_with_options(doc, &EmitOptions::default())
+}
+
+/// Emit a document as an EPUB file with custom options.
+pub fn emit_with_options(
+    doc: &Document,
+    options: &EmitOptions,
+) -> Result<ConversionResult<Vec<u8>>, EmitError> {
     let mut warnings = Vec::new();
     let mut output = Vec::new();
 
@@ -62,8 +84,17 @@ pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
             .map_err(|e| EmitError::Io(std::io::Error::other(format!("Metadata error: {}", e))))?;
     }
 
-    // Split document into chapters (each h1 starts a new chapter)
-    let chapters = split_into_chapters(&doc.content);
+    // Split document into chapters (each h1 starts a new chapter), with the
+    // navigation document built from the table of contents
+    let (doc, toc) = GenerateToc::new()
+        .table(doc.clone())
+        .map_err(|e| EmitError::Io(std::io::Error::other(format!("TOC error: {}", e))))?;
+    let chapters = split_into_chapters(&doc);
+    let mut nav = chapter_nav(toc.as_ref(), &chapters);
+    let mut html_options = options.clone();
+    html_options
+        .format
+        .set("mathml", options.format.flag(&options::MATHML));
 
     for (i, chapter) in chapters.iter().enumerate() {
         let title = chapter
@@ -72,11 +103,14 @@ pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
             .unwrap_or_else(|| format!("Chapter {}", i + 1));
 
         // Convert chapter content to HTML
-        let html = chapter_to_html(chapter, &mut warnings)?;
+        let html = chapter_to_html(chapter, &html_options, &mut warnings)?;
 
-        let content = EpubContent::new(format!("chapter{}.xhtml", i + 1), html.as_bytes())
+        let mut content = EpubContent::new(chapter_file(i), html.as_bytes())
             .title(title)
             .reftype(ReferenceType::Text);
+        for element in std::mem::take(&mut nav[i]) {
+            content = content.child(element);
+        }
 
         builder
             .add_content(content)
@@ -93,54 +127,109 @@ pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
 
 struct Chapter {
     title: Option<String>,
-    nodes: Vec<Node>,
+    /// The identifier of the heading the title comes from.
+    id: Option<String>,
+    /// The chapter's content, with the resources it refers to.
+    doc: Document,
 }
 
-fn split_into_chapters(root: &Node) -> Vec<Chapter> {
-    let mut chapters = Vec::new();
-    let mut current_nodes = Vec::new();
-    let mut current_title: Option<String> = None;
-
-    for node in &root.children {
-        // Check if this is an h1 heading
-        if node.kind.as_str() == node::HEADING {
-            let level = node.props.get_int(prop::LEVEL).unwrap_or(1);
-            if level == 1 {
-                // Start a new chapter
-                if !current_nodes.is_empty() || current_title.is_some() {
-                    chapters.push(Chapter {
-                        title: current_title.take(),
-                        nodes: std::mem::take(&mut current_nodes),
-                    });
-                }
-
-                // Extract title from heading
-                current_title = Some(extract_text(node));
-                // Don't include the h1 itself in nodes (it will be the chapter title)
-                continue;
+fn split_into_chapters(doc: &Document) -> Vec<Chapter> {
+    SplitAt::new(1)
+        .split(doc.clone())
+        .into_iter()
+        .map(|mut part| {
+            // The h1 becomes the chapter title rather than content
+            let heading = part
+                .content
+                .children
+                .first()
+                .and_then(|first| first.as_heading())
+                .filter(|heading| heading.level() == 1);
+            let title = heading.map(|heading| extract_text(heading.node()));
+            let id = heading.and_then(|heading| heading.id()).map(str::to_string);
+            if title.is_some() {
+                part.content.children.remove(0);
             }
-        }
+            Chapter {
+                title,
+                id,
+                doc: part,
+            }
+        })
+        .collect()
+}
 
-        current_nodes.push(node.clone());
+fn chapter_file(index: usize) -> String {
+    format!("chapter{}.xhtml", index + 1)
+}
+
+/// The navigation entries under each chapter, from the table of contents
+/// [`GenerateToc`] built. A chapter's own heading is the chapter's entry,
+/// so the headings below it become its children.
+fn chapter_nav(toc: Option<&Node>, chapters: &[Chapter]) -> Vec<Vec<TocElement>> {
+    let mut nav: Vec<Vec<TocElement>> = chapters.iter().map(|_| Vec::new()).collect();
+    let Some(list) = toc.and_then(|toc| toc.children.last()) else {
+        return nav;
+    };
+
+    let mut chapter_of = HashMap::new();
+    for (index, chapter) in chapters.iter().enumerate() {
+        if let Some(id) = &chapter.id {
+            chapter_of.insert(id.clone(), index);
+        }
+        collect_ids(&chapter.doc.content, index, &mut chapter_of);
     }
 
-    // Don't forget the last chapter
-    if !current_nodes.is_empty() || current_title.is_some() {
-        chapters.push(Chapter {
-            title: current_title,
-            nodes: current_nodes,
-        });
+    for item in &list.children {
+        let Some((id, _, sublist)) = toc_entry(item) else {
+            continue;
+        };
+        let Some(&index) = chapter_of.get(id) else {
+            continue;
+        };
+        if chapters[index].id.as_deref() == Some(id) {
+            if let Some(sublist) = sublist {
+                nav[index].extend(
+                    sublist
+                        .children
+                        .iter()
+                        .filter_map(|item| toc_element(item, &chapter_of)),
+                );
+            }
+        } else if let Some(element) = toc_element(item, &chapter_of) {
+            nav[index].push(element);
+        }
     }
+    nav
+}
 
-    // If no chapters were created (no h1 headings), create a single chapter
-    if chapters.is_empty() {
-        chapters.push(Chapter {
-            title: None,
-            nodes: root.children.clone(),
-        });
+fn collect_ids(node: &Node, index: usize, out: &mut HashMap<String, usize>) {
+    if let Some(id) = node.props.get_str(prop::ID) {
+        out.entry(id.to_string()).or_insert(index);
+    }
+    for child in &node.children {
+        collect_ids(child, index, out);
     }
+}
+
+/// The identifier a table of contents item links to, its text and its
+/// list of subentries.
+fn toc_entry(item: &Node) -> Option<(&str, String, Option<&Node>)> {
+    let link = item.children.first()?.children.first()?;
+    let id = link.props.get_str(prop::URL)?.strip_prefix('#')?;
+    Some((id, extract_text(link), item.children.get(1)))
+}
 
-    chapters
+fn toc_element(item: &Node, chapter_of: &HashMap<String, usize>) -> Option<TocElement> {
+    let (id, title, sublist) = toc_entry(item)?;
+    let index = chapter_of.get(id)?;
+    let mut element = TocElement::new(format!("{}#{}", chapter_file(*index), id), title);
+    for child in sublist.into_iter().flat_map(|list| &list.children) {
+        if let Some(child) = toc_element(child, chapter_of) {
+            element = element.child(child);
+        }
+    }
+    Some(element)
 }
 
 fn extract_text(node: &Node) -> String {
@@ -162,14 +251,11 @@ fn extract_text_recursive(node: &Node, output: &mut String) {
 
 fn chapter_to_html(
     chapter: &Chapter,
+    options: &EmitOptions,
     warnings: &mut Vec<FidelityWarning>,
 ) -> Result<String, EmitError> {
-    // Create a temporary document with the chapter content
-    let temp_doc =
-        Document::new().with_content(Node::new(node::DOCUMENT).children(chapter.nodes.clone()));
-
     // Use HTML writer to convert
-    let result = rescribe_write_html::emit_full_document(&temp_doc)
+    let result = rescribe_write_html::emit_full_document_with_options(&chapter.doc, options)
         .map_err(|e| EmitError::Io(std::io::Error::other(format!("HTML emit error: {}", e))))?;
 
     warnings.extend(result.warnings);
@@ -210,9 +296,50 @@ mod tests {
             )
             .child(Node::new(node::PARAGRAPH));
 
-        let chapters = split_into_chapters(&root);
+        let chapters = split_into_chapters(&Document::new().with_content(root));
         assert_eq!(chapters.len(), 2);
         assert_eq!(chapters[0].title, Some("Ch1".to_string()));
         assert_eq!(chapters[1].title, Some("Ch2".to_string()));
     }
+
+    #[test]
+    fn test_chapter_nav() {
+        let document = doc(|d| {
+            d.para(|i| i.text("Preface"))
+                .heading(2, |i| i.text("Foreword"))
+                .heading(1, |i| i.text("Ch1"))
+                .heading(2, |i| i.text("Setup"))
+                .heading(3, |i| i.text("Details"))
+                .heading(1, |i| i.text("Ch2"))
+        });
+        let (document, toc) = GenerateToc::new().table(document).unwrap();
+        let chapters = split_into_chapters(&document);
+        let nav = chapter_nav(toc.as_ref(), &chapters);
+
+        let urls = |elements: &[TocElement]| -> Vec<String> {
+            elements.iter().map(|e| e.url.clone()).collect()
+        };
+        assert_eq!(urls(&nav[0]), ["chapter1.xhtml#foreword"]);
+        assert_eq!(urls(&nav[1]), ["chapter2.xhtml#setup"]);
+        assert_eq!(nav[1][0].title, "Setup");
+        assert_eq!(urls(&nav[1][0].children), ["chapter2.xhtml#details"]);
+        assert!(nav[2].is_empty());
+    }
+
+    #[test]
+    fn test_chapter_math_is_mathml() {
+        let math = Node::new("math_display").prop("math:source", r"\sqrt{2}");
+        let chapter = Chapter {
+            title: None,
+            id: None,
+            doc: Document::new().with_content(Node::new(node::DOCUMENT).child(math)),
+        };
+        let mut html_options = EmitOptions::default();
+        html_options.format.set("mathml", true);
+        let html = chapter_to_html(&chapter, &html_options, &mut Vec::new()).unwrap();
+        assert!(
+            html.contains("<math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"block\">")
+        );
+        assert!(html.contains("<msqrt><mn>2</mn></msqrt>"));
+    }
 }
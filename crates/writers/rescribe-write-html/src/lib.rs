@@ -8,7 +8,26 @@ use rescribe_core::{
     ConversionResult, Document, EmitError, EmitOptions, FidelityWarning, Node, ResourceId,
     ResourceMap, Severity, WarningKind,
 };
-use rescribe_std::{node, prop};
+use rescribe_std::{NodeExt, node, prop};
+use std::io::{self, Write};
+
+/// Writer options, set through [`EmitOptions::format`].
+pub mod options {
+    use rescribe_core::OptionSpec;
+
+    /// Emit a complete document with doctype, as [`emit_full_document`](crate::emit_full_document).
+    pub const STANDALONE: OptionSpec =
+        OptionSpec::flag("standalone", false, "Emit a complete HTML document");
+    /// Wrap each heading and the content up to the next heading of the same
+    /// or a higher level in a `<section>`.
+    pub const SECTION_DIVS: OptionSpec =
+        OptionSpec::flag("section_divs", false, "Wrap sections in <section> elements");
+    /// Write math as MathML rather than as TeX between MathJax delimiters.
+    pub const MATHML: OptionSpec = OptionSpec::flag("mathml", false, "Write math as MathML");
+
+    /// All writer options.
+    pub const ALL: &[OptionSpec] = &[STANDALONE, SECTION_DIVS, MATHML];
+}
 
 /// Emit a document as HTML.
 pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
@@ -20,15 +39,32 @@ pub fn emit_with_options(
     doc: &Document,
     options: &EmitOptions,
 ) -> Result<ConversionResult<Vec<u8>>, EmitError> {
-    let mut ctx = EmitContext::new(&doc.resources, options.pretty);
+    let mut output = Vec::new();
+    let result = emit_to(doc, options, &mut output)?;
+    Ok(ConversionResult::with_warnings(output, result.warnings))
+}
+
+/// Emit a document as HTML, streaming it to `out`.
+///
+/// Each top-level block is written as soon as it has been emitted, so a
+/// document wrapped in a single `div` is held in memory whole. Sections
+/// opened by the `section_divs` option are closed between blocks rather
+/// than held back. Wrap unbuffered sinks such as files in a
+/// [`std::io::BufWriter`].
+pub fn emit_to(
+    doc: &Document,
+    options: &EmitOptions,
+    out: &mut dyn Write,
+) -> Result<ConversionResult<()>, EmitError> {
+    if options.format.flag(&options::STANDALONE) {
+        return emit_full_document_to(doc, options, out);
+    }
+    let mut ctx = EmitContext::new(&doc.resources, options);
 
     // Emit children of the root document node
-    emit_nodes(&doc.content.children, &mut ctx);
+    emit_blocks(&doc.content.children, options, &mut ctx, out)?;
 
-    Ok(ConversionResult::with_warnings(
-        ctx.output.into_bytes(),
-        ctx.warnings,
-    ))
+    Ok(ConversionResult::with_warnings((), ctx.warnings))
 }
 
 /// Emit a document as a complete HTML document with doctype.
@@ -41,20 +77,69 @@ pub fn emit_full_document_with_options(
     doc: &Document,
     options: &EmitOptions,
 ) -> Result<ConversionResult<Vec<u8>>, EmitError> {
-    let mut ctx = EmitContext::new(&doc.resources, options.pretty);
+    let mut output = Vec::new();
+    let result = emit_full_document_to(doc, options, &mut output)?;
+    Ok(ConversionResult::with_warnings(output, result.warnings))
+}
+
+/// Emit a complete HTML document, streaming it to `out`. See [`emit_to`].
+pub fn emit_full_document_to(
+    doc: &Document,
+    options: &EmitOptions,
+    out: &mut dyn Write,
+) -> Result<ConversionResult<()>, EmitError> {
+    let mut ctx = EmitContext::new(&doc.resources, options);
 
     if ctx.pretty {
         ctx.write("<!DOCTYPE html>\n<html>\n<head>\n  <meta charset=\"utf-8\">\n</head>\n<body>\n");
     } else {
         ctx.write("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n</head>\n<body>\n");
     }
-    emit_nodes(&doc.content.children, &mut ctx);
+    emit_blocks(&doc.content.children, options, &mut ctx, out)?;
     ctx.write("\n</body>\n</html>\n");
+    ctx.drain(out)?;
 
-    Ok(ConversionResult::with_warnings(
-        ctx.output.into_bytes(),
-        ctx.warnings,
-    ))
+    Ok(ConversionResult::with_warnings((), ctx.warnings))
+}
+
+/// Emit top-level blocks, draining the output after each one.
+fn emit_blocks(
+    nodes: &[Node],
+    options: &EmitOptions,
+    ctx: &mut EmitContext,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let section_divs = options.format.flag(&options::SECTION_DIVS);
+    // Levels of the headings whose sections are open
+    let mut sections: Vec<u8> = Vec::new();
+
+    for node in nodes {
+        if section_divs && let Some(heading) = node.as_heading() {
+            while sections
+                .last()
+                .is_some_and(|&level| level >= heading.level())
+            {
+                sections.pop();
+                close_section(ctx);
+            }
+            ctx.newline();
+            ctx.write("<section>");
+            ctx.indent();
+            sections.push(heading.level());
+        }
+        emit_node(node, ctx);
+        ctx.drain(out)?;
+    }
+    for _ in sections {
+        close_section(ctx);
+    }
+    ctx.drain(out)
+}
+
+fn close_section(ctx: &mut EmitContext) {
+    ctx.dedent();
+    ctx.newline();
+    ctx.write("</section>");
 }
 
 /// Emit context for tracking state during emission.
@@ -64,16 +149,19 @@ struct EmitContext<'a> {
     resources: &'a ResourceMap,
     pretty: bool,
     indent: usize,
+    /// Whether math is written as MathML.
+    mathml: bool,
 }
 
 impl<'a> EmitContext<'a> {
-    fn new(resources: &'a ResourceMap, pretty: bool) -> Self {
+    fn new(resources: &'a ResourceMap, options: &EmitOptions) -> Self {
         Self {
             output: String::new(),
             warnings: Vec::new(),
             resources,
-            pretty,
+            pretty: options.pretty,
             indent: 0,
+            mathml: options.format.flag(&options::MATHML),
         }
     }
 
@@ -81,6 +169,13 @@ impl<'a> EmitContext<'a> {
         self.output.push_str(s);
     }
 
+    /// Write buffered output to `out`.
+    fn drain(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(self.output.as_bytes())?;
+        self.output.clear();
+        Ok(())
+    }
+
     /// Write a newline and indentation (only in pretty mode).
     fn newline(&mut self) {
         if self.pretty {
@@ -248,8 +343,10 @@ fn emit_common_attrs(node: &Node, ctx: &mut EmitContext) {
 
 /// Emit a heading element.
 fn emit_heading(node: &Node, ctx: &mut EmitContext) {
-    let level = node.props.get_int(prop::LEVEL).unwrap_or(1);
-    let tag = match level {
+    let Some(heading) = node.as_heading() else {
+        return;
+    };
+    let tag = match heading.level() {
         1 => "h1",
         2 => "h2",
         3 => "h3",
@@ -282,17 +379,18 @@ fn emit_code_block(node: &Node, ctx: &mut EmitContext) {
 
 /// Emit a list.
 fn emit_list(node: &Node, ctx: &mut EmitContext) {
-    let ordered = node.props.get_bool(prop::ORDERED).unwrap_or(false);
+    let Some(list) = node.as_list() else {
+        return;
+    };
+    let ordered = list.ordered();
     let tag = if ordered { "ol" } else { "ul" };
 
     ctx.newline();
     ctx.write("<");
     ctx.write(tag);
 
-    if ordered
-        && let Some(start) = node.props.get_int(prop::START)
-        && start != 1
-    {
+    let start = list.start();
+    if ordered && start != 1 {
         ctx.write(" start=\"");
         ctx.write(&start.to_string());
         ctx.write("\"");
@@ -474,13 +572,27 @@ fn emit_footnote_def(node: &Node, ctx: &mut EmitContext) {
 
 /// Emit quoted text.
 fn emit_quoted(node: &Node, ctx: &mut EmitContext) {
-    ctx.write("<q>");
-    emit_nodes(&node.children, ctx);
-    ctx.write("</q>");
+    let marks = (
+        node.props.get_str(prop::QUOTE_OPEN),
+        node.props.get_str(prop::QUOTE_CLOSE),
+    );
+    // `<q>` lets the browser pick the marks; explicit marks are written out
+    if let (Some(open), Some(close)) = marks {
+        ctx.write(&escape_html(open));
+        emit_nodes(&node.children, ctx);
+        ctx.write(&escape_html(close));
+    } else {
+        ctx.write("<q>");
+        emit_nodes(&node.children, ctx);
+        ctx.write("</q>");
+    }
 }
 
 /// Emit inline math.
 fn emit_math_inline(node: &Node, ctx: &mut EmitContext) {
+    if ctx.mathml && emit_mathml(node, ctx) {
+        return;
+    }
     if let Some(source) = node.props.get_str("math:source") {
         ctx.write("<span class=\"math math-inline\">\\(");
         ctx.write(&escape_html(source));
@@ -490,6 +602,9 @@ fn emit_math_inline(node: &Node, ctx: &mut EmitContext) {
 
 /// Emit display math.
 fn emit_math_display(node: &Node, ctx: &mut EmitContext) {
+    if ctx.mathml && emit_mathml(node, ctx) {
+        return;
+    }
     if let Some(source) = node.props.get_str("math:source") {
         ctx.write("<div class=\"math math-display\">\\[");
         ctx.write(&escape_html(source));
@@ -497,6 +612,25 @@ fn emit_math_display(node: &Node, ctx: &mut EmitContext) {
     }
 }
 
+/// Emit math as a MathML `<math>` element. Math that cannot be written as
+/// MathML is left to the caller, with a warning.
+fn emit_mathml(node: &Node, ctx: &mut EmitContext) -> bool {
+    match rescribe_math::mathml::emit(node) {
+        Ok(mathml) => {
+            ctx.write(&mathml);
+            true
+        }
+        Err(e) => {
+            ctx.warnings.push(FidelityWarning::new(
+                Severity::Minor,
+                WarningKind::FeatureLost("math".to_string()),
+                e.to_string(),
+            ));
+            false
+        }
+    }
+}
+
 /// Escape HTML special characters.
 fn escape_html(text: &str) -> String {
     let mut result = String::with_capacity(text.len());
@@ -667,4 +801,80 @@ mod tests {
         assert!(output.contains('\n'));
         assert!(output.contains("  <p>"));
     }
+
+    #[test]
+    fn test_section_divs() {
+        let doc = html(|d| {
+            d.h1(|i| i.text("A"))
+                .p(|i| i.text("a"))
+                .h2(|i| i.text("B"))
+                .h1(|i| i.text("C"))
+        });
+        let options = EmitOptions {
+            format: rescribe_core::FormatOptions::new().with("section_divs", true),
+            ..Default::default()
+        };
+        let output = String::from_utf8(emit_with_options(&doc, &options).unwrap().value).unwrap();
+        assert_eq!(
+            output,
+            "<section><h1>A</h1><p>a</p><section><h2>B</h2></section></section>\
+             <section><h1>C</h1></section>"
+        );
+    }
+
+    #[test]
+    fn test_standalone_option() {
+        let doc = html(|d| d.p(|i| i.text("Hi")));
+        let options = EmitOptions {
+            format: rescribe_core::FormatOptions::new().with("standalone", true),
+            ..Default::default()
+        };
+        let output = String::from_utf8(emit_with_options(&doc, &options).unwrap().value).unwrap();
+        assert!(output.starts_with("<!DOCTYPE html>"));
+    }
+
+    #[test]
+    fn test_mathml_option() {
+        let math = |source: &str| Node::new("math_inline").prop("math:source", source);
+        let mut doc = html(|d| d.p(|i| i.text("Area ")));
+        doc.content.children[0].children.push(math("r^2"));
+        doc.content.children[0].children.push(math(r"\frac{"));
+
+        assert_eq!(
+            emit_str(&doc),
+            "<p>Area <span class=\"math math-inline\">\\(r^2\\)</span>\
+             <span class=\"math math-inline\">\\(\\frac{\\)</span></p>"
+        );
+
+        let options = EmitOptions {
+            format: rescribe_core::FormatOptions::new().with("mathml", true),
+            ..Default::default()
+        };
+        let result = emit_with_options(&doc, &options).unwrap();
+        let output = String::from_utf8(result.value).unwrap();
+        assert!(output.starts_with(
+            "<p>Area <math xmlns=\"http://www.w3.org/1998/Math/MathML\"><semantics>\
+             <msup><mi>r</mi><mn>2</mn></msup>"
+        ));
+        // Invalid TeX stays TeX
+        assert!(output.ends_with("<span class=\"math math-inline\">\\(\\frac{\\)</span></p>"));
+        assert_eq!(result.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_emit_quoted() {
+        let quoted = || {
+            rescribe_std::Node::new(node::QUOTED)
+                .child(rescribe_std::Node::new(node::TEXT).prop(prop::CONTENT, "ja"))
+        };
+        let mut doc = html(|d| d.p(|i| i.text("Er sagte ")));
+        doc.content.children[0].children.push(quoted());
+        assert_eq!(emit_str(&doc), "<p>Er sagte <q>ja</q></p>");
+
+        let marked = quoted()
+            .prop(prop::QUOTE_OPEN, "\u{201E}")
+            .prop(prop::QUOTE_CLOSE, "\u{201C}");
+        doc.content.children[0].children[1] = marked;
+        assert_eq!(emit_str(&doc), "<p>Er sagte \u{201E}ja\u{201C}</p>");
+    }
 }
@@ -4,14 +4,50 @@
 
 use quick_xml::Writer;
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
-use rescribe_core::{ConversionResult, Document, EmitError, Node};
-use rescribe_std::{node, prop};
-use std::io::Cursor;
+use rescribe_core::{ConversionResult, Document, EmitError, EmitOptions, Node};
+use rescribe_std::{NodeExt, node, prop};
+use std::io::Write;
+
+/// Writer options, set through [`EmitOptions::format`].
+pub mod options {
+    use rescribe_core::OptionSpec;
+
+    /// Write MathML alongside the TeX source of formulas, as
+    /// `<alternatives>`.
+    pub const MATHML: OptionSpec =
+        OptionSpec::flag("mathml", false, "Write formulas as MathML as well as TeX");
+
+    /// All writer options.
+    pub const ALL: &[OptionSpec] = &[MATHML];
+}
 
 /// Emit a document to JATS XML.
 pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
+    emit_with_options(doc, &EmitOptions::default())
+}
+
+/// Emit a document to JATS XML with custom options.
+pub fn emit_with_options(
+    doc: &Document,
+    options: &EmitOptions,
+) -> Result<ConversionResult<Vec<u8>>, EmitError> {
+    let mut output = Vec::new();
+    let result = emit_to(doc, options, &mut output)?;
+    Ok(ConversionResult::with_warnings(output, result.warnings))
+}
+
+/// Emit a document to JATS XML, streaming it to `out`.
+///
+/// Output is written as it is produced; wrap unbuffered sinks such as files
+/// in a [`std::io::BufWriter`].
+pub fn emit_to(
+    doc: &Document,
+    options: &EmitOptions,
+    out: &mut dyn Write,
+) -> Result<ConversionResult<()>, EmitError> {
     let warnings = Vec::new();
-    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let mathml = options.format.flag(&options::MATHML);
+    let mut writer = Writer::new(out);
 
     // XML declaration
     writer
@@ -60,7 +96,7 @@ pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
         .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
 
     for child in &doc.content.children {
-        write_node(&mut writer, child)?;
+        write_node(&mut writer, child, mathml)?;
     }
 
     writer
@@ -72,12 +108,11 @@ pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
         .write_event(Event::End(BytesEnd::new("article")))
         .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
 
-    let result = writer.into_inner().into_inner();
-    Ok(ConversionResult::with_warnings(result, warnings))
+    Ok(ConversionResult::with_warnings((), warnings))
 }
 
 fn write_element(
-    writer: &mut Writer<Cursor<Vec<u8>>>,
+    writer: &mut Writer<&mut dyn Write>,
     tag: &str,
     text: &str,
 ) -> Result<(), EmitError> {
@@ -93,11 +128,89 @@ fn write_element(
     Ok(())
 }
 
-fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), EmitError> {
+/// Write math as a formula: its TeX source, and if `mathml` is set and the
+/// source can be written as MathML, MathML as an alternative.
+fn write_formula(
+    writer: &mut Writer<&mut dyn Write>,
+    node: &Node,
+    element: &str,
+    mathml: bool,
+) -> Result<(), EmitError> {
+    let source = node.props.get_str("math:source");
+    let math = if mathml {
+        rescribe_math::mathml::emit_prefixed(node, "mml").ok()
+    } else {
+        None
+    };
+    let alternatives = source.is_some() && math.is_some();
+
+    writer
+        .write_event(Event::Start(BytesStart::new(element)))
+        .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+    if alternatives {
+        writer
+            .write_event(Event::Start(BytesStart::new("alternatives")))
+            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+    }
+    if let Some(source) = source {
+        write_element(writer, "tex-math", source)?;
+    }
+    if let Some(math) = math {
+        writer
+            .write_event(Event::Text(BytesText::from_escaped(math)))
+            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+    }
+    if alternatives {
+        writer
+            .write_event(Event::End(BytesEnd::new("alternatives")))
+            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+    }
+    writer
+        .write_event(Event::End(BytesEnd::new(element)))
+        .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+    Ok(())
+}
+
+fn write_node(
+    writer: &mut Writer<&mut dyn Write>,
+    node: &Node,
+    mathml: bool,
+) -> Result<(), EmitError> {
+    if let Some(section) = node.as_section() {
+        let mut start = BytesStart::new("sec");
+        if let Some(id) = section.id() {
+            start.push_attribute(("id", id));
+        }
+        writer
+            .write_event(Event::Start(start))
+            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+        if let Some(heading) = section.heading() {
+            if let Some(number) = heading.number() {
+                write_element(writer, "label", number)?;
+            }
+            writer
+                .write_event(Event::Start(BytesStart::new("title")))
+                .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+            for child in heading.content() {
+                write_inline(writer, child, mathml)?;
+            }
+            writer
+                .write_event(Event::End(BytesEnd::new("title")))
+                .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+        }
+        for child in section.body() {
+            write_node(writer, child, mathml)?;
+        }
+        writer
+            .write_event(Event::End(BytesEnd::new("sec")))
+            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+        return Ok(());
+    }
+
     match node.kind.as_str() {
         node::DOCUMENT | node::DIV => {
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
         }
 
@@ -117,7 +230,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                         EmitError::Io(std::io::Error::other(format!("XML error: {}", e)))
                     })?;
                 for child in &node.children {
-                    write_inline(writer, child)?;
+                    write_inline(writer, child, mathml)?;
                 }
                 writer
                     .write_event(Event::End(BytesEnd::new("title")))
@@ -141,7 +254,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                         EmitError::Io(std::io::Error::other(format!("XML error: {}", e)))
                     })?;
                 for child in &node.children {
-                    write_inline(writer, child)?;
+                    write_inline(writer, child, mathml)?;
                 }
                 writer
                     .write_event(Event::End(BytesEnd::new("title")))
@@ -161,7 +274,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("p")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("p")))
@@ -173,7 +286,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("disp-quote")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("disp-quote")))
@@ -193,7 +306,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(list))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("list")))
@@ -205,7 +318,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("list-item")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("list-item")))
@@ -227,10 +340,10 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                     })?;
 
                 if i < node.children.len() {
-                    write_node(writer, &node.children[i])?;
+                    write_node(writer, &node.children[i], mathml)?;
                 }
                 if i + 1 < node.children.len() {
-                    write_node(writer, &node.children[i + 1])?;
+                    write_node(writer, &node.children[i + 1], mathml)?;
                 }
 
                 writer
@@ -252,7 +365,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("term")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("term")))
@@ -264,7 +377,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("def")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("def")))
@@ -308,7 +421,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
 
             if has_structure {
                 for child in &node.children {
-                    write_node(writer, child)?;
+                    write_node(writer, child, mathml)?;
                 }
             } else {
                 // Wrap in tbody
@@ -318,7 +431,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                         EmitError::Io(std::io::Error::other(format!("XML error: {}", e)))
                     })?;
                 for child in &node.children {
-                    write_node(writer, child)?;
+                    write_node(writer, child, mathml)?;
                 }
                 writer
                     .write_event(Event::End(BytesEnd::new("tbody")))
@@ -340,7 +453,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("thead")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("thead")))
@@ -352,7 +465,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("tbody")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("tbody")))
@@ -364,7 +477,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("tr")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("tr")))
@@ -376,7 +489,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("td")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("td")))
@@ -388,7 +501,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("th")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("th")))
@@ -400,7 +513,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("fig")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("fig")))
@@ -426,45 +539,21 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("fn")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("fn")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
         }
 
-        "math_display" => {
-            writer
-                .write_event(Event::Start(BytesStart::new("disp-formula")))
-                .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-            if let Some(source) = node.props.get_str("math:source") {
-                writer
-                    .write_event(Event::Start(BytesStart::new("tex-math")))
-                    .map_err(|e| {
-                        EmitError::Io(std::io::Error::other(format!("XML error: {}", e)))
-                    })?;
-                writer
-                    .write_event(Event::Text(BytesText::new(source)))
-                    .map_err(|e| {
-                        EmitError::Io(std::io::Error::other(format!("XML error: {}", e)))
-                    })?;
-                writer
-                    .write_event(Event::End(BytesEnd::new("tex-math")))
-                    .map_err(|e| {
-                        EmitError::Io(std::io::Error::other(format!("XML error: {}", e)))
-                    })?;
-            }
-            writer
-                .write_event(Event::End(BytesEnd::new("disp-formula")))
-                .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-        }
+        "math_display" => write_formula(writer, node, "disp-formula", mathml)?,
 
         // Inline nodes that appear at block level
         node::TEXT | node::EMPHASIS | node::STRONG | node::CODE | node::LINK => {
             writer
                 .write_event(Event::Start(BytesStart::new("p")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-            write_inline(writer, node)?;
+            write_inline(writer, node, mathml)?;
             writer
                 .write_event(Event::End(BytesEnd::new("p")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
@@ -472,7 +561,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
 
         _ => {
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
         }
     }
@@ -480,7 +569,11 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
     Ok(())
 }
 
-fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), EmitError> {
+fn write_inline(
+    writer: &mut Writer<&mut dyn Write>,
+    node: &Node,
+    mathml: bool,
+) -> Result<(), EmitError> {
     match node.kind.as_str() {
         node::TEXT => {
             if let Some(content) = node.props.get_str(prop::CONTENT) {
@@ -497,7 +590,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(BytesStart::new("italic")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("italic")))
@@ -509,7 +602,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(BytesStart::new("bold")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("bold")))
@@ -521,7 +614,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(BytesStart::new("underline")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("underline")))
@@ -533,7 +626,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(BytesStart::new("strike")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("strike")))
@@ -552,7 +645,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                     })?;
             }
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("monospace")))
@@ -569,19 +662,39 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(link))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("ext-link")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
         }
 
+        node::CITE => {
+            // One cross-reference to all cited entries; `rid` takes a list.
+            let ids: Vec<String> = node
+                .as_cite()
+                .map(|c| c.citations().into_iter().map(|c| c.id).collect())
+                .unwrap_or_default();
+            let mut xref = BytesStart::new("xref");
+            xref.push_attribute(("ref-type", "bibr"));
+            xref.push_attribute(("rid", ids.join(" ").as_str()));
+            writer
+                .write_event(Event::Start(xref))
+                .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+            for child in &node.children {
+                write_inline(writer, child, mathml)?;
+            }
+            writer
+                .write_event(Event::End(BytesEnd::new("xref")))
+                .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+        }
+
         node::SUBSCRIPT => {
             writer
                 .write_event(Event::Start(BytesStart::new("sub")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("sub")))
@@ -593,7 +706,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(BytesStart::new("sup")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("sup")))
@@ -605,7 +718,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(BytesStart::new("sc")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("sc")))
@@ -634,35 +747,11 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
         }
 
-        "math_inline" => {
-            writer
-                .write_event(Event::Start(BytesStart::new("inline-formula")))
-                .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-            if let Some(source) = node.props.get_str("math:source") {
-                writer
-                    .write_event(Event::Start(BytesStart::new("tex-math")))
-                    .map_err(|e| {
-                        EmitError::Io(std::io::Error::other(format!("XML error: {}", e)))
-                    })?;
-                writer
-                    .write_event(Event::Text(BytesText::new(source)))
-                    .map_err(|e| {
-                        EmitError::Io(std::io::Error::other(format!("XML error: {}", e)))
-                    })?;
-                writer
-                    .write_event(Event::End(BytesEnd::new("tex-math")))
-                    .map_err(|e| {
-                        EmitError::Io(std::io::Error::other(format!("XML error: {}", e)))
-                    })?;
-            }
-            writer
-                .write_event(Event::End(BytesEnd::new("inline-formula")))
-                .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-        }
+        "math_inline" => write_formula(writer, node, "inline-formula", mathml)?,
 
         _ => {
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
         }
     }
@@ -675,6 +764,47 @@ mod tests {
     use super::*;
     use rescribe_core::Properties;
 
+    #[test]
+    fn test_emit_to_writer() {
+        let doc = Document::new().with_content(Node::new(node::DOCUMENT).child(
+            Node::new(node::PARAGRAPH).child(Node::new(node::TEXT).prop(prop::CONTENT, "One")),
+        ));
+
+        let mut out = Vec::new();
+        emit_to(&doc, &EmitOptions::default(), &mut out).unwrap();
+        assert!(String::from_utf8_lossy(&out).contains("<p>One</p>"));
+        assert_eq!(out, emit(&doc).unwrap().value);
+
+        let mut full = [0u8; 16];
+        let err = emit_to(&doc, &EmitOptions::default(), &mut &mut full[..]).unwrap_err();
+        assert!(matches!(err, EmitError::Io(_)));
+    }
+
+    #[test]
+    fn test_emit_nested_sections() {
+        let heading = |level: i64, id: &str, text: &str| {
+            Node::new(node::HEADING)
+                .prop(prop::LEVEL, level)
+                .prop(prop::ID, id)
+                .child(Node::new(node::TEXT).prop(prop::CONTENT, text))
+        };
+        let section =
+            |level: i64| Node::new(node::DIV).prop(prop::CLASSES, format!("section level{level}"));
+        let doc = Document::new().with_content(
+            Node::new(node::DOCUMENT).child(
+                section(1)
+                    .child(heading(1, "methods", "Methods").prop(prop::NUMBER, "2"))
+                    .child(section(2).child(heading(2, "sampling", "Sampling"))),
+            ),
+        );
+
+        let xml = String::from_utf8(emit(&doc).unwrap().value).unwrap();
+        assert!(xml.contains(
+            "<sec id=\"methods\"><label>2</label><title>Methods</title>\
+             <sec id=\"sampling\"><title>Sampling</title></sec></sec>"
+        ));
+    }
+
     #[test]
     fn test_emit_empty() {
         let doc = Document {
@@ -749,4 +879,43 @@ mod tests {
         assert!(xml.contains("<italic>italic</italic>"));
         assert!(xml.contains("<bold>bold</bold>"));
     }
+
+    #[test]
+    fn test_emit_cite() {
+        use rescribe_std::Citation;
+
+        let cite = Node::new(node::CITE)
+            .prop(
+                prop::CITATIONS,
+                Citation::list([Citation::new("doe99"), Citation::new("roe")]),
+            )
+            .child(Node::new(node::TEXT).prop(prop::CONTENT, "[@doe99; @roe]"));
+        let doc = Document::new()
+            .with_content(Node::new(node::DOCUMENT).child(Node::new(node::PARAGRAPH).child(cite)));
+
+        let xml = String::from_utf8(emit(&doc).unwrap().value).unwrap();
+        assert!(xml.contains(r#"<xref ref-type="bibr" rid="doe99 roe">[@doe99; @roe]</xref>"#));
+    }
+
+    #[test]
+    fn test_emit_formula() {
+        let math = Node::new("math_inline").prop("math:source", "x^2");
+        let doc = Document::new()
+            .with_content(Node::new(node::DOCUMENT).child(Node::new(node::PARAGRAPH).child(math)));
+
+        let xml = String::from_utf8(emit(&doc).unwrap().value).unwrap();
+        assert!(xml.contains("<inline-formula><tex-math>x^2</tex-math></inline-formula>"));
+
+        let options = EmitOptions {
+            format: rescribe_core::FormatOptions::new().with("mathml", true),
+            ..Default::default()
+        };
+        let xml = String::from_utf8(emit_with_options(&doc, &options).unwrap().value).unwrap();
+        assert!(xml.contains(
+            "<inline-formula><alternatives><tex-math>x^2</tex-math>\
+             <mml:math xmlns:mml=\"http://www.w3.org/1998/Math/MathML\"><mml:semantics>\
+             <mml:msup><mml:mi>x</mml:mi><mml:mn>2</mml:mn></mml:msup>"
+        ));
+        assert!(xml.contains("</mml:math></alternatives></inline-formula>"));
+    }
 }
@@ -0,0 +1,214 @@
+//! rescribe JSON writer.
+//!
+//! Emits rescribe's document IR as "rescribe JSON", the canonical on-disk
+//! form of a [`Document`]. Unlike Pandoc JSON it is lossless: every node
+//! kind, property, span and resource is preserved.
+//!
+//! Resources are embedded as base64 by default. [`emit_external`] instead
+//! returns them as separate files referenced from the JSON by relative path.
+
+use rescribe_core::{ConversionResult, Document, EmitError, EmitOptions, ResourceId};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// A resource written outside the JSON document.
+#[derive(Debug, Clone)]
+pub struct ExternalResource {
+    /// Path relative to the JSON file, as referenced by its `href`.
+    pub filename: String,
+    pub content: Vec<u8>,
+}
+
+/// JSON output with resources stored as separate files.
+#[derive(Debug, Clone)]
+pub struct ExternalOutput {
+    pub json: Vec<u8>,
+    pub resources: Vec<ExternalResource>,
+}
+
+/// Emit a document as rescribe JSON.
+pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
+    emit_with_options(doc, &EmitOptions::default())
+}
+
+/// Emit a document as rescribe JSON with custom options.
+pub fn emit_with_options(
+    doc: &Document,
+    options: &EmitOptions,
+) -> Result<ConversionResult<Vec<u8>>, EmitError> {
+    let json = if options.pretty {
+        serde_json::to_vec_pretty(doc)
+    } else {
+        serde_json::to_vec(doc)
+    }
+    .map_err(json_error)?;
+
+    Ok(ConversionResult::ok(json))
+}
+
+/// Emit a document as rescribe JSON, storing resources as separate files
+/// under `dir` (relative to the JSON file; may be empty).
+///
+/// Files are named after resource IDs reduced to safe file names, so every
+/// file stays inside `dir`.
+pub fn emit_external(
+    doc: &Document,
+    options: &EmitOptions,
+    dir: &str,
+) -> Result<ConversionResult<ExternalOutput>, EmitError> {
+    let mut value = serde_json::to_value(doc).map_err(json_error)?;
+    let mut resources = Vec::new();
+    let mut taken = HashSet::new();
+
+    if let Some(Value::Object(entries)) = value.get_mut("resources") {
+        for (id, entry) in entries.iter_mut() {
+            let Some(resource) = doc.resources.get(&ResourceId::from_string(id.as_str())) else {
+                continue;
+            };
+            let Value::Object(entry) = entry else {
+                continue;
+            };
+            let stem = ResourceId::from_string(id.as_str()).safe_stem();
+            let extension = extension_for(&resource.mime_type);
+            let mut file = format!("{stem}.{extension}");
+            let mut n = 1;
+            while !taken.insert(file.clone()) {
+                n += 1;
+                file = format!("{stem}-{n}.{extension}");
+            }
+            let filename = if dir.is_empty() {
+                file
+            } else {
+                format!("{}/{file}", dir.trim_end_matches('/'))
+            };
+            entry.remove("data");
+            entry.insert("href".to_string(), Value::String(filename.clone()));
+            resources.push(ExternalResource {
+                filename,
+                content: resource.data.clone(),
+            });
+        }
+    }
+
+    let json = if options.pretty {
+        serde_json::to_vec_pretty(&value)
+    } else {
+        serde_json::to_vec(&value)
+    }
+    .map_err(json_error)?;
+
+    Ok(ConversionResult::ok(ExternalOutput { json, resources }))
+}
+
+fn json_error(e: serde_json::Error) -> EmitError {
+    EmitError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// File extension for a resource's MIME type.
+fn extension_for(mime_type: &str) -> &'static str {
+    match mime_type {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/svg+xml" => "svg",
+        "image/webp" => "webp",
+        "image/bmp" => "bmp",
+        "image/tiff" => "tiff",
+        "application/pdf" => "pdf",
+        "font/ttf" => "ttf",
+        "font/otf" => "otf",
+        "font/woff" => "woff",
+        "font/woff2" => "woff2",
+        "text/css" => "css",
+        "text/plain" => "txt",
+        _ => "bin",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rescribe_core::{Resource, Span};
+    use rescribe_std::builder::doc;
+    use rescribe_std::{Node, node, prop};
+
+    fn emit_str(doc: &Document) -> String {
+        String::from_utf8(emit(doc).unwrap().value).unwrap()
+    }
+
+    #[test]
+    fn test_emit_is_versioned_and_sorted() {
+        let document = doc(|d| d.heading(2, |i| i.text("Title")));
+        let json = emit_str(&document);
+        assert!(json.starts_with(r#"{"version":1,"#));
+        assert!(json.contains(
+            r#"{"kind":"heading","props":{"level":{"int":2}},"children":[{"kind":"text","props":{"content":{"string":"Title"}}}]}"#
+        ));
+    }
+
+    #[test]
+    fn test_emit_is_deterministic() {
+        let mut node = Node::new(node::PARAGRAPH);
+        for key in ["b", "a", "d", "c", "f", "e"] {
+            node = node.prop(key, key);
+        }
+        let document = Document::new().with_content(Node::new(node::DOCUMENT).child(node));
+        assert_eq!(emit_str(&document), emit_str(&document.clone()));
+        assert!(emit_str(&document).contains(r#""a":{"string":"a"},"b""#));
+    }
+
+    #[test]
+    fn test_emit_span_and_resource() {
+        let mut document = Document::new();
+        let id = document.embed(Resource::png(vec![1, 2, 3]));
+        document.content = Node::new(node::DOCUMENT).child(
+            Node::new(node::IMAGE)
+                .prop(prop::URL, id.as_str())
+                .span(Span { start: 0, end: 4 }),
+        );
+        let json = emit_str(&document);
+        assert!(json.contains(r#""span":{"start":0,"end":4}"#));
+        assert!(json.contains(r#""mime_type":"image/png","data":"AQID""#));
+    }
+
+    #[test]
+    fn test_emit_external_resources() {
+        let mut document = Document::new();
+        let id = document.embed(Resource::jpeg(vec![0xff, 0xd8]));
+        let output = emit_external(&document, &EmitOptions::default(), "media/")
+            .unwrap()
+            .value;
+        let json = String::from_utf8(output.json).unwrap();
+
+        let filename = format!("media/{}.jpg", id.as_str());
+        assert!(json.contains(&format!(r#""href":"{filename}""#)));
+        assert!(!json.contains(r#""data""#));
+        assert_eq!(output.resources.len(), 1);
+        assert_eq!(output.resources[0].filename, filename);
+        assert_eq!(output.resources[0].content, vec![0xff, 0xd8]);
+    }
+
+    #[test]
+    fn test_emit_external_hostile_ids() {
+        let mut document = Document::new();
+        for id in ["../../x", "/etc/passwd", "a/b", "a_b"] {
+            document
+                .resources
+                .insert(ResourceId::from_string(id), Resource::png(vec![1]));
+        }
+        let output = emit_external(&document, &EmitOptions::default(), "media")
+            .unwrap()
+            .value;
+
+        let mut names: Vec<_> = output
+            .resources
+            .iter()
+            .map(|r| r.filename.strip_prefix("media/").unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            ["______x.png", "_etc_passwd.png", "a_b-2.png", "a_b.png"]
+        );
+    }
+}
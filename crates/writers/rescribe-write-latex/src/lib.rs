@@ -8,7 +8,26 @@ use rescribe_core::{
     ConversionResult, Document, EmitError, EmitOptions, FidelityWarning, Node, Severity,
     WarningKind,
 };
-use rescribe_std::{node, prop};
+use rescribe_std::{Citation, CitationMode, NodeExt, node, prop};
+use std::io::{self, Write};
+
+/// Writer options, set through [`EmitOptions::format`].
+pub mod options {
+    use rescribe_core::OptionSpec;
+
+    /// Emit a complete document with preamble, as [`emit_full_document`](crate::emit_full_document).
+    pub const STANDALONE: OptionSpec =
+        OptionSpec::flag("standalone", false, "Emit a complete LaTeX document");
+    /// Class passed to `\documentclass` in standalone output.
+    pub const DOCUMENT_CLASS: OptionSpec = OptionSpec::string(
+        "document_class",
+        "article",
+        "Document class for standalone output",
+    );
+
+    /// All writer options.
+    pub const ALL: &[OptionSpec] = &[STANDALONE, DOCUMENT_CLASS];
+}
 
 /// Emit a document as LaTeX fragment (body content only).
 pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
@@ -18,24 +37,62 @@ pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
 /// Emit a document as LaTeX with custom options.
 pub fn emit_with_options(
     doc: &Document,
-    _options: &EmitOptions,
+    options: &EmitOptions,
 ) -> Result<ConversionResult<Vec<u8>>, EmitError> {
+    let mut output = Vec::new();
+    let result = emit_to(doc, options, &mut output)?;
+    Ok(ConversionResult::with_warnings(output, result.warnings))
+}
+
+/// Emit a document as LaTeX, streaming it to `out`.
+///
+/// Each top-level block is written as soon as it has been emitted, in
+/// standalone documents too, where the preamble is written first.
+pub fn emit_to(
+    doc: &Document,
+    options: &EmitOptions,
+    out: &mut dyn Write,
+) -> Result<ConversionResult<()>, EmitError> {
+    if options.format.flag(&options::STANDALONE) {
+        return emit_full_document_to(doc, options, out);
+    }
     let mut ctx = EmitContext::new();
 
-    emit_nodes(&doc.content.children, &mut ctx);
+    for node in &doc.content.children {
+        emit_node(node, &mut ctx);
+        ctx.drain(out)?;
+    }
 
-    Ok(ConversionResult::with_warnings(
-        ctx.output.into_bytes(),
-        ctx.warnings,
-    ))
+    Ok(ConversionResult::with_warnings((), ctx.warnings))
 }
 
 /// Emit a complete LaTeX document with preamble.
 pub fn emit_full_document(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
+    emit_full_document_with_options(doc, &EmitOptions::default())
+}
+
+/// Emit a complete LaTeX document with preamble and options.
+pub fn emit_full_document_with_options(
+    doc: &Document,
+    options: &EmitOptions,
+) -> Result<ConversionResult<Vec<u8>>, EmitError> {
+    let mut output = Vec::new();
+    let result = emit_full_document_to(doc, options, &mut output)?;
+    Ok(ConversionResult::with_warnings(output, result.warnings))
+}
+
+/// Emit a complete LaTeX document, streaming it to `out`. See [`emit_to`].
+pub fn emit_full_document_to(
+    doc: &Document,
+    options: &EmitOptions,
+    out: &mut dyn Write,
+) -> Result<ConversionResult<()>, EmitError> {
     let mut ctx = EmitContext::new();
 
     // Preamble
-    ctx.write("\\documentclass{article}\n");
+    ctx.write("\\documentclass{");
+    ctx.write(options.format.string(&options::DOCUMENT_CLASS));
+    ctx.write("}\n");
     ctx.write("\\usepackage[utf8]{inputenc}\n");
     ctx.write("\\usepackage{graphicx}\n");
     ctx.write("\\usepackage{hyperref}\n");
@@ -43,16 +100,29 @@ pub fn emit_full_document(doc: &Document) -> Result<ConversionResult<Vec<u8>>, E
     ctx.write("\\usepackage{amsmath}\n");
     ctx.write("\\usepackage{amssymb}\n");
     ctx.write("\\usepackage{ulem}\n"); // For strikethrough
+    let citations = contains_cite(&doc.content);
+    if citations {
+        ctx.write("\\usepackage{biblatex}\n");
+        if let Some(bibliography) = doc.metadata.get_str("bibliography") {
+            ctx.write("\\addbibresource{");
+            ctx.write(bibliography);
+            ctx.write("}\n");
+        }
+    }
     ctx.write("\n\\begin{document}\n\n");
 
-    emit_nodes(&doc.content.children, &mut ctx);
+    for node in &doc.content.children {
+        emit_node(node, &mut ctx);
+        ctx.drain(out)?;
+    }
 
+    if citations {
+        ctx.write("\n\\printbibliography\n");
+    }
     ctx.write("\n\\end{document}\n");
+    ctx.drain(out)?;
 
-    Ok(ConversionResult::with_warnings(
-        ctx.output.into_bytes(),
-        ctx.warnings,
-    ))
+    Ok(ConversionResult::with_warnings((), ctx.warnings))
 }
 
 /// Emit context for tracking state during emission.
@@ -75,6 +145,13 @@ impl EmitContext {
         self.output.push_str(s);
     }
 
+    /// Write buffered output to `out`.
+    fn drain(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(self.output.as_bytes())?;
+        self.output.clear();
+        Ok(())
+    }
+
     fn write_escaped(&mut self, s: &str) {
         if self.in_verbatim {
             self.output.push_str(s);
@@ -184,6 +261,7 @@ fn emit_node(node: &Node, ctx: &mut EmitContext) {
 
         node::FOOTNOTE_REF => emit_footnote_ref(node, ctx),
         node::FOOTNOTE_DEF => emit_footnote_def(node, ctx),
+        node::CITE => emit_cite(node, ctx),
 
         node::SMALL_CAPS => {
             ctx.write("\\textsc{");
@@ -222,8 +300,10 @@ fn emit_node(node: &Node, ctx: &mut EmitContext) {
 
 /// Emit a heading.
 fn emit_heading(node: &Node, ctx: &mut EmitContext) {
-    let level = node.props.get_int(prop::LEVEL).unwrap_or(1);
-    let cmd = match level {
+    let Some(heading) = node.as_heading() else {
+        return;
+    };
+    let cmd = match heading.level() {
         1 => "\\section{",
         2 => "\\subsection{",
         3 => "\\subsubsection{",
@@ -274,7 +354,7 @@ fn emit_blockquote(node: &Node, ctx: &mut EmitContext) {
 
 /// Emit a list.
 fn emit_list(node: &Node, ctx: &mut EmitContext) {
-    let ordered = node.props.get_bool(prop::ORDERED).unwrap_or(false);
+    let ordered = node.as_list().is_some_and(|list| list.ordered());
     let env = if ordered { "enumerate" } else { "itemize" };
 
     ctx.write("\\begin{");
@@ -435,10 +515,106 @@ fn emit_footnote_def(node: &Node, ctx: &mut EmitContext) {
     }
 }
 
+/// Emit a citation as a biblatex command: `\parencite`, `\textcite` for
+/// author-in-text citations or `\parencite*` when the author is
+/// suppressed. Citations with notes on more than the first and last key
+/// use the multicite form, `\parencites[see][4]{doe}[12]{roe}`.
+fn emit_cite(node: &Node, ctx: &mut EmitContext) {
+    let citations = node.as_cite().map(|c| c.citations()).unwrap_or_default();
+    let Some(first) = citations.first() else {
+        emit_nodes(&node.children, ctx);
+        return;
+    };
+    let command = match first.mode {
+        CitationMode::Normal => "parencite",
+        CitationMode::AuthorInText => "textcite",
+        CitationMode::SuppressAuthor => "parencite*",
+    };
+    if citations.iter().skip(1).any(|c| {
+        c.mode != first.mode
+            && !(c.mode == CitationMode::Normal && first.mode == CitationMode::AuthorInText)
+    }) {
+        ctx.warnings.push(FidelityWarning::new(
+            Severity::Minor,
+            WarningKind::UnsupportedProperty("mode".to_string()),
+            format!("Citation modes mixed in one citation; using \\{command}"),
+        ));
+    }
+
+    let last = citations.len() - 1;
+    let single = citations
+        .iter()
+        .enumerate()
+        .all(|(i, c)| (i == 0 || c.prefix.is_none()) && (i == last || postnote(c).is_empty()));
+    if single {
+        ctx.write(&format!("\\{command}"));
+        write_notes(
+            ctx,
+            first.prefix.as_deref().unwrap_or(""),
+            &postnote(&citations[last]),
+        );
+        let keys: Vec<&str> = citations.iter().map(|c| c.id.as_str()).collect();
+        ctx.write(&format!("{{{}}}", keys.join(",")));
+    } else {
+        let command = command.replace('*', "");
+        ctx.write(&format!("\\{command}s"));
+        for citation in &citations {
+            write_notes(
+                ctx,
+                citation.prefix.as_deref().unwrap_or(""),
+                &postnote(citation),
+            );
+            ctx.write(&format!("{{{}}}", citation.id));
+        }
+    }
+}
+
+/// Write biblatex `[prenote][postnote]` arguments.
+fn write_notes(ctx: &mut EmitContext, prenote: &str, postnote: &str) {
+    if !prenote.is_empty() {
+        ctx.write("[");
+        ctx.write_escaped(prenote);
+        ctx.write("]");
+    }
+    if !prenote.is_empty() || !postnote.is_empty() {
+        ctx.write("[");
+        ctx.write_escaped(postnote);
+        ctx.write("]");
+    }
+}
+
+/// The biblatex postnote of a citation. Page locators are bare, as biblatex
+/// adds the page prefix itself.
+fn postnote(citation: &Citation) -> String {
+    let locator = match citation.label.as_deref() {
+        Some("page") | None => citation.locator.clone(),
+        Some(_) => citation.locator_text(),
+    };
+    let suffix = citation.suffix.as_deref().unwrap_or("");
+    match locator {
+        Some(locator) => format!("{locator}{suffix}"),
+        None => suffix.trim_start_matches([',', ' ']).to_string(),
+    }
+}
+
+/// Whether a node or its descendants contain citations.
+fn contains_cite(node: &Node) -> bool {
+    node.kind.as_str() == node::CITE || node.children.iter().any(contains_cite)
+}
+
 /// Emit quoted text.
 fn emit_quoted(node: &Node, ctx: &mut EmitContext) {
     let quote_type = node.props.get_str(prop::QUOTE_TYPE).unwrap_or("double");
-    if quote_type == "single" {
+    let marks = (
+        node.props.get_str(prop::QUOTE_OPEN),
+        node.props.get_str(prop::QUOTE_CLOSE),
+    );
+    if let (Some(open), Some(close)) = marks {
+        // Locale marks, with the narrow spaces of French quotes as thin spaces
+        ctx.write(&open.replace('\u{202F}', "\\,"));
+        emit_nodes(&node.children, ctx);
+        ctx.write(&close.replace('\u{202F}', "\\,"));
+    } else if quote_type == "single" {
         ctx.write("`");
         emit_nodes(&node.children, ctx);
         ctx.write("'");
@@ -595,4 +771,48 @@ mod tests {
         assert!(output.contains("Hello"));
         assert!(output.contains("\\end{document}"));
     }
+
+    #[test]
+    fn test_document_class_option() {
+        let doc = latex(|d| d.para(|i| i.text("Hello")));
+        let options = EmitOptions {
+            format: rescribe_core::FormatOptions::new()
+                .with("standalone", true)
+                .with("document_class", "report"),
+            ..Default::default()
+        };
+        let output = String::from_utf8(emit_with_options(&doc, &options).unwrap().value).unwrap();
+        assert!(output.starts_with("\\documentclass{report}"));
+        assert!(output.contains("\\end{document}"));
+    }
+
+    #[test]
+    fn test_emit_cite() {
+        use rescribe_std::builder::doc;
+
+        let cite =
+            |citations: Vec<Citation>| emit_str(&doc(|d| d.para(|i| i.cite(citations, |i| i))));
+        assert_eq!(
+            cite(vec![
+                Citation::new("doe").with_prefix("see"),
+                Citation::new("roe").with_suffix(", p. 4")
+            ]),
+            "\\parencite[see][4]{doe,roe}\n\n"
+        );
+        assert_eq!(
+            cite(vec![
+                Citation::new("doe")
+                    .with_mode(CitationMode::AuthorInText)
+                    .with_suffix(", chap. 2")
+            ]),
+            "\\textcite[chap. 2]{doe}\n\n"
+        );
+        assert_eq!(
+            cite(vec![
+                Citation::new("doe").with_suffix("12"),
+                Citation::new("roe").with_prefix("also")
+            ]),
+            "\\parencites[12]{doe}[also][]{roe}\n\n"
+        );
+    }
 }
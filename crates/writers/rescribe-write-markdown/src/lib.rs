@@ -8,7 +8,8 @@ use rescribe_core::{
     ConversionResult, Document, EmitError, EmitOptions, FidelityWarning, Node, Severity,
     WarningKind,
 };
-use rescribe_std::{node, prop};
+use rescribe_std::{Alignment, CitationMode, NodeExt, node, prop};
+use std::io::{self, Write};
 
 /// Emit a document as Markdown.
 pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
@@ -20,16 +21,34 @@ pub fn emit_with_options(
     doc: &Document,
     options: &EmitOptions,
 ) -> Result<ConversionResult<Vec<u8>>, EmitError> {
+    let mut output = Vec::new();
+    let result = emit_to(doc, options, &mut output)?;
+    Ok(ConversionResult::with_warnings(output, result.warnings))
+}
+
+/// Emit a document as Markdown, streaming it to `out`.
+///
+/// Each top-level block is written once the blank line separating it from
+/// the next has been decided; trailing whitespace is held back until then,
+/// since the document's own is trimmed.
+pub fn emit_to(
+    doc: &Document,
+    options: &EmitOptions,
+    out: &mut dyn Write,
+) -> Result<ConversionResult<()>, EmitError> {
     let mut ctx = EmitContext::new(options.use_source_info);
 
     // Emit children of the root document node
-    emit_nodes(&doc.content.children, &mut ctx);
+    let nodes = &doc.content.children;
+    for (i, node) in nodes.iter().enumerate() {
+        emit_node(node, &mut ctx);
+        separate_blocks(nodes, i, &mut ctx);
+        ctx.drain(out)?;
+    }
 
-    let output = ctx.output.trim_end().to_string() + "\n";
-    Ok(ConversionResult::with_warnings(
-        output.into_bytes(),
-        ctx.warnings,
-    ))
+    out.write_all(ctx.output.trim_end().as_bytes())?;
+    out.write_all(b"\n")?;
+    Ok(ConversionResult::with_warnings((), ctx.warnings))
 }
 
 /// Emit context for tracking state during emission.
@@ -57,6 +76,20 @@ impl EmitContext {
         self.output.push_str(s);
     }
 
+    /// Write buffered output to `out`.
+    ///
+    /// Trailing whitespace (and at least the last character) stays buffered,
+    /// since later writes inspect and trim the end of the output.
+    fn drain(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        let mut keep = self.output.trim_end().len();
+        if keep == self.output.len() {
+            keep = self.output.char_indices().next_back().map_or(0, |(i, _)| i);
+        }
+        out.write_all(&self.output.as_bytes()[..keep])?;
+        self.output.drain(..keep);
+        Ok(())
+    }
+
     fn newline(&mut self) {
         if !self.output.ends_with('\n') {
             self.output.push('\n');
@@ -79,11 +112,14 @@ impl EmitContext {
 fn emit_nodes(nodes: &[Node], ctx: &mut EmitContext) {
     for (i, node) in nodes.iter().enumerate() {
         emit_node(node, ctx);
+        separate_blocks(nodes, i, ctx);
+    }
+}
 
-        // Add blank lines between block elements
-        if i + 1 < nodes.len() && is_block_node(node) && is_block_node(&nodes[i + 1]) {
-            ctx.blank_line();
-        }
+/// Add a blank line after `nodes[i]` if it and the next node are blocks.
+fn separate_blocks(nodes: &[Node], i: usize, ctx: &mut EmitContext) {
+    if i + 1 < nodes.len() && is_block_node(&nodes[i]) && is_block_node(&nodes[i + 1]) {
+        ctx.blank_line();
     }
 }
 
@@ -127,6 +163,7 @@ fn emit_node(node: &Node, ctx: &mut EmitContext) {
         node::RAW_BLOCK => emit_raw_block(node, ctx),
         node::RAW_INLINE => emit_raw_inline(node, ctx),
         node::FOOTNOTE_REF => emit_footnote_ref(node, ctx),
+        node::CITE => emit_cite(node, ctx),
         node::FOOTNOTE_DEF => emit_footnote_def(node, ctx),
         node::DEFINITION_LIST => emit_definition_list(node, ctx),
         "math_inline" => emit_math_inline(node, ctx),
@@ -149,7 +186,10 @@ fn emit_paragraph(node: &Node, ctx: &mut EmitContext) {
 }
 
 fn emit_heading(node: &Node, ctx: &mut EmitContext) {
-    let level = node.props.get_int(prop::LEVEL).unwrap_or(1) as usize;
+    let Some(heading) = node.as_heading() else {
+        return;
+    };
+    let level = heading.level() as usize;
 
     // Check for setext style preference (only works for level 1 and 2)
     let use_setext = ctx.use_source_info
@@ -171,7 +211,7 @@ fn emit_heading(node: &Node, ctx: &mut EmitContext) {
         ctx.newline();
     } else {
         // ATX style
-        let hashes = "#".repeat(level.min(6));
+        let hashes = "#".repeat(level);
         ctx.write(&hashes);
         ctx.write(" ");
         emit_nodes(&node.children, ctx);
@@ -180,8 +220,11 @@ fn emit_heading(node: &Node, ctx: &mut EmitContext) {
 }
 
 fn emit_code_block(node: &Node, ctx: &mut EmitContext) {
-    let lang = node.props.get_str(prop::LANGUAGE).unwrap_or("");
-    let content = node.props.get_str(prop::CONTENT).unwrap_or("");
+    let Some(code) = node.as_code_block() else {
+        return;
+    };
+    let lang = code.language().unwrap_or("");
+    let content = code.content();
 
     // Get fence character and length from source info
     let fence_char = if ctx.use_source_info {
@@ -229,9 +272,12 @@ fn emit_blockquote(node: &Node, ctx: &mut EmitContext) {
 }
 
 fn emit_list(node: &Node, ctx: &mut EmitContext) {
-    let ordered = node.props.get_bool(prop::ORDERED).unwrap_or(false);
-    let start = node.props.get_int(prop::START).unwrap_or(1) as usize;
-    let tight = node.props.get_bool(prop::TIGHT).unwrap_or(true);
+    let Some(list) = node.as_list() else {
+        return;
+    };
+    let ordered = list.ordered();
+    let start = list.start();
+    let tight = list.tight();
 
     // Get list marker from source info for unordered lists
     let list_marker = if ctx.use_source_info && !ordered {
@@ -252,7 +298,7 @@ fn emit_list(node: &Node, ctx: &mut EmitContext) {
         ctx.write(&indent);
 
         if ordered {
-            ctx.write(&format!("{}. ", start + i));
+            ctx.write(&format!("{}. ", start + i as i64));
         } else {
             ctx.write(&format!("{} ", list_marker));
         }
@@ -308,40 +354,18 @@ fn emit_list_item_content(node: &Node, ctx: &mut EmitContext) {
 }
 
 fn emit_table(node: &Node, ctx: &mut EmitContext) {
+    let Some(table) = node.as_table() else {
+        return;
+    };
     let mut rows: Vec<Vec<String>> = Vec::new();
     let mut col_widths: Vec<usize> = Vec::new();
-
-    // Get column alignments if present
-    let alignments: Vec<&str> = node
-        .props
-        .get_str("column_alignments")
-        .map(|s| s.split(',').collect())
-        .unwrap_or_default();
+    let alignments = table.alignments();
 
     // First pass: collect all cell contents and calculate widths
-    for child in &node.children {
-        match child.kind.as_str() {
-            node::TABLE_HEAD => {
-                for row in &child.children {
-                    let cells = collect_row_cells(row, ctx.use_source_info);
-                    update_col_widths(&cells, &mut col_widths);
-                    rows.push(cells);
-                }
-            }
-            node::TABLE_ROW => {
-                let cells = collect_row_cells(child, ctx.use_source_info);
-                update_col_widths(&cells, &mut col_widths);
-                rows.push(cells);
-            }
-            node::TABLE_BODY => {
-                for row in &child.children {
-                    let cells = collect_row_cells(row, ctx.use_source_info);
-                    update_col_widths(&cells, &mut col_widths);
-                    rows.push(cells);
-                }
-            }
-            _ => {}
-        }
+    for row in table.rows() {
+        let cells = collect_row_cells(row.node(), ctx.use_source_info);
+        update_col_widths(&cells, &mut col_widths);
+        rows.push(cells);
     }
 
     // Emit header row (first row)
@@ -351,23 +375,23 @@ fn emit_table(node: &Node, ctx: &mut EmitContext) {
         // Emit separator with alignment markers
         ctx.write("|");
         for (i, width) in col_widths.iter().enumerate() {
-            let align = alignments.get(i).copied().unwrap_or("none");
+            let align = alignments.get(i).copied().unwrap_or_default();
             let dashes = width.saturating_sub(match align {
-                "left" | "right" => 1,
-                "center" => 2,
-                _ => 0,
+                Alignment::Left | Alignment::Right => 1,
+                Alignment::Center => 2,
+                Alignment::Default => 0,
             });
             match align {
-                "left" => {
+                Alignment::Left => {
                     ctx.write(&format!(" :{} |", "-".repeat(dashes.max(1) + 1)));
                 }
-                "right" => {
+                Alignment::Right => {
                     ctx.write(&format!(" {}: |", "-".repeat(dashes.max(1) + 1)));
                 }
-                "center" => {
+                Alignment::Center => {
                     ctx.write(&format!(" :{}: |", "-".repeat(dashes.max(1))));
                 }
-                _ => {
+                Alignment::Default => {
                     ctx.write(&format!(" {} |", "-".repeat(*width)));
                 }
             }
@@ -403,14 +427,19 @@ fn update_col_widths(cells: &[String], widths: &mut Vec<usize>) {
     }
 }
 
-fn emit_table_row(cells: &[String], widths: &[usize], alignments: &[&str], ctx: &mut EmitContext) {
+fn emit_table_row(
+    cells: &[String],
+    widths: &[usize],
+    alignments: &[Alignment],
+    ctx: &mut EmitContext,
+) {
     ctx.write("|");
     for (i, cell) in cells.iter().enumerate() {
         let width = widths.get(i).copied().unwrap_or(3);
-        let align = alignments.get(i).copied().unwrap_or("none");
+        let align = alignments.get(i).copied().unwrap_or_default();
         match align {
-            "right" => ctx.write(&format!(" {:>width$} |", cell, width = width)),
-            "center" => ctx.write(&format!(" {:^width$} |", cell, width = width)),
+            Alignment::Right => ctx.write(&format!(" {:>width$} |", cell, width = width)),
+            Alignment::Center => ctx.write(&format!(" {:^width$} |", cell, width = width)),
             _ => ctx.write(&format!(" {:width$} |", cell, width = width)),
         }
     }
@@ -543,8 +572,11 @@ fn emit_inline_code(node: &Node, ctx: &mut EmitContext) {
 }
 
 fn emit_link(node: &Node, ctx: &mut EmitContext) {
-    let url = node.props.get_str(prop::URL).unwrap_or("");
-    let title = node.props.get_str(prop::TITLE);
+    let Some(link) = node.as_link() else {
+        return;
+    };
+    let url = link.url();
+    let title = link.title();
 
     ctx.write("[");
     emit_nodes(&node.children, ctx);
@@ -559,9 +591,10 @@ fn emit_link(node: &Node, ctx: &mut EmitContext) {
 }
 
 fn emit_image(node: &Node, ctx: &mut EmitContext) {
-    let url = node.props.get_str(prop::URL).unwrap_or("");
-    let alt = node.props.get_str(prop::ALT).unwrap_or("");
-    let title = node.props.get_str(prop::TITLE);
+    let Some(image) = node.as_image() else {
+        return;
+    };
+    let (url, alt, title) = (image.url(), image.alt(), image.title());
 
     ctx.write("![");
     ctx.write(alt);
@@ -601,6 +634,63 @@ fn emit_footnote_ref(node: &Node, ctx: &mut EmitContext) {
     ctx.write("]");
 }
 
+/// Write citations in Pandoc syntax: `@doe [p. 4]` for an author-in-text
+/// citation, `[see @doe, p. 4; -@roe]` otherwise.
+fn emit_cite(node: &Node, ctx: &mut EmitContext) {
+    let citations = node.as_cite().map(|c| c.citations()).unwrap_or_default();
+    let Some((first, rest)) = citations.split_first() else {
+        emit_nodes(&node.children, ctx);
+        return;
+    };
+    let bracketed = if first.mode == CitationMode::AuthorInText {
+        ctx.write(&format!("@{}", citation_key(&first.id)));
+        if let Some(suffix) = first.full_suffix() {
+            let suffix = suffix.trim_start_matches([',', ' ']);
+            ctx.write(&format!(" [{suffix}]"));
+        }
+        if rest.is_empty() {
+            return;
+        }
+        ctx.write(" ");
+        rest
+    } else {
+        &citations[..]
+    };
+
+    let parts: Vec<String> = bracketed
+        .iter()
+        .map(|c| {
+            let mut part = String::new();
+            if let Some(prefix) = &c.prefix {
+                part.push_str(prefix);
+                part.push(' ');
+            }
+            if c.mode == CitationMode::SuppressAuthor {
+                part.push('-');
+            }
+            part.push('@');
+            part.push_str(&citation_key(&c.id));
+            part.push_str(&c.full_suffix().unwrap_or_default());
+            part
+        })
+        .collect();
+    ctx.write(&format!("[{}]", parts.join("; ")));
+}
+
+/// A citation key, braced unless it reads back unchanged.
+fn citation_key(id: &str) -> String {
+    let plain = id.starts_with(|c: char| c.is_alphanumeric() || c == '_')
+        && id.ends_with(|c: char| c.is_alphanumeric() || c == '_')
+        && id
+            .chars()
+            .all(|c| c.is_alphanumeric() || "_:.#$%&-+?<>~/".contains(c));
+    if plain {
+        id.to_string()
+    } else {
+        format!("{{{id}}}")
+    }
+}
+
 fn emit_footnote_def(node: &Node, ctx: &mut EmitContext) {
     let label = node.props.get_str(prop::LABEL).unwrap_or("?");
     ctx.write("[^");
@@ -712,6 +802,27 @@ mod tests {
         assert!(output.contains("- item 1"));
         assert!(output.contains("- item 2"));
     }
+
+    #[test]
+    fn test_emit_cite() {
+        use rescribe_std::builder::doc;
+        use rescribe_std::{Citation, CitationMode};
+
+        let citations = [
+            Citation::new("doe99")
+                .with_prefix("see")
+                .with_suffix(", pp. 33-35"),
+            Citation::new("roe").with_mode(CitationMode::SuppressAuthor),
+        ];
+        let document = doc(|d| d.para(|i| i.cite(citations, |i| i.text("ignored"))));
+        assert_eq!(emit_str(&document), "[see @doe99, pp. 33-35; -@roe]\n");
+
+        let citations = [Citation::new("smith 04")
+            .with_mode(CitationMode::AuthorInText)
+            .with_suffix("p. 4")];
+        let document = doc(|d| d.para(|i| i.cite(citations, |i| i.text("ignored"))));
+        assert_eq!(emit_str(&document), "@{smith 04} [p. 4]\n");
+    }
 }
 
 #[cfg(test)]
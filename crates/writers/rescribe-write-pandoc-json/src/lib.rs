@@ -4,7 +4,7 @@
 //! This enables interoperability with Pandoc's extensive format support.
 
 use rescribe_core::{ConversionResult, Document, EmitError, EmitOptions, FidelityWarning};
-use rescribe_std::{Node, node, prop};
+use rescribe_std::{CitationMode, Node, NodeExt, node, prop};
 use serde_json::{Map, Value, json};
 
 /// Pandoc API version we emit.
@@ -473,9 +473,35 @@ impl Emitter {
                 }))
             }
             node::CITE => {
-                // Simple cite - just output the inlines with empty citations
+                let cite = node.as_cite()?;
+                let note_num = cite.note_num().unwrap_or(0);
+                let citations: Vec<Value> = cite
+                    .citations()
+                    .iter()
+                    .map(|c| {
+                        let mode = match c.mode {
+                            CitationMode::Normal => "NormalCitation",
+                            CitationMode::AuthorInText => "AuthorInText",
+                            CitationMode::SuppressAuthor => "SuppressAuthor",
+                        };
+                        let suffix = c.full_suffix().unwrap_or_default();
+                        let mut suffix_inlines = Vec::new();
+                        if suffix.starts_with(char::is_whitespace) {
+                            suffix_inlines.push(json!({"t": "Space"}));
+                        }
+                        suffix_inlines.extend(self.text_to_inlines(&suffix));
+                        json!({
+                            "citationId": c.id,
+                            "citationPrefix": self.text_to_inlines(c.prefix.as_deref().unwrap_or("")),
+                            "citationSuffix": suffix_inlines,
+                            "citationMode": {"t": mode},
+                            "citationNoteNum": note_num,
+                            "citationHash": 0
+                        })
+                    })
+                    .collect();
                 let inlines = self.emit_inlines(&node.children);
-                Some(json!({"t": "Cite", "c": [[], inlines]}))
+                Some(json!({"t": "Cite", "c": [citations, inlines]}))
             }
             _ => None,
         }
@@ -560,6 +586,28 @@ mod tests {
         assert_eq!(link["c"][2][0], "https://example.com");
     }
 
+    #[test]
+    fn test_emit_cite() {
+        use rescribe_std::Citation;
+
+        let citations = [
+            Citation::new("smith2020")
+                .with_prefix("see")
+                .with_suffix(", p. 4"),
+            Citation::new("doe").with_mode(CitationMode::AuthorInText),
+        ];
+        let document = doc(|d| d.para(|i| i.cite(citations, |i| i.text("[see @smith2020, p. 4]"))));
+        let json = emit_json(&document);
+
+        let cite = &json["blocks"][0]["c"][0];
+        assert_eq!(cite["t"], "Cite");
+        let first = &cite["c"][0][0];
+        assert_eq!(first["citationId"], "smith2020");
+        assert_eq!(first["citationPrefix"][0]["c"], "see");
+        assert_eq!(first["citationSuffix"].as_array().unwrap().len(), 5);
+        assert_eq!(cite["c"][0][1]["citationMode"]["t"], "AuthorInText");
+    }
+
     #[test]
     fn test_roundtrip() {
         use rescribe_read_pandoc_json::parse;
@@ -6,7 +6,23 @@
 pub mod builder;
 
 use rescribe_core::{ConversionResult, Document, EmitError, EmitOptions, Node};
-use rescribe_std::{node, prop};
+use rescribe_std::{NodeExt, node, prop};
+use std::io::{self, Write};
+
+/// Writer options, set through [`EmitOptions::format`].
+pub mod options {
+    use rescribe_core::OptionSpec;
+
+    /// Wrap paragraphs to this many columns; 0 disables wrapping.
+    pub const COLUMNS: OptionSpec = OptionSpec::int(
+        "columns",
+        0,
+        "Wrap paragraphs to this many columns (0 disables wrapping)",
+    );
+
+    /// All writer options.
+    pub const ALL: &[OptionSpec] = &[COLUMNS];
+}
 
 /// Emit a document as plain text.
 pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
@@ -18,20 +34,38 @@ pub fn emit_with_options(
     doc: &Document,
     options: &EmitOptions,
 ) -> Result<ConversionResult<Vec<u8>>, EmitError> {
+    let mut output = Vec::new();
+    let result = emit_to(doc, options, &mut output)?;
+    Ok(ConversionResult::with_warnings(output, result.warnings))
+}
+
+/// Emit a document as plain text, streaming it to `out`.
+///
+/// Each top-level block is written as soon as it has been emitted, except
+/// for trailing whitespace, held back so that the output ends in a single
+/// newline.
+pub fn emit_to(
+    doc: &Document,
+    options: &EmitOptions,
+    out: &mut dyn Write,
+) -> Result<ConversionResult<()>, EmitError> {
     let mut ctx = EmitContext::new(options);
 
-    emit_nodes(&doc.content.children, &mut ctx);
+    for node in &doc.content.children {
+        emit_node(node, &mut ctx);
+        ctx.drain(out)?;
+    }
 
     // Trim trailing whitespace and ensure single trailing newline
-    let output = ctx.output.trim_end().to_string() + "\n";
+    out.write_all(ctx.output.trim_end().as_bytes())?;
+    out.write_all(b"\n")?;
 
-    Ok(ConversionResult::ok(output.into_bytes()))
+    Ok(ConversionResult::ok(()))
 }
 
 /// Configuration options for plain text emission.
 struct EmitContext<'a> {
     output: String,
-    #[allow(dead_code)]
     options: &'a EmitOptions,
     list_depth: usize,
     ordered_list_counters: Vec<usize>,
@@ -57,6 +91,20 @@ impl<'a> EmitContext<'a> {
         self.output.push_str(s);
     }
 
+    /// Write buffered output to `out`.
+    ///
+    /// Trailing whitespace (and at least the last character) stays buffered,
+    /// since later writes inspect and trim the end of the output.
+    fn drain(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        let mut keep = self.output.trim_end().len();
+        if keep == self.output.len() {
+            keep = self.output.char_indices().next_back().map_or(0, |(i, _)| i);
+        }
+        out.write_all(&self.output.as_bytes()[..keep])?;
+        self.output.drain(..keep);
+        Ok(())
+    }
+
     fn write_line(&mut self, s: &str) {
         self.write(s);
         self.write("\n");
@@ -80,6 +128,31 @@ impl<'a> EmitContext<'a> {
     }
 }
 
+/// Greedily wrap each line of `text` to `width` characters. Words longer
+/// than `width` get a line of their own.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for source_line in text.lines() {
+        let mut line = String::new();
+        let mut line_width = 0;
+        for word in source_line.split_whitespace() {
+            let word_width = word.chars().count();
+            if !line.is_empty() && line_width + 1 + word_width > width {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            if !line.is_empty() {
+                line.push(' ');
+                line_width += 1;
+            }
+            line.push_str(word);
+            line_width += word_width;
+        }
+        lines.push(line);
+    }
+    lines
+}
+
 /// Emit a sequence of nodes.
 fn emit_nodes(nodes: &[Node], ctx: &mut EmitContext) {
     for node in nodes {
@@ -95,8 +168,18 @@ fn emit_node(node: &Node, ctx: &mut EmitContext) {
         node::PARAGRAPH => {
             ctx.ensure_newline();
             let indent = ctx.indent();
-            ctx.write(&indent);
-            emit_nodes(&node.children, ctx);
+            let columns = ctx.options.format.int(&options::COLUMNS).max(0) as usize;
+            if columns == 0 {
+                ctx.write(&indent);
+                emit_nodes(&node.children, ctx);
+            } else {
+                let mut inner_ctx = EmitContext::new(ctx.options);
+                emit_nodes(&node.children, &mut inner_ctx);
+                for line in wrap(&inner_ctx.output, columns.saturating_sub(indent.len())) {
+                    ctx.write(&indent);
+                    ctx.write_line(&line);
+                }
+            }
             ctx.ensure_blank_line();
         }
 
@@ -131,8 +214,11 @@ fn emit_node(node: &Node, ctx: &mut EmitContext) {
 
         node::LIST => {
             ctx.ensure_newline();
-            let ordered = node.props.get_bool(prop::ORDERED).unwrap_or(false);
-            let start = node.props.get_int(prop::START).unwrap_or(1) as usize;
+            let Some(list) = node.as_list() else {
+                return;
+            };
+            let ordered = list.ordered();
+            let start = list.start().max(0) as usize;
 
             if ordered {
                 ctx.ordered_list_counters.push(start);
@@ -445,4 +531,18 @@ mod tests {
         let output = emit_str(&doc);
         assert!(output.contains("[Image: Test image]"));
     }
+
+    #[test]
+    fn test_wrap_columns() {
+        let doc = plaintext(|d| d.para("The quick brown fox jumps over the lazy dog"));
+        let options = EmitOptions {
+            format: rescribe_core::FormatOptions::new().with("columns", 16i64),
+            ..Default::default()
+        };
+        let output = emit_with_options(&doc, &options).unwrap().value;
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "The quick brown\nfox jumps over\nthe lazy dog\n"
+        );
+    }
 }
@@ -1,13 +1,14 @@
 //! RIS (Research Information Systems) writer for rescribe.
 //!
-//! Emits documents as RIS format for bibliographic data.
+//! Emits documents as RIS format for bibliographic data. Each `bib:entry`
+//! node (see [`rescribe_bib`]) becomes a reference; `ris:` custom fields are
+//! written back as tags.
 
-use rescribe_core::{ConversionResult, Document, EmitError, EmitOptions, FidelityWarning, Node};
-
-/// RIS entry node type.
-const RIS_ENTRY: &str = "ris:entry";
-const BIBTEX_ENTRY: &str = "bibtex:entry";
-const CITATION_ENTRY: &str = "citation_entry";
+use rescribe_bib::{Date, Entry, Name, prop};
+use rescribe_core::{
+    ConversionResult, Document, EmitError, EmitOptions, FidelityWarning, Severity, WarningKind,
+};
+use std::collections::HashSet;
 
 /// Emit a document as RIS.
 pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
@@ -20,7 +21,9 @@ pub fn emit_with_options(
     _options: &EmitOptions,
 ) -> Result<ConversionResult<Vec<u8>>, EmitError> {
     let mut ctx = EmitContext::new();
-    emit_nodes(&doc.content.children, &mut ctx);
+    for entry in rescribe_bib::entries(&doc.content) {
+        emit_entry(entry, &mut ctx);
+    }
     Ok(ConversionResult::with_warnings(
         ctx.output.into_bytes(),
         ctx.warnings,
@@ -48,252 +51,216 @@ impl EmitContext {
     }
 }
 
-fn emit_nodes(nodes: &[Node], ctx: &mut EmitContext) {
-    for node in nodes {
-        emit_node(node, ctx);
-    }
+/// The tags of an entry, collected in output order.
+struct Tags<'a> {
+    entry: Entry<'a>,
+    /// Properties that have been written.
+    used: HashSet<&'a str>,
+    tags: Vec<(&'a str, String)>,
 }
 
-fn emit_node(node: &Node, ctx: &mut EmitContext) {
-    match node.kind.as_str() {
-        "document" | "definition_list" => emit_nodes(&node.children, ctx),
-
-        RIS_ENTRY => emit_ris_entry(node, ctx),
-        BIBTEX_ENTRY => emit_bibtex_entry(node, ctx),
-        CITATION_ENTRY => emit_citation_entry(node, ctx),
-
-        _ => {
-            // Check for known entry types
-            if is_bibtex_type(node.kind.as_str()) {
-                emit_typed_entry(node, ctx);
-            } else {
-                emit_nodes(&node.children, ctx);
-            }
+impl<'a> Tags<'a> {
+    /// Add a tag from a string variable.
+    fn text(&mut self, tag: &'a str, key: &'a str) {
+        self.used.insert(key);
+        if let Some(value) = self.entry.text(key) {
+            self.tags.push((tag, value.to_string()));
         }
     }
-}
 
-fn is_bibtex_type(s: &str) -> bool {
-    matches!(
-        s.to_lowercase().as_str(),
-        "article"
-            | "book"
-            | "booklet"
-            | "conference"
-            | "inbook"
-            | "incollection"
-            | "inproceedings"
-            | "manual"
-            | "mastersthesis"
-            | "misc"
-            | "phdthesis"
-            | "proceedings"
-            | "techreport"
-            | "unpublished"
-            | "online"
-            | "software"
-            | "dataset"
-    )
-}
-
-fn emit_ris_entry(node: &Node, ctx: &mut EmitContext) {
-    let ris_type = node.props.get_str("ris:type").unwrap_or("GEN");
-    ctx.write_tag("TY", ris_type);
-
-    // Emit all ris: prefixed properties
-    for (key, value) in node.props.iter() {
-        if let Some(tag) = key.strip_prefix("ris:")
-            && tag != "type"
-            && tag != "key"
-            && let rescribe_core::PropValue::String(s) = value
-        {
-            ctx.write_tag(&tag.to_uppercase(), s);
+    /// Add a tag per name of a name variable.
+    fn names(&mut self, tag: &'a str, key: &'a str) {
+        self.used.insert(key);
+        for name in self.entry.names(key) {
+            self.tags.push((tag, format_name(&name)));
         }
     }
 
-    ctx.write_tag("ER", "");
-    ctx.output.push('\n');
-}
-
-fn emit_bibtex_entry(node: &Node, ctx: &mut EmitContext) {
-    let bibtex_type = node.props.get_str("bibtex:type").unwrap_or("misc");
-    let ris_type = bibtex_type_to_ris(bibtex_type);
-
-    ctx.write_tag("TY", ris_type);
-
-    // Map bibtex fields to RIS tags
-    emit_bibtex_fields(node, ctx);
-
-    ctx.write_tag("ER", "");
-    ctx.output.push('\n');
-}
-
-fn emit_bibtex_fields(node: &Node, ctx: &mut EmitContext) {
-    for (key, value) in node.props.iter() {
-        if let Some(field) = key.strip_prefix("bibtex:")
-            && let rescribe_core::PropValue::String(s) = value
-            && let Some(ris_tag) = bibtex_field_to_ris(field)
-        {
-            ctx.write_tag(ris_tag, s);
+    /// Add a tag from a date variable.
+    fn date(&mut self, tag: &'a str, key: &'a str) {
+        self.used.insert(key);
+        if let Some(date) = self.entry.date(key) {
+            self.tags.push((tag, format_date(&date)));
         }
     }
 }
 
-fn emit_citation_entry(node: &Node, ctx: &mut EmitContext) {
-    let csl_type = node.props.get_str("type").unwrap_or("misc");
-    let ris_type = csl_type_to_ris(csl_type);
-
-    ctx.write_tag("TY", ris_type);
-
-    // Map CSL fields to RIS
-    if let Some(title) = node.props.get_str("title") {
-        ctx.write_tag("TI", title);
-    }
-    if let Some(author) = node.props.get_str("author") {
-        // Authors might be semicolon-separated
-        for a in author.split(';') {
-            ctx.write_tag("AU", a.trim());
-        }
-    }
-    if let Some(container) = node.props.get_str("container-title") {
-        ctx.write_tag("JO", container);
-    }
-    if let Some(issued) = node.props.get_str("issued") {
-        ctx.write_tag("PY", issued);
-    }
-    if let Some(volume) = node.props.get_str("volume") {
-        ctx.write_tag("VL", volume);
-    }
-    if let Some(page) = node.props.get_str("page") {
-        // Pages might be in format "start-end"
-        let parts: Vec<&str> = page.split('-').collect();
-        if !parts.is_empty() {
-            ctx.write_tag("SP", parts[0]);
+fn emit_entry(entry: Entry, ctx: &mut EmitContext) {
+    let mut tags = Tags {
+        entry,
+        used: HashSet::new(),
+        tags: Vec::new(),
+    };
+
+    tags.names("AU", prop::AUTHOR);
+    tags.names("A2", prop::EDITOR);
+    tags.names("A3", prop::COLLECTION_EDITOR);
+    tags.names("A4", prop::TRANSLATOR);
+    tags.text("TI", prop::TITLE);
+    // Journals go in JO, other containers in T2.
+    let container_tag = if entry.entry_type().starts_with("article") {
+        "JO"
+    } else {
+        "T2"
+    };
+    tags.text(container_tag, prop::CONTAINER_TITLE);
+    tags.text("J2", prop::CONTAINER_TITLE_SHORT);
+    tags.text("T3", prop::COLLECTION_TITLE);
+    tags.text("ST", prop::TITLE_SHORT);
+
+    // PY holds the year, DA the full date.
+    tags.used.insert(prop::ISSUED);
+    if let Some(date) = entry.date(prop::ISSUED) {
+        match date.year() {
+            Some(year) => tags.tags.push(("PY", year.to_string())),
+            None => tags.tags.push(("PY", format_date(&date))),
         }
-        if parts.len() > 1 {
-            ctx.write_tag("EP", parts[1]);
+        if date.start.is_some_and(|start| start.month.is_some()) {
+            tags.tags.push(("DA", format_date(&date)));
         }
     }
-    if let Some(doi) = node.props.get_str("DOI") {
-        ctx.write_tag("DO", doi);
-    }
-    if let Some(url) = node.props.get_str("URL") {
-        ctx.write_tag("UR", url);
+    tags.date("Y2", prop::ACCESSED);
+
+    tags.text("VL", prop::VOLUME);
+    tags.text("IS", prop::ISSUE);
+    tags.used.insert(prop::PAGE);
+    if let Some(page) = entry.text(prop::PAGE) {
+        match page.split_once(['-', '–']) {
+            Some((start, end)) if !page.contains(',') => {
+                tags.tags.push(("SP", start.trim().to_string()));
+                tags.tags
+                    .push(("EP", end.trim_start_matches('-').trim().to_string()));
+            }
+            _ => tags.tags.push(("SP", page.to_string())),
+        }
     }
-    if let Some(abs) = node.props.get_str("abstract") {
-        ctx.write_tag("AB", abs);
+    tags.text("ET", prop::EDITION);
+    tags.text("NV", prop::NUMBER_OF_VOLUMES);
+    tags.text("PB", prop::PUBLISHER);
+    tags.text("CY", prop::PUBLISHER_PLACE);
+    tags.text("SN", prop::ISBN);
+    tags.text("SN", prop::ISSN);
+    tags.text("DO", prop::DOI);
+    tags.text("UR", prop::URL);
+    tags.text("AB", prop::ABSTRACT);
+    tags.used.insert(prop::KEYWORD);
+    if let Some(keywords) = entry.text(prop::KEYWORD) {
+        for keyword in keywords.split(',').map(str::trim) {
+            tags.tags.push(("KW", keyword.to_string()));
+        }
     }
-
-    ctx.write_tag("ER", "");
-    ctx.output.push('\n');
-}
-
-fn emit_typed_entry(node: &Node, ctx: &mut EmitContext) {
-    let bibtex_type = node.kind.as_str().to_lowercase();
-    let ris_type = bibtex_type_to_ris(&bibtex_type);
-
-    ctx.write_tag("TY", ris_type);
-
-    // Emit standard fields
-    let field_mappings = [
-        ("author", "AU"),
-        ("title", "TI"),
-        ("journal", "JO"),
-        ("booktitle", "T2"),
-        ("year", "PY"),
-        ("volume", "VL"),
-        ("number", "IS"),
-        ("pages", "SP"), // Note: pages needs special handling
-        ("publisher", "PB"),
-        ("address", "CY"),
-        ("doi", "DO"),
-        ("url", "UR"),
-        ("abstract", "AB"),
-        ("keywords", "KW"),
-        ("isbn", "SN"),
-        ("issn", "SN"),
-    ];
-
-    for (prop_name, ris_tag) in field_mappings {
-        if let Some(value) = node.props.get_str(prop_name) {
-            if prop_name == "author" {
-                // Authors might be "and"-separated
-                for author in value.split(" and ") {
-                    ctx.write_tag(ris_tag, author.trim());
-                }
-            } else if prop_name == "pages" {
-                // Handle page ranges
-                let parts: Vec<&str> = value.split('-').collect();
-                if !parts.is_empty() {
-                    ctx.write_tag("SP", parts[0].trim());
-                }
-                if parts.len() > 1 {
-                    ctx.write_tag("EP", parts[1].trim());
+    tags.text("N1", prop::NOTE);
+    tags.text("LA", prop::LANGUAGE);
+    tags.text("CN", prop::CALL_NUMBER);
+    tags.text("M3", prop::MEDIUM);
+
+    // Tags read from RIS that have no CSL variable
+    let mut lost = Vec::new();
+    for (key, value) in entry.custom_fields() {
+        match key.strip_prefix("ris:") {
+            Some("TY") => {}
+            Some(tag) if !tags.tags.iter().any(|(written, _)| *written == tag) => {
+                for value in value.lines() {
+                    tags.tags.push((tag, value.to_string()));
                 }
-            } else {
-                ctx.write_tag(ris_tag, value);
             }
+            Some(_) => {}
+            None => lost.push(key),
         }
     }
+    lost.extend(
+        entry
+            .variables()
+            .into_iter()
+            .filter(|(name, _)| !tags.used.contains(format!("bib:{name}").as_str()))
+            .map(|(name, _)| name),
+    );
+    if !lost.is_empty() {
+        ctx.warnings.push(FidelityWarning::new(
+            Severity::Minor,
+            WarningKind::UnsupportedProperty(lost.join(", ")),
+            format!(
+                "RIS has no tag for {} in entry '{}'",
+                lost.join(", "),
+                entry.id()
+            ),
+        ));
+    }
 
+    let ris_type = entry
+        .custom("ris:TY")
+        .unwrap_or_else(|| ris_type(entry.entry_type()));
+    ctx.write_tag("TY", ris_type);
+    if !entry.id().is_empty() {
+        ctx.write_tag("ID", entry.id());
+    }
+    for (tag, value) in &tags.tags {
+        ctx.write_tag(tag, value);
+    }
     ctx.write_tag("ER", "");
     ctx.output.push('\n');
 }
 
-fn bibtex_type_to_ris(bibtex: &str) -> &'static str {
-    match bibtex.to_lowercase().as_str() {
-        "article" => "JOUR",
-        "book" => "BOOK",
-        "incollection" | "inbook" => "CHAP",
-        "inproceedings" | "conference" => "CONF",
-        "phdthesis" => "THES",
-        "mastersthesis" => "THES",
-        "techreport" => "RPRT",
-        "online" => "ELEC",
-        "software" => "COMP",
-        "dataset" => "DATA",
-        "unpublished" => "UNPB",
-        "booklet" => "PAMP",
-        "proceedings" => "CONF",
-        "manual" => "BOOK",
-        _ => "GEN",
+/// Format a name as `Last, First, Suffix`.
+fn format_name(name: &Name) -> String {
+    if let Some(literal) = &name.literal {
+        return literal.clone();
     }
+    let mut text = name.family_part().unwrap_or_default();
+    text.push(',');
+    if let Some(given) = &name.given {
+        text.push(' ');
+        text.push_str(given);
+    }
+    if let Some(suffix) = &name.suffix {
+        text.push_str(", ");
+        text.push_str(suffix);
+    }
+    text
 }
 
-fn bibtex_field_to_ris(field: &str) -> Option<&'static str> {
-    match field {
-        "author" => Some("AU"),
-        "title" => Some("TI"),
-        "journal" => Some("JO"),
-        "booktitle" => Some("T2"),
-        "year" => Some("PY"),
-        "volume" => Some("VL"),
-        "number" => Some("IS"),
-        "publisher" => Some("PB"),
-        "address" => Some("CY"),
-        "doi" => Some("DO"),
-        "url" => Some("UR"),
-        "abstract" => Some("AB"),
-        "keywords" => Some("KW"),
-        "isbn" | "issn" => Some("SN"),
-        "edition" => Some("ET"),
-        "note" => Some("N1"),
-        "type" | "key" => None,
-        _ => None,
-    }
+/// Format a date as `YYYY/MM/DD/`, or as its literal text.
+fn format_date(date: &Date) -> String {
+    let Some(start) = date.start else {
+        return date.literal.clone().unwrap_or_default();
+    };
+    let part = |n: Option<u8>| n.map(|n| format!("{n:02}")).unwrap_or_default();
+    format!(
+        "{:04}/{}/{}/",
+        start.year,
+        part(start.month),
+        part(start.day)
+    )
 }
 
-fn csl_type_to_ris(csl: &str) -> &'static str {
-    match csl {
-        "article-journal" | "article-magazine" | "article-newspaper" => "JOUR",
+fn ris_type(csl_type: &str) -> &'static str {
+    match csl_type {
+        "article-journal" | "article" => "JOUR",
+        "article-magazine" => "MGZN",
+        "article-newspaper" => "NEWS",
         "book" => "BOOK",
         "chapter" => "CHAP",
-        "paper-conference" => "CONF",
+        "paper-conference" => "CPAPER",
         "thesis" => "THES",
         "report" => "RPRT",
-        "webpage" | "post-weblog" => "ELEC",
+        "webpage" | "post" => "ELEC",
+        "post-weblog" => "BLOG",
         "software" => "COMP",
         "dataset" => "DATA",
+        "patent" => "PAT",
+        "manuscript" => "MANSCPT",
+        "pamphlet" => "PAMP",
+        "entry-encyclopedia" => "ENCYC",
+        "entry-dictionary" => "DICT",
+        "map" => "MAP",
+        "graphic" => "ART",
+        "motion_picture" => "VIDEO",
+        "song" => "SOUND",
+        "hearing" => "HEAR",
+        "bill" => "BILL",
+        "legal_case" => "CASE",
+        "legislation" => "STAT",
+        "standard" => "STAND",
         _ => "GEN",
     }
 }
@@ -301,37 +268,46 @@ fn csl_type_to_ris(csl: &str) -> &'static str {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rescribe_core::NodeKind;
+    use rescribe_bib::node;
+    use rescribe_core::{Node, NodeKind};
 
     fn emit_str(doc: &Document) -> String {
         String::from_utf8(emit(doc).unwrap().value).unwrap()
     }
 
+    fn article() -> Node {
+        Node::new(node::ENTRY)
+            .prop(prop::ID, "smith2020")
+            .prop(prop::TYPE, "article-journal")
+    }
+
     #[test]
-    fn test_emit_typed_entry() {
-        let entry = Node::new(NodeKind::from("article"))
-            .prop("author", "Smith, John")
-            .prop("title", "A Great Paper")
-            .prop("journal", "Nature")
-            .prop("year", "2020");
+    fn test_emit_entry() {
+        let entry = article()
+            .prop(prop::AUTHOR, Name::list([Name::parse("Smith, John")]))
+            .prop(prop::TITLE, "A Great Paper")
+            .prop(prop::CONTAINER_TITLE, "Nature")
+            .prop(prop::ISSUED, Date::parse("2020").unwrap());
 
         let doc = Document::new()
             .with_content(Node::new(NodeKind::from("document")).children(vec![entry]));
         let output = emit_str(&doc);
 
         assert!(output.contains("TY  - JOUR"));
+        assert!(output.contains("ID  - smith2020"));
         assert!(output.contains("AU  - Smith, John"));
         assert!(output.contains("TI  - A Great Paper"));
         assert!(output.contains("JO  - Nature"));
         assert!(output.contains("PY  - 2020"));
+        assert!(!output.contains("DA  -"));
         assert!(output.contains("ER  -"));
     }
 
     #[test]
     fn test_emit_pages() {
-        let entry = Node::new(NodeKind::from("article"))
-            .prop("title", "Test")
-            .prop("pages", "123-456");
+        let entry = article()
+            .prop(prop::TITLE, "Test")
+            .prop(prop::PAGE, "123-456");
 
         let doc = Document::new()
             .with_content(Node::new(NodeKind::from("document")).children(vec![entry]));
@@ -343,9 +319,14 @@ mod tests {
 
     #[test]
     fn test_emit_multiple_authors() {
-        let entry = Node::new(NodeKind::from("article"))
-            .prop("title", "Test")
-            .prop("author", "Smith, John and Doe, Jane");
+        let entry = article().prop(prop::TITLE, "Test").prop(
+            prop::AUTHOR,
+            Name::list([
+                Name::parse("Smith, John"),
+                Name::parse("Doe, Jane"),
+                Name::literal("WHO"),
+            ]),
+        );
 
         let doc = Document::new()
             .with_content(Node::new(NodeKind::from("document")).children(vec![entry]));
@@ -353,5 +334,26 @@ mod tests {
 
         assert!(output.contains("AU  - Smith, John"));
         assert!(output.contains("AU  - Doe, Jane"));
+        assert!(output.contains("AU  - WHO\n"));
+    }
+
+    #[test]
+    fn test_emit_dates_and_keywords() {
+        let entry = article()
+            .prop(prop::ISSUED, Date::parse("2021-05").unwrap())
+            .prop(prop::ACCESSED, Date::literal("Spring 2022"))
+            .prop(prop::KEYWORD, "health, policy")
+            .prop("bib:event-title", "Conference");
+
+        let doc = Document::new()
+            .with_content(Node::new(NodeKind::from("document")).children(vec![entry]));
+        let result = emit(&doc).unwrap();
+        let output = String::from_utf8(result.value).unwrap();
+
+        assert!(output.contains("PY  - 2021\nDA  - 2021/05//\n"));
+        assert!(output.contains("Y2  - Spring 2022"));
+        assert!(output.contains("KW  - health\nKW  - policy\n"));
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].message.contains("event-title"));
     }
 }
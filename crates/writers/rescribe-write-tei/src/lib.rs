@@ -4,13 +4,33 @@
 
 use quick_xml::Writer;
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
-use rescribe_core::{ConversionResult, Document, EmitError, Node};
-use rescribe_std::{node, prop};
+use rescribe_core::{ConversionResult, Document, EmitError, EmitOptions, Node};
+use rescribe_std::{NodeExt, node, prop};
 use std::io::Cursor;
 
+/// Writer options, set through [`EmitOptions::format`].
+pub mod options {
+    use rescribe_core::OptionSpec;
+
+    /// Write formulas as MathML rather than as their TeX source.
+    pub const MATHML: OptionSpec = OptionSpec::flag("mathml", false, "Write formulas as MathML");
+
+    /// All writer options.
+    pub const ALL: &[OptionSpec] = &[MATHML];
+}
+
 /// Emit a document to TEI XML.
 pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
+    emit_with_options(doc, &EmitOptions::default())
+}
+
+/// Emit a document to TEI XML with custom options.
+pub fn emit_with_options(
+    doc: &Document,
+    options: &EmitOptions,
+) -> Result<ConversionResult<Vec<u8>>, EmitError> {
     let warnings = Vec::new();
+    let mathml = options.format.flag(&options::MATHML);
     let mut writer = Writer::new(Cursor::new(Vec::new()));
 
     // XML declaration
@@ -76,7 +96,7 @@ pub fn emit(doc: &Document) -> Result<ConversionResult<Vec<u8>>, EmitError> {
         .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
 
     for child in &doc.content.children {
-        write_node(&mut writer, child)?;
+        write_node(&mut writer, child, mathml)?;
     }
 
     writer
@@ -112,19 +132,65 @@ fn write_element(
     Ok(())
 }
 
-fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), EmitError> {
+/// Write math as a `<formula>`: MathML with `notation="MathML"` if `mathml`
+/// is set and the source can be written as MathML, and otherwise its TeX
+/// source.
+fn write_formula(
+    writer: &mut Writer<Cursor<Vec<u8>>>,
+    node: &Node,
+    mathml: bool,
+) -> Result<(), EmitError> {
+    let math = if mathml {
+        rescribe_math::mathml::emit(node).ok()
+    } else {
+        None
+    };
+    let mut formula = BytesStart::new("formula");
+    formula.push_attribute(("notation", if math.is_some() { "MathML" } else { "TeX" }));
+    writer
+        .write_event(Event::Start(formula))
+        .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+    let text = match (&math, node.props.get_str("math:source")) {
+        (Some(math), _) => Some(BytesText::from_escaped(math.as_str())),
+        (None, Some(source)) => Some(BytesText::new(source)),
+        (None, None) => None,
+    };
+    if let Some(text) = text {
+        writer
+            .write_event(Event::Text(text))
+            .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+    }
+    writer
+        .write_event(Event::End(BytesEnd::new("formula")))
+        .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
+    Ok(())
+}
+
+fn write_node(
+    writer: &mut Writer<Cursor<Vec<u8>>>,
+    node: &Node,
+    mathml: bool,
+) -> Result<(), EmitError> {
     match node.kind.as_str() {
         node::DOCUMENT | node::DIV => {
             // Wrap divisions in div element
             if node.kind.as_str() == node::DIV {
-                writer
-                    .write_event(Event::Start(BytesStart::new("div")))
-                    .map_err(|e| {
-                        EmitError::Io(std::io::Error::other(format!("XML error: {}", e)))
-                    })?;
+                let mut start = BytesStart::new("div");
+                if let Some(section) = node.as_section() {
+                    start.push_attribute(("type", "section"));
+                    if let Some(id) = section.id() {
+                        start.push_attribute(("xml:id", id));
+                    }
+                    if let Some(number) = section.heading().and_then(|h| h.number()) {
+                        start.push_attribute(("n", number));
+                    }
+                }
+                writer.write_event(Event::Start(start)).map_err(|e| {
+                    EmitError::Io(std::io::Error::other(format!("XML error: {}", e)))
+                })?;
             }
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             if node.kind.as_str() == node::DIV {
                 writer
@@ -140,7 +206,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("head")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("head")))
@@ -152,7 +218,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("p")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("p")))
@@ -164,7 +230,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("quote")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("quote")))
@@ -182,7 +248,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(list))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("list")))
@@ -194,7 +260,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("item")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("item")))
@@ -206,7 +272,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("gloss")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("gloss")))
@@ -218,7 +284,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("term")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("term")))
@@ -230,7 +296,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("def")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("def")))
@@ -261,7 +327,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
 
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
 
             writer
@@ -272,7 +338,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
         node::TABLE_HEAD | node::TABLE_BODY | node::TABLE_FOOT => {
             // TEI doesn't have thead/tbody, pass through
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
         }
 
@@ -281,7 +347,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("row")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("row")))
@@ -293,7 +359,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("cell")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("cell")))
@@ -307,7 +373,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(cell))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("cell")))
@@ -319,7 +385,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("figure")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("figure")))
@@ -348,35 +414,21 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
                 .write_event(Event::Start(BytesStart::new("note")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("note")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
         }
 
-        "math_display" => {
-            writer
-                .write_event(Event::Start(BytesStart::new("formula")))
-                .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-            if let Some(source) = node.props.get_str("math:source") {
-                writer
-                    .write_event(Event::Text(BytesText::new(source)))
-                    .map_err(|e| {
-                        EmitError::Io(std::io::Error::other(format!("XML error: {}", e)))
-                    })?;
-            }
-            writer
-                .write_event(Event::End(BytesEnd::new("formula")))
-                .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-        }
+        "math_display" => write_formula(writer, node, mathml)?,
 
         // Inline nodes that appear at block level
         node::TEXT | node::EMPHASIS | node::STRONG | node::CODE | node::LINK => {
             writer
                 .write_event(Event::Start(BytesStart::new("p")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-            write_inline(writer, node)?;
+            write_inline(writer, node, mathml)?;
             writer
                 .write_event(Event::End(BytesEnd::new("p")))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
@@ -384,7 +436,7 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
 
         _ => {
             for child in &node.children {
-                write_node(writer, child)?;
+                write_node(writer, child, mathml)?;
             }
         }
     }
@@ -392,7 +444,11 @@ fn write_node(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), E
     Ok(())
 }
 
-fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(), EmitError> {
+fn write_inline(
+    writer: &mut Writer<Cursor<Vec<u8>>>,
+    node: &Node,
+    mathml: bool,
+) -> Result<(), EmitError> {
     match node.kind.as_str() {
         node::TEXT => {
             if let Some(content) = node.props.get_str(prop::CONTENT) {
@@ -411,7 +467,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(hi))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("hi")))
@@ -425,7 +481,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(hi))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("hi")))
@@ -439,7 +495,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(hi))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("hi")))
@@ -453,7 +509,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(hi))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("hi")))
@@ -467,7 +523,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(hi))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("hi")))
@@ -481,7 +537,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(hi))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("hi")))
@@ -495,7 +551,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(hi))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("hi")))
@@ -514,7 +570,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                     })?;
             }
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("code")))
@@ -530,7 +586,7 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .write_event(Event::Start(link))
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
             writer
                 .write_event(Event::End(BytesEnd::new("ref")))
@@ -559,25 +615,11 @@ fn write_inline(writer: &mut Writer<Cursor<Vec<u8>>>, node: &Node) -> Result<(),
                 .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
         }
 
-        "math_inline" => {
-            writer
-                .write_event(Event::Start(BytesStart::new("formula")))
-                .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-            if let Some(source) = node.props.get_str("math:source") {
-                writer
-                    .write_event(Event::Text(BytesText::new(source)))
-                    .map_err(|e| {
-                        EmitError::Io(std::io::Error::other(format!("XML error: {}", e)))
-                    })?;
-            }
-            writer
-                .write_event(Event::End(BytesEnd::new("formula")))
-                .map_err(|e| EmitError::Io(std::io::Error::other(format!("XML error: {}", e))))?;
-        }
+        "math_inline" => write_formula(writer, node, mathml)?,
 
         _ => {
             for child in &node.children {
-                write_inline(writer, child)?;
+                write_inline(writer, child, mathml)?;
             }
         }
     }
@@ -605,6 +647,31 @@ mod tests {
         assert!(xml.contains("</TEI>"));
     }
 
+    #[test]
+    fn test_emit_nested_sections() {
+        let heading = |level: i64, id: &str, text: &str| {
+            Node::new(node::HEADING)
+                .prop(prop::LEVEL, level)
+                .prop(prop::ID, id)
+                .child(Node::new(node::TEXT).prop(prop::CONTENT, text))
+        };
+        let section =
+            |level: i64| Node::new(node::DIV).prop(prop::CLASSES, format!("section level{level}"));
+        let doc = Document::new().with_content(
+            Node::new(node::DOCUMENT).child(
+                section(1)
+                    .child(heading(1, "act-1", "Act I").prop(prop::NUMBER, "1"))
+                    .child(section(2).child(heading(2, "scene-1", "Scene 1"))),
+            ),
+        );
+
+        let xml = String::from_utf8(emit(&doc).unwrap().value).unwrap();
+        assert!(xml.contains(
+            "<div type=\"section\" xml:id=\"act-1\" n=\"1\"><head>Act I</head>\
+             <div type=\"section\" xml:id=\"scene-1\"><head>Scene 1</head></div></div>"
+        ));
+    }
+
     #[test]
     fn test_emit_paragraph() {
         let doc = Document {
@@ -664,4 +731,24 @@ mod tests {
         assert!(xml.contains("<hi rend=\"italic\">italic</hi>"));
         assert!(xml.contains("<hi rend=\"bold\">bold</hi>"));
     }
+
+    #[test]
+    fn test_emit_formula() {
+        let doc = Document::new().with_content(
+            Node::new(node::DOCUMENT).child(Node::new("math_display").prop("math:source", "x^2")),
+        );
+
+        let xml = String::from_utf8(emit(&doc).unwrap().value).unwrap();
+        assert!(xml.contains(r#"<formula notation="TeX">x^2</formula>"#));
+
+        let options = EmitOptions {
+            format: rescribe_core::FormatOptions::new().with("mathml", true),
+            ..Default::default()
+        };
+        let xml = String::from_utf8(emit_with_options(&doc, &options).unwrap().value).unwrap();
+        assert!(xml.contains(
+            r#"<formula notation="MathML"><math xmlns="http://www.w3.org/1998/Math/MathML" display="block">"#
+        ));
+        assert!(xml.contains("<msup><mi>x</mi><mn>2</mn></msup>"));
+    }
 }
@@ -159,7 +159,15 @@ fn emit_node(node: &Node, ctx: &mut EmitContext) {
 
         node::QUOTED => {
             let quote_type = node.props.get_str(prop::QUOTE_TYPE).unwrap_or("double");
-            if quote_type == "single" {
+            let marks = (
+                node.props.get_str(prop::QUOTE_OPEN),
+                node.props.get_str(prop::QUOTE_CLOSE),
+            );
+            if let (Some(open), Some(close)) = marks {
+                ctx.write(open);
+                emit_nodes(&node.children, ctx);
+                ctx.write(close);
+            } else if quote_type == "single" {
                 ctx.write("'");
                 emit_nodes(&node.children, ctx);
                 ctx.write("'");